@@ -14,7 +14,7 @@ Actions ==
    "ingest_ok", "ingest_fail",
    "tokenize_ok", "tokenize_fail",
    "generate_chunk", "generate_done", "generate_fail",
-   "client_disconnect",
+   "client_disconnect", "server_shutdown",
    "reset"}
 
 VARIABLES
@@ -22,6 +22,7 @@ VARIABLES
   clientConnected,
   outputTokens,
   responseCompleted,
+  usageRecorded,
   lastAction
 
 vars ==
@@ -29,6 +30,7 @@ vars ==
     clientConnected,
     outputTokens,
     responseCompleted,
+    usageRecorded,
     lastAction>>
 
 Init ==
@@ -36,6 +38,7 @@ Init ==
   /\ clientConnected = FALSE
   /\ outputTokens = 0
   /\ responseCompleted = FALSE
+  /\ usageRecorded = FALSE
   /\ lastAction = "none"
 
 Start ==
@@ -44,32 +47,33 @@ Start ==
   /\ clientConnected' = TRUE
   /\ outputTokens' = 0
   /\ responseCompleted' = FALSE
+  /\ usageRecorded' = FALSE
   /\ lastAction' = "start"
 
 IngestOK ==
   /\ kstate = "ingest"
   /\ kstate' = "tokenize"
-  /\ UNCHANGED <<clientConnected, outputTokens, responseCompleted>>
+  /\ UNCHANGED <<clientConnected, outputTokens, responseCompleted, usageRecorded>>
   /\ lastAction' = "ingest_ok"
 
 IngestFail ==
   /\ kstate = "ingest"
   /\ kstate' = "failed"
   /\ responseCompleted' = FALSE
-  /\ UNCHANGED <<clientConnected, outputTokens>>
+  /\ UNCHANGED <<clientConnected, outputTokens, usageRecorded>>
   /\ lastAction' = "ingest_fail"
 
 TokenizeOK ==
   /\ kstate = "tokenize"
   /\ kstate' = "generate"
-  /\ UNCHANGED <<clientConnected, outputTokens, responseCompleted>>
+  /\ UNCHANGED <<clientConnected, outputTokens, responseCompleted, usageRecorded>>
   /\ lastAction' = "tokenize_ok"
 
 TokenizeFail ==
   /\ kstate = "tokenize"
   /\ kstate' = "failed"
   /\ responseCompleted' = FALSE
-  /\ UNCHANGED <<clientConnected, outputTokens>>
+  /\ UNCHANGED <<clientConnected, outputTokens, usageRecorded>>
   /\ lastAction' = "tokenize_fail"
 
 GenerateChunk ==
@@ -77,13 +81,14 @@ GenerateChunk ==
   /\ outputTokens < MaxOutputTokens
   /\ kstate' = "generate"
   /\ outputTokens' = outputTokens + 1
-  /\ UNCHANGED <<clientConnected, responseCompleted>>
+  /\ UNCHANGED <<clientConnected, responseCompleted, usageRecorded>>
   /\ lastAction' = "generate_chunk"
 
 GenerateDone ==
   /\ kstate = "generate"
   /\ kstate' = "done"
   /\ responseCompleted' = TRUE
+  /\ usageRecorded' = TRUE
   /\ UNCHANGED <<clientConnected, outputTokens>>
   /\ lastAction' = "generate_done"
 
@@ -91,20 +96,24 @@ GenerateFail ==
   /\ kstate = "generate"
   /\ kstate' = "failed"
   /\ responseCompleted' = FALSE
-  /\ UNCHANGED <<clientConnected, outputTokens>>
+  /\ UNCHANGED <<clientConnected, outputTokens, usageRecorded>>
   /\ lastAction' = "generate_fail"
 
-ClientDisconnect ==
+\* Client disconnect and server shutdown cancel a request the same way: the upstream call is
+\* dropped and the usage so far (prompt estimate plus streamed output) is recorded.
+Cancel(action) ==
   /\ kstate \in {"ingest", "tokenize", "generate"}
   /\ clientConnected
   /\ clientConnected' = FALSE
-  /\ IF kstate \in {"ingest", "tokenize"}
-       THEN /\ kstate' = "failed"
-            /\ responseCompleted' = FALSE
-       ELSE /\ kstate' = "generate"
-            /\ responseCompleted' = responseCompleted
+  /\ kstate' = "failed"
+  /\ responseCompleted' = FALSE
+  /\ usageRecorded' = TRUE
   /\ UNCHANGED <<outputTokens>>
-  /\ lastAction' = "client_disconnect"
+  /\ lastAction' = action
+
+ClientDisconnect == Cancel("client_disconnect")
+
+ServerShutdown == Cancel("server_shutdown")
 
 Reset ==
   /\ kstate \in {"done", "failed"}
@@ -112,6 +121,7 @@ Reset ==
   /\ clientConnected' = FALSE
   /\ outputTokens' = 0
   /\ responseCompleted' = FALSE
+  /\ usageRecorded' = FALSE
   /\ lastAction' = "reset"
 
 Next ==
@@ -124,6 +134,7 @@ Next ==
   \/ GenerateDone
   \/ GenerateFail
   \/ ClientDisconnect
+  \/ ServerShutdown
   \/ Reset
 
 Spec ==
@@ -136,6 +147,7 @@ TypeInv ==
   /\ clientConnected \in BOOLEAN
   /\ outputTokens \in 0..MaxOutputTokens
   /\ responseCompleted \in BOOLEAN
+  /\ usageRecorded \in BOOLEAN
   /\ lastAction \in Actions
 
 FlowInv ==
@@ -147,7 +159,14 @@ StreamingInv ==
 
 DisconnectSafetyInv ==
   /\ kstate \in {"ingest", "tokenize"} => clientConnected
-  /\ ~clientConnected => kstate \in {"idle", "generate", "done", "failed"}
+  /\ kstate = "generate" => clientConnected
+  /\ ~clientConnected => kstate \in {"idle", "done", "failed"}
+
+UsageInv ==
+  /\ kstate = "done" => usageRecorded
+  /\ kstate \in {"idle", "ingest", "tokenize", "generate"} => ~usageRecorded
+  /\ (kstate = "failed" /\ lastAction \in {"client_disconnect", "server_shutdown"})
+       => usageRecorded
 
 GenerateProgressLiveness ==
   [](kstate = "generate" ~> (kstate = "done" \/ kstate = "failed"))
@@ -0,0 +1,295 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serde_json::{Value, json};
+use tracing::{info, warn};
+
+use crate::{
+    config::{AlertMetric, AlertRuleConfig},
+    provider_stats::{ProviderStatsRegistry, ProviderStatsSnapshot},
+};
+
+pub(crate) const ALERT_EVALUATION_INTERVAL: Duration = Duration::from_secs(30);
+const ALERT_WINDOW: &str = "5m";
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default)]
+struct RuleState {
+    breaching_since: Option<Instant>,
+    firing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AlertState {
+    Firing,
+    Resolved,
+}
+
+impl AlertState {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Firing => "firing",
+            Self::Resolved => "resolved",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AlertNotification {
+    pub(crate) rule: String,
+    pub(crate) provider: String,
+    pub(crate) state: AlertState,
+    pub(crate) metric: AlertMetric,
+    /// `None` when a firing alert resolves because its provider went quiet.
+    pub(crate) value: Option<f64>,
+    pub(crate) threshold: f64,
+}
+
+impl AlertNotification {
+    /// Slack-compatible payload: `text` for incoming webhooks, `alert` for everything else.
+    pub(crate) fn payload(&self) -> Value {
+        let metric = metric_name(self.metric);
+        let text = match (self.state, self.value) {
+            (AlertState::Firing, Some(value)) => format!(
+                "[FIRING] {}: {} {metric} {value:.1} > {} over the last {ALERT_WINDOW}",
+                self.rule, self.provider, self.threshold
+            ),
+            (_, Some(value)) => format!(
+                "[RESOLVED] {}: {} {metric} {value:.1} <= {} over the last {ALERT_WINDOW}",
+                self.rule, self.provider, self.threshold
+            ),
+            (_, None) => format!(
+                "[RESOLVED] {}: {} has too little traffic to evaluate {metric}",
+                self.rule, self.provider
+            ),
+        };
+        json!({
+            "text": text,
+            "alert": {
+                "rule": self.rule,
+                "provider": self.provider,
+                "state": self.state.as_str(),
+                "metric": metric,
+                "value": self.value,
+                "threshold": self.threshold,
+            }
+        })
+    }
+}
+
+/// Evaluates `XR_ALERT_RULES` against provider statistics, remembering per rule and provider
+/// how long a breach has lasted and whether it was notified.
+#[derive(Debug)]
+pub(crate) struct AlertEvaluator {
+    rules: Vec<AlertRuleConfig>,
+    states: HashMap<(String, String), RuleState>,
+}
+
+impl AlertEvaluator {
+    pub(crate) fn new(rules: Vec<AlertRuleConfig>) -> Self {
+        Self { rules, states: HashMap::new() }
+    }
+
+    /// Notifications for alerts that started firing or resolved since the last evaluation.
+    pub(crate) fn evaluate(
+        &mut self,
+        snapshots: &[ProviderStatsSnapshot],
+        now: Instant,
+    ) -> Vec<AlertNotification> {
+        let mut notifications = Vec::new();
+        for rule in &self.rules {
+            let watched = snapshots.iter().filter(|snapshot| {
+                rule.provider.as_ref().is_none_or(|provider| *provider == snapshot.provider)
+            });
+            for snapshot in watched {
+                let state =
+                    self.states.entry((rule.name.clone(), snapshot.provider.clone())).or_default();
+                let value = metric_value(rule, snapshot);
+                let notification = |state, value| AlertNotification {
+                    rule: rule.name.clone(),
+                    provider: snapshot.provider.clone(),
+                    state,
+                    metric: rule.metric,
+                    value,
+                    threshold: rule.threshold,
+                };
+                match value {
+                    Some(value) if value > rule.threshold => {
+                        let since = *state.breaching_since.get_or_insert(now);
+                        let held_for = Duration::from_secs(rule.for_minutes * 60);
+                        if !state.firing && now.duration_since(since) >= held_for {
+                            state.firing = true;
+                            notifications.push(notification(AlertState::Firing, Some(value)));
+                        }
+                    }
+                    value => {
+                        state.breaching_since = None;
+                        if std::mem::take(&mut state.firing) {
+                            notifications.push(notification(AlertState::Resolved, value));
+                        }
+                    }
+                }
+            }
+        }
+        notifications
+    }
+}
+
+/// `None` while the window has fewer than `min_requests` calls, or no successful call to take
+/// a latency from.
+fn metric_value(rule: &AlertRuleConfig, snapshot: &ProviderStatsSnapshot) -> Option<f64> {
+    let window = snapshot.windows.iter().find(|window| window.window == ALERT_WINDOW)?;
+    if window.requests == 0 || window.requests < rule.min_requests {
+        return None;
+    }
+    match rule.metric {
+        AlertMetric::ErrorRate => Some(window.failures as f64 * 100.0 / window.requests as f64),
+        AlertMetric::P95LatencyMs => window.p95_ms.map(|p95_ms| p95_ms as f64),
+    }
+}
+
+fn metric_name(metric: AlertMetric) -> &'static str {
+    match metric {
+        AlertMetric::ErrorRate => "error_rate",
+        AlertMetric::P95LatencyMs => "p95_latency_ms",
+    }
+}
+
+/// Evaluates the rules every `interval` and POSTs each notification to `webhook_url`; a failed
+/// delivery is logged and not retried.
+pub(crate) fn spawn_alert_loop(
+    rules: Vec<AlertRuleConfig>,
+    webhook_url: String,
+    provider_stats: Arc<ProviderStatsRegistry>,
+    interval: Duration,
+) {
+    let client = match reqwest::Client::builder().timeout(DELIVERY_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(error) => {
+            warn!(event = "app.alerts.disabled", error = %error);
+            return;
+        }
+    };
+    let mut evaluator = AlertEvaluator::new(rules);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let snapshots = provider_stats.snapshot(&[]);
+            for notification in evaluator.evaluate(&snapshots, Instant::now()) {
+                match notification.state {
+                    AlertState::Firing => warn!(
+                        event = "app.alert.firing",
+                        rule = %notification.rule,
+                        provider = %notification.provider,
+                        value = notification.value
+                    ),
+                    AlertState::Resolved => info!(
+                        event = "app.alert.resolved",
+                        rule = %notification.rule,
+                        provider = %notification.provider
+                    ),
+                }
+                let delivery = client
+                    .post(&webhook_url)
+                    .json(&notification.payload())
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status);
+                if let Err(error) = delivery {
+                    warn!(
+                        event = "app.alerts.delivery_failed",
+                        rule = %notification.rule,
+                        provider = %notification.provider,
+                        error = %error
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        config::{AlertMetric, AlertRuleConfig},
+        provider_stats::{ProviderStatsSnapshot, ProviderStatus, WindowStats},
+    };
+
+    use super::{AlertEvaluator, AlertState};
+
+    fn snapshot(provider: &str, requests: usize, failures: usize) -> ProviderStatsSnapshot {
+        ProviderStatsSnapshot {
+            provider: provider.to_string(),
+            status: ProviderStatus::Healthy,
+            in_flight: 0,
+            consecutive_failures: 0,
+            last_error: None,
+            windows: vec![WindowStats {
+                window: "5m",
+                requests,
+                failures,
+                success_percent: None,
+                p50_ms: Some(100),
+                p95_ms: Some(2000),
+            }],
+        }
+    }
+
+    fn rule(metric: AlertMetric, threshold: f64, for_minutes: u64) -> AlertRuleConfig {
+        AlertRuleConfig {
+            name: "errors".to_string(),
+            provider: None,
+            metric,
+            threshold,
+            for_minutes,
+            min_requests: 10,
+        }
+    }
+
+    #[test]
+    fn breaches_fire_once_they_last_long_enough_and_resolve_when_cleared() {
+        let mut evaluator = AlertEvaluator::new(vec![rule(AlertMetric::ErrorRate, 5.0, 2)]);
+        let start = Instant::now();
+        let failing = [snapshot("zai", 20, 8), snapshot("deepseek", 20, 0)];
+
+        assert!(evaluator.evaluate(&failing, start).is_empty());
+        assert!(evaluator.evaluate(&failing, start + Duration::from_secs(60)).is_empty());
+        let fired = evaluator.evaluate(&failing, start + Duration::from_secs(120));
+        assert_eq!(fired.len(), 1);
+        assert_eq!((fired[0].provider.as_str(), fired[0].state), ("zai", AlertState::Firing));
+        assert_eq!(fired[0].value, Some(40.0));
+        assert_eq!(
+            fired[0].payload()["text"],
+            "[FIRING] errors: zai error_rate 40.0 > 5 over the last 5m"
+        );
+        assert!(evaluator.evaluate(&failing, start + Duration::from_secs(150)).is_empty());
+
+        let recovered = [snapshot("zai", 20, 1)];
+        let resolved = evaluator.evaluate(&recovered, start + Duration::from_secs(180));
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].state, AlertState::Resolved);
+        assert_eq!(resolved[0].payload()["alert"]["state"], "resolved");
+    }
+
+    #[test]
+    fn providers_below_min_requests_are_not_evaluated() {
+        let mut latency = rule(AlertMetric::P95LatencyMs, 1000.0, 0);
+        latency.provider = Some("zai".to_string());
+        let mut evaluator = AlertEvaluator::new(vec![latency]);
+        let now = Instant::now();
+
+        assert!(evaluator.evaluate(&[snapshot("zai", 3, 0)], now).is_empty());
+        assert!(evaluator.evaluate(&[snapshot("deepseek", 50, 0)], now).is_empty());
+        let fired = evaluator.evaluate(&[snapshot("zai", 50, 0)], now);
+        assert_eq!(fired[0].value, Some(2000.0));
+        let resolved = evaluator.evaluate(&[snapshot("zai", 3, 0)], now);
+        assert_eq!((resolved[0].state, resolved[0].value), (AlertState::Resolved, None));
+    }
+}
@@ -0,0 +1,559 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use xrouter_contracts::RequestPriority;
+
+use crate::{
+    config::TruncationStrategy, response_store::PostgresConnection, storage::SqliteDatabase,
+};
+
+const API_KEY_PREFIX: &str = "xr-";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ApiKeyRecord {
+    pub(crate) id: String,
+    pub(crate) label: String,
+    pub(crate) organization: String,
+    pub(crate) project: String,
+    #[serde(default)]
+    pub(crate) allowed_models: Vec<String>,
+    #[serde(default)]
+    pub(crate) denied_models: Vec<String>,
+    #[serde(default)]
+    pub(crate) expires_at: Option<u64>,
+    #[serde(default)]
+    pub(crate) disabled: bool,
+    /// Identical concurrent requests of this key always reach the provider, see
+    /// `XR_REQUEST_COALESCING`.
+    #[serde(default)]
+    pub(crate) coalescing_opt_out: bool,
+    /// Scheduling class of every request made with this key, see `XR_PRIORITY_SCHEDULING`.
+    #[serde(default)]
+    pub(crate) priority: Option<RequestPriority>,
+    /// Handling of input over the context window for this key, see `XR_TRUNCATION`.
+    #[serde(default)]
+    pub(crate) truncation: Option<TruncationStrategy>,
+    /// Reasoning is left out of responses to this key, see `XR_HIDE_REASONING_MODELS`.
+    #[serde(default)]
+    pub(crate) hide_reasoning: bool,
+    pub(crate) created_at: u64,
+    pub(crate) rotated_at: Option<u64>,
+    salt: String,
+    hash: String,
+}
+
+impl ApiKeyRecord {
+    pub(crate) fn issue(
+        label: String,
+        organization: String,
+        project: String,
+        allowed_models: Vec<String>,
+        denied_models: Vec<String>,
+        expires_at: Option<u64>,
+    ) -> (Self, String) {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let mut record = Self {
+            id,
+            label,
+            organization,
+            project,
+            allowed_models,
+            denied_models,
+            expires_at,
+            disabled: false,
+            coalescing_opt_out: false,
+            priority: None,
+            truncation: None,
+            hide_reasoning: false,
+            created_at: unix_now(),
+            rotated_at: None,
+            salt: String::new(),
+            hash: String::new(),
+        };
+        let key = record.reset_secret();
+        (record, key)
+    }
+
+    pub(crate) fn rotate(&mut self) -> String {
+        self.rotated_at = Some(unix_now());
+        self.reset_secret()
+    }
+
+    pub(crate) fn is_expired_at(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    fn reset_secret(&mut self) -> String {
+        let secret = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        self.salt = uuid::Uuid::new_v4().simple().to_string();
+        self.hash = hash_secret(&self.salt, &secret);
+        format!("{API_KEY_PREFIX}{}.{secret}", self.id)
+    }
+
+    fn verify_secret(&self, secret: &str) -> bool {
+        let candidate = hash_secret(&self.salt, secret);
+        candidate.len() == self.hash.len()
+            && candidate.bytes().zip(self.hash.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+}
+
+fn hash_secret(salt: &str, secret: &str) -> String {
+    let digest =
+        Sha256::new().chain_update(salt).chain_update(b":").chain_update(secret).finalize();
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+fn split_api_key(api_key: &str) -> Option<(&str, &str)> {
+    let (id, secret) = api_key.strip_prefix(API_KEY_PREFIX)?.split_once('.')?;
+    if id.is_empty() || secret.is_empty() { None } else { Some((id, secret)) }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum KeyStoreError {
+    #[error("key store io error: {0}")]
+    Io(String),
+    #[error("key store data is invalid: {0}")]
+    Corrupt(String),
+    #[error("key store is unavailable: {0}")]
+    Backend(String),
+}
+
+#[async_trait]
+pub(crate) trait ApiKeyStore: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn list(&self) -> Result<Vec<ApiKeyRecord>, KeyStoreError>;
+
+    async fn get(&self, id: &str) -> Result<Option<ApiKeyRecord>, KeyStoreError>;
+
+    async fn put(&self, record: ApiKeyRecord) -> Result<(), KeyStoreError>;
+
+    async fn delete(&self, id: &str) -> Result<bool, KeyStoreError>;
+
+    async fn verify(&self, api_key: &str) -> Result<Option<ApiKeyRecord>, KeyStoreError> {
+        let Some((id, secret)) = split_api_key(api_key) else {
+            return Ok(None);
+        };
+        Ok(self.get(id).await?.filter(|record| record.verify_secret(secret)))
+    }
+}
+
+/// Oldest key first, the order every store lists in.
+fn sort_records(records: &mut [ApiKeyRecord]) {
+    records
+        .sort_by(|left, right| left.created_at.cmp(&right.created_at).then(left.id.cmp(&right.id)));
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct InMemoryApiKeyStore {
+    records: Mutex<HashMap<String, ApiKeyRecord>>,
+}
+
+impl InMemoryApiKeyStore {
+    fn records(&self) -> MutexGuard<'_, HashMap<String, ApiKeyRecord>> {
+        self.records.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn sorted(&self) -> Vec<ApiKeyRecord> {
+        let mut records = self.records().values().cloned().collect::<Vec<_>>();
+        sort_records(&mut records);
+        records
+    }
+}
+
+#[async_trait]
+impl ApiKeyStore for InMemoryApiKeyStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn list(&self) -> Result<Vec<ApiKeyRecord>, KeyStoreError> {
+        Ok(self.sorted())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<ApiKeyRecord>, KeyStoreError> {
+        Ok(self.records().get(id).cloned())
+    }
+
+    async fn put(&self, record: ApiKeyRecord) -> Result<(), KeyStoreError> {
+        self.records().insert(record.id.clone(), record);
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, KeyStoreError> {
+        Ok(self.records().remove(id).is_some())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct FileApiKeyStore {
+    path: PathBuf,
+    memory: InMemoryApiKeyStore,
+    /// Held from the in-memory change until its snapshot is on disk, so writes land in order.
+    writes: Mutex<()>,
+}
+
+impl FileApiKeyStore {
+    pub(crate) fn open(path: PathBuf) -> Result<Self, KeyStoreError> {
+        let memory = InMemoryApiKeyStore::default();
+        match fs::read_to_string(&path) {
+            Ok(raw) if !raw.trim().is_empty() => {
+                let records = serde_json::from_str::<Vec<ApiKeyRecord>>(&raw)
+                    .map_err(|error| KeyStoreError::Corrupt(error.to_string()))?;
+                let mut stored = memory.records();
+                for record in records {
+                    stored.insert(record.id.clone(), record);
+                }
+            }
+            Ok(_) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(KeyStoreError::Io(error.to_string())),
+        }
+        info!(
+            event = "app.key_store.loaded",
+            store = "file",
+            path = %path.display(),
+            key_count = memory.records().len()
+        );
+        Ok(Self { path, memory, writes: Mutex::new(()) })
+    }
+
+    fn lock_writes(&self) -> MutexGuard<'_, ()> {
+        self.writes.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Saves the store after a change to `id`; when that fails, `id` goes back to `previous`.
+    fn persist_or_restore(
+        &self,
+        id: &str,
+        previous: Option<ApiKeyRecord>,
+    ) -> Result<(), KeyStoreError> {
+        self.persist().inspect_err(|error| {
+            warn!(event = "app.key_store.persist_failed", store = "file", key_id = id, error = %error);
+            let mut records = self.memory.records();
+            match previous {
+                Some(previous) => records.insert(id.to_string(), previous),
+                None => records.remove(id),
+            };
+        })
+    }
+
+    fn persist(&self) -> Result<(), KeyStoreError> {
+        let payload = serde_json::to_string_pretty(&self.memory.sorted())
+            .map_err(|error| KeyStoreError::Corrupt(error.to_string()))?;
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, payload).map_err(|error| KeyStoreError::Io(error.to_string()))?;
+        fs::rename(&temp_path, &self.path).map_err(|error| KeyStoreError::Io(error.to_string()))
+    }
+}
+
+#[async_trait]
+impl ApiKeyStore for FileApiKeyStore {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn list(&self) -> Result<Vec<ApiKeyRecord>, KeyStoreError> {
+        Ok(self.memory.sorted())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<ApiKeyRecord>, KeyStoreError> {
+        Ok(self.memory.records().get(id).cloned())
+    }
+
+    async fn put(&self, record: ApiKeyRecord) -> Result<(), KeyStoreError> {
+        let _write = self.lock_writes();
+        let id = record.id.clone();
+        let previous = self.memory.records().insert(id.clone(), record);
+        self.persist_or_restore(&id, previous)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, KeyStoreError> {
+        let _write = self.lock_writes();
+        let Some(removed) = self.memory.records().remove(id) else {
+            return Ok(false);
+        };
+        self.persist_or_restore(id, Some(removed))?;
+        Ok(true)
+    }
+}
+
+/// Rows of the `xrouter_api_keys` table, created by the database migrations, so replicas
+/// sharing the database see each other's admin changes at once.
+pub(crate) struct PostgresApiKeyStore {
+    connection: PostgresConnection,
+}
+
+impl PostgresApiKeyStore {
+    pub(crate) fn open(url: &str) -> Result<Self, tokio_postgres::Error> {
+        Ok(Self { connection: PostgresConnection::new(url)? })
+    }
+
+    async fn client(&self) -> Result<Arc<tokio_postgres::Client>, KeyStoreError> {
+        self.connection.client().await.map_err(|error| KeyStoreError::Backend(error.to_string()))
+    }
+}
+
+fn postgres_error(error: tokio_postgres::Error) -> KeyStoreError {
+    KeyStoreError::Backend(error.to_string())
+}
+
+fn record_from_value(value: serde_json::Value) -> Result<ApiKeyRecord, KeyStoreError> {
+    serde_json::from_value(value).map_err(|error| KeyStoreError::Corrupt(error.to_string()))
+}
+
+#[async_trait]
+impl ApiKeyStore for PostgresApiKeyStore {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn list(&self) -> Result<Vec<ApiKeyRecord>, KeyStoreError> {
+        let rows = self
+            .client()
+            .await?
+            .query("SELECT record FROM xrouter_api_keys", &[])
+            .await
+            .map_err(postgres_error)?;
+        let mut records = rows
+            .into_iter()
+            .map(|row| record_from_value(row.get(0)))
+            .collect::<Result<Vec<_>, _>>()?;
+        sort_records(&mut records);
+        Ok(records)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<ApiKeyRecord>, KeyStoreError> {
+        let row = self
+            .client()
+            .await?
+            .query_opt("SELECT record FROM xrouter_api_keys WHERE id = $1", &[&id])
+            .await
+            .map_err(postgres_error)?;
+        row.map(|row| record_from_value(row.get(0))).transpose()
+    }
+
+    async fn put(&self, record: ApiKeyRecord) -> Result<(), KeyStoreError> {
+        let payload = serde_json::to_value(&record)
+            .map_err(|error| KeyStoreError::Corrupt(error.to_string()))?;
+        self.client()
+            .await?
+            .execute(
+                "INSERT INTO xrouter_api_keys (id, record) VALUES ($1, $2) \
+                 ON CONFLICT (id) DO UPDATE SET record = EXCLUDED.record",
+                &[&record.id, &payload],
+            )
+            .await
+            .map_err(postgres_error)?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, KeyStoreError> {
+        let deleted = self
+            .client()
+            .await?
+            .execute("DELETE FROM xrouter_api_keys WHERE id = $1", &[&id])
+            .await
+            .map_err(postgres_error)?;
+        Ok(deleted > 0)
+    }
+}
+
+/// Rows of the `xrouter_api_keys` table of the embedded database of `XR_STORAGE=sqlite`.
+pub(crate) struct SqliteApiKeyStore {
+    database: Arc<SqliteDatabase>,
+}
+
+impl SqliteApiKeyStore {
+    pub(crate) fn new(database: Arc<SqliteDatabase>) -> Self {
+        Self { database }
+    }
+}
+
+fn record_from_json(raw: &str) -> Result<ApiKeyRecord, KeyStoreError> {
+    serde_json::from_str(raw).map_err(|error| KeyStoreError::Corrupt(error.to_string()))
+}
+
+#[async_trait]
+impl ApiKeyStore for SqliteApiKeyStore {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn list(&self) -> Result<Vec<ApiKeyRecord>, KeyStoreError> {
+        let rows = self
+            .database
+            .call(|connection| {
+                let mut statement = connection.prepare("SELECT record FROM xrouter_api_keys")?;
+                let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+                rows.collect::<Result<Vec<_>, _>>()
+            })
+            .await
+            .map_err(KeyStoreError::Backend)?;
+        let mut records =
+            rows.iter().map(|raw| record_from_json(raw)).collect::<Result<Vec<_>, _>>()?;
+        sort_records(&mut records);
+        Ok(records)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<ApiKeyRecord>, KeyStoreError> {
+        let id = id.to_string();
+        let raw = self
+            .database
+            .call(move |connection| {
+                connection
+                    .query_row("SELECT record FROM xrouter_api_keys WHERE id = ?1", [id], |row| {
+                        row.get::<_, String>(0)
+                    })
+                    .optional()
+            })
+            .await
+            .map_err(KeyStoreError::Backend)?;
+        raw.map(|raw| record_from_json(&raw)).transpose()
+    }
+
+    async fn put(&self, record: ApiKeyRecord) -> Result<(), KeyStoreError> {
+        let payload = serde_json::to_string(&record)
+            .map_err(|error| KeyStoreError::Corrupt(error.to_string()))?;
+        self.database
+            .call(move |connection| {
+                connection.execute(
+                    "INSERT INTO xrouter_api_keys (id, record) VALUES (?1, ?2) \
+                     ON CONFLICT (id) DO UPDATE SET record = excluded.record",
+                    [record.id, payload],
+                )
+            })
+            .await
+            .map_err(KeyStoreError::Backend)?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, KeyStoreError> {
+        let id = id.to_string();
+        let deleted = self
+            .database
+            .call(move |connection| {
+                connection.execute("DELETE FROM xrouter_api_keys WHERE id = ?1", [id])
+            })
+            .await
+            .map_err(KeyStoreError::Backend)?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue() -> (ApiKeyRecord, String) {
+        ApiKeyRecord::issue(
+            "ci".to_string(),
+            "acme".to_string(),
+            "web".to_string(),
+            Vec::new(),
+            Vec::new(),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn issued_key_verifies_and_is_stored_only_as_salted_hash() {
+        let store = InMemoryApiKeyStore::default();
+        let (record, key) = issue();
+        let (other, _) = issue();
+        assert_ne!(record.salt, other.salt);
+        assert!(!record.hash.contains(key.as_str()));
+        store.put(record.clone()).await.expect("put must succeed");
+
+        assert_eq!(store.verify(&key).await.expect("verify must succeed"), Some(record));
+        assert_eq!(store.verify(&format!("{key}x")).await.expect("verify must succeed"), None);
+        assert_eq!(store.verify("not-a-key").await.expect("verify must succeed"), None);
+    }
+
+    #[tokio::test]
+    async fn rotate_invalidates_previous_secret() {
+        let store = InMemoryApiKeyStore::default();
+        let (mut record, old_key) = issue();
+        let new_key = record.rotate();
+        store.put(record).await.expect("put must succeed");
+
+        assert!(store.verify(&old_key).await.expect("verify must succeed").is_none());
+        assert!(store.verify(&new_key).await.expect("verify must succeed").is_some());
+    }
+
+    #[tokio::test]
+    async fn file_store_persists_records_across_reopen() {
+        let path = std::env::temp_dir().join(format!("xrouter-keys-{}.json", uuid::Uuid::new_v4()));
+        let (record, key) = issue();
+        {
+            let store = FileApiKeyStore::open(path.clone()).expect("store must open");
+            store.put(record.clone()).await.expect("put must succeed");
+        }
+        let reopened = FileApiKeyStore::open(path.clone()).expect("store must reopen");
+        assert_eq!(reopened.verify(&key).await.expect("verify must succeed"), Some(record.clone()));
+        assert!(reopened.delete(&record.id).await.expect("delete must succeed"));
+        assert!(reopened.list().await.expect("list must succeed").is_empty());
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn file_store_rolls_back_writes_it_cannot_save() {
+        let dir = std::env::temp_dir().join(format!("xrouter-keys-{}", uuid::Uuid::new_v4()));
+        fs::create_dir(&dir).expect("dir must be created");
+        let path = dir.join("keys.json");
+        let store = FileApiKeyStore::open(path.clone()).expect("store must open");
+        let (record, key) = issue();
+        store.put(record.clone()).await.expect("put must succeed");
+
+        fs::remove_dir_all(&dir).expect("dir must be removed");
+        let (other, _) = issue();
+        assert!(matches!(store.put(other.clone()).await, Err(KeyStoreError::Io(_))));
+        assert_eq!(store.get(&other.id).await.expect("get must succeed"), None);
+        assert!(matches!(store.delete(&record.id).await, Err(KeyStoreError::Io(_))));
+        assert_eq!(store.verify(&key).await.expect("verify must succeed"), Some(record));
+    }
+
+    #[tokio::test]
+    async fn sqlite_store_persists_records_across_reopen() {
+        let path = std::env::temp_dir().join(format!("xrouter-{}.db", uuid::Uuid::new_v4()));
+        let (record, key) = issue();
+        let (later, _) = issue();
+        {
+            let database = SqliteDatabase::open(&path).expect("database must open");
+            let store = SqliteApiKeyStore::new(Arc::new(database));
+            store.put(record.clone()).await.expect("put must succeed");
+            store.put(later.clone()).await.expect("put must succeed");
+        }
+        let database = SqliteDatabase::open(&path).expect("database must reopen");
+        let reopened = SqliteApiKeyStore::new(Arc::new(database));
+        assert_eq!(reopened.verify(&key).await.expect("verify must succeed"), Some(record.clone()));
+        assert_eq!(reopened.list().await.expect("list must succeed").len(), 2);
+        assert!(reopened.delete(&record.id).await.expect("delete must succeed"));
+        assert!(!reopened.delete(&record.id).await.expect("delete must succeed"));
+        assert_eq!(reopened.list().await.expect("list must succeed"), vec![later]);
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn postgres_store_reports_an_unreachable_database_as_backend_error() {
+        let store = PostgresApiKeyStore::open("postgres://xrouter@127.0.0.1:1/xrouter")
+            .expect("url must parse");
+        let (record, key) = issue();
+        assert!(matches!(store.put(record).await, Err(KeyStoreError::Backend(_))));
+        assert!(matches!(store.verify(&key).await, Err(KeyStoreError::Backend(_))));
+    }
+}
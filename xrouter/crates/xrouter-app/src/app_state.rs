@@ -1,16 +1,84 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
-use xrouter_core::{CoreError, ExecutionEngine, ModelDescriptor, synthesize_model_id};
+use tracing::warn;
+use xrouter_clients_openai::{SharedChaosPolicy, TranscriptStore};
+use xrouter_core::{
+    CoreError, ExecutionEngine, ModelDescriptor, ModelPricing, synthesize_model_id,
+};
 
-use crate::{config, startup::app_builder::AppBuilder};
+use crate::{
+    alerts::spawn_alert_loop,
+    api_keys::ApiKeyStore,
+    bandit::BanditRegistry,
+    config,
+    conversation_budget::ConversationBudgets,
+    coordination::RedisCoordinator,
+    event_bus::{EventBus, RequestEvents},
+    http::{
+        coalescing::InflightRequests, idempotency::IdempotencyCache, overload::OverloadGuard,
+        rate_limit::FixedWindowRateLimiter,
+    },
+    model_stats::ModelStatsRegistry,
+    provider_health::ProviderHealthRegistry,
+    provider_stats::ProviderStatsRegistry,
+    reasoning_carryover::ReasoningCarryOver,
+    response_store::ResponseStore,
+    response_tee::ResponseTee,
+    scheduling::PriorityScheduler,
+    startup::app_builder::AppBuilder,
+    stream_tasks::StreamTasks,
+    tenancy::TenantRegistry,
+    usage_export::UsageExport,
+    usage_ledger::UsageLedger,
+};
 
 #[derive(Clone)]
 pub struct AppState {
     pub(crate) openai_compatible_api: bool,
     pub(crate) byok_enabled: bool,
+    pub(crate) catalog_only: bool,
     pub(crate) default_provider: String,
     pub(crate) models: Vec<ModelDescriptor>,
     pub(crate) engines: HashMap<String, Arc<ExecutionEngine>>,
+    pub(crate) user_rate_limiter: Option<Arc<FixedWindowRateLimiter>>,
+    pub(crate) stream_retained_output_bytes: Option<usize>,
+    pub(crate) stream_stall_timeout: Option<Duration>,
+    pub(crate) stream_salvage_partial: bool,
+    pub(crate) max_request_body_bytes: usize,
+    pub(crate) max_hops: u32,
+    pub(crate) upstream_limits: config::UpstreamLimitsConfig,
+    pub(crate) overload: OverloadGuard,
+    pub(crate) cors: config::CorsConfig,
+    pub(crate) passthrough_fields: Arc<HashMap<String, Vec<String>>>,
+    pub(crate) unknown_model: config::UnknownModelConfig,
+    pub(crate) truncation: config::TruncationStrategy,
+    pub(crate) truncation_summary_model: Option<String>,
+    pub(crate) hide_reasoning_models: Arc<HashSet<String>>,
+    pub(crate) header_overrides: config::HeaderOverrides,
+    pub(crate) provider_projects: Arc<HashMap<String, String>>,
+    pub(crate) tenants: Option<Arc<TenantRegistry>>,
+    pub(crate) admin_token: Option<Arc<str>>,
+    pub(crate) key_store: Option<Arc<dyn ApiKeyStore>>,
+    pub(crate) provider_health: Arc<ProviderHealthRegistry>,
+    pub(crate) provider_stats: Arc<ProviderStatsRegistry>,
+    pub(crate) model_stats: Arc<ModelStatsRegistry>,
+    pub(crate) stream_tasks: StreamTasks,
+    pub(crate) reasoning_carryover: Arc<ReasoningCarryOver>,
+    pub(crate) conversation_budgets: Option<Arc<ConversationBudgets>>,
+    pub(crate) idempotency: Arc<IdempotencyCache>,
+    pub(crate) request_coalescing: Option<Arc<InflightRequests>>,
+    pub(crate) scheduler: Option<Arc<PriorityScheduler>>,
+    pub(crate) bandits: Option<Arc<BanditRegistry>>,
+    pub(crate) response_tee: Option<Arc<ResponseTee>>,
+    pub(crate) event_bus: Option<Arc<EventBus>>,
+    pub(crate) usage_export: Option<Arc<UsageExport>>,
+    pub(crate) usage_ledger: Option<Arc<UsageLedger>>,
+    pub(crate) transcripts: Option<Arc<TranscriptStore>>,
+    pub(crate) chaos: Option<SharedChaosPolicy>,
 }
 
 impl AppState {
@@ -37,7 +105,309 @@ impl AppState {
                 .unwrap_or_else(|| "openrouter".to_string())
         };
 
-        Self { openai_compatible_api, byok_enabled, default_provider, models, engines }
+        Self {
+            openai_compatible_api,
+            byok_enabled,
+            catalog_only: false,
+            default_provider,
+            models,
+            engines,
+            user_rate_limiter: None,
+            stream_retained_output_bytes: None,
+            stream_stall_timeout: None,
+            stream_salvage_partial: false,
+            max_request_body_bytes: config::DEFAULT_MAX_REQUEST_BODY_BYTES,
+            max_hops: config::DEFAULT_MAX_HOPS,
+            upstream_limits: config::UpstreamLimitsConfig::default(),
+            overload: OverloadGuard::default(),
+            cors: config::CorsConfig::default(),
+            passthrough_fields: Arc::default(),
+            unknown_model: config::UnknownModelConfig::Passthrough,
+            truncation: config::TruncationStrategy::Off,
+            truncation_summary_model: None,
+            hide_reasoning_models: Arc::default(),
+            header_overrides: config::HeaderOverrides::default(),
+            provider_projects: Arc::default(),
+            tenants: None,
+            admin_token: None,
+            key_store: None,
+            provider_health: Arc::default(),
+            provider_stats: Arc::default(),
+            model_stats: Arc::new(ModelStatsRegistry::for_models()),
+            stream_tasks: StreamTasks::default(),
+            reasoning_carryover: Arc::default(),
+            conversation_budgets: None,
+            idempotency: Arc::new(IdempotencyCache::new(Duration::from_secs(
+                config::DEFAULT_IDEMPOTENCY_TTL_SECONDS,
+            ))),
+            request_coalescing: None,
+            scheduler: None,
+            bandits: None,
+            response_tee: None,
+            event_bus: None,
+            usage_export: None,
+            usage_ledger: None,
+            transcripts: None,
+            chaos: None,
+        }
+    }
+
+    pub(crate) fn with_catalog_only(mut self, catalog_only: bool) -> Self {
+        self.catalog_only = catalog_only;
+        self
+    }
+
+    pub(crate) fn with_response_store(mut self, store: Arc<dyn ResponseStore>) -> Self {
+        self.reasoning_carryover = Arc::new(ReasoningCarryOver::new(store));
+        self
+    }
+
+    pub(crate) fn with_conversation_budget(
+        mut self,
+        limit_tokens: Option<u64>,
+        enforced: bool,
+    ) -> Self {
+        self.conversation_budgets = limit_tokens
+            .map(|limit_tokens| Arc::new(ConversationBudgets::new(limit_tokens, enforced)));
+        self
+    }
+
+    pub(crate) fn with_idempotency_ttl(mut self, ttl: Duration) -> Self {
+        self.idempotency = Arc::new(IdempotencyCache::new(ttl));
+        self
+    }
+
+    pub(crate) fn with_response_tee(mut self, response_tee: Option<Arc<ResponseTee>>) -> Self {
+        self.response_tee = response_tee;
+        self
+    }
+
+    pub(crate) fn with_event_bus(mut self, event_bus: Option<Arc<EventBus>>) -> Self {
+        self.event_bus = event_bus;
+        self
+    }
+
+    pub(crate) fn with_usage_export(mut self, usage_export: Option<Arc<UsageExport>>) -> Self {
+        self.usage_export = usage_export;
+        self
+    }
+
+    pub(crate) fn with_usage_ledger(mut self, usage_ledger: Option<Arc<UsageLedger>>) -> Self {
+        self.usage_ledger = usage_ledger;
+        self
+    }
+
+    /// Lifecycle events of one request, when an event bus, usage export or usage ledger is
+    /// configured.
+    pub(crate) fn request_events(
+        &self,
+        route: &str,
+        model: &str,
+        provider: &str,
+        tenant_id: &str,
+    ) -> Option<RequestEvents> {
+        RequestEvents::new(
+            self.event_bus.as_ref(),
+            self.usage_export.as_ref(),
+            self.usage_ledger.as_ref(),
+            route,
+            model,
+            provider,
+            tenant_id,
+        )
+    }
+
+    pub(crate) fn with_request_coalescing(mut self, enabled: bool) -> Self {
+        self.request_coalescing = enabled.then(Arc::default);
+        self
+    }
+
+    pub(crate) fn with_scheduler(mut self, scheduler: Option<Arc<PriorityScheduler>>) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    pub(crate) fn with_user_rate_limit(
+        mut self,
+        limit_per_minute: Option<usize>,
+        coordinator: Option<Arc<RedisCoordinator>>,
+    ) -> Self {
+        self.user_rate_limiter = limit_per_minute.map(|limit| {
+            Arc::new(FixedWindowRateLimiter::per_minute(limit).shared(coordinator, "user"))
+        });
+        self
+    }
+
+    pub(crate) fn with_stream_retained_output_limit(mut self, max_bytes: Option<usize>) -> Self {
+        self.stream_retained_output_bytes = max_bytes;
+        self
+    }
+
+    pub(crate) fn with_stream_stall_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.stream_stall_timeout = timeout;
+        self
+    }
+
+    pub(crate) fn with_stream_salvage_partial(mut self, enabled: bool) -> Self {
+        self.stream_salvage_partial = enabled;
+        self
+    }
+
+    /// Starts evaluating `rules` against the provider statistics every `interval`.
+    pub(crate) fn with_alerts(
+        self,
+        rules: &[config::AlertRuleConfig],
+        webhook_url: Option<&str>,
+        interval: Duration,
+    ) -> Self {
+        if let Some(webhook_url) = webhook_url.filter(|_| !rules.is_empty()) {
+            spawn_alert_loop(
+                rules.to_vec(),
+                webhook_url.to_string(),
+                Arc::clone(&self.provider_stats),
+                interval,
+            );
+        }
+        self
+    }
+
+    pub(crate) fn with_max_request_body_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_request_body_bytes = max_bytes;
+        self
+    }
+
+    pub(crate) fn with_max_hops(mut self, max_hops: u32) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    pub(crate) fn with_upstream_limits(mut self, limits: config::UpstreamLimitsConfig) -> Self {
+        self.upstream_limits = limits;
+        self
+    }
+
+    pub(crate) fn with_http_limits(mut self, limits: &config::HttpLimitsConfig) -> Self {
+        self.overload = OverloadGuard::new(limits);
+        self
+    }
+
+    pub(crate) fn with_cors(mut self, cors: config::CorsConfig) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    pub(crate) fn with_passthrough_fields(
+        mut self,
+        passthrough_fields: &HashMap<String, Vec<String>>,
+    ) -> Self {
+        self.passthrough_fields = Arc::new(passthrough_fields.clone());
+        self
+    }
+
+    pub(crate) fn with_bandits(mut self, bandits: Option<Arc<BanditRegistry>>) -> Self {
+        if let Some(bandits) = &bandits {
+            bandits.set_prices(&self.models);
+        }
+        self.bandits = bandits;
+        self
+    }
+
+    pub(crate) fn with_unknown_model(mut self, unknown_model: config::UnknownModelConfig) -> Self {
+        if let config::UnknownModelConfig::DefaultModel { model } = &unknown_model
+            && self.routing_fallback_reason(model).is_some()
+        {
+            warn!(event = "app.default_model.unknown", model = %model);
+        }
+        self.unknown_model = unknown_model;
+        self
+    }
+
+    pub(crate) fn with_truncation(
+        mut self,
+        truncation: config::TruncationStrategy,
+        summary_model: Option<String>,
+    ) -> Self {
+        if let Some(model) = &summary_model
+            && self.routing_fallback_reason(model).is_some()
+        {
+            warn!(event = "app.truncation_summary_model.unknown", model = %model);
+        }
+        self.truncation = truncation;
+        self.truncation_summary_model = summary_model;
+        self
+    }
+
+    pub(crate) fn with_hide_reasoning_models(mut self, models: &[String]) -> Self {
+        self.hide_reasoning_models = Arc::new(models.iter().cloned().collect());
+        self
+    }
+
+    pub(crate) fn with_header_overrides(mut self, overrides: config::HeaderOverrides) -> Self {
+        self.header_overrides = overrides;
+        self
+    }
+
+    pub(crate) fn with_provider_projects(mut self, projects: HashMap<String, String>) -> Self {
+        self.provider_projects = Arc::new(projects);
+        self
+    }
+
+    /// Keeps the request fields `provider` allows to pass through; returns the dropped names.
+    pub(crate) fn retain_passthrough_fields(
+        &self,
+        provider: &str,
+        extra: &mut serde_json::Map<String, serde_json::Value>,
+    ) -> Vec<String> {
+        let allowed = self.passthrough_fields.get(provider).map(Vec::as_slice).unwrap_or_default();
+        let dropped =
+            extra.keys().filter(|name| !allowed.contains(name)).cloned().collect::<Vec<_>>();
+        for name in &dropped {
+            extra.remove(name);
+        }
+        dropped
+    }
+
+    pub(crate) fn with_tenants(
+        mut self,
+        tenants: &[config::TenantConfig],
+        key_store: Option<Arc<dyn ApiKeyStore>>,
+        coordinator: Option<Arc<RedisCoordinator>>,
+    ) -> Self {
+        self.tenants =
+            TenantRegistry::from_config(tenants, key_store.clone(), coordinator).map(Arc::new);
+        self.key_store = key_store;
+        self
+    }
+
+    pub(crate) fn with_admin_token(mut self, admin_token: Option<&str>) -> Self {
+        self.admin_token = admin_token.map(Arc::from);
+        self
+    }
+
+    pub(crate) fn with_transcripts(mut self, transcripts: Option<Arc<TranscriptStore>>) -> Self {
+        self.transcripts = transcripts;
+        self
+    }
+
+    pub(crate) fn with_chaos(mut self, chaos: Option<SharedChaosPolicy>) -> Self {
+        self.chaos = chaos;
+        self
+    }
+
+    /// Merged model catalog served by `/v1/models`.
+    pub fn models(&self) -> &[ModelDescriptor] {
+        &self.models
+    }
+
+    /// Generations streaming on this replica.
+    pub fn tasks_in_flight(&self) -> usize {
+        self.stream_tasks.in_flight()
+    }
+
+    /// Cancels the generations still streaming and waits for their tasks to stop; call once
+    /// the server no longer accepts requests.
+    pub async fn shutdown_streams(&self) {
+        self.stream_tasks.shutdown().await;
     }
 
     pub(crate) fn resolve_provider_key(&self, model: &str) -> String {
@@ -59,6 +429,120 @@ impl AppState {
         self.default_provider.clone()
     }
 
+    /// Why `resolve_provider_key` picked a provider the model id does not name, if it did.
+    pub(crate) fn routing_fallback_reason(&self, model: &str) -> Option<&'static str> {
+        let named = model
+            .split_once('/')
+            .is_some_and(|(candidate, _rest)| self.engines.contains_key(candidate))
+            || self
+                .models
+                .iter()
+                .any(|m| m.id == model || synthesize_model_id(&m.provider, &m.id) == model);
+        (!named).then_some("unknown_model_default_provider")
+    }
+
+    /// Applies `XR_UNKNOWN_MODEL` to a model id no provider prefix or catalog entry names:
+    /// rejects it with close catalog ids, or rewrites it to the default model. Returns the
+    /// fallback reason to report for the request.
+    pub(crate) fn route_unknown_model(
+        &self,
+        model: &mut String,
+    ) -> Result<Option<&'static str>, CoreError> {
+        let Some(reason) = self.routing_fallback_reason(model) else {
+            return Ok(None);
+        };
+        match &self.unknown_model {
+            config::UnknownModelConfig::Passthrough => Ok(Some(reason)),
+            config::UnknownModelConfig::Reject => {
+                let suggestions = self.similar_model_ids(model);
+                let message = if suggestions.is_empty() {
+                    format!("unknown model {model}; see the models list for available ids")
+                } else {
+                    format!("unknown model {model}; did you mean {}?", suggestions.join(", "))
+                };
+                Err(CoreError::InvalidParam { param: "model".to_string(), message })
+            }
+            config::UnknownModelConfig::DefaultModel { model: default_model } => {
+                *model = default_model.clone();
+                Ok(Some("unknown_model_default_model"))
+            }
+        }
+    }
+
+    /// Up to three public catalog ids closest to `model` by edit distance, either in full or
+    /// without the provider prefix.
+    fn similar_model_ids(&self, model: &str) -> Vec<String> {
+        let query = model.to_ascii_lowercase();
+        let threshold = (query.chars().count() / 3).max(2);
+        let mut scored = self
+            .models
+            .iter()
+            .map(|entry| synthesize_model_id(&entry.provider, &entry.id))
+            .filter_map(|id| {
+                let lowered = id.to_ascii_lowercase();
+                let bare = lowered.split_once('/').map_or(lowered.as_str(), |(_, rest)| rest);
+                let distance = edit_distance(&query, &lowered).min(edit_distance(&query, bare));
+                (distance <= threshold).then_some((distance, id))
+            })
+            .collect::<Vec<_>>();
+        scored.sort();
+        scored.dedup_by(|a, b| a.1 == b.1);
+        scored.into_iter().take(3).map(|(_, id)| id).collect()
+    }
+
+    /// Whether the catalog lists `model` of `provider` with image input (`text+image->text`).
+    pub(crate) fn accepts_image_input(&self, provider: &str, model: &str) -> bool {
+        self.models.iter().any(|m| {
+            m.provider == provider
+                && m.id == model
+                && m.modality.split("->").next().is_some_and(|input| input.contains("image"))
+        })
+    }
+
+    /// Catalog entries serving the public `model_id`: the model itself and the same id listed by
+    /// aggregating providers (`deepseek/deepseek-chat` on `openrouter`).
+    pub(crate) fn model_endpoints(&self, model_id: &str) -> Vec<&ModelDescriptor> {
+        self.models
+            .iter()
+            .filter(|m| m.id == model_id || synthesize_model_id(&m.provider, &m.id) == model_id)
+            .collect()
+    }
+
+    /// Catalog context window of `model`; `None` when the model is not in the catalog.
+    pub(crate) fn context_length(&self, provider: &str, model: &str) -> Option<u32> {
+        self.models
+            .iter()
+            .find(|m| m.provider == provider && m.id == model)
+            .map(|m| m.context_length)
+            .filter(|length| *length > 0)
+    }
+
+    /// Catalog pricing of `model`; `None` when the catalog has none.
+    pub(crate) fn model_pricing(&self, provider: &str, model: &str) -> Option<ModelPricing> {
+        self.models
+            .iter()
+            .find(|m| m.provider == provider && m.id == model)
+            .and_then(|m| m.pricing.clone())
+    }
+
+    /// Whether `model` of `provider` can answer in `modality`; text always works, anything else
+    /// must be on the output side of the catalog modality (`text->text+image`).
+    pub(crate) fn supports_output_modality(
+        &self,
+        provider: &str,
+        model: &str,
+        modality: &str,
+    ) -> bool {
+        modality == "text"
+            || self.models.iter().any(|m| {
+                m.provider == provider
+                    && m.id == model
+                    && m.modality
+                        .split_once("->")
+                        .is_some_and(|(_, output)| output.split('+').any(|kind| kind == modality))
+            })
+    }
+
     pub(crate) fn resolve_provider_model_id(&self, model: &str) -> String {
         if let Some((provider, provider_model)) = model.split_once('/')
             && self.engines.contains_key(provider)
@@ -76,6 +560,20 @@ impl AppState {
     }
 }
 
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right = right.chars().collect::<Vec<_>>();
+    let mut previous = (0..=right.len()).collect::<Vec<_>>();
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1; right.len() + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left_char != *right_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
 impl Default for AppState {
     fn default() -> Self {
         Self::new()
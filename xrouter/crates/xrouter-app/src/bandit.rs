@@ -0,0 +1,553 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use xrouter_core::{
+    CoreError, ModelDescriptor, ProviderClient, ProviderGenerateRequest,
+    ProviderGenerateStreamRequest, ProviderOutcome, synthesize_model_id,
+};
+
+use crate::config::{BanditModelConfig, BanditReward, BanditStrategy};
+
+/// Engine key and model namespace of bandit-routed models: `bandit/<alias>`.
+pub(crate) const BANDIT_ENGINE: &str = "bandit";
+
+/// Learned rewards are written at most this often.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct ArmStats {
+    pulls: u64,
+    /// Sum of the rewards earned, each between 0 and 1.
+    reward: f64,
+}
+
+impl ArmStats {
+    fn mean(&self) -> Option<f64> {
+        (self.pulls > 0).then(|| self.reward / self.pulls as f64)
+    }
+}
+
+struct Bandit {
+    strategy: BanditStrategy,
+    reward: BanditReward,
+    epsilon: f64,
+    models: Vec<String>,
+    arms: Vec<ArmStats>,
+    /// Catalog price per token of each model, for [`BanditReward::Cost`].
+    prices: Vec<Option<f64>>,
+}
+
+impl Bandit {
+    fn choose(&self) -> usize {
+        match self.strategy {
+            BanditStrategy::EpsilonGreedy => {
+                if let Some(untried) = self.arms.iter().position(|arm| arm.pulls == 0) {
+                    return untried;
+                }
+                if uniform() < self.epsilon {
+                    return ((uniform() * self.arms.len() as f64) as usize)
+                        .min(self.arms.len() - 1);
+                }
+                best_by(&self.arms, |arm| arm.mean().unwrap_or_default())
+            }
+            BanditStrategy::Thompson => best_by(&self.arms, |arm| {
+                beta(1.0 + arm.reward, 1.0 + arm.pulls as f64 - arm.reward)
+            }),
+        }
+    }
+
+    fn reward(&self, arm: usize, elapsed: Duration) -> f64 {
+        match self.reward {
+            BanditReward::Success => 1.0,
+            BanditReward::Latency => 1.0 / (1.0 + elapsed.as_secs_f64()),
+            // Models without catalog pricing earn the full reward.
+            BanditReward::Cost => {
+                let cheapest = self.prices.iter().flatten().copied().reduce(f64::min);
+                match (cheapest, self.prices[arm]) {
+                    (Some(cheapest), Some(price)) if price > 0.0 => cheapest / price,
+                    _ => 1.0,
+                }
+            }
+        }
+    }
+}
+
+/// Index of the arm scoring highest; ties go to the earlier arm.
+fn best_by(arms: &[ArmStats], mut score: impl FnMut(&ArmStats) -> f64) -> usize {
+    let mut best = (0, f64::NEG_INFINITY);
+    for (index, arm) in arms.iter().enumerate() {
+        let value = score(arm);
+        if value > best.1 {
+            best = (index, value);
+        }
+    }
+    best.0
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BanditSnapshot {
+    pub(crate) alias: String,
+    pub(crate) strategy: BanditStrategy,
+    pub(crate) reward: BanditReward,
+    pub(crate) arms: Vec<ArmSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ArmSnapshot {
+    pub(crate) model: String,
+    pub(crate) pulls: u64,
+    pub(crate) mean_reward: Option<f64>,
+}
+
+/// Saved learned rewards: alias, then public model id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StateFile {
+    bandits: BTreeMap<String, BTreeMap<String, ArmStats>>,
+}
+
+/// Learned rewards of every `bandit/<alias>` model. Models of disabled providers are dropped,
+/// and so are aliases left with fewer than two models.
+pub(crate) struct BanditRegistry {
+    bandits: Mutex<BTreeMap<String, Bandit>>,
+    path: Option<PathBuf>,
+    saved_at: Mutex<Option<Instant>>,
+}
+
+impl BanditRegistry {
+    pub(crate) fn new(
+        config: &BTreeMap<String, BanditModelConfig>,
+        enabled_providers: &HashSet<String>,
+        path: Option<PathBuf>,
+    ) -> Self {
+        let mut saved = path.as_ref().map(load_state).unwrap_or_default();
+        let mut bandits = BTreeMap::new();
+        for (alias, bandit) in config {
+            let models = bandit
+                .models
+                .iter()
+                .filter(|model| {
+                    let enabled = model
+                        .split_once('/')
+                        .is_some_and(|(provider, _)| enabled_providers.contains(provider));
+                    if !enabled {
+                        warn!(event = "app.bandit.arm_disabled", bandit_model = %alias, model = %model);
+                    }
+                    enabled
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if models.len() < 2 {
+                warn!(event = "app.bandit.skipped", bandit_model = %alias, models = models.len());
+                continue;
+            }
+            let mut learned = saved.bandits.remove(alias).unwrap_or_default();
+            let arms =
+                models.iter().map(|model| learned.remove(model).unwrap_or_default()).collect();
+            bandits.insert(
+                alias.clone(),
+                Bandit {
+                    strategy: bandit.strategy,
+                    reward: bandit.reward,
+                    epsilon: bandit.epsilon,
+                    prices: vec![None; models.len()],
+                    models,
+                    arms,
+                },
+            );
+        }
+        if !bandits.is_empty() {
+            info!(event = "app.bandit.initialized", bandit_models = bandits.len());
+        }
+        Self { bandits: Mutex::new(bandits), path, saved_at: Mutex::default() }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bandits().is_empty()
+    }
+
+    /// Public model ids of each alias, in arm order.
+    pub(crate) fn models(&self) -> Vec<(String, Vec<String>)> {
+        self.bandits()
+            .iter()
+            .map(|(alias, bandit)| (alias.clone(), bandit.models.clone()))
+            .collect()
+    }
+
+    /// Takes per-token prices from the loaded catalog, for cost rewards.
+    pub(crate) fn set_prices(&self, catalog: &[ModelDescriptor]) {
+        let prices = catalog
+            .iter()
+            .filter_map(|model| {
+                let pricing = model.pricing.as_ref()?;
+                let price =
+                    pricing.prompt.parse::<f64>().ok()? + pricing.completion.parse::<f64>().ok()?;
+                Some((synthesize_model_id(&model.provider, &model.id), price))
+            })
+            .collect::<HashMap<_, _>>();
+        for bandit in self.bandits().values_mut() {
+            bandit.prices = bandit.models.iter().map(|model| prices.get(model).copied()).collect();
+        }
+    }
+
+    pub(crate) fn choose(&self, alias: &str) -> Option<usize> {
+        self.bandits().get(alias).map(Bandit::choose)
+    }
+
+    /// Credits `arm` with the reward of a finished request. As with model stats, only provider
+    /// failures earn nothing; other errors are not the model's fault and are ignored.
+    pub(crate) fn record_result<T>(
+        &self,
+        alias: &str,
+        arm: usize,
+        result: &Result<T, CoreError>,
+        elapsed: Duration,
+    ) {
+        {
+            let mut bandits = self.bandits();
+            let Some(bandit) = bandits.get_mut(alias) else {
+                return;
+            };
+            let reward = match result {
+                Ok(_) => bandit.reward(arm, elapsed),
+                Err(CoreError::Provider(_)) => 0.0,
+                Err(_) => return,
+            };
+            let stats = &mut bandit.arms[arm];
+            stats.pulls += 1;
+            stats.reward += reward;
+        }
+        self.persist_if_due();
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<BanditSnapshot> {
+        self.bandits()
+            .iter()
+            .map(|(alias, bandit)| BanditSnapshot {
+                alias: alias.clone(),
+                strategy: bandit.strategy,
+                reward: bandit.reward,
+                arms: bandit
+                    .models
+                    .iter()
+                    .zip(&bandit.arms)
+                    .map(|(model, arm)| ArmSnapshot {
+                        model: model.clone(),
+                        pulls: arm.pulls,
+                        mean_reward: arm.mean(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn persist_if_due(&self) {
+        if self.path.is_none() {
+            return;
+        }
+        {
+            let mut saved_at =
+                self.saved_at.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if saved_at.is_some_and(|at| at.elapsed() < PERSIST_INTERVAL) {
+                return;
+            }
+            *saved_at = Some(Instant::now());
+        }
+        self.persist();
+    }
+
+    /// Writes the learned rewards to the state file, if one is configured.
+    pub(crate) fn persist(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let state = StateFile {
+            bandits: self
+                .bandits()
+                .iter()
+                .map(|(alias, bandit)| {
+                    (
+                        alias.clone(),
+                        bandit.models.iter().cloned().zip(bandit.arms.clone()).collect(),
+                    )
+                })
+                .collect(),
+        };
+        let result = serde_json::to_string_pretty(&state)
+            .map_err(|error| error.to_string())
+            .and_then(|payload| {
+                let temp_path = path.with_extension("tmp");
+                fs::write(&temp_path, payload)
+                    .and_then(|()| fs::rename(&temp_path, path))
+                    .map_err(|error| error.to_string())
+            });
+        if let Err(error) = result {
+            warn!(event = "bandit.state.save_failed", path = %path.display(), error = %error);
+        }
+    }
+
+    fn bandits(&self) -> MutexGuard<'_, BTreeMap<String, Bandit>> {
+        self.bandits.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Saves rewards learned since the last throttled write.
+impl Drop for BanditRegistry {
+    fn drop(&mut self) {
+        self.persist();
+    }
+}
+
+/// A missing or unreadable state file starts every bandit from scratch.
+fn load_state(path: &PathBuf) -> StateFile {
+    match fs::read_to_string(path) {
+        Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|error| {
+            warn!(event = "bandit.state.invalid", path = %path.display(), error = %error);
+            StateFile::default()
+        }),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => StateFile::default(),
+        Err(error) => {
+            warn!(event = "bandit.state.unreadable", path = %path.display(), error = %error);
+            StateFile::default()
+        }
+    }
+}
+
+/// Uniform in `[0, 1)`.
+fn uniform() -> f64 {
+    (uuid::Uuid::new_v4().as_u128() as u64 >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn standard_normal() -> f64 {
+    let radius = (-2.0 * (1.0 - uniform()).ln()).sqrt();
+    radius * (std::f64::consts::TAU * uniform()).cos()
+}
+
+/// Marsaglia-Tsang sampling; `shape` is at least 1 for every bandit arm.
+fn gamma(shape: f64) -> f64 {
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = standard_normal();
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u = 1.0 - uniform();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+fn beta(alpha: f64, beta: f64) -> f64 {
+    let x = gamma(alpha);
+    x / (x + gamma(beta))
+}
+
+pub(crate) struct BanditArm {
+    provider: String,
+    model: String,
+    client: Arc<dyn ProviderClient>,
+}
+
+impl BanditArm {
+    pub(crate) fn new(provider: &str, model: &str, client: Arc<dyn ProviderClient>) -> Self {
+        Self { provider: provider.to_string(), model: model.to_string(), client }
+    }
+}
+
+/// Sends each request to the one arm of its alias the bandit picks, and credits that arm with
+/// the outcome.
+pub(crate) struct BanditProviderClient {
+    arms: HashMap<String, Vec<BanditArm>>,
+    registry: Arc<BanditRegistry>,
+}
+
+impl BanditProviderClient {
+    /// `arms` must follow the model order of [`BanditRegistry::models`].
+    pub(crate) fn new(
+        arms: HashMap<String, Vec<BanditArm>>,
+        registry: Arc<BanditRegistry>,
+    ) -> Self {
+        Self { arms, registry }
+    }
+
+    fn pick(
+        &self,
+        request: &ProviderGenerateRequest<'_>,
+    ) -> Result<(usize, &BanditArm), CoreError> {
+        if request.auth_bearer.is_some() {
+            return Err(CoreError::Validation(
+                "bandit models do not accept a caller-supplied provider key".to_string(),
+            ));
+        }
+        let unknown = || {
+            CoreError::Validation(format!(
+                "unknown bandit model: {BANDIT_ENGINE}/{}",
+                request.model
+            ))
+        };
+        let arms = self.arms.get(request.model).ok_or_else(unknown)?;
+        let index = self.registry.choose(request.model).ok_or_else(unknown)?;
+        let arm = arms.get(index).ok_or_else(unknown)?;
+        debug!(
+            event = "provider.bandit.selected",
+            bandit_model = %request.model,
+            provider = %arm.provider,
+            provider_model = %arm.model
+        );
+        Ok((index, arm))
+    }
+}
+
+#[async_trait]
+impl ProviderClient for BanditProviderClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let (index, arm) = self.pick(&request)?;
+        let started_at = Instant::now();
+        let result =
+            arm.client.generate(ProviderGenerateRequest { model: &arm.model, ..request }).await;
+        self.registry.record_result(request.model, index, &result, started_at.elapsed());
+        result
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let (index, arm) = self.pick(&request.request)?;
+        let started_at = Instant::now();
+        let result = arm
+            .client
+            .generate_stream(ProviderGenerateStreamRequest {
+                request_id: request.request_id,
+                request: ProviderGenerateRequest { model: &arm.model, ..request.request },
+                sender: request.sender,
+            })
+            .await;
+        self.registry.record_result(request.request.model, index, &result, started_at.elapsed());
+        result
+    }
+
+    fn supports_required_tool_choice(&self) -> bool {
+        self.arms.values().flatten().all(|arm| arm.client.supports_required_tool_choice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, HashSet},
+        fs,
+        time::Duration,
+    };
+
+    use xrouter_core::CoreError;
+
+    use super::BanditRegistry;
+    use crate::config::{BanditModelConfig, BanditReward, BanditStrategy};
+
+    fn registry(strategy: BanditStrategy, path: Option<std::path::PathBuf>) -> BanditRegistry {
+        let config = BTreeMap::from([(
+            "chat".to_string(),
+            BanditModelConfig {
+                models: vec!["deepseek/deepseek-chat".to_string(), "zai/glm-4.5".to_string()],
+                strategy,
+                reward: BanditReward::Success,
+                epsilon: 0.1,
+            },
+        )]);
+        let enabled = HashSet::from(["deepseek".to_string(), "zai".to_string()]);
+        BanditRegistry::new(&config, &enabled, path)
+    }
+
+    /// Arm 0 always fails upstream and arm 1 always succeeds; returns how often arm 1 was
+    /// picked over the last 100 of 300 requests.
+    fn train(registry: &BanditRegistry) -> usize {
+        let mut picked_good = 0;
+        for round in 0..300 {
+            let arm = registry.choose("chat").expect("bandit must exist");
+            let result: Result<(), CoreError> = if arm == 1 {
+                Ok(())
+            } else {
+                Err(CoreError::Provider("upstream failed".to_string()))
+            };
+            registry.record_result("chat", arm, &result, Duration::from_millis(10));
+            if round >= 200 && arm == 1 {
+                picked_good += 1;
+            }
+        }
+        picked_good
+    }
+
+    #[test]
+    fn thompson_sampling_converges_on_the_succeeding_model() {
+        let registry = registry(BanditStrategy::Thompson, None);
+
+        assert!(train(&registry) >= 95);
+        // A lucky first pick of the good model may leave the failing one untried.
+        let arms = &registry.snapshot()[0].arms;
+        assert!(arms[0].mean_reward.is_none_or(|mean| mean == 0.0));
+        assert!(arms[0].pulls < 30, "failing model picked {} times", arms[0].pulls);
+        assert_eq!(arms[1].mean_reward, Some(1.0));
+    }
+
+    #[test]
+    fn epsilon_greedy_tries_every_model_then_exploits_the_best() {
+        let registry = registry(BanditStrategy::EpsilonGreedy, None);
+
+        assert!(train(&registry) >= 80);
+        assert!(registry.snapshot()[0].arms.iter().all(|arm| arm.pulls > 0));
+    }
+
+    #[test]
+    fn non_provider_errors_do_not_count_against_a_model() {
+        let registry = registry(BanditStrategy::Thompson, None);
+
+        let result: Result<(), CoreError> = Err(CoreError::Validation("bad request".to_string()));
+        registry.record_result("chat", 0, &result, Duration::ZERO);
+
+        assert_eq!(registry.snapshot()[0].arms[0].pulls, 0);
+    }
+
+    #[test]
+    fn learned_rewards_survive_a_restart() {
+        let path = std::env::temp_dir()
+            .join(format!("xrouter-bandit-state-{}.json", uuid::Uuid::new_v4()));
+        let first = registry(BanditStrategy::Thompson, Some(path.clone()));
+        train(&first);
+        first.persist();
+
+        let restarted = registry(BanditStrategy::Thompson, Some(path.clone()));
+
+        assert_eq!(restarted.snapshot(), first.snapshot());
+        fs::remove_file(path).expect("state file must be removed");
+    }
+
+    #[test]
+    fn aliases_with_fewer_than_two_enabled_models_are_dropped() {
+        let config = BTreeMap::from([(
+            "chat".to_string(),
+            BanditModelConfig {
+                models: vec!["deepseek/deepseek-chat".to_string(), "zai/glm-4.5".to_string()],
+                strategy: BanditStrategy::Thompson,
+                reward: BanditReward::Success,
+                epsilon: 0.1,
+            },
+        )]);
+        let enabled = HashSet::from(["deepseek".to_string()]);
+
+        assert!(BanditRegistry::new(&config, &enabled, None).is_empty());
+    }
+}
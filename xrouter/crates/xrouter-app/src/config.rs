@@ -1,5 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use xrouter_clients_openai::{
+    ChaosPolicy, MinTlsVersion, ProviderHttpConfig, ProviderPoolConfig, ProviderProxy,
+    ProviderTlsConfig, SelfHostedQuirks,
+};
+use xrouter_core::build_builtin_transform;
+
+use crate::secrets::SecretResolver;
 
 pub const DEFAULT_OPENROUTER_SUPPORTED_MODELS: &[&str] = &[
     "anthropic/claude-haiku-4.5",
@@ -44,15 +55,403 @@ pub const DEFAULT_OPENROUTER_SUPPORTED_MODELS: &[&str] = &[
     "z-ai/glm-4.7-flash",
     "z-ai/glm-5",
 ];
+pub const DEFAULT_OUTPUT_MODERATION_MESSAGE: &str =
+    "This response was withheld by the output content policy.";
+pub const DEFAULT_MAX_REQUEST_BODY_BYTES: usize = 2 * 1024 * 1024;
+pub const DEFAULT_MAX_HOPS: u32 = 3;
+pub const DEFAULT_BANDIT_EPSILON: f64 = 0.1;
+pub const DEFAULT_ALERT_MIN_REQUESTS: usize = 10;
+pub const DEFAULT_HTTP_REQUEST_TIMEOUT_SECONDS: u64 = 600;
+pub const DEFAULT_HTTP_STREAM_TIMEOUT_SECONDS: u64 = 3600;
+pub const DEFAULT_MAX_PROVIDER_TIMEOUT_MS: u64 = 600_000;
+pub const DEFAULT_MAX_PROVIDER_RETRIES: u32 = 3;
+pub const DEFAULT_STREAM_TRANSCRIPT_MAX_ENTRIES: usize = 50;
+pub const DEFAULT_RESPONSE_STORE_TTL_SECONDS: u64 = 24 * 60 * 60;
+pub const DEFAULT_IDEMPOTENCY_TTL_SECONDS: u64 = 24 * 60 * 60;
+pub const DEFAULT_MODEL_CATALOG_FETCH_TIMEOUT_SECONDS: u64 = 10;
+pub const DEFAULT_MODEL_CATALOG_STARTUP_BUDGET_SECONDS: u64 = 20;
+pub const DEFAULT_RESPONSE_TEE_BUFFER: usize = 1024;
+pub const DEFAULT_EVENT_BUS_TOPIC: &str = "xrouter.events";
+pub const DEFAULT_EVENT_BUS_BUFFER: usize = 1024;
+pub const DEFAULT_USAGE_EXPORT_REGION: &str = "us-east-1";
+pub const DEFAULT_USAGE_EXPORT_PREFIX: &str = "xrouter/";
+pub const DEFAULT_CORS_ALLOWED_METHODS: &[&str] = &["GET", "POST", "PUT", "DELETE", "OPTIONS"];
 pub const DEFAULT_GIGACHAT_SUPPORTED_MODELS: &[&str] =
     &["gigachat/GigaChat-2", "gigachat/GigaChat-2-Max", "gigachat/GigaChat-2-Pro"];
 
-#[derive(Debug, Clone)]
+/// Browser access to the API; CORS is off while `allowed_origins` is empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// Allowed `Origin` values; `*` allows any origin.
+    pub allowed_origins: Vec<String>,
+    /// Allowed request headers; empty echoes whatever the preflight asks for.
+    pub allowed_headers: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub max_age_seconds: Option<u64>,
+}
+
+impl CorsConfig {
+    pub fn enabled(&self) -> bool {
+        !self.allowed_origins.is_empty()
+    }
+}
+
+/// Overload protection of the HTTP layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpLimitsConfig {
+    /// Requests in flight at once before new ones are shed with `503`; unlimited when unset.
+    pub max_concurrent_requests: Option<usize>,
+    /// Time an inference route has to produce its response (the headers, for streams).
+    pub request_timeout_seconds: u64,
+    /// Total lifetime of an event stream, counted from the request.
+    pub stream_timeout_seconds: u64,
+}
+
+impl Default for HttpLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: None,
+            request_timeout_seconds: DEFAULT_HTTP_REQUEST_TIMEOUT_SECONDS,
+            stream_timeout_seconds: DEFAULT_HTTP_STREAM_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+/// Ceilings on the upstream timeout and retries a request may ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpstreamLimitsConfig {
+    pub max_timeout_ms: u64,
+    pub max_retries: u32,
+}
+
+impl Default for UpstreamLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_timeout_ms: DEFAULT_MAX_PROVIDER_TIMEOUT_MS,
+            max_retries: DEFAULT_MAX_PROVIDER_RETRIES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ProviderConfig {
     pub enabled: bool,
     pub api_key: Option<String>,
     pub base_url: Option<String>,
     pub project: Option<String>,
+    pub http: ProviderHttpConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TransformConfig {
+    pub name: String,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(flatten)]
+    pub params: Map<String, Value>,
+}
+
+/// How a `bandit/<alias>` model picks the model serving each request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BanditStrategy {
+    /// Samples each model's reward distribution and takes the best draw.
+    #[default]
+    Thompson,
+    /// Takes the best mean reward, or a random model for an `epsilon` share of requests.
+    EpsilonGreedy,
+}
+
+/// What a served request earns its model, between 0 and 1; provider failures earn 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BanditReward {
+    #[default]
+    Success,
+    /// `1 / (1 + seconds)` of the request.
+    Latency,
+    /// Catalog price of the cheapest model over this model's price.
+    Cost,
+}
+
+/// One `bandit/<alias>` model, see `XR_BANDIT_MODELS`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BanditModelConfig {
+    /// Public model ids the bandit chooses between.
+    pub models: Vec<String>,
+    #[serde(default)]
+    pub strategy: BanditStrategy,
+    #[serde(default)]
+    pub reward: BanditReward,
+    /// Share of requests an epsilon-greedy bandit sends to a random model.
+    #[serde(default = "default_bandit_epsilon")]
+    pub epsilon: f64,
+}
+
+fn default_bandit_epsilon() -> f64 {
+    DEFAULT_BANDIT_EPSILON
+}
+
+/// Provider statistic an alert rule watches over the last 5 minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// Percentage of calls that failed with a provider error.
+    ErrorRate,
+    /// 95th percentile latency of successful calls, in milliseconds.
+    P95LatencyMs,
+}
+
+/// One `XR_ALERT_RULES` entry.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRuleConfig {
+    pub name: String,
+    /// Provider the rule watches; each provider separately when unset.
+    #[serde(default)]
+    pub provider: Option<String>,
+    pub metric: AlertMetric,
+    /// The rule is breached while the metric is above this value.
+    pub threshold: f64,
+    /// How long the breach must last before a notification is sent.
+    #[serde(default)]
+    pub for_minutes: u64,
+    /// Calls the 5 minute window needs before the rule is evaluated.
+    #[serde(default = "default_alert_min_requests")]
+    pub min_requests: usize,
+}
+
+fn default_alert_min_requests() -> usize {
+    DEFAULT_ALERT_MIN_REQUESTS
+}
+
+/// Operator corrections merged over the catalog entry of one model; unset fields keep the
+/// fetched or built-in value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelOverrideConfig {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub context_length: Option<u32>,
+    #[serde(default)]
+    pub max_completion_tokens: Option<u32>,
+    #[serde(default)]
+    pub supports_tools: Option<bool>,
+    #[serde(default)]
+    pub is_moderated: Option<bool>,
+    #[serde(default)]
+    pub pricing: Option<ModelPricingConfig>,
+    #[serde(default)]
+    pub supported_parameters: Option<Vec<String>>,
+}
+
+/// USD per token as decimal strings, e.g. `"0.0000025"`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPricingConfig {
+    pub prompt: String,
+    pub completion: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantConfig {
+    pub organization: String,
+    pub project: String,
+    pub api_keys: Vec<String>,
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    #[serde(default)]
+    pub denied_models: Vec<String>,
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<usize>,
+    #[serde(default)]
+    pub token_budget: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderFixturesConfig {
+    Off,
+    Record { dir: PathBuf },
+    Replay { dir: PathBuf },
+}
+
+/// Routing of a model id that neither names an enabled provider nor is in the catalog.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum UnknownModelConfig {
+    /// Sent unchanged to the default provider.
+    #[default]
+    Passthrough,
+    /// Rejected with `400`, suggesting close catalog ids.
+    Reject,
+    /// Replaced by this public model id.
+    DefaultModel { model: String },
+}
+
+/// How input over the model's context window is cut down before it is sent upstream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    /// Rejected with `400`.
+    #[default]
+    Off,
+    DropOldest,
+    MiddleOut,
+    /// Earlier turns are replaced by a summary from `XR_TRUNCATION_SUMMARY_MODEL`.
+    Summarize,
+}
+
+impl TruncationStrategy {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "off" => Some(Self::Off),
+            "drop_oldest" => Some(Self::DropOldest),
+            "middle_out" => Some(Self::MiddleOut),
+            "summarize" => Some(Self::Summarize),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::DropOldest => "drop_oldest",
+            Self::MiddleOut => "middle_out",
+            Self::Summarize => "summarize",
+        }
+    }
+}
+
+/// Request headers allowed to override body fields, see `XR_HEADER_OVERRIDES`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeaderOverrides {
+    /// `x-xrouter-model` replaces `model`.
+    pub model: bool,
+    /// `x-xrouter-provider` routes the model to another enabled provider.
+    pub provider: bool,
+    /// `x-xrouter-reasoning-effort` replaces `reasoning.effort`.
+    pub reasoning_effort: bool,
+}
+
+impl HeaderOverrides {
+    pub const NAMES: [&'static str; 3] = ["model", "provider", "reasoning_effort"];
+
+    /// Comma-separated override names, `all`, or `none`. `None` for unknown names.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let mut overrides = Self::default();
+        for name in raw.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name.to_ascii_lowercase().as_str() {
+                "none" => {}
+                "all" => {
+                    overrides = Self { model: true, provider: true, reasoning_effort: true };
+                }
+                "model" => overrides.model = true,
+                "provider" => overrides.provider = true,
+                "reasoning_effort" => overrides.reasoning_effort = true,
+                _ => return None,
+            }
+        }
+        Some(overrides)
+    }
+
+    pub fn names(self) -> Vec<&'static str> {
+        Self::NAMES
+            .into_iter()
+            .zip([self.model, self.provider, self.reasoning_effort])
+            .filter_map(|(name, enabled)| enabled.then_some(name))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyStoreConfig {
+    Memory,
+    File {
+        path: PathBuf,
+    },
+    Postgres {
+        url: String,
+    },
+    /// In the database of `StorageConfig::Sqlite`.
+    Sqlite,
+}
+
+/// Where state for `previous_response_id` follow-ups is kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResponseStoreConfig {
+    Memory,
+    Redis {
+        url: String,
+    },
+    Postgres {
+        url: String,
+    },
+    /// In the database of `StorageConfig::Sqlite`.
+    Sqlite,
+}
+
+/// Whether the stores of a single node share one embedded database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageConfig {
+    /// Every store uses the backend of its own setting.
+    PerStore,
+    /// The usage ledger, generation log, key store and response store live in this file.
+    Sqlite { path: PathBuf },
+}
+
+/// Where completed responses are copied for downstream analytics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResponseTeeConfig {
+    Off,
+    /// JSON arrays of records POSTed to the URL.
+    Webhook {
+        url: String,
+    },
+    /// Records produced to the topic through a Kafka REST proxy at the URL.
+    Kafka {
+        url: String,
+        topic: String,
+    },
+}
+
+/// Message bus that receives request lifecycle events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventBusConfig {
+    Off,
+    /// Produced to the topic through a Kafka REST proxy at the URL.
+    Kafka {
+        url: String,
+        topic: String,
+    },
+    Nats {
+        url: String,
+        subject: String,
+    },
+}
+
+/// S3-compatible bucket that receives the daily usage and generation exports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageExportConfig {
+    /// Path-style bucket URL, e.g. `https://s3.eu-central-1.amazonaws.com/analytics`.
+    pub bucket_url: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Prepended to every object key; empty or ending in `/`.
+    pub prefix: String,
+}
+
+/// Where rate-limit windows and provider in-flight budgets are counted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoordinationConfig {
+    /// Per replica, in process memory.
+    Memory,
+    /// Shared by every replica using the same Redis.
+    Redis { url: String },
 }
 
 #[derive(Debug, Clone)]
@@ -61,11 +460,94 @@ pub struct AppConfig {
     pub port: u16,
     pub openai_compatible_api: bool,
     pub byok_enabled: bool,
+    /// Serves the model catalog only; no provider clients are built and inference routes
+    /// answer `503`.
+    pub catalog_only: bool,
     pub provider_timeout_seconds: u64,
+    /// Bounds each upstream model catalog request made at startup.
+    pub model_catalog_fetch_timeout_seconds: u64,
+    /// Bounds loading all upstream model catalogs, which are fetched concurrently; providers
+    /// still pending afterwards are served their fallback models.
+    pub model_catalog_startup_budget_seconds: u64,
+    /// File holding the last successfully fetched upstream catalogs, served for providers whose
+    /// catalog cannot be fetched at startup.
+    pub model_catalog_cache_path: Option<PathBuf>,
     pub provider_max_inflight: usize,
     pub gigachat_insecure_tls: bool,
+    /// Talk to TGI through its native `generate_stream` API instead of the Messages API.
+    pub tgi_native_api: bool,
+    /// Talk to Yandex through chat completions instead of the Responses API.
+    pub yandex_chat_completions: bool,
+    /// API deviations the `selfhosted` provider works around, see `SELFHOSTED_QUIRKS`.
+    pub selfhosted_quirks: SelfHostedQuirks,
+    /// Account whose Workers AI models the `cloudflare` provider runs.
+    pub cloudflare_account_id: Option<String>,
     pub openrouter_supported_models: Vec<String>,
     pub gigachat_supported_models: Vec<String>,
+    pub transforms: Vec<TransformConfig>,
+    /// Request body fields outside the API schema that are forwarded upstream, per provider.
+    pub passthrough_fields: HashMap<String, Vec<String>>,
+    /// `race/<alias>` models: public model ids raced against each other, see `XR_RACE_MODELS`.
+    pub race_models: BTreeMap<String, Vec<String>>,
+    /// `bandit/<alias>` models, see `XR_BANDIT_MODELS`.
+    pub bandit_models: BTreeMap<String, BanditModelConfig>,
+    /// Where learned bandit rewards are kept across restarts; in memory only when unset.
+    pub bandit_state_path: Option<PathBuf>,
+    pub unknown_model: UnknownModelConfig,
+    /// Default for requests whose key and `x-xrouter-truncation` header name none.
+    pub truncation: TruncationStrategy,
+    /// Public model id that writes summaries for [`TruncationStrategy::Summarize`].
+    pub truncation_summary_model: Option<String>,
+    /// Public model ids whose reasoning is left out of responses, see `XR_HIDE_REASONING_MODELS`.
+    pub hide_reasoning_models: Vec<String>,
+    pub header_overrides: HeaderOverrides,
+    /// Catalog metadata corrections keyed by model id, see `XR_MODEL_OVERRIDES`.
+    pub model_overrides: BTreeMap<String, ModelOverrideConfig>,
+    pub output_moderation_blocklist: Vec<String>,
+    pub output_moderation_message: String,
+    pub output_moderation_buffer_stream: bool,
+    pub tool_choice_required_emulation: bool,
+    pub user_rate_limit_per_minute: Option<usize>,
+    pub stream_retained_output_bytes: Option<usize>,
+    /// Streams whose provider sends no event for this long are aborted; `None` never aborts.
+    pub stream_stall_timeout_seconds: Option<u64>,
+    /// Ends a stream that fails after some text with the partial text instead of an error.
+    pub stream_salvage_partial: bool,
+    pub max_request_body_bytes: usize,
+    /// Routers a request may already have passed (`X-XRouter-Hop`) before this one refuses it.
+    pub max_hops: u32,
+    pub http_limits: HttpLimitsConfig,
+    pub upstream_limits: UpstreamLimitsConfig,
+    pub request_coalescing: bool,
+    pub priority_scheduling: bool,
+    pub cors: CorsConfig,
+    pub stream_transcript_dir: Option<PathBuf>,
+    pub stream_transcript_max_entries: usize,
+    pub provider_fixtures: ProviderFixturesConfig,
+    pub chaos: Option<ChaosPolicy>,
+    pub tenants: Vec<TenantConfig>,
+    pub admin_token: Option<String>,
+    pub storage: StorageConfig,
+    pub key_store: KeyStoreConfig,
+    pub coordination: CoordinationConfig,
+    pub response_store: ResponseStoreConfig,
+    pub response_store_ttl_seconds: u64,
+    /// Token cap of a `previous_response_id` conversation; untracked when unset.
+    pub conversation_token_budget: Option<u64>,
+    /// Refuses turns past `conversation_token_budget` instead of only reporting it.
+    pub conversation_budget_enforced: bool,
+    pub alert_rules: Vec<AlertRuleConfig>,
+    /// Where alert notifications are POSTed; required with `alert_rules`.
+    pub alert_webhook_url: Option<String>,
+    /// Applies pending database migrations before serving instead of refusing to start.
+    pub database_migrate_on_startup: bool,
+    pub idempotency_ttl_seconds: u64,
+    pub response_tee: ResponseTeeConfig,
+    pub response_tee_deltas: bool,
+    pub response_tee_buffer: usize,
+    pub event_bus: EventBusConfig,
+    pub event_bus_buffer: usize,
+    pub usage_export: Option<UsageExportConfig>,
     pub providers: HashMap<String, ProviderConfig>,
 }
 
@@ -77,10 +559,122 @@ pub enum ConfigError {
     InvalidOpenAiCompatibleApiBool(String),
     #[error("invalid XR_BYOK_ENABLED value: {0}")]
     InvalidByokEnabledBool(String),
+    #[error("invalid XR_CATALOG_ONLY value: {0}")]
+    InvalidCatalogOnlyBool(String),
+    #[error("invalid XR_REQUEST_COALESCING value: {0}")]
+    InvalidRequestCoalescingBool(String),
+    #[error("invalid XR_PRIORITY_SCHEDULING value: {0}")]
+    InvalidPrioritySchedulingBool(String),
     #[error("invalid XR_PROVIDER_TIMEOUT value: {0}")]
     InvalidProviderConnectTimeout(String),
     #[error("invalid XR_PROVIDER_MAX_INFLIGHT value: {0}")]
     InvalidProviderMaxInflight(String),
+    #[error("invalid XR_TRANSFORMS value: {0}")]
+    InvalidTransforms(String),
+    #[error("invalid XR_PASSTHROUGH_FIELDS value: {0}")]
+    InvalidPassthroughFields(String),
+    #[error("invalid XR_RACE_MODELS value: {0}")]
+    InvalidRaceModels(String),
+    #[error("invalid XR_BANDIT_MODELS value: {0}")]
+    InvalidBanditModels(String),
+    #[error("invalid XR_MODEL_OVERRIDES value: {0}")]
+    InvalidModelOverrides(String),
+    #[error("invalid XR_OUTPUT_MODERATION_BUFFER_STREAM value: {0}")]
+    InvalidOutputModerationBufferStreamBool(String),
+    #[error("invalid XR_TOOL_CHOICE_REQUIRED_EMULATION value: {0}")]
+    InvalidToolChoiceRequiredEmulationBool(String),
+    #[error("invalid XR_USER_RATE_LIMIT_PER_MINUTE value: {0}")]
+    InvalidUserRateLimit(String),
+    #[error("invalid XR_STREAM_RETAINED_OUTPUT_BYTES value: {0}")]
+    InvalidStreamRetainedOutputBytes(String),
+    #[error("invalid XR_STREAM_STALL_TIMEOUT_SECONDS value: {0}")]
+    InvalidStreamStallTimeout(String),
+    #[error("invalid XR_STREAM_SALVAGE_PARTIAL value: {0}")]
+    InvalidStreamSalvagePartialBool(String),
+    #[error("invalid XR_MAX_REQUEST_BODY_BYTES value: {0}")]
+    InvalidMaxRequestBodyBytes(String),
+    #[error("invalid XR_MAX_HOPS value: {0}")]
+    InvalidMaxHops(String),
+    #[error("invalid XR_HTTP_MAX_CONCURRENT_REQUESTS value: {0}")]
+    InvalidHttpMaxConcurrentRequests(String),
+    #[error("invalid XR_HTTP_REQUEST_TIMEOUT_SECONDS value: {0}")]
+    InvalidHttpRequestTimeout(String),
+    #[error("invalid XR_HTTP_STREAM_TIMEOUT_SECONDS value: {0}")]
+    InvalidHttpStreamTimeout(String),
+    #[error("invalid XR_MAX_PROVIDER_TIMEOUT_MS value: {0}")]
+    InvalidMaxProviderTimeout(String),
+    #[error("invalid XR_MAX_PROVIDER_RETRIES value: {0}")]
+    InvalidMaxProviderRetries(String),
+    #[error("invalid XR_CORS_ALLOWED_ORIGINS value: {0}")]
+    InvalidCorsAllowedOrigins(String),
+    #[error("invalid XR_CORS_ALLOWED_HEADERS value: {0}")]
+    InvalidCorsAllowedHeaders(String),
+    #[error("invalid XR_CORS_ALLOWED_METHODS value: {0}")]
+    InvalidCorsAllowedMethods(String),
+    #[error("invalid XR_CORS_MAX_AGE_SECONDS value: {0}")]
+    InvalidCorsMaxAge(String),
+    #[error("invalid XR_STREAM_TRANSCRIPT_MAX_ENTRIES value: {0}")]
+    InvalidStreamTranscriptMaxEntries(String),
+    #[error("invalid XR_PROVIDER_FIXTURES value: {0}")]
+    InvalidProviderFixtures(String),
+    #[error("invalid XR_CHAOS value: {0}")]
+    InvalidChaos(String),
+    #[error("invalid XR_TENANTS value: {0}")]
+    InvalidTenants(String),
+    #[error("invalid XR_UNKNOWN_MODEL value: {0}")]
+    InvalidUnknownModel(String),
+    #[error("invalid XR_TRUNCATION value: {0}")]
+    InvalidTruncation(String),
+    #[error("invalid XR_HEADER_OVERRIDES value: {0}")]
+    InvalidHeaderOverrides(String),
+    #[error("invalid XR_KEY_STORE value: {0}")]
+    InvalidKeyStore(String),
+    #[error("invalid XR_STORAGE value: {0}")]
+    InvalidStorage(String),
+    #[error("invalid XR_COORDINATION value: {0}")]
+    InvalidCoordination(String),
+    #[error("invalid XR_RESPONSE_STORE value: {0}")]
+    InvalidResponseStore(String),
+    #[error("invalid XR_RESPONSE_STORE_TTL_SECONDS value: {0}")]
+    InvalidResponseStoreTtl(String),
+    #[error("invalid XR_DATABASE_MIGRATE_ON_STARTUP value: {0}")]
+    InvalidDatabaseMigrateOnStartupBool(String),
+    #[error("invalid XR_RESPONSE_TEE value: {0}")]
+    InvalidResponseTee(String),
+    #[error("invalid XR_RESPONSE_TEE_DELTAS value: {0}")]
+    InvalidResponseTeeDeltasBool(String),
+    #[error("invalid XR_RESPONSE_TEE_BUFFER value: {0}")]
+    InvalidResponseTeeBuffer(String),
+    #[error("invalid XR_EVENT_BUS value: {0}")]
+    InvalidEventBus(String),
+    #[error("invalid XR_EVENT_BUS_BUFFER value: {0}")]
+    InvalidEventBusBuffer(String),
+    #[error("invalid XR_USAGE_EXPORT_URL value: {0}")]
+    InvalidUsageExport(String),
+    #[error("invalid XR_CONVERSATION_TOKEN_BUDGET value: {0}")]
+    InvalidConversationTokenBudget(String),
+    #[error("invalid XR_CONVERSATION_BUDGET_ENFORCED value: {0}")]
+    InvalidConversationBudgetEnforcedBool(String),
+    #[error("invalid XR_ALERT_RULES value: {0}")]
+    InvalidAlertRules(String),
+    #[error("invalid XR_IDEMPOTENCY_TTL_SECONDS value: {0}")]
+    InvalidIdempotencyTtl(String),
+    #[error("invalid XR_MODEL_CATALOG_FETCH_TIMEOUT_SECONDS value: {0}")]
+    InvalidModelCatalogFetchTimeout(String),
+    #[error("invalid XR_MODEL_CATALOG_STARTUP_BUDGET_SECONDS value: {0}")]
+    InvalidModelCatalogStartupBudget(String),
+    #[error("invalid secret reference: {0}")]
+    InvalidSecret(String),
+    #[error("invalid provider TLS settings: {0}")]
+    InvalidProviderTls(String),
+    #[error("invalid provider proxy value for {0}")]
+    InvalidProviderProxy(String),
+    #[error("invalid provider connection pool value: {0}")]
+    InvalidProviderPool(String),
+    #[error("invalid config file: {0}")]
+    InvalidConfigFile(String),
+    #[error("invalid SELFHOSTED_QUIRKS value: {0}")]
+    InvalidSelfhostedQuirks(String),
 }
 
 impl AppConfig {
@@ -99,59 +693,685 @@ impl AppConfig {
         let byok_enabled_raw = env::var("XR_BYOK_ENABLED").unwrap_or_else(|_| "false".to_string());
         let byok_enabled = parse_bool(&byok_enabled_raw)
             .ok_or_else(|| ConfigError::InvalidByokEnabledBool(byok_enabled_raw.clone()))?;
+        let catalog_only_raw = env::var("XR_CATALOG_ONLY").unwrap_or_else(|_| "false".to_string());
+        let catalog_only = parse_bool(&catalog_only_raw)
+            .ok_or_else(|| ConfigError::InvalidCatalogOnlyBool(catalog_only_raw.clone()))?;
         let provider_timeout_raw =
             env::var("XR_PROVIDER_TIMEOUT").unwrap_or_else(|_| "15".to_string());
         let provider_timeout_seconds = provider_timeout_raw.parse::<u64>().map_err(|_| {
             ConfigError::InvalidProviderConnectTimeout(provider_timeout_raw.clone())
         })?;
+        let model_catalog_fetch_timeout_seconds =
+            match env::var("XR_MODEL_CATALOG_FETCH_TIMEOUT_SECONDS") {
+                Ok(raw) if !raw.trim().is_empty() => parse_positive_usize(&raw)
+                    .map(|seconds| seconds as u64)
+                    .ok_or(ConfigError::InvalidModelCatalogFetchTimeout(raw))?,
+                _ => DEFAULT_MODEL_CATALOG_FETCH_TIMEOUT_SECONDS,
+            };
+        let model_catalog_startup_budget_seconds =
+            match env::var("XR_MODEL_CATALOG_STARTUP_BUDGET_SECONDS") {
+                Ok(raw) if !raw.trim().is_empty() => parse_positive_usize(&raw)
+                    .map(|seconds| seconds as u64)
+                    .ok_or(ConfigError::InvalidModelCatalogStartupBudget(raw))?,
+                _ => DEFAULT_MODEL_CATALOG_STARTUP_BUDGET_SECONDS,
+            };
+        let model_catalog_cache_path = env::var("XR_MODEL_CATALOG_CACHE_PATH")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(PathBuf::from);
         let provider_max_inflight_raw =
             env::var("XR_PROVIDER_MAX_INFLIGHT").unwrap_or_else(|_| "100".to_string());
         let provider_max_inflight = parse_positive_usize(&provider_max_inflight_raw)
             .ok_or(ConfigError::InvalidProviderMaxInflight(provider_max_inflight_raw))?;
         let gigachat_insecure_tls =
             env::var("GIGACHAT_INSECURE_TLS").ok().and_then(|v| parse_bool(&v)).unwrap_or(false);
+        let tgi_native_api =
+            env::var("TGI_NATIVE_API").ok().and_then(|v| parse_bool(&v)).unwrap_or(false);
+        let yandex_chat_completions =
+            env::var("YANDEX_CHAT_COMPLETIONS").ok().and_then(|v| parse_bool(&v)).unwrap_or(false);
+        let selfhosted_quirks = match env::var("SELFHOSTED_QUIRKS") {
+            Ok(raw) if !raw.trim().is_empty() => {
+                SelfHostedQuirks::parse(&raw).ok_or(ConfigError::InvalidSelfhostedQuirks(raw))?
+            }
+            _ => SelfHostedQuirks::default(),
+        };
+        let cloudflare_account_id =
+            env::var("CLOUDFLARE_ACCOUNT_ID").ok().filter(|value| !value.trim().is_empty());
         let openrouter_supported_models = parse_string_list_env(
             "OPENROUTER_SUPPORTED_MODELS",
             DEFAULT_OPENROUTER_SUPPORTED_MODELS,
         );
         let gigachat_supported_models =
             parse_string_list_env("GIGACHAT_SUPPORTED_MODELS", DEFAULT_GIGACHAT_SUPPORTED_MODELS);
+        let transforms = match env::var("XR_TRANSFORMS") {
+            Ok(raw) => parse_transforms(&raw).map_err(ConfigError::InvalidTransforms)?,
+            Err(_) => Vec::new(),
+        };
+        let output_moderation_blocklist =
+            parse_string_list_env("XR_OUTPUT_MODERATION_BLOCKLIST", &[]);
+        let output_moderation_message = env::var("XR_OUTPUT_MODERATION_MESSAGE")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_OUTPUT_MODERATION_MESSAGE.to_string());
+        let output_moderation_buffer_stream_raw =
+            env::var("XR_OUTPUT_MODERATION_BUFFER_STREAM").unwrap_or_else(|_| "false".to_string());
+        let output_moderation_buffer_stream = parse_bool(&output_moderation_buffer_stream_raw)
+            .ok_or(ConfigError::InvalidOutputModerationBufferStreamBool(
+                output_moderation_buffer_stream_raw,
+            ))?;
+        let tool_choice_required_emulation_raw =
+            env::var("XR_TOOL_CHOICE_REQUIRED_EMULATION").unwrap_or_else(|_| "false".to_string());
+        let tool_choice_required_emulation = parse_bool(&tool_choice_required_emulation_raw)
+            .ok_or(ConfigError::InvalidToolChoiceRequiredEmulationBool(
+                tool_choice_required_emulation_raw,
+            ))?;
+
+        let user_rate_limit_per_minute = match env::var("XR_USER_RATE_LIMIT_PER_MINUTE") {
+            Ok(raw) if !raw.trim().is_empty() => {
+                Some(parse_positive_usize(&raw).ok_or(ConfigError::InvalidUserRateLimit(raw))?)
+            }
+            _ => None,
+        };
+        let stream_retained_output_bytes = match env::var("XR_STREAM_RETAINED_OUTPUT_BYTES") {
+            Ok(raw) if !raw.trim().is_empty() => Some(
+                parse_positive_usize(&raw)
+                    .ok_or(ConfigError::InvalidStreamRetainedOutputBytes(raw))?,
+            ),
+            _ => None,
+        };
+        let stream_stall_timeout_seconds = match env::var("XR_STREAM_STALL_TIMEOUT_SECONDS") {
+            Ok(raw) if !raw.trim().is_empty() => Some(
+                parse_positive_usize(&raw)
+                    .map(|seconds| seconds as u64)
+                    .ok_or(ConfigError::InvalidStreamStallTimeout(raw))?,
+            ),
+            _ => None,
+        };
+        let stream_salvage_partial_raw =
+            env::var("XR_STREAM_SALVAGE_PARTIAL").unwrap_or_else(|_| "false".to_string());
+        let stream_salvage_partial = parse_bool(&stream_salvage_partial_raw).ok_or_else(|| {
+            ConfigError::InvalidStreamSalvagePartialBool(stream_salvage_partial_raw.clone())
+        })?;
+        let request_coalescing_raw =
+            env::var("XR_REQUEST_COALESCING").unwrap_or_else(|_| "false".to_string());
+        let request_coalescing = parse_bool(&request_coalescing_raw).ok_or_else(|| {
+            ConfigError::InvalidRequestCoalescingBool(request_coalescing_raw.clone())
+        })?;
+        let priority_scheduling_raw =
+            env::var("XR_PRIORITY_SCHEDULING").unwrap_or_else(|_| "false".to_string());
+        let priority_scheduling = parse_bool(&priority_scheduling_raw).ok_or_else(|| {
+            ConfigError::InvalidPrioritySchedulingBool(priority_scheduling_raw.clone())
+        })?;
+        let max_request_body_bytes = match env::var("XR_MAX_REQUEST_BODY_BYTES") {
+            Ok(raw) if !raw.trim().is_empty() => {
+                parse_positive_usize(&raw).ok_or(ConfigError::InvalidMaxRequestBodyBytes(raw))?
+            }
+            _ => DEFAULT_MAX_REQUEST_BODY_BYTES,
+        };
+        let max_hops = match env::var("XR_MAX_HOPS") {
+            Ok(raw) if !raw.trim().is_empty() => {
+                raw.trim().parse::<u32>().map_err(|_| ConfigError::InvalidMaxHops(raw))?
+            }
+            _ => DEFAULT_MAX_HOPS,
+        };
+        let http_limits = parse_http_limits()?;
+        let upstream_limits = parse_upstream_limits()?;
+        let cors = parse_cors()?;
+        let stream_transcript_dir = env::var("XR_STREAM_TRANSCRIPT_DIR")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(PathBuf::from);
+        let stream_transcript_max_entries = match env::var("XR_STREAM_TRANSCRIPT_MAX_ENTRIES") {
+            Ok(raw) if !raw.trim().is_empty() => parse_positive_usize(&raw)
+                .ok_or(ConfigError::InvalidStreamTranscriptMaxEntries(raw))?,
+            _ => DEFAULT_STREAM_TRANSCRIPT_MAX_ENTRIES,
+        };
+        let provider_fixtures = parse_provider_fixtures(
+            &env::var("XR_PROVIDER_FIXTURES").unwrap_or_default(),
+            env::var("XR_PROVIDER_FIXTURES_DIR").ok().as_deref(),
+        )
+        .map_err(ConfigError::InvalidProviderFixtures)?;
+        let chaos = parse_chaos(&env::var("XR_CHAOS").unwrap_or_default())
+            .map_err(ConfigError::InvalidChaos)?;
+        let passthrough_fields =
+            parse_passthrough_fields(&env::var("XR_PASSTHROUGH_FIELDS").unwrap_or_default())
+                .map_err(ConfigError::InvalidPassthroughFields)?;
+        let race_models = parse_race_models(&env::var("XR_RACE_MODELS").unwrap_or_default())
+            .map_err(ConfigError::InvalidRaceModels)?;
+        let bandit_models = parse_bandit_models(&env::var("XR_BANDIT_MODELS").unwrap_or_default())
+            .map_err(ConfigError::InvalidBanditModels)?;
+        let bandit_state_path = env::var("XR_BANDIT_STATE_PATH")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(PathBuf::from);
+        let unknown_model = parse_unknown_model(
+            &env::var("XR_UNKNOWN_MODEL").unwrap_or_default(),
+            env::var("XR_DEFAULT_MODEL").ok().as_deref(),
+        )
+        .map_err(ConfigError::InvalidUnknownModel)?;
+        let truncation_raw = env::var("XR_TRUNCATION").unwrap_or_default();
+        let truncation = TruncationStrategy::parse(&truncation_raw)
+            .ok_or_else(|| ConfigError::InvalidTruncation(truncation_raw.clone()))?;
+        let truncation_summary_model = env::var("XR_TRUNCATION_SUMMARY_MODEL")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        if truncation == TruncationStrategy::Summarize && truncation_summary_model.is_none() {
+            return Err(ConfigError::InvalidTruncation(
+                "summarize requires XR_TRUNCATION_SUMMARY_MODEL".to_string(),
+            ));
+        }
+        let hide_reasoning_models = parse_string_list_env("XR_HIDE_REASONING_MODELS", &[]);
+        let header_overrides_raw = env::var("XR_HEADER_OVERRIDES").unwrap_or_default();
+        let header_overrides = HeaderOverrides::parse(&header_overrides_raw)
+            .ok_or(ConfigError::InvalidHeaderOverrides(header_overrides_raw))?;
+        let model_overrides =
+            parse_model_overrides(&env::var("XR_MODEL_OVERRIDES").unwrap_or_default())
+                .map_err(ConfigError::InvalidModelOverrides)?;
+        let tenants = parse_tenants(&env::var("XR_TENANTS").unwrap_or_default())
+            .map_err(ConfigError::InvalidTenants)?;
+        let secrets = SecretResolver::from_env();
+        let admin_token = env::var("XR_ADMIN_TOKEN")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| secrets.resolve(&value))
+            .transpose()
+            .map_err(|error| ConfigError::InvalidSecret(format!("XR_ADMIN_TOKEN: {error}")))?;
+        let key_store_url = env::var("XR_KEY_STORE_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| secrets.resolve(&value))
+            .transpose()
+            .map_err(|error| ConfigError::InvalidSecret(format!("XR_KEY_STORE_URL: {error}")))?;
+        let key_store = parse_key_store(
+            &env::var("XR_KEY_STORE").unwrap_or_default(),
+            env::var("XR_KEY_STORE_PATH").ok().as_deref(),
+            key_store_url.as_deref(),
+        )
+        .map_err(ConfigError::InvalidKeyStore)?;
+        let redis_url = env::var("XR_REDIS_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| secrets.resolve(&value))
+            .transpose()
+            .map_err(|error| ConfigError::InvalidSecret(format!("XR_REDIS_URL: {error}")))?;
+        let coordination = parse_coordination(
+            &env::var("XR_COORDINATION").unwrap_or_default(),
+            redis_url.as_deref(),
+        )
+        .map_err(ConfigError::InvalidCoordination)?;
+        let response_store_url = env::var("XR_RESPONSE_STORE_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| secrets.resolve(&value))
+            .transpose()
+            .map_err(|error| {
+                ConfigError::InvalidSecret(format!("XR_RESPONSE_STORE_URL: {error}"))
+            })?;
+        let response_store = parse_response_store(
+            &env::var("XR_RESPONSE_STORE").unwrap_or_default(),
+            response_store_url.as_deref(),
+        )
+        .map_err(ConfigError::InvalidResponseStore)?;
+        let storage = parse_storage(&env::var("XR_STORAGE").unwrap_or_default())
+            .map_err(ConfigError::InvalidStorage)?;
+        let (key_store, response_store) = place_stores(&storage, key_store, response_store)
+            .map_err(ConfigError::InvalidStorage)?;
+        let response_store_ttl_seconds = match env::var("XR_RESPONSE_STORE_TTL_SECONDS") {
+            Ok(raw) if !raw.trim().is_empty() => parse_positive_usize(&raw)
+                .map(|seconds| seconds as u64)
+                .ok_or(ConfigError::InvalidResponseStoreTtl(raw))?,
+            _ => DEFAULT_RESPONSE_STORE_TTL_SECONDS,
+        };
+        let conversation_token_budget = match env::var("XR_CONVERSATION_TOKEN_BUDGET") {
+            Ok(raw) if !raw.trim().is_empty() => Some(
+                parse_positive_usize(&raw)
+                    .map(|tokens| tokens as u64)
+                    .ok_or(ConfigError::InvalidConversationTokenBudget(raw))?,
+            ),
+            _ => None,
+        };
+        let conversation_budget_enforced_raw =
+            env::var("XR_CONVERSATION_BUDGET_ENFORCED").unwrap_or_else(|_| "false".to_string());
+        let conversation_budget_enforced = parse_bool(&conversation_budget_enforced_raw).ok_or(
+            ConfigError::InvalidConversationBudgetEnforcedBool(conversation_budget_enforced_raw),
+        )?;
+        let alert_webhook_url = env::var("XR_ALERT_WEBHOOK_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| secrets.resolve(&value))
+            .transpose()
+            .map_err(|error| {
+                ConfigError::InvalidSecret(format!("XR_ALERT_WEBHOOK_URL: {error}"))
+            })?;
+        let alert_rules = parse_alert_rules(
+            &env::var("XR_ALERT_RULES").unwrap_or_default(),
+            alert_webhook_url.as_deref(),
+        )
+        .map_err(ConfigError::InvalidAlertRules)?;
+        let database_migrate_on_startup_raw =
+            env::var("XR_DATABASE_MIGRATE_ON_STARTUP").unwrap_or_else(|_| "true".to_string());
+        let database_migrate_on_startup = parse_bool(&database_migrate_on_startup_raw).ok_or(
+            ConfigError::InvalidDatabaseMigrateOnStartupBool(database_migrate_on_startup_raw),
+        )?;
+        let idempotency_ttl_seconds = match env::var("XR_IDEMPOTENCY_TTL_SECONDS") {
+            Ok(raw) if !raw.trim().is_empty() => parse_positive_usize(&raw)
+                .map(|seconds| seconds as u64)
+                .ok_or(ConfigError::InvalidIdempotencyTtl(raw))?,
+            _ => DEFAULT_IDEMPOTENCY_TTL_SECONDS,
+        };
+        let response_tee_url = env::var("XR_RESPONSE_TEE_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| secrets.resolve(&value))
+            .transpose()
+            .map_err(|error| ConfigError::InvalidSecret(format!("XR_RESPONSE_TEE_URL: {error}")))?;
+        let response_tee = parse_response_tee(
+            &env::var("XR_RESPONSE_TEE").unwrap_or_default(),
+            response_tee_url.as_deref(),
+            env::var("XR_RESPONSE_TEE_TOPIC").ok().as_deref(),
+        )
+        .map_err(ConfigError::InvalidResponseTee)?;
+        let response_tee_deltas_raw =
+            env::var("XR_RESPONSE_TEE_DELTAS").unwrap_or_else(|_| "false".to_string());
+        let response_tee_deltas = parse_bool(&response_tee_deltas_raw)
+            .ok_or(ConfigError::InvalidResponseTeeDeltasBool(response_tee_deltas_raw))?;
+        let response_tee_buffer = match env::var("XR_RESPONSE_TEE_BUFFER") {
+            Ok(raw) if !raw.trim().is_empty() => {
+                parse_positive_usize(&raw).ok_or(ConfigError::InvalidResponseTeeBuffer(raw))?
+            }
+            _ => DEFAULT_RESPONSE_TEE_BUFFER,
+        };
+        let event_bus_url = env::var("XR_EVENT_BUS_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| secrets.resolve(&value))
+            .transpose()
+            .map_err(|error| ConfigError::InvalidSecret(format!("XR_EVENT_BUS_URL: {error}")))?;
+        let event_bus = parse_event_bus(
+            &env::var("XR_EVENT_BUS").unwrap_or_default(),
+            event_bus_url.as_deref(),
+            env::var("XR_EVENT_BUS_TOPIC").ok().as_deref(),
+        )
+        .map_err(ConfigError::InvalidEventBus)?;
+        let event_bus_buffer = match env::var("XR_EVENT_BUS_BUFFER") {
+            Ok(raw) if !raw.trim().is_empty() => {
+                parse_positive_usize(&raw).ok_or(ConfigError::InvalidEventBusBuffer(raw))?
+            }
+            _ => DEFAULT_EVENT_BUS_BUFFER,
+        };
+        let usage_export_secret = |name: &str| {
+            env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .map(|value| secrets.resolve(&value))
+                .transpose()
+                .map_err(|error| ConfigError::InvalidSecret(format!("{name}: {error}")))
+        };
+        let usage_export = parse_usage_export(
+            usage_export_secret("XR_USAGE_EXPORT_URL")?.as_deref(),
+            env::var("XR_USAGE_EXPORT_REGION").ok().as_deref(),
+            usage_export_secret("XR_USAGE_EXPORT_ACCESS_KEY_ID")?.as_deref(),
+            usage_export_secret("XR_USAGE_EXPORT_SECRET_ACCESS_KEY")?.as_deref(),
+            env::var("XR_USAGE_EXPORT_PREFIX").ok().as_deref(),
+        )
+        .map_err(ConfigError::InvalidUsageExport)?;
+        if byok_enabled && (!tenants.is_empty() || admin_token.is_some()) {
+            return Err(ConfigError::InvalidTenants(
+                "tenant API keys cannot be combined with XR_BYOK_ENABLED=true".to_string(),
+            ));
+        }
 
-        let providers = [
-            provider_from_env("openrouter", "OPENROUTER"),
-            provider_from_env("deepseek", "DEEPSEEK"),
-            provider_from_env("gigachat", "GIGACHAT"),
-            provider_from_env("yandex", "YANDEX"),
-            provider_from_env("ollama", "OLLAMA"),
-            provider_from_env("zai", "ZAI"),
-            provider_from_env("xrouter", "XROUTER"),
+        let mut providers = [
+            provider_from_env("openrouter", "OPENROUTER")?,
+            provider_from_env("deepseek", "DEEPSEEK")?,
+            provider_from_env("gigachat", "GIGACHAT")?,
+            provider_from_env("yandex", "YANDEX")?,
+            provider_from_env("ollama", "OLLAMA")?,
+            provider_from_env("zai", "ZAI")?,
+            provider_from_env("xrouter", "XROUTER")?,
+            provider_from_env("tgi", "TGI")?,
+            provider_from_env("selfhosted", "SELFHOSTED")?,
+            provider_from_env("cohere", "COHERE")?,
+            provider_from_env("perplexity", "PERPLEXITY")?,
+            provider_from_env("cloudflare", "CLOUDFLARE")?,
         ]
         .into_iter()
         .collect::<HashMap<_, _>>();
+        if let Some(unknown) = passthrough_fields.keys().find(|name| !providers.contains_key(*name))
+        {
+            return Err(ConfigError::InvalidPassthroughFields(format!(
+                "unknown provider {unknown}"
+            )));
+        }
+        if let Some(unknown) = race_models.values().flatten().find(|model| {
+            model.split_once('/').is_none_or(|(provider, _)| !providers.contains_key(provider))
+        }) {
+            return Err(ConfigError::InvalidRaceModels(format!(
+                "{unknown} does not start with a known provider"
+            )));
+        }
+        if byok_enabled && !race_models.is_empty() {
+            return Err(ConfigError::InvalidRaceModels(
+                "race models cannot be combined with XR_BYOK_ENABLED=true".to_string(),
+            ));
+        }
+        if let Some(unknown) =
+            bandit_models.values().flat_map(|bandit| &bandit.models).find(|model| {
+                model.split_once('/').is_none_or(|(provider, _)| !providers.contains_key(provider))
+            })
+        {
+            return Err(ConfigError::InvalidBanditModels(format!(
+                "{unknown} does not start with a known provider"
+            )));
+        }
+        if byok_enabled && !bandit_models.is_empty() {
+            return Err(ConfigError::InvalidBanditModels(
+                "bandit models cannot be combined with XR_BYOK_ENABLED=true".to_string(),
+            ));
+        }
+        for (name, provider_config) in providers.iter_mut() {
+            if !provider_config.enabled {
+                continue;
+            }
+            if let Some(raw) = provider_config.api_key.as_deref() {
+                let resolved = secrets.resolve(raw).map_err(|error| {
+                    ConfigError::InvalidSecret(format!("{name} api key: {error}"))
+                })?;
+                provider_config.api_key = Some(resolved);
+            }
+        }
 
         Ok(Self {
             host,
             port,
             openai_compatible_api,
             byok_enabled,
+            catalog_only,
             provider_timeout_seconds,
+            model_catalog_fetch_timeout_seconds,
+            model_catalog_startup_budget_seconds,
+            model_catalog_cache_path,
             provider_max_inflight,
             gigachat_insecure_tls,
+            tgi_native_api,
+            yandex_chat_completions,
+            selfhosted_quirks,
+            cloudflare_account_id,
             openrouter_supported_models,
             gigachat_supported_models,
+            transforms,
+            passthrough_fields,
+            race_models,
+            bandit_models,
+            bandit_state_path,
+            unknown_model,
+            truncation,
+            truncation_summary_model,
+            hide_reasoning_models,
+            header_overrides,
+            model_overrides,
+            output_moderation_blocklist,
+            output_moderation_message,
+            output_moderation_buffer_stream,
+            tool_choice_required_emulation,
+            user_rate_limit_per_minute,
+            stream_retained_output_bytes,
+            stream_stall_timeout_seconds,
+            stream_salvage_partial,
+            max_request_body_bytes,
+            max_hops,
+            http_limits,
+            upstream_limits,
+            request_coalescing,
+            priority_scheduling,
+            cors,
+            stream_transcript_dir,
+            stream_transcript_max_entries,
+            provider_fixtures,
+            chaos,
+            tenants,
+            admin_token,
+            storage,
+            key_store,
+            coordination,
+            response_store,
+            response_store_ttl_seconds,
+            conversation_token_budget,
+            conversation_budget_enforced,
+            alert_rules,
+            alert_webhook_url,
+            database_migrate_on_startup,
+            idempotency_ttl_seconds,
+            response_tee,
+            response_tee_deltas,
+            response_tee_buffer,
+            event_bus,
+            event_bus_buffer,
+            usage_export,
             providers,
         })
     }
 
-    pub fn for_tests() -> Self {
+    /// Effective settings as JSON for `config validate`; secrets are reported only as set/unset.
+    pub fn redacted_summary(&self) -> Value {
+        let redacted = |value: Option<&String>| value.map(|_| "<redacted>");
+        let mut providers = self.providers.iter().collect::<Vec<_>>();
+        providers.sort_by_key(|(name, _)| name.as_str());
+        let providers = providers
+            .into_iter()
+            .map(|(name, provider)| {
+                (
+                    name.clone(),
+                    json!({
+                        "enabled": provider.enabled,
+                        "api_key": redacted(provider.api_key.as_ref()),
+                        "base_url": provider.base_url,
+                        "project": provider.project,
+                        "proxy": provider.http.proxy.as_ref().map(|_| "<redacted>"),
+                        "ca_bundle": provider.http.tls.ca_bundle_path,
+                        "client_cert": provider.http.tls.client_cert_path,
+                    }),
+                )
+            })
+            .collect::<Map<_, _>>();
+        let tenants = self
+            .tenants
+            .iter()
+            .map(|tenant| {
+                json!({
+                    "organization": tenant.organization,
+                    "project": tenant.project,
+                    "api_key_count": tenant.api_keys.len(),
+                    "allowed_models": tenant.allowed_models,
+                    "denied_models": tenant.denied_models,
+                    "rate_limit_per_minute": tenant.rate_limit_per_minute,
+                    "token_budget": tenant.token_budget,
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "server": {
+                "host": self.host,
+                "port": self.port,
+                "openai_compatible_api": self.openai_compatible_api,
+                "byok_enabled": self.byok_enabled,
+                "catalog_only": self.catalog_only,
+            },
+            "limits": {
+                "provider_timeout": self.provider_timeout_seconds,
+                "provider_max_inflight": self.provider_max_inflight,
+                "user_rate_limit_per_minute": self.user_rate_limit_per_minute,
+                "stream_retained_output_bytes": self.stream_retained_output_bytes,
+                "stream_stall_timeout_seconds": self.stream_stall_timeout_seconds,
+                "stream_salvage_partial": self.stream_salvage_partial,
+                "max_request_body_bytes": self.max_request_body_bytes,
+                "max_hops": self.max_hops,
+                "max_concurrent_requests": self.http_limits.max_concurrent_requests,
+                "request_timeout_seconds": self.http_limits.request_timeout_seconds,
+                "stream_timeout_seconds": self.http_limits.stream_timeout_seconds,
+                "max_provider_timeout_ms": self.upstream_limits.max_timeout_ms,
+                "max_provider_retries": self.upstream_limits.max_retries,
+                "request_coalescing": self.request_coalescing,
+                "priority_scheduling": self.priority_scheduling,
+            },
+            "catalog": {
+                "fetch_timeout_seconds": self.model_catalog_fetch_timeout_seconds,
+                "startup_budget_seconds": self.model_catalog_startup_budget_seconds,
+                "cache_path": self.model_catalog_cache_path,
+                "model_overrides": self.model_overrides.keys().collect::<Vec<_>>(),
+            },
+            "cors": {
+                "allowed_origins": self.cors.allowed_origins,
+                "allowed_headers": self.cors.allowed_headers,
+                "allowed_methods": self.cors.allowed_methods,
+                "max_age_seconds": self.cors.max_age_seconds,
+            },
+            "debug": {
+                "stream_transcript_dir": self.stream_transcript_dir,
+                "stream_transcript_max_entries": self.stream_transcript_max_entries,
+                "provider_fixtures": match &self.provider_fixtures {
+                    ProviderFixturesConfig::Off => json!("off"),
+                    ProviderFixturesConfig::Record { dir } => json!({ "record": dir }),
+                    ProviderFixturesConfig::Replay { dir } => json!({ "replay": dir }),
+                },
+                "chaos": self.chaos.is_some(),
+            },
+            "routing": {
+                "transforms": self.transforms.iter().map(|transform| transform.name.as_str()).collect::<Vec<_>>(),
+                "passthrough_fields": self.passthrough_fields,
+                "race_models": self.race_models,
+                "bandit_models": self.bandit_models.iter().map(|(alias, bandit)| {
+                    (alias.clone(), json!({
+                        "models": bandit.models,
+                        "strategy": bandit.strategy,
+                        "reward": bandit.reward,
+                    }))
+                }).collect::<Map<_, _>>(),
+                "bandit_state_path": self.bandit_state_path,
+                "unknown_model": match &self.unknown_model {
+                    UnknownModelConfig::Passthrough => json!("passthrough"),
+                    UnknownModelConfig::Reject => json!("reject"),
+                    UnknownModelConfig::DefaultModel { model } => json!({ "default_model": model }),
+                },
+                "truncation": self.truncation.as_str(),
+                "truncation_summary_model": self.truncation_summary_model,
+                "hide_reasoning_models": self.hide_reasoning_models,
+                "header_overrides": self.header_overrides.names(),
+                "tool_choice_required_emulation": self.tool_choice_required_emulation,
+                "openrouter_supported_models": self.openrouter_supported_models.len(),
+                "gigachat_supported_models": self.gigachat_supported_models.len(),
+            },
+            "moderation": {
+                "blocklist": self.output_moderation_blocklist.len(),
+                "buffer_stream": self.output_moderation_buffer_stream,
+            },
+            "tenancy": {
+                "tenants": tenants,
+                "admin_token": redacted(self.admin_token.as_ref()),
+                "key_store": match &self.key_store {
+                    KeyStoreConfig::Memory => json!("memory"),
+                    KeyStoreConfig::File { path } => json!({ "file": path }),
+                    KeyStoreConfig::Postgres { .. } => json!({ "postgres": "<redacted>" }),
+                    KeyStoreConfig::Sqlite => json!("sqlite"),
+                },
+            },
+            "response_store": {
+                "backend": match &self.response_store {
+                    ResponseStoreConfig::Memory => "memory",
+                    ResponseStoreConfig::Redis { .. } => "redis",
+                    ResponseStoreConfig::Postgres { .. } => "postgres",
+                    ResponseStoreConfig::Sqlite => "sqlite",
+                },
+                "url": match &self.response_store {
+                    ResponseStoreConfig::Memory | ResponseStoreConfig::Sqlite => None,
+                    _ => Some("<redacted>"),
+                },
+                "ttl_seconds": self.response_store_ttl_seconds,
+            },
+            "conversation": {
+                "token_budget": self.conversation_token_budget,
+                "budget_enforced": self.conversation_budget_enforced,
+            },
+            "alerts": {
+                "rules": self.alert_rules.iter().map(|rule| rule.name.as_str()).collect::<Vec<_>>(),
+                "webhook_url": redacted(self.alert_webhook_url.as_ref()),
+            },
+            "database": {
+                "migrate_on_startup": self.database_migrate_on_startup,
+                "storage": match &self.storage {
+                    StorageConfig::PerStore => json!("per_store"),
+                    StorageConfig::Sqlite { path } => json!({ "sqlite": path }),
+                },
+            },
+            "idempotency": { "ttl_seconds": self.idempotency_ttl_seconds },
+            "response_tee": {
+                "backend": match &self.response_tee {
+                    ResponseTeeConfig::Off => "off",
+                    ResponseTeeConfig::Webhook { .. } => "webhook",
+                    ResponseTeeConfig::Kafka { .. } => "kafka",
+                },
+                "url": match &self.response_tee {
+                    ResponseTeeConfig::Off => None,
+                    _ => Some("<redacted>"),
+                },
+                "topic": match &self.response_tee {
+                    ResponseTeeConfig::Kafka { topic, .. } => Some(topic.as_str()),
+                    _ => None,
+                },
+                "deltas": self.response_tee_deltas,
+                "buffer": self.response_tee_buffer,
+            },
+            "event_bus": {
+                "backend": match &self.event_bus {
+                    EventBusConfig::Off => "off",
+                    EventBusConfig::Kafka { .. } => "kafka",
+                    EventBusConfig::Nats { .. } => "nats",
+                },
+                "url": match &self.event_bus {
+                    EventBusConfig::Off => None,
+                    _ => Some("<redacted>"),
+                },
+                "topic": match &self.event_bus {
+                    EventBusConfig::Off => None,
+                    EventBusConfig::Kafka { topic, .. } => Some(topic.as_str()),
+                    EventBusConfig::Nats { subject, .. } => Some(subject.as_str()),
+                },
+                "buffer": self.event_bus_buffer,
+            },
+            "usage_export": self.usage_export.as_ref().map(|export| json!({
+                "bucket_url": "<redacted>",
+                "region": export.region,
+                "prefix": export.prefix,
+            })),
+            "coordination": match &self.coordination {
+                CoordinationConfig::Memory => json!({ "backend": "memory" }),
+                CoordinationConfig::Redis { .. } => {
+                    json!({ "backend": "redis", "redis_url": "<redacted>" })
+                }
+            },
+            "providers": providers,
+        })
+    }
+
+    /// Defaults for embedding via `XRouter::builder()`: optional features off and no providers.
+    pub fn embedded() -> Self {
         Self {
             host: "127.0.0.1".to_string(),
             port: 3000,
             openai_compatible_api: false,
             byok_enabled: false,
+            catalog_only: false,
             provider_timeout_seconds: 15,
+            model_catalog_fetch_timeout_seconds: DEFAULT_MODEL_CATALOG_FETCH_TIMEOUT_SECONDS,
+            model_catalog_startup_budget_seconds: DEFAULT_MODEL_CATALOG_STARTUP_BUDGET_SECONDS,
+            model_catalog_cache_path: None,
             provider_max_inflight: 100,
             gigachat_insecure_tls: false,
+            tgi_native_api: false,
+            yandex_chat_completions: false,
+            selfhosted_quirks: SelfHostedQuirks::default(),
+            cloudflare_account_id: None,
             openrouter_supported_models: DEFAULT_OPENROUTER_SUPPORTED_MODELS
                 .iter()
                 .map(|model| (*model).to_string())
@@ -160,43 +1380,141 @@ impl AppConfig {
                 .iter()
                 .map(|model| (*model).to_string())
                 .collect(),
+            transforms: Vec::new(),
+            passthrough_fields: HashMap::new(),
+            race_models: BTreeMap::new(),
+            bandit_models: BTreeMap::new(),
+            bandit_state_path: None,
+            unknown_model: UnknownModelConfig::Passthrough,
+            truncation: TruncationStrategy::Off,
+            truncation_summary_model: None,
+            hide_reasoning_models: Vec::new(),
+            header_overrides: HeaderOverrides::default(),
+            model_overrides: BTreeMap::new(),
+            output_moderation_blocklist: Vec::new(),
+            output_moderation_message: DEFAULT_OUTPUT_MODERATION_MESSAGE.to_string(),
+            output_moderation_buffer_stream: false,
+            tool_choice_required_emulation: false,
+            user_rate_limit_per_minute: None,
+            stream_retained_output_bytes: None,
+            stream_stall_timeout_seconds: None,
+            stream_salvage_partial: false,
+            max_request_body_bytes: DEFAULT_MAX_REQUEST_BODY_BYTES,
+            max_hops: DEFAULT_MAX_HOPS,
+            http_limits: HttpLimitsConfig::default(),
+            upstream_limits: UpstreamLimitsConfig::default(),
+            request_coalescing: false,
+            priority_scheduling: false,
+            cors: CorsConfig::default(),
+            stream_transcript_dir: None,
+            stream_transcript_max_entries: DEFAULT_STREAM_TRANSCRIPT_MAX_ENTRIES,
+            provider_fixtures: ProviderFixturesConfig::Off,
+            chaos: None,
+            tenants: Vec::new(),
+            admin_token: None,
+            storage: StorageConfig::PerStore,
+            key_store: KeyStoreConfig::Memory,
+            coordination: CoordinationConfig::Memory,
+            response_store: ResponseStoreConfig::Memory,
+            response_store_ttl_seconds: DEFAULT_RESPONSE_STORE_TTL_SECONDS,
+            conversation_token_budget: None,
+            conversation_budget_enforced: false,
+            alert_rules: Vec::new(),
+            alert_webhook_url: None,
+            database_migrate_on_startup: true,
+            idempotency_ttl_seconds: DEFAULT_IDEMPOTENCY_TTL_SECONDS,
+            response_tee: ResponseTeeConfig::Off,
+            response_tee_deltas: false,
+            response_tee_buffer: DEFAULT_RESPONSE_TEE_BUFFER,
+            event_bus: EventBusConfig::Off,
+            event_bus_buffer: DEFAULT_EVENT_BUS_BUFFER,
+            usage_export: None,
+            providers: HashMap::new(),
+        }
+    }
+
+    pub fn for_tests() -> Self {
+        Self {
             providers: [
                 (
                     "openrouter".to_string(),
-                    ProviderConfig { enabled: true, api_key: None, base_url: None, project: None },
+                    ProviderConfig {
+                        enabled: true,
+                        api_key: None,
+                        base_url: None,
+                        project: None,
+                        http: ProviderHttpConfig::default(),
+                    },
                 ),
                 (
                     "deepseek".to_string(),
-                    ProviderConfig { enabled: true, api_key: None, base_url: None, project: None },
+                    ProviderConfig {
+                        enabled: true,
+                        api_key: None,
+                        base_url: None,
+                        project: None,
+                        http: ProviderHttpConfig::default(),
+                    },
                 ),
                 (
                     "gigachat".to_string(),
-                    ProviderConfig { enabled: true, api_key: None, base_url: None, project: None },
+                    ProviderConfig {
+                        enabled: true,
+                        api_key: None,
+                        base_url: None,
+                        project: None,
+                        http: ProviderHttpConfig::default(),
+                    },
                 ),
                 (
                     "yandex".to_string(),
-                    ProviderConfig { enabled: true, api_key: None, base_url: None, project: None },
+                    ProviderConfig {
+                        enabled: true,
+                        api_key: None,
+                        base_url: None,
+                        project: None,
+                        http: ProviderHttpConfig::default(),
+                    },
                 ),
                 (
                     "ollama".to_string(),
-                    ProviderConfig { enabled: true, api_key: None, base_url: None, project: None },
+                    ProviderConfig {
+                        enabled: true,
+                        api_key: None,
+                        base_url: None,
+                        project: None,
+                        http: ProviderHttpConfig::default(),
+                    },
                 ),
                 (
                     "zai".to_string(),
-                    ProviderConfig { enabled: true, api_key: None, base_url: None, project: None },
+                    ProviderConfig {
+                        enabled: true,
+                        api_key: None,
+                        base_url: None,
+                        project: None,
+                        http: ProviderHttpConfig::default(),
+                    },
                 ),
                 (
                     "xrouter".to_string(),
-                    ProviderConfig { enabled: true, api_key: None, base_url: None, project: None },
+                    ProviderConfig {
+                        enabled: true,
+                        api_key: None,
+                        base_url: None,
+                        project: None,
+                        http: ProviderHttpConfig::default(),
+                    },
                 ),
             ]
             .into_iter()
             .collect(),
+            ..Self::embedded()
         }
     }
 }
 
-fn provider_from_env(name: &str, prefix: &str) -> (String, ProviderConfig) {
+fn provider_from_env(name: &str, prefix: &str) -> Result<(String, ProviderConfig), ConfigError> {
     let enabled_var = format!("{prefix}_ENABLED");
     let enabled = env::var(enabled_var).ok().and_then(|v| parse_bool(&v)).unwrap_or(true);
 
@@ -222,16 +1540,99 @@ fn provider_from_env(name: &str, prefix: &str) -> (String, ProviderConfig) {
         env::var(project_var).ok().filter(|v| !v.trim().is_empty())
     };
 
-    (name.to_string(), ProviderConfig { enabled, api_key, base_url, project })
+    let proxy = match env::var(format!("{prefix}_PROXY")).ok().filter(|v| !v.trim().is_empty()) {
+        Some(raw) => Some(
+            ProviderProxy::parse(&raw)
+                .ok_or_else(|| ConfigError::InvalidProviderProxy(format!("{prefix}_PROXY")))?,
+        ),
+        None => global_proxy_from_env()?,
+    };
+    let http = ProviderHttpConfig {
+        proxy,
+        tls: provider_tls_from_env(prefix)?,
+        pool: provider_pool_from_env(prefix)?,
+    };
+
+    Ok((name.to_string(), ProviderConfig { enabled, api_key, base_url, project, http }))
+}
+
+fn global_proxy_from_env() -> Result<Option<ProviderProxy>, ConfigError> {
+    env::var("XR_PROVIDER_PROXY")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(|raw| {
+            ProviderProxy::parse(&raw)
+                .ok_or_else(|| ConfigError::InvalidProviderProxy("XR_PROVIDER_PROXY".to_string()))
+        })
+        .transpose()
+}
+
+fn provider_pool_from_env(prefix: &str) -> Result<ProviderPoolConfig, ConfigError> {
+    let read = |suffix: &str| {
+        [format!("{prefix}_{suffix}"), format!("XR_PROVIDER_{suffix}")].into_iter().find_map(
+            |name| {
+                env::var(&name).ok().filter(|value| !value.trim().is_empty()).map(|raw| (name, raw))
+            },
+        )
+    };
+    let invalid = |name: &str, raw: &str| ConfigError::InvalidProviderPool(format!("{name}={raw}"));
+    let max_idle_per_host = read("POOL_MAX_IDLE_PER_HOST")
+        .map(|(name, raw)| raw.trim().parse::<usize>().map_err(|_| invalid(&name, &raw)))
+        .transpose()?;
+    let idle_timeout_seconds = read("POOL_IDLE_TIMEOUT_SECONDS")
+        .map(|(name, raw)| raw.trim().parse::<u64>().map_err(|_| invalid(&name, &raw)))
+        .transpose()?;
+    let http2_keep_alive_interval_seconds = read("HTTP2_KEEPALIVE_SECONDS")
+        .map(|(name, raw)| {
+            parse_positive_usize(&raw).map(|value| value as u64).ok_or_else(|| invalid(&name, &raw))
+        })
+        .transpose()?;
+    let tcp_nodelay = read("TCP_NODELAY")
+        .map(|(name, raw)| parse_bool(&raw).ok_or_else(|| invalid(&name, &raw)))
+        .transpose()?;
+    Ok(ProviderPoolConfig {
+        max_idle_per_host,
+        idle_timeout_seconds,
+        http2_keep_alive_interval_seconds,
+        tcp_nodelay,
+    })
+}
+
+fn provider_tls_from_env(prefix: &str) -> Result<ProviderTlsConfig, ConfigError> {
+    let read = |suffix: &str| {
+        env::var(format!("{prefix}_{suffix}")).ok().filter(|value| !value.trim().is_empty())
+    };
+    let min_tls_version = read("MIN_TLS_VERSION")
+        .map(|raw| {
+            MinTlsVersion::parse(&raw).ok_or_else(|| {
+                ConfigError::InvalidProviderTls(format!("{prefix}_MIN_TLS_VERSION={raw}"))
+            })
+        })
+        .transpose()?;
+    let tls = ProviderTlsConfig {
+        ca_bundle_path: read("CA_BUNDLE").map(PathBuf::from),
+        client_cert_path: read("CLIENT_CERT").map(PathBuf::from),
+        client_key_path: read("CLIENT_KEY").map(PathBuf::from),
+        min_tls_version,
+    };
+    if tls.client_cert_path.is_some() != tls.client_key_path.is_some() {
+        return Err(ConfigError::InvalidProviderTls(format!(
+            "{prefix}_CLIENT_CERT and {prefix}_CLIENT_KEY must be set together"
+        )));
+    }
+    Ok(tls)
 }
 
-fn default_provider_base_url(provider: &str) -> Option<&'static str> {
+pub(crate) fn default_provider_base_url(provider: &str) -> Option<&'static str> {
     match provider {
         "deepseek" => Some("https://api.deepseek.com"),
         "openrouter" => Some("https://openrouter.ai/api/v1"),
         "gigachat" => Some("https://gigachat.devices.sberbank.ru/api/v1"),
         "zai" => Some("https://api.z.ai/api/paas/v4"),
         "yandex" => Some("https://ai.api.cloud.yandex.net/v1"),
+        "cohere" => Some("https://api.cohere.com/v2"),
+        "perplexity" => Some("https://api.perplexity.ai"),
+        "cloudflare" => Some("https://api.cloudflare.com/client/v4"),
         _ => None,
     }
 }
@@ -244,6 +1645,84 @@ fn parse_bool(value: &str) -> Option<bool> {
     }
 }
 
+fn parse_http_limits() -> Result<HttpLimitsConfig, ConfigError> {
+    let max_concurrent_requests = match env::var("XR_HTTP_MAX_CONCURRENT_REQUESTS") {
+        Ok(raw) if !raw.trim().is_empty() => Some(
+            parse_positive_usize(&raw).ok_or(ConfigError::InvalidHttpMaxConcurrentRequests(raw))?,
+        ),
+        _ => None,
+    };
+    let request_timeout_seconds = match env::var("XR_HTTP_REQUEST_TIMEOUT_SECONDS") {
+        Ok(raw) if !raw.trim().is_empty() => parse_positive_usize(&raw)
+            .map(|seconds| seconds as u64)
+            .ok_or(ConfigError::InvalidHttpRequestTimeout(raw))?,
+        _ => DEFAULT_HTTP_REQUEST_TIMEOUT_SECONDS,
+    };
+    let stream_timeout_seconds = match env::var("XR_HTTP_STREAM_TIMEOUT_SECONDS") {
+        Ok(raw) if !raw.trim().is_empty() => parse_positive_usize(&raw)
+            .map(|seconds| seconds as u64)
+            .ok_or(ConfigError::InvalidHttpStreamTimeout(raw))?,
+        _ => DEFAULT_HTTP_STREAM_TIMEOUT_SECONDS,
+    };
+    Ok(HttpLimitsConfig {
+        max_concurrent_requests,
+        request_timeout_seconds,
+        stream_timeout_seconds,
+    })
+}
+
+fn parse_upstream_limits() -> Result<UpstreamLimitsConfig, ConfigError> {
+    let max_timeout_ms = match env::var("XR_MAX_PROVIDER_TIMEOUT_MS") {
+        Ok(raw) if !raw.trim().is_empty() => parse_positive_usize(&raw)
+            .map(|ms| ms as u64)
+            .ok_or(ConfigError::InvalidMaxProviderTimeout(raw))?,
+        _ => DEFAULT_MAX_PROVIDER_TIMEOUT_MS,
+    };
+    let max_retries = match env::var("XR_MAX_PROVIDER_RETRIES") {
+        Ok(raw) if !raw.trim().is_empty() => {
+            raw.trim().parse::<u32>().map_err(|_| ConfigError::InvalidMaxProviderRetries(raw))?
+        }
+        _ => DEFAULT_MAX_PROVIDER_RETRIES,
+    };
+    Ok(UpstreamLimitsConfig { max_timeout_ms, max_retries })
+}
+
+fn parse_cors() -> Result<CorsConfig, ConfigError> {
+    let allowed_origins = parse_string_list_env("XR_CORS_ALLOWED_ORIGINS", &[]);
+    if let Some(invalid) = allowed_origins.iter().find(|origin| {
+        origin.as_str() != "*" && axum::http::HeaderValue::from_str(origin.as_str()).is_err()
+    }) {
+        return Err(ConfigError::InvalidCorsAllowedOrigins(invalid.clone()));
+    }
+    if allowed_origins.len() > 1 && allowed_origins.iter().any(|origin| origin == "*") {
+        return Err(ConfigError::InvalidCorsAllowedOrigins(
+            "`*` cannot be combined with explicit origins".to_string(),
+        ));
+    }
+    let allowed_headers = parse_string_list_env("XR_CORS_ALLOWED_HEADERS", &[]);
+    if let Some(invalid) = allowed_headers
+        .iter()
+        .find(|header| axum::http::HeaderName::from_bytes(header.as_bytes()).is_err())
+    {
+        return Err(ConfigError::InvalidCorsAllowedHeaders(invalid.clone()));
+    }
+    let allowed_methods =
+        parse_string_list_env("XR_CORS_ALLOWED_METHODS", DEFAULT_CORS_ALLOWED_METHODS);
+    if let Some(invalid) = allowed_methods
+        .iter()
+        .find(|method| axum::http::Method::from_bytes(method.as_bytes()).is_err())
+    {
+        return Err(ConfigError::InvalidCorsAllowedMethods(invalid.clone()));
+    }
+    let max_age_seconds = match env::var("XR_CORS_MAX_AGE_SECONDS") {
+        Ok(raw) if !raw.trim().is_empty() => {
+            Some(raw.trim().parse::<u64>().map_err(|_| ConfigError::InvalidCorsMaxAge(raw))?)
+        }
+        _ => None,
+    };
+    Ok(CorsConfig { allowed_origins, allowed_headers, allowed_methods, max_age_seconds })
+}
+
 fn parse_positive_usize(value: &str) -> Option<usize> {
     let parsed = value.trim().parse::<usize>().ok()?;
     if parsed == 0 { None } else { Some(parsed) }
@@ -280,9 +1759,474 @@ fn parse_string_list(trimmed: &str, default: &[&str]) -> Vec<String> {
     if parsed.is_empty() { fallback() } else { parsed }
 }
 
+fn parse_transforms(raw: &str) -> Result<Vec<TransformConfig>, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Ok(Vec::new());
+    }
+    let transforms = serde_json::from_str::<Vec<TransformConfig>>(trimmed)
+        .map_err(|error| format!("expected JSON array of transforms: {error}"))?;
+    for transform in &transforms {
+        build_builtin_transform(&transform.name, &transform.params)
+            .map_err(|error| error.to_string())?;
+    }
+    Ok(transforms)
+}
+
+fn parse_tenants(raw: &str) -> Result<Vec<TenantConfig>, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Ok(Vec::new());
+    }
+    let tenants = serde_json::from_str::<Vec<TenantConfig>>(trimmed)
+        .map_err(|error| format!("expected JSON array of tenants: {error}"))?;
+    let mut seen_ids = std::collections::HashSet::new();
+    let mut seen_keys = std::collections::HashSet::new();
+    for tenant in &tenants {
+        if tenant.organization.trim().is_empty() || tenant.project.trim().is_empty() {
+            return Err("tenant organization and project must be non-empty".to_string());
+        }
+        if tenant.organization.contains('/') || tenant.project.contains('/') {
+            return Err("tenant organization and project must not contain '/'".to_string());
+        }
+        if !seen_ids.insert((tenant.organization.as_str(), tenant.project.as_str())) {
+            return Err(format!("duplicate tenant: {}/{}", tenant.organization, tenant.project));
+        }
+        if tenant.api_keys.is_empty() || tenant.api_keys.iter().any(|key| key.trim().is_empty()) {
+            return Err(format!(
+                "tenant {}/{} must have non-empty api_keys",
+                tenant.organization, tenant.project
+            ));
+        }
+        if tenant.api_keys.iter().any(|key| !seen_keys.insert(key.as_str())) {
+            return Err(format!(
+                "tenant {}/{} reuses an api key of another tenant",
+                tenant.organization, tenant.project
+            ));
+        }
+        if tenant.rate_limit_per_minute == Some(0) || tenant.token_budget == Some(0) {
+            return Err(format!(
+                "tenant {}/{} limits must be positive",
+                tenant.organization, tenant.project
+            ));
+        }
+    }
+    Ok(tenants)
+}
+
+fn parse_key_store(
+    kind: &str,
+    path: Option<&str>,
+    url: Option<&str>,
+) -> Result<KeyStoreConfig, String> {
+    match kind.trim().to_ascii_lowercase().as_str() {
+        "" | "memory" => Ok(KeyStoreConfig::Memory),
+        "file" => path
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| KeyStoreConfig::File { path: PathBuf::from(value) })
+            .ok_or_else(|| "file key store requires XR_KEY_STORE_PATH".to_string()),
+        "postgres" => match url.map(str::trim).filter(|value| !value.is_empty()) {
+            Some(url) if url.starts_with("postgres://") || url.starts_with("postgresql://") => url
+                .parse::<tokio_postgres::Config>()
+                .map(|_| KeyStoreConfig::Postgres { url: url.to_string() })
+                .map_err(|error| format!("XR_KEY_STORE_URL does not parse: {error}")),
+            Some(_) => Err("XR_KEY_STORE_URL must be a postgres:// URL".to_string()),
+            None => Err("postgres key store requires XR_KEY_STORE_URL".to_string()),
+        },
+        "sqlite" => Ok(KeyStoreConfig::Sqlite),
+        other => Err(format!("unsupported key store: {other}")),
+    }
+}
+
+fn parse_storage(raw: &str) -> Result<StorageConfig, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(StorageConfig::PerStore);
+    }
+    match raw.split_once(':') {
+        Some((kind, path)) if kind.eq_ignore_ascii_case("sqlite") => match path.trim() {
+            "" => Err("sqlite storage requires a path: sqlite:<path>".to_string()),
+            path => Ok(StorageConfig::Sqlite { path: PathBuf::from(path) }),
+        },
+        _ => Err(format!("unsupported storage: {raw}")),
+    }
+}
+
+/// Moves the key and response stores into the shared SQLite database, which cannot be combined
+/// with a store kept elsewhere.
+fn place_stores(
+    storage: &StorageConfig,
+    key_store: KeyStoreConfig,
+    response_store: ResponseStoreConfig,
+) -> Result<(KeyStoreConfig, ResponseStoreConfig), String> {
+    match storage {
+        StorageConfig::Sqlite { .. } => {
+            let key_store = match key_store {
+                KeyStoreConfig::Memory | KeyStoreConfig::Sqlite => KeyStoreConfig::Sqlite,
+                _ => return Err("sqlite storage keeps API keys itself; unset XR_KEY_STORE".into()),
+            };
+            let response_store = match response_store {
+                ResponseStoreConfig::Memory | ResponseStoreConfig::Sqlite => {
+                    ResponseStoreConfig::Sqlite
+                }
+                _ => {
+                    return Err(
+                        "sqlite storage keeps responses itself; unset XR_RESPONSE_STORE".into()
+                    );
+                }
+            };
+            Ok((key_store, response_store))
+        }
+        StorageConfig::PerStore
+            if key_store == KeyStoreConfig::Sqlite
+                || response_store == ResponseStoreConfig::Sqlite =>
+        {
+            Err("a sqlite key or response store requires XR_STORAGE=sqlite:<path>".to_string())
+        }
+        StorageConfig::PerStore => Ok((key_store, response_store)),
+    }
+}
+
+fn parse_coordination(kind: &str, redis_url: Option<&str>) -> Result<CoordinationConfig, String> {
+    match kind.trim().to_ascii_lowercase().as_str() {
+        "" | "memory" => Ok(CoordinationConfig::Memory),
+        "redis" => match redis_url.map(str::trim).filter(|value| !value.is_empty()) {
+            Some(url) if url.starts_with("redis://") => redis::Client::open(url)
+                .map(|_| CoordinationConfig::Redis { url: url.to_string() })
+                .map_err(|error| format!("XR_REDIS_URL does not parse: {error}")),
+            Some(_) => Err("XR_REDIS_URL must be a redis:// URL".to_string()),
+            None => Err("redis coordination requires XR_REDIS_URL".to_string()),
+        },
+        other => Err(format!("unsupported coordination backend: {other}")),
+    }
+}
+
+fn parse_response_store(kind: &str, url: Option<&str>) -> Result<ResponseStoreConfig, String> {
+    let url = url.map(str::trim).filter(|value| !value.is_empty());
+    let kind = kind.trim().to_ascii_lowercase();
+    if kind.is_empty() || kind == "memory" {
+        return Ok(ResponseStoreConfig::Memory);
+    }
+    if kind == "sqlite" {
+        return Ok(ResponseStoreConfig::Sqlite);
+    }
+    let Some(url) = url else {
+        return Err(format!("{kind} response store requires XR_RESPONSE_STORE_URL"));
+    };
+    match kind.as_str() {
+        "redis" if url.starts_with("redis://") => redis::Client::open(url)
+            .map(|_| ResponseStoreConfig::Redis { url: url.to_string() })
+            .map_err(|error| format!("XR_RESPONSE_STORE_URL does not parse: {error}")),
+        "postgres" if url.starts_with("postgres://") || url.starts_with("postgresql://") => url
+            .parse::<tokio_postgres::Config>()
+            .map(|_| ResponseStoreConfig::Postgres { url: url.to_string() })
+            .map_err(|error| format!("XR_RESPONSE_STORE_URL does not parse: {error}")),
+        "redis" | "postgres" => Err(format!("XR_RESPONSE_STORE_URL must be a {kind}:// URL")),
+        other => Err(format!("unsupported response store: {other}")),
+    }
+}
+
+fn parse_provider_fixtures(
+    mode: &str,
+    dir: Option<&str>,
+) -> Result<ProviderFixturesConfig, String> {
+    let dir = || {
+        dir.map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .ok_or_else(|| "fixture record/replay requires XR_PROVIDER_FIXTURES_DIR".to_string())
+    };
+    match mode.trim().to_ascii_lowercase().as_str() {
+        "" | "off" => Ok(ProviderFixturesConfig::Off),
+        "record" => Ok(ProviderFixturesConfig::Record { dir: dir()? }),
+        "replay" => Ok(ProviderFixturesConfig::Replay { dir: dir()? }),
+        other => Err(format!("unsupported fixture mode: {other}")),
+    }
+}
+
+fn parse_passthrough_fields(raw: &str) -> Result<HashMap<String, Vec<String>>, String> {
+    if raw.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    let parsed = serde_json::from_str::<HashMap<String, Vec<String>>>(raw)
+        .map_err(|error| format!("expected an object of provider field lists: {error}"))?;
+    if let Some((provider, _)) =
+        parsed.iter().find(|(_, fields)| fields.iter().any(|field| field.trim().is_empty()))
+    {
+        return Err(format!("empty field name for provider {provider}"));
+    }
+    Ok(parsed)
+}
+
+fn parse_response_tee(
+    kind: &str,
+    url: Option<&str>,
+    topic: Option<&str>,
+) -> Result<ResponseTeeConfig, String> {
+    let url = url.map(str::trim).filter(|value| !value.is_empty());
+    let kind = kind.trim().to_ascii_lowercase();
+    if kind.is_empty() || kind == "off" {
+        return Ok(ResponseTeeConfig::Off);
+    }
+    if !matches!(kind.as_str(), "webhook" | "kafka") {
+        return Err(format!("unsupported response tee: {kind}"));
+    }
+    let Some(url) = url else {
+        return Err(format!("{kind} response tee requires XR_RESPONSE_TEE_URL"));
+    };
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err("XR_RESPONSE_TEE_URL must be an http(s) URL".to_string());
+    }
+    let url = url.trim_end_matches('/').to_string();
+    if kind == "webhook" {
+        return Ok(ResponseTeeConfig::Webhook { url });
+    }
+    match topic.map(str::trim).filter(|value| !value.is_empty()) {
+        Some(topic)
+            if topic
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-')) =>
+        {
+            Ok(ResponseTeeConfig::Kafka { url, topic: topic.to_string() })
+        }
+        Some(topic) => Err(format!("invalid Kafka topic name: {topic}")),
+        None => Err("kafka response tee requires XR_RESPONSE_TEE_TOPIC".to_string()),
+    }
+}
+
+fn parse_event_bus(
+    kind: &str,
+    url: Option<&str>,
+    topic: Option<&str>,
+) -> Result<EventBusConfig, String> {
+    let url = url.map(str::trim).filter(|value| !value.is_empty());
+    let kind = kind.trim().to_ascii_lowercase();
+    if kind.is_empty() || kind == "off" {
+        return Ok(EventBusConfig::Off);
+    }
+    let topic = topic.map(str::trim).filter(|value| !value.is_empty());
+    let topic = topic.unwrap_or(DEFAULT_EVENT_BUS_TOPIC);
+    if !topic.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-')) {
+        return Err(format!("invalid XR_EVENT_BUS_TOPIC: {topic}"));
+    }
+    let Some(url) = url else {
+        return Err(format!("{kind} event bus requires XR_EVENT_BUS_URL"));
+    };
+    match kind.as_str() {
+        "kafka" if url.starts_with("http://") || url.starts_with("https://") => {
+            Ok(EventBusConfig::Kafka {
+                url: url.trim_end_matches('/').to_string(),
+                topic: topic.to_string(),
+            })
+        }
+        "kafka" => {
+            Err("XR_EVENT_BUS_URL must be the http(s) URL of a Kafka REST proxy".to_string())
+        }
+        "nats" if url.starts_with("nats://") => {
+            Ok(EventBusConfig::Nats { url: url.to_string(), subject: topic.to_string() })
+        }
+        "nats" => Err("XR_EVENT_BUS_URL must be a nats:// URL".to_string()),
+        other => Err(format!("unsupported event bus: {other}")),
+    }
+}
+
+fn parse_usage_export(
+    bucket_url: Option<&str>,
+    region: Option<&str>,
+    access_key_id: Option<&str>,
+    secret_access_key: Option<&str>,
+    prefix: Option<&str>,
+) -> Result<Option<UsageExportConfig>, String> {
+    fn non_empty(value: Option<&str>) -> Option<&str> {
+        value.map(str::trim).filter(|value| !value.is_empty())
+    }
+    let Some(bucket_url) = non_empty(bucket_url) else {
+        return Ok(None);
+    };
+    let bucket_url = bucket_url.trim_end_matches('/');
+    let parsed =
+        reqwest::Url::parse(bucket_url).map_err(|error| format!("invalid bucket URL: {error}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.path().trim_matches('/').is_empty() {
+        return Err("expected a path-style http(s) bucket URL such as \
+             https://s3.<region>.amazonaws.com/<bucket>"
+            .to_string());
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err("the bucket URL must not have a query or fragment".to_string());
+    }
+    let region = non_empty(region).unwrap_or(DEFAULT_USAGE_EXPORT_REGION);
+    if !region.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-') {
+        return Err(format!("invalid XR_USAGE_EXPORT_REGION: {region}"));
+    }
+    let (Some(access_key_id), Some(secret_access_key)) =
+        (non_empty(access_key_id), non_empty(secret_access_key))
+    else {
+        return Err("usage export requires XR_USAGE_EXPORT_ACCESS_KEY_ID and \
+             XR_USAGE_EXPORT_SECRET_ACCESS_KEY"
+            .to_string());
+    };
+    let prefix = prefix.map(str::trim).unwrap_or(DEFAULT_USAGE_EXPORT_PREFIX);
+    if prefix.starts_with('/')
+        || prefix.contains("..")
+        || !prefix
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-' | '/'))
+    {
+        return Err(format!("invalid XR_USAGE_EXPORT_PREFIX: {prefix}"));
+    }
+    let prefix = match prefix.trim_end_matches('/') {
+        "" => String::new(),
+        prefix => format!("{prefix}/"),
+    };
+    Ok(Some(UsageExportConfig {
+        bucket_url: bucket_url.to_string(),
+        region: region.to_string(),
+        access_key_id: access_key_id.to_string(),
+        secret_access_key: secret_access_key.to_string(),
+        prefix,
+    }))
+}
+
+fn parse_unknown_model(
+    kind: &str,
+    default_model: Option<&str>,
+) -> Result<UnknownModelConfig, String> {
+    match kind.trim().to_ascii_lowercase().as_str() {
+        "" | "passthrough" => Ok(UnknownModelConfig::Passthrough),
+        "reject" => Ok(UnknownModelConfig::Reject),
+        "default_model" => default_model
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|model| UnknownModelConfig::DefaultModel { model: model.to_string() })
+            .ok_or_else(|| "default_model requires XR_DEFAULT_MODEL".to_string()),
+        other => Err(format!("unsupported unknown model policy: {other}")),
+    }
+}
+
+fn parse_race_models(raw: &str) -> Result<BTreeMap<String, Vec<String>>, String> {
+    if raw.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let parsed = serde_json::from_str::<BTreeMap<String, Vec<String>>>(raw)
+        .map_err(|error| format!("expected an object of model id lists: {error}"))?;
+    for (alias, models) in &parsed {
+        if alias.trim().is_empty() || alias.contains('/') {
+            return Err(format!("alias `{alias}` must be non-empty and must not contain '/'"));
+        }
+        let distinct = models.iter().collect::<HashSet<_>>();
+        if distinct.len() < 2 || distinct.len() != models.len() {
+            return Err(format!("{alias} must list at least two distinct models"));
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_bandit_models(raw: &str) -> Result<BTreeMap<String, BanditModelConfig>, String> {
+    if raw.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let parsed = serde_json::from_str::<BTreeMap<String, BanditModelConfig>>(raw)
+        .map_err(|error| format!("expected an object of bandit models: {error}"))?;
+    for (alias, bandit) in &parsed {
+        if alias.trim().is_empty() || alias.contains('/') {
+            return Err(format!("alias `{alias}` must be non-empty and must not contain '/'"));
+        }
+        let distinct = bandit.models.iter().collect::<HashSet<_>>();
+        if distinct.len() < 2 || distinct.len() != bandit.models.len() {
+            return Err(format!("{alias} must list at least two distinct models"));
+        }
+        if !(0.0..=1.0).contains(&bandit.epsilon) {
+            return Err(format!("{alias}: epsilon must be between 0 and 1"));
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_alert_rules(raw: &str, webhook_url: Option<&str>) -> Result<Vec<AlertRuleConfig>, String> {
+    if raw.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let rules = serde_json::from_str::<Vec<AlertRuleConfig>>(raw)
+        .map_err(|error| format!("expected a list of alert rules: {error}"))?;
+    let mut names = HashSet::new();
+    for rule in &rules {
+        if rule.name.trim().is_empty() || !names.insert(rule.name.as_str()) {
+            return Err(format!("rule names must be non-empty and unique: `{}`", rule.name));
+        }
+        if !rule.threshold.is_finite() || rule.threshold < 0.0 {
+            return Err(format!("{}: threshold must be a non-negative number", rule.name));
+        }
+        if rule.metric == AlertMetric::ErrorRate && rule.threshold >= 100.0 {
+            return Err(format!("{}: an error rate threshold must be below 100", rule.name));
+        }
+    }
+    match webhook_url {
+        Some(url) if url.starts_with("http://") || url.starts_with("https://") => Ok(rules),
+        Some(_) => Err("XR_ALERT_WEBHOOK_URL must be an http(s) URL".to_string()),
+        None if rules.is_empty() => Ok(rules),
+        None => Err("alert rules require XR_ALERT_WEBHOOK_URL".to_string()),
+    }
+}
+
+fn parse_model_overrides(raw: &str) -> Result<BTreeMap<String, ModelOverrideConfig>, String> {
+    if raw.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let parsed = serde_json::from_str::<BTreeMap<String, ModelOverrideConfig>>(raw)
+        .map_err(|error| format!("expected an object of model overrides: {error}"))?;
+    for (model, entry) in &parsed {
+        if model.trim().is_empty() {
+            return Err("model id must be non-empty".to_string());
+        }
+        if entry.context_length == Some(0) || entry.max_completion_tokens == Some(0) {
+            return Err(format!("{model}: token limits must be positive"));
+        }
+        if let Some(pricing) = &entry.pricing {
+            let valid = |price: &str| price.parse::<f64>().is_ok_and(|v| v.is_finite() && v >= 0.0);
+            if !valid(&pricing.prompt) || !valid(&pricing.completion) {
+                return Err(format!("{model}: prices must be non-negative decimal strings"));
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_chaos(raw: &str) -> Result<Option<ChaosPolicy>, String> {
+    if raw.trim().is_empty() {
+        return Ok(None);
+    }
+    let policy = serde_json::from_str::<ChaosPolicy>(raw).map_err(|error| error.to_string())?;
+    policy.validate()?;
+    Ok(Some(policy))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{DEFAULT_OPENROUTER_SUPPORTED_MODELS, parse_positive_usize, parse_string_list};
+    use super::{
+        AlertMetric, BanditReward, BanditStrategy, CoordinationConfig, DEFAULT_ALERT_MIN_REQUESTS,
+        DEFAULT_BANDIT_EPSILON, DEFAULT_OPENROUTER_SUPPORTED_MODELS, EventBusConfig,
+        HeaderOverrides, KeyStoreConfig, ProviderFixturesConfig, ResponseStoreConfig,
+        ResponseTeeConfig, StorageConfig, UnknownModelConfig, parse_alert_rules,
+        parse_bandit_models, parse_chaos, parse_coordination, parse_event_bus, parse_key_store,
+        parse_model_overrides, parse_passthrough_fields, parse_positive_usize,
+        parse_provider_fixtures, parse_race_models, parse_response_store, parse_response_tee,
+        parse_storage, parse_string_list, parse_tenants, parse_transforms, parse_unknown_model,
+        parse_usage_export, place_stores,
+    };
+
+    #[test]
+    fn header_overrides_parse_names_all_and_none() {
+        let overrides = HeaderOverrides::parse("provider, reasoning_effort").expect("must parse");
+        assert_eq!(overrides.names(), ["provider", "reasoning_effort"]);
+        assert_eq!(
+            HeaderOverrides::parse("all").map(HeaderOverrides::names),
+            Some(vec!["model", "provider", "reasoning_effort"])
+        );
+        assert_eq!(HeaderOverrides::parse(""), Some(HeaderOverrides::default()));
+        assert_eq!(HeaderOverrides::parse("none"), Some(HeaderOverrides::default()));
+        assert_eq!(HeaderOverrides::parse("model,temperature"), None);
+    }
 
     #[test]
     fn parse_string_list_accepts_json_array() {
@@ -308,4 +2252,340 @@ mod tests {
         assert_eq!(parse_positive_usize("0"), None);
         assert_eq!(parse_positive_usize("abc"), None);
     }
+
+    #[test]
+    fn parse_transforms_keeps_order_scope_and_params() {
+        let parsed = parse_transforms(
+            r#"[{"name":"strip_think_tags","provider":"deepseek","model":"deepseek-reasoner"},{"name":"stop_sequences","stop":["END"]}]"#,
+        )
+        .expect("transforms must parse");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].name, "strip_think_tags");
+        assert_eq!(parsed[0].provider.as_deref(), Some("deepseek"));
+        assert_eq!(parsed[0].model.as_deref(), Some("deepseek-reasoner"));
+        assert_eq!(parsed[1].params.get("stop"), Some(&serde_json::json!(["END"])));
+        assert!(parse_transforms("  ").expect("empty value must parse").is_empty());
+    }
+
+    #[test]
+    fn parse_transforms_rejects_unknown_transform_and_bad_params() {
+        assert!(parse_transforms(r#"[{"name":"nope"}]"#).is_err());
+        assert!(parse_transforms(r#"[{"name":"stop_sequences"}]"#).is_err());
+        assert!(parse_transforms("strip_think_tags").is_err());
+    }
+
+    #[test]
+    fn parse_tenants_reads_scoped_limits() {
+        let parsed = parse_tenants(
+            r#"[{"organization":"acme","project":"web","api_keys":["k1"],"allowed_models":["deepseek/deepseek-chat"],"rate_limit_per_minute":10,"token_budget":1000}]"#,
+        )
+        .expect("tenants must parse");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].organization, "acme");
+        assert_eq!(parsed[0].allowed_models, vec!["deepseek/deepseek-chat"]);
+        assert_eq!(parsed[0].rate_limit_per_minute, Some(10));
+        assert_eq!(parsed[0].token_budget, Some(1000));
+        assert!(parse_tenants("").expect("empty value must parse").is_empty());
+    }
+
+    #[test]
+    fn parse_tenants_rejects_shared_keys_and_missing_keys() {
+        assert!(
+            parse_tenants(
+                r#"[{"organization":"a","project":"p","api_keys":["k"]},{"organization":"b","project":"p","api_keys":["k"]}]"#
+            )
+            .is_err()
+        );
+        assert!(parse_tenants(r#"[{"organization":"a","project":"p","api_keys":[]}]"#).is_err());
+        assert!(
+            parse_tenants(
+                r#"[{"organization":"a","project":"p","api_keys":["k"],"token_budget":0}]"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_key_store_defaults_to_memory_and_requires_a_path_or_url() {
+        assert_eq!(parse_key_store("", None, None), Ok(KeyStoreConfig::Memory));
+        assert_eq!(
+            parse_key_store("file", Some("/var/lib/xrouter/keys.json"), None),
+            Ok(KeyStoreConfig::File { path: "/var/lib/xrouter/keys.json".into() })
+        );
+        assert_eq!(
+            parse_key_store("postgres", None, Some("postgres://xrouter@db/xrouter")),
+            Ok(KeyStoreConfig::Postgres { url: "postgres://xrouter@db/xrouter".to_string() })
+        );
+        assert!(parse_key_store("file", None, None).is_err());
+        assert!(parse_key_store("postgres", None, None).is_err());
+        assert!(parse_key_store("postgres", None, Some("redis://cache:6379/0")).is_err());
+        assert!(parse_key_store("redis", None, None).is_err());
+    }
+
+    #[test]
+    fn parse_coordination_defaults_to_memory_and_requires_a_redis_url() {
+        assert_eq!(parse_coordination("", None), Ok(CoordinationConfig::Memory));
+        assert_eq!(
+            parse_coordination("redis", Some("redis://cache:6379/0")),
+            Ok(CoordinationConfig::Redis { url: "redis://cache:6379/0".to_string() })
+        );
+        assert!(parse_coordination("redis", None).is_err());
+        assert!(parse_coordination("redis", Some("http://cache:6379")).is_err());
+        assert!(parse_coordination("etcd", None).is_err());
+    }
+
+    #[test]
+    fn parse_response_store_checks_the_url_matches_the_backend() {
+        assert_eq!(parse_response_store("", None), Ok(ResponseStoreConfig::Memory));
+        assert_eq!(
+            parse_response_store("postgres", Some("postgres://xrouter@db/xrouter")),
+            Ok(ResponseStoreConfig::Postgres { url: "postgres://xrouter@db/xrouter".to_string() })
+        );
+        assert_eq!(
+            parse_response_store("redis", Some("redis://cache:6379/1")),
+            Ok(ResponseStoreConfig::Redis { url: "redis://cache:6379/1".to_string() })
+        );
+        assert!(parse_response_store("redis", None).is_err());
+        assert!(parse_response_store("redis", Some("postgres://db/xrouter")).is_err());
+        assert!(parse_response_store("dynamodb", Some("https://example")).is_err());
+    }
+
+    #[test]
+    fn sqlite_storage_takes_over_the_key_and_response_stores() {
+        assert_eq!(parse_storage(""), Ok(StorageConfig::PerStore));
+        let sqlite = StorageConfig::Sqlite { path: "/var/lib/xrouter/xrouter.db".into() };
+        assert_eq!(parse_storage("sqlite:/var/lib/xrouter/xrouter.db"), Ok(sqlite.clone()));
+        assert!(parse_storage("sqlite:").is_err());
+        assert!(parse_storage("postgres://db/xrouter").is_err());
+
+        assert_eq!(
+            place_stores(&sqlite, KeyStoreConfig::Memory, ResponseStoreConfig::Memory),
+            Ok((KeyStoreConfig::Sqlite, ResponseStoreConfig::Sqlite))
+        );
+        let redis = ResponseStoreConfig::Redis { url: "redis://cache:6379/1".to_string() };
+        assert!(place_stores(&sqlite, KeyStoreConfig::Memory, redis).is_err());
+        let file = KeyStoreConfig::File { path: "/var/lib/xrouter/keys.json".into() };
+        assert!(place_stores(&sqlite, file, ResponseStoreConfig::Memory).is_err());
+        assert!(
+            place_stores(
+                &StorageConfig::PerStore,
+                KeyStoreConfig::Sqlite,
+                ResponseStoreConfig::Memory
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_chaos_is_off_when_empty_and_validates_probabilities() {
+        assert_eq!(parse_chaos(""), Ok(None));
+        let policy = parse_chaos(r#"{"providers":["zai"],"error_probability":0.25}"#)
+            .expect("policy must parse")
+            .expect("policy must be set");
+        assert_eq!(policy.providers, vec!["zai"]);
+        assert_eq!(policy.error_status, 503);
+        assert!(parse_chaos(r#"{"error_probability":2}"#).is_err());
+        assert!(parse_chaos(r#"{"latency":5}"#).is_err());
+    }
+
+    #[test]
+    fn parse_passthrough_fields_reads_provider_allowlists() {
+        assert!(parse_passthrough_fields(" ").expect("empty must parse").is_empty());
+        let parsed = parse_passthrough_fields(r#"{"openrouter":["top_k","repetition_penalty"]}"#)
+            .expect("allowlist must parse");
+        assert_eq!(parsed["openrouter"], vec!["top_k", "repetition_penalty"]);
+        assert!(parse_passthrough_fields(r#"["top_k"]"#).is_err());
+        assert!(parse_passthrough_fields(r#"{"zai":[""]}"#).is_err());
+    }
+
+    #[test]
+    fn parse_event_bus_defaults_the_topic_and_checks_the_url_scheme() {
+        assert_eq!(parse_event_bus("off", Some("nats://bus:4222"), None), Ok(EventBusConfig::Off));
+        assert_eq!(
+            parse_event_bus("nats", Some("nats://bus:4222"), None),
+            Ok(EventBusConfig::Nats {
+                url: "nats://bus:4222".to_string(),
+                subject: "xrouter.events".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_event_bus("kafka", Some("https://rest-proxy/"), Some("audit")),
+            Ok(EventBusConfig::Kafka {
+                url: "https://rest-proxy".to_string(),
+                topic: "audit".to_string(),
+            })
+        );
+        assert!(parse_event_bus("kafka", Some("kafka://broker:9092"), None).is_err());
+        assert!(parse_event_bus("nats", Some("http://bus:4222"), None).is_err());
+        assert!(parse_event_bus("nats", None, None).is_err());
+        assert!(parse_event_bus("nats", Some("nats://bus:4222"), Some("a b")).is_err());
+    }
+
+    #[test]
+    fn parse_usage_export_requires_a_bucket_path_and_credentials() {
+        assert_eq!(parse_usage_export(None, None, Some("AKID"), Some("secret"), None), Ok(None));
+        let parsed = parse_usage_export(
+            Some("https://storage.googleapis.com/analytics/"),
+            Some("auto"),
+            Some("AKID"),
+            Some("secret"),
+            Some("exports/xrouter"),
+        )
+        .expect("config must parse")
+        .expect("export must be enabled");
+        assert_eq!(parsed.bucket_url, "https://storage.googleapis.com/analytics");
+        assert_eq!(parsed.region, "auto");
+        assert_eq!(parsed.prefix, "exports/xrouter/");
+        let defaults = parse_usage_export(
+            Some("http://minio:9000/usage"),
+            None,
+            Some("AKID"),
+            Some("secret"),
+            None,
+        )
+        .expect("config must parse")
+        .expect("export must be enabled");
+        assert_eq!((defaults.region.as_str(), defaults.prefix.as_str()), ("us-east-1", "xrouter/"));
+        let export =
+            |url, prefix| parse_usage_export(Some(url), None, Some("AKID"), Some("secret"), prefix);
+        assert!(export("https://s3.amazonaws.com", None).is_err());
+        assert!(export("s3://analytics", None).is_err());
+        assert!(export("https://s3.amazonaws.com/analytics", Some("/abs")).is_err());
+        assert!(export("https://s3.amazonaws.com/analytics", Some("a/../b")).is_err());
+        assert!(export("https://s3.amazonaws.com/analytics", Some("a b")).is_err());
+        assert!(
+            parse_usage_export(Some("https://s3.amazonaws.com/a"), None, None, Some("s"), None)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_response_tee_requires_an_http_url_and_a_kafka_topic() {
+        assert_eq!(parse_response_tee("", None, None), Ok(ResponseTeeConfig::Off));
+        assert_eq!(
+            parse_response_tee("webhook", Some("https://hooks.example.com/xr/"), None),
+            Ok(ResponseTeeConfig::Webhook { url: "https://hooks.example.com/xr".to_string() })
+        );
+        assert_eq!(
+            parse_response_tee("kafka", Some("http://rest-proxy:8082"), Some("xrouter.responses")),
+            Ok(ResponseTeeConfig::Kafka {
+                url: "http://rest-proxy:8082".to_string(),
+                topic: "xrouter.responses".to_string(),
+            })
+        );
+        assert!(parse_response_tee("webhook", None, None).is_err());
+        assert!(parse_response_tee("webhook", Some("kafka://broker:9092"), None).is_err());
+        assert!(parse_response_tee("kafka", Some("http://rest-proxy:8082"), None).is_err());
+        assert!(parse_response_tee("kafka", Some("http://rest-proxy:8082"), Some("a/b")).is_err());
+        assert!(parse_response_tee("nats", Some("http://nats:4222"), None).is_err());
+    }
+
+    #[test]
+    fn parse_model_overrides_reads_partial_entries_and_checks_values() {
+        assert!(parse_model_overrides(" ").expect("empty must parse").is_empty());
+        let parsed = parse_model_overrides(
+            r#"{"zai/glm-5":{"context_length":200000,"pricing":{"prompt":"0.000001","completion":"0.0000032"}}}"#,
+        )
+        .expect("overrides must parse");
+        let entry = &parsed["zai/glm-5"];
+        assert_eq!(entry.context_length, Some(200_000));
+        assert_eq!(entry.description, None);
+        assert_eq!(
+            entry.pricing.as_ref().map(|pricing| pricing.completion.as_str()),
+            Some("0.0000032")
+        );
+        assert!(parse_model_overrides(r#"{"zai/glm-5":{"context_window":1}}"#).is_err());
+        assert!(parse_model_overrides(r#"{"zai/glm-5":{"max_completion_tokens":0}}"#).is_err());
+        assert!(
+            parse_model_overrides(
+                r#"{"zai/glm-5":{"pricing":{"prompt":"free","completion":"0"}}}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_unknown_model_defaults_to_passthrough_and_requires_a_default_model() {
+        assert_eq!(parse_unknown_model("", None), Ok(UnknownModelConfig::Passthrough));
+        assert_eq!(parse_unknown_model("Reject", None), Ok(UnknownModelConfig::Reject));
+        assert_eq!(
+            parse_unknown_model("default_model", Some(" deepseek/deepseek-chat ")),
+            Ok(UnknownModelConfig::DefaultModel { model: "deepseek/deepseek-chat".to_string() })
+        );
+        assert!(parse_unknown_model("default_model", None).is_err());
+        assert!(parse_unknown_model("guess", None).is_err());
+    }
+
+    #[test]
+    fn parse_alert_rules_requires_a_webhook_and_sane_thresholds() {
+        let raw = r#"[{"name":"zai-errors","provider":"zai","metric":"error_rate","threshold":5,
+                       "for_minutes":10},
+                      {"name":"slow","metric":"p95_latency_ms","threshold":20000}]"#;
+        let rules =
+            parse_alert_rules(raw, Some("https://hooks.example.com/x")).expect("rules must parse");
+        assert_eq!(rules[0].provider.as_deref(), Some("zai"));
+        assert_eq!(rules[0].for_minutes, 10);
+        assert_eq!(rules[1].metric, AlertMetric::P95LatencyMs);
+        assert_eq!((rules[1].for_minutes, rules[1].min_requests), (0, DEFAULT_ALERT_MIN_REQUESTS));
+
+        assert!(parse_alert_rules("", None).expect("empty must parse").is_empty());
+        assert!(parse_alert_rules(raw, None).is_err());
+        assert!(parse_alert_rules(raw, Some("hooks.example.com")).is_err());
+        let duplicate = r#"[{"name":"a","metric":"error_rate","threshold":5},
+                            {"name":"a","metric":"error_rate","threshold":9}]"#;
+        assert!(parse_alert_rules(duplicate, Some("https://hooks.example.com/x")).is_err());
+        let impossible = r#"[{"name":"a","metric":"error_rate","threshold":100}]"#;
+        assert!(parse_alert_rules(impossible, Some("https://hooks.example.com/x")).is_err());
+    }
+
+    #[test]
+    fn parse_bandit_models_defaults_to_thompson_sampling_on_success() {
+        assert!(parse_bandit_models("").expect("empty must parse").is_empty());
+        let parsed = parse_bandit_models(
+            r#"{"chat":{"models":["deepseek/deepseek-chat","zai/glm-4.5"]},
+                "cheap":{"models":["deepseek/deepseek-chat","zai/glm-4.5"],
+                         "strategy":"epsilon_greedy","reward":"cost","epsilon":0.2}}"#,
+        )
+        .expect("bandit models must parse");
+        assert_eq!(parsed["chat"].strategy, BanditStrategy::Thompson);
+        assert_eq!(parsed["chat"].reward, BanditReward::Success);
+        assert_eq!(parsed["chat"].epsilon, DEFAULT_BANDIT_EPSILON);
+        assert_eq!(parsed["cheap"].strategy, BanditStrategy::EpsilonGreedy);
+        assert_eq!(parsed["cheap"].reward, BanditReward::Cost);
+        assert!(parse_bandit_models(r#"{"chat":{"models":["zai/glm-4.5"]}}"#).is_err());
+        assert!(
+            parse_bandit_models(
+                r#"{"chat":{"models":["deepseek/deepseek-chat","zai/glm-4.5"],"epsilon":2}}"#
+            )
+            .is_err()
+        );
+        assert!(
+            parse_bandit_models(
+                r#"{"chat":{"models":["deepseek/deepseek-chat","zai/glm-4.5"],"weights":[1]}}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_race_models_requires_two_distinct_models_per_alias() {
+        assert!(parse_race_models("").expect("empty must parse").is_empty());
+        let parsed = parse_race_models(r#"{"fast":["deepseek/deepseek-chat","zai/glm-4.5"]}"#)
+            .expect("race models must parse");
+        assert_eq!(parsed["fast"], vec!["deepseek/deepseek-chat", "zai/glm-4.5"]);
+        assert!(parse_race_models(r#"{"fast":["zai/glm-4.5"]}"#).is_err());
+        assert!(parse_race_models(r#"{"fast":["zai/glm-4.5","zai/glm-4.5"]}"#).is_err());
+        assert!(parse_race_models(r#"{"a/b":["zai/glm-4.5","deepseek/deepseek-chat"]}"#).is_err());
+    }
+
+    #[test]
+    fn parse_provider_fixtures_requires_dir_for_record_and_replay() {
+        assert_eq!(parse_provider_fixtures("", None), Ok(ProviderFixturesConfig::Off));
+        assert_eq!(
+            parse_provider_fixtures("Replay", Some("fixtures")),
+            Ok(ProviderFixturesConfig::Replay { dir: "fixtures".into() })
+        );
+        assert!(parse_provider_fixtures("record", Some(" ")).is_err());
+        assert!(parse_provider_fixtures("proxy", Some("fixtures")).is_err());
+    }
 }
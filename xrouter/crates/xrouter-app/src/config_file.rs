@@ -0,0 +1,310 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde_json::{Map, Value};
+
+use crate::config::ConfigError;
+
+/// Settings a config file may set, keyed by `<section>.<key>` and mapped to the environment
+/// variable they stand for. Providers are handled separately, see `provider_env_name`.
+const FILE_KEYS: &[(&str, &str)] = &[
+    ("server.host", "XR_HOST"),
+    ("server.port", "XR_PORT"),
+    ("server.openai_compatible_api", "ENABLE_OPENAI_COMPATIBLE_API"),
+    ("server.byok_enabled", "XR_BYOK_ENABLED"),
+    ("server.catalog_only", "XR_CATALOG_ONLY"),
+    ("limits.provider_timeout", "XR_PROVIDER_TIMEOUT"),
+    ("limits.provider_max_inflight", "XR_PROVIDER_MAX_INFLIGHT"),
+    ("limits.user_rate_limit_per_minute", "XR_USER_RATE_LIMIT_PER_MINUTE"),
+    ("limits.stream_retained_output_bytes", "XR_STREAM_RETAINED_OUTPUT_BYTES"),
+    ("limits.stream_stall_timeout_seconds", "XR_STREAM_STALL_TIMEOUT_SECONDS"),
+    ("limits.stream_salvage_partial", "XR_STREAM_SALVAGE_PARTIAL"),
+    ("limits.max_request_body_bytes", "XR_MAX_REQUEST_BODY_BYTES"),
+    ("limits.max_hops", "XR_MAX_HOPS"),
+    ("limits.max_concurrent_requests", "XR_HTTP_MAX_CONCURRENT_REQUESTS"),
+    ("limits.request_timeout_seconds", "XR_HTTP_REQUEST_TIMEOUT_SECONDS"),
+    ("limits.stream_timeout_seconds", "XR_HTTP_STREAM_TIMEOUT_SECONDS"),
+    ("limits.max_provider_timeout_ms", "XR_MAX_PROVIDER_TIMEOUT_MS"),
+    ("limits.max_provider_retries", "XR_MAX_PROVIDER_RETRIES"),
+    ("limits.request_coalescing", "XR_REQUEST_COALESCING"),
+    ("limits.priority_scheduling", "XR_PRIORITY_SCHEDULING"),
+    ("catalog.fetch_timeout_seconds", "XR_MODEL_CATALOG_FETCH_TIMEOUT_SECONDS"),
+    ("catalog.startup_budget_seconds", "XR_MODEL_CATALOG_STARTUP_BUDGET_SECONDS"),
+    ("catalog.cache_path", "XR_MODEL_CATALOG_CACHE_PATH"),
+    ("catalog.model_overrides", "XR_MODEL_OVERRIDES"),
+    ("cors.allowed_origins", "XR_CORS_ALLOWED_ORIGINS"),
+    ("cors.allowed_headers", "XR_CORS_ALLOWED_HEADERS"),
+    ("cors.allowed_methods", "XR_CORS_ALLOWED_METHODS"),
+    ("cors.max_age_seconds", "XR_CORS_MAX_AGE_SECONDS"),
+    ("debug.stream_transcript_dir", "XR_STREAM_TRANSCRIPT_DIR"),
+    ("debug.stream_transcript_max_entries", "XR_STREAM_TRANSCRIPT_MAX_ENTRIES"),
+    ("debug.provider_fixtures", "XR_PROVIDER_FIXTURES"),
+    ("debug.provider_fixtures_dir", "XR_PROVIDER_FIXTURES_DIR"),
+    ("debug.chaos", "XR_CHAOS"),
+    ("routing.transforms", "XR_TRANSFORMS"),
+    ("routing.passthrough_fields", "XR_PASSTHROUGH_FIELDS"),
+    ("routing.race_models", "XR_RACE_MODELS"),
+    ("routing.bandit_models", "XR_BANDIT_MODELS"),
+    ("routing.bandit_state_path", "XR_BANDIT_STATE_PATH"),
+    ("routing.unknown_model", "XR_UNKNOWN_MODEL"),
+    ("routing.default_model", "XR_DEFAULT_MODEL"),
+    ("routing.truncation", "XR_TRUNCATION"),
+    ("routing.truncation_summary_model", "XR_TRUNCATION_SUMMARY_MODEL"),
+    ("routing.hide_reasoning_models", "XR_HIDE_REASONING_MODELS"),
+    ("routing.header_overrides", "XR_HEADER_OVERRIDES"),
+    ("routing.tool_choice_required_emulation", "XR_TOOL_CHOICE_REQUIRED_EMULATION"),
+    ("routing.openrouter_supported_models", "OPENROUTER_SUPPORTED_MODELS"),
+    ("routing.gigachat_supported_models", "GIGACHAT_SUPPORTED_MODELS"),
+    ("moderation.blocklist", "XR_OUTPUT_MODERATION_BLOCKLIST"),
+    ("moderation.message", "XR_OUTPUT_MODERATION_MESSAGE"),
+    ("moderation.buffer_stream", "XR_OUTPUT_MODERATION_BUFFER_STREAM"),
+    ("tenancy.tenants", "XR_TENANTS"),
+    ("tenancy.admin_token", "XR_ADMIN_TOKEN"),
+    ("tenancy.key_store", "XR_KEY_STORE"),
+    ("tenancy.key_store_path", "XR_KEY_STORE_PATH"),
+    ("tenancy.key_store_url", "XR_KEY_STORE_URL"),
+    ("coordination.backend", "XR_COORDINATION"),
+    ("coordination.redis_url", "XR_REDIS_URL"),
+    ("response_store.backend", "XR_RESPONSE_STORE"),
+    ("response_store.url", "XR_RESPONSE_STORE_URL"),
+    ("response_store.ttl_seconds", "XR_RESPONSE_STORE_TTL_SECONDS"),
+    ("conversation.token_budget", "XR_CONVERSATION_TOKEN_BUDGET"),
+    ("conversation.budget_enforced", "XR_CONVERSATION_BUDGET_ENFORCED"),
+    ("database.storage", "XR_STORAGE"),
+    ("database.migrate_on_startup", "XR_DATABASE_MIGRATE_ON_STARTUP"),
+    ("idempotency.ttl_seconds", "XR_IDEMPOTENCY_TTL_SECONDS"),
+    ("response_tee.backend", "XR_RESPONSE_TEE"),
+    ("response_tee.url", "XR_RESPONSE_TEE_URL"),
+    ("response_tee.topic", "XR_RESPONSE_TEE_TOPIC"),
+    ("response_tee.deltas", "XR_RESPONSE_TEE_DELTAS"),
+    ("response_tee.buffer", "XR_RESPONSE_TEE_BUFFER"),
+    ("event_bus.backend", "XR_EVENT_BUS"),
+    ("event_bus.url", "XR_EVENT_BUS_URL"),
+    ("event_bus.topic", "XR_EVENT_BUS_TOPIC"),
+    ("event_bus.buffer", "XR_EVENT_BUS_BUFFER"),
+    ("usage_export.url", "XR_USAGE_EXPORT_URL"),
+    ("usage_export.region", "XR_USAGE_EXPORT_REGION"),
+    ("usage_export.access_key_id", "XR_USAGE_EXPORT_ACCESS_KEY_ID"),
+    ("usage_export.secret_access_key", "XR_USAGE_EXPORT_SECRET_ACCESS_KEY"),
+    ("usage_export.prefix", "XR_USAGE_EXPORT_PREFIX"),
+    ("alerts.rules", "XR_ALERT_RULES"),
+    ("alerts.webhook_url", "XR_ALERT_WEBHOOK_URL"),
+    ("observability.log_level", "XR_LOG_LEVEL"),
+    ("observability.log_span_events", "XR_LOG_SPAN_EVENTS"),
+    ("observability.log_exporter", "XR_LOG_EXPORTER"),
+    ("observability.trace_enabled", "XR_TRACE_ENABLED"),
+    ("observability.trace_exporter", "XR_OTEL_TRACE_EXPORTER"),
+    ("observability.trace_exporters", "XR_OTEL_TRACE_EXPORTERS"),
+    ("observability.trace_endpoint", "XR_OTEL_TRACE_ENDPOINT"),
+    ("observability.trace_timeout_ms", "XR_OTEL_TRACE_TIMEOUT_MS"),
+    ("observability.trace_http_protocol", "XR_OTEL_TRACE_HTTP_PROTOCOL"),
+    ("observability.metrics_enabled", "XR_METRICS_ENABLED"),
+    ("observability.metrics_exporter", "XR_OTEL_METRICS_EXPORTER"),
+    ("observability.metrics_endpoint", "XR_OTEL_METRICS_ENDPOINT"),
+    ("observability.environment", "XR_ENVIRONMENT"),
+];
+
+const PROVIDERS: &[&str] = &[
+    "openrouter",
+    "deepseek",
+    "gigachat",
+    "yandex",
+    "ollama",
+    "zai",
+    "xrouter",
+    "tgi",
+    "selfhosted",
+    "cohere",
+    "perplexity",
+    "cloudflare",
+];
+
+const PROVIDER_KEYS: &[&str] = &[
+    "enabled",
+    "api_key",
+    "base_url",
+    "project",
+    "proxy",
+    "ca_bundle",
+    "client_cert",
+    "client_key",
+    "min_tls_version",
+    "pool_max_idle_per_host",
+    "pool_idle_timeout_seconds",
+    "http2_keepalive_seconds",
+    "tcp_nodelay",
+];
+
+/// Keys of the `providers.defaults` section, applied to every provider as `XR_PROVIDER_*`.
+const PROVIDER_DEFAULT_KEYS: &[&str] = &[
+    "proxy",
+    "pool_max_idle_per_host",
+    "pool_idle_timeout_seconds",
+    "http2_keepalive_seconds",
+    "tcp_nodelay",
+];
+
+/// Reads a YAML (`.yaml`/`.yml`) or TOML (`.toml`) config file and flattens it into the
+/// environment variables it stands for. Lists and objects become JSON, the format the matching
+/// variables already use.
+pub fn load_config_file(path: &Path) -> Result<BTreeMap<String, String>, ConfigError> {
+    let invalid =
+        |message: String| ConfigError::InvalidConfigFile(format!("{}: {message}", path.display()));
+    let raw = fs::read_to_string(path).map_err(|error| invalid(error.to_string()))?;
+    let document = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("yaml" | "yml") => {
+            serde_yaml::from_str::<Value>(&raw).map_err(|error| invalid(error.to_string()))?
+        }
+        Some("toml") => {
+            toml::from_str::<Value>(&raw).map_err(|error| invalid(error.to_string()))?
+        }
+        _ => return Err(invalid("expected a .yaml, .yml or .toml file".to_string())),
+    };
+    flatten_config(document).map_err(invalid)
+}
+
+fn flatten_config(document: Value) -> Result<BTreeMap<String, String>, String> {
+    let sections = match document {
+        Value::Object(sections) => sections,
+        Value::Null => return Ok(BTreeMap::new()),
+        _ => return Err("top level must be a mapping of sections".to_string()),
+    };
+    let mut values = BTreeMap::new();
+    for (section, body) in sections {
+        let entries = as_section(&section, body)?;
+        if section == "providers" {
+            flatten_providers(entries, &mut values)?;
+            continue;
+        }
+        for (key, value) in entries {
+            let path = format!("{section}.{key}");
+            let Some((_, env_name)) = FILE_KEYS.iter().find(|(known, _)| *known == path) else {
+                return Err(format!("unknown setting `{path}`"));
+            };
+            insert_value(&mut values, env_name, value);
+        }
+    }
+    Ok(values)
+}
+
+fn flatten_providers(
+    providers: Map<String, Value>,
+    values: &mut BTreeMap<String, String>,
+) -> Result<(), String> {
+    for (provider, body) in providers {
+        let path = format!("providers.{provider}");
+        let entries = as_section(&path, body)?;
+        for (key, value) in entries {
+            let env_name = if provider == "defaults" {
+                PROVIDER_DEFAULT_KEYS
+                    .contains(&key.as_str())
+                    .then(|| format!("XR_PROVIDER_{}", key.to_ascii_uppercase()))
+            } else if PROVIDERS.contains(&provider.as_str()) {
+                provider_env_name(&provider, &key)
+            } else {
+                return Err(format!("unknown provider `{path}`"));
+            };
+            let Some(env_name) = env_name else {
+                return Err(format!("unknown setting `{path}.{key}`"));
+            };
+            insert_value(values, &env_name, value);
+        }
+    }
+    Ok(())
+}
+
+fn provider_env_name(provider: &str, key: &str) -> Option<String> {
+    match (provider, key) {
+        ("gigachat", "api_key") => Some("GIGACHAT_CREDENTIALS".to_string()),
+        ("gigachat", "insecure_tls") => Some("GIGACHAT_INSECURE_TLS".to_string()),
+        ("tgi", "native_api") => Some("TGI_NATIVE_API".to_string()),
+        ("yandex", "chat_completions") => Some("YANDEX_CHAT_COMPLETIONS".to_string()),
+        ("selfhosted", "quirks") => Some("SELFHOSTED_QUIRKS".to_string()),
+        ("cloudflare", "account_id") => Some("CLOUDFLARE_ACCOUNT_ID".to_string()),
+        _ if PROVIDER_KEYS.contains(&key) => {
+            Some(format!("{}_{}", provider.to_ascii_uppercase(), key.to_ascii_uppercase()))
+        }
+        _ => None,
+    }
+}
+
+fn as_section(name: &str, body: Value) -> Result<Map<String, Value>, String> {
+    match body {
+        Value::Object(entries) => Ok(entries),
+        Value::Null => Ok(Map::new()),
+        _ => Err(format!("`{name}` must be a mapping")),
+    }
+}
+
+fn insert_value(values: &mut BTreeMap<String, String>, env_name: &str, value: Value) {
+    let rendered = match value {
+        Value::Null => return,
+        Value::String(value) => value,
+        other => other.to_string(),
+    };
+    values.insert(env_name.to_string(), rendered);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn flatten_config_maps_sections_and_providers_to_env_names() {
+        let values = flatten_config(json!({
+            "server": {"host": "0.0.0.0", "port": 8900},
+            "routing": {"transforms": [{"name": "strip_think_tags"}]},
+            "observability": {"trace_enabled": true},
+            "providers": {
+                "defaults": {"proxy": "direct"},
+                "deepseek": {"enabled": false, "base_url": "https://example.test"},
+                "gigachat": {"api_key": "env:GIGACHAT_SECRET"}
+            }
+        }))
+        .expect("config must flatten");
+
+        assert_eq!(values["XR_HOST"], "0.0.0.0");
+        assert_eq!(values["XR_PORT"], "8900");
+        assert_eq!(values["XR_TRANSFORMS"], r#"[{"name":"strip_think_tags"}]"#);
+        assert_eq!(values["XR_TRACE_ENABLED"], "true");
+        assert_eq!(values["XR_PROVIDER_PROXY"], "direct");
+        assert_eq!(values["DEEPSEEK_ENABLED"], "false");
+        assert_eq!(values["DEEPSEEK_BASE_URL"], "https://example.test");
+        assert_eq!(values["GIGACHAT_CREDENTIALS"], "env:GIGACHAT_SECRET");
+    }
+
+    #[test]
+    fn flatten_config_rejects_unknown_settings() {
+        for document in [
+            json!({"server": {"hots": "x"}}),
+            json!({"providers": {"acme": {"enabled": true}}}),
+            json!({"providers": {"zai": {"insecure_tls": true}}}),
+            json!({"server": "x"}),
+        ] {
+            assert!(flatten_config(document).is_err());
+        }
+    }
+
+    #[test]
+    fn load_config_file_reads_yaml_and_toml() {
+        let dir = std::env::temp_dir();
+        let yaml = dir.join(format!("xrouter-{}.yaml", uuid::Uuid::new_v4()));
+        let toml = dir.join(format!("xrouter-{}.toml", uuid::Uuid::new_v4()));
+        fs::write(&yaml, "server:\n  port: 8901\nproviders:\n  zai:\n    enabled: false\n")
+            .expect("yaml must write");
+        fs::write(&toml, "[server]\nport = 8902\n\n[providers.zai]\nenabled = true\n")
+            .expect("toml must write");
+
+        let from_yaml = load_config_file(&yaml).expect("yaml must load");
+        let from_toml = load_config_file(&toml).expect("toml must load");
+        assert_eq!(from_yaml["XR_PORT"], "8901");
+        assert_eq!(from_yaml["ZAI_ENABLED"], "false");
+        assert_eq!(from_toml["XR_PORT"], "8902");
+        assert_eq!(from_toml["ZAI_ENABLED"], "true");
+        assert!(load_config_file(&dir.join("xrouter.json")).is_err());
+
+        let _ = fs::remove_file(yaml);
+        let _ = fs::remove_file(toml);
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+use xrouter_contracts::{ConversationBudget, Usage};
+
+const MAX_TRACKED_RESPONSES: usize = 4096;
+
+/// Cumulative token usage of `/responses` conversations, keyed by the id of their latest
+/// response so a `previous_response_id` follow-up picks up the running total. Totals are kept
+/// per replica; a follow-up to a forgotten response starts from zero.
+pub(crate) struct ConversationBudgets {
+    limit_tokens: u64,
+    enforced: bool,
+    used: Mutex<TrackedResponses>,
+}
+
+#[derive(Default)]
+struct TrackedResponses {
+    used_tokens: HashMap<String, u64>,
+    order: VecDeque<String>,
+}
+
+impl ConversationBudgets {
+    pub(crate) fn new(limit_tokens: u64, enforced: bool) -> Self {
+        Self { limit_tokens, enforced, used: Mutex::default() }
+    }
+
+    /// Tokens already spent by the conversation `previous_response_id` continues.
+    pub(crate) fn used_before(&self, previous_response_id: Option<&str>) -> u64 {
+        let Some(previous_response_id) = previous_response_id else {
+            return 0;
+        };
+        let used = self.used.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        used.used_tokens.get(previous_response_id).copied().unwrap_or_default()
+    }
+
+    /// Refuses a turn of an enforced budget once the cap is spent, or when the turn's
+    /// `max_output_tokens` alone would go past what is left.
+    pub(crate) fn admit(
+        &self,
+        used_before: u64,
+        max_output_tokens: Option<u32>,
+    ) -> Result<(), ConversationBudgetExceeded> {
+        if !self.enforced {
+            return Ok(());
+        }
+        let budget = self.budget(used_before);
+        let error = if budget.remaining_tokens == 0 {
+            format!(
+                "conversation token budget exhausted: {} of {} tokens used",
+                budget.used_tokens, budget.limit_tokens
+            )
+        } else if let Some(max_output_tokens) =
+            max_output_tokens.filter(|max| u64::from(*max) > budget.remaining_tokens)
+        {
+            format!(
+                "max_output_tokens {max_output_tokens} exceeds the {} tokens left in the \
+                 conversation token budget",
+                budget.remaining_tokens
+            )
+        } else {
+            return Ok(());
+        };
+        Err(ConversationBudgetExceeded { error, budget })
+    }
+
+    /// Adds a finished turn to its conversation's total, now kept under `response_id`.
+    pub(crate) fn record(
+        &self,
+        response_id: &str,
+        used_before: u64,
+        usage: &Usage,
+    ) -> ConversationBudget {
+        let budget = self.budget(used_before + u64::from(usage.total_tokens));
+        if budget.used_tokens > self.limit_tokens && used_before <= self.limit_tokens {
+            warn!(
+                event = "app.conversation_budget.exceeded",
+                response_id = response_id,
+                used_tokens = budget.used_tokens,
+                limit_tokens = budget.limit_tokens
+            );
+        }
+        let mut used = self.used.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if used.used_tokens.insert(response_id.to_string(), budget.used_tokens).is_none() {
+            used.order.push_back(response_id.to_string());
+        }
+        while used.order.len() > MAX_TRACKED_RESPONSES {
+            if let Some(evicted) = used.order.pop_front() {
+                used.used_tokens.remove(&evicted);
+            }
+        }
+        budget
+    }
+
+    fn budget(&self, used_tokens: u64) -> ConversationBudget {
+        ConversationBudget {
+            used_tokens,
+            limit_tokens: self.limit_tokens,
+            remaining_tokens: self.limit_tokens.saturating_sub(used_tokens),
+        }
+    }
+}
+
+/// A turn refused by an enforced conversation budget; answered with `402` and the budget.
+#[derive(Debug)]
+pub(crate) struct ConversationBudgetExceeded {
+    error: String,
+    budget: ConversationBudget,
+}
+
+impl ConversationBudgetExceeded {
+    pub(crate) fn error(&self) -> &str {
+        &self.error
+    }
+}
+
+impl IntoResponse for ConversationBudgetExceeded {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "error": self.error,
+            "param": "previous_response_id",
+            "budget": self.budget,
+        });
+        (StatusCode::PAYMENT_REQUIRED, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xrouter_contracts::{ConversationBudget, Usage};
+
+    use super::ConversationBudgets;
+
+    fn usage(total_tokens: u32) -> Usage {
+        Usage {
+            input_tokens: total_tokens / 2,
+            output_tokens: total_tokens - total_tokens / 2,
+            total_tokens,
+            input_tokens_details: None,
+            output_tokens_details: None,
+        }
+    }
+
+    #[test]
+    fn follow_ups_add_to_the_total_of_the_turn_they_continue() {
+        let budgets = ConversationBudgets::new(100, false);
+
+        let first = budgets.record("resp_1", budgets.used_before(None), &usage(30));
+        let second = budgets.record("resp_2", budgets.used_before(Some("resp_1")), &usage(50));
+        let branch = budgets.record("resp_3", budgets.used_before(Some("resp_1")), &usage(10));
+
+        assert_eq!(
+            first,
+            ConversationBudget { used_tokens: 30, limit_tokens: 100, remaining_tokens: 70 }
+        );
+        assert_eq!(second.used_tokens, 80);
+        assert_eq!(branch.used_tokens, 40);
+        assert_eq!(budgets.used_before(Some("resp_unknown")), 0);
+    }
+
+    #[test]
+    fn enforced_budgets_refuse_turns_past_the_cap() {
+        let budgets = ConversationBudgets::new(100, true);
+        budgets.record("resp_1", 0, &usage(90));
+        let used = budgets.used_before(Some("resp_1"));
+
+        assert!(budgets.admit(used, None).is_ok());
+        assert!(budgets.admit(used, Some(10)).is_ok());
+        let refused = budgets.admit(used, Some(11)).expect_err("must refuse");
+        assert!(refused.error().contains("10 tokens left"), "{}", refused.error());
+
+        budgets.record("resp_2", used, &usage(20));
+        let refused =
+            budgets.admit(budgets.used_before(Some("resp_2")), None).expect_err("must refuse");
+        assert_eq!(refused.budget.remaining_tokens, 0);
+        assert!(ConversationBudgets::new(100, false).admit(500, None).is_ok());
+    }
+}
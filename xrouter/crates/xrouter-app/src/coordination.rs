@@ -0,0 +1,297 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use redis::{
+    ErrorKind, RedisResult, Script,
+    aio::{ConnectionManager, ConnectionManagerConfig},
+};
+use tokio::sync::OnceCell;
+use tracing::warn;
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome,
+};
+
+const KEY_PREFIX: &str = "xrouter";
+/// Upper bound on one Redis round trip, connecting included.
+pub(crate) const REDIS_TIMEOUT: Duration = Duration::from_millis(500);
+/// After a failed call Redis is skipped for this long, so an outage costs requests no latency.
+const REDIS_RETRY_AFTER: Duration = Duration::from_secs(5);
+/// Slots of a replica that dies mid-request are reclaimed once no replica has taken a slot for
+/// the provider for this long.
+const INFLIGHT_LEASE: Duration = Duration::from_secs(10 * 60);
+
+const WINDOW_HIT_SCRIPT: &str = r"
+local count = redis.call('INCR', KEYS[1])
+if count == 1 then redis.call('PEXPIRE', KEYS[1], ARGV[1]) end
+return {count, redis.call('PTTL', KEYS[1])}";
+
+const ACQUIRE_SLOT_SCRIPT: &str = r"
+local taken = redis.call('INCR', KEYS[1])
+if taken > tonumber(ARGV[1]) then
+  redis.call('DECR', KEYS[1])
+  return 0
+end
+redis.call('PEXPIRE', KEYS[1], ARGV[2])
+return 1";
+
+/// Counters shared by every replica pointing at the same Redis. Callers fall back to their
+/// in-memory state when Redis cannot be reached, so an outage degrades to per-replica limits.
+pub(crate) struct RedisCoordinator {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+    paused_until: Mutex<Option<Instant>>,
+    window_hit: Script,
+    acquire_slot: Script,
+}
+
+impl std::fmt::Debug for RedisCoordinator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisCoordinator").finish_non_exhaustive()
+    }
+}
+
+impl RedisCoordinator {
+    pub(crate) fn open(url: &str) -> RedisResult<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            connection: OnceCell::new(),
+            paused_until: Mutex::new(None),
+            window_hit: Script::new(WINDOW_HIT_SCRIPT),
+            acquire_slot: Script::new(ACQUIRE_SLOT_SCRIPT),
+        })
+    }
+
+    async fn connection(&self) -> RedisResult<ConnectionManager> {
+        self.connection.get_or_try_init(|| connect_redis(&self.client)).await.cloned()
+    }
+
+    async fn call<T, F, Fut>(&self, op: F) -> RedisResult<T>
+    where
+        F: FnOnce(ConnectionManager) -> Fut,
+        Fut: Future<Output = RedisResult<T>>,
+    {
+        let paused_until = || self.paused_until.lock().unwrap_or_else(|p| p.into_inner());
+        if paused_until().is_some_and(|until| Instant::now() < until) {
+            return Err((ErrorKind::IoError, "redis skipped after a recent failure").into());
+        }
+        let result =
+            tokio::time::timeout(REDIS_TIMEOUT, async { op(self.connection().await?).await })
+                .await
+                .unwrap_or_else(|_| Err((ErrorKind::IoError, "redis call timed out").into()));
+        *paused_until() = result.is_err().then(|| Instant::now() + REDIS_RETRY_AFTER);
+        result
+    }
+
+    /// Counts one hit in the fixed window `key` belongs to; returns the hits so far and the time
+    /// left until the window resets.
+    pub(crate) async fn hit_window(
+        &self,
+        scope: &str,
+        key: &str,
+        window: Duration,
+    ) -> RedisResult<(u64, Duration)> {
+        self.call(|mut connection| async move {
+            self.window_hit
+                .key(redis_key("rate", scope, key))
+                .arg(window.as_millis() as u64)
+                .invoke_async::<(u64, i64)>(&mut connection)
+                .await
+                .map(|(hits, ttl_ms)| (hits, Duration::from_millis(ttl_ms.max(0) as u64)))
+        })
+        .await
+    }
+
+    pub(crate) async fn try_acquire_slot(&self, provider: &str, limit: usize) -> RedisResult<bool> {
+        self.call(|mut connection| async move {
+            self.acquire_slot
+                .key(redis_key("inflight", "provider", provider))
+                .arg(limit)
+                .arg(INFLIGHT_LEASE.as_millis() as u64)
+                .invoke_async(&mut connection)
+                .await
+        })
+        .await
+    }
+
+    pub(crate) async fn release_slot(&self, provider: &str) -> RedisResult<()> {
+        self.call(|mut connection| async move {
+            redis::cmd("DECR")
+                .arg(redis_key("inflight", "provider", provider))
+                .exec_async(&mut connection)
+                .await
+        })
+        .await
+    }
+}
+
+/// Connection that reconnects on its own once established; shared with the response store.
+pub(crate) async fn connect_redis(client: &redis::Client) -> RedisResult<ConnectionManager> {
+    client
+        .get_connection_manager_with_config(
+            ConnectionManagerConfig::new()
+                .set_connection_timeout(REDIS_TIMEOUT)
+                .set_response_timeout(REDIS_TIMEOUT)
+                .set_number_of_retries(1),
+        )
+        .await
+}
+
+fn redis_key(kind: &str, scope: &str, key: &str) -> String {
+    format!("{KEY_PREFIX}:{kind}:{scope}:{key}")
+}
+
+/// Holds a provider's cluster-wide in-flight budget around `inner`, on top of the per-replica
+/// semaphore each client keeps.
+pub(crate) struct CoordinatedProviderClient {
+    provider_id: String,
+    inner: Arc<dyn ProviderClient>,
+    coordinator: Arc<RedisCoordinator>,
+    max_inflight: usize,
+}
+
+impl CoordinatedProviderClient {
+    pub(crate) fn new(
+        provider_id: String,
+        inner: Arc<dyn ProviderClient>,
+        coordinator: Arc<RedisCoordinator>,
+        max_inflight: usize,
+    ) -> Self {
+        Self { provider_id, inner, coordinator, max_inflight }
+    }
+
+    async fn acquire(&self) -> Result<Option<SlotGuard>, CoreError> {
+        match self.coordinator.try_acquire_slot(&self.provider_id, self.max_inflight).await {
+            Ok(true) => Ok(Some(SlotGuard {
+                provider_id: self.provider_id.clone(),
+                coordinator: Arc::clone(&self.coordinator),
+            })),
+            Ok(false) => Err(CoreError::Provider(format!(
+                "provider overloaded: max in-flight limit reached for {}",
+                self.provider_id
+            ))),
+            Err(error) => {
+                warn!(
+                    event = "coordination.redis.unavailable",
+                    scope = "inflight",
+                    provider = %self.provider_id,
+                    error = %error
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Returns the slot when the request ends, including when its future is dropped.
+struct SlotGuard {
+    provider_id: String,
+    coordinator: Arc<RedisCoordinator>,
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        let provider_id = std::mem::take(&mut self.provider_id);
+        let coordinator = Arc::clone(&self.coordinator);
+        tokio::spawn(async move {
+            if let Err(error) = coordinator.release_slot(&provider_id).await {
+                warn!(
+                    event = "coordination.redis.release_failed",
+                    provider = %provider_id,
+                    error = %error
+                );
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl ProviderClient for CoordinatedProviderClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let _slot = self.acquire().await?;
+        self.inner.generate(request).await
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let _slot = self.acquire().await?;
+        self.inner.generate_stream(request).await
+    }
+
+    fn supports_required_tool_choice(&self) -> bool {
+        self.inner.supports_required_tool_choice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use xrouter_clients_openai::MockProviderClient;
+    use xrouter_contracts::ResponsesInput;
+    use xrouter_core::{ExecutionControls, ProviderClient, ProviderGenerateRequest};
+
+    use super::{CoordinatedProviderClient, REDIS_TIMEOUT, RedisCoordinator, redis_key};
+
+    // Nothing listens on port 1, so every Redis call fails fast with a connection error.
+    const UNREACHABLE_REDIS: &str = "redis://127.0.0.1:1/";
+
+    #[test]
+    fn redis_keys_are_namespaced_by_kind_and_scope() {
+        assert_eq!(redis_key("rate", "tenant", "acme/web"), "xrouter:rate:tenant:acme/web");
+        assert!(RedisCoordinator::open("http://localhost").is_err());
+    }
+
+    #[tokio::test]
+    async fn unreachable_redis_reports_errors_instead_of_blocking() {
+        let coordinator = RedisCoordinator::open(UNREACHABLE_REDIS).expect("url must parse");
+        let started_at = Instant::now();
+        assert!(coordinator.hit_window("user", "alice", Duration::from_secs(60)).await.is_err());
+        assert!(started_at.elapsed() <= REDIS_TIMEOUT + Duration::from_millis(100));
+
+        let retried_at = Instant::now();
+        assert!(coordinator.try_acquire_slot("zai", 1).await.is_err());
+        assert!(retried_at.elapsed() < Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn coordinated_client_serves_requests_when_redis_is_down() {
+        let client = CoordinatedProviderClient::new(
+            "zai".to_string(),
+            Arc::new(MockProviderClient::new("zai".to_string())),
+            Arc::new(RedisCoordinator::open(UNREACHABLE_REDIS).expect("url must parse")),
+            1,
+        );
+        let input = ResponsesInput::Text("hello".to_string());
+        let result = client
+            .generate(ProviderGenerateRequest {
+                model: "glm-4.5",
+                instructions: None,
+                input: &input,
+                reasoning: None,
+                tools: None,
+                tool_choice: None,
+                include: None,
+                text: None,
+                modalities: None,
+                auth_bearer: None,
+                forward_headers: &[],
+                extra: None,
+                controls: ExecutionControls::default(),
+            })
+            .await;
+        assert!(result.is_ok());
+    }
+}
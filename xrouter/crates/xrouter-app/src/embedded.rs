@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+use xrouter_contracts::{ResponseEvent, ResponsesRequest, ResponsesResponse};
+use xrouter_core::{CoreError, ExecutionEngine, ModelDescriptor};
+
+use crate::{
+    AppBuilder, AppState,
+    config::{AppConfig, ProviderConfig, default_provider_base_url},
+    http::routes::inference::{
+        StreamBilling, ensure_id_prefix, routing_meta, run_responses_request, spawn_engine_stream,
+        validate_input_length, validate_output_controls,
+    },
+};
+
+/// The router without its HTTP layer: model routing, provider mapping, retries and fallbacks of
+/// the HTTP API, called directly from Rust.
+#[derive(Clone)]
+pub struct XRouter {
+    state: AppState,
+}
+
+pub struct XRouterBuilder {
+    config: AppConfig,
+}
+
+impl XRouter {
+    /// Starts from [`AppConfig::embedded`]: no providers and every optional feature off.
+    pub fn builder() -> XRouterBuilder {
+        XRouterBuilder { config: AppConfig::embedded() }
+    }
+
+    /// Merged model catalog, as served by `/api/v1/models`.
+    pub fn models(&self) -> &[ModelDescriptor] {
+        self.state.models()
+    }
+
+    pub async fn respond(
+        &self,
+        mut request: ResponsesRequest,
+    ) -> Result<ResponsesResponse, CoreError> {
+        request.stream = false;
+        let route = self.route(&mut request)?;
+        let mut response = run_responses_request(
+            &self.state,
+            &route.provider,
+            route.engine,
+            request,
+            None,
+            Vec::new(),
+            Default::default(),
+        )
+        .await?;
+        response.id = ensure_id_prefix(&response.id, "resp_");
+        response.meta =
+            Some(routing_meta(&route.provider, route.fallback_reason, response.meta.as_ref()));
+        Ok(response)
+    }
+
+    /// Streams the events of one response; the stream ends after `ResponseCompleted` or
+    /// `ResponseError`. Must be called within a Tokio runtime.
+    pub fn respond_stream(
+        &self,
+        mut request: ResponsesRequest,
+    ) -> Result<impl Stream<Item = Result<ResponseEvent, CoreError>> + Send + 'static, CoreError>
+    {
+        request.stream = true;
+        let route = self.route(&mut request)?;
+        let provider = route.provider.clone();
+        let fallback_reason = route.fallback_reason;
+        let events = spawn_engine_stream(
+            &self.state,
+            &route.provider,
+            route.engine,
+            request,
+            None,
+            Vec::new(),
+            Default::default(),
+            StreamBilling::default(),
+        );
+        Ok(events.map(move |event| match event {
+            Ok(ResponseEvent::ResponseCompleted { id, output, finish_reason, usage, meta }) => {
+                Ok(ResponseEvent::ResponseCompleted {
+                    id,
+                    output,
+                    finish_reason,
+                    usage,
+                    meta: Some(routing_meta(&provider, fallback_reason, meta.as_ref())),
+                })
+            }
+            other => other,
+        }))
+    }
+
+    /// Cancels the responses still streaming and waits until their upstream calls stopped.
+    pub async fn shutdown(&self) {
+        self.state.shutdown_streams().await;
+    }
+
+    /// Same request checks and provider resolution as the HTTP handlers; rewrites
+    /// `request.model` to the provider's own model id.
+    fn route(&self, request: &mut ResponsesRequest) -> Result<Route, CoreError> {
+        let fallback_reason = self.state.route_unknown_model(&mut request.model)?;
+        let provider = self.state.resolve_provider_key(&request.model);
+        let provider_model = self.state.resolve_provider_model_id(&request.model);
+        validate_output_controls(&self.state, &provider, &provider_model, request)?;
+        validate_input_length(&self.state, &provider, &provider_model, request)?;
+        self.state.retain_passthrough_fields(&provider, &mut request.extra);
+        let engine = self.state.resolve_engine(&request.model)?;
+        request.model = provider_model;
+        Ok(Route { provider, fallback_reason, engine })
+    }
+}
+
+struct Route {
+    provider: String,
+    fallback_reason: Option<&'static str>,
+    engine: Arc<ExecutionEngine>,
+}
+
+impl XRouterBuilder {
+    /// Replaces everything set so far, e.g. with [`AppConfig::from_env`].
+    pub fn config(mut self, config: AppConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Enables `name` at its default endpoint. Names without one (`ollama`, `xrouter`, custom
+    /// OpenAI-compatible upstreams) need [`XRouterBuilder::provider_config`] with a `base_url`.
+    pub fn provider(self, name: &str, api_key: impl Into<String>) -> Self {
+        let config = ProviderConfig {
+            enabled: true,
+            api_key: Some(api_key.into()),
+            base_url: default_provider_base_url(name).map(ToString::to_string),
+            ..ProviderConfig::default()
+        };
+        self.provider_config(name, config)
+    }
+
+    pub fn provider_config(mut self, name: &str, config: ProviderConfig) -> Self {
+        self.config.providers.insert(name.to_string(), config);
+        self
+    }
+
+    /// Builds provider clients and loads the model catalog, blocking on remote catalog fetches
+    /// for up to `model_catalog_startup_budget_seconds`; call it before serving traffic, as the
+    /// HTTP server does.
+    pub fn build(self) -> XRouter {
+        XRouter { state: AppBuilder::new(&self.config).build_state() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use xrouter_contracts::{ResponseEvent, ResponseOutputItem, ResponsesRequest};
+    use xrouter_core::CoreError;
+
+    use super::XRouter;
+    use crate::config::AppConfig;
+
+    fn request(model: &str, input: &str) -> ResponsesRequest {
+        serde_json::from_value(serde_json::json!({"model": model, "input": input}))
+            .expect("request must parse")
+    }
+
+    fn output_text(output: &[ResponseOutputItem]) -> String {
+        output
+            .iter()
+            .filter_map(|item| match item {
+                ResponseOutputItem::Message { content, .. } => {
+                    Some(content.iter().map(|part| part.text.as_str()).collect::<String>())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn respond_routes_by_model_prefix_and_reports_the_provider() {
+        let router = XRouter::builder().config(AppConfig::for_tests()).build();
+
+        let response = router
+            .respond(request("openrouter/anthropic/claude-3.5-sonnet", "hello world"))
+            .await
+            .expect("response");
+
+        assert_eq!(output_text(&response.output).trim_end(), "[openrouter] hello world");
+        assert!(response.id.starts_with("resp_"));
+        let meta = response.meta.expect("routing meta");
+        assert_eq!(meta.provider.as_deref(), Some("openrouter"));
+        assert_eq!(meta.fallback_reason, None);
+    }
+
+    #[tokio::test]
+    async fn respond_stream_ends_with_the_completed_response() {
+        let router = XRouter::builder().config(AppConfig::for_tests()).build();
+
+        let mut events = router
+            .respond_stream(request("deepseek/deepseek-chat", "hello world"))
+            .expect("stream");
+        let mut text = String::new();
+        let mut completed = None;
+        while let Some(event) = events.next().await {
+            match event.expect("event") {
+                ResponseEvent::OutputTextDelta { delta, .. } => text.push_str(&delta),
+                ResponseEvent::ResponseCompleted { output, meta, .. } => {
+                    completed = Some((output, meta))
+                }
+                _ => {}
+            }
+        }
+
+        let (output, meta) = completed.expect("completed event");
+        assert_eq!(text, output_text(&output));
+        assert_eq!(meta.and_then(|meta| meta.provider).as_deref(), Some("deepseek"));
+    }
+
+    #[tokio::test]
+    async fn requests_fail_without_a_provider_to_route_to() {
+        let router = XRouter::builder().build();
+
+        let error = router
+            .respond(request("openrouter/anthropic/claude-3.5-sonnet", "hello world"))
+            .await
+            .expect_err("no providers are configured");
+        assert!(matches!(error, CoreError::Validation(_)), "{error:?}");
+    }
+
+    #[test]
+    fn provider_uses_its_default_endpoint() {
+        let builder = XRouter::builder().provider("openrouter", "sk-test");
+
+        let provider = &builder.config.providers["openrouter"];
+        assert!(provider.enabled);
+        assert_eq!(provider.base_url.as_deref(), Some("https://openrouter.ai/api/v1"));
+    }
+}
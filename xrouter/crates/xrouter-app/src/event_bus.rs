@@ -0,0 +1,169 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{Value, json};
+use tracing::{info, warn};
+use xrouter_contracts::Usage;
+
+use crate::{
+    config::EventBusConfig,
+    record_queue::{RecordQueue, RecordSink},
+    stream_latency::StreamLatency,
+    usage_export::UsageExport,
+    usage_ledger::UsageLedger,
+};
+
+/// Publishes request lifecycle events to Kafka or NATS for billing, alerting and audit
+/// consumers. Events carry routing metadata and usage only, never prompts or outputs.
+pub(crate) struct EventBus {
+    queue: RecordQueue,
+}
+
+impl EventBus {
+    pub(crate) fn from_config(config: &EventBusConfig, buffer: usize) -> Option<Arc<Self>> {
+        let (backend, sink) = match config {
+            EventBusConfig::Off => return None,
+            // Keyed by request id, so the events of one request stay in order on one partition.
+            EventBusConfig::Kafka { url, topic } => (
+                "kafka",
+                RecordSink::KafkaRest {
+                    url: url.clone(),
+                    topic: topic.clone(),
+                    key_field: "request_id",
+                },
+            ),
+            EventBusConfig::Nats { url, subject } => {
+                ("nats", RecordSink::Nats { url: url.clone(), subject: subject.clone() })
+            }
+        };
+        let queue = match RecordQueue::new("event_bus", sink, buffer) {
+            Ok(queue) => queue,
+            Err(error) => {
+                warn!(event = "app.event_bus.client_failed", error = %error);
+                return None;
+            }
+        };
+        info!(event = "app.event_bus.enabled", backend = backend, buffer = buffer);
+        Some(Arc::new(Self { queue }))
+    }
+}
+
+/// Lifecycle of one request, correlated by a fresh `request_id`. Every event goes to the event
+/// bus; finished requests are also recorded for the usage export and the usage ledger.
+#[derive(Clone)]
+pub(crate) struct RequestEvents {
+    bus: Option<Arc<EventBus>>,
+    usage_export: Option<Arc<UsageExport>>,
+    usage_ledger: Option<Arc<UsageLedger>>,
+    request_id: String,
+    route: String,
+    model: String,
+    provider: String,
+    tenant_id: String,
+}
+
+impl RequestEvents {
+    /// `None` when no event bus, usage export or usage ledger is configured.
+    pub(crate) fn new(
+        bus: Option<&Arc<EventBus>>,
+        usage_export: Option<&Arc<UsageExport>>,
+        usage_ledger: Option<&Arc<UsageLedger>>,
+        route: &str,
+        model: &str,
+        provider: &str,
+        tenant_id: &str,
+    ) -> Option<Self> {
+        if bus.is_none() && usage_export.is_none() && usage_ledger.is_none() {
+            return None;
+        }
+        Some(Self {
+            bus: bus.cloned(),
+            usage_export: usage_export.cloned(),
+            usage_ledger: usage_ledger.cloned(),
+            request_id: format!("req_{}", uuid::Uuid::new_v4().simple()),
+            route: route.to_string(),
+            model: model.to_string(),
+            provider: provider.to_string(),
+            tenant_id: tenant_id.to_string(),
+        })
+    }
+
+    pub(crate) fn received(&self, stream: bool) {
+        self.emit("request.received", json!({"stream": stream}));
+    }
+
+    pub(crate) fn provider_selected(&self, fallback_reason: Option<&str>) {
+        self.emit("provider.selected", json!({"fallback_reason": fallback_reason}));
+    }
+
+    /// `stream.completed`, with the stream's delta latency, for streams; `request.completed`
+    /// otherwise.
+    pub(crate) fn completed(
+        &self,
+        finish_reason: &str,
+        usage: &Usage,
+        duration: Duration,
+        stream_latency: Option<&StreamLatency>,
+    ) {
+        let kind = if stream_latency.is_some() { "stream.completed" } else { "request.completed" };
+        let mut details = json!({
+            "finish_reason": finish_reason,
+            "usage": usage,
+            "duration_ms": duration.as_millis() as u64,
+        });
+        if let Some(latency) = stream_latency {
+            details["time_to_first_token_ms"] = json!(latency.time_to_first_token_ms());
+            details["inter_token_latency_ms"] = json!(latency.inter_token_latency_ms());
+        }
+        let record = self.emit(kind, details);
+        self.export(Some(usage), record);
+    }
+
+    pub(crate) fn failed(&self, error: &str, duration: Duration) {
+        let record = self.emit(
+            "request.failed",
+            json!({"error": error, "duration_ms": duration.as_millis() as u64}),
+        );
+        self.export(None, record);
+    }
+
+    fn emit(&self, kind: &'static str, details: Value) -> Value {
+        let mut record = json!({
+            "type": kind,
+            "request_id": self.request_id,
+            "route": self.route,
+            "model": self.model,
+            "provider": self.provider,
+            "tenant_id": self.tenant_id,
+            "timestamp_ms": unix_millis(),
+        });
+        if let (Some(record), Value::Object(details)) = (record.as_object_mut(), details) {
+            record.extend(details);
+        }
+        if let Some(bus) = &self.bus {
+            bus.queue.publish(record.clone());
+        }
+        record
+    }
+
+    fn export(&self, usage: Option<&Usage>, record: Value) {
+        if let Some(usage_ledger) = &self.usage_ledger {
+            usage_ledger.record(
+                &self.tenant_id,
+                &self.model,
+                &self.provider,
+                usage,
+                record.clone(),
+            );
+        }
+        if let Some(usage_export) = &self.usage_export {
+            usage_export.record(&self.tenant_id, &self.model, &self.provider, usage, record);
+        }
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
@@ -0,0 +1,69 @@
+use axum::http::HeaderMap;
+use xrouter_contracts::ResponsesRequest;
+use xrouter_core::CoreError;
+
+use crate::AppState;
+
+pub(crate) const MODEL_HEADER: &str = "x-xrouter-model";
+pub(crate) const PROVIDER_HEADER: &str = "x-xrouter-provider";
+pub(crate) const REASONING_EFFORT_HEADER: &str = "x-xrouter-reasoning-effort";
+
+const REASONING_EFFORTS: [&str; 6] = ["none", "minimal", "low", "medium", "high", "xhigh"];
+
+/// Applies the override headers enabled by `XR_HEADER_OVERRIDES` to `request` and returns the
+/// headers it applied; headers that are not enabled are ignored.
+pub(crate) fn apply_header_overrides(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &mut ResponsesRequest,
+) -> Result<Vec<&'static str>, CoreError> {
+    let overrides = state.header_overrides;
+    let mut applied = Vec::new();
+    if overrides.model
+        && let Some(model) = header_value(headers, MODEL_HEADER)?
+    {
+        request.model = model.to_string();
+        applied.push(MODEL_HEADER);
+    }
+    if overrides.provider
+        && let Some(provider) = header_value(headers, PROVIDER_HEADER)?
+    {
+        let provider = provider.to_ascii_lowercase();
+        if !state.engines.contains_key(&provider) {
+            return Err(CoreError::Validation(format!(
+                "{PROVIDER_HEADER} names no enabled provider: {provider}"
+            )));
+        }
+        request.model = format!("{provider}/{}", state.resolve_provider_model_id(&request.model));
+        applied.push(PROVIDER_HEADER);
+    }
+    if overrides.reasoning_effort
+        && let Some(effort) = header_value(headers, REASONING_EFFORT_HEADER)?
+    {
+        let effort = effort.to_ascii_lowercase();
+        if !REASONING_EFFORTS.contains(&effort.as_str()) {
+            return Err(CoreError::Validation(format!(
+                "{REASONING_EFFORT_HEADER} must be one of {}",
+                REASONING_EFFORTS.join(", ")
+            )));
+        }
+        let reasoning = request.reasoning.get_or_insert_default();
+        reasoning.effort = Some(effort);
+        reasoning.max_tokens = None;
+        applied.push(REASONING_EFFORT_HEADER);
+    }
+    Ok(applied)
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Result<Option<&'a str>, CoreError> {
+    let Some(value) = headers.get(name) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(Some)
+        .ok_or_else(|| CoreError::Validation(format!("{name} must be a non-empty string")))
+}
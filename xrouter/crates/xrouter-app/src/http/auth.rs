@@ -1,7 +1,34 @@
-use axum::http::HeaderMap;
+use axum::{
+    extract::{Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
 use tracing::info;
 use xrouter_core::CoreError;
 
+use crate::{AppState, tenancy::authenticate_request};
+
+/// Authenticates inference requests once, ahead of replays, coalescing and the handlers, and
+/// hands the caller's [`TenantPrincipal`](crate::tenancy::TenantPrincipal) on in the request
+/// extensions.
+pub(crate) async fn authenticate_requests(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let route = request.uri().path().to_string();
+    match authenticate_request(state.tenants.as_deref(), request.headers(), &route).await {
+        Ok(principal) => {
+            if let Some(principal) = principal {
+                request.extensions_mut().insert(principal);
+            }
+            next.run(request).await
+        }
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
 pub(crate) fn resolve_byok_bearer(
     headers: &HeaderMap,
     byok_enabled: bool,
@@ -0,0 +1,50 @@
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{StatusCode, header::CONTENT_LENGTH, header::CONTENT_TYPE},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::http::docs::ErrorResponse;
+
+/// Rejects bodies over `limit` bytes with a JSON `413` before any handler buffers them.
+/// Declared lengths are checked up front; chunked bodies are cut off by `DefaultBodyLimit`,
+/// whose plain-text rejection is rewritten here to the same JSON shape.
+pub(crate) async fn reject_oversized_body(
+    State(limit): State<usize>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    let route = request.uri().path().to_string();
+    if let Some(bytes) = declared.filter(|bytes| *bytes > limit as u64) {
+        warn!(
+            event = "http.request.body_too_large",
+            route = %route,
+            body_bytes = bytes,
+            limit_bytes = limit
+        );
+        return too_large(format!("request body is {bytes} bytes, over the {limit}-byte limit"));
+    }
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        warn!(event = "http.request.body_too_large", route = %route, limit_bytes = limit);
+        return too_large(format!("request body is over the {limit}-byte limit"));
+    }
+    response
+}
+
+pub(crate) fn too_large(error: String) -> Response {
+    (StatusCode::PAYLOAD_TOO_LARGE, Json(ErrorResponse { error, param: None })).into_response()
+}
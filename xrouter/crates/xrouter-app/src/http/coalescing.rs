@@ -0,0 +1,248 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    http::{StatusCode, header::AUTHORIZATION},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::watch;
+use tracing::{debug, warn};
+
+use crate::{
+    app_state::AppState,
+    http::{
+        body_limit::too_large,
+        idempotency::{CachedResponse, hash_headers, is_stream_request, rejection},
+    },
+    request_context::effective_headers,
+    tenancy::TenantPrincipal,
+};
+
+pub(crate) const COALESCED_HEADER: &str = "x-xrouter-coalesced";
+
+type SharedResponse = Option<Arc<CachedResponse>>;
+
+/// Identical non-streaming requests currently running, by canonical request hash.
+#[derive(Debug, Default)]
+pub(crate) struct InflightRequests {
+    flights: Mutex<HashMap<[u8; 32], watch::Receiver<SharedResponse>>>,
+}
+
+enum Flight {
+    Leader(LeaderGuard),
+    Follower(watch::Receiver<SharedResponse>),
+}
+
+impl InflightRequests {
+    fn join(self: &Arc<Self>, key: [u8; 32]) -> Flight {
+        let mut flights = self.flights.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(receiver) = flights.get(&key) {
+            return Flight::Follower(receiver.clone());
+        }
+        let (sender, receiver) = watch::channel(None);
+        flights.insert(key, receiver);
+        Flight::Leader(LeaderGuard { requests: Arc::clone(self), key, sender })
+    }
+
+    fn len(&self) -> usize {
+        self.flights.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).len()
+    }
+}
+
+/// Ends the flight when the leading request finishes or is dropped; followers of a dropped
+/// leader run their own request.
+struct LeaderGuard {
+    requests: Arc<InflightRequests>,
+    key: [u8; 32],
+    sender: watch::Sender<SharedResponse>,
+}
+
+impl LeaderGuard {
+    fn publish(&self, response: Arc<CachedResponse>) {
+        self.sender.send_replace(Some(response));
+    }
+}
+
+impl Drop for LeaderGuard {
+    fn drop(&mut self) {
+        self.requests
+            .flights
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.key);
+    }
+}
+
+/// Runs identical concurrent non-streaming requests of one caller upstream once and hands every
+/// waiting caller a copy of the result, flagged with `x-xrouter-coalesced: true`.
+pub(crate) async fn coalesce_identical_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(inflight) = state.request_coalescing.clone() else {
+        return next.run(request).await;
+    };
+    if key_opted_out(&request) {
+        return next.run(request).await;
+    }
+    let (parts, body) = request.into_parts();
+    let limit = state.max_request_body_bytes;
+    let Ok(body) = to_bytes(body, limit).await else {
+        return too_large(format!("request body is over the {limit}-byte limit"));
+    };
+    let key = serde_json::from_slice::<Value>(&body)
+        .ok()
+        .filter(|_| !is_stream_request(&body))
+        .map(|payload| {
+            request_hash(
+                parts.uri.path(),
+                parts.headers.get(AUTHORIZATION),
+                &effective_headers(&state, &parts.headers),
+                &payload,
+            )
+        });
+    let request = Request::from_parts(parts, Body::from(body));
+    let Some(key) = key else {
+        return next.run(request).await;
+    };
+
+    match inflight.join(key) {
+        Flight::Leader(guard) => {
+            let response = next.run(request).await;
+            let cached = match CachedResponse::buffer(response).await {
+                Ok(cached) => Arc::new(cached),
+                Err(error) => {
+                    warn!(event = "http.coalescing.response_unreadable", error = %error);
+                    return rejection(
+                        StatusCode::BAD_GATEWAY,
+                        "response body could not be read".to_string(),
+                    );
+                }
+            };
+            guard.publish(Arc::clone(&cached));
+            cached.to_response()
+        }
+        Flight::Follower(mut receiver) => {
+            let shared =
+                receiver.wait_for(Option::is_some).await.ok().and_then(|shared| shared.clone());
+            match shared {
+                Some(cached) => {
+                    debug!(event = "http.coalescing.joined", inflight = inflight.len());
+                    cached.to_response_marked(COALESCED_HEADER)
+                }
+                None => next.run(request).await,
+            }
+        }
+    }
+}
+
+fn key_opted_out(request: &Request) -> bool {
+    request
+        .extensions()
+        .get::<TenantPrincipal>()
+        .and_then(|principal| principal.key.as_ref())
+        .is_some_and(|key| key.coalescing_opt_out)
+}
+
+/// Hash of the route, credentials, effective `x-xrouter-*` headers and body with object keys
+/// sorted, so requests that differ only in field order or whitespace coalesce.
+fn request_hash(
+    path: &str,
+    authorization: Option<&axum::http::HeaderValue>,
+    effective_headers: &[(&str, &[u8])],
+    payload: &Value,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(path.as_bytes());
+    hasher.update([0]);
+    hasher.update(authorization.map(|value| value.as_bytes()).unwrap_or_default());
+    hasher.update([0]);
+    hash_headers(effective_headers, &mut hasher);
+    hash_canonical(payload, &mut hasher);
+    hasher.finalize().into()
+}
+
+fn hash_canonical(value: &Value, hasher: &mut Sha256) {
+    match value {
+        Value::Object(map) => {
+            let mut keys = map.keys().collect::<Vec<_>>();
+            keys.sort();
+            hasher.update(b"{");
+            for key in keys {
+                hash_canonical(&Value::String(key.clone()), hasher);
+                hash_canonical(&map[key], hasher);
+            }
+            hasher.update(b"}");
+        }
+        Value::Array(items) => {
+            hasher.update(b"[");
+            items.iter().for_each(|item| hash_canonical(item, hasher));
+            hasher.update(b"]");
+        }
+        scalar => {
+            hasher.update(scalar.to_string().as_bytes());
+            hasher.update(b",");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::{Flight, InflightRequests, request_hash};
+
+    #[test]
+    fn request_hash_ignores_key_order_but_not_values_or_credentials() {
+        let alice = axum::http::HeaderValue::from_static("Bearer alice");
+        let first = json!({"model": "m", "input": [{"role": "user", "content": "hi"}]});
+        let reordered = json!({"input": [{"content": "hi", "role": "user"}], "model": "m"});
+        let hash = |payload: &serde_json::Value| {
+            request_hash("/api/v1/responses", Some(&alice), &[], payload)
+        };
+
+        assert_eq!(hash(&first), hash(&reordered));
+        assert_ne!(hash(&first), hash(&json!({"model": "m", "input": "hi"})));
+        assert_ne!(hash(&first), request_hash("/api/v1/responses", None, &[], &first));
+        assert_ne!(
+            hash(&first),
+            request_hash("/api/v1/chat/completions", Some(&alice), &[], &first)
+        );
+    }
+
+    #[test]
+    fn request_hash_differs_per_effective_header_override() {
+        let payload = json!({"model": "m", "input": "hi"});
+        let hash =
+            |headers: &[(&str, &[u8])]| request_hash("/api/v1/responses", None, headers, &payload);
+
+        assert_eq!(hash(&[("x-xrouter-model", b"a")]), hash(&[("x-xrouter-model", b"a")]));
+        assert_ne!(hash(&[]), hash(&[("x-xrouter-model", b"a")]));
+        assert_ne!(hash(&[("x-xrouter-model", b"a")]), hash(&[("x-xrouter-model", b"b")]));
+        assert_ne!(hash(&[("x-xrouter-model", b"a")]), hash(&[("x-xrouter-provider", b"a")]));
+    }
+
+    #[test]
+    fn second_request_follows_until_the_leader_finishes() {
+        let inflight = Arc::new(InflightRequests::default());
+        let Flight::Leader(leader) = inflight.join([1; 32]) else {
+            panic!("first request must lead");
+        };
+        assert!(matches!(inflight.join([1; 32]), Flight::Follower(_)));
+        assert!(matches!(inflight.join([2; 32]), Flight::Leader(_)));
+
+        drop(leader);
+        assert!(matches!(inflight.join([1; 32]), Flight::Leader(_)));
+        assert_eq!(inflight.len(), 0);
+    }
+}
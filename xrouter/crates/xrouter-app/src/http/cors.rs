@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowOrigin, CorsLayer, ExposeHeaders};
+
+use crate::{config::CorsConfig, http::rate_limit::QUOTA_HEADERS};
+
+/// Response headers browsers may read besides the CORS-safelisted ones.
+const EXPOSED_HEADERS: [HeaderName; 10] = [
+    HeaderName::from_static("x-xrouter-provider"),
+    HeaderName::from_static("x-xrouter-upstream-provider"),
+    HeaderName::from_static("x-xrouter-attempts"),
+    HeaderName::from_static("x-xrouter-fallback-reason"),
+    HeaderName::from_static("x-xrouter-input-tokens"),
+    HeaderName::from_static("x-xrouter-output-tokens"),
+    HeaderName::from_static("x-xrouter-cost"),
+    HeaderName::from_static(crate::http::idempotency::IDEMPOTENT_REPLAY_HEADER),
+    HeaderName::from_static(crate::http::coalescing::COALESCED_HEADER),
+    HeaderName::from_static(crate::http::versioning::VERSION_HEADER),
+];
+
+/// Layer answering preflight `OPTIONS` requests and tagging responses for allowed origins;
+/// `None` while no origin is configured. Values are validated when the config is loaded.
+pub(crate) fn cors_layer(config: &CorsConfig) -> Option<CorsLayer> {
+    if !config.enabled() {
+        return None;
+    }
+    let origins = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config.allowed_origins.iter().filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+    let headers = if config.allowed_headers.is_empty() {
+        AllowHeaders::mirror_request()
+    } else {
+        AllowHeaders::list(
+            config
+                .allowed_headers
+                .iter()
+                .filter_map(|header| HeaderName::from_bytes(header.as_bytes()).ok()),
+        )
+    };
+    let methods = config
+        .allowed_methods
+        .iter()
+        .filter_map(|method| Method::from_bytes(method.as_bytes()).ok())
+        .collect::<Vec<_>>();
+    let mut layer = CorsLayer::new()
+        .allow_origin(origins)
+        .allow_headers(headers)
+        .allow_methods(methods)
+        .expose_headers(ExposeHeaders::list(EXPOSED_HEADERS.into_iter().chain(QUOTA_HEADERS)));
+    if let Some(seconds) = config.max_age_seconds {
+        layer = layer.max_age(Duration::from_secs(seconds));
+    }
+    Some(layer)
+}
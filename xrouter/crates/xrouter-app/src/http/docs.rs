@@ -1,15 +1,33 @@
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
+    middleware,
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
-use utoipa::{OpenApi, ToSchema};
+use serde_json::Value;
+use utoipa::{
+    IntoParams, IntoResponses, Modify, OpenApi, ToSchema,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+#[cfg(feature = "docs")]
 use utoipa_swagger_ui::SwaggerUi;
+use xrouter_clients_openai::{ChaosPolicy, ToolNormalization};
 use xrouter_contracts::{
-    ChatCompletionsRequest, ChatCompletionsResponse, ResponsesRequest, ResponsesResponse,
+    ChatCompletionsRequest, ChatCompletionsResponse, CompletionChoice, CompletionPrompt,
+    CompletionsRequest, CompletionsResponse, RequestPriority, ResponseMeta, ResponseOutputItem,
+    ResponsesRequest, ResponsesResponse, Usage,
 };
+use xrouter_core::ModelPricing;
 
-use crate::AppState;
+use crate::{
+    AppState,
+    bandit::{ArmSnapshot, BanditSnapshot},
+    config::{BanditReward, BanditStrategy, TruncationStrategy},
+    model_stats::StatsSummary,
+    provider_stats::{ProviderStatsSnapshot, ProviderStatus},
+    scheduling::{ClassSnapshot, GateSnapshot},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub(crate) struct HealthResponse {
@@ -22,6 +40,8 @@ pub(crate) struct CompatibleModelEntry {
     pub(crate) object: String,
     pub(crate) created: i64,
     pub(crate) owned_by: String,
+    /// Request parameters the model accepts, e.g. `tools`, `reasoning`, `response_format`.
+    pub(crate) supported_parameters: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -30,6 +50,21 @@ pub(crate) struct CompatibleModelsResponse {
     pub(crate) data: Vec<CompatibleModelEntry>,
 }
 
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ModelListQuery {
+    /// Only models served by this provider, e.g. `deepseek`.
+    pub(crate) provider: Option<String>,
+    /// Only models that accept `tools`.
+    pub(crate) supports_tools: Option<bool>,
+    /// Only models whose context window is at least this many tokens.
+    pub(crate) min_context: Option<u32>,
+    /// Case-insensitive substring of the model id or description.
+    pub(crate) q: Option<String>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) offset: Option<usize>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub(crate) struct ModelArchitecture {
     pub(crate) tokenizer: String,
@@ -37,6 +72,19 @@ pub(crate) struct ModelArchitecture {
     pub(crate) modality: String,
 }
 
+/// USD per token, as decimal strings.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ModelPricingEntry {
+    pub(crate) prompt: String,
+    pub(crate) completion: String,
+}
+
+impl From<&ModelPricing> for ModelPricingEntry {
+    fn from(pricing: &ModelPricing) -> Self {
+        Self { prompt: pricing.prompt.clone(), completion: pricing.completion.clone() }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub(crate) struct ModelTopProvider {
     pub(crate) context_length: u32,
@@ -59,6 +107,35 @@ pub(crate) struct XrouterModelEntry {
     pub(crate) architecture: ModelArchitecture,
     pub(crate) top_provider: ModelTopProvider,
     pub(crate) per_request_limits: ModelPerRequestLimits,
+    /// Request parameters the model accepts, e.g. `tools`, `reasoning`, `response_format`.
+    pub(crate) supported_parameters: Vec<String>,
+    /// Omitted while the catalog carries no pricing for the model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) pricing: Option<ModelPricingEntry>,
+    /// Observed upstream behaviour over the last 24 hours; omitted until the model serves traffic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) stats: Option<ModelStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ModelStats {
+    pub(crate) requests_24h: usize,
+    /// Share of requests that failed upstream, in `0.0..=1.0`.
+    pub(crate) error_rate_24h: f64,
+    /// End-to-end latency percentiles of successful requests; `null` when none succeeded.
+    pub(crate) p50_ms: Option<u64>,
+    pub(crate) p95_ms: Option<u64>,
+}
+
+impl From<StatsSummary> for ModelStats {
+    fn from(snapshot: StatsSummary) -> Self {
+        Self {
+            requests_24h: snapshot.requests,
+            error_rate_24h: snapshot.error_rate(),
+            p50_ms: snapshot.p50_ms,
+            p95_ms: snapshot.p95_ms,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -66,9 +143,626 @@ pub(crate) struct XrouterModelsResponse {
     pub(crate) data: Vec<XrouterModelEntry>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ModelEndpointEntry {
+    pub(crate) name: String,
+    pub(crate) model_id: String,
+    pub(crate) provider_name: String,
+    pub(crate) context_length: u32,
+    pub(crate) max_completion_tokens: u32,
+    pub(crate) max_prompt_tokens: Option<u32>,
+    pub(crate) is_moderated: bool,
+    /// Per-token prices; `null` while the catalog carries no pricing for the endpoint.
+    pub(crate) pricing: Option<ModelPricingEntry>,
+    pub(crate) quantization: Option<String>,
+    /// `0` when operational, `-1` when recent uptime is below the degraded threshold.
+    pub(crate) status: i32,
+    /// Share of successful upstream requests over the last 30 minutes; `null` without traffic.
+    pub(crate) uptime_last_30m: Option<f64>,
+    pub(crate) requests_last_30m: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ModelEndpointsData {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) architecture: ModelArchitecture,
+    pub(crate) endpoints: Vec<ModelEndpointEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ModelEndpointsResponse {
+    pub(crate) data: ModelEndpointsData,
+}
+
+/// Outcome of a dry run of `/api/v1/responses`; nothing is sent to the provider.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ValidateResponse {
+    /// Public model id the request resolves to, after header overrides and unknown model handling.
+    pub(crate) model: String,
+    pub(crate) provider: String,
+    pub(crate) fallback_reason: Option<String>,
+    /// Input tokens as counted by the tokenize stage.
+    pub(crate) input_tokens: u32,
+    pub(crate) context_length: Option<u32>,
+    /// Strategy that would cut the input down to the context window, when it is over it.
+    pub(crate) truncation: Option<String>,
+    /// Catalog price of the input tokens plus `max_output_tokens`, in USD.
+    pub(crate) estimated_cost: Option<String>,
+    /// Body fields `XR_PASSTHROUGH_FIELDS` would drop.
+    pub(crate) dropped_fields: Vec<String>,
+}
+
+/// Estimated cost of a `/api/v1/responses` body on every provider serving its model.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct EstimateResponse {
+    pub(crate) model: String,
+    pub(crate) max_output_tokens: Option<u32>,
+    /// Cheapest eligible endpoint with no output, in USD; `None` unless every eligible endpoint
+    /// has one.
+    pub(crate) min_cost: Option<String>,
+    /// Most expensive eligible endpoint with its full output allowance, in USD; `None` unless
+    /// every eligible endpoint has one.
+    pub(crate) max_cost: Option<String>,
+    pub(crate) endpoints: Vec<EstimateEndpoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct EstimateEndpoint {
+    pub(crate) provider: String,
+    pub(crate) model_id: String,
+    /// Input tokens as counted by the tokenize stage of this provider.
+    pub(crate) input_tokens: Option<u32>,
+    pub(crate) context_length: Option<u32>,
+    /// Input only.
+    pub(crate) min_cost: Option<String>,
+    /// Input plus `max_output_tokens`, else the endpoint's output limit; `None` when neither is
+    /// known.
+    pub(crate) max_cost: Option<String>,
+    /// Why the endpoint cannot serve the request: `provider_disabled`, `context_window` or
+    /// `tools_unsupported`.
+    pub(crate) excluded: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct TenantUsageResponse {
+    pub(crate) tenant_id: String,
+    pub(crate) organization: String,
+    pub(crate) project: String,
+    pub(crate) requests: u64,
+    pub(crate) input_tokens: u64,
+    pub(crate) output_tokens: u64,
+    pub(crate) total_tokens: u64,
+    pub(crate) token_budget: Option<u64>,
+    pub(crate) remaining_tokens: Option<u64>,
+    /// Completed requests grouped by the `HTTP-Referer` / `X-Title` the client sent.
+    pub(crate) apps: Vec<AppUsageEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct AppUsageEntry {
+    pub(crate) referer: Option<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) requests: u64,
+    pub(crate) input_tokens: u64,
+    pub(crate) output_tokens: u64,
+    pub(crate) total_tokens: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ApiKeyEntry {
+    pub(crate) id: String,
+    pub(crate) label: String,
+    pub(crate) organization: String,
+    pub(crate) project: String,
+    pub(crate) allowed_models: Vec<String>,
+    pub(crate) denied_models: Vec<String>,
+    pub(crate) expires_at: Option<u64>,
+    pub(crate) disabled: bool,
+    pub(crate) coalescing_opt_out: bool,
+    pub(crate) priority: Option<RequestPriority>,
+    #[schema(value_type = Option<String>)]
+    pub(crate) truncation: Option<TruncationStrategy>,
+    pub(crate) hide_reasoning: bool,
+    pub(crate) created_at: u64,
+    pub(crate) rotated_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ApiKeyListResponse {
+    pub(crate) data: Vec<ApiKeyEntry>,
+}
+
+/// Fault injection policy, see `XR_CHAOS`. Probabilities are in `0.0..=1.0`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ChaosSettings {
+    /// Providers the policy applies to; empty means every provider.
+    pub(crate) providers: Vec<String>,
+    pub(crate) latency_ms: u64,
+    pub(crate) latency_probability: f64,
+    pub(crate) error_status: u16,
+    pub(crate) error_probability: f64,
+    pub(crate) disconnect_probability: f64,
+    pub(crate) malformed_frame_probability: f64,
+}
+
+impl Default for ChaosSettings {
+    fn default() -> Self {
+        ChaosPolicy::default().into()
+    }
+}
+
+impl From<ChaosPolicy> for ChaosSettings {
+    fn from(policy: ChaosPolicy) -> Self {
+        Self {
+            providers: policy.providers,
+            latency_ms: policy.latency_ms,
+            latency_probability: policy.latency_probability,
+            error_status: policy.error_status,
+            error_probability: policy.error_probability,
+            disconnect_probability: policy.disconnect_probability,
+            malformed_frame_probability: policy.malformed_frame_probability,
+        }
+    }
+}
+
+impl From<ChaosSettings> for ChaosPolicy {
+    fn from(settings: ChaosSettings) -> Self {
+        Self {
+            providers: settings.providers,
+            latency_ms: settings.latency_ms,
+            latency_probability: settings.latency_probability,
+            error_status: settings.error_status,
+            error_probability: settings.error_probability,
+            disconnect_probability: settings.disconnect_probability,
+            malformed_frame_probability: settings.malformed_frame_probability,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct StreamTranscriptEntry {
+    pub(crate) id: String,
+    pub(crate) request_id: String,
+    pub(crate) provider: String,
+    pub(crate) stream_kind: String,
+    pub(crate) recorded_at_ms: u64,
+    pub(crate) complete: bool,
+    pub(crate) truncated: bool,
+    /// Raw upstream SSE body; only returned when fetching a single transcript.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) body: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct StreamTranscriptListResponse {
+    pub(crate) data: Vec<StreamTranscriptEntry>,
+}
+
+/// Provider slot usage by priority class, see `XR_PRIORITY_SCHEDULING`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct SchedulingResponse {
+    pub(crate) data: Vec<ProviderScheduling>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ProviderScheduling {
+    pub(crate) provider: String,
+    pub(crate) capacity: usize,
+    pub(crate) in_flight: usize,
+    pub(crate) classes: Vec<PriorityClassStats>,
+}
+
+/// Counters since startup; `waiting` is the current queue length.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct PriorityClassStats {
+    pub(crate) priority: RequestPriority,
+    pub(crate) admitted: u64,
+    pub(crate) queued: u64,
+    pub(crate) rejected: u64,
+    pub(crate) waiting: usize,
+    /// Mean queue wait of admitted requests that had to queue.
+    pub(crate) avg_wait_ms: u64,
+}
+
+/// Learned rewards of every `bandit/<alias>` model, see `XR_BANDIT_MODELS`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct BanditsResponse {
+    pub(crate) data: Vec<BanditEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct BanditEntry {
+    /// Public model id, e.g. `bandit/chat`.
+    pub(crate) model: String,
+    /// `thompson` or `epsilon_greedy`.
+    #[schema(value_type = String)]
+    pub(crate) strategy: BanditStrategy,
+    /// `success`, `latency` or `cost`.
+    #[schema(value_type = String)]
+    pub(crate) reward: BanditReward,
+    pub(crate) arms: Vec<BanditArmEntry>,
+}
+
+/// Requests served by one model of a bandit and their mean reward, between 0 and 1.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct BanditArmEntry {
+    pub(crate) model: String,
+    pub(crate) pulls: u64,
+    /// Unset until the model has served a request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) mean_reward: Option<f64>,
+}
+
+/// Rolling upstream stats of every provider, for triage.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ProviderHealthResponse {
+    pub(crate) data: Vec<ProviderHealthEntry>,
+    /// Engine executions streaming a response right now, across providers; a generation is
+    /// cancelled when its client disconnects.
+    pub(crate) tasks_in_flight: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ProviderHealthEntry {
+    pub(crate) provider: String,
+    /// `down` after 5 provider failures in a row, else `degraded` below 95% success over the
+    /// shortest window with traffic, `healthy` otherwise and `idle` without traffic.
+    #[schema(value_type = String)]
+    pub(crate) status: ProviderStatus,
+    pub(crate) in_flight: usize,
+    pub(crate) consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) last_error: Option<ProviderLastError>,
+    /// `5m`, `1h` and `24h`, in that order.
+    pub(crate) windows: Vec<ProviderHealthWindow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ProviderLastError {
+    pub(crate) message: String,
+    /// Unix seconds.
+    pub(crate) at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ProviderHealthWindow {
+    pub(crate) window: String,
+    pub(crate) requests: usize,
+    pub(crate) failures: usize,
+    /// Unset without requests in the window.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) success_percent: Option<f64>,
+    /// Latency percentiles over successful requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) p50_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) p95_ms: Option<u64>,
+}
+
+impl From<ProviderStatsSnapshot> for ProviderHealthEntry {
+    fn from(snapshot: ProviderStatsSnapshot) -> Self {
+        Self {
+            provider: snapshot.provider,
+            status: snapshot.status,
+            in_flight: snapshot.in_flight,
+            consecutive_failures: snapshot.consecutive_failures,
+            last_error: snapshot
+                .last_error
+                .map(|error| ProviderLastError { message: error.message, at: error.at }),
+            windows: snapshot
+                .windows
+                .into_iter()
+                .map(|window| ProviderHealthWindow {
+                    window: window.window.to_string(),
+                    requests: window.requests,
+                    failures: window.failures,
+                    success_percent: window.success_percent,
+                    p50_ms: window.p50_ms,
+                    p95_ms: window.p95_ms,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<BanditSnapshot> for BanditEntry {
+    fn from(snapshot: BanditSnapshot) -> Self {
+        Self {
+            model: format!("{}/{}", crate::bandit::BANDIT_ENGINE, snapshot.alias),
+            strategy: snapshot.strategy,
+            reward: snapshot.reward,
+            arms: snapshot.arms.into_iter().map(BanditArmEntry::from).collect(),
+        }
+    }
+}
+
+impl From<ArmSnapshot> for BanditArmEntry {
+    fn from(snapshot: ArmSnapshot) -> Self {
+        Self { model: snapshot.model, pulls: snapshot.pulls, mean_reward: snapshot.mean_reward }
+    }
+}
+
+impl From<GateSnapshot> for ProviderScheduling {
+    fn from(snapshot: GateSnapshot) -> Self {
+        Self {
+            provider: snapshot.provider,
+            capacity: snapshot.capacity,
+            in_flight: snapshot.in_flight,
+            classes: snapshot.classes.into_iter().map(PriorityClassStats::from).collect(),
+        }
+    }
+}
+
+impl From<ClassSnapshot> for PriorityClassStats {
+    fn from(snapshot: ClassSnapshot) -> Self {
+        Self {
+            priority: snapshot.priority,
+            admitted: snapshot.admitted,
+            queued: snapshot.queued,
+            rejected: snapshot.rejected,
+            waiting: snapshot.waiting,
+            avg_wait_ms: snapshot.avg_wait_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct NormalizePreviewQuery {
+    /// Comma-separated providers to preview; defaults to the provider the model routes to.
+    pub(crate) providers: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct NormalizePreviewResponse {
+    pub(crate) model: String,
+    pub(crate) candidates: Vec<NormalizePreviewCandidate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct NormalizePreviewCandidate {
+    pub(crate) provider: String,
+    /// Path the payload would be posted to, relative to the provider base URL.
+    pub(crate) endpoint: Option<String>,
+    /// Upstream request body; `null` when the provider rejected the request.
+    pub(crate) payload: Option<Value>,
+    pub(crate) normalization: Option<PayloadNormalization>,
+    /// Unknown request fields the provider's passthrough allowlist drops.
+    pub(crate) dropped_fields: Vec<String>,
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct PayloadNormalization {
+    pub(crate) tools_in: usize,
+    pub(crate) tools_out: usize,
+    pub(crate) tools_dropped: usize,
+    pub(crate) dropped_tool_types: Vec<String>,
+    pub(crate) tool_choice_in: String,
+    pub(crate) tool_choice_out: String,
+}
+
+impl From<ToolNormalization> for PayloadNormalization {
+    fn from(normalization: ToolNormalization) -> Self {
+        Self {
+            tools_in: normalization.tools_in,
+            tools_out: normalization.tools_out,
+            tools_dropped: normalization.tools_dropped,
+            dropped_tool_types: normalization.dropped_tool_types,
+            tool_choice_in: normalization.tool_choice_in,
+            tool_choice_out: normalization.tool_choice_out,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct IssuedApiKeyResponse {
+    pub(crate) key: String,
+    pub(crate) api_key: ApiKeyEntry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct CreateApiKeyRequest {
+    #[serde(default)]
+    pub(crate) label: String,
+    pub(crate) organization: String,
+    pub(crate) project: String,
+    #[serde(default)]
+    pub(crate) allowed_models: Vec<String>,
+    #[serde(default)]
+    pub(crate) denied_models: Vec<String>,
+    #[serde(default)]
+    pub(crate) expires_at: Option<u64>,
+    #[serde(default)]
+    pub(crate) coalescing_opt_out: bool,
+    #[serde(default)]
+    pub(crate) priority: Option<RequestPriority>,
+    /// `off`, `drop_oldest`, `middle_out` or `summarize`.
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub(crate) truncation: Option<TruncationStrategy>,
+    #[serde(default)]
+    pub(crate) hide_reasoning: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct UpdateApiKeyRequest {
+    #[serde(default)]
+    pub(crate) label: Option<String>,
+    #[serde(default)]
+    pub(crate) allowed_models: Option<Vec<String>>,
+    #[serde(default)]
+    pub(crate) denied_models: Option<Vec<String>>,
+    #[serde(default)]
+    pub(crate) expires_at: Option<u64>,
+    #[serde(default)]
+    pub(crate) disabled: Option<bool>,
+    #[serde(default)]
+    pub(crate) coalescing_opt_out: Option<bool>,
+    #[serde(default)]
+    pub(crate) priority: Option<RequestPriority>,
+    /// `off`, `drop_oldest`, `middle_out` or `summarize`.
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub(crate) truncation: Option<TruncationStrategy>,
+    #[serde(default)]
+    pub(crate) hide_reasoning: Option<bool>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub(crate) struct ErrorResponse {
     pub(crate) error: String,
+    /// Request field the error refers to, e.g. `tools[0].function.name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) param: Option<String>,
+}
+
+/// Error statuses of the inference routes; every body is an [`ErrorResponse`].
+#[allow(dead_code)]
+#[derive(IntoResponses)]
+pub(crate) enum InferenceErrorResponses {
+    #[response(
+        status = 400,
+        description = "Malformed JSON body, validation or provider error, or a malformed \
+            `Idempotency-Key`"
+    )]
+    BadRequest(ErrorResponse),
+    #[response(status = 401, description = "Missing or invalid tenant API key")]
+    Unauthorized(ErrorResponse),
+    #[response(
+        status = 402,
+        description = "Tenant token budget or enforced conversation token budget exhausted"
+    )]
+    PaymentRequired(ErrorResponse),
+    #[response(status = 403, description = "Model not allowed for tenant")]
+    Forbidden(ErrorResponse),
+    #[response(status = 409, description = "A request with this `Idempotency-Key` is in progress")]
+    Conflict(ErrorResponse),
+    #[response(status = 413, description = "Request body over the configured size limit")]
+    PayloadTooLarge(ErrorResponse),
+    #[response(status = 422, description = "An `Idempotency-Key` reused with another body")]
+    UnprocessableEntity(ErrorResponse),
+    #[response(
+        status = 429,
+        description = "Per-user or per-tenant rate limit exceeded, or the provider is overloaded"
+    )]
+    TooManyRequests(ErrorResponse),
+    #[response(status = 502, description = "A shared or replayed upstream response was unreadable")]
+    BadGateway(ErrorResponse),
+    #[response(status = 503, description = "Instance runs in catalog-only mode")]
+    ServiceUnavailable(ErrorResponse),
+}
+
+/// `data:` payload of a streamed Responses API request; the SSE `event:` name equals `type`.
+/// Every payload also carries `sequence_number`, counting the events of the stream from 0.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "type")]
+pub(crate) enum ResponseStreamEvent {
+    #[serde(rename = "response.created")]
+    Created { response: StreamedResponse },
+    #[serde(rename = "response.output_item.added")]
+    OutputItemAdded {
+        output_index: u32,
+        #[schema(value_type = Object)]
+        item: Value,
+    },
+    #[serde(rename = "response.content_part.added")]
+    ContentPartAdded {
+        output_index: u32,
+        item_id: String,
+        content_index: u32,
+        #[schema(value_type = Object)]
+        part: Value,
+    },
+    #[serde(rename = "response.output_text.delta")]
+    OutputTextDelta { output_index: u32, item_id: String, content_index: u32, delta: String },
+    #[serde(rename = "response.reasoning.delta")]
+    ReasoningDelta { delta: String },
+    #[serde(rename = "response.output_item.done")]
+    OutputItemDone { output_index: u32, item: ResponseOutputItem },
+    #[serde(rename = "response.completed")]
+    Completed { response: StreamedResponse },
+    /// Ends a stream that failed after it started.
+    #[serde(rename = "response.error")]
+    Error { error: String },
+}
+
+/// Response snapshot of `response.created` (`in_progress`, no output yet) and
+/// `response.completed`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub(crate) struct StreamedResponse {
+    pub(crate) id: String,
+    pub(crate) status: String,
+    pub(crate) model: Option<String>,
+    pub(crate) output: Vec<ResponseOutputItem>,
+    pub(crate) finish_reason: Option<String>,
+    pub(crate) usage: Option<Usage>,
+    pub(crate) meta: Option<ResponseMeta>,
+}
+
+/// `data:` payload of a streamed Chat Completions request; the stream ends with `data: [DONE]`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub(crate) struct ChatCompletionChunk {
+    pub(crate) id: String,
+    /// Always `chat.completion.chunk`; absent on error chunks.
+    pub(crate) object: Option<String>,
+    pub(crate) choices: Option<Vec<ChatCompletionChunkChoice>>,
+    /// Token usage, on the final chunk only.
+    pub(crate) usage: Option<Usage>,
+    /// Routing metadata, on the final chunk only.
+    pub(crate) meta: Option<ResponseMeta>,
+    /// Set instead of `choices` when the stream failed after it started.
+    pub(crate) error: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub(crate) struct ChatCompletionChunkChoice {
+    pub(crate) index: u32,
+    pub(crate) delta: ChatCompletionChunkDelta,
+    /// Set on the final chunk.
+    pub(crate) finish_reason: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub(crate) struct ChatCompletionChunkDelta {
+    /// `assistant`, on the first chunk only.
+    pub(crate) role: Option<String>,
+    pub(crate) content: Option<String>,
+    pub(crate) reasoning_content: Option<String>,
+    #[schema(value_type = Option<Vec<Object>>)]
+    pub(crate) tool_calls: Option<Vec<Value>>,
+}
+
+/// Registers the bearer schemes: tenant API keys (`api_key`, optional unless tenancy is on) and
+/// the admin token (`admin_token`).
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        for (name, description) in [
+            ("api_key", "Tenant API key, or the provider key when BYOK is enabled"),
+            ("admin_token", "Value of `XR_ADMIN_TOKEN`"),
+        ] {
+            components.add_security_scheme(
+                name,
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .description(Some(description))
+                        .build(),
+                ),
+            );
+        }
+    }
 }
 
 #[derive(OpenApi)]
@@ -76,8 +770,13 @@ pub(crate) struct ErrorResponse {
     paths(
         crate::http::routes::basic::get_health,
         crate::http::routes::basic::get_xrouter_models,
+        crate::http::routes::basic::get_model_endpoints,
+        crate::http::routes::usage::get_usage,
+        crate::http::routes::validate::post_validate,
+        crate::http::routes::estimate::post_estimate,
         crate::http::routes::inference::post_responses,
-        crate::http::routes::inference::post_chat_completions
+        crate::http::routes::inference::post_chat_completions,
+        crate::http::routes::completions::post_completions
     ),
     components(
         schemas(
@@ -85,15 +784,35 @@ pub(crate) struct ErrorResponse {
             ErrorResponse,
             ModelArchitecture,
             ModelTopProvider,
+            ModelPricingEntry,
             ModelPerRequestLimits,
             XrouterModelEntry,
+            ModelStats,
             XrouterModelsResponse,
+            ModelEndpointEntry,
+            ModelEndpointsData,
+            ModelEndpointsResponse,
+            TenantUsageResponse,
+            AppUsageEntry,
+            ValidateResponse,
+            EstimateResponse,
+            EstimateEndpoint,
             ResponsesRequest,
             ResponsesResponse,
             ChatCompletionsRequest,
-            ChatCompletionsResponse
+            ChatCompletionsResponse,
+            ResponseStreamEvent,
+            StreamedResponse,
+            ChatCompletionChunk,
+            ChatCompletionChunkChoice,
+            ChatCompletionChunkDelta,
+            CompletionsRequest,
+            CompletionPrompt,
+            CompletionsResponse,
+            CompletionChoice
         )
     ),
+    modifiers(&SecuritySchemes),
     tags(
         (name = "xrouter-app", description = "xrouter application API")
     )
@@ -106,7 +825,8 @@ struct XrouterApiDoc;
         crate::http::routes::basic::get_health,
         crate::http::routes::basic::get_compatible_models,
         post_responses_openai_doc,
-        post_chat_completions_openai_doc
+        post_chat_completions_openai_doc,
+        post_completions_openai_doc
     ),
     components(
         schemas(
@@ -117,44 +837,232 @@ struct XrouterApiDoc;
             ResponsesRequest,
             ResponsesResponse,
             ChatCompletionsRequest,
-            ChatCompletionsResponse
+            ChatCompletionsResponse,
+            ResponseStreamEvent,
+            StreamedResponse,
+            ChatCompletionChunk,
+            ChatCompletionChunkChoice,
+            ChatCompletionChunkDelta,
+            CompletionsRequest,
+            CompletionPrompt,
+            CompletionsResponse,
+            CompletionChoice
         )
     ),
+    modifiers(&SecuritySchemes),
     tags(
         (name = "xrouter-app", description = "xrouter application API")
     )
 )]
 struct OpenAiApiDoc;
 
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        crate::http::routes::admin_keys::list_keys,
+        crate::http::routes::admin_keys::create_key,
+        crate::http::routes::admin_keys::get_key,
+        crate::http::routes::admin_keys::update_key,
+        crate::http::routes::admin_keys::rotate_key,
+        crate::http::routes::admin_keys::delete_key,
+        crate::http::routes::admin_providers::probe_provider,
+        crate::http::routes::admin_transcripts::list_transcripts,
+        crate::http::routes::admin_transcripts::get_transcript,
+        crate::http::routes::admin_chaos::get_chaos,
+        crate::http::routes::admin_chaos::put_chaos,
+        crate::http::routes::admin_scheduling::get_scheduling,
+        crate::http::routes::admin_bandits::get_bandits,
+        crate::http::routes::admin_health::get_provider_health,
+        crate::http::routes::admin_normalize::preview_normalization
+    ),
+    components(
+        schemas(
+            ErrorResponse,
+            ApiKeyEntry,
+            ApiKeyListResponse,
+            IssuedApiKeyResponse,
+            CreateApiKeyRequest,
+            UpdateApiKeyRequest,
+            crate::ProbeReport,
+            StreamTranscriptEntry,
+            StreamTranscriptListResponse,
+            ChaosSettings,
+            RequestPriority,
+            SchedulingResponse,
+            ProviderScheduling,
+            PriorityClassStats,
+            BanditsResponse,
+            BanditEntry,
+            BanditArmEntry,
+            ProviderHealthResponse,
+            ProviderHealthEntry,
+            ProviderLastError,
+            ProviderHealthWindow,
+            NormalizePreviewResponse,
+            NormalizePreviewCandidate,
+            PayloadNormalization
+        )
+    ),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "xrouter-admin", description = "xrouter administration API")
+    )
+)]
+struct AdminApiDoc;
+
+#[cfg(feature = "docs")]
+fn with_docs(router: Router, openapi: utoipa::openapi::OpenApi) -> Router {
+    router.merge(SwaggerUi::new("/docs").url("/openapi.json", openapi))
+}
+
+/// Without the `docs` feature there is no Swagger UI and no `/openapi.json`.
+#[cfg(not(feature = "docs"))]
+fn with_docs(router: Router, _openapi: utoipa::openapi::OpenApi) -> Router {
+    router
+}
+
+fn admin_router() -> Router<AppState> {
+    use crate::http::routes::{
+        admin_bandits, admin_chaos, admin_health, admin_keys, admin_normalize, admin_providers,
+        admin_scheduling, admin_transcripts,
+    };
+
+    Router::new()
+        .route("/admin/v1/keys", get(admin_keys::list_keys).post(admin_keys::create_key))
+        .route(
+            "/admin/v1/keys/{id}",
+            get(admin_keys::get_key).patch(admin_keys::update_key).delete(admin_keys::delete_key),
+        )
+        .route("/admin/v1/keys/{id}/rotate", post(admin_keys::rotate_key))
+        .route("/admin/v1/providers/{name}/probe", post(admin_providers::probe_provider))
+        .route("/admin/v1/chaos", get(admin_chaos::get_chaos).put(admin_chaos::put_chaos))
+        .route("/admin/v1/scheduling", get(admin_scheduling::get_scheduling))
+        .route("/admin/v1/bandits", get(admin_bandits::get_bandits))
+        .route("/admin/v1/health/providers", get(admin_health::get_provider_health))
+        .route("/admin/v1/transcripts", get(admin_transcripts::list_transcripts))
+        .route("/admin/v1/transcripts/{id}", get(admin_transcripts::get_transcript))
+        .route("/api/v1/debug/normalize", post(admin_normalize::preview_normalization))
+}
+
 pub fn build_router(state: AppState) -> Router {
+    use crate::http::routes::{completions, inference};
+
     let openai_compatible_api = state.openai_compatible_api;
-    let (router, openapi) = if openai_compatible_api {
+    // Callers are authenticated once, before replays, coalescing and the handlers run.
+    let authenticated =
+        || middleware::from_fn_with_state(state.clone(), crate::http::auth::authenticate_requests);
+    let (responses, chat_completions, text_completions) = if state.catalog_only {
         (
-            Router::new()
-                .route("/health", get(crate::http::routes::basic::get_health))
-                .route("/v1/models", get(crate::http::routes::basic::get_compatible_models))
-                .route("/v1/responses", post(crate::http::routes::inference::post_responses))
-                .route(
-                    "/v1/chat/completions",
-                    post(crate::http::routes::inference::post_chat_completions),
-                ),
-            OpenAiApiDoc::openapi(),
+            post(inference::reject_catalog_only),
+            post(inference::reject_catalog_only),
+            post(inference::reject_catalog_only),
         )
     } else {
+        let idempotent = || {
+            middleware::from_fn_with_state(
+                state.clone(),
+                crate::http::idempotency::idempotent_requests,
+            )
+        };
+        let coalesced = || {
+            middleware::from_fn_with_state(
+                state.clone(),
+                crate::http::coalescing::coalesce_identical_requests,
+            )
+        };
+        let timeouts = || {
+            middleware::from_fn_with_state(
+                state.overload.clone(),
+                crate::http::overload::enforce_timeouts,
+            )
+        };
+        // Replays of stored responses are answered before identical requests are coalesced.
+        let hops =
+            || middleware::from_fn_with_state(state.max_hops, crate::http::hops::reject_hop_loops);
         (
-            Router::new()
-                .route("/health", get(crate::http::routes::basic::get_health))
-                .route("/api/v1/models", get(crate::http::routes::basic::get_xrouter_models))
-                .route("/api/v1/responses", post(crate::http::routes::inference::post_responses))
-                .route(
-                    "/api/v1/chat/completions",
-                    post(crate::http::routes::inference::post_chat_completions),
-                ),
-            XrouterApiDoc::openapi(),
+            post(inference::post_responses)
+                .layer(coalesced())
+                .layer(idempotent())
+                .layer(authenticated())
+                .layer(timeouts())
+                .layer(hops()),
+            post(inference::post_chat_completions)
+                .layer(coalesced())
+                .layer(idempotent())
+                .layer(authenticated())
+                .layer(timeouts())
+                .layer(hops()),
+            post(completions::post_completions)
+                .layer(coalesced())
+                .layer(idempotent())
+                .layer(authenticated())
+                .layer(timeouts())
+                .layer(hops()),
         )
     };
+    // Every API version is served by the same handlers; see `http::versioning`.
+    let versioned = |prefix: &str| {
+        let router = Router::new()
+            .route(&format!("{prefix}/responses"), responses.clone())
+            .route(&format!("{prefix}/chat/completions"), chat_completions.clone())
+            .route(&format!("{prefix}/completions"), text_completions.clone());
+        if openai_compatible_api {
+            router.route(
+                &format!("{prefix}/models"),
+                get(crate::http::routes::basic::get_compatible_models),
+            )
+        } else {
+            router
+                .route(
+                    &format!("{prefix}/models"),
+                    get(crate::http::routes::basic::get_xrouter_models),
+                )
+                .route(
+                    &format!("{prefix}/models/{{*model_path}}"),
+                    get(crate::http::routes::basic::get_model_endpoints),
+                )
+                .route(&format!("{prefix}/usage"), get(crate::http::routes::usage::get_usage))
+                .route(
+                    &format!("{prefix}/validate"),
+                    post(crate::http::routes::validate::post_validate).layer(authenticated()),
+                )
+                .route(
+                    &format!("{prefix}/estimate"),
+                    post(crate::http::routes::estimate::post_estimate).layer(authenticated()),
+                )
+        }
+    };
+    let (prefixes, mut openapi) = if openai_compatible_api {
+        (["/v1", "/v2"], OpenAiApiDoc::openapi())
+    } else {
+        (["/api/v1", "/api/v2"], XrouterApiDoc::openapi())
+    };
+    let router = prefixes
+        .into_iter()
+        .fold(Router::new(), |router, prefix| router.merge(versioned(prefix)))
+        .route("/health", get(crate::http::routes::basic::get_health));
 
-    router.with_state(state).merge(SwaggerUi::new("/docs").url("/openapi.json", openapi))
+    openapi.merge(AdminApiDoc::openapi());
+
+    let body_limit = state.max_request_body_bytes;
+    let overload = state.overload.clone();
+    let cors = crate::http::cors::cors_layer(&state.cors);
+    let router = router
+        .merge(admin_router())
+        .layer(middleware::from_fn_with_state(
+            body_limit,
+            crate::http::body_limit::reject_oversized_body,
+        ))
+        .layer(DefaultBodyLimit::max(body_limit))
+        .layer(middleware::from_fn_with_state(overload, crate::http::overload::shed_load))
+        .layer(middleware::from_fn(crate::http::versioning::serialize_for_version))
+        .with_state(state);
+    let router = with_docs(router, openapi);
+    // Outermost, so preflights are answered before routing, auth or body checks.
+    match cors {
+        Some(cors) => router.layer(cors),
+        None => router,
+    }
 }
 
 #[allow(dead_code)]
@@ -163,9 +1071,18 @@ pub fn build_router(state: AppState) -> Router {
     path = "/v1/responses",
     request_body = ResponsesRequest,
     responses(
-        (status = 200, description = "Responses API result", body = ResponsesResponse),
-        (status = 400, description = "Validation or provider error", body = ErrorResponse)
+        (
+            status = 200,
+            description = "Responses API result; with `stream: true`, server-sent events whose \
+                `event:` name equals the payload `type`",
+            content(
+                (ResponsesResponse = "application/json"),
+                (ResponseStreamEvent = "text/event-stream")
+            )
+        ),
+        InferenceErrorResponses
     ),
+    security(("api_key" = []), ()),
     tag = "xrouter-app"
 )]
 fn post_responses_openai_doc() {}
@@ -176,9 +1093,40 @@ fn post_responses_openai_doc() {}
     path = "/v1/chat/completions",
     request_body = ChatCompletionsRequest,
     responses(
-        (status = 200, description = "Chat Completions API result", body = ChatCompletionsResponse),
-        (status = 400, description = "Validation or provider error", body = ErrorResponse)
+        (
+            status = 200,
+            description = "Chat Completions API result; with `stream: true`, server-sent \
+                `chat.completion.chunk` events ending with `data: [DONE]`",
+            content(
+                (ChatCompletionsResponse = "application/json"),
+                (ChatCompletionChunk = "text/event-stream")
+            )
+        ),
+        InferenceErrorResponses
     ),
+    security(("api_key" = []), ()),
     tag = "xrouter-app"
 )]
 fn post_chat_completions_openai_doc() {}
+
+#[allow(dead_code)]
+#[utoipa::path(
+    post,
+    path = "/v1/completions",
+    request_body = CompletionsRequest,
+    responses(
+        (
+            status = 200,
+            description = "Legacy text completion, served as a one-message chat completion; with \
+                `stream: true`, server-sent `text_completion` chunks ending with `data: [DONE]`",
+            content(
+                (CompletionsResponse = "application/json"),
+                (CompletionsResponse = "text/event-stream")
+            )
+        ),
+        InferenceErrorResponses
+    ),
+    security(("api_key" = []), ()),
+    tag = "xrouter-app"
+)]
+fn post_completions_openai_doc() {}
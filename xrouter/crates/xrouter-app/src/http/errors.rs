@@ -15,14 +15,18 @@ pub(crate) fn error_response(err: CoreError) -> Response {
         _ => axum::http::StatusCode::BAD_REQUEST,
     };
     match &err {
-        CoreError::Validation(_) | CoreError::Provider(_) => {
+        CoreError::Validation(_) | CoreError::InvalidParam { .. } | CoreError::Provider(_) => {
             warn!(event = "http.error_response", error = %err);
         }
-        CoreError::ClientDisconnected(_) => {
+        CoreError::ClientDisconnected { .. } => {
             error!(event = "http.error_response", error = %err);
         }
     }
-    (status, Json(ErrorResponse { error: err.to_string() })).into_response()
+    let param = match &err {
+        CoreError::InvalidParam { param, .. } => Some(param.clone()),
+        _ => None,
+    };
+    (status, Json(ErrorResponse { error: err.to_string(), param })).into_response()
 }
 
 fn is_provider_overloaded(message: &str) -> bool {
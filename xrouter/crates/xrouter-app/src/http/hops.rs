@@ -0,0 +1,101 @@
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::http::docs::ErrorResponse;
+
+/// Routers a request has already passed; each router sends one more to a downstream xrouter.
+pub(crate) const HOP_HEADER: &str = "x-xrouter-hop";
+
+/// W3C trace context; with the `otel` feature the provider clients inject it from the request
+/// span instead.
+#[cfg(not(feature = "otel"))]
+const TRACE_CONTEXT_HEADERS: [&str; 2] = ["traceparent", "tracestate"];
+
+fn parse_hop(headers: &HeaderMap) -> Result<u32, ()> {
+    match headers.get(HOP_HEADER) {
+        None => Ok(0),
+        Some(value) => value.to_str().map_err(drop)?.trim().parse().map_err(drop),
+    }
+}
+
+/// Refuses requests that already passed `max_hops` routers (`508`), so misconfigured tiers
+/// that point back at each other fail fast instead of looping.
+pub(crate) async fn reject_hop_loops(
+    State(max_hops): State<u32>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request.uri().path().to_string();
+    let (status, error) = match parse_hop(request.headers()) {
+        Ok(hop) if hop <= max_hops => return next.run(request).await,
+        Ok(hop) => {
+            warn!(
+                event = "http.request.hop_limit_exceeded",
+                route = %route,
+                hop = hop,
+                max_hops = max_hops
+            );
+            (
+                StatusCode::LOOP_DETECTED,
+                format!("request already passed {hop} routers, over the limit of {max_hops}"),
+            )
+        }
+        Err(()) => {
+            (StatusCode::BAD_REQUEST, format!("{HOP_HEADER} must be a non-negative integer"))
+        }
+    };
+    (status, Json(ErrorResponse { error, param: None })).into_response()
+}
+
+/// Headers for a request sent on to a downstream xrouter: the next hop count and, without
+/// OpenTelemetry, the caller's trace context as received.
+pub(crate) fn downstream_router_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    let hop = parse_hop(headers).unwrap_or_default().saturating_add(1);
+    std::iter::once((HOP_HEADER.to_string(), hop.to_string()))
+        .chain(trace_context_headers(headers))
+        .collect()
+}
+
+#[cfg(not(feature = "otel"))]
+fn trace_context_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    TRACE_CONTEXT_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let value = headers.get(*name)?.to_str().ok()?;
+            Some(((*name).to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(feature = "otel")]
+fn trace_context_headers(_headers: &HeaderMap) -> Vec<(String, String)> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn downstream_router_headers_count_the_next_hop() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(downstream_router_headers(&headers)[0], (HOP_HEADER.to_string(), "1".into()));
+        headers.insert(HOP_HEADER, HeaderValue::from_static("2"));
+        headers.insert(
+            "traceparent",
+            HeaderValue::from_static("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+        );
+        let forwarded = downstream_router_headers(&headers);
+        assert_eq!(forwarded[0], (HOP_HEADER.to_string(), "3".to_string()));
+        let forwards_trace = forwarded.iter().any(|(name, _)| name == "traceparent");
+        assert_eq!(forwards_trace, cfg!(not(feature = "otel")));
+    }
+}
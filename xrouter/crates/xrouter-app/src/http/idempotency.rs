@@ -0,0 +1,362 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Json,
+    body::{Body, Bytes, to_bytes},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::AUTHORIZATION},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
+
+use crate::{
+    app_state::AppState,
+    http::{body_limit::too_large, docs::ErrorResponse, rate_limit::strip_quota_headers},
+    request_context::effective_headers,
+};
+
+pub(crate) const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub(crate) const IDEMPOTENT_REPLAY_HEADER: &str = "idempotent-replayed";
+const MAX_KEY_LENGTH: usize = 255;
+const MAX_ENTRIES: usize = 10_000;
+
+/// Completed non-streaming responses by `Idempotency-Key`, scoped to the caller's credentials.
+#[derive(Debug)]
+pub(crate) struct IdempotencyCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    body_hash: [u8; 32],
+    created_at: Instant,
+    response: Option<CachedResponse>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl CachedResponse {
+    /// Reads the whole body of `response`; non-streaming bodies are already complete JSON.
+    pub(crate) async fn buffer(response: Response) -> Result<Self, axum::Error> {
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await?;
+        Ok(Self { status: parts.status, headers: parts.headers, body })
+    }
+
+    pub(crate) fn to_response(&self) -> Response {
+        (self.status, self.headers.clone(), self.body.clone()).into_response()
+    }
+
+    /// Copy of the response flagged with a `true`-valued `header`, for a request that did not
+    /// run the handler and so was not counted against any quota.
+    pub(crate) fn to_response_marked(&self, header: &'static str) -> Response {
+        let mut response = self.to_response();
+        strip_quota_headers(response.headers_mut());
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(header), HeaderValue::from_static("true"));
+        response
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Claim {
+    /// First request with this key; the caller runs it and reports back.
+    Fresh,
+    Replay(CachedResponse),
+    InProgress,
+    BodyMismatch,
+    /// The cache is full of live entries; the request runs without idempotency.
+    Untracked,
+}
+
+impl IdempotencyCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self { ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    pub(crate) fn claim(&self, key: &str, body_hash: [u8; 32]) -> Claim {
+        self.claim_at(key, body_hash, Instant::now())
+    }
+
+    fn claim_at(&self, key: &str, body_hash: [u8; 32], now: Instant) -> Claim {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        entries.retain(|_, entry| now.duration_since(entry.created_at) < self.ttl);
+        match entries.get(key) {
+            Some(entry) if entry.body_hash != body_hash => Claim::BodyMismatch,
+            Some(Entry { response: Some(response), .. }) => Claim::Replay(response.clone()),
+            Some(_) => Claim::InProgress,
+            None if entries.len() >= MAX_ENTRIES => Claim::Untracked,
+            None => {
+                entries
+                    .insert(key.to_string(), Entry { body_hash, created_at: now, response: None });
+                Claim::Fresh
+            }
+        }
+    }
+
+    pub(crate) fn complete(&self, key: &str, response: CachedResponse) {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(entry) = entries.get_mut(key) {
+            entry.response = Some(response);
+        }
+    }
+
+    /// Forgets a claim whose request failed, so a retry runs again.
+    pub(crate) fn abandon(&self, key: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if entries.get(key).is_some_and(|entry| entry.response.is_none()) {
+            entries.remove(key);
+        }
+    }
+}
+
+/// Releases a claim when the request future is dropped before it completes.
+struct ClaimGuard {
+    cache: Arc<IdempotencyCache>,
+    key: Option<String>,
+}
+
+impl ClaimGuard {
+    fn complete(mut self, response: CachedResponse) {
+        if let Some(key) = self.key.take() {
+            self.cache.complete(&key, response);
+        }
+    }
+}
+
+impl Drop for ClaimGuard {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.cache.abandon(&key);
+        }
+    }
+}
+
+/// Replays the stored response of a completed non-streaming request when a client retries with
+/// the same `Idempotency-Key` and body. Only successful responses are stored; streaming
+/// requests ignore the header.
+pub(crate) async fn idempotent_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(raw_key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match raw_key.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key.to_string(),
+        _ => {
+            return rejection(
+                StatusCode::BAD_REQUEST,
+                format!("Idempotency-Key must be 1 to {MAX_KEY_LENGTH} visible ASCII characters"),
+            );
+        }
+    };
+    let scope = credential_scope(request.headers());
+    let (parts, body) = request.into_parts();
+    let limit = state.max_request_body_bytes;
+    let Ok(body) = to_bytes(body, limit).await else {
+        return too_large(format!("request body is over the {limit}-byte limit"));
+    };
+    let request = Request::from_parts(parts, Body::from(body.clone()));
+    if is_stream_request(&body) {
+        debug!(event = "http.idempotency.ignored_stream");
+        return next.run(request).await;
+    }
+
+    let scoped_key = format!("{scope}:{key}");
+    let body_hash = request_fingerprint(&body, &effective_headers(&state, request.headers()));
+    match state.idempotency.claim(&scoped_key, body_hash) {
+        Claim::Fresh => {}
+        Claim::Replay(cached) => {
+            info!(event = "http.idempotency.replayed");
+            return cached.to_response_marked(IDEMPOTENT_REPLAY_HEADER);
+        }
+        Claim::InProgress => {
+            return rejection(
+                StatusCode::CONFLICT,
+                "a request with this Idempotency-Key is still in progress".to_string(),
+            );
+        }
+        Claim::BodyMismatch => {
+            return rejection(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used with a different request body or \
+                 x-xrouter-* overrides"
+                    .to_string(),
+            );
+        }
+        Claim::Untracked => {
+            warn!(event = "http.idempotency.cache_full", max_entries = MAX_ENTRIES);
+            return next.run(request).await;
+        }
+    }
+
+    let guard = ClaimGuard { cache: Arc::clone(&state.idempotency), key: Some(scoped_key) };
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let cached = match CachedResponse::buffer(response).await {
+        Ok(cached) => cached,
+        Err(error) => {
+            warn!(event = "http.idempotency.response_unreadable", error = %error);
+            return rejection(
+                StatusCode::BAD_GATEWAY,
+                "response body could not be read".to_string(),
+            );
+        }
+    };
+    let response = cached.to_response();
+    guard.complete(cached);
+    response
+}
+
+/// Hash of the body and the `x-xrouter-*` headers that shape the request, so a key reused
+/// with other overrides is rejected instead of replaying a response it did not ask for.
+fn request_fingerprint(body: &[u8], effective_headers: &[(&str, &[u8])]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hash_headers(effective_headers, &mut hasher);
+    hasher.update(body);
+    hasher.finalize().into()
+}
+
+/// Feeds `name:value` pairs into `hasher`, terminated so they cannot run into what follows.
+pub(crate) fn hash_headers(headers: &[(&str, &[u8])], hasher: &mut Sha256) {
+    for (name, value) in headers {
+        hasher.update(name.as_bytes());
+        hasher.update(b":");
+        hasher.update(value);
+        hasher.update([0]);
+    }
+    hasher.update([0]);
+}
+
+/// Hash of the `Authorization` header, so callers with different keys never share entries.
+pub(crate) fn credential_scope(headers: &HeaderMap) -> String {
+    let credential = headers.get(AUTHORIZATION).map(HeaderValue::as_bytes).unwrap_or_default();
+    Sha256::digest(credential).iter().take(8).map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(crate) fn is_stream_request(body: &[u8]) -> bool {
+    #[derive(serde::Deserialize)]
+    struct StreamFlag {
+        #[serde(default)]
+        stream: Option<bool>,
+    }
+    serde_json::from_slice::<StreamFlag>(body).is_ok_and(|flag| flag.stream == Some(true))
+}
+
+pub(crate) fn rejection(status: StatusCode, error: String) -> Response {
+    (status, Json(ErrorResponse { error, param: None })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use axum::{
+        body::Bytes,
+        http::{HeaderMap, HeaderValue, StatusCode, header::AUTHORIZATION},
+    };
+
+    use super::{
+        CachedResponse, Claim, IdempotencyCache, credential_scope, is_stream_request,
+        request_fingerprint,
+    };
+
+    fn cached(body: &'static str) -> CachedResponse {
+        CachedResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(body.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn claims_replay_completed_responses_until_they_expire() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        let now = Instant::now();
+        assert!(matches!(cache.claim_at("k", [1; 32], now), Claim::Fresh));
+        assert!(matches!(cache.claim_at("k", [1; 32], now), Claim::InProgress));
+        assert!(matches!(cache.claim_at("k", [2; 32], now), Claim::BodyMismatch));
+
+        cache.complete("k", cached("{}"));
+        assert!(matches!(
+            cache.claim_at("k", [1; 32], now),
+            Claim::Replay(response) if response.body == "{}"
+        ));
+        assert!(matches!(
+            cache.claim_at("k", [1; 32], now + Duration::from_secs(61)),
+            Claim::Fresh
+        ));
+    }
+
+    #[test]
+    fn abandoned_claims_let_retries_run_again() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        assert!(matches!(cache.claim("k", [1; 32]), Claim::Fresh));
+        cache.abandon("k");
+        assert!(matches!(cache.claim("k", [1; 32]), Claim::Fresh));
+
+        cache.complete("k", cached("{}"));
+        cache.abandon("k");
+        assert!(matches!(cache.claim("k", [1; 32]), Claim::Replay(_)));
+    }
+
+    #[test]
+    fn replays_drop_the_quota_headers_of_the_original_request() {
+        let mut response = cached("{}");
+        response.headers.insert("x-ratelimit-remaining-requests", HeaderValue::from_static("4"));
+        response.headers.insert("retry-after", HeaderValue::from_static("30"));
+        response.headers.insert("x-xrouter-provider", HeaderValue::from_static("deepseek"));
+
+        let replayed = response.to_response_marked(super::IDEMPOTENT_REPLAY_HEADER);
+        assert!(!replayed.headers().contains_key("x-ratelimit-remaining-requests"));
+        assert!(!replayed.headers().contains_key("retry-after"));
+        assert_eq!(replayed.headers()["x-xrouter-provider"], "deepseek");
+        assert_eq!(replayed.headers()[super::IDEMPOTENT_REPLAY_HEADER], "true");
+    }
+
+    #[test]
+    fn fingerprints_differ_per_body_and_effective_header_override() {
+        let body = br#"{"model":"m","input":"hi"}"#;
+        let fast = [("x-xrouter-provider-timeout-ms", &b"1000"[..])];
+        let slow = [("x-xrouter-provider-timeout-ms", &b"9000"[..])];
+
+        assert_eq!(request_fingerprint(body, &fast), request_fingerprint(body, &fast));
+        assert_ne!(request_fingerprint(body, &fast), request_fingerprint(body, &slow));
+        assert_ne!(request_fingerprint(body, &[]), request_fingerprint(body, &fast));
+        assert_ne!(
+            request_fingerprint(body, &fast),
+            request_fingerprint(br#"{"model":"m","input":"bye"}"#, &fast)
+        );
+    }
+
+    #[test]
+    fn scopes_differ_per_credential_and_stream_flag_is_detected() {
+        let mut alice = HeaderMap::new();
+        alice.insert(AUTHORIZATION, HeaderValue::from_static("Bearer alice"));
+        let mut bob = HeaderMap::new();
+        bob.insert(AUTHORIZATION, HeaderValue::from_static("Bearer bob"));
+        assert_ne!(credential_scope(&alice), credential_scope(&bob));
+        assert_eq!(credential_scope(&alice).len(), 16);
+
+        assert!(is_stream_request(br#"{"model":"m","stream":true}"#));
+        assert!(!is_stream_request(br#"{"model":"m","stream":false}"#));
+        assert!(!is_stream_request(b"not json"));
+    }
+}
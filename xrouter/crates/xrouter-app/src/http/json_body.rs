@@ -0,0 +1,161 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+use serde_json::error::Category;
+use tracing::info;
+
+use crate::http::docs::ErrorResponse;
+
+/// `Json` extractor whose rejections are JSON `400`s naming the offending field, e.g.
+/// ``messages[2].content: invalid type: integer `5`, expected a string``. The content type is
+/// not checked.
+pub(crate) struct JsonBody<T>(pub(crate) T);
+
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let route = request.uri().path().to_string();
+        let body =
+            Bytes::from_request(request, state).await.map_err(IntoResponse::into_response)?;
+        parse_json_body(&route, &body).map(Self).map_err(IntoResponse::into_response)
+    }
+}
+
+/// A body [`parse_json_body`] could not turn into the route's request type.
+pub(crate) struct InvalidJsonBody {
+    error: String,
+    param: Option<String>,
+}
+
+impl IntoResponse for InvalidJsonBody {
+    fn into_response(self) -> Response {
+        let body = ErrorResponse { error: self.error, param: self.param };
+        (StatusCode::BAD_REQUEST, Json(body)).into_response()
+    }
+}
+
+impl InvalidJsonBody {
+    pub(crate) fn log(&self, route: &str, body_bytes: usize) {
+        info!(
+            event = "http.request.invalid_json",
+            route = route,
+            body_bytes = body_bytes,
+            param = self.param.as_deref().unwrap_or_default(),
+            error = %self.error
+        );
+    }
+}
+
+pub(crate) fn parse_json_body<T: DeserializeOwned>(
+    route: &str,
+    body: &[u8],
+) -> Result<T, InvalidJsonBody> {
+    deserialize_json_body(body).inspect_err(|rejection| rejection.log(route, body.len()))
+}
+
+/// [`parse_json_body`] without logging the rejection.
+pub(crate) fn deserialize_json_body<T: DeserializeOwned>(
+    body: &[u8],
+) -> Result<T, InvalidJsonBody> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let parsed = serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|error| {
+            let path = error.path().to_string();
+            (path, error.into_inner())
+        })
+        .and_then(|value| deserializer.end().map(|()| value).map_err(|error| (".".into(), error)));
+    let (path, error) = match parsed {
+        Ok(value) => return Ok(value),
+        Err(failure) => failure,
+    };
+    let (error, param) = match error.classify() {
+        Category::Data if path != "." => {
+            (format!("invalid request body: {path}: {}", without_location(&error)), Some(path))
+        }
+        Category::Data => (format!("invalid request body: {}", without_location(&error)), None),
+        Category::Syntax | Category::Eof | Category::Io => {
+            (format!("request body is not valid JSON: {error}"), None)
+        }
+    };
+    Err(InvalidJsonBody { error, param })
+}
+
+/// Type errors point at a field already; the line and column only help with syntax errors.
+fn without_location(error: &serde_json::Error) -> String {
+    let message = error.to_string();
+    let location = format!(" at line {} column {}", error.line(), error.column());
+    message.strip_suffix(&location).map(ToString::to_string).unwrap_or(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::to_bytes, response::IntoResponse};
+    use serde::Deserialize;
+
+    use super::parse_json_body;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Chat {
+        messages: Vec<Message>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Message {
+        content: String,
+    }
+
+    async fn rejection(body: &str) -> serde_json::Value {
+        let response = parse_json_body::<Chat>("/test", body.as_bytes())
+            .expect_err("must reject")
+            .into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body(), usize::MAX).await.expect("body");
+        serde_json::from_slice(&body).expect("json")
+    }
+
+    #[tokio::test]
+    async fn type_errors_name_the_field_path() {
+        let payload =
+            rejection(r#"{"messages":[{"content":"a"},{"content":"b"},{"content":5}]}"#).await;
+
+        assert_eq!(
+            payload["error"],
+            "invalid request body: messages[2].content: invalid type: integer `5`, expected a string"
+        );
+        assert_eq!(payload["param"], "messages[2].content");
+    }
+
+    #[tokio::test]
+    async fn syntax_errors_keep_the_location() {
+        let payload = rejection(r#"{"messages": [}"#).await;
+
+        assert!(
+            payload["error"]
+                .as_str()
+                .expect("error")
+                .starts_with("request body is not valid JSON: expected value at line 1 column 15"),
+            "{payload}"
+        );
+        assert!(payload.get("param").is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_top_level_fields_have_no_param() {
+        let payload = rejection("{}").await;
+
+        assert_eq!(payload["error"], "invalid request body: missing field `messages`");
+        assert!(payload.get("param").is_none());
+    }
+}
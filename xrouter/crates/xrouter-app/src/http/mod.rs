@@ -1,4 +1,13 @@
 pub mod auth;
+pub(crate) mod body_limit;
+pub(crate) mod coalescing;
+pub(crate) mod cors;
 pub mod docs;
 pub mod errors;
+pub(crate) mod hops;
+pub(crate) mod idempotency;
+pub(crate) mod json_body;
+pub(crate) mod overload;
+pub(crate) mod rate_limit;
 pub mod routes;
+pub(crate) mod versioning;
@@ -0,0 +1,301 @@
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{
+        HeaderValue, StatusCode,
+        header::{CONTENT_TYPE, RETRY_AFTER},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body::{Frame, SizeHint};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::{Instant, Sleep},
+};
+use tracing::warn;
+
+use crate::{config::HttpLimitsConfig, http::docs::ErrorResponse};
+
+/// Global concurrency limit and per-route deadlines of the HTTP layer.
+#[derive(Clone)]
+pub(crate) struct OverloadGuard {
+    permits: Option<Arc<Semaphore>>,
+    max_concurrent_requests: usize,
+    request_timeout: Duration,
+    stream_timeout: Duration,
+}
+
+impl OverloadGuard {
+    pub(crate) fn new(limits: &HttpLimitsConfig) -> Self {
+        Self {
+            permits: limits.max_concurrent_requests.map(|limit| Arc::new(Semaphore::new(limit))),
+            max_concurrent_requests: limits.max_concurrent_requests.unwrap_or_default(),
+            request_timeout: Duration::from_secs(limits.request_timeout_seconds),
+            stream_timeout: Duration::from_secs(limits.stream_timeout_seconds),
+        }
+    }
+}
+
+impl Default for OverloadGuard {
+    fn default() -> Self {
+        Self::new(&HttpLimitsConfig::default())
+    }
+}
+
+/// Answers `503` instead of queueing once `max_concurrent_requests` are in flight. A request
+/// holds its slot until the response body is fully sent, so open streams count too; `/health`
+/// is never shed.
+pub(crate) async fn shed_load(
+    State(guard): State<OverloadGuard>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(permits) = guard.permits.clone() else {
+        return next.run(request).await;
+    };
+    if request.uri().path() == "/health" {
+        return next.run(request).await;
+    }
+    let Ok(permit) = permits.try_acquire_owned() else {
+        warn!(
+            event = "http.request.shed",
+            route = %request.uri().path(),
+            max_concurrent_requests = guard.max_concurrent_requests
+        );
+        let mut response = error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!(
+                "server is at its limit of {} concurrent requests, retry later",
+                guard.max_concurrent_requests
+            ),
+        );
+        response.headers_mut().insert(RETRY_AFTER, HeaderValue::from_static("1"));
+        return response;
+    };
+    let (parts, body) = next.run(request).await.into_parts();
+    let body = GuardedBody { inner: body, permit: Some(permit), deadline: None };
+    Response::from_parts(parts, Body::new(body))
+}
+
+/// Gives an inference route `request_timeout` to answer with `504` otherwise; an event stream
+/// is then cut off once `stream_timeout` has passed since the request arrived.
+pub(crate) async fn enforce_timeouts(
+    State(guard): State<OverloadGuard>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let route = request.uri().path().to_string();
+    let Ok(response) = tokio::time::timeout(guard.request_timeout, next.run(request)).await else {
+        warn!(
+            event = "http.request.timeout",
+            route = %route,
+            timeout_seconds = guard.request_timeout.as_secs()
+        );
+        return error_response(
+            StatusCode::GATEWAY_TIMEOUT,
+            format!("no response within {} seconds", guard.request_timeout.as_secs()),
+        );
+    };
+    let is_stream = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    if !is_stream {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = GuardedBody {
+        inner: body,
+        permit: None,
+        deadline: Some(StreamDeadline {
+            sleep: Box::pin(tokio::time::sleep_until(started + guard.stream_timeout)),
+            route,
+            timeout: guard.stream_timeout,
+        }),
+    };
+    Response::from_parts(parts, Body::new(body))
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(ErrorResponse { error, param: None })).into_response()
+}
+
+struct StreamDeadline {
+    sleep: Pin<Box<Sleep>>,
+    route: String,
+    timeout: Duration,
+}
+
+/// Response body that releases its concurrency slot when dropped and ends with an error once
+/// its deadline passes.
+struct GuardedBody {
+    inner: Body,
+    permit: Option<OwnedSemaphorePermit>,
+    deadline: Option<StreamDeadline>,
+}
+
+impl http_body::Body for GuardedBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        if let Some(deadline) = this.deadline.as_mut()
+            && deadline.sleep.as_mut().poll(cx).is_ready()
+        {
+            warn!(
+                event = "http.stream.timeout",
+                route = %deadline.route,
+                timeout_seconds = deadline.timeout.as_secs()
+            );
+            let error = format!("stream exceeded {} seconds", deadline.timeout.as_secs());
+            this.deadline = None;
+            this.permit = None;
+            this.inner = Body::empty();
+            return Poll::Ready(Some(Err(axum::Error::new(error))));
+        }
+        let frame = Pin::new(&mut this.inner).poll_frame(cx);
+        if let Poll::Ready(None) = frame {
+            this.permit = None;
+        }
+        frame
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Request, StatusCode, header::RETRY_AFTER},
+        middleware,
+        routing::get,
+    };
+    use futures::StreamExt;
+    use tower::ServiceExt;
+
+    use super::{OverloadGuard, enforce_timeouts, shed_load};
+    use crate::config::HttpLimitsConfig;
+
+    fn guard(
+        max_concurrent_requests: Option<usize>,
+        request_timeout_seconds: u64,
+    ) -> OverloadGuard {
+        OverloadGuard::new(&HttpLimitsConfig {
+            max_concurrent_requests,
+            request_timeout_seconds,
+            stream_timeout_seconds: 1,
+        })
+    }
+
+    #[tokio::test]
+    async fn requests_over_the_concurrency_limit_are_shed_with_503() {
+        let guard = guard(Some(1), 60);
+        let router = Router::new()
+            .route("/slow", get(|| async { "done" }))
+            .route("/health", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(guard.clone(), shed_load));
+
+        // The first response holds its slot until its body is consumed.
+        let held = router
+            .clone()
+            .oneshot(Request::get("/slow").body(Body::empty()).expect("request"))
+            .await
+            .expect("response");
+        assert_eq!(held.status(), StatusCode::OK);
+
+        let shed = router
+            .clone()
+            .oneshot(Request::get("/slow").body(Body::empty()).expect("request"))
+            .await
+            .expect("response");
+        assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(shed.headers().get(RETRY_AFTER).expect("retry-after"), "1");
+        let body = to_bytes(shed.into_body(), usize::MAX).await.expect("body");
+        let payload: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        assert!(payload["error"].as_str().expect("error").contains("1 concurrent requests"));
+
+        let health = router
+            .clone()
+            .oneshot(Request::get("/health").body(Body::empty()).expect("request"))
+            .await
+            .expect("response");
+        assert_eq!(health.status(), StatusCode::OK);
+
+        to_bytes(held.into_body(), usize::MAX).await.expect("body");
+        let next = router
+            .oneshot(Request::get("/slow").body(Body::empty()).expect("request"))
+            .await
+            .expect("response");
+        assert_eq!(next.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn slow_handlers_are_answered_with_504() {
+        let router = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "done"
+                }),
+            )
+            .layer(middleware::from_fn_with_state(guard(None, 1), enforce_timeouts));
+
+        let response = router
+            .oneshot(Request::get("/slow").body(Body::empty()).expect("request"))
+            .await
+            .expect("response");
+
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn event_streams_are_cut_off_after_the_stream_timeout() {
+        let router = Router::new()
+            .route(
+                "/stream",
+                get(|| async {
+                    let events = futures::stream::once(async {
+                        Ok::<_, std::convert::Infallible>("data: first\n\n")
+                    })
+                    .chain(futures::stream::pending());
+                    ([("content-type", "text/event-stream")], Body::from_stream(events))
+                }),
+            )
+            .layer(middleware::from_fn_with_state(guard(None, 60), enforce_timeouts));
+
+        let response = router
+            .oneshot(Request::get("/stream").body(Body::empty()).expect("request"))
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let error = to_bytes(response.into_body(), usize::MAX).await.expect_err("cut off");
+        assert!(error.to_string().contains("stream exceeded 1 seconds"), "{error}");
+    }
+}
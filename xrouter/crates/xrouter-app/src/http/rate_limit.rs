@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Json,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::RETRY_AFTER},
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::{coordination::RedisCoordinator, http::docs::ErrorResponse};
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+const LIMIT_REQUESTS: HeaderName = HeaderName::from_static("x-ratelimit-limit-requests");
+const REMAINING_REQUESTS: HeaderName = HeaderName::from_static("x-ratelimit-remaining-requests");
+const RESET_REQUESTS: HeaderName = HeaderName::from_static("x-ratelimit-reset-requests");
+const LIMIT_TOKENS: HeaderName = HeaderName::from_static("x-ratelimit-limit-tokens");
+const REMAINING_TOKENS: HeaderName = HeaderName::from_static("x-ratelimit-remaining-tokens");
+/// Everything [`QuotaReport::apply`] may set.
+pub(crate) const QUOTA_HEADERS: [HeaderName; 6] = [
+    LIMIT_REQUESTS,
+    REMAINING_REQUESTS,
+    RESET_REQUESTS,
+    LIMIT_TOKENS,
+    REMAINING_TOKENS,
+    RETRY_AFTER,
+];
+
+/// Standing of a key in its current window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WindowQuota {
+    pub(crate) limit: u64,
+    pub(crate) remaining: u64,
+    pub(crate) reset_after: Duration,
+}
+
+#[derive(Debug)]
+pub(crate) struct FixedWindowRateLimiter {
+    limit_per_window: usize,
+    window: Duration,
+    counters: Mutex<HashMap<String, (Instant, usize)>>,
+    /// Shares the counters with other replicas; the local counters only serve while Redis is
+    /// unreachable.
+    shared: Option<(Arc<RedisCoordinator>, &'static str)>,
+}
+
+impl FixedWindowRateLimiter {
+    pub(crate) fn per_minute(limit_per_window: usize) -> Self {
+        Self {
+            limit_per_window,
+            window: RATE_LIMIT_WINDOW,
+            counters: Mutex::new(HashMap::new()),
+            shared: None,
+        }
+    }
+
+    /// Counts against Redis under `scope` (e.g. `user`, `tenant`) when a coordinator is given.
+    pub(crate) fn shared(
+        mut self,
+        coordinator: Option<Arc<RedisCoordinator>>,
+        scope: &'static str,
+    ) -> Self {
+        self.shared = coordinator.map(|coordinator| (coordinator, scope));
+        self
+    }
+
+    /// Counts a request for `key`; `Err` when the window is already full. Either way the quota
+    /// is returned for the response's rate-limit headers.
+    pub(crate) async fn acquire(&self, key: &str) -> Result<WindowQuota, WindowQuota> {
+        if let Some((coordinator, scope)) = &self.shared {
+            match coordinator.hit_window(scope, key, self.window).await {
+                Ok((hits, reset_after)) => {
+                    let quota = self.quota(hits, reset_after);
+                    return if hits <= self.limit_per_window as u64 {
+                        Ok(quota)
+                    } else {
+                        Err(quota)
+                    };
+                }
+                Err(error) => warn!(
+                    event = "coordination.redis.unavailable",
+                    scope = *scope,
+                    error = %error
+                ),
+            }
+        }
+        self.count_at(key, Instant::now())
+    }
+
+    fn count_at(&self, key: &str, now: Instant) -> Result<WindowQuota, WindowQuota> {
+        let mut counters = self.counters.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        counters.retain(|_, (window_started_at, _)| {
+            now.duration_since(*window_started_at) < self.window
+        });
+        let (window_started_at, count) = counters.entry(key.to_string()).or_insert((now, 0));
+        let reset_after = self.window.saturating_sub(now.duration_since(*window_started_at));
+        if *count >= self.limit_per_window {
+            return Err(self.quota(*count as u64, reset_after));
+        }
+        *count += 1;
+        Ok(self.quota(*count as u64, reset_after))
+    }
+
+    fn quota(&self, hits: u64, reset_after: Duration) -> WindowQuota {
+        let limit = self.limit_per_window as u64;
+        WindowQuota { limit, remaining: limit.saturating_sub(hits), reset_after }
+    }
+}
+
+/// Quotas a request was counted against; the handler adds them to its response as
+/// OpenAI-style `x-ratelimit-*` headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct QuotaReport {
+    requests: Option<WindowQuota>,
+    tokens: Option<(u64, u64)>,
+}
+
+impl QuotaReport {
+    /// Keeps the tightest of the request limits, so the headers describe the one that runs out
+    /// first.
+    pub(crate) fn record_requests(&mut self, quota: WindowQuota) {
+        let tighter = self.requests.is_none_or(|current| {
+            (quota.remaining, std::cmp::Reverse(quota.reset_after))
+                < (current.remaining, std::cmp::Reverse(current.reset_after))
+        });
+        if tighter {
+            self.requests = Some(quota);
+        }
+    }
+
+    /// Token budgets do not reset, so no `x-ratelimit-reset-tokens` is reported for them.
+    pub(crate) fn record_tokens(&mut self, limit: u64, remaining: u64) {
+        self.tokens = Some((limit, remaining));
+    }
+
+    pub(crate) fn merge(&mut self, other: QuotaReport) {
+        if let Some(quota) = other.requests {
+            self.record_requests(quota);
+        }
+        if other.tokens.is_some() {
+            self.tokens = other.tokens;
+        }
+    }
+
+    /// Adds the headers to `response`, and `retry-after` when one of the limits rejected it.
+    pub(crate) fn apply(&self, response: &mut Response) {
+        let rate_limited = response.status() == StatusCode::TOO_MANY_REQUESTS;
+        let headers = response.headers_mut();
+        if let Some(quota) = self.requests {
+            insert_number(headers, LIMIT_REQUESTS, quota.limit);
+            insert_number(headers, REMAINING_REQUESTS, quota.remaining);
+            if let Ok(reset) = HeaderValue::from_str(&format_reset(quota.reset_after)) {
+                headers.insert(RESET_REQUESTS, reset);
+            }
+            if rate_limited && quota.remaining == 0 && !headers.contains_key(RETRY_AFTER) {
+                insert_number(headers, RETRY_AFTER, quota.reset_after.as_secs_f64().ceil() as u64);
+            }
+        }
+        if let Some((limit, remaining)) = self.tokens {
+            insert_number(headers, LIMIT_TOKENS, limit);
+            insert_number(headers, REMAINING_TOKENS, remaining);
+        }
+    }
+}
+
+/// Drops the quota headers of a response served again to another request, whose own quota
+/// they do not describe.
+pub(crate) fn strip_quota_headers(headers: &mut HeaderMap) {
+    for name in QUOTA_HEADERS {
+        headers.remove(name);
+    }
+}
+
+fn insert_number(headers: &mut HeaderMap, name: HeaderName, value: u64) {
+    headers.insert(name, HeaderValue::from(value));
+}
+
+/// Formats like OpenAI's reset headers: `120ms`, `17s`, `6m0s`.
+fn format_reset(reset_after: Duration) -> String {
+    if reset_after < Duration::from_secs(1) {
+        return format!("{}ms", reset_after.as_millis());
+    }
+    let seconds = reset_after.as_secs_f64().ceil() as u64;
+    match seconds / 60 {
+        0 => format!("{seconds}s"),
+        minutes => format!("{minutes}m{}s", seconds % 60),
+    }
+}
+
+/// Counts the request against its user's limit; the quota comes back whether or not the user
+/// is over it.
+pub(crate) async fn user_rate_limit_rejection(
+    limiter: Option<&FixedWindowRateLimiter>,
+    user: Option<&str>,
+    route: &str,
+) -> (Option<Response>, QuotaReport) {
+    let mut quota = QuotaReport::default();
+    let (Some(limiter), Some(user)) = (limiter, user.filter(|value| !value.trim().is_empty()))
+    else {
+        return (None, quota);
+    };
+    let result = limiter.acquire(user).await;
+    let (Ok(window) | Err(window)) = result;
+    quota.record_requests(window);
+    if result.is_ok() {
+        return (None, quota);
+    }
+    warn!(event = "http.rate_limit.user_exceeded", route = route, user = %user);
+    let rejection = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(ErrorResponse { error: "rate limit exceeded for user".to_string(), param: None }),
+    )
+        .into_response();
+    (Some(rejection), quota)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limiter_counts_keys_independently_and_resets_after_window() {
+        let limiter = FixedWindowRateLimiter::per_minute(2);
+        let start = Instant::now();
+        assert!(limiter.count_at("alice", start).is_ok());
+        assert!(limiter.count_at("alice", start).is_ok());
+        assert!(limiter.count_at("alice", start).is_err());
+        assert!(limiter.count_at("bob", start).is_ok());
+        assert!(limiter.count_at("alice", start + RATE_LIMIT_WINDOW).is_ok());
+    }
+
+    #[test]
+    fn limiter_reports_remaining_requests_and_time_to_reset() {
+        let limiter = FixedWindowRateLimiter::per_minute(2);
+        let start = Instant::now();
+        let first = limiter.count_at("alice", start).expect("first request must pass");
+        assert_eq!(first, WindowQuota { limit: 2, remaining: 1, reset_after: RATE_LIMIT_WINDOW });
+        let later = start + Duration::from_secs(45);
+        assert_eq!(limiter.count_at("alice", later).map(|quota| quota.remaining), Ok(0));
+        assert_eq!(
+            limiter.count_at("alice", later),
+            Err(WindowQuota { limit: 2, remaining: 0, reset_after: Duration::from_secs(15) })
+        );
+        assert_eq!(format_reset(Duration::from_millis(120)), "120ms");
+        assert_eq!(format_reset(Duration::from_millis(16_200)), "17s");
+        assert_eq!(format_reset(Duration::from_secs(360)), "6m0s");
+    }
+
+    #[tokio::test]
+    async fn shared_limiter_falls_back_to_local_counters_when_redis_is_down() {
+        let coordinator = RedisCoordinator::open("redis://127.0.0.1:1/").expect("url must parse");
+        let limiter =
+            FixedWindowRateLimiter::per_minute(1).shared(Some(Arc::new(coordinator)), "user");
+        assert!(limiter.acquire("alice").await.is_ok());
+        assert!(limiter.acquire("alice").await.is_err());
+    }
+}
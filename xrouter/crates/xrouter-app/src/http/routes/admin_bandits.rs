@@ -0,0 +1,39 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    AppState,
+    http::{
+        docs::{BanditEntry, BanditsResponse, ErrorResponse},
+        routes::admin_keys::{admin_error, authorize_admin, rejection_response},
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/admin/v1/bandits",
+    responses(
+        (status = 200, description = "Learned rewards of every bandit model", body = BanditsResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Admin API or bandit models are not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn get_bandits(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(status) = authorize_admin(&state, &headers, "/admin/v1/bandits") {
+        return rejection_response(status);
+    }
+    let Some(bandits) = state.bandits.as_ref() else {
+        return admin_error(
+            StatusCode::NOT_FOUND,
+            "bandit models are not enabled; start with XR_BANDIT_MODELS set",
+        );
+    };
+    Json(BanditsResponse { data: bandits.snapshot().into_iter().map(BanditEntry::from).collect() })
+        .into_response()
+}
@@ -0,0 +1,76 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+use xrouter_clients_openai::ChaosPolicy;
+
+use crate::{
+    AppState,
+    http::{
+        docs::{ChaosSettings, ErrorResponse},
+        json_body::JsonBody,
+        routes::admin_keys::{admin_error, authorize_admin, rejection_response},
+    },
+};
+
+fn chaos_disabled() -> Response {
+    admin_error(StatusCode::NOT_FOUND, "chaos injection is not enabled; start with XR_CHAOS set")
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/v1/chaos",
+    responses(
+        (status = 200, description = "Current fault injection policy", body = ChaosSettings),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Admin API or chaos injection is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn get_chaos(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(status) = authorize_admin(&state, &headers, "/admin/v1/chaos") {
+        return rejection_response(status);
+    }
+    let Some(chaos) = state.chaos.as_ref() else {
+        return chaos_disabled();
+    };
+    let policy = chaos.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    Json(ChaosSettings::from(policy)).into_response()
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/v1/chaos",
+    request_body = ChaosSettings,
+    responses(
+        (status = 200, description = "Fault injection policy replaced", body = ChaosSettings),
+        (status = 400, description = "Probability or status out of range", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Admin API or chaos injection is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn put_chaos(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    JsonBody(settings): JsonBody<ChaosSettings>,
+) -> Response {
+    if let Err(status) = authorize_admin(&state, &headers, "/admin/v1/chaos") {
+        return rejection_response(status);
+    }
+    let Some(chaos) = state.chaos.as_ref() else {
+        return chaos_disabled();
+    };
+    let policy = ChaosPolicy::from(settings);
+    if let Err(message) = policy.validate() {
+        return admin_error(StatusCode::BAD_REQUEST, &message);
+    }
+    warn!(event = "http.admin.chaos_updated", policy = ?policy);
+    *chaos.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = policy.clone();
+    Json(ChaosSettings::from(policy)).into_response()
+}
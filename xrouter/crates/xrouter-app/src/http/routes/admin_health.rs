@@ -0,0 +1,48 @@
+use axum::{
+    Json,
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    AppState,
+    bandit::BANDIT_ENGINE,
+    http::{
+        docs::{ErrorResponse, ProviderHealthEntry, ProviderHealthResponse},
+        routes::admin_keys::{authorize_admin, rejection_response},
+    },
+    racing::RACE_ENGINE,
+};
+
+#[utoipa::path(
+    get,
+    path = "/admin/v1/health/providers",
+    responses(
+        (status = 200, description = "Success rate, latency, in-flight calls and last error of every provider over 5m/1h/24h", body = ProviderHealthResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Admin API is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn get_provider_health(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(status) = authorize_admin(&state, &headers, "/admin/v1/health/providers") {
+        return rejection_response(status);
+    }
+    // Race and bandit models are listed once they served traffic.
+    let providers = state
+        .engines
+        .keys()
+        .filter(|provider| ![RACE_ENGINE, BANDIT_ENGINE].contains(&provider.as_str()));
+    let data = state
+        .provider_stats
+        .snapshot(providers)
+        .into_iter()
+        .map(ProviderHealthEntry::from)
+        .collect();
+    Json(ProviderHealthResponse { data, tasks_in_flight: state.tasks_in_flight() }).into_response()
+}
@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    AppState,
+    api_keys::{ApiKeyRecord, ApiKeyStore, KeyStoreError},
+    http::{
+        auth::parse_bearer_token,
+        docs::{
+            ApiKeyEntry, ApiKeyListResponse, CreateApiKeyRequest, ErrorResponse,
+            IssuedApiKeyResponse, UpdateApiKeyRequest,
+        },
+        json_body::JsonBody,
+    },
+};
+
+pub(crate) fn admin_error(status: StatusCode, message: &str) -> Response {
+    (status, Json(ErrorResponse { error: message.to_string(), param: None })).into_response()
+}
+
+pub(crate) fn authorize_admin(
+    state: &AppState,
+    headers: &HeaderMap,
+    route: &str,
+) -> Result<Arc<dyn ApiKeyStore>, StatusCode> {
+    let (Some(admin_token), Some(key_store)) = (state.admin_token.as_deref(), &state.key_store)
+    else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let authorized = parse_bearer_token(headers).is_some_and(|token| {
+        token.len() == admin_token.len()
+            && token.bytes().zip(admin_token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    });
+    if !authorized {
+        warn!(event = "http.admin.unauthorized", route = route);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(key_store.clone())
+}
+
+pub(crate) fn rejection_response(status: StatusCode) -> Response {
+    match status {
+        StatusCode::NOT_FOUND => admin_error(status, "admin API is not enabled"),
+        _ => admin_error(status, "admin token is required"),
+    }
+}
+
+fn store_error_response(route: &str, err: KeyStoreError) -> Response {
+    error!(event = "http.admin.key_store_failed", route = route, error = %err);
+    admin_error(StatusCode::INTERNAL_SERVER_ERROR, "key store error")
+}
+
+fn key_not_found() -> Response {
+    admin_error(StatusCode::NOT_FOUND, "API key not found")
+}
+
+fn entry(record: ApiKeyRecord) -> ApiKeyEntry {
+    ApiKeyEntry {
+        id: record.id,
+        label: record.label,
+        organization: record.organization,
+        project: record.project,
+        allowed_models: record.allowed_models,
+        denied_models: record.denied_models,
+        expires_at: record.expires_at,
+        disabled: record.disabled,
+        coalescing_opt_out: record.coalescing_opt_out,
+        priority: record.priority,
+        truncation: record.truncation,
+        hide_reasoning: record.hide_reasoning,
+        created_at: record.created_at,
+        rotated_at: record.rotated_at,
+    }
+}
+
+fn is_valid_scope(organization: &str, project: &str) -> bool {
+    let valid = |value: &str| !value.trim().is_empty() && !value.contains('/');
+    valid(organization) && valid(project)
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/v1/keys",
+    responses(
+        (status = 200, description = "Managed API keys", body = ApiKeyListResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Admin API is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn list_keys(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let route = "/admin/v1/keys";
+    let key_store = match authorize_admin(&state, &headers, route) {
+        Ok(key_store) => key_store,
+        Err(status) => return rejection_response(status),
+    };
+    match key_store.list().await {
+        Ok(records) => Json(ApiKeyListResponse { data: records.into_iter().map(entry).collect() })
+            .into_response(),
+        Err(err) => store_error_response(route, err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/v1/keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "API key created; the key is returned only once", body = IssuedApiKeyResponse),
+        (status = 400, description = "Invalid key metadata", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Admin API is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn create_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    JsonBody(request): JsonBody<CreateApiKeyRequest>,
+) -> Response {
+    let route = "/admin/v1/keys";
+    let key_store = match authorize_admin(&state, &headers, route) {
+        Ok(key_store) => key_store,
+        Err(status) => return rejection_response(status),
+    };
+    if !is_valid_scope(&request.organization, &request.project) {
+        return admin_error(
+            StatusCode::BAD_REQUEST,
+            "organization and project must be non-empty and must not contain '/'",
+        );
+    }
+    let (mut record, key) = ApiKeyRecord::issue(
+        request.label,
+        request.organization,
+        request.project,
+        request.allowed_models,
+        request.denied_models,
+        request.expires_at,
+    );
+    record.coalescing_opt_out = request.coalescing_opt_out;
+    record.priority = request.priority;
+    record.truncation = request.truncation;
+    record.hide_reasoning = request.hide_reasoning;
+    if let Err(err) = key_store.put(record.clone()).await {
+        return store_error_response(route, err);
+    }
+    info!(
+        event = "http.admin.key_created",
+        route = route,
+        key_id = %record.id,
+        tenant_id = %format!("{}/{}", record.organization, record.project)
+    );
+    (StatusCode::CREATED, Json(IssuedApiKeyResponse { key, api_key: entry(record) }))
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/v1/keys/{id}",
+    params(("id" = String, Path, description = "API key id")),
+    responses(
+        (status = 200, description = "Managed API key", body = ApiKeyEntry),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "API key not found or admin API is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn get_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let route = "/admin/v1/keys/{id}";
+    let key_store = match authorize_admin(&state, &headers, route) {
+        Ok(key_store) => key_store,
+        Err(status) => return rejection_response(status),
+    };
+    match key_store.get(&id).await {
+        Ok(Some(record)) => Json(entry(record)).into_response(),
+        Ok(None) => key_not_found(),
+        Err(err) => store_error_response(route, err),
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/admin/v1/keys/{id}",
+    params(("id" = String, Path, description = "API key id")),
+    request_body = UpdateApiKeyRequest,
+    responses(
+        (status = 200, description = "Updated API key", body = ApiKeyEntry),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "API key not found or admin API is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn update_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    JsonBody(request): JsonBody<UpdateApiKeyRequest>,
+) -> Response {
+    let route = "/admin/v1/keys/{id}";
+    let key_store = match authorize_admin(&state, &headers, route) {
+        Ok(key_store) => key_store,
+        Err(status) => return rejection_response(status),
+    };
+    let mut record = match key_store.get(&id).await {
+        Ok(Some(record)) => record,
+        Ok(None) => return key_not_found(),
+        Err(err) => return store_error_response(route, err),
+    };
+    if let Some(label) = request.label {
+        record.label = label;
+    }
+    if let Some(allowed_models) = request.allowed_models {
+        record.allowed_models = allowed_models;
+    }
+    if let Some(denied_models) = request.denied_models {
+        record.denied_models = denied_models;
+    }
+    if let Some(expires_at) = request.expires_at {
+        record.expires_at = Some(expires_at);
+    }
+    if let Some(disabled) = request.disabled {
+        record.disabled = disabled;
+    }
+    if let Some(coalescing_opt_out) = request.coalescing_opt_out {
+        record.coalescing_opt_out = coalescing_opt_out;
+    }
+    if let Some(priority) = request.priority {
+        record.priority = Some(priority);
+    }
+    if let Some(truncation) = request.truncation {
+        record.truncation = Some(truncation);
+    }
+    if let Some(hide_reasoning) = request.hide_reasoning {
+        record.hide_reasoning = hide_reasoning;
+    }
+    if let Err(err) = key_store.put(record.clone()).await {
+        return store_error_response(route, err);
+    }
+    info!(
+        event = "http.admin.key_updated",
+        route = route,
+        key_id = %record.id,
+        disabled = record.disabled
+    );
+    Json(entry(record)).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/v1/keys/{id}/rotate",
+    params(("id" = String, Path, description = "API key id")),
+    responses(
+        (status = 200, description = "API key rotated; the new key is returned only once", body = IssuedApiKeyResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "API key not found or admin API is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn rotate_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let route = "/admin/v1/keys/{id}/rotate";
+    let key_store = match authorize_admin(&state, &headers, route) {
+        Ok(key_store) => key_store,
+        Err(status) => return rejection_response(status),
+    };
+    let mut record = match key_store.get(&id).await {
+        Ok(Some(record)) => record,
+        Ok(None) => return key_not_found(),
+        Err(err) => return store_error_response(route, err),
+    };
+    let key = record.rotate();
+    if let Err(err) = key_store.put(record.clone()).await {
+        return store_error_response(route, err);
+    }
+    info!(event = "http.admin.key_rotated", route = route, key_id = %record.id);
+    Json(IssuedApiKeyResponse { key, api_key: entry(record) }).into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/v1/keys/{id}",
+    params(("id" = String, Path, description = "API key id")),
+    responses(
+        (status = 204, description = "API key deleted"),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "API key not found or admin API is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn delete_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let route = "/admin/v1/keys/{id}";
+    let key_store = match authorize_admin(&state, &headers, route) {
+        Ok(key_store) => key_store,
+        Err(status) => return rejection_response(status),
+    };
+    match key_store.delete(&id).await {
+        Ok(true) => {
+            info!(event = "http.admin.key_deleted", route = route, key_id = %id);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => key_not_found(),
+        Err(err) => store_error_response(route, err),
+    }
+}
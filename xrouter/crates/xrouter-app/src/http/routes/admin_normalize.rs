@@ -0,0 +1,113 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::info;
+use xrouter_clients_openai::preview_payload;
+use xrouter_contracts::ResponsesRequest;
+use xrouter_core::{ExecutionControls, ProviderGenerateRequest};
+
+use crate::{
+    AppState,
+    http::{
+        docs::{
+            ErrorResponse, NormalizePreviewCandidate, NormalizePreviewQuery,
+            NormalizePreviewResponse,
+        },
+        json_body::JsonBody,
+        routes::admin_keys::{admin_error, authorize_admin, rejection_response},
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/debug/normalize",
+    params(NormalizePreviewQuery),
+    request_body = ResponsesRequest,
+    responses(
+        (status = 200, description = "Upstream payload each candidate provider would receive; nothing is sent", body = NormalizePreviewResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Unknown or disabled provider, or admin API is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn preview_normalization(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<NormalizePreviewQuery>,
+    JsonBody(request): JsonBody<ResponsesRequest>,
+) -> Response {
+    if let Err(status) = authorize_admin(&state, &headers, "/api/v1/debug/normalize") {
+        return rejection_response(status);
+    }
+    let providers = match query.providers.as_deref() {
+        Some(list) => list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        None => vec![state.resolve_provider_key(&request.model)],
+    };
+    if let Some(unknown) = providers.iter().find(|name| !state.engines.contains_key(*name)) {
+        return admin_error(
+            StatusCode::NOT_FOUND,
+            &format!("provider {unknown} is unknown or disabled"),
+        );
+    }
+    let upstream_model = state.resolve_provider_model_id(&request.model);
+    let candidates = providers
+        .iter()
+        .map(|provider| {
+            let engine = &state.engines[provider];
+            let mut candidate = engine.rewrite_request(ResponsesRequest {
+                model: upstream_model.clone(),
+                ..request.clone()
+            });
+            let dropped_fields = state.retain_passthrough_fields(provider, &mut candidate.extra);
+            let provider_request = ProviderGenerateRequest {
+                model: &candidate.model,
+                instructions: candidate.instructions.as_deref(),
+                input: &candidate.input,
+                reasoning: candidate.reasoning.as_ref(),
+                tools: candidate.tools.as_deref(),
+                tool_choice: candidate.tool_choice.as_ref(),
+                include: candidate.include.as_deref(),
+                text: candidate.text.as_ref(),
+                modalities: candidate.modalities.as_deref(),
+                auth_bearer: None,
+                forward_headers: &[],
+                extra: Some(&candidate.extra).filter(|extra| !extra.is_empty()),
+                controls: ExecutionControls::default(),
+            };
+            let project = state.provider_projects.get(provider).map(String::as_str);
+            match preview_payload(provider, &provider_request, project) {
+                Ok(preview) => NormalizePreviewCandidate {
+                    provider: provider.clone(),
+                    endpoint: Some(preview.endpoint.to_string()),
+                    payload: Some(preview.payload),
+                    normalization: preview.normalization.map(Into::into),
+                    dropped_fields,
+                    error: None,
+                },
+                Err(error) => NormalizePreviewCandidate {
+                    provider: provider.clone(),
+                    endpoint: None,
+                    payload: None,
+                    normalization: None,
+                    dropped_fields,
+                    error: Some(error.to_string()),
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+    info!(
+        event = "http.admin.normalize_previewed",
+        model = %request.model,
+        providers = ?providers
+    );
+    Json(NormalizePreviewResponse { model: request.model, candidates }).into_response()
+}
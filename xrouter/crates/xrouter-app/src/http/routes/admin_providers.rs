@@ -0,0 +1,41 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    AppState, ProbeReport,
+    http::{
+        docs::ErrorResponse,
+        routes::admin_keys::{admin_error, authorize_admin, rejection_response},
+    },
+    probe,
+};
+
+#[utoipa::path(
+    post,
+    path = "/admin/v1/providers/{name}/probe",
+    params(("name" = String, Path, description = "Provider name, e.g. `deepseek`")),
+    responses(
+        (status = 200, description = "Probe result; `ok` is false when the upstream call failed", body = ProbeReport),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Unknown or disabled provider, or admin API is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn probe_provider(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Response {
+    if let Err(status) = authorize_admin(&state, &headers, "/admin/v1/providers/{name}/probe") {
+        return rejection_response(status);
+    }
+    match probe::probe_provider(&state, &name).await {
+        Some(report) => Json(report).into_response(),
+        None => admin_error(StatusCode::NOT_FOUND, "provider is unknown or disabled"),
+    }
+}
@@ -0,0 +1,41 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    AppState,
+    http::{
+        docs::{ErrorResponse, ProviderScheduling, SchedulingResponse},
+        routes::admin_keys::{admin_error, authorize_admin, rejection_response},
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/admin/v1/scheduling",
+    responses(
+        (status = 200, description = "Provider slot usage by priority class", body = SchedulingResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Admin API or priority scheduling is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn get_scheduling(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(status) = authorize_admin(&state, &headers, "/admin/v1/scheduling") {
+        return rejection_response(status);
+    }
+    let Some(scheduler) = state.scheduler.as_ref() else {
+        return admin_error(
+            StatusCode::NOT_FOUND,
+            "priority scheduling is not enabled; start with XR_PRIORITY_SCHEDULING=true",
+        );
+    };
+    Json(SchedulingResponse {
+        data: scheduler.snapshot().into_iter().map(ProviderScheduling::from).collect(),
+    })
+    .into_response()
+}
@@ -0,0 +1,100 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::error;
+use xrouter_clients_openai::StreamTranscript;
+
+use crate::{
+    AppState,
+    http::{
+        docs::{ErrorResponse, StreamTranscriptEntry, StreamTranscriptListResponse},
+        routes::admin_keys::{admin_error, authorize_admin, rejection_response},
+    },
+};
+
+fn capture_disabled() -> Response {
+    admin_error(StatusCode::NOT_FOUND, "stream transcript capture is not enabled")
+}
+
+fn entry(transcript: StreamTranscript, with_body: bool) -> StreamTranscriptEntry {
+    StreamTranscriptEntry {
+        id: transcript.id,
+        request_id: transcript.request_id,
+        provider: transcript.provider,
+        stream_kind: transcript.stream_kind,
+        recorded_at_ms: transcript.recorded_at_ms,
+        complete: transcript.complete,
+        truncated: transcript.truncated,
+        body: with_body.then_some(transcript.body),
+    }
+}
+
+fn store_error_response(route: &str, err: std::io::Error) -> Response {
+    error!(event = "http.admin.transcript_store_failed", route = route, error = %err);
+    admin_error(StatusCode::INTERNAL_SERVER_ERROR, "transcript store error")
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/v1/transcripts",
+    responses(
+        (status = 200, description = "Captured upstream stream transcripts, newest first, without bodies", body = StreamTranscriptListResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Admin API or transcript capture is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn list_transcripts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let route = "/admin/v1/transcripts";
+    if let Err(status) = authorize_admin(&state, &headers, route) {
+        return rejection_response(status);
+    }
+    let Some(store) = state.transcripts.as_ref() else {
+        return capture_disabled();
+    };
+    match store.list() {
+        Ok(transcripts) => Json(StreamTranscriptListResponse {
+            data: transcripts.into_iter().map(|transcript| entry(transcript, false)).collect(),
+        })
+        .into_response(),
+        Err(err) => store_error_response(route, err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/v1/transcripts/{id}",
+    params(("id" = String, Path, description = "Transcript id from the list endpoint")),
+    responses(
+        (status = 200, description = "Captured transcript with the raw upstream SSE body", body = StreamTranscriptEntry),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Unknown transcript, or admin API or transcript capture is not enabled", body = ErrorResponse)
+    ),
+    security(("admin_token" = [])),
+    tag = "xrouter-admin"
+)]
+pub(crate) async fn get_transcript(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let route = "/admin/v1/transcripts/{id}";
+    if let Err(status) = authorize_admin(&state, &headers, route) {
+        return rejection_response(status);
+    }
+    let Some(store) = state.transcripts.as_ref() else {
+        return capture_disabled();
+    };
+    match store.get(&id) {
+        Ok(Some(transcript)) => Json(entry(transcript, true)).into_response(),
+        Ok(None) => admin_error(StatusCode::NOT_FOUND, "transcript not found"),
+        Err(err) => store_error_response(route, err),
+    }
+}
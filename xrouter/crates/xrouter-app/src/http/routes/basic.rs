@@ -1,15 +1,25 @@
-use axum::{Json, extract::State};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
 use tracing::{debug, info};
-use xrouter_core::synthesize_model_id;
+use xrouter_core::{ModelDescriptor, synthesize_model_id};
 
 use crate::{
     AppState,
     http::docs::{
-        CompatibleModelEntry, CompatibleModelsResponse, HealthResponse, ModelArchitecture,
-        ModelPerRequestLimits, ModelTopProvider, XrouterModelEntry, XrouterModelsResponse,
+        CompatibleModelEntry, CompatibleModelsResponse, ErrorResponse, HealthResponse,
+        ModelArchitecture, ModelEndpointEntry, ModelEndpointsData, ModelEndpointsResponse,
+        ModelListQuery, ModelPerRequestLimits, ModelPricingEntry, ModelStats, ModelTopProvider,
+        XrouterModelEntry, XrouterModelsResponse,
     },
+    tenancy::{TenantPrincipal, listing_principal},
 };
 
+const DEGRADED_UPTIME_PERCENT: f64 = 90.0;
+
 #[utoipa::path(
     get,
     path = "/health",
@@ -23,21 +33,31 @@ pub(crate) async fn get_health() -> Json<HealthResponse> {
 #[utoipa::path(
     get,
     path = "/v1/models",
-    responses((status = 200, description = "OpenAI-compatible model list", body = CompatibleModelsResponse)),
+    params(ModelListQuery),
+    responses(
+        (status = 200, description = "OpenAI-compatible model list, narrowed to the models a presented tenant API key may call", body = CompatibleModelsResponse),
+        (status = 401, description = "Invalid tenant API key", body = ErrorResponse)
+    ),
     tag = "xrouter-app"
 )]
 pub(crate) async fn get_compatible_models(
     State(state): State<AppState>,
-) -> Json<CompatibleModelsResponse> {
+    headers: HeaderMap,
+    Query(query): Query<ModelListQuery>,
+) -> Response {
     debug!(event = "http.request.received", route = "/v1/models", openai_compatible_api = true);
-    let data = state
-        .models
-        .iter()
+    let principal = match listing_principal(state.tenants.as_deref(), &headers, "/v1/models").await
+    {
+        Ok(principal) => principal,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let data = filter_models(&state.models, &query, principal.as_ref())
         .map(|m| CompatibleModelEntry {
             id: synthesize_model_id(&m.provider, &m.id),
             object: "model".to_string(),
             created: 1_710_979_200,
             owned_by: m.provider.clone(),
+            supported_parameters: m.supported_parameters.clone(),
         })
         .collect::<Vec<_>>();
     info!(event = "http.models.served", route = "/v1/models", model_count = data.len());
@@ -46,45 +66,60 @@ pub(crate) async fn get_compatible_models(
         route = "/v1/models",
         model_ids = ?data.iter().map(|m| m.id.as_str()).collect::<Vec<_>>()
     );
-    Json(CompatibleModelsResponse { object: "list".to_string(), data })
+    Json(CompatibleModelsResponse { object: "list".to_string(), data }).into_response()
 }
 
 #[utoipa::path(
     get,
     path = "/api/v1/models",
-    responses((status = 200, description = "xrouter model list", body = XrouterModelsResponse)),
+    params(ModelListQuery),
+    responses(
+        (status = 200, description = "xrouter model list, narrowed to the models a presented tenant API key may call", body = XrouterModelsResponse),
+        (status = 401, description = "Invalid tenant API key", body = ErrorResponse)
+    ),
     tag = "xrouter-app"
 )]
 pub(crate) async fn get_xrouter_models(
     State(state): State<AppState>,
-) -> Json<XrouterModelsResponse> {
+    headers: HeaderMap,
+    Query(query): Query<ModelListQuery>,
+) -> Response {
     debug!(
         event = "http.request.received",
         route = "/api/v1/models",
         openai_compatible_api = false
     );
-    let data = state
-        .models
-        .iter()
-        .map(|m| XrouterModelEntry {
-            id: synthesize_model_id(&m.provider, &m.id),
-            name: synthesize_model_id(&m.provider, &m.id),
-            description: m.description.clone(),
-            context_length: m.context_length,
-            architecture: ModelArchitecture {
-                tokenizer: m.tokenizer.clone(),
-                instruct_type: m.instruct_type.clone(),
-                modality: m.modality.clone(),
-            },
-            top_provider: ModelTopProvider {
-                context_length: m.top_provider_context_length,
-                max_completion_tokens: m.max_completion_tokens,
-                is_moderated: m.is_moderated,
-            },
-            per_request_limits: ModelPerRequestLimits {
-                prompt_tokens: None,
-                completion_tokens: Some(m.max_completion_tokens),
-            },
+    let principal =
+        match listing_principal(state.tenants.as_deref(), &headers, "/api/v1/models").await {
+            Ok(principal) => principal,
+            Err(rejection) => return rejection.into_response(),
+        };
+    let data = filter_models(&state.models, &query, principal.as_ref())
+        .map(|m| {
+            let id = synthesize_model_id(&m.provider, &m.id);
+            XrouterModelEntry {
+                stats: state.model_stats.snapshot(&id).map(ModelStats::from),
+                name: id.clone(),
+                id,
+                description: m.description.clone(),
+                context_length: m.context_length,
+                architecture: ModelArchitecture {
+                    tokenizer: m.tokenizer.clone(),
+                    instruct_type: m.instruct_type.clone(),
+                    modality: m.modality.clone(),
+                },
+                top_provider: ModelTopProvider {
+                    context_length: m.top_provider_context_length,
+                    max_completion_tokens: m.max_completion_tokens,
+                    is_moderated: m.is_moderated,
+                },
+                per_request_limits: ModelPerRequestLimits {
+                    prompt_tokens: None,
+                    completion_tokens: Some(m.max_completion_tokens),
+                },
+                pricing: m.pricing.as_ref().map(ModelPricingEntry::from),
+                supported_parameters: m.supported_parameters.clone(),
+            }
         })
         .collect::<Vec<_>>();
     info!(event = "http.models.served", route = "/api/v1/models", model_count = data.len());
@@ -93,5 +128,104 @@ pub(crate) async fn get_xrouter_models(
         route = "/api/v1/models",
         model_ids = ?data.iter().map(|m| m.id.as_str()).collect::<Vec<_>>()
     );
-    Json(XrouterModelsResponse { data })
+    Json(XrouterModelsResponse { data }).into_response()
+}
+
+fn filter_models<'a>(
+    models: &'a [ModelDescriptor],
+    query: &'a ModelListQuery,
+    principal: Option<&'a TenantPrincipal>,
+) -> impl Iterator<Item = &'a ModelDescriptor> {
+    let needle = query.q.as_deref().map(str::to_lowercase);
+    models
+        .iter()
+        .filter(move |m| {
+            principal.is_none_or(|principal| {
+                principal.allows_model(&synthesize_model_id(&m.provider, &m.id))
+            })
+        })
+        .filter(|m| query.provider.as_deref().is_none_or(|provider| m.provider == provider))
+        .filter(|m| query.supports_tools.is_none_or(|supports| m.supports_tools == supports))
+        .filter(|m| query.min_context.is_none_or(|min| m.context_length >= min))
+        .filter(move |m| {
+            needle.as_deref().is_none_or(|needle| {
+                synthesize_model_id(&m.provider, &m.id).to_lowercase().contains(needle)
+                    || m.description.to_lowercase().contains(needle)
+            })
+        })
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(usize::MAX))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/models/{model_id}/endpoints",
+    params(("model_id" = String, Path, description = "Model id, e.g. `deepseek/deepseek-chat`")),
+    responses(
+        (status = 200, description = "Per-provider endpoints serving the model", body = ModelEndpointsResponse),
+        (status = 404, description = "Unknown model", body = ErrorResponse)
+    ),
+    tag = "xrouter-app"
+)]
+pub(crate) async fn get_model_endpoints(
+    State(state): State<AppState>,
+    Path(model_path): Path<String>,
+) -> Response {
+    let not_found = |message: String| {
+        (StatusCode::NOT_FOUND, Json(ErrorResponse { error: message, param: None })).into_response()
+    };
+    // Model ids contain slashes, so the route captures the whole tail and peels `/endpoints` off.
+    let Some(model_id) = model_path.strip_suffix("/endpoints") else {
+        return not_found(format!("unknown route: /api/v1/models/{model_path}"));
+    };
+    debug!(event = "http.request.received", route = "/api/v1/models/{model_id}/endpoints");
+    let matches = state.model_endpoints(model_id);
+    let Some(primary) = matches.first() else {
+        return not_found(format!("model not found: {model_id}"));
+    };
+
+    let endpoints = matches
+        .iter()
+        .map(|m| {
+            let health = state.provider_health.snapshot(&m.provider);
+            let uptime = health.uptime_percent();
+            ModelEndpointEntry {
+                name: format!("{} | {}", m.provider, m.id),
+                model_id: synthesize_model_id(&m.provider, &m.id),
+                provider_name: m.provider.clone(),
+                context_length: m.context_length,
+                max_completion_tokens: m.max_completion_tokens,
+                max_prompt_tokens: None,
+                is_moderated: m.is_moderated,
+                pricing: m.pricing.as_ref().map(ModelPricingEntry::from),
+                quantization: None,
+                status: if uptime.is_some_and(|uptime| uptime < DEGRADED_UPTIME_PERCENT) {
+                    -1
+                } else {
+                    0
+                },
+                uptime_last_30m: uptime,
+                requests_last_30m: health.requests,
+            }
+        })
+        .collect::<Vec<_>>();
+    info!(
+        event = "http.model_endpoints.served",
+        model_id = %model_id,
+        endpoint_count = endpoints.len()
+    );
+    Json(ModelEndpointsResponse {
+        data: ModelEndpointsData {
+            id: model_id.to_string(),
+            name: model_id.to_string(),
+            description: primary.description.clone(),
+            architecture: ModelArchitecture {
+                tokenizer: primary.tokenizer.clone(),
+                instruct_type: primary.instruct_type.clone(),
+                modality: primary.modality.clone(),
+            },
+            endpoints,
+        },
+    })
+    .into_response()
 }
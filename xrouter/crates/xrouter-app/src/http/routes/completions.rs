@@ -0,0 +1,168 @@
+use axum::{
+    body::{Body, Bytes, to_bytes},
+    extract::{Extension, MatchedPath, State},
+    http::{HeaderMap, StatusCode, header::CONTENT_LENGTH, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use serde_json::{Value, json};
+use xrouter_contracts::{ChatCompletionsResponse, CompletionsRequest, CompletionsResponse};
+use xrouter_core::CoreError;
+
+use crate::{
+    AppState,
+    http::{
+        docs::{ErrorResponse, InferenceErrorResponses},
+        errors::error_response,
+        json_body::JsonBody,
+        routes::inference::post_chat_completions,
+    },
+    tenancy::TenantPrincipal,
+};
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/completions",
+    request_body = CompletionsRequest,
+    responses(
+        (
+            status = 200,
+            description = "Legacy text completion, served as a one-message chat completion; with \
+                `stream: true`, server-sent `text_completion` chunks ending with `data: [DONE]`",
+            content(
+                (CompletionsResponse = "application/json"),
+                (CompletionsResponse = "text/event-stream")
+            )
+        ),
+        InferenceErrorResponses
+    ),
+    security(("api_key" = []), ()),
+    tag = "xrouter-app"
+)]
+pub(crate) async fn post_completions(
+    state: State<AppState>,
+    matched_path: Option<MatchedPath>,
+    principal: Option<Extension<TenantPrincipal>>,
+    headers: HeaderMap,
+    JsonBody(request): JsonBody<CompletionsRequest>,
+) -> Response {
+    if request.extra.get("n").and_then(Value::as_u64).is_some_and(|n| n > 1) {
+        return error_response(CoreError::InvalidParam {
+            param: "n".to_string(),
+            message: "only one completion per request is supported".to_string(),
+        });
+    }
+    let Some(chat) = request.to_chat_completions_request() else {
+        return error_response(CoreError::InvalidParam {
+            param: "prompt".to_string(),
+            message: "exactly one prompt per request is supported".to_string(),
+        });
+    };
+    let echo = request.echo.then(|| request.prompt.single().unwrap_or_default().to_string());
+    let response =
+        post_chat_completions(state, matched_path, principal, headers, JsonBody(chat)).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    let streamed = parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    if streamed {
+        let mut translator = ChunkTranslator { buffer: Vec::new(), model: request.model, echo };
+        let body = body
+            .into_data_stream()
+            .map(move |bytes| bytes.map(|bytes| Bytes::from(translator.push(&bytes))));
+        return Response::from_parts(parts, Body::from_stream(body));
+    }
+    let completion = to_bytes(body, usize::MAX)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<ChatCompletionsResponse>(&bytes).ok());
+    match completion {
+        Some(chat) => {
+            let completion = CompletionsResponse::from_chat(chat, request.model, echo.as_deref());
+            let body = serde_json::to_vec(&completion).unwrap_or_default();
+            Response::from_parts(parts, Body::from(body))
+        }
+        None => (
+            StatusCode::BAD_GATEWAY,
+            axum::Json(ErrorResponse {
+                error: "chat completion could not be read as a text completion".to_string(),
+                param: None,
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Rewrites the `chat.completion.chunk` events of a chat stream into `text_completion`
+/// chunks; events without text or a finish reason, such as the role chunk, are dropped.
+struct ChunkTranslator {
+    buffer: Vec<u8>,
+    model: String,
+    /// Prompt still to be prepended to the first text.
+    echo: Option<String>,
+}
+
+impl ChunkTranslator {
+    fn push(&mut self, bytes: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(bytes);
+        let mut translated = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|window| window == b"\n\n") {
+            let frame = self.buffer.drain(..end + 2).collect::<Vec<_>>();
+            translated.extend(self.translate(frame));
+        }
+        translated
+    }
+
+    fn translate(&mut self, frame: Vec<u8>) -> Vec<u8> {
+        let text = String::from_utf8_lossy(&frame);
+        let chunk = text
+            .trim_end()
+            .strip_prefix("data: ")
+            .and_then(|data| serde_json::from_str::<Value>(data).ok());
+        let Some(chunk) = chunk else {
+            // `[DONE]` and anything else that is not a chunk.
+            return frame;
+        };
+        match self.text_chunk(chunk) {
+            Some(chunk) => format!("data: {chunk}\n\n").into_bytes(),
+            None => Vec::new(),
+        }
+    }
+
+    fn text_chunk(&mut self, chunk: Value) -> Option<Value> {
+        let Some(choice) = chunk["choices"].get(0) else {
+            // Error chunks end the stream the same way on both APIs.
+            return Some(chunk);
+        };
+        let delta = choice["delta"]["content"].as_str().unwrap_or_default();
+        let finish_reason = &choice["finish_reason"];
+        if delta.is_empty() && finish_reason.is_null() {
+            return None;
+        }
+        let text = self.echo.take().unwrap_or_default() + delta;
+        let id = chunk["id"].as_str().unwrap_or_default().replacen("chatcmpl_", "cmpl_", 1);
+        let mut translated = json!({
+            "id": id,
+            "object": "text_completion",
+            "model": self.model,
+            "choices": [{
+                "text": text,
+                "index": 0,
+                "logprobs": Value::Null,
+                "finish_reason": finish_reason
+            }]
+        });
+        for key in ["usage", "meta", "error"] {
+            if let Some(value) = chunk.get(key) {
+                translated[key] = value.clone();
+            }
+        }
+        Some(translated)
+    }
+}
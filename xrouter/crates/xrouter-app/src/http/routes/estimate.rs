@@ -0,0 +1,148 @@
+use axum::{
+    Json,
+    extract::{Extension, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use tracing::info;
+use xrouter_contracts::ResponsesRequest;
+use xrouter_core::{ModelDescriptor, synthesize_model_id};
+
+use crate::{
+    AppState,
+    http::{
+        docs::{EstimateEndpoint, EstimateResponse, InferenceErrorResponses},
+        errors::error_response,
+        json_body::JsonBody,
+        routes::inference::{format_cost, token_cost, validate_input_length},
+    },
+    request_context::override_from_headers,
+    tenancy::{TenantPrincipal, check_tenant_request},
+};
+
+const ROUTE: &str = "/api/v1/estimate";
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/estimate",
+    request_body = ResponsesRequest,
+    responses(
+        (status = 200, description = "Input tokens and cost range of the request on every provider serving its model; nothing is sent to a provider", body = EstimateResponse),
+        InferenceErrorResponses
+    ),
+    security(("api_key" = []), ()),
+    tag = "xrouter-app"
+)]
+pub(crate) async fn post_estimate(
+    State(state): State<AppState>,
+    principal: Option<Extension<TenantPrincipal>>,
+    headers: HeaderMap,
+    JsonBody(mut request): JsonBody<ResponsesRequest>,
+) -> Response {
+    let principal = principal.map(|Extension(principal)| principal);
+    if let Err(err) = override_from_headers(&state, ROUTE, &headers, &mut request) {
+        return error_response(err);
+    }
+    if let Err(err) = state.route_unknown_model(&mut request.model) {
+        return error_response(err);
+    }
+    let provider = state.resolve_provider_key(&request.model);
+    let provider_model = state.resolve_provider_model_id(&request.model);
+    let public_model_id = synthesize_model_id(&provider, &provider_model);
+    if let Err(rejection) = check_tenant_request(principal.as_ref(), &public_model_id) {
+        return rejection.into_response();
+    }
+    // The provider the request routes to comes first, even when the catalog does not list it.
+    let catalog = state.model_endpoints(&public_model_id);
+    let routed = catalog.iter().any(|m| m.provider == provider && m.id == provider_model);
+    let candidates = (!routed)
+        .then_some((provider, provider_model, None))
+        .into_iter()
+        .chain(catalog.into_iter().map(|m| (m.provider.clone(), m.id.clone(), Some(m))))
+        .collect::<Vec<_>>();
+
+    let mut endpoints = Vec::with_capacity(candidates.len());
+    for (provider, provider_model, descriptor) in candidates {
+        match estimate_endpoint(&state, &request, provider, provider_model, descriptor).await {
+            Ok(endpoint) => endpoints.push(endpoint),
+            Err(err) => return error_response(err),
+        }
+    }
+    let eligible = || endpoints.iter().filter(|endpoint| endpoint.excluded.is_none());
+    // A bound is only known when every eligible endpoint has one.
+    let bound = |pick: fn(&EstimateEndpoint) -> Option<&str>, fold: fn(f64, f64) -> f64| {
+        eligible()
+            .map(|endpoint| pick(endpoint).and_then(|cost| cost.parse::<f64>().ok()))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|costs| costs.into_iter().reduce(fold))
+    };
+    let min_cost = bound(|endpoint| endpoint.min_cost.as_deref(), f64::min);
+    let max_cost = bound(|endpoint| endpoint.max_cost.as_deref(), f64::max);
+    info!(
+        event = "http.request.estimated",
+        route = ROUTE,
+        model = %public_model_id,
+        endpoints = endpoints.len(),
+        eligible = eligible().count()
+    );
+    Json(EstimateResponse {
+        model: public_model_id,
+        max_output_tokens: request.max_output_tokens,
+        min_cost: min_cost.map(format_cost),
+        max_cost: max_cost.map(format_cost),
+        endpoints,
+    })
+    .into_response()
+}
+
+async fn estimate_endpoint(
+    state: &AppState,
+    request: &ResponsesRequest,
+    provider: String,
+    provider_model: String,
+    descriptor: Option<&ModelDescriptor>,
+) -> Result<EstimateEndpoint, xrouter_core::CoreError> {
+    let model_id = synthesize_model_id(&provider, &provider_model);
+    let context_length = descriptor.map(|m| m.context_length).filter(|length| *length > 0);
+    let Some(engine) = state.engines.get(&provider) else {
+        return Ok(EstimateEndpoint {
+            provider,
+            model_id,
+            input_tokens: None,
+            context_length,
+            min_cost: None,
+            max_cost: None,
+            excluded: Some("provider_disabled".to_string()),
+        });
+    };
+    let input_tokens = engine
+        .validate(ResponsesRequest { model: provider_model.clone(), ..request.clone() })
+        .await?;
+    let excluded = if validate_input_length(state, &provider, &provider_model, request).is_err() {
+        Some("context_window")
+    } else if descriptor.is_some_and(|m| !m.supports_tools)
+        && request.tools.as_ref().is_some_and(|tools| !tools.is_empty())
+    {
+        Some("tools_unsupported")
+    } else {
+        None
+    };
+    let output_allowance = request.max_output_tokens.or_else(|| {
+        let descriptor = descriptor?;
+        (descriptor.max_completion_tokens > 0)
+            .then_some(descriptor.max_completion_tokens)
+            .or_else(|| context_length.map(|length| length.saturating_sub(input_tokens)))
+    });
+    let pricing = descriptor.and_then(|m| m.pricing.as_ref());
+    Ok(EstimateEndpoint {
+        provider,
+        model_id,
+        input_tokens: Some(input_tokens),
+        context_length,
+        min_cost: token_cost(pricing, input_tokens, 0).map(format_cost),
+        max_cost: output_allowance
+            .and_then(|output| token_cost(pricing, input_tokens, output))
+            .map(format_cost),
+        excluded: excluded.map(str::to_string),
+    })
+}
@@ -1,53 +1,243 @@
-use std::{convert::Infallible, sync::Arc, time::Instant};
+use std::{
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use async_trait::async_trait;
 use axum::{
     Json,
     body::Bytes,
-    extract::{MatchedPath, State},
-    http::HeaderMap,
+    extract::{Extension, MatchedPath, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::WARNING},
     response::{IntoResponse, Response, Sse, sse::Event},
 };
 use futures::StreamExt;
+#[cfg(feature = "otel")]
 use opentelemetry::{global, propagation::Extractor, trace::Status};
-use serde_json::{Value, json};
+use serde_json::{Map, Value, json};
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::{Span, debug, field, info, info_span, trace_span, warn};
+#[cfg(feature = "otel")]
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use xrouter_contracts::{
-    ChatCompletionsRequest, ChatCompletionsResponse, ResponseEvent, ResponseOutputItem,
-    ResponsesRequest, ResponsesResponse,
+    ChatCompletionsRequest, ChatCompletionsResponse, ResponseEvent, ResponseMeta,
+    ResponseOutputItem, ResponseOutputText, ResponsesRequest, ResponsesResponse, TextFormatType,
+    Usage, upgrade_legacy_responses_input,
+};
+use xrouter_core::{
+    CoreError, ExecutionControls, ExecutionEngine, ModelPricing, OutputTokenBudget,
+    ResponseEventSink, synthesize_model_id,
 };
-use xrouter_core::{CoreError, ExecutionEngine, ResponseEventSink, synthesize_model_id};
 
 use crate::{
-    AppState, http::auth::resolve_byok_bearer, http::docs::ErrorResponse,
+    AppState,
+    http::auth::resolve_byok_bearer,
+    http::docs::{
+        ChatCompletionChunk, ErrorResponse, InferenceErrorResponses, ResponseStreamEvent,
+    },
     http::errors::error_response,
+    http::json_body::{InvalidJsonBody, JsonBody, deserialize_json_body, parse_json_body},
+    http::rate_limit::{QuotaReport, user_rate_limit_rejection},
+    request_context::RequestContext,
+    response_tee::ResponseTee,
+    stall_watchdog::{StreamProgress, watch_for_stall},
+    stream_latency::StreamLatency,
+    tenancy::{AppAttribution, Tenant, TenantPrincipal, admit_tenant_request},
+    truncation::fit_to_context,
 };
 
+pub(crate) const INPUT_CHARS_PER_TOKEN: usize = 4;
+
 struct AxumResponseEventSink {
     sender: mpsc::Sender<Result<ResponseEvent, CoreError>>,
+    retained_output_limit: Option<usize>,
+    tee: Option<(Arc<ResponseTee>, String, String)>,
+    progress: Option<Arc<StreamProgress>>,
+    output: Arc<StreamedOutput>,
 }
 
 #[async_trait]
 impl ResponseEventSink for AxumResponseEventSink {
     async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+        if let (Some((tee, model, provider)), Ok(event)) = (&self.tee, &event) {
+            tee.observe(model, provider, event);
+        }
+        if let Ok(ResponseEvent::OutputTextDelta { delta, .. }) = &event {
+            self.output.push(delta);
+        }
+        if let Some(progress) = &self.progress {
+            progress.sending();
+        }
         let _ = self.sender.send(event).await;
+        if let Some(progress) = &self.progress {
+            progress.sent();
+        }
+    }
+
+    fn retained_output_limit(&self) -> Option<usize> {
+        self.retained_output_limit
+    }
+}
+
+/// Output tokens a stream has sent, counted the way `max_output_tokens` counts them.
+struct StreamedOutput(Mutex<OutputTokenBudget>);
+
+impl StreamedOutput {
+    fn new() -> Self {
+        Self(Mutex::new(OutputTokenBudget::new(u32::MAX)))
+    }
+
+    fn push(&self, delta: &str) {
+        self.lock().admit(delta);
+    }
+
+    fn tokens(&self) -> u32 {
+        self.lock().used()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, OutputTokenBudget> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Who a stream's usage is recorded for. A cancelled generation never sends
+/// `ResponseCompleted` to its handler, so its task records the usage itself.
+#[derive(Clone, Default)]
+pub(crate) struct StreamBilling {
+    pub(crate) tenant: Option<Arc<Tenant>>,
+    pub(crate) app: Option<AppAttribution>,
+}
+
+impl StreamBilling {
+    fn record(&self, usage: &Usage) {
+        if let Some(tenant) = &self.tenant {
+            tenant.record_usage(usage, self.app.as_ref());
+        }
     }
 }
 
-fn spawn_engine_stream(
+/// Stands in for the completion routes when the instance serves the model catalog only.
+pub(crate) async fn reject_catalog_only(matched_path: MatchedPath) -> Response {
+    warn!(event = "http.request.catalog_only_rejected", route = %matched_path.as_str());
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ErrorResponse {
+            error: "inference is disabled: this xrouter instance serves the model catalog only"
+                .to_string(),
+            param: None,
+        }),
+    )
+        .into_response()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn spawn_engine_stream(
+    state: &AppState,
+    provider: &str,
     engine: Arc<ExecutionEngine>,
     request: ResponsesRequest,
     auth_bearer: Option<String>,
     forward_headers: Vec<(String, String)>,
+    controls: ExecutionControls,
+    billing: StreamBilling,
 ) -> ReceiverStream<Result<ResponseEvent, CoreError>> {
     let (tx, rx) = mpsc::channel(32);
-    let sink: Arc<dyn ResponseEventSink> = Arc::new(AxumResponseEventSink { sender: tx });
-    tokio::spawn(async move {
-        let _ =
-            engine.execute_stream_to_sink(request, None, auth_bearer, forward_headers, sink).await;
+    let public_model_id = synthesize_model_id(provider, &request.model);
+    // Estimated as the engine's tokenize stage does, for billing a cancelled generation.
+    let input_tokens = request.input.to_canonical_text().split_whitespace().count() as u32;
+    let output = Arc::new(StreamedOutput::new());
+    let stall_timeout = state.stream_stall_timeout;
+    let progress = stall_timeout.map(|_| Arc::new(StreamProgress::new()));
+    let stall_sender = tx.clone();
+    let cancellation = state.stream_tasks.cancellation(tx.clone());
+    let sink: Arc<dyn ResponseEventSink> = Arc::new(AxumResponseEventSink {
+        sender: tx,
+        retained_output_limit: state.stream_retained_output_bytes,
+        tee: state
+            .response_tee
+            .clone()
+            .map(|tee| (tee, public_model_id.clone(), provider.to_string())),
+        progress: progress.clone(),
+        output: Arc::clone(&output),
+    });
+    let provider_health = Arc::clone(&state.provider_health);
+    let provider_stats = Arc::clone(&state.provider_stats);
+    let model_stats = Arc::clone(&state.model_stats);
+    let provider = provider.to_string();
+    state.stream_tasks.spawn(async move {
+        let started_at = Instant::now();
+        let in_flight = provider_stats.start(&provider);
+        let execution = engine.execute_stream_to_sink(
+            request,
+            None,
+            auth_bearer,
+            forward_headers,
+            controls,
+            sink,
+        );
+        let supervised = async {
+            match stall_timeout.zip(progress) {
+                Some((timeout, progress)) => {
+                    match watch_for_stall(execution, &progress, timeout).await {
+                        Some(result) => result,
+                        None => {
+                            warn!(
+                                event = "http.stream.stalled",
+                                provider = %provider,
+                                model = %public_model_id,
+                                stall_timeout_seconds = timeout.as_secs_f64()
+                            );
+                            let error = CoreError::Provider(format!(
+                                "provider stalled: no stream data for {}s",
+                                timeout.as_secs_f64()
+                            ));
+                            let _ = stall_sender.send(Err(error.clone())).await;
+                            Err(error)
+                        }
+                    }
+                }
+                None => execution.await,
+            }
+        };
+        let result = match cancellation.run(supervised).await {
+            Ok(result) => result,
+            Err(reason) => {
+                drop(in_flight);
+                let output_tokens = output.tokens();
+                let usage = Usage {
+                    input_tokens,
+                    output_tokens,
+                    total_tokens: input_tokens + output_tokens,
+                    input_tokens_details: None,
+                    output_tokens_details: None,
+                };
+                info!(
+                    event = "http.stream.cancelled",
+                    provider = %provider,
+                    model = %public_model_id,
+                    reason = reason.as_str(),
+                    input_tokens = usage.input_tokens,
+                    output_tokens = usage.output_tokens,
+                    duration_ms = started_at.elapsed().as_millis() as u64
+                );
+                // A provider that was streaming answered; one cut off before its first token
+                // tells nothing about its health.
+                if output_tokens > 0 {
+                    let partial = Ok::<_, CoreError>(());
+                    provider_health.record_result(&provider, &partial);
+                    provider_stats.record_result(&provider, &partial, started_at.elapsed());
+                    model_stats.record_result(&public_model_id, &partial, started_at.elapsed());
+                }
+                billing.record(&usage);
+                return;
+            }
+        };
+        drop(in_flight);
+        provider_health.record_result(&provider, &result);
+        provider_stats.record_result(&provider, &result, started_at.elapsed());
+        model_stats.record_result(&public_model_id, &result, started_at.elapsed());
     });
     ReceiverStream::new(rx)
 }
@@ -57,19 +247,103 @@ fn spawn_engine_stream(
     path = "/api/v1/responses",
     request_body = ResponsesRequest,
     responses(
-        (status = 200, description = "Responses API result", body = ResponsesResponse),
-        (status = 400, description = "Validation or provider error", body = ErrorResponse)
+        (
+            status = 200,
+            description = "Responses API result; with `stream: true`, server-sent events whose \
+                `event:` name equals the payload `type`",
+            content(
+                (ResponsesResponse = "application/json"),
+                (ResponseStreamEvent = "text/event-stream")
+            )
+        ),
+        InferenceErrorResponses
     ),
+    security(("api_key" = []), ()),
     tag = "xrouter-app"
 )]
 pub(crate) async fn post_responses(
     State(state): State<AppState>,
     matched_path: Option<MatchedPath>,
+    principal: Option<Extension<TenantPrincipal>>,
     headers: HeaderMap,
     request_body: Bytes,
 ) -> Response {
     let started_at = Instant::now();
     let route = matched_path.as_ref().map_or("/api/v1/responses", MatchedPath::as_str).to_string();
+    let (request, legacy_input_field) = match parse_responses_body(&route, &request_body) {
+        Ok(parsed) => parsed,
+        Err(rejection) => {
+            debug!(
+                event = "http.request.invalid_json.payload",
+                route = route,
+                payload_preview = %preview_request_body(&request_body)
+            );
+            return rejection.into_response();
+        }
+    };
+    let principal = principal.map(|Extension(principal)| principal);
+    let mut response =
+        respond_to_responses_request(state, route, principal, headers, request, started_at).await;
+    if let Some(field) = legacy_input_field {
+        let warning = format!("299 - \"`{field}` is deprecated on this route; send `input`\"");
+        if let Ok(warning) = HeaderValue::from_str(&warning) {
+            response.headers_mut().insert(WARNING, warning);
+        }
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+    }
+    response
+}
+
+/// Parses a Responses request, falling back to legacy `prompt` or `messages` in place of
+/// `input`; the fallback only runs for bodies the strict parse rejects.
+fn parse_responses_body(
+    route: &str,
+    body: &[u8],
+) -> Result<(ResponsesRequest, Option<&'static str>), InvalidJsonBody> {
+    let rejection = match deserialize_json_body(body) {
+        Ok(request) => return Ok((request, None)),
+        Err(rejection) => rejection,
+    };
+    let mut fields = serde_json::from_slice::<Map<String, Value>>(body).ok();
+    let legacy_field = fields.as_mut().and_then(upgrade_legacy_responses_input);
+    let (Some(fields), Some(field)) = (fields, legacy_field) else {
+        rejection.log(route, body.len());
+        return Err(rejection);
+    };
+    info!(event = "http.request.legacy_input", route = route, field = field);
+    let request = serde_json::to_vec(&fields)
+        .map_err(|_| rejection)
+        .and_then(|body| parse_json_body(route, &body))?;
+    Ok((request, Some(field)))
+}
+
+async fn respond_to_responses_request(
+    state: AppState,
+    route: String,
+    principal: Option<TenantPrincipal>,
+    headers: HeaderMap,
+    request: ResponsesRequest,
+    started_at: Instant,
+) -> Response {
+    let mut quota = QuotaReport::default();
+    let mut response =
+        respond_with_quota(state, route, principal, headers, request, started_at, &mut quota).await;
+    quota.apply(&mut response);
+    response
+}
+
+/// The Responses handler proper; records the quotas it counts the request against in `quota`.
+async fn respond_with_quota(
+    state: AppState,
+    route: String,
+    principal: Option<TenantPrincipal>,
+    headers: HeaderMap,
+    mut request: ResponsesRequest,
+    started_at: Instant,
+    quota: &mut QuotaReport,
+) -> Response {
     let request_span = info_span!(
         "http.request",
         otel.name = "http.request",
@@ -81,38 +355,60 @@ pub(crate) async fn post_responses(
         model = field::Empty,
         provider = field::Empty,
         stream = field::Empty,
+        enduser.id = field::Empty,
+        tenant.id = field::Empty,
         input.value = field::Empty,
-        output.value = field::Empty
+        output.value = field::Empty,
+        llm.time_to_first_token_ms = field::Empty,
+        llm.inter_token_latency_ms = field::Empty
     );
     attach_parent_context(&request_span, &headers);
     let _request_span_guard = request_span.enter();
-    let mut request: ResponsesRequest = match serde_json::from_slice(&request_body) {
-        Ok(request) => request,
+    let normalized_input = request.input.to_canonical_text();
+    let context =
+        match RequestContext::resolve(&state, principal.as_ref(), &route, &headers, &mut request) {
+            Ok(context) => context,
+            Err(err) => return error_response(err),
+        };
+    let fallback_reason = match state.route_unknown_model(&mut request.model) {
+        Ok(reason) => reason,
         Err(err) => {
-            info!(
-                event = "http.request.invalid_json",
-                route = route,
-                body_bytes = request_body.len(),
-                error = %err
-            );
-            debug!(
-                event = "http.request.invalid_json.payload",
-                route = route,
-                payload_preview = %preview_request_body(&request_body)
-            );
-            return (
-                axum::http::StatusCode::UNPROCESSABLE_ENTITY,
-                Json(ErrorResponse { error: "invalid request body".to_string() }),
-            )
-                .into_response();
+            info!(event = "http.request.unknown_model", route = route, error = %err);
+            return error_response(err);
         }
     };
-    let normalized_input = request.input.to_canonical_text();
     let request_model = request.model.clone();
     let provider = state.resolve_provider_key(&request.model);
     let provider_model = state.resolve_provider_model_id(&request.model);
     let public_model_id = synthesize_model_id(&provider, &provider_model);
-    let forward_headers = extract_forward_headers(&headers, provider.as_str());
+    if let Err(err) = validate_output_controls(&state, &provider, &provider_model, &request) {
+        info!(
+            event = "http.request.invalid_output_controls",
+            route = route,
+            model = %public_model_id,
+            error = %err
+        );
+        return error_response(err);
+    }
+    let dropped_fields = state.retain_passthrough_fields(&provider, &mut request.extra);
+    if !dropped_fields.is_empty() {
+        debug!(
+            event = "http.request.passthrough_dropped",
+            route = route,
+            provider = %provider,
+            fields = ?dropped_fields
+        );
+    }
+    let forward_headers = context.forward_headers;
+    let app = AppAttribution::from_headers(&headers);
+    let (admission, tenant_quota) =
+        admit_tenant_request(principal.as_ref(), &public_model_id, route.as_str()).await;
+    quota.merge(tenant_quota);
+    let tenant = match admission {
+        Ok(tenant) => tenant,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let tenant_id = tenant.as_ref().map(|tenant| tenant.id.clone()).unwrap_or_default();
     let auth_bearer = match resolve_byok_bearer(
         &headers,
         state.byok_enabled,
@@ -125,16 +421,73 @@ pub(crate) async fn post_responses(
     request_span.record("model", public_model_id.as_str());
     request_span.record("provider", provider.as_str());
     request_span.record("stream", request.stream);
+    if tenant.is_some() {
+        request_span.record("tenant.id", tenant_id.as_str());
+    }
     request_span.record("input.value", truncate_attr_value(&normalized_input, 512));
+    if let Some(user) = request.user.as_deref() {
+        request_span.record("enduser.id", user);
+    }
+    let (rejection, user_quota) = user_rate_limit_rejection(
+        state.user_rate_limiter.as_deref(),
+        request.user.as_deref(),
+        route.as_str(),
+    )
+    .await;
+    quota.merge(user_quota);
+    if let Some(response) = rejection {
+        return response;
+    }
+    let conversation = state.conversation_budgets.clone().map(|budgets| {
+        let used_before = budgets.used_before(request.previous_response_id.as_deref());
+        (budgets, used_before)
+    });
+    if let Some((budgets, used_before)) = &conversation
+        && let Err(rejection) = budgets.admit(*used_before, request.max_output_tokens)
+    {
+        info!(
+            event = "http.request.conversation_budget_exhausted",
+            route = route,
+            model = %public_model_id,
+            error = rejection.error()
+        );
+        return rejection.into_response();
+    }
+    fit_to_context(&state, &route, &provider, &provider_model, context.truncation, &mut request)
+        .await;
+    hide_reasoning(&state, principal.as_ref(), &public_model_id, &mut request);
+    if let Err(err) = validate_input_length(&state, &provider, &provider_model, &request) {
+        info!(
+            event = "http.request.input_too_long",
+            route = route,
+            model = %public_model_id,
+            error = %err
+        );
+        return error_response(err);
+    }
+    let pricing = state.model_pricing(&provider, &provider_model);
     request.model = provider_model;
+    let restored_reasoning_items = state.reasoning_carryover.restore(&mut request).await;
+    // `store: false` clients resend reasoning themselves, so nothing is kept for them.
+    let carry_reasoning = request.store != Some(false);
     info!(
         event = "http.request.received",
         route = route,
         model = %public_model_id,
         provider = %provider,
         stream = request.stream,
-        input_chars = normalized_input.len()
+        priority = context.controls.priority.as_str(),
+        tenant_id = %tenant_id,
+        app_referer = app.as_ref().and_then(|app| app.referer.as_deref()).unwrap_or_default(),
+        app_title = app.as_ref().and_then(|app| app.title.as_deref()).unwrap_or_default(),
+        input_chars = normalized_input.len(),
+        restored_reasoning_items = restored_reasoning_items,
+        fallback_reason = fallback_reason.unwrap_or_default()
     );
+    let events = state.request_events(&route, &public_model_id, &provider, &tenant_id);
+    if let Some(events) = &events {
+        events.received(request.stream);
+    }
     debug!(
         event = "http.request.payload",
         route = route,
@@ -143,7 +496,7 @@ pub(crate) async fn post_responses(
         request_text = %normalized_input
     );
 
-    let engine = match state.resolve_engine(&request.model) {
+    let engine = match state.resolve_engine(&request_model) {
         Ok(engine) => engine,
         Err(err) => {
             warn!(
@@ -155,14 +508,28 @@ pub(crate) async fn post_responses(
                 duration_ms = started_at.elapsed().as_millis() as u64,
                 error = %err
             );
+            if let Some(events) = &events {
+                events.failed(&err.to_string(), started_at.elapsed());
+            }
             return error_response(err);
         }
     };
 
+    if let Some(events) = &events {
+        events.provider_selected(fallback_reason);
+    }
+
     if request.stream {
         let stream_route = route.clone();
         let stream_provider = provider.clone();
         let stream_request_span = request_span.clone();
+        let stream_tenant = tenant.clone();
+        let stream_app = app.clone();
+        let stream_events = events.clone();
+        let stream_carryover = carry_reasoning.then(|| state.reasoning_carryover.clone());
+        let stream_conversation = conversation.clone();
+        let mut stream_latency = StreamLatency::new(started_at);
+        let mut partial_text = state.stream_salvage_partial.then(String::new);
         let response_id = new_prefixed_id("resp_");
         let stream_item_id = "msg_0".to_string();
         info!(
@@ -203,14 +570,25 @@ pub(crate) async fn post_responses(
             }
         });
 
+        let mut sequence = ResponsesEventSequence::default();
+        let bootstrap = futures::stream::iter(vec![
+            sequence.event(created),
+            sequence.event(output_item_added),
+            sequence.event(content_part_added),
+        ]);
+
         let stream = spawn_engine_stream(
+            &state,
+            &provider,
             engine.clone(),
             request,
             auth_bearer.clone(),
             forward_headers.clone(),
+            context.controls,
+            StreamBilling { tenant: tenant.clone(), app: app.clone() },
         )
         .flat_map(move |event| {
-            let mut events = Vec::<Result<Event, Infallible>>::new();
+            let mut payloads = Vec::<Value>::new();
             if let Ok(ref mapped) = event {
                 if let Some(request_id) = response_event_request_id(mapped) {
                     stream_request_span.record("request.id", request_id);
@@ -225,27 +603,51 @@ pub(crate) async fn post_responses(
             }
             match event {
                 Ok(ResponseEvent::OutputTextDelta { delta, .. }) => {
-                    events.push(Ok(Event::default().event("response.output_text.delta").data(
-                        json!({
-                            "type": "response.output_text.delta",
-                            "output_index": 0,
-                            "item_id": "msg_0",
-                            "content_index": 0,
-                            "delta": delta
-                        })
-                        .to_string(),
-                    )));
+                    stream_latency.delta();
+                    if let Some(partial_text) = &mut partial_text {
+                        partial_text.push_str(&delta);
+                    }
+                    payloads.push(json!({
+                        "type": "response.output_text.delta",
+                        "output_index": 0,
+                        "item_id": "msg_0",
+                        "content_index": 0,
+                        "delta": delta
+                    }));
                 }
                 Ok(ResponseEvent::ReasoningDelta { delta, .. }) => {
-                    events.push(Ok(Event::default().event("response.reasoning.delta").data(
-                        json!({
-                            "type": "response.reasoning.delta",
-                            "delta": delta
-                        })
-                        .to_string(),
-                    )));
+                    stream_latency.delta();
+                    payloads.push(json!({
+                        "type": "response.reasoning.delta",
+                        "delta": delta
+                    }));
                 }
-                Ok(ResponseEvent::ResponseCompleted { output, finish_reason, usage, .. }) => {
+                Ok(ResponseEvent::ResponseCompleted {
+                    output, finish_reason, usage, meta, ..
+                }) => {
+                    if let Some(tenant) = &stream_tenant {
+                        tenant.record_usage(&usage, stream_app.as_ref());
+                    }
+                    stream_latency.record_on(&stream_request_span);
+                    if let Some(events) = &stream_events {
+                        events.completed(
+                            &finish_reason,
+                            &usage,
+                            started_at.elapsed(),
+                            Some(&stream_latency),
+                        );
+                    }
+                    if let Some(carryover) = &stream_carryover {
+                        let carryover = Arc::clone(carryover);
+                        let response_id = response_id.clone();
+                        let output = output.clone();
+                        tokio::spawn(async move {
+                            carryover.remember(&response_id, &output).await;
+                        });
+                    }
+                    let budget = stream_conversation.as_ref().map(|(budgets, used_before)| {
+                        budgets.record(&response_id, *used_before, &usage)
+                    });
                     let reasoning = extract_reasoning_from_output(&output);
                     info!(
                         event = "http.stream.completed",
@@ -258,90 +660,125 @@ pub(crate) async fn post_responses(
                         input_tokens = usage.input_tokens,
                         output_tokens = usage.output_tokens,
                         total_tokens = usage.total_tokens,
+                        time_to_first_token_ms = stream_latency.time_to_first_token_ms(),
+                        inter_token_latency_ms = stream_latency.inter_token_latency_ms(),
                         duration_ms = started_at.elapsed().as_millis() as u64
                     );
+                    let mut meta = routing_meta(&stream_provider, fallback_reason, meta.as_ref());
+                    stream_latency.apply(&mut meta);
+                    meta.cost = usage_cost(pricing.as_ref(), &usage);
                     for (output_index, item) in output.iter().enumerate() {
-                        events.push(Ok(Event::default().event("response.output_item.done").data(
-                            json!({
-                                "type": "response.output_item.done",
-                                "output_index": output_index,
-                                "item": item
-                            })
-                            .to_string(),
-                        )));
+                        payloads.push(json!({
+                            "type": "response.output_item.done",
+                            "output_index": output_index,
+                            "item": item
+                        }));
                     }
-                    events.push(Ok(Event::default().event("response.completed").data(
-                        json!({
-                            "type": "response.completed",
-                            "response": {
-                                "id": response_id,
-                                "status": "completed",
-                                "output": output,
-                                "finish_reason": finish_reason,
-                                "usage": {
-                                    "input_tokens": usage.input_tokens,
-                                    "output_tokens": usage.output_tokens,
-                                    "total_tokens": usage.total_tokens
-                                }
-                            }
-                        })
-                        .to_string(),
-                    )));
+                    payloads.push(json!({
+                        "type": "response.completed",
+                        "response": {
+                            "id": response_id,
+                            "status": "completed",
+                            "output": output,
+                            "finish_reason": finish_reason,
+                            "usage": {
+                                "input_tokens": usage.input_tokens,
+                                "output_tokens": usage.output_tokens,
+                                "total_tokens": usage.total_tokens
+                            },
+                            "meta": meta,
+                            "budget": budget
+                        }
+                    }));
                 }
                 Ok(ResponseEvent::ResponseError { message, .. }) => {
-                    stream_request_span.set_status(Status::error(message.clone()));
+                    mark_span_error(&stream_request_span, message.clone());
+                    if let Some(events) = &stream_events {
+                        events.failed(&message, started_at.elapsed());
+                    }
+                    let partial_text = partial_text.take().filter(|text| !text.is_empty());
                     warn!(
                         event = "http.stream.failed",
                         route = stream_route,
                         response_id = %response_id,
                         provider = %stream_provider,
                         duration_ms = started_at.elapsed().as_millis() as u64,
+                        salvaged_chars = partial_text.as_ref().map_or(0, String::len),
                         error = %message
                     );
-                    events.push(Ok(Event::default()
-                        .event("response.error")
-                        .data(json!({"type": "response.error", "error": message}).to_string())));
+                    payloads.extend(responses_stream_failure_events(
+                        &response_id,
+                        &message,
+                        partial_text,
+                        routing_meta(&stream_provider, fallback_reason, None),
+                    ));
                 }
                 Err(error) => {
-                    stream_request_span.set_status(Status::error(error.to_string()));
+                    mark_span_error(&stream_request_span, error.to_string());
+                    if let Some(events) = &stream_events {
+                        events.failed(&error.to_string(), started_at.elapsed());
+                    }
+                    let partial_text = partial_text.take().filter(|text| !text.is_empty());
                     warn!(
                         event = "http.stream.failed",
                         route = stream_route,
                         response_id = %response_id,
                         provider = %stream_provider,
                         duration_ms = started_at.elapsed().as_millis() as u64,
+                        salvaged_chars = partial_text.as_ref().map_or(0, String::len),
                         error = %error
                     );
-                    events.push(Ok(Event::default().event("response.error").data(
-                        json!({"type": "response.error", "error": error.to_string()}).to_string(),
-                    )));
+                    payloads.extend(responses_stream_failure_events(
+                        &response_id,
+                        &error.to_string(),
+                        partial_text,
+                        routing_meta(&stream_provider, fallback_reason, None),
+                    ));
                 }
             }
-            futures::stream::iter(events)
+            futures::stream::iter(
+                payloads.into_iter().map(|payload| sequence.event(payload)).collect::<Vec<_>>(),
+            )
         });
 
-        let bootstrap = futures::stream::iter(vec![
-            Ok::<Event, Infallible>(
-                Event::default().event("response.created").data(created.to_string()),
-            ),
-            Ok::<Event, Infallible>(
-                Event::default()
-                    .event("response.output_item.added")
-                    .data(output_item_added.to_string()),
-            ),
-            Ok::<Event, Infallible>(
-                Event::default()
-                    .event("response.content_part.added")
-                    .data(content_part_added.to_string()),
-            ),
-        ]);
         let full_stream = bootstrap.chain(stream);
-        return Sse::new(full_stream).into_response();
+        let mut response = Sse::new(full_stream).into_response();
+        insert_routing_headers(
+            &mut response,
+            &routing_meta(&provider, fallback_reason, None),
+            None,
+        );
+        return response;
     }
 
-    match run_responses_request(engine, request, auth_bearer, forward_headers).await {
+    match run_responses_request(
+        &state,
+        &provider,
+        engine,
+        request,
+        auth_bearer,
+        forward_headers,
+        context.controls,
+    )
+    .await
+    {
         Ok(mut resp) => {
+            if let Some(tenant) = &tenant {
+                tenant.record_usage(&resp.usage, app.as_ref());
+            }
+            if let Some(events) = &events {
+                events.completed(&resp.finish_reason, &resp.usage, started_at.elapsed(), None);
+            }
             resp.id = ensure_id_prefix(&resp.id, "resp_");
+            let mut meta = routing_meta(&provider, fallback_reason, resp.meta.as_ref());
+            meta.cost = usage_cost(pricing.as_ref(), &resp.usage);
+            resp.meta = Some(meta.clone());
+            if carry_reasoning {
+                state.reasoning_carryover.remember(&resp.id, &resp.output).await;
+            }
+            resp.budget = conversation
+                .as_ref()
+                .map(|(budgets, used_before)| budgets.record(&resp.id, *used_before, &resp.usage));
             request_span.record("request.id", resp.id.as_str());
             request_span.record("response.id", resp.id.as_str());
             let response_text = extract_message_text_from_output(&resp.output);
@@ -368,10 +805,16 @@ pub(crate) async fn post_responses(
                 total_tokens = resp.usage.total_tokens,
                 duration_ms = started_at.elapsed().as_millis() as u64
             );
-            Json(resp).into_response()
+            let usage = resp.usage.clone();
+            let mut response = Json(resp).into_response();
+            insert_routing_headers(&mut response, &meta, Some(&usage));
+            response
         }
         Err(err) => {
-            request_span.set_status(Status::error(err.to_string()));
+            mark_span_error(&request_span, err.to_string());
+            if let Some(events) = &events {
+                events.failed(&err.to_string(), started_at.elapsed());
+            }
             warn!(
                 event = "http.request.failed",
                 route = route,
@@ -390,15 +833,46 @@ pub(crate) async fn post_responses(
     path = "/api/v1/chat/completions",
     request_body = ChatCompletionsRequest,
     responses(
-        (status = 200, description = "Chat Completions API result", body = ChatCompletionsResponse),
-        (status = 400, description = "Validation or provider error", body = ErrorResponse)
+        (
+            status = 200,
+            description = "Chat Completions API result; with `stream: true`, server-sent \
+                `chat.completion.chunk` events ending with `data: [DONE]`",
+            content(
+                (ChatCompletionsResponse = "application/json"),
+                (ChatCompletionChunk = "text/event-stream")
+            )
+        ),
+        InferenceErrorResponses
     ),
+    security(("api_key" = []), ()),
     tag = "xrouter-app"
 )]
 pub(crate) async fn post_chat_completions(
     State(state): State<AppState>,
+    matched_path: Option<MatchedPath>,
+    principal: Option<Extension<TenantPrincipal>>,
+    headers: HeaderMap,
+    JsonBody(request): JsonBody<ChatCompletionsRequest>,
+) -> Response {
+    let route =
+        matched_path.as_ref().map_or("/api/v1/chat/completions", MatchedPath::as_str).to_string();
+    let principal = principal.map(|Extension(principal)| principal);
+    let mut quota = QuotaReport::default();
+    let mut response =
+        respond_to_chat_request(state, route, principal, headers, request, &mut quota).await;
+    quota.apply(&mut response);
+    response
+}
+
+/// The Chat Completions handler proper; records the quotas it counts the request against in
+/// `quota`.
+async fn respond_to_chat_request(
+    state: AppState,
+    route: String,
+    principal: Option<TenantPrincipal>,
     headers: HeaderMap,
-    Json(request): Json<ChatCompletionsRequest>,
+    request: ChatCompletionsRequest,
+    quota: &mut QuotaReport,
 ) -> Response {
     let started_at = Instant::now();
     let request_span = info_span!(
@@ -408,32 +882,86 @@ pub(crate) async fn post_chat_completions(
         openinference.span.kind = "CHAIN",
         request.id = field::Empty,
         response.id = field::Empty,
-        route = "/api/v1/chat/completions",
+        route = %route,
         model = field::Empty,
         provider = field::Empty,
         stream = field::Empty,
+        enduser.id = field::Empty,
+        tenant.id = field::Empty,
         input.value = field::Empty,
-        output.value = field::Empty
+        output.value = field::Empty,
+        llm.time_to_first_token_ms = field::Empty,
+        llm.inter_token_latency_ms = field::Empty
     );
     attach_parent_context(&request_span, &headers);
     let _request_span_guard = request_span.enter();
     let request_payload = request
         .messages
         .iter()
-        .map(|message| format!("{}:{}", message.role, message.content))
+        .map(|message| format!("{}:{}", message.role, message.content.to_text()))
         .collect::<Vec<_>>()
         .join("\n");
     let mut core_request = request.clone().into_responses_request();
+    let context = match RequestContext::resolve(
+        &state,
+        principal.as_ref(),
+        route.as_str(),
+        &headers,
+        &mut core_request,
+    ) {
+        Ok(context) => context,
+        Err(err) => return error_response(err),
+    };
+    let fallback_reason = match state.route_unknown_model(&mut core_request.model) {
+        Ok(reason) => reason,
+        Err(err) => {
+            info!(
+                event = "http.request.unknown_model",
+                route = route,
+                error = %err
+            );
+            return error_response(err);
+        }
+    };
     let request_model = core_request.model.clone();
     let provider = state.resolve_provider_key(&core_request.model);
     let provider_model = state.resolve_provider_model_id(&core_request.model);
     let public_model_id = synthesize_model_id(&provider, &provider_model);
-    let forward_headers = extract_forward_headers(&headers, provider.as_str());
+    if request.has_image_parts() && !state.accepts_image_input(&provider, &provider_model) {
+        warn!(
+            event = "http.request.unsupported_image_input",
+            route = route,
+            model = %public_model_id,
+            provider = %provider
+        );
+        return error_response(CoreError::Validation(format!(
+            "model {public_model_id} does not accept image input; send text-only content"
+        )));
+    }
+    let dropped_fields = state.retain_passthrough_fields(&provider, &mut core_request.extra);
+    if !dropped_fields.is_empty() {
+        debug!(
+            event = "http.request.passthrough_dropped",
+            route = route,
+            provider = %provider,
+            fields = ?dropped_fields
+        );
+    }
+    let forward_headers = context.forward_headers;
+    let app = AppAttribution::from_headers(&headers);
+    let (admission, tenant_quota) =
+        admit_tenant_request(principal.as_ref(), &public_model_id, route.as_str()).await;
+    quota.merge(tenant_quota);
+    let tenant = match admission {
+        Ok(tenant) => tenant,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let tenant_id = tenant.as_ref().map(|tenant| tenant.id.clone()).unwrap_or_default();
     let auth_bearer = match resolve_byok_bearer(
         &headers,
         state.byok_enabled,
         provider.as_str(),
-        "/api/v1/chat/completions",
+        route.as_str(),
     ) {
         Ok(token) => token,
         Err(err) => return error_response(err),
@@ -441,55 +969,131 @@ pub(crate) async fn post_chat_completions(
     request_span.record("model", public_model_id.as_str());
     request_span.record("provider", provider.as_str());
     request_span.record("stream", request.stream);
+    if tenant.is_some() {
+        request_span.record("tenant.id", tenant_id.as_str());
+    }
     request_span.record("input.value", truncate_attr_value(&request_payload, 512));
+    if let Some(user) = core_request.user.as_deref() {
+        request_span.record("enduser.id", user);
+    }
+    let (rejection, user_quota) = user_rate_limit_rejection(
+        state.user_rate_limiter.as_deref(),
+        core_request.user.as_deref(),
+        route.as_str(),
+    )
+    .await;
+    quota.merge(user_quota);
+    if let Some(response) = rejection {
+        return response;
+    }
+    fit_to_context(
+        &state,
+        route.as_str(),
+        &provider,
+        &provider_model,
+        context.truncation,
+        &mut core_request,
+    )
+    .await;
+    hide_reasoning(&state, principal.as_ref(), &public_model_id, &mut core_request);
+    if let Err(err) = validate_input_length(&state, &provider, &provider_model, &core_request) {
+        info!(
+            event = "http.request.input_too_long",
+            route = route,
+            model = %public_model_id,
+            error = %err
+        );
+        return error_response(err);
+    }
+    let pricing = state.model_pricing(&provider, &provider_model);
     core_request.model = provider_model;
     info!(
         event = "http.request.received",
-        route = "/api/v1/chat/completions",
+        route = route,
         model = %public_model_id,
         provider = %provider,
         stream = request.stream,
+        priority = context.controls.priority.as_str(),
+        tenant_id = %tenant_id,
+        app_referer = app.as_ref().and_then(|app| app.referer.as_deref()).unwrap_or_default(),
+        app_title = app.as_ref().and_then(|app| app.title.as_deref()).unwrap_or_default(),
         message_count = request.messages.len()
     );
+    let events = state.request_events(&route, &public_model_id, &provider, &tenant_id);
+    if let Some(events) = &events {
+        events.received(request.stream);
+    }
     debug!(
         event = "http.request.payload",
-        route = "/api/v1/chat/completions",
+        route = route,
         model = %request_model,
         provider = %provider,
         request_text = %request_payload
     );
-    let engine = match state.resolve_engine(&core_request.model) {
+    let engine = match state.resolve_engine(&request_model) {
         Ok(engine) => engine,
         Err(err) => {
             warn!(
                 event = "http.request.failed",
-                route = "/api/v1/chat/completions",
+                route = route,
                 model = %public_model_id,
                 provider = %provider,
                 duration_ms = started_at.elapsed().as_millis() as u64,
                 error = %err
             );
+            if let Some(events) = &events {
+                events.failed(&err.to_string(), started_at.elapsed());
+            }
             return error_response(err);
         }
     };
 
+    if let Some(events) = &events {
+        events.provider_selected(fallback_reason);
+    }
+
     if request.stream {
         let chat_completion_id = new_prefixed_id("chatcmpl_");
         info!(
             event = "http.stream.started",
-            route = "/api/v1/chat/completions",
+            route = route,
             model = %public_model_id,
             provider = %provider
         );
         let stream_provider = provider.clone();
-        let stream_route = "/api/v1/chat/completions".to_string();
+        let stream_route = route.clone();
         let stream_request_span = request_span.clone();
+        let stream_tenant = tenant.clone();
+        let stream_app = app.clone();
+        let stream_events = events.clone();
         let stream_started_at = started_at;
+        let mut stream_latency = StreamLatency::new(started_at);
+        let salvage_partial = state.stream_salvage_partial;
+        let mut streamed_text = false;
+        // Strict SDKs expect the assistant role in the first chunk.
+        let role = futures::stream::iter(vec![Ok::<Event, Infallible>(
+            Event::default().data(
+                json!({
+                    "id": chat_completion_id,
+                    "object": "chat.completion.chunk",
+                    "choices": [{
+                        "delta": {"role": "assistant", "content": ""},
+                        "index": 0,
+                        "finish_reason": Value::Null
+                    }]
+                })
+                .to_string(),
+            ),
+        )]);
         let stream = spawn_engine_stream(
+                &state,
+                &provider,
                 engine.clone(),
                 core_request,
                 auth_bearer.clone(),
                 forward_headers.clone(),
+                context.controls,
+                StreamBilling { tenant: tenant.clone(), app: app.clone() },
             ).map(
                 move |evt| {
                     if let Ok(ref mapped) = evt {
@@ -506,6 +1110,8 @@ pub(crate) async fn post_chat_completions(
                     }
                     match evt {
                         Ok(ResponseEvent::OutputTextDelta { delta, .. }) => {
+                            stream_latency.delta();
+                            streamed_text |= !delta.is_empty();
                             Ok::<Event, Infallible>(Event::default().data(
                                 json!({
                                     "id": chat_completion_id.clone(),
@@ -516,6 +1122,7 @@ pub(crate) async fn post_chat_completions(
                             ))
                         }
                         Ok(ResponseEvent::ReasoningDelta { delta, .. }) => {
+                            stream_latency.delta();
                             Ok::<Event, Infallible>(Event::default().data(
                                 json!({
                                     "id": chat_completion_id.clone(),
@@ -533,20 +1140,39 @@ pub(crate) async fn post_chat_completions(
                             id,
                             output,
                             finish_reason,
-                            ..
+                            usage,
+                            meta,
                         }) => {
+                            if let Some(tenant) = &stream_tenant {
+                                tenant.record_usage(&usage, stream_app.as_ref());
+                            }
+                            stream_latency.record_on(&stream_request_span);
+                            if let Some(events) = &stream_events {
+                                events.completed(
+                                    &finish_reason,
+                                    &usage,
+                                    stream_started_at.elapsed(),
+                                    Some(&stream_latency),
+                                );
+                            }
                             let reasoning = extract_reasoning_from_output(&output);
                             let tool_calls = extract_tool_calls_from_output(&output);
                             info!(
                                 event = "http.stream.completed",
-                                route = "/api/v1/chat/completions",
+                                route = stream_route.as_str(),
                                 response_id = %id,
                                 provider = %stream_provider,
                                 finish_reason = %finish_reason,
                                 reasoning_present = reasoning.is_some(),
                                 reasoning_chars = reasoning.as_ref().map(|it| it.len()).unwrap_or(0),
+                                time_to_first_token_ms = stream_latency.time_to_first_token_ms(),
+                                inter_token_latency_ms = stream_latency.inter_token_latency_ms(),
                                 duration_ms = stream_started_at.elapsed().as_millis() as u64
                             );
+                            let mut meta =
+                                routing_meta(&stream_provider, fallback_reason, meta.as_ref());
+                            stream_latency.apply(&mut meta);
+                            meta.cost = usage_cost(pricing.as_ref(), &usage);
                             let chunk = if let Some(tool_call) =
                                 tool_calls.as_ref().and_then(|calls| calls.first())
                             {
@@ -556,45 +1182,71 @@ pub(crate) async fn post_chat_completions(
                                     "choices": [{
                                         "delta": {"tool_calls": [{"index": 0, "id": tool_call.id, "type": tool_call.kind, "function": tool_call.function}]},
                                         "index": 0,
-                                        "finish_reason": "tool_calls"
-                                    }]
+                                        "finish_reason": finish_reason
+                                    }],
+                                    "usage": usage,
+                                    "meta": meta
                                 })
                             } else {
                                 json!({
                                     "id": chat_completion_id.clone(),
                                     "object": "chat.completion.chunk",
-                                    "choices": [{"delta": {}, "index": 0, "finish_reason": "stop"}]
+                                    "choices": [{"delta": {}, "index": 0, "finish_reason": finish_reason}],
+                                    "usage": usage,
+                                    "meta": meta
                                 })
                             };
                             Ok(Event::default().data(chunk.to_string()))
                         }
                         Ok(ResponseEvent::ResponseError { id, message }) => {
-                            stream_request_span.set_status(Status::error(message.clone()));
+                            mark_span_error(&stream_request_span, message.clone());
+                            if let Some(events) = &stream_events {
+                                events.failed(&message, stream_started_at.elapsed());
+                            }
+                            let salvaged = salvage_partial && streamed_text;
                             warn!(
                                 event = "http.stream.failed",
-                                route = "/api/v1/chat/completions",
+                                route = stream_route.as_str(),
                                 response_id = %id,
                                 provider = %stream_provider,
                                 duration_ms = stream_started_at.elapsed().as_millis() as u64,
+                                salvaged = salvaged,
                                 error = %message
                             );
                             Ok(Event::default().data(
-                                json!({"id": chat_completion_id.clone(), "error": message})
-                                    .to_string(),
+                                chat_stream_failure_chunk(
+                                    &chat_completion_id,
+                                    &message,
+                                    salvaged.then(|| {
+                                        routing_meta(&stream_provider, fallback_reason, None)
+                                    }),
+                                )
+                                .to_string(),
                             ))
                         }
                         Err(error) => {
-                            stream_request_span.set_status(Status::error(error.to_string()));
+                            mark_span_error(&stream_request_span, error.to_string());
+                            if let Some(events) = &stream_events {
+                                events.failed(&error.to_string(), stream_started_at.elapsed());
+                            }
+                            let salvaged = salvage_partial && streamed_text;
                             warn!(
                                 event = "http.stream.failed",
-                                route = "/api/v1/chat/completions",
+                                route = stream_route.as_str(),
                                 provider = %stream_provider,
                                 duration_ms = stream_started_at.elapsed().as_millis() as u64,
+                                salvaged = salvaged,
                                 error = %error
                             );
                             Ok(Event::default().data(
-                                json!({"id": chat_completion_id.clone(), "error": error.to_string()})
-                                    .to_string(),
+                                chat_stream_failure_chunk(
+                                    &chat_completion_id,
+                                    &error.to_string(),
+                                    salvaged.then(|| {
+                                        routing_meta(&stream_provider, fallback_reason, None)
+                                    }),
+                                )
+                                .to_string(),
                             ))
                         }
                     }
@@ -603,12 +1255,37 @@ pub(crate) async fn post_chat_completions(
 
         let done =
             futures::stream::iter(vec![Ok::<Event, Infallible>(Event::default().data("[DONE]"))]);
-        return Sse::new(stream.chain(done)).into_response();
+        let mut response = Sse::new(role.chain(stream).chain(done)).into_response();
+        insert_routing_headers(
+            &mut response,
+            &routing_meta(&provider, fallback_reason, None),
+            None,
+        );
+        return response;
     }
 
-    match run_responses_request(engine, core_request, auth_bearer, forward_headers).await {
+    match run_responses_request(
+        &state,
+        &provider,
+        engine,
+        core_request,
+        auth_bearer,
+        forward_headers,
+        context.controls,
+    )
+    .await
+    {
         Ok(mut resp) => {
+            if let Some(tenant) = &tenant {
+                tenant.record_usage(&resp.usage, app.as_ref());
+            }
+            if let Some(events) = &events {
+                events.completed(&resp.finish_reason, &resp.usage, started_at.elapsed(), None);
+            }
             resp.id = ensure_id_prefix(&resp.id, "resp_");
+            let mut meta = routing_meta(&provider, fallback_reason, resp.meta.as_ref());
+            meta.cost = usage_cost(pricing.as_ref(), &resp.usage);
+            resp.meta = Some(meta);
             request_span.record("request.id", resp.id.as_str());
             request_span.record("response.id", resp.id.as_str());
             let response_text = extract_message_text_from_output(&resp.output);
@@ -616,14 +1293,14 @@ pub(crate) async fn post_chat_completions(
             let reasoning = extract_reasoning_from_output(&resp.output);
             debug!(
                 event = "http.response.payload",
-                route = "/api/v1/chat/completions",
+                route = route,
                 model = %request_model,
                 provider = %provider,
                 response_text = %response_text
             );
             info!(
                 event = "http.request.succeeded",
-                route = "/api/v1/chat/completions",
+                route = route,
                 model = %request_model,
                 provider = %provider,
                 status = %resp.status,
@@ -637,13 +1314,20 @@ pub(crate) async fn post_chat_completions(
             );
             let mut chat = ChatCompletionsResponse::from_responses(resp);
             chat.id = ensure_id_prefix(&chat.id, "chatcmpl_");
-            Json(chat).into_response()
+            let meta = chat.meta.clone().unwrap_or_default();
+            let usage = chat.usage.clone();
+            let mut response = Json(chat).into_response();
+            insert_routing_headers(&mut response, &meta, Some(&usage));
+            response
         }
         Err(err) => {
-            request_span.set_status(Status::error(err.to_string()));
+            mark_span_error(&request_span, err.to_string());
+            if let Some(events) = &events {
+                events.failed(&err.to_string(), started_at.elapsed());
+            }
             warn!(
                 event = "http.request.failed",
-                route = "/api/v1/chat/completions",
+                route = route,
                 model = %request_model,
                 provider = %provider,
                 duration_ms = started_at.elapsed().as_millis() as u64,
@@ -654,36 +1338,255 @@ pub(crate) async fn post_chat_completions(
     }
 }
 
-async fn run_responses_request(
+/// Leaves reasoning out of the response for models in `XR_HIDE_REASONING_MODELS` and managed
+/// keys with `hide_reasoning`; requested effort and budget still reach the provider.
+fn hide_reasoning(
+    state: &AppState,
+    principal: Option<&TenantPrincipal>,
+    public_model_id: &str,
+    request: &mut ResponsesRequest,
+) {
+    let hidden = state.hide_reasoning_models.contains(public_model_id)
+        || principal
+            .and_then(|principal| principal.key.as_ref())
+            .is_some_and(|key| key.hide_reasoning);
+    if hidden {
+        request.reasoning.get_or_insert_default().exclude = Some(true);
+    }
+}
+
+pub(crate) async fn run_responses_request(
+    state: &AppState,
+    provider: &str,
     engine: Arc<ExecutionEngine>,
     request: ResponsesRequest,
     auth_bearer: Option<String>,
     forward_headers: Vec<(String, String)>,
+    controls: ExecutionControls,
 ) -> Result<ResponsesResponse, CoreError> {
-    engine.execute_with_auth(request, auth_bearer, forward_headers).await
+    let public_model_id = synthesize_model_id(provider, &request.model);
+    let started_at = Instant::now();
+    let in_flight = state.provider_stats.start(provider);
+    let result = engine.execute_with_auth(request, auth_bearer, forward_headers, controls).await;
+    drop(in_flight);
+    state.provider_health.record_result(provider, &result);
+    state.provider_stats.record_result(provider, &result, started_at.elapsed());
+    state.model_stats.record_result(&public_model_id, &result, started_at.elapsed());
+    if let (Some(tee), Ok(response)) = (&state.response_tee, &result) {
+        tee.completed(
+            &response.id,
+            &public_model_id,
+            provider,
+            &response.output,
+            &response.finish_reason,
+            &response.usage,
+        );
+    }
+    result
+}
+
+/// Rejects `text.format`/`modalities` the target model cannot honour.
+pub(crate) fn validate_output_controls(
+    state: &AppState,
+    provider: &str,
+    provider_model: &str,
+    request: &ResponsesRequest,
+) -> Result<(), CoreError> {
+    if let Some(format) = request.text.as_ref().and_then(|text| text.format.as_ref())
+        && format.kind == TextFormatType::JsonSchema
+        && format.schema.is_none()
+    {
+        return Err(CoreError::Validation("text.format json_schema requires a schema".to_string()));
+    }
+    for modality in request.modalities.iter().flatten() {
+        if !state.supports_output_modality(provider, provider_model, modality) {
+            return Err(CoreError::Validation(format!(
+                "model {} does not support output modality `{modality}`",
+                synthesize_model_id(provider, provider_model)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects input that cannot fit the model's context window. Tokens are estimated at four
+/// characters each, which undercounts most tokenizers, so only clearly oversized input fails.
+pub(crate) fn validate_input_length(
+    state: &AppState,
+    provider: &str,
+    provider_model: &str,
+    request: &ResponsesRequest,
+) -> Result<(), CoreError> {
+    let Some(context_length) = state.context_length(provider, provider_model) else {
+        return Ok(());
+    };
+    let chars = request.input.to_canonical_text().chars().count()
+        + request.instructions.as_deref().map_or(0, |text| text.chars().count());
+    let estimated_tokens = chars.div_ceil(INPUT_CHARS_PER_TOKEN);
+    if estimated_tokens <= context_length as usize {
+        return Ok(());
+    }
+    Err(CoreError::InvalidParam {
+        param: "input".to_string(),
+        message: format!(
+            "input is about {estimated_tokens} tokens ({chars} characters), over the \
+             {context_length}-token context window of {}",
+            synthesize_model_id(provider, provider_model)
+        ),
+    })
 }
 
-fn extract_forward_headers(headers: &HeaderMap, provider: &str) -> Vec<(String, String)> {
-    if provider != "openrouter" {
-        return Vec::new();
+/// Names the provider that served the request and why routing picked it, on top of the
+/// attempt count and upstream id reported by the engine.
+pub(crate) fn routing_meta(
+    provider: &str,
+    fallback_reason: Option<&str>,
+    engine_meta: Option<&ResponseMeta>,
+) -> ResponseMeta {
+    ResponseMeta {
+        provider: Some(provider.to_string()),
+        attempts: engine_meta.map_or(0, |meta| meta.attempts),
+        fallback_reason: fallback_reason.map(str::to_string),
+        provider_response_id: engine_meta.and_then(|meta| meta.provider_response_id.clone()),
+        upstream_provider: engine_meta.and_then(|meta| meta.upstream_provider.clone()),
+        time_to_first_token_ms: None,
+        inter_token_latency_ms: None,
+        cost: None,
     }
+}
 
-    const OPENROUTER_FORWARD_HEADERS: [&str; 4] =
-        ["HTTP-Referer", "X-OpenRouter-Title", "X-Title", "X-OpenRouter-Categories"];
+/// `usage` priced per token at `pricing`, see [`token_cost`].
+fn usage_cost(pricing: Option<&ModelPricing>, usage: &Usage) -> Option<String> {
+    token_cost(pricing, usage.input_tokens, usage.output_tokens).map(format_cost)
+}
 
-    OPENROUTER_FORWARD_HEADERS
-        .iter()
-        .filter_map(|name| {
-            headers
-                .get(*name)
-                .and_then(|value| value.to_str().ok())
-                .map(|value| ((*name).to_string(), value.to_string()))
-        })
-        .collect()
+/// Tokens priced at `pricing`; `None` when the pricing is missing or not a non-negative
+/// decimal (OpenRouter marks variable pricing with `-1`).
+pub(crate) fn token_cost(
+    pricing: Option<&ModelPricing>,
+    input_tokens: u32,
+    output_tokens: u32,
+) -> Option<f64> {
+    let pricing = pricing?;
+    let rate = |price: &str| price.trim().parse::<f64>().ok().filter(|rate| *rate >= 0.0);
+    Some(
+        f64::from(input_tokens) * rate(&pricing.prompt)?
+            + f64::from(output_tokens) * rate(&pricing.completion)?,
+    )
 }
 
+/// A cost with trailing zeros trimmed.
+pub(crate) fn format_cost(cost: f64) -> String {
+    let cost = format!("{cost:.12}");
+    cost.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Numbers the events of one `/responses` stream in the order they are sent, as strict SDKs
+/// expect every event to carry a `sequence_number`.
+#[derive(Debug, Default)]
+struct ResponsesEventSequence {
+    next: u64,
+}
+
+impl ResponsesEventSequence {
+    /// SSE event named after the payload `type`.
+    fn event(&mut self, mut payload: Value) -> Result<Event, Infallible> {
+        payload["sequence_number"] = json!(self.next);
+        self.next += 1;
+        let kind = payload["type"].as_str().unwrap_or_default().to_string();
+        Ok(Event::default().event(kind).data(payload.to_string()))
+    }
+}
+
+/// Payloads ending a failed `/responses` stream: a `response.error`, or with salvaged
+/// `partial_text` an incomplete `response.completed` carrying it.
+fn responses_stream_failure_events(
+    response_id: &str,
+    error: &str,
+    partial_text: Option<String>,
+    meta: ResponseMeta,
+) -> Vec<Value> {
+    let Some(text) = partial_text else {
+        return vec![json!({"type": "response.error", "error": error})];
+    };
+    let item = ResponseOutputItem::Message {
+        id: "msg_0".to_string(),
+        role: "assistant".to_string(),
+        content: vec![ResponseOutputText {
+            kind: "output_text".to_string(),
+            text,
+            annotations: Vec::new(),
+        }],
+    };
+    vec![
+        json!({"type": "response.output_item.done", "output_index": 0, "item": item}),
+        json!({
+            "type": "response.completed",
+            "response": {
+                "id": response_id,
+                "status": "incomplete",
+                "output": [item],
+                "finish_reason": "error",
+                "error": error,
+                "meta": meta
+            }
+        }),
+    ]
+}
+
+/// Last chunk of a failed Chat Completions stream; with salvage `meta`, a regular final chunk
+/// with `finish_reason: "error"` so the streamed text reads as a finished answer.
+fn chat_stream_failure_chunk(
+    chat_completion_id: &str,
+    error: &str,
+    salvage_meta: Option<ResponseMeta>,
+) -> Value {
+    match salvage_meta {
+        Some(meta) => json!({
+            "id": chat_completion_id,
+            "object": "chat.completion.chunk",
+            "choices": [{"delta": {}, "index": 0, "finish_reason": "error"}],
+            "error": error,
+            "meta": meta
+        }),
+        None => json!({"id": chat_completion_id, "error": error}),
+    }
+}
+
+/// Mirrors `meta` and `usage` into `x-xrouter-*` headers; streams set them before any attempt
+/// is known and report usage and cost in their final event instead.
+fn insert_routing_headers(response: &mut Response, meta: &ResponseMeta, usage: Option<&Usage>) {
+    let headers = response.headers_mut();
+    let mut insert = |name: &'static str, value: &str| {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    };
+    if let Some(provider) = meta.provider.as_deref() {
+        insert("x-xrouter-provider", provider);
+    }
+    if meta.attempts > 0 {
+        insert("x-xrouter-attempts", &meta.attempts.to_string());
+    }
+    if let Some(fallback_reason) = meta.fallback_reason.as_deref() {
+        insert("x-xrouter-fallback-reason", fallback_reason);
+    }
+    if let Some(upstream_provider) = meta.upstream_provider.as_deref() {
+        insert("x-xrouter-upstream-provider", upstream_provider);
+    }
+    if let Some(usage) = usage {
+        insert("x-xrouter-input-tokens", &usage.input_tokens.to_string());
+        insert("x-xrouter-output-tokens", &usage.output_tokens.to_string());
+    }
+    if let Some(cost) = meta.cost.as_deref() {
+        insert("x-xrouter-cost", cost);
+    }
+}
+
+#[cfg(feature = "otel")]
 struct HeaderMapExtractor<'a>(&'a HeaderMap);
 
+#[cfg(feature = "otel")]
 impl<'a> Extractor for HeaderMapExtractor<'a> {
     fn get(&self, key: &str) -> Option<&str> {
         self.0.get(key).and_then(|value| value.to_str().ok())
@@ -694,6 +1597,7 @@ impl<'a> Extractor for HeaderMapExtractor<'a> {
     }
 }
 
+#[cfg(feature = "otel")]
 fn attach_parent_context(span: &Span, headers: &HeaderMap) {
     global::get_text_map_propagator(|propagator| {
         let context = propagator.extract(&HeaderMapExtractor(headers));
@@ -701,6 +1605,17 @@ fn attach_parent_context(span: &Span, headers: &HeaderMap) {
     });
 }
 
+#[cfg(not(feature = "otel"))]
+fn attach_parent_context(_span: &Span, _headers: &HeaderMap) {}
+
+#[cfg(feature = "otel")]
+fn mark_span_error(span: &Span, message: String) {
+    span.set_status(Status::error(message));
+}
+
+#[cfg(not(feature = "otel"))]
+fn mark_span_error(_span: &Span, _message: String) {}
+
 fn response_event_otel_name(event: &ResponseEvent) -> &'static str {
     match event {
         ResponseEvent::OutputTextDelta { .. } => "output_text_delta",
@@ -757,7 +1672,7 @@ fn first_function_tool_name(output: &[ResponseOutputItem]) -> Option<&str> {
     })
 }
 
-fn ensure_id_prefix(id: &str, prefix: &str) -> String {
+pub(crate) fn ensure_id_prefix(id: &str, prefix: &str) -> String {
     if id.starts_with(prefix) { id.to_string() } else { format!("{prefix}{id}") }
 }
 
@@ -787,7 +1702,7 @@ fn truncate_attr_value(text: &str, max_chars: usize) -> String {
     out
 }
 
-fn extract_message_text_from_output(output: &[ResponseOutputItem]) -> String {
+pub(crate) fn extract_message_text_from_output(output: &[ResponseOutputItem]) -> String {
     output
         .iter()
         .find_map(|item| {
@@ -1,2 +1,14 @@
+pub(crate) mod admin_bandits;
+pub(crate) mod admin_chaos;
+pub(crate) mod admin_health;
+pub(crate) mod admin_keys;
+pub(crate) mod admin_normalize;
+pub(crate) mod admin_providers;
+pub(crate) mod admin_scheduling;
+pub(crate) mod admin_transcripts;
 pub(crate) mod basic;
+pub(crate) mod completions;
+pub(crate) mod estimate;
 pub(crate) mod inference;
+pub(crate) mod usage;
+pub(crate) mod validate;
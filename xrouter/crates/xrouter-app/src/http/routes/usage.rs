@@ -0,0 +1,45 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::info;
+
+use crate::{
+    AppState,
+    http::docs::{ErrorResponse, TenantUsageResponse},
+    tenancy::authenticate_tenant,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/usage",
+    responses(
+        (status = 200, description = "Usage aggregated for the calling tenant", body = TenantUsageResponse),
+        (status = 401, description = "Missing or invalid API key", body = ErrorResponse),
+        (status = 404, description = "Tenancy is not configured", body = ErrorResponse)
+    ),
+    security(("api_key" = [])),
+    tag = "xrouter-app"
+)]
+pub(crate) async fn get_usage(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let Some(registry) = state.tenants.as_deref() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "tenancy is not configured".to_string(), param: None }),
+        )
+            .into_response();
+    };
+    match authenticate_tenant(registry, &headers, "/api/v1/usage").await {
+        Ok(principal) => {
+            info!(
+                event = "http.usage.served",
+                route = "/api/v1/usage",
+                tenant_id = %principal.tenant.id
+            );
+            Json(principal.tenant.usage_snapshot()).into_response()
+        }
+        Err(rejection) => rejection.into_response(),
+    }
+}
@@ -0,0 +1,107 @@
+use axum::{
+    Json,
+    extract::{Extension, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+use tracing::info;
+use xrouter_contracts::ResponsesRequest;
+use xrouter_core::synthesize_model_id;
+
+use crate::{
+    AppState,
+    config::TruncationStrategy,
+    http::{
+        auth::resolve_byok_bearer,
+        docs::{InferenceErrorResponses, ValidateResponse},
+        errors::error_response,
+        json_body::JsonBody,
+        routes::inference::{
+            format_cost, token_cost, validate_input_length, validate_output_controls,
+        },
+    },
+    request_context::RequestContext,
+    tenancy::{TenantPrincipal, check_tenant_request},
+};
+
+const ROUTE: &str = "/api/v1/validate";
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/validate",
+    request_body = ResponsesRequest,
+    responses(
+        (status = 200, description = "`/api/v1/responses` would accept the request; nothing is sent to the provider", body = ValidateResponse),
+        InferenceErrorResponses
+    ),
+    security(("api_key" = []), ()),
+    tag = "xrouter-app"
+)]
+pub(crate) async fn post_validate(
+    State(state): State<AppState>,
+    principal: Option<Extension<TenantPrincipal>>,
+    headers: HeaderMap,
+    JsonBody(mut request): JsonBody<ResponsesRequest>,
+) -> Response {
+    let principal = principal.map(|Extension(principal)| principal);
+    let context =
+        match RequestContext::resolve(&state, principal.as_ref(), ROUTE, &headers, &mut request) {
+            Ok(context) => context,
+            Err(err) => return error_response(err),
+        };
+    let fallback_reason = match state.route_unknown_model(&mut request.model) {
+        Ok(reason) => reason,
+        Err(err) => return error_response(err),
+    };
+    let provider = state.resolve_provider_key(&request.model);
+    let provider_model = state.resolve_provider_model_id(&request.model);
+    let public_model_id = synthesize_model_id(&provider, &provider_model);
+    if let Err(err) = validate_output_controls(&state, &provider, &provider_model, &request) {
+        return error_response(err);
+    }
+    let dropped_fields = state.retain_passthrough_fields(&provider, &mut request.extra);
+    if let Err(rejection) = check_tenant_request(principal.as_ref(), &public_model_id) {
+        return rejection.into_response();
+    }
+    if let Err(err) = resolve_byok_bearer(&headers, state.byok_enabled, &provider, ROUTE) {
+        return error_response(err);
+    }
+    let truncation = match validate_input_length(&state, &provider, &provider_model, &request) {
+        Ok(()) => None,
+        Err(_) if context.truncation != TruncationStrategy::Off => {
+            Some(context.truncation.as_str().to_string())
+        }
+        Err(err) => return error_response(err),
+    };
+    let engine = match state.resolve_engine(&request.model) {
+        Ok(engine) => engine,
+        Err(err) => return error_response(err),
+    };
+    let pricing = state.model_pricing(&provider, &provider_model);
+    let context_length = state.context_length(&provider, &provider_model);
+    let output_tokens = request.max_output_tokens.unwrap_or(0);
+    request.model = provider_model;
+    let input_tokens = match engine.validate(request).await {
+        Ok(tokens) => tokens,
+        Err(err) => return error_response(err),
+    };
+    let estimated_cost = token_cost(pricing.as_ref(), input_tokens, output_tokens).map(format_cost);
+    info!(
+        event = "http.request.validated",
+        route = ROUTE,
+        model = %public_model_id,
+        provider = %provider,
+        input_tokens = input_tokens
+    );
+    Json(ValidateResponse {
+        model: public_model_id,
+        provider,
+        fallback_reason: fallback_reason.map(str::to_string),
+        input_tokens,
+        context_length,
+        truncation,
+        estimated_cost,
+        dropped_fields,
+    })
+    .into_response()
+}
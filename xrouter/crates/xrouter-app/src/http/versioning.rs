@@ -0,0 +1,114 @@
+use axum::{
+    Json,
+    body::to_bytes,
+    extract::Request,
+    http::{HeaderValue, StatusCode, header::CONTENT_LENGTH},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use crate::http::docs::ErrorResponse;
+
+pub(crate) const VERSION_HEADER: &str = "x-xrouter-version";
+
+/// Public API version, chosen by the route prefix. `/v1` shapes are frozen; `/v2` is where
+/// response and error shapes may evolve, over the same handlers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    /// `None` for unversioned paths such as `/health` and the admin API.
+    pub(crate) fn from_path(path: &str) -> Option<Self> {
+        let path = path.strip_prefix("/api").unwrap_or(path);
+        if path.starts_with("/v1/") {
+            Some(Self::V1)
+        } else if path.starts_with("/v2/") {
+            Some(Self::V2)
+        } else {
+            None
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+        }
+    }
+}
+
+/// Tags versioned responses with `x-xrouter-version` and gives `/v2` errors their envelope.
+pub(crate) async fn serialize_for_version(request: Request, next: Next) -> Response {
+    let Some(version) = ApiVersion::from_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let mut response = next.run(request).await;
+    if version == ApiVersion::V2 && is_json_error(&response) {
+        response = v2_error(response).await;
+    }
+    response.headers_mut().insert(VERSION_HEADER, HeaderValue::from_static(version.as_str()));
+    response
+}
+
+fn is_json_error(response: &Response) -> bool {
+    (response.status().is_client_error() || response.status().is_server_error())
+        && response
+            .headers()
+            .get(axum::http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/json"))
+}
+
+/// `{"error": "...", "param": ...}` becomes
+/// `{"error": {"message": "...", "type": "...", "param": ..., "code": <status>}}`.
+async fn v2_error(response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let bytes = to_bytes(body, usize::MAX).await.unwrap_or_default();
+    let Ok(error) = serde_json::from_slice::<ErrorResponse>(&bytes) else {
+        return Response::from_parts(parts, bytes.into());
+    };
+    parts.headers.remove(CONTENT_LENGTH);
+    let envelope = json!({
+        "error": {
+            "message": error.error,
+            "type": error_type(parts.status),
+            "param": error.param,
+            "code": parts.status.as_u16(),
+        }
+    });
+    let mut rewritten = Json(envelope).into_response();
+    *rewritten.status_mut() = parts.status;
+    rewritten.headers_mut().extend(parts.headers);
+    rewritten
+}
+
+fn error_type(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::UNAUTHORIZED => "authentication_error",
+        StatusCode::FORBIDDEN => "permission_error",
+        StatusCode::NOT_FOUND => "not_found_error",
+        StatusCode::PAYLOAD_TOO_LARGE => "request_too_large",
+        StatusCode::TOO_MANY_REQUESTS => "rate_limit_error",
+        status if status.is_server_error() => "api_error",
+        _ => "invalid_request_error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiVersion;
+
+    #[test]
+    fn versions_follow_the_route_prefix() {
+        assert_eq!(ApiVersion::from_path("/api/v1/responses"), Some(ApiVersion::V1));
+        assert_eq!(ApiVersion::from_path("/v2/chat/completions"), Some(ApiVersion::V2));
+        assert_eq!(ApiVersion::from_path("/api/v2/models"), Some(ApiVersion::V2));
+        assert_eq!(ApiVersion::from_path("/health"), None);
+        assert_eq!(ApiVersion::from_path("/admin/v1/keys"), None);
+        assert_eq!(ApiVersion::from_path("/api/v10/models"), None);
+    }
+}
@@ -1,9 +1,43 @@
+mod alerts;
+mod api_keys;
 mod app_state;
+mod bandit;
 pub mod config;
+pub mod config_file;
+mod conversation_budget;
+mod coordination;
+mod embedded;
+mod event_bus;
+mod header_overrides;
 mod http;
+mod migrations;
+mod model_stats;
+mod probe;
+mod provider_health;
+mod provider_stats;
+mod racing;
+mod reasoning_carryover;
+mod record_queue;
+mod request_context;
+mod response_store;
+mod response_tee;
+mod scheduling;
+pub mod secrets;
+mod stall_watchdog;
 mod startup;
+mod storage;
+mod stream_latency;
+mod stream_tasks;
+mod tenancy;
+mod truncation;
+mod upstream_limits;
+mod usage_export;
+mod usage_ledger;
 pub use app_state::AppState;
+pub use embedded::{XRouter, XRouterBuilder};
 pub use http::docs::build_router;
+pub use migrations::{MigrationError, migrate_database, prepare_database};
+pub use probe::{ProbeReport, probe_provider};
 pub use startup::app_builder::AppBuilder;
 
 #[cfg(test)]
@@ -22,8 +56,11 @@ mod tests {
     use serde_json::{Map, Value, json};
     use tower::ServiceExt;
 
-    use crate::startup::model_catalog_remote::fetch_openrouter_models;
-    use crate::{AppBuilder, AppState, build_router, http::errors::error_response};
+    use crate::startup::model_catalog_remote::{catalog_http_client, fetch_openrouter_models};
+    use crate::{
+        AppBuilder, AppState, build_router, config::UnknownModelConfig,
+        http::errors::error_response,
+    };
     use xrouter_clients_openai::models::{
         OpenRouterModelsResponse, XrouterProviderModelsResponse, build_models_from_registry,
         map_openrouter_models, map_xrouter_models,
@@ -58,8 +95,15 @@ mod tests {
                 output_tokens: 1,
                 reasoning: None,
                 reasoning_details: None,
+                reasoning_encrypted_content: None,
                 tool_calls: None,
+                annotations: None,
                 emitted_live: false,
+                finish_reason: None,
+                usage: None,
+                upstream_attempts: 1,
+                provider_response_id: None,
+                upstream_provider: None,
             })
         }
     }
@@ -86,6 +130,9 @@ mod tests {
                 top_provider_context_length: 128000,
                 is_moderated: true,
                 max_completion_tokens: 16384,
+                supports_tools: true,
+                pricing: None,
+                supported_parameters: Vec::new(),
             }],
             engines,
         );
@@ -155,15 +202,18 @@ mod tests {
         assert_eq!(model.instruct_type, "none");
     }
 
-    #[test]
-    fn fetch_openrouter_models_returns_none_when_request_fails() {
+    #[tokio::test]
+    async fn fetch_openrouter_models_returns_none_when_request_fails() {
         let provider = crate::config::ProviderConfig {
             enabled: true,
             api_key: None,
             base_url: Some("http://127.0.0.1:0".to_string()),
             project: None,
+            http: Default::default(),
         };
-        let models = fetch_openrouter_models(&provider, &["openai/gpt-5.2".to_string()], 1);
+        let client = catalog_http_client(1, 1, false);
+        let models =
+            fetch_openrouter_models(&client, &provider, &["openai/gpt-5.2".to_string()]).await;
         assert!(models.is_none());
     }
 
@@ -519,8 +569,8 @@ path=/api/v1/responses
 body={"model":"deepseek/deepseek-chat","input":[1],"stream":false}
 "#,
                 r#"
-status=422
-json.error=invalid request body
+status=400
+json.error=invalid request body: input: data did not match any variant of untagged enum ResponsesInput
 "#,
             ),
             (
@@ -660,6 +710,782 @@ json.first_id=<none>
         assert!(id.starts_with("resp_"), "unexpected id: {id}");
     }
 
+    #[tokio::test]
+    async fn responses_user_rate_limit_rejects_requests_over_limit_per_user() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.user_rate_limit_per_minute = Some(1);
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let send = |user: &str| {
+            let app = app.clone();
+            let body = format!(
+                r#"{{"model":"deepseek/deepseek-chat","input":"hello","stream":false,"user":"{user}"}}"#
+            );
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete")
+                .status()
+            }
+        };
+
+        assert_eq!(send("alice").await, StatusCode::OK);
+        assert_eq!(send("alice").await, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(send("bob").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn requests_over_the_hop_limit_are_rejected_as_loops() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.max_hops = 2;
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let send = |hop: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .header("x-xrouter-hop", hop)
+                        .body(Body::from(
+                            r#"{"model":"deepseek/deepseek-chat","input":"hello","stream":false}"#,
+                        ))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete")
+                .status()
+            }
+        };
+
+        assert_eq!(send("2").await, StatusCode::OK);
+        assert_eq!(send("3").await, StatusCode::LOOP_DETECTED);
+        assert_eq!(send("-1").await, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn upstream_timeout_and_retry_headers_are_bounded_by_server_config() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.upstream_limits =
+            crate::config::UpstreamLimitsConfig { max_timeout_ms: 30_000, max_retries: 1 };
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let send = |timeout_ms: &'static str, retries: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/chat/completions")
+                        .header("content-type", "application/json")
+                        .header("x-xrouter-provider-timeout-ms", timeout_ms)
+                        .header("x-xrouter-max-retries", retries)
+                        .body(Body::from(
+                            r#"{"model":"deepseek/deepseek-chat","messages":[{"role":"user","content":"hello"}]}"#,
+                        ))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete")
+                .status()
+            }
+        };
+
+        assert_eq!(send("30000", "1").await, StatusCode::OK);
+        assert_eq!(send("30001", "1").await, StatusCode::BAD_REQUEST);
+        assert_eq!(send("500", "2").await, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn chat_responses_carry_openai_rate_limit_headers_of_the_tightest_limit() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.user_rate_limit_per_minute = Some(5);
+        config.tenants = vec![crate::config::TenantConfig {
+            organization: "acme".to_string(),
+            project: "web".to_string(),
+            api_keys: vec!["tenant-key".to_string()],
+            allowed_models: Vec::new(),
+            denied_models: Vec::new(),
+            rate_limit_per_minute: Some(2),
+            token_budget: Some(100_000),
+        }];
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let send = || {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/chat/completions")
+                        .header("content-type", "application/json")
+                        .header("authorization", "Bearer tenant-key")
+                        .body(Body::from(
+                            r#"{"model":"deepseek/deepseek-chat","messages":[{"role":"user","content":"hello"}],"user":"alice"}"#,
+                        ))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete")
+            }
+        };
+        let header = |response: &axum::response::Response, name: &str| {
+            response.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+        };
+
+        let first = send().await;
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(header(&first, "x-ratelimit-limit-requests").as_deref(), Some("2"));
+        assert_eq!(header(&first, "x-ratelimit-remaining-requests").as_deref(), Some("1"));
+        assert!(
+            header(&first, "x-ratelimit-reset-requests").is_some_and(|reset| reset.ends_with('s'))
+        );
+        assert_eq!(header(&first, "x-ratelimit-limit-tokens").as_deref(), Some("100000"));
+        assert_eq!(header(&first, "x-ratelimit-remaining-tokens").as_deref(), Some("100000"));
+        assert_eq!(header(&first, "retry-after"), None);
+
+        let second = send().await;
+        let remaining_tokens = header(&second, "x-ratelimit-remaining-tokens")
+            .and_then(|value| value.parse::<u64>().ok())
+            .expect("remaining tokens must be reported");
+        assert!(remaining_tokens < 100_000);
+
+        let rejected = send().await;
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&rejected, "x-ratelimit-remaining-requests").as_deref(), Some("0"));
+        let retry_after = header(&rejected, "retry-after")
+            .and_then(|value| value.parse::<u64>().ok())
+            .expect("retry-after must be set");
+        assert!((1..=60).contains(&retry_after));
+    }
+
+    #[tokio::test]
+    async fn tenant_api_key_scopes_requests_and_usage() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.tenants = vec![crate::config::TenantConfig {
+            organization: "acme".to_string(),
+            project: "web".to_string(),
+            api_keys: vec!["tenant-key".to_string()],
+            allowed_models: vec!["deepseek/deepseek-chat".to_string()],
+            denied_models: Vec::new(),
+            rate_limit_per_minute: None,
+            token_budget: None,
+        }];
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let send = |model: &str, bearer: Option<&str>| {
+            let app = app.clone();
+            let mut builder = Request::builder()
+                .method("POST")
+                .uri("/api/v1/responses")
+                .header("content-type", "application/json");
+            if let Some(token) = bearer {
+                builder = builder.header("authorization", format!("Bearer {token}"));
+            }
+            let request = builder
+                .body(Body::from(format!(
+                    r#"{{"model":"{model}","input":"hello","stream":false}}"#
+                )))
+                .expect("request must build");
+            async move { app.oneshot(request).await.expect("request must complete").status() }
+        };
+
+        assert_eq!(send("deepseek/deepseek-chat", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(send("deepseek/deepseek-chat", Some("wrong")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(send("zai/glm-4.5", Some("tenant-key")).await, StatusCode::FORBIDDEN);
+        assert_eq!(send("deepseek/deepseek-chat", Some("tenant-key")).await, StatusCode::OK);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri("/api/v1/usage")
+                    .header("authorization", "Bearer tenant-key")
+                    .body(Body::empty())
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body read must succeed");
+        let payload: Value =
+            serde_json::from_slice(&body).expect("response body must be valid json");
+        assert_eq!(payload.get("tenant_id").and_then(Value::as_str), Some("acme/web"));
+        assert_eq!(payload.get("requests").and_then(Value::as_u64), Some(1));
+        assert!(payload.get("total_tokens").and_then(Value::as_u64).unwrap_or_default() > 0);
+    }
+
+    #[tokio::test]
+    async fn tenant_usage_breaks_down_by_attribution_headers() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.tenants = vec![crate::config::TenantConfig {
+            organization: "acme".to_string(),
+            project: "web".to_string(),
+            api_keys: vec!["tenant-key".to_string()],
+            allowed_models: Vec::new(),
+            denied_models: Vec::new(),
+            rate_limit_per_minute: None,
+            token_budget: None,
+        }];
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let chat = |title: Option<&'static str>, stream: bool| {
+            let app = app.clone();
+            let mut builder = Request::builder()
+                .method("POST")
+                .uri("/api/v1/chat/completions")
+                .header("content-type", "application/json")
+                .header("authorization", "Bearer tenant-key");
+            if let Some(title) = title {
+                builder =
+                    builder.header("HTTP-Referer", "https://example.com").header("X-Title", title);
+            }
+            let request = builder
+                .body(Body::from(
+                    json!({
+                        "model": "deepseek/deepseek-chat",
+                        "messages": [{"role": "user", "content": "hello"}],
+                        "stream": stream
+                    })
+                    .to_string(),
+                ))
+                .expect("request must build");
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                assert_eq!(response.status(), StatusCode::OK);
+                to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+            }
+        };
+
+        chat(Some("Example Chat App"), false).await;
+        chat(Some("Example Chat App"), true).await;
+        chat(None, false).await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/usage")
+                    .header("authorization", "Bearer tenant-key")
+                    .body(Body::empty())
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body read must succeed");
+        let payload: Value =
+            serde_json::from_slice(&body).expect("response body must be valid json");
+        assert_eq!(payload["requests"], json!(3));
+        let apps = payload["apps"].as_array().expect("apps must be an array");
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0]["referer"], json!("https://example.com"));
+        assert_eq!(apps[0]["title"], json!("Example Chat App"));
+        assert_eq!(apps[0]["requests"], json!(2));
+        assert!(apps[0]["total_tokens"].as_u64().unwrap_or_default() > 0);
+    }
+
+    #[tokio::test]
+    async fn passthrough_fields_follow_the_provider_allowlist() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.passthrough_fields =
+            [("openrouter".to_string(), vec!["top_k".to_string()])].into_iter().collect();
+        let state = AppBuilder::new(&config).build_state();
+        let body = json!({
+            "model": "deepseek/deepseek-chat",
+            "messages": [{"role": "user", "content": "hello"}],
+            "top_k": 40,
+            "repetition_penalty": 1.1
+        });
+
+        let request: xrouter_contracts::ChatCompletionsRequest =
+            serde_json::from_value(body.clone()).expect("unknown fields must be accepted");
+        let mut extra = request.into_responses_request().extra;
+        assert_eq!(
+            state.retain_passthrough_fields("openrouter", &mut extra),
+            vec!["repetition_penalty".to_string()]
+        );
+        assert_eq!(Value::Object(extra.clone()), json!({"top_k": 40}));
+        assert_eq!(
+            state.retain_passthrough_fields("deepseek", &mut extra),
+            vec!["top_k".to_string()]
+        );
+        assert!(extra.is_empty());
+
+        let response = build_router(state)
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/chat/completions")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn tenant_model_globs_gate_requests_and_narrow_model_listing() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.tenants = vec![crate::config::TenantConfig {
+            organization: "acme".to_string(),
+            project: "web".to_string(),
+            api_keys: vec!["tenant-key".to_string()],
+            allowed_models: vec!["deepseek/*".to_string(), "zai/*".to_string()],
+            denied_models: vec!["deepseek/deepseek-reasoner".to_string()],
+            rate_limit_per_minute: None,
+            token_budget: None,
+        }];
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let list = |bearer: Option<&'static str>| {
+            let app = app.clone();
+            let mut builder = Request::builder().uri("/api/v1/models");
+            if let Some(token) = bearer {
+                builder = builder.header("authorization", format!("Bearer {token}"));
+            }
+            let request = builder.body(Body::empty()).expect("request must build");
+            async move { app.oneshot(request).await.expect("request must complete") }
+        };
+
+        let response = list(Some("tenant-key")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body read must succeed");
+        let payload: Value =
+            serde_json::from_slice(&body).expect("response body must be valid json");
+        let ids = payload["data"]
+            .as_array()
+            .expect("data must be an array")
+            .iter()
+            .map(|entry| entry["id"].as_str().expect("id must be a string").to_string())
+            .collect::<Vec<_>>();
+        assert!(ids.contains(&"deepseek/deepseek-chat".to_string()));
+        assert!(!ids.contains(&"deepseek/deepseek-reasoner".to_string()));
+        assert!(ids.iter().all(|id| id.starts_with("deepseek/") || id.starts_with("zai/")));
+
+        assert_eq!(list(Some("wrong")).await.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(list(None).await.status(), StatusCode::OK);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/responses")
+                    .header("content-type", "application/json")
+                    .header("authorization", "Bearer tenant-key")
+                    .body(Body::from(
+                        r#"{"model":"deepseek/deepseek-reasoner","input":"hello","stream":false}"#,
+                    ))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn admin_provider_probe_reports_latency_and_requires_admin_token() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let probe = |provider: &str, bearer: &str| {
+            let app = app.clone();
+            let request = Request::builder()
+                .method("POST")
+                .uri(format!("/admin/v1/providers/{provider}/probe"))
+                .header("authorization", format!("Bearer {bearer}"))
+                .body(Body::empty())
+                .expect("request must build");
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, payload) = probe("deepseek", "admin-secret").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(payload["provider"], json!("deepseek"));
+        assert_eq!(payload["ok"], json!(true));
+        assert!(payload["latency_ms"].is_u64());
+        assert_eq!(payload["error"], Value::Null);
+
+        assert_eq!(probe("deepseek", "wrong").await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(probe("acme", "admin-secret").await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn admin_transcripts_list_and_fetch_captured_streams() {
+        let dir =
+            std::env::temp_dir().join(format!("xrouter-app-transcripts-{}", uuid::Uuid::new_v4()));
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        config.stream_transcript_dir = Some(dir.clone());
+        let state = AppBuilder::new(&config).build_state();
+        let mut capture = xrouter_clients_openai::TranscriptCapture::default();
+        capture.push(b"data: {\"id\":\"gen-1\"}\n\ndata: [DONE]\n\n");
+        let id = state
+            .transcripts
+            .as_ref()
+            .expect("transcript store must be configured")
+            .record("req_1", "zai", "chat_completions", capture, true)
+            .expect("record must succeed");
+        let app = build_router(state);
+        let get = |uri: String, bearer: &str| {
+            let app = app.clone();
+            let request = Request::builder()
+                .uri(uri)
+                .header("authorization", format!("Bearer {bearer}"))
+                .body(Body::empty())
+                .expect("request must build");
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, listed) = get("/admin/v1/transcripts".to_string(), "admin-secret").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed["data"][0]["id"], json!(id));
+        assert_eq!(listed["data"][0]["provider"], json!("zai"));
+        assert_eq!(listed["data"][0].get("body"), None);
+
+        let (status, fetched) = get(format!("/admin/v1/transcripts/{id}"), "admin-secret").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched["body"], json!("data: {\"id\":\"gen-1\"}\n\ndata: [DONE]\n\n"));
+        assert_eq!(fetched["complete"], json!(true));
+
+        assert_eq!(
+            get("/admin/v1/transcripts/missing".to_string(), "admin-secret").await.0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            get("/admin/v1/transcripts".to_string(), "wrong").await.0,
+            StatusCode::UNAUTHORIZED
+        );
+
+        std::fs::remove_dir_all(dir).expect("temp dir must be removed");
+    }
+
+    #[tokio::test]
+    async fn provider_fixtures_recorded_over_http_are_replayed_offline() {
+        let dir =
+            std::env::temp_dir().join(format!("xrouter-app-fixtures-{}", uuid::Uuid::new_v4()));
+        let post = |fixtures: crate::config::ProviderFixturesConfig, input: &str| {
+            let mut config = crate::config::AppConfig::for_tests();
+            config.provider_fixtures = fixtures;
+            let app = build_router(AppBuilder::new(&config).build_state());
+            let body = json!({"model": "deepseek/deepseek-chat", "input": input, "stream": false});
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/responses")
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let record = crate::config::ProviderFixturesConfig::Record { dir: dir.clone() };
+        let replay = crate::config::ProviderFixturesConfig::Replay { dir: dir.clone() };
+
+        let (status, recorded) = post(record, "hello fixtures").await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, replayed) = post(replay.clone(), "hello fixtures").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(replayed["output"], recorded["output"]);
+
+        let (status, missing) = post(replay, "never recorded").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(missing["error"].as_str().is_some_and(|error| error.contains("no replay fixture")));
+
+        std::fs::remove_dir_all(dir).expect("temp dir must be removed");
+    }
+
+    #[tokio::test]
+    async fn debug_normalize_previews_payloads_per_provider_without_sending() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        config.providers.get_mut("yandex").expect("yandex must be configured").project =
+            Some("folder".to_string());
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let body = json!({
+            "model": "zai/glm-4.5",
+            "input": "list files",
+            "tools": [
+                {"type": "function", "name": "list_dir", "parameters": {"type": "object"}},
+                {"type": "web_search"}
+            ]
+        });
+        let preview = |uri: &str, bearer: &str| {
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {bearer}"))
+                .body(Body::from(body.to_string()))
+                .expect("request must build");
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        assert_eq!(preview("/api/v1/debug/normalize", "wrong").await.0, StatusCode::UNAUTHORIZED);
+        let (status, routed) = preview("/api/v1/debug/normalize", "admin-secret").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(routed["candidates"][0]["provider"], json!("zai"));
+        assert_eq!(routed["candidates"][0]["payload"]["model"], json!("glm-4.5"));
+
+        let (status, compared) =
+            preview("/api/v1/debug/normalize?providers=zai,yandex", "admin-secret").await;
+        assert_eq!(status, StatusCode::OK);
+        let yandex = &compared["candidates"][1];
+        assert_eq!(yandex["endpoint"], json!("responses"));
+        assert_eq!(yandex["payload"]["model"], json!("gpt://folder/glm-4.5"));
+        assert_eq!(yandex["normalization"]["tools_dropped"], json!(1));
+
+        assert_eq!(
+            preview("/api/v1/debug/normalize?providers=nope", "admin-secret").await.0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn admin_chaos_policy_injects_provider_errors_at_runtime() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        config.chaos = Some(xrouter_clients_openai::ChaosPolicy::default());
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let call = |method: &str, uri: &str, bearer: &str, body: Value| {
+            let app = app.clone();
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {bearer}"))
+                .body(Body::from(body.to_string()))
+                .expect("request must build");
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let (_, created) = call(
+            "POST",
+            "/admin/v1/keys",
+            "admin-secret",
+            json!({"label": "chaos", "organization": "acme", "project": "web"}),
+        )
+        .await;
+        let key = created["key"].as_str().expect("key must be returned").to_string();
+        let infer = || {
+            call(
+                "POST",
+                "/api/v1/responses",
+                &key,
+                json!({"model": "deepseek/deepseek-chat", "input": "hello", "stream": false}),
+            )
+        };
+
+        assert_eq!(infer().await.0, StatusCode::OK);
+
+        let (status, updated) = call(
+            "PUT",
+            "/admin/v1/chaos",
+            "admin-secret",
+            json!({"providers": ["deepseek"], "error_probability": 1.0, "error_status": 502}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["error_status"], json!(502));
+
+        let (status, failed) = infer().await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(failed["error"].as_str().is_some_and(|error| error.contains("chaos injection")));
+
+        let (status, _) =
+            call("PUT", "/admin/v1/chaos", "admin-secret", json!({"disconnect_probability": 3.0}))
+                .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (_, current) = call("GET", "/admin/v1/chaos", "admin-secret", Value::Null).await;
+        assert_eq!(current["providers"], json!(["deepseek"]));
+    }
+
+    #[tokio::test]
+    async fn admin_keys_issue_rotate_and_disable_managed_keys() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let call = |method: &str, uri: String, bearer: &str, body: Option<Value>| {
+            let app = app.clone();
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {bearer}"))
+                .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+                .expect("request must build");
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let infer = |key: &str| {
+            call(
+                "POST",
+                "/api/v1/responses".to_string(),
+                key,
+                Some(json!({"model": "deepseek/deepseek-chat", "input": "hello", "stream": false})),
+            )
+        };
+
+        let (status, _) = call("GET", "/admin/v1/keys".to_string(), "wrong", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, created) = call(
+            "POST",
+            "/admin/v1/keys".to_string(),
+            "admin-secret",
+            Some(json!({"label": "ci", "organization": "acme", "project": "web"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let key = created["key"].as_str().expect("key must be returned").to_string();
+        let id = created["api_key"]["id"].as_str().expect("id must be returned").to_string();
+        assert_eq!(infer(&key).await.0, StatusCode::OK);
+
+        let (status, listed) =
+            call("GET", "/admin/v1/keys".to_string(), "admin-secret", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed["data"].as_array().map(Vec::len), Some(1));
+        assert!(!listed.to_string().contains(&key));
+
+        let (status, rotated) =
+            call("POST", format!("/admin/v1/keys/{id}/rotate"), "admin-secret", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let rotated_key = rotated["key"].as_str().expect("key must be returned").to_string();
+        assert_eq!(infer(&key).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(infer(&rotated_key).await.0, StatusCode::OK);
+
+        let (status, _) = call(
+            "PATCH",
+            format!("/admin/v1/keys/{id}"),
+            "admin-secret",
+            Some(json!({"disabled": true})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(infer(&rotated_key).await.0, StatusCode::UNAUTHORIZED);
+
+        let (status, _) =
+            call("DELETE", format!("/admin/v1/keys/{id}"), "admin-secret", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn sqlite_storage_keeps_managed_keys_and_usage_across_restarts() {
+        use crate::config::{KeyStoreConfig, ResponseStoreConfig, StorageConfig};
+
+        let path = std::env::temp_dir().join(format!("xrouter-{}.db", uuid::Uuid::new_v4()));
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        config.storage = StorageConfig::Sqlite { path: path.clone() };
+        config.key_store = KeyStoreConfig::Sqlite;
+        config.response_store = ResponseStoreConfig::Sqlite;
+        let call = |app: axum::Router, uri: &str, bearer: &str, body: Value| {
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {bearer}"))
+                .body(Body::from(body.to_string()))
+                .expect("request must build");
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let request = json!({"model": "deepseek/deepseek-chat", "input": "hello", "stream": false});
+
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let (status, created) = call(
+            app.clone(),
+            "/admin/v1/keys",
+            "admin-secret",
+            json!({"label": "ci", "organization": "acme", "project": "web"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let key = created["key"].as_str().expect("key must be returned").to_string();
+        assert_eq!(call(app, "/api/v1/responses", &key, request.clone()).await.0, StatusCode::OK);
+
+        let restarted = build_router(AppBuilder::new(&config).build_state());
+        assert_eq!(call(restarted, "/api/v1/responses", &key, request).await.0, StatusCode::OK);
+
+        let connection = crate::storage::connect_sqlite(&path).expect("database must open");
+        let mut requests = 0;
+        for _ in 0..100 {
+            requests = connection
+                .query_row(
+                    "SELECT COALESCE(SUM(requests), 0) FROM xrouter_usage \
+                     WHERE tenant_id = 'acme/web'",
+                    [],
+                    |row| row.get::<_, i64>(0),
+                )
+                .expect("usage ledger must be readable");
+            if requests == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(requests, 2);
+        let _ = std::fs::remove_file(path);
+    }
+
     #[tokio::test]
     async fn chat_non_stream_uses_chatcmpl_id_prefix() {
         let app = build_router(test_app_state(false));
@@ -742,6 +1568,7 @@ json.first_id=<none>
                 ("HTTP-Referer".to_string(), "https://example.com".to_string()),
                 ("X-OpenRouter-Title".to_string(), "Example App".to_string()),
                 ("X-OpenRouter-Categories".to_string(), "cli-agent".to_string()),
+                ("x-xrouter-hop".to_string(), "1".to_string()),
             ]
         );
     }
@@ -773,6 +1600,7 @@ json.first_id=<none>
             vec![
                 ("HTTP-Referer".to_string(), "https://example.com".to_string()),
                 ("X-Title".to_string(), "Example Chat App".to_string()),
+                ("x-xrouter-hop".to_string(), "1".to_string()),
             ]
         );
     }
@@ -902,25 +1730,1707 @@ json.first_id=<none>
     }
 
     #[tokio::test]
-    async fn responses_stream_emits_response_error_without_completion_on_provider_failure() {
-        let app = build_router(test_app_state(false));
-        let response = app
+    async fn oversized_bodies_get_413_and_overlong_input_gets_400() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.max_request_body_bytes = 512;
+        let small_limit = build_router(AppBuilder::new(&config).build_state());
+        let post = |app: axum::Router, body: String| async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .body(Body::from(body))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            let status = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("response body read must succeed");
+            (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+        };
+
+        let large = json!({"model": "deepseek/deepseek-chat", "input": "word ".repeat(200)});
+        let (status, payload) = post(small_limit, large.to_string()).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(payload["error"].as_str().is_some_and(|error| error.contains("512-byte limit")));
+
+        let overlong = json!({"model": "deepseek/deepseek-chat", "input": "word ".repeat(200_000)});
+        let (status, payload) =
+            post(build_router(test_app_state(false)), overlong.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(payload["param"], json!("input"));
+        assert!(payload["error"].as_str().is_some_and(|error| error.contains("context window")));
+    }
+
+    #[tokio::test]
+    async fn overlong_conversations_are_truncated_with_the_requested_strategy() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.model_overrides = [(
+            "deepseek/deepseek-chat".to_string(),
+            crate::config::ModelOverrideConfig { context_length: Some(100), ..Default::default() },
+        )]
+        .into();
+        config.truncation_summary_model = Some("zai/glm-4.5".to_string());
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let post = |truncation: Option<&str>| {
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/api/v1/chat/completions")
+                .header("content-type", "application/json");
+            if let Some(truncation) = truncation {
+                request = request.header("x-xrouter-truncation", truncation);
+            }
+            let messages = (0..6)
+                .map(|turn| {
+                    let role = if turn % 2 == 0 { "user" } else { "assistant" };
+                    json!({"role": role, "content": format!("turn {turn} {}", "word ".repeat(30))})
+                })
+                .chain([json!({"role": "user", "content": "and now?"})])
+                .collect::<Vec<_>>();
+            let request = request
+                .body(Body::from(
+                    json!({"model": "deepseek/deepseek-chat", "messages": messages}).to_string(),
+                ))
+                .expect("request must build");
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+
+        let (status, payload) = post(None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(payload["error"].as_str().is_some_and(|error| error.contains("context window")));
+
+        for strategy in ["drop_oldest", "middle_out", "summarize"] {
+            let (status, payload) = post(Some(strategy)).await;
+            assert_eq!(status, StatusCode::OK, "{strategy}: {payload}");
+        }
+
+        let (status, payload) = post(Some("newest_only")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(
+            payload["error"].as_str().is_some_and(|error| error.contains("x-xrouter-truncation"))
+        );
+    }
+
+    #[tokio::test]
+    async fn cors_answers_preflights_and_tags_streaming_responses_for_allowed_origins() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.cors = crate::config::CorsConfig {
+            allowed_origins: vec!["https://app.example.com".to_string()],
+            allowed_headers: Vec::new(),
+            allowed_methods: vec!["POST".to_string(), "OPTIONS".to_string()],
+            max_age_seconds: Some(600),
+        };
+        let app = build_router(AppBuilder::new(&config).build_state());
+
+        let preflight = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .method("POST")
-                    .uri("/api/v1/responses")
-                    .header("content-type", "application/json")
-                    .body(Body::from(
-                        r#"{"model":"deepseek/deepseek-chat","input":"__FAIL_PROVIDER__","stream":true}"#,
-                    ))
+                    .method("OPTIONS")
+                    .uri("/api/v1/chat/completions")
+                    .header("origin", "https://app.example.com")
+                    .header("access-control-request-method", "POST")
+                    .header("access-control-request-headers", "authorization,content-type")
+                    .body(Body::empty())
                     .expect("request must build"),
             )
             .await
             .expect("request must complete");
+        assert_eq!(preflight.status(), StatusCode::OK);
+        let headers = preflight.headers();
+        assert_eq!(headers["access-control-allow-origin"], "https://app.example.com");
+        assert_eq!(headers["access-control-allow-headers"], "authorization,content-type");
+        assert_eq!(headers["access-control-max-age"], "600");
+        assert!(
+            headers["access-control-allow-methods"]
+                .to_str()
+                .is_ok_and(|value| value.contains("POST"))
+        );
 
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = to_bytes(response.into_body(), usize::MAX)
-            .await
+        let streamed = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/chat/completions")
+                    .header("origin", "https://app.example.com")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({
+                            "model": "deepseek/deepseek-chat",
+                            "messages": [{"role": "user", "content": "hello"}],
+                            "stream": true
+                        })
+                        .to_string(),
+                    ))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        assert_eq!(streamed.status(), StatusCode::OK);
+        assert_eq!(streamed.headers()["access-control-allow-origin"], "https://app.example.com");
+        let exposed = streamed.headers()["access-control-expose-headers"]
+            .to_str()
+            .expect("exposed headers must be text")
+            .split(',')
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        for header in [
+            "x-xrouter-provider",
+            "x-ratelimit-limit-requests",
+            "x-ratelimit-remaining-requests",
+            "x-ratelimit-reset-requests",
+            "x-ratelimit-limit-tokens",
+            "x-ratelimit-remaining-tokens",
+            "retry-after",
+        ] {
+            assert!(exposed.contains(&header), "{header} must be exposed");
+        }
+
+        let foreign = app
+            .oneshot(
+                Request::builder()
+                    .method("OPTIONS")
+                    .uri("/api/v1/chat/completions")
+                    .header("origin", "https://evil.example.com")
+                    .header("access-control-request-method", "POST")
+                    .body(Body::empty())
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        assert!(foreign.headers().get("access-control-allow-origin").is_none());
+    }
+
+    #[tokio::test]
+    async fn responses_rejects_invalid_tool_name_with_param_path() {
+        let app = build_router(test_app_state(false));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/responses")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"model":"deepseek/deepseek-chat","input":"hi","stream":false,"tools":[{"type":"function","function":{"name":"list dir"}}]}"#,
+                    ))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body read must succeed");
+        let payload: Value =
+            serde_json::from_slice(&body).expect("response body must be valid json");
+        assert_eq!(payload.get("param").and_then(Value::as_str), Some("tools[0].function.name"));
+        assert_eq!(
+            payload.get("error").and_then(Value::as_str),
+            Some("validation failed: tools[0].function.name: must match ^[a-zA-Z0-9_-]{1,64}$")
+        );
+    }
+
+    #[cfg(feature = "docs")]
+    #[tokio::test]
+    async fn openapi_documents_streams_error_envelopes_and_bearer_auth() {
+        for openai_compatible_api in [false, true] {
+            let mut config = crate::config::AppConfig::for_tests();
+            config.openai_compatible_api = openai_compatible_api;
+            let app = build_router(AppBuilder::new(&config).build_state());
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri("/openapi.json")
+                        .body(Body::empty())
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("response body read must succeed");
+            let spec: Value = serde_json::from_slice(&body).expect("spec must be valid json");
+            let prefix = if openai_compatible_api { "/v1" } else { "/api/v1" };
+
+            for (route, stream_schema) in
+                [("responses", "ResponseStreamEvent"), ("chat/completions", "ChatCompletionChunk")]
+            {
+                let operation = &spec["paths"][format!("{prefix}/{route}")]["post"];
+                assert_eq!(
+                    operation["responses"]["200"]["content"]["text/event-stream"]["schema"]["$ref"],
+                    format!("#/components/schemas/{stream_schema}")
+                );
+                for status in ["400", "401", "403", "409", "413", "422", "429", "503"] {
+                    assert_eq!(
+                        operation["responses"][status]["content"]["application/json"]["schema"]["$ref"],
+                        "#/components/schemas/ErrorResponse",
+                        "{route} {status}"
+                    );
+                }
+                assert_eq!(operation["security"], json!([{"api_key": []}, {}]));
+            }
+            assert_eq!(
+                spec["paths"]["/admin/v1/keys"]["get"]["security"],
+                json!([{"admin_token": []}])
+            );
+            let schemes = &spec["components"]["securitySchemes"];
+            assert_eq!(schemes["api_key"]["scheme"], "bearer");
+            assert_eq!(schemes["admin_token"]["scheme"], "bearer");
+            for schema in ["ResponseStreamEvent", "ChatCompletionChunk", "ResponseOutputItem"] {
+                assert!(spec["components"]["schemas"][schema].is_object(), "{schema}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn catalog_only_mode_serves_models_and_rejects_completions() {
+        for openai_compatible_api in [false, true] {
+            let mut config = crate::config::AppConfig::for_tests();
+            config.openai_compatible_api = openai_compatible_api;
+            config.catalog_only = true;
+            let state = AppBuilder::new(&config).build_state();
+            assert!(state.engines.is_empty());
+            let app = build_router(state);
+            let prefix = if openai_compatible_api { "/v1" } else { "/api/v1" };
+
+            let models = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("{prefix}/models"))
+                        .body(Body::empty())
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            assert_eq!(models.status(), StatusCode::OK);
+            let body = to_bytes(models.into_body(), usize::MAX)
+                .await
+                .expect("response body read must succeed");
+            let payload: Value =
+                serde_json::from_slice(&body).expect("response body must be valid json");
+            assert!(payload["data"].as_array().is_some_and(|data| !data.is_empty()));
+
+            for route in ["responses", "chat/completions"] {
+                let response = app
+                    .clone()
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(format!("{prefix}/{route}"))
+                            .header("content-type", "application/json")
+                            .body(Body::from(
+                                r#"{"model":"deepseek/deepseek-chat","input":"hi","messages":[]}"#,
+                            ))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                let payload: Value =
+                    serde_json::from_slice(&body).expect("response body must be valid json");
+                assert!(
+                    payload["error"]
+                        .as_str()
+                        .is_some_and(|error| error.contains("model catalog only"))
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_replays_completed_responses_for_the_same_body() {
+        let app = build_router(test_app_state(false));
+        let post = |key: &'static str, input: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/responses")
+                            .header("content-type", "application/json")
+                            .header("idempotency-key", key)
+                            .body(Body::from(
+                                json!({"model": "deepseek/deepseek-chat", "input": input})
+                                    .to_string(),
+                            ))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                let status = response.status();
+                let replayed = response.headers().contains_key("idempotent-replayed");
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, replayed, body)
+            }
+        };
+
+        let (status, replayed, first) = post("order-1", "hello").await;
+        assert_eq!((status, replayed), (StatusCode::OK, false));
+        let (status, replayed, retry) = post("order-1", "hello").await;
+        assert_eq!((status, replayed), (StatusCode::OK, true));
+        assert_eq!(retry, first);
+
+        let (status, _, body) = post("order-1", "goodbye").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(String::from_utf8_lossy(&body).contains("different request body"));
+
+        let (status, _, _) = post("order-2", "__FAIL_PROVIDER__").await;
+        assert_ne!(status, StatusCode::OK);
+        let (retry_status, replayed, _) = post("order-2", "__FAIL_PROVIDER__").await;
+        assert_eq!((retry_status, replayed), (status, false));
+    }
+
+    #[tokio::test]
+    async fn identical_concurrent_requests_reach_the_provider_once_unless_the_key_opts_out() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        config.request_coalescing = true;
+        config.chaos = Some(xrouter_clients_openai::ChaosPolicy {
+            latency_ms: 200,
+            latency_probability: 1.0,
+            ..Default::default()
+        });
+        let state = AppBuilder::new(&config).build_state();
+        let app = build_router(state.clone());
+        let call = |uri: &'static str, bearer: String, body: Value| {
+            let app = app.clone();
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {bearer}"))
+                .body(Body::from(body.to_string()))
+                .expect("request must build");
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let coalesced = response.headers().contains_key("x-xrouter-coalesced");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, coalesced, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let issue = |opt_out: bool| {
+            let created = call(
+                "/admin/v1/keys",
+                "admin-secret".to_string(),
+                json!({
+                    "organization": "acme",
+                    "project": "web",
+                    "coalescing_opt_out": opt_out
+                }),
+            );
+            async move {
+                let (status, _, created) = created.await;
+                assert_eq!(status, StatusCode::CREATED);
+                assert_eq!(created["api_key"]["coalescing_opt_out"], json!(opt_out));
+                created["key"].as_str().expect("key must be returned").to_string()
+            }
+        };
+        let requests = || {
+            state
+                .model_stats
+                .snapshot("deepseek/deepseek-chat")
+                .map_or(0, |snapshot| snapshot.requests)
+        };
+
+        let key = issue(false).await;
+        let (first, second) = tokio::join!(
+            call(
+                "/api/v1/responses",
+                key.clone(),
+                json!({"model": "deepseek/deepseek-chat", "input": "hello"})
+            ),
+            call(
+                "/api/v1/responses",
+                key.clone(),
+                json!({"input": "hello", "model": "deepseek/deepseek-chat"})
+            ),
+        );
+        assert_eq!((first.0, second.0), (StatusCode::OK, StatusCode::OK));
+        assert_eq!([first.1, second.1].iter().filter(|coalesced| **coalesced).count(), 1);
+        assert_eq!(first.2, second.2);
+        assert_eq!(requests(), 1);
+
+        let opted_out = issue(true).await;
+        let body = json!({"model": "deepseek/deepseek-chat", "input": "hello"});
+        let (first, second) = tokio::join!(
+            call("/api/v1/responses", opted_out.clone(), body.clone()),
+            call("/api/v1/responses", opted_out.clone(), body.clone()),
+        );
+        assert_eq!((first.1, second.1), (false, false));
+        assert_eq!(requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn response_tee_forwards_streamed_and_buffered_completions_to_the_webhook() {
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel::<Value>();
+        let hook = axum::Router::new().route(
+            "/hook",
+            axum::routing::post(move |axum::Json(body): axum::Json<Value>| {
+                let sender = sender.clone();
+                async move {
+                    let _ = sender.send(body);
+                }
+            }),
+        );
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("listener must bind");
+        let url = format!("http://{}/hook", listener.local_addr().expect("address must resolve"));
+        tokio::spawn(async move { axum::serve(listener, hook).await });
+
+        let mut config = crate::config::AppConfig::for_tests();
+        config.response_tee = crate::config::ResponseTeeConfig::Webhook { url };
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let mut types = Vec::new();
+        for stream in [false, true] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            json!({"model": "zai/glm-4.5", "input": "hello", "stream": stream})
+                                .to_string(),
+                        ))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            assert_eq!(response.status(), StatusCode::OK);
+            to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+            let records = tokio::time::timeout(std::time::Duration::from_secs(5), received.recv())
+                .await
+                .expect("tee delivery must arrive")
+                .expect("webhook must stay up");
+            for record in records.as_array().expect("records must be an array") {
+                assert_eq!(record["model"], "zai/glm-4.5");
+                types.push(record["type"].as_str().unwrap_or_default().to_string());
+            }
+        }
+        assert_eq!(types, ["response.completed", "response.completed"]);
+    }
+
+    #[tokio::test]
+    async fn event_bus_publishes_correlated_lifecycle_events_per_request() {
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel::<Value>();
+        let proxy = axum::Router::new().route(
+            "/topics/xrouter.events",
+            axum::routing::post(move |body: axum::body::Bytes| {
+                let sender = sender.clone();
+                async move {
+                    let body: Value = serde_json::from_slice(&body).unwrap_or_default();
+                    for record in body["records"].as_array().into_iter().flatten() {
+                        let _ = sender.send(record["value"].clone());
+                    }
+                }
+            }),
+        );
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("listener must bind");
+        let url = format!("http://{}", listener.local_addr().expect("address must resolve"));
+        tokio::spawn(async move { axum::serve(listener, proxy).await });
+
+        let mut config = crate::config::AppConfig::for_tests();
+        config.event_bus =
+            crate::config::EventBusConfig::Kafka { url, topic: "xrouter.events".to_string() };
+        let app = build_router(AppBuilder::new(&config).build_state());
+        for input in ["hello", "__FAIL_PROVIDER__"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            json!({"model": "zai/glm-4.5", "input": input}).to_string(),
+                        ))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+        }
+
+        let mut events = Vec::new();
+        while events.len() < 6 {
+            let event = tokio::time::timeout(std::time::Duration::from_secs(5), received.recv())
+                .await
+                .expect("events must arrive")
+                .expect("proxy must stay up");
+            events.push(event);
+        }
+        let types = events.iter().map(|event| event["type"].as_str().unwrap_or_default());
+        assert_eq!(
+            types.collect::<Vec<_>>(),
+            [
+                "request.received",
+                "provider.selected",
+                "request.completed",
+                "request.received",
+                "provider.selected",
+                "request.failed"
+            ]
+        );
+        assert_eq!(events[0]["request_id"], events[2]["request_id"]);
+        assert_ne!(events[0]["request_id"], events[3]["request_id"]);
+        assert_eq!(events[2]["model"], "zai/glm-4.5");
+        assert!(events[2]["usage"]["total_tokens"].is_u64());
+        assert!(events[0].get("input").is_none());
+    }
+
+    #[tokio::test]
+    async fn chat_completions_report_and_rate_limit_under_the_matched_route() {
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel::<Value>();
+        let proxy = axum::Router::new().route(
+            "/topics/xrouter.events",
+            axum::routing::post(move |body: axum::body::Bytes| {
+                let sender = sender.clone();
+                async move {
+                    let body: Value = serde_json::from_slice(&body).unwrap_or_default();
+                    for record in body["records"].as_array().into_iter().flatten() {
+                        let _ = sender.send(record["value"].clone());
+                    }
+                }
+            }),
+        );
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("listener must bind");
+        let url = format!("http://{}", listener.local_addr().expect("address must resolve"));
+        tokio::spawn(async move { axum::serve(listener, proxy).await });
+
+        let mut config = crate::config::AppConfig::for_tests();
+        config.event_bus =
+            crate::config::EventBusConfig::Kafka { url, topic: "xrouter.events".to_string() };
+        config.user_rate_limit_per_minute = Some(1);
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let call = |uri: &'static str, user: &'static str| {
+            let app = app.clone();
+            async move {
+                let body = json!({
+                    "model": "zai/glm-4.5",
+                    "messages": [{"role": "user", "content": "hello"}],
+                    "user": user
+                });
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                let status = response.status();
+                to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+                status
+            }
+        };
+
+        assert_eq!(call("/api/v2/chat/completions", "alice").await, StatusCode::OK);
+        assert_eq!(call("/api/v2/chat/completions", "alice").await, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(call("/api/v1/chat/completions", "bob").await, StatusCode::OK);
+
+        let mut events = Vec::new();
+        while events.len() < 6 {
+            let event = tokio::time::timeout(std::time::Duration::from_secs(5), received.recv())
+                .await
+                .expect("events must arrive")
+                .expect("proxy must stay up");
+            events.push(event);
+        }
+        let routes = events.iter().map(|event| event["route"].as_str().unwrap_or_default());
+        assert_eq!(
+            routes.collect::<Vec<_>>(),
+            [
+                "/api/v2/chat/completions",
+                "/api/v2/chat/completions",
+                "/api/v2/chat/completions",
+                "/api/v1/chat/completions",
+                "/api/v1/chat/completions",
+                "/api/v1/chat/completions"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn alert_rules_notify_the_webhook_when_a_provider_breaches_them() {
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel::<Value>();
+        let hook = axum::Router::new().route(
+            "/alerts",
+            axum::routing::post(move |body: axum::body::Bytes| {
+                let sender = sender.clone();
+                async move {
+                    let _ = sender.send(serde_json::from_slice(&body).unwrap_or_default());
+                }
+            }),
+        );
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("listener must bind");
+        let url = format!("http://{}/alerts", listener.local_addr().expect("address must resolve"));
+        tokio::spawn(async move { axum::serve(listener, hook).await });
+
+        let config = crate::config::AppConfig::for_tests();
+        let rule = crate::config::AlertRuleConfig {
+            name: "zai-errors".to_string(),
+            provider: Some("zai".to_string()),
+            metric: crate::config::AlertMetric::ErrorRate,
+            threshold: 50.0,
+            for_minutes: 0,
+            min_requests: 2,
+        };
+        let state = AppBuilder::new(&config).build_state().with_alerts(
+            &[rule],
+            Some(&url),
+            std::time::Duration::from_millis(50),
+        );
+        let app = build_router(state);
+        for input in ["__FAIL_PROVIDER__", "__FAIL_PROVIDER__", "hello"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            json!({"model": "zai/glm-4.5", "input": input}).to_string(),
+                        ))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+        }
+
+        let notification = tokio::time::timeout(std::time::Duration::from_secs(5), received.recv())
+            .await
+            .expect("notification must arrive")
+            .expect("webhook must stay up");
+        assert_eq!(notification["alert"]["state"], "firing");
+        assert_eq!(notification["alert"]["rule"], "zai-errors");
+        assert_eq!(notification["alert"]["provider"], "zai");
+        let value = notification["alert"]["value"].as_f64().expect("value must be a number");
+        assert!(value > 50.0, "error rate {value} must breach the threshold");
+        assert!(notification["text"].as_str().is_some_and(|text| text.starts_with("[FIRING]")));
+    }
+
+    #[tokio::test]
+    async fn streams_report_time_to_first_token_in_their_final_meta() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.chaos = Some(xrouter_clients_openai::ChaosPolicy {
+            providers: vec!["zai".to_string()],
+            latency_ms: 50,
+            latency_probability: 1.0,
+            ..Default::default()
+        });
+        let app = build_router(AppBuilder::new(&config).build_state());
+        for (uri, body) in [
+            (
+                "/api/v1/responses",
+                json!({"model": "zai/glm-4.5", "input": "hello", "stream": true}),
+            ),
+            (
+                "/api/v1/chat/completions",
+                json!({
+                    "model": "zai/glm-4.5",
+                    "messages": [{"role": "user", "content": "hello"}],
+                    "stream": true
+                }),
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(uri)
+                        .header("content-type", "application/json")
+                        .body(Body::from(body.to_string()))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            let body = to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+            let meta = String::from_utf8_lossy(&body)
+                .lines()
+                .filter_map(|line| line.strip_prefix("data: "))
+                .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+                .find_map(|event| {
+                    event["response"]["meta"]
+                        .as_object()
+                        .or_else(|| event["meta"].as_object())
+                        .cloned()
+                })
+                .expect("final event must carry meta");
+            let ttft = meta["time_to_first_token_ms"].as_u64().expect("ttft must be reported");
+            assert!(ttft >= 50, "{uri}: {ttft}");
+        }
+    }
+
+    #[tokio::test]
+    async fn token_counts_and_catalog_cost_are_reported_in_headers_and_final_events() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.model_overrides = [(
+            "deepseek/deepseek-chat".to_string(),
+            crate::config::ModelOverrideConfig {
+                pricing: Some(crate::config::ModelPricingConfig {
+                    prompt: "0.000001".to_string(),
+                    completion: "0.000002".to_string(),
+                }),
+                ..Default::default()
+            },
+        )]
+        .into();
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let post = |uri: &'static str, body: Value| {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(uri)
+                        .header("content-type", "application/json")
+                        .body(Body::from(body.to_string()))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete")
+            }
+        };
+        let expected_cost = |usage: &Value| {
+            let tokens = |field: &str| usage[field].as_f64().expect("usage must be reported");
+            tokens("input_tokens") * 0.000001 + tokens("output_tokens") * 0.000002
+        };
+
+        let chat = json!({
+            "model": "deepseek/deepseek-chat",
+            "messages": [{"role": "user", "content": "hello"}]
+        });
+        for (uri, body) in [
+            ("/api/v1/responses", json!({"model": "deepseek/deepseek-chat", "input": "hello"})),
+            ("/api/v1/chat/completions", chat.clone()),
+        ] {
+            let response = post(uri, body).await;
+            let header = |name: &str| {
+                response.headers().get(name).and_then(|value| value.to_str().ok()).map(String::from)
+            };
+            let headers = (
+                header("x-xrouter-provider"),
+                header("x-xrouter-input-tokens"),
+                header("x-xrouter-output-tokens"),
+                header("x-xrouter-cost"),
+            );
+            let body = to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+            let payload = serde_json::from_slice::<Value>(&body).expect("body must be json");
+            let usage = &payload["usage"];
+            assert_eq!(headers.0.as_deref(), Some("deepseek"), "{uri}");
+            assert_eq!(headers.1, Some(usage["input_tokens"].to_string()), "{uri}");
+            assert_eq!(headers.2, Some(usage["output_tokens"].to_string()), "{uri}");
+            assert_eq!(
+                headers.3.as_ref(),
+                payload["meta"]["cost"].as_str().map(String::from).as_ref()
+            );
+            let cost =
+                headers.3.and_then(|cost| cost.parse::<f64>().ok()).expect("cost must parse");
+            assert!((cost - expected_cost(usage)).abs() < 1e-12, "{uri}: {cost}");
+        }
+
+        let mut streamed = chat;
+        streamed["stream"] = json!(true);
+        let response = post("/api/v1/chat/completions", streamed).await;
+        assert!(!response.headers().contains_key("x-xrouter-cost"));
+        let body = to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+        let last = String::from_utf8_lossy(&body)
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+            .find(|chunk| chunk.get("meta").is_some())
+            .expect("final chunk must carry meta");
+        assert_eq!(last["meta"]["provider"], "deepseek");
+        let cost = last["meta"]["cost"].as_str().and_then(|cost| cost.parse::<f64>().ok());
+        assert!(cost.is_some_and(|cost| (cost - expected_cost(&last["usage"])).abs() < 1e-12));
+    }
+
+    #[tokio::test]
+    async fn streams_follow_the_event_order_strict_sdks_expect() {
+        let app = build_router(test_app_state(false));
+        let stream = |uri: &'static str, body: Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                let body =
+                    to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+                String::from_utf8(body.to_vec()).expect("stream must be utf-8")
+            }
+        };
+
+        let body = stream(
+            "/api/v1/responses",
+            json!({"model": "zai/glm-4.5", "input": "hello world", "stream": true}),
+        )
+        .await;
+        let frames = body.split("\n\n").filter(|frame| !frame.trim().is_empty());
+        let mut types = Vec::new();
+        for (index, frame) in frames.enumerate() {
+            let event = frame.lines().find_map(|line| line.strip_prefix("event: "));
+            let data = frame.lines().find_map(|line| line.strip_prefix("data: "));
+            let payload = serde_json::from_str::<Value>(data.expect("frame must carry data"))
+                .expect("data must be json");
+            assert_eq!(event, payload["type"].as_str(), "{frame}");
+            assert_eq!(payload["sequence_number"], json!(index), "{frame}");
+            types.push(payload["type"].as_str().unwrap_or_default().to_string());
+        }
+        assert_eq!(
+            types[..3],
+            ["response.created", "response.output_item.added", "response.content_part.added"]
+        );
+        assert!(types[3..types.len() - 2].iter().all(|kind| kind.ends_with(".delta")), "{types:?}");
+        assert_eq!(types[types.len() - 2..], ["response.output_item.done", "response.completed"]);
+
+        let body = stream(
+            "/api/v1/chat/completions",
+            json!({
+                "model": "zai/glm-4.5",
+                "messages": [{"role": "user", "content": "hello world"}],
+                "stream": true
+            }),
+        )
+        .await;
+        let data = body.lines().filter_map(|line| line.strip_prefix("data: ")).collect::<Vec<_>>();
+        assert_eq!(data.last(), Some(&"[DONE]"));
+        let chunks = data[..data.len() - 1]
+            .iter()
+            .map(|chunk| serde_json::from_str::<Value>(chunk).expect("chunk must be json"))
+            .collect::<Vec<_>>();
+        assert_eq!(chunks[0]["choices"][0]["delta"], json!({"role": "assistant", "content": ""}));
+        assert!(chunks.iter().all(|chunk| chunk["id"] == chunks[0]["id"]));
+        assert!(chunks[1..].iter().all(|chunk| chunk["choices"][0]["delta"].get("role").is_none()));
+        let finished =
+            chunks.iter().filter(|chunk| chunk["choices"][0]["finish_reason"].is_string());
+        assert_eq!(finished.count(), 1);
+        assert!(chunks.last().is_some_and(|chunk| chunk["choices"][0]["finish_reason"] == "stop"));
+    }
+
+    #[tokio::test]
+    async fn v2_routes_share_handlers_with_v1_and_wrap_errors_in_an_envelope() {
+        let app = build_router(test_app_state(false));
+        let call = |method: &'static str, uri: &'static str, body: Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method(method)
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                let status = response.status();
+                let version = response
+                    .headers()
+                    .get("x-xrouter-version")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                let body =
+                    to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+                let body = serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null);
+                (status, version, body)
+            }
+        };
+        let invalid = json!({"model": "zai/glm-4.5", "messages": []});
+
+        let (status, version, body) =
+            call("POST", "/api/v1/chat/completions", invalid.clone()).await;
+        assert_eq!((status, version.as_deref()), (StatusCode::BAD_REQUEST, Some("v1")));
+        assert!(body["error"].is_string(), "{body}");
+
+        let (status, version, body) = call("POST", "/api/v2/chat/completions", invalid).await;
+        assert_eq!((status, version.as_deref()), (StatusCode::BAD_REQUEST, Some("v2")));
+        assert!(body["error"]["message"].is_string(), "{body}");
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+        assert_eq!(body["error"]["code"], 400);
+
+        let (status, version, body) = call(
+            "POST",
+            "/api/v2/responses",
+            json!({"model": "zai/glm-4.5", "input": "hello world"}),
+        )
+        .await;
+        assert_eq!((status, version.as_deref()), (StatusCode::OK, Some("v2")));
+        assert_eq!(body["status"], "completed");
+
+        let (status, version, _) = call("GET", "/api/v2/models", Value::Null).await;
+        assert_eq!((status, version.as_deref()), (StatusCode::OK, Some("v2")));
+        let (_, version, _) = call("GET", "/health", Value::Null).await;
+        assert_eq!(version, None);
+    }
+
+    #[tokio::test]
+    async fn legacy_completions_answer_prompts_as_text_completions() {
+        let app = build_router(test_app_state(false));
+        let call = |body: Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/completions")
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                let status = response.status();
+                let provider = response.headers().get("x-xrouter-provider").cloned();
+                let body =
+                    to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+                (status, provider, String::from_utf8(body.to_vec()).expect("body must be utf-8"))
+            }
+        };
+
+        let (status, provider, body) =
+            call(json!({"model": "zai/glm-4.5", "prompt": "Say hi", "echo": true})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(provider.as_ref().and_then(|value| value.to_str().ok()), Some("zai"));
+        let completion = serde_json::from_str::<Value>(&body).expect("completion must be json");
+        assert_eq!(completion["object"], "text_completion");
+        assert!(completion["id"].as_str().is_some_and(|id| id.starts_with("cmpl_")), "{body}");
+        let text = completion["choices"][0]["text"].as_str().unwrap_or_default();
+        assert!(text.starts_with("Say hi") && text.len() > "Say hi".len(), "{body}");
+        assert_eq!(completion["choices"][0]["finish_reason"], "stop");
+        assert!(completion["usage"]["total_tokens"].as_u64().is_some_and(|total| total > 0));
+
+        let (status, _, body) =
+            call(json!({"model": "zai/glm-4.5", "prompt": "Say hi", "stream": true})).await;
+        assert_eq!(status, StatusCode::OK);
+        let data = body.lines().filter_map(|line| line.strip_prefix("data: ")).collect::<Vec<_>>();
+        assert_eq!(data.last(), Some(&"[DONE]"));
+        let chunks = data[..data.len() - 1]
+            .iter()
+            .map(|chunk| serde_json::from_str::<Value>(chunk).expect("chunk must be json"))
+            .collect::<Vec<_>>();
+        assert!(chunks.iter().all(|chunk| chunk["object"] == "text_completion"), "{body}");
+        let streamed = chunks
+            .iter()
+            .filter_map(|chunk| chunk["choices"][0]["text"].as_str())
+            .collect::<String>();
+        assert!(!streamed.is_empty());
+        assert_eq!(
+            chunks.last().map(|chunk| &chunk["choices"][0]["finish_reason"]),
+            Some(&json!("stop"))
+        );
+
+        let (status, _, body) =
+            call(json!({"model": "zai/glm-4.5", "prompt": ["one", "two"]})).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let error = serde_json::from_str::<Value>(&body).expect("error must be json");
+        assert_eq!(error["param"], "prompt");
+    }
+
+    #[tokio::test]
+    async fn streams_whose_provider_goes_quiet_are_aborted_with_a_stall_error() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.chaos = Some(xrouter_clients_openai::ChaosPolicy {
+            providers: vec!["zai".to_string()],
+            latency_ms: 2_000,
+            latency_probability: 1.0,
+            ..Default::default()
+        });
+        let state = AppBuilder::new(&config)
+            .build_state()
+            .with_stream_stall_timeout(Some(std::time::Duration::from_millis(100)));
+        let app = build_router(state);
+        let started_at = std::time::Instant::now();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/responses")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({"model": "zai/glm-4.5", "input": "hello", "stream": true})
+                            .to_string(),
+                    ))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        let body = to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+        let body = String::from_utf8_lossy(&body);
+
+        assert!(started_at.elapsed() < std::time::Duration::from_secs(2));
+        assert!(body.contains("event: response.error"), "{body}");
+        assert!(body.contains("provider stalled: no stream data for 0.1s"), "{body}");
+        assert!(!body.contains("response.completed"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn generations_are_cancelled_when_the_client_leaves_or_the_server_shuts_down() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.chaos = Some(xrouter_clients_openai::ChaosPolicy {
+            providers: vec!["zai".to_string()],
+            latency_ms: 30_000,
+            latency_probability: 1.0,
+            ..Default::default()
+        });
+        config.tenants = vec![crate::config::TenantConfig {
+            organization: "acme".to_string(),
+            project: "web".to_string(),
+            api_keys: vec!["tenant-key".to_string()],
+            allowed_models: Vec::new(),
+            denied_models: Vec::new(),
+            rate_limit_per_minute: None,
+            token_budget: None,
+        }];
+        let state = AppBuilder::new(&config).build_state();
+        let app = build_router(state.clone());
+        let start_stream = || {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/responses")
+                    .header("content-type", "application/json")
+                    .header("authorization", "Bearer tenant-key")
+                    .body(Body::from(
+                        json!({"model": "zai/glm-4.5", "input": "hello there", "stream": true})
+                            .to_string(),
+                    ))
+                    .expect("request must build"),
+            )
+        };
+        let wait_for_tasks = |expected: usize| {
+            let state = state.clone();
+            async move {
+                tokio::time::timeout(std::time::Duration::from_secs(5), async {
+                    while state.stream_tasks.in_flight() != expected {
+                        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                    }
+                })
+                .await
+                .expect("stream tasks must settle");
+            }
+        };
+
+        let response = start_stream().await.expect("request must complete");
+        wait_for_tasks(1).await;
+        drop(response);
+        wait_for_tasks(0).await;
+
+        let response = start_stream().await.expect("request must complete");
+        wait_for_tasks(1).await;
+        tokio::time::timeout(std::time::Duration::from_secs(5), state.shutdown_streams())
+            .await
+            .expect("shutdown must not wait for the provider");
+        let body = to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+        assert!(!String::from_utf8_lossy(&body).contains("response.completed"));
+        assert_eq!(state.stream_tasks.in_flight(), 0);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/usage")
+                    .header("authorization", "Bearer tenant-key")
+                    .body(Body::empty())
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        let body = to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+        let usage: Value = serde_json::from_slice(&body).expect("usage must be json");
+        assert_eq!(usage["input_tokens"], json!(4), "both cancelled prompts are billed: {usage}");
+        assert_eq!(usage["output_tokens"], json!(0));
+    }
+
+    #[tokio::test]
+    async fn streams_cut_mid_answer_complete_with_the_salvaged_text() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.chaos = Some(xrouter_clients_openai::ChaosPolicy {
+            providers: vec!["zai".to_string()],
+            disconnect_probability: 1.0,
+            ..Default::default()
+        });
+        let stream = |app: axum::Router, uri: &'static str, body: Value| async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(uri)
+                        .header("content-type", "application/json")
+                        .body(Body::from(body.to_string()))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            let body = to_bytes(response.into_body(), usize::MAX).await.expect("body must be read");
+            String::from_utf8_lossy(&body)
+                .lines()
+                .filter_map(|line| line.strip_prefix("data: "))
+                .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+                .collect::<Vec<_>>()
+        };
+        let responses_body = json!({"model": "zai/glm-4.5", "input": "hello", "stream": true});
+        let chat_body = json!({
+            "model": "zai/glm-4.5",
+            "messages": [{"role": "user", "content": "hello"}],
+            "stream": true
+        });
+
+        let plain = build_router(AppBuilder::new(&config).build_state());
+        let events = stream(plain, "/api/v1/responses", responses_body.clone()).await;
+        assert_eq!(events.last().map(|event| &event["type"]), Some(&json!("response.error")));
+
+        config.stream_salvage_partial = true;
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let events = stream(app.clone(), "/api/v1/responses", responses_body).await;
+        let streamed =
+            events.iter().filter_map(|event| event["delta"].as_str()).collect::<String>();
+        let completed = events.last().expect("stream must end with an event");
+        assert!(!streamed.is_empty());
+        assert_eq!(completed["type"], "response.completed");
+        assert_eq!(completed["response"]["status"], "incomplete");
+        assert_eq!(completed["response"]["finish_reason"], "error");
+        assert_eq!(completed["response"]["output"][0]["content"][0]["text"], streamed);
+        assert!(completed["response"]["error"].is_string());
+
+        let chunks = stream(app, "/api/v1/chat/completions", chat_body).await;
+        let last = chunks.last().expect("stream must end with a chunk");
+        assert_eq!(last["choices"][0]["finish_reason"], "error");
+        assert!(last["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn race_models_answer_from_the_first_provider_to_respond() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.race_models = [(
+            "fast-chat".to_string(),
+            vec!["deepseek/deepseek-chat".to_string(), "zai/glm-4.5".to_string()],
+        )]
+        .into();
+        config.chaos = Some(xrouter_clients_openai::ChaosPolicy {
+            providers: vec!["deepseek".to_string()],
+            latency_ms: 500,
+            latency_probability: 1.0,
+            ..Default::default()
+        });
+        let state = AppBuilder::new(&config).build_state();
+        let app = build_router(state.clone());
+        let call = |stream: bool| {
+            let request = Request::builder()
+                .method("POST")
+                .uri("/api/v1/chat/completions")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({
+                        "model": "race/fast-chat",
+                        "messages": [{"role": "user", "content": "hello"}],
+                        "stream": stream
+                    })
+                    .to_string(),
+                ))
+                .expect("request must build");
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, String::from_utf8_lossy(&body).into_owned())
+            }
+        };
+
+        let (status, body) = call(false).await;
+        assert_eq!(status, StatusCode::OK);
+        let payload: Value = serde_json::from_str(&body).expect("body must be json");
+        let content = payload["choices"][0]["message"]["content"].as_str().unwrap_or_default();
+        assert!(content.starts_with("[zai] "), "unexpected content: {content}");
+
+        let (status, body) = call(true).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("[zai]"), "unexpected stream: {body}");
+        assert!(!body.contains("[deepseek]"), "loser output leaked: {body}");
+        assert!(state.model_stats.snapshot("deepseek/deepseek-chat").is_none());
+    }
+
+    #[tokio::test]
+    async fn bandit_models_learn_to_avoid_a_failing_provider() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        config.bandit_models = [(
+            "chat".to_string(),
+            crate::config::BanditModelConfig {
+                models: vec!["deepseek/deepseek-chat".to_string(), "zai/glm-4.5".to_string()],
+                strategy: crate::config::BanditStrategy::EpsilonGreedy,
+                reward: crate::config::BanditReward::Success,
+                epsilon: 0.0,
+            },
+        )]
+        .into();
+        config.chaos = Some(xrouter_clients_openai::ChaosPolicy {
+            providers: vec!["deepseek".to_string()],
+            error_probability: 1.0,
+            ..Default::default()
+        });
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let call = |method: &str, uri: &str, token: &str, body: Value| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {token}"))
+                .body(Body::from(body.to_string()))
+                .expect("request must build");
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let (status, created) = call(
+            "POST",
+            "/admin/v1/keys",
+            "admin-secret",
+            json!({"organization": "acme", "project": "chat"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let key = created["key"].as_str().expect("key must be returned").to_string();
+        let completion = json!({
+            "model": "bandit/chat",
+            "messages": [{"role": "user", "content": "hello"}]
+        });
+
+        // Each model is tried once; the failing one is avoided afterwards.
+        let mut statuses = Vec::new();
+        for _ in 0..4 {
+            let (status, _) =
+                call("POST", "/api/v1/chat/completions", &key, completion.clone()).await;
+            statuses.push(status);
+        }
+        assert_eq!(statuses.iter().filter(|status| **status == StatusCode::OK).count(), 3);
+
+        let (status, bandits) = call("GET", "/admin/v1/bandits", "admin-secret", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(bandits["data"][0]["model"], "bandit/chat");
+        assert_eq!(bandits["data"][0]["strategy"], "epsilon_greedy");
+        assert_eq!(
+            bandits["data"][0]["arms"],
+            json!([
+                {"model": "deepseek/deepseek-chat", "pulls": 1, "mean_reward": 0.0},
+                {"model": "zai/glm-4.5", "pulls": 3, "mean_reward": 1.0}
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn provider_health_dashboard_reports_windows_status_and_last_error() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let call = |method: &str, uri: &str, token: &str, body: Value| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {token}"))
+                .body(Body::from(body.to_string()))
+                .expect("request must build");
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let (status, created) = call(
+            "POST",
+            "/admin/v1/keys",
+            "admin-secret",
+            json!({"organization": "acme", "project": "ops"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let key = created["key"].as_str().expect("key must be returned").to_string();
+        for input in ["hello", "hello again", "__FAIL_PROVIDER__"] {
+            call(
+                "POST",
+                "/api/v1/responses",
+                &key,
+                json!({"model": "zai/glm-4.5", "input": input}),
+            )
+            .await;
+        }
+
+        let (status, _) = call("GET", "/admin/v1/health/providers", &key, Value::Null).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, health) =
+            call("GET", "/admin/v1/health/providers", "admin-secret", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let providers = health["data"].as_array().expect("data must be a list");
+        let zai =
+            providers.iter().find(|entry| entry["provider"] == "zai").expect("zai must be listed");
+        assert_eq!(zai["status"], "degraded");
+        assert_eq!(zai["in_flight"], 0);
+        assert_eq!(zai["consecutive_failures"], 1);
+        assert!(zai["last_error"]["message"].is_string());
+        let windows = zai["windows"].as_array().expect("windows must be a list");
+        assert_eq!(
+            windows.iter().map(|window| window["window"].clone()).collect::<Vec<_>>(),
+            [json!("5m"), json!("1h"), json!("24h")]
+        );
+        assert_eq!(windows[0]["requests"], 3);
+        assert_eq!(windows[0]["failures"], 1);
+        assert!(windows[0]["p95_ms"].is_u64());
+        let idle = providers
+            .iter()
+            .find(|entry| entry["provider"] == "deepseek")
+            .expect("configured providers must be listed");
+        assert_eq!(idle["status"], "idle");
+        assert!(providers.iter().all(|entry| entry["provider"] != "race"));
+    }
+
+    #[tokio::test]
+    async fn priority_classes_come_from_key_metadata_or_header_and_are_counted_per_provider() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        config.priority_scheduling = true;
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let call = |method: &str, uri: &str, headers: Vec<(&str, String)>, body: Value| {
+            let app = app.clone();
+            let mut request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json");
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            let request = request.body(Body::from(body.to_string())).expect("request must build");
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null))
+            }
+        };
+        let admin = || vec![("authorization", "Bearer admin-secret".to_string())];
+        let completion = json!({"model": "deepseek/deepseek-chat", "input": "hello"});
+
+        let (status, created) = call(
+            "POST",
+            "/admin/v1/keys",
+            admin(),
+            json!({"organization": "acme", "project": "jobs", "priority": "batch"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["api_key"]["priority"], json!("batch"));
+        let batch_key = created["key"].as_str().expect("key must be returned").to_string();
+        let (status, created) = call(
+            "POST",
+            "/admin/v1/keys",
+            admin(),
+            json!({"organization": "acme", "project": "web"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let plain_key = created["key"].as_str().expect("key must be returned").to_string();
+
+        let with_key = |key: &str, priority: Option<&str>| {
+            let mut headers = vec![("authorization", format!("Bearer {key}"))];
+            headers.extend(priority.map(|priority| ("x-xrouter-priority", priority.to_string())));
+            headers
+        };
+        for headers in [
+            with_key(&batch_key, Some("interactive")),
+            with_key(&plain_key, None),
+            with_key(&plain_key, Some("batch")),
+        ] {
+            let (status, _) = call("POST", "/api/v1/responses", headers, completion.clone()).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let (status, rejected) = call(
+            "POST",
+            "/api/v1/responses",
+            with_key(&plain_key, Some("urgent")),
+            completion.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(
+            rejected["error"].as_str().is_some_and(|error| error.contains("x-xrouter-priority"))
+        );
+
+        let (status, scheduling) = call("GET", "/admin/v1/scheduling", admin(), Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let deepseek = scheduling["data"]
+            .as_array()
+            .and_then(|providers| providers.iter().find(|entry| entry["provider"] == "deepseek"))
+            .expect("deepseek must be listed");
+        assert_eq!(deepseek["in_flight"], json!(0));
+        let admitted = deepseek["classes"]
+            .as_array()
+            .expect("classes must be listed")
+            .iter()
+            .map(|class| (class["priority"].clone(), class["admitted"].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(admitted, vec![(json!("interactive"), json!(1)), (json!("batch"), json!(2))]);
+    }
+
+    #[tokio::test]
+    async fn model_list_reports_daily_stats_only_for_models_with_traffic() {
+        let app = build_router(test_app_state(false));
+        for input in ["hello", "__FAIL_PROVIDER__"] {
+            app.clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            json!({"model":"deepseek/deepseek-chat","input":input,"stream":false})
+                                .to_string(),
+                        ))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+        }
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/models")
+                    .body(Body::empty())
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body read must succeed");
+        let payload: Value =
+            serde_json::from_slice(&body).expect("response body must be valid json");
+        let models = payload["data"].as_array().expect("data must be an array");
+        let deepseek = models
+            .iter()
+            .find(|model| model["id"] == json!("deepseek/deepseek-chat"))
+            .expect("deepseek model must be listed");
+        assert_eq!(deepseek["stats"]["requests_24h"], json!(2));
+        assert_eq!(deepseek["stats"]["error_rate_24h"], json!(0.5));
+        assert!(deepseek["stats"]["p50_ms"].is_u64());
+        assert!(
+            models
+                .iter()
+                .filter(|model| model["id"] != json!("deepseek/deepseek-chat"))
+                .all(|model| model.get("stats").is_none())
+        );
+    }
+
+    #[tokio::test]
+    async fn model_endpoints_report_catalog_limits_and_recent_provider_uptime() {
+        let app = build_router(test_app_state(false));
+        let failed = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/responses")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"model":"deepseek/deepseek-chat","input":"__FAIL_PROVIDER__","stream":false}"#,
+                    ))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        assert_eq!(failed.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/models/deepseek/deepseek-chat/endpoints")
+                    .body(Body::empty())
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body read must succeed");
+        let payload: Value =
+            serde_json::from_slice(&body).expect("response body must be valid json");
+        assert_eq!(payload["data"]["id"], json!("deepseek/deepseek-chat"));
+        let endpoint = &payload["data"]["endpoints"][0];
+        assert_eq!(endpoint["provider_name"], json!("deepseek"));
+        assert!(endpoint["context_length"].as_u64().is_some_and(|value| value > 0));
+        assert_eq!(endpoint["pricing"], Value::Null);
+        assert_eq!(endpoint["requests_last_30m"], json!(1));
+        assert_eq!(endpoint["uptime_last_30m"], json!(0.0));
+        assert_eq!(endpoint["status"], json!(-1));
+
+        let missing = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/models/nope/missing/endpoints")
+                    .body(Body::empty())
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn model_lists_apply_query_filters_and_pagination() {
+        let xrouter_app = build_router(test_app_state(false));
+        let compatible_app = build_router(test_app_state(true));
+        let fetch = |uri: &'static str| {
+            let app = if uri.starts_with("/api/") { &xrouter_app } else { &compatible_app }.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .uri(uri)
+                            .body(Body::empty())
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                serde_json::from_slice::<Value>(&body).expect("response body must be valid json")
+            }
+        };
+        let ids = |payload: &Value| {
+            payload["data"]
+                .as_array()
+                .expect("data must be an array")
+                .iter()
+                .map(|entry| entry["id"].as_str().expect("id must be a string").to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let deepseek = ids(&fetch("/api/v1/models?provider=deepseek").await);
+        assert!(!deepseek.is_empty());
+        assert!(deepseek.iter().all(|id| id.starts_with("deepseek/")));
+
+        let large = fetch("/api/v1/models?min_context=200000").await;
+        assert!(
+            large["data"]
+                .as_array()
+                .expect("data must be an array")
+                .iter()
+                .all(|entry| entry["context_length"].as_u64().is_some_and(|len| len >= 200_000))
+        );
+
+        let searched = ids(&fetch("/v1/models?q=DEEPSEEK-CHAT").await);
+        assert!(searched.contains(&"deepseek/deepseek-chat".to_string()));
+        assert!(searched.iter().all(|id| id.contains("deepseek")));
+
+        let all = ids(&fetch("/v1/models").await);
+        let page = ids(&fetch("/v1/models?offset=1&limit=2").await);
+        assert_eq!(page, all[1..3].to_vec());
+
+        assert!(ids(&fetch("/v1/models?supports_tools=false").await).is_empty());
+
+        let parameters = |payload: &Value| {
+            payload["data"][0]["supported_parameters"]
+                .as_array()
+                .expect("supported_parameters must be an array")
+                .iter()
+                .map(|parameter| parameter.as_str().expect("parameter").to_string())
+                .collect::<Vec<_>>()
+        };
+        let deepseek = parameters(&fetch("/api/v1/models?provider=deepseek").await);
+        assert!(deepseek.contains(&"reasoning".to_string()));
+        assert!(deepseek.contains(&"response_format".to_string()));
+        assert!(!deepseek.contains(&"structured_outputs".to_string()));
+        let gigachat = parameters(&fetch("/v1/models?provider=gigachat").await);
+        assert!(gigachat.contains(&"tools".to_string()));
+        assert!(!gigachat.contains(&"reasoning".to_string()));
+    }
+
+    #[tokio::test]
+    async fn responses_stream_emits_response_error_without_completion_on_provider_failure() {
+        let app = build_router(test_app_state(false));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/responses")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"model":"deepseek/deepseek-chat","input":"__FAIL_PROVIDER__","stream":true}"#,
+                    ))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
             .expect("response body read must succeed");
         let payload = String::from_utf8_lossy(&body);
         assert!(payload.contains("event: response.created"));
@@ -1051,6 +3561,349 @@ json.first_id=<none>
         assert!(!reasoning.is_empty(), "expected reasoning for deepseek-reasoner");
     }
 
+    #[tokio::test]
+    async fn conversation_budgets_carry_usage_into_follow_ups_and_refuse_turns_past_the_cap() {
+        let app = build_router(test_app_state(false).with_conversation_budget(Some(1), true));
+        let post = |body: Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/responses")
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).expect("body must be json"))
+            }
+        };
+
+        let (status, first) =
+            post(json!({"model": "deepseek/deepseek-chat", "input": "hello"})).await;
+        assert_eq!(status, StatusCode::OK);
+        let used = first["usage"]["total_tokens"].as_u64().expect("usage must be reported");
+        assert_eq!(
+            first["budget"],
+            json!({"used_tokens": used, "limit_tokens": 1, "remaining_tokens": 0})
+        );
+
+        let (status, refused) = post(json!({
+            "model": "deepseek/deepseek-chat",
+            "previous_response_id": first["id"],
+            "input": "again"
+        }))
+        .await;
+        assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(refused["param"], "previous_response_id");
+        assert_eq!(refused["budget"]["used_tokens"], json!(used));
+
+        let (status, fresh) =
+            post(json!({"model": "deepseek/deepseek-chat", "input": "new conversation"})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fresh["budget"]["limit_tokens"], json!(1));
+    }
+
+    #[tokio::test]
+    async fn encrypted_reasoning_is_included_on_request_and_carried_into_follow_ups() {
+        let app = build_router(test_app_state(false));
+        let post = |body: Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/responses")
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                serde_json::from_slice::<Value>(&body).expect("response body must be valid json")
+            }
+        };
+        let encrypted = |payload: &Value| {
+            payload["output"]
+                .as_array()
+                .and_then(|items| items.iter().find(|item| item["type"] == "reasoning"))
+                .and_then(|item| item.get("encrypted_content"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let text = |payload: &Value| payload["output"][0]["content"][0]["text"].to_string();
+
+        let plain = post(json!({"model": "deepseek/deepseek-reasoner", "input": "first"})).await;
+        assert_eq!(encrypted(&plain), None);
+
+        let first = post(json!({
+            "model": "deepseek/deepseek-reasoner",
+            "input": "first",
+            "include": ["reasoning.encrypted_content"]
+        }))
+        .await;
+        assert_eq!(encrypted(&first).as_deref(), Some("mock-encrypted:deepseek"));
+
+        let follow_up = post(json!({
+            "model": "deepseek/deepseek-reasoner",
+            "previous_response_id": first["id"],
+            "input": "second"
+        }))
+        .await;
+        assert!(text(&follow_up).contains("assistant_reasoning:Reasoned"), "{follow_up}");
+
+        let unstored = post(json!({
+            "model": "deepseek/deepseek-reasoner",
+            "input": "first",
+            "include": ["reasoning.encrypted_content"],
+            "store": false
+        }))
+        .await;
+        let follow_up = post(json!({
+            "model": "deepseek/deepseek-reasoner",
+            "previous_response_id": unstored["id"],
+            "input": "second"
+        }))
+        .await;
+        assert!(!text(&follow_up).contains("assistant_reasoning"), "{follow_up}");
+    }
+
+    #[tokio::test]
+    async fn serving_provider_and_fallback_reason_are_reported_in_headers_and_meta() {
+        let app = build_router(test_app_state(false));
+        let post = |uri: &'static str, body: Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                assert_eq!(response.status(), StatusCode::OK);
+                let header = |name: &str| {
+                    response
+                        .headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string)
+                };
+                let headers = (
+                    header("x-xrouter-provider"),
+                    header("x-xrouter-attempts"),
+                    header("x-xrouter-fallback-reason"),
+                );
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                let payload = serde_json::from_slice::<Value>(&body)
+                    .expect("response body must be valid json");
+                (headers, payload["meta"].clone())
+            }
+        };
+
+        let (headers, meta) =
+            post("/api/v1/responses", json!({"model": "deepseek/deepseek-chat", "input": "hi"}))
+                .await;
+        assert_eq!(headers, (Some("deepseek".to_string()), Some("1".to_string()), None));
+        assert_eq!(meta, json!({"provider": "deepseek", "attempts": 1}));
+
+        let (headers, meta) = post(
+            "/api/v1/chat/completions",
+            json!({"model": "no-such-model", "messages": [{"role": "user", "content": "hi"}]}),
+        )
+        .await;
+        let provider = meta["provider"].as_str().expect("provider must be reported").to_string();
+        assert_eq!(
+            headers,
+            (
+                Some(provider),
+                Some("1".to_string()),
+                Some("unknown_model_default_provider".to_string())
+            )
+        );
+        assert_eq!(meta["fallback_reason"], "unknown_model_default_provider");
+    }
+
+    #[tokio::test]
+    async fn legacy_prompt_and_messages_are_accepted_with_a_deprecation_warning() {
+        let app = build_router(test_app_state(false));
+        for (body, field) in [
+            (json!({"model": "deepseek/deepseek-chat", "prompt": "hello world"}), "prompt"),
+            (
+                json!({
+                    "model": "deepseek/deepseek-chat",
+                    "messages": [{"role": "user", "content": "hello world"}]
+                }),
+                "messages",
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .body(Body::from(body.to_string()))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["deprecation"], "true");
+            let warning = response.headers()["warning"].to_str().expect("warning header");
+            assert!(warning.contains(&format!("`{field}` is deprecated")), "{warning}");
+            let body = to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("response body read must succeed");
+            let payload =
+                serde_json::from_slice::<Value>(&body).expect("response body must be valid json");
+            assert!(
+                payload["output"][0]["content"][0]["text"]
+                    .as_str()
+                    .expect("output text")
+                    .contains("hello world"),
+                "{payload}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_models_are_rejected_with_suggestions_or_replaced_by_the_default_model() {
+        let post = |state: AppState, model: &'static str| async move {
+            let response = build_router(state)
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .body(Body::from(json!({"model": model, "input": "hi"}).to_string()))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            let status = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("response body read must succeed");
+            (status, serde_json::from_slice::<Value>(&body).expect("response body must be json"))
+        };
+
+        let rejecting = test_app_state(false).with_unknown_model(UnknownModelConfig::Reject);
+        let (status, payload) = post(rejecting.clone(), "deepseek-chatt").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(payload["param"], "model");
+        let error = payload["error"].as_str().expect("error");
+        assert!(error.contains("did you mean deepseek/deepseek-chat"), "{error}");
+        let (status, payload) = post(rejecting, "completely-unrelated-name-xyz").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(!payload["error"].as_str().expect("error").contains("did you mean"));
+
+        let defaulting =
+            test_app_state(false).with_unknown_model(UnknownModelConfig::DefaultModel {
+                model: "deepseek/deepseek-chat".to_string(),
+            });
+        let (status, payload) = post(defaulting, "no-such-model").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(payload["meta"]["provider"], "deepseek");
+        assert_eq!(payload["meta"]["fallback_reason"], "unknown_model_default_model");
+    }
+
+    #[tokio::test]
+    async fn responses_reject_output_controls_the_model_cannot_honour() {
+        let app = build_router(test_app_state(false));
+        for (body, expected) in [
+            (
+                json!({"model": "deepseek/deepseek-chat", "input": "hi", "modalities": ["text", "audio"]}),
+                "does not support output modality `audio`",
+            ),
+            (
+                json!({"model": "deepseek/deepseek-chat", "input": "hi", "text": {"format": {"type": "json_schema", "name": "answer"}}}),
+                "json_schema requires a schema",
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/v1/responses")
+                        .header("content-type", "application/json")
+                        .body(Body::from(body.to_string()))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body = to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("response body read must succeed");
+            let payload: Value =
+                serde_json::from_slice(&body).expect("response body must be valid json");
+            assert!(
+                payload["error"].as_str().is_some_and(|error| error.contains(expected)),
+                "{payload}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn chat_image_parts_are_rejected_for_text_only_models() {
+        let app = build_router(test_app_state(false));
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/chat/completions")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({
+                            "model": "deepseek/deepseek-chat",
+                            "messages": [{"role": "user", "content": [
+                                {"type": "text", "text": "what is this?"},
+                                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+                            ]}]
+                        })
+                        .to_string(),
+                    ))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body read must succeed");
+        let payload: Value =
+            serde_json::from_slice(&body).expect("response body must be valid json");
+        assert!(
+            payload["error"]
+                .as_str()
+                .is_some_and(|error| error.contains("does not accept image input")),
+            "{payload}"
+        );
+    }
+
     #[tokio::test]
     async fn chat_reasoner_model_maps_reasoning_to_message_field() {
         let app = build_router(test_app_state(false));
@@ -1087,6 +3940,326 @@ json.first_id=<none>
         assert!(!reasoning.is_empty(), "expected reasoning in chat message for reasoner model");
     }
 
+    #[tokio::test]
+    async fn validate_reports_routing_tokens_and_cost_without_calling_the_provider() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.model_overrides = [(
+            "deepseek/deepseek-chat".to_string(),
+            crate::config::ModelOverrideConfig {
+                pricing: Some(crate::config::ModelPricingConfig {
+                    prompt: "0.000001".to_string(),
+                    completion: "0.000002".to_string(),
+                }),
+                ..Default::default()
+            },
+        )]
+        .into();
+        let state = AppBuilder::new(&config).build_state();
+        let app = build_router(state.clone());
+        let post = |body: Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/validate")
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .expect("request must build"),
+                    )
+                    .await
+                    .expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).expect("body must be json"))
+            }
+        };
+
+        let (status, validated) = post(json!({
+            "model": "deepseek/deepseek-chat",
+            "input": "count these four words",
+            "max_output_tokens": 100
+        }))
+        .await;
+        assert_eq!(status, StatusCode::OK, "{validated}");
+        assert_eq!(validated["model"], "deepseek/deepseek-chat");
+        assert_eq!(validated["provider"], "deepseek");
+        assert_eq!(validated["input_tokens"], 4);
+        assert_eq!(validated["context_length"], 128000);
+        assert_eq!(validated["estimated_cost"], "0.000204");
+        assert!(state.model_stats.snapshot("deepseek/deepseek-chat").is_none());
+
+        let (status, rejected) = post(json!({
+            "model": "deepseek/deepseek-chat",
+            "input": "hello",
+            "tools": [{"type": "function", "function": {"name": "lookup"}}],
+            "tool_choice": {"type": "function", "function": {"name": "missing"}}
+        }))
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(rejected["param"].as_str().is_some_and(|param| param.starts_with("tool_choice")));
+    }
+
+    #[tokio::test]
+    async fn estimate_prices_every_endpoint_of_the_model() {
+        let mut config = crate::config::AppConfig::for_tests();
+        let pricing = |prompt: &str, completion: &str| crate::config::ModelOverrideConfig {
+            pricing: Some(crate::config::ModelPricingConfig {
+                prompt: prompt.to_string(),
+                completion: completion.to_string(),
+            }),
+            ..Default::default()
+        };
+        config.openrouter_supported_models = vec!["deepseek/deepseek-chat".to_string()];
+        config.model_overrides = [
+            ("deepseek/deepseek-chat".to_string(), pricing("0.000001", "0.000002")),
+            ("openrouter/deepseek/deepseek-chat".to_string(), pricing("0.000003", "0.000004")),
+        ]
+        .into();
+        let response = build_router(AppBuilder::new(&config).build_state())
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/estimate")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({
+                            "model": "deepseek/deepseek-chat",
+                            "input": "count these four words",
+                            "max_output_tokens": 100
+                        })
+                        .to_string(),
+                    ))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body read must succeed");
+        let estimate: Value = serde_json::from_slice(&body).expect("body must be json");
+        let endpoints = estimate["endpoints"].as_array().expect("endpoints must be listed");
+        assert_eq!(
+            endpoints.iter().map(|endpoint| endpoint["provider"].clone()).collect::<Vec<_>>(),
+            [json!("deepseek"), json!("openrouter")]
+        );
+        assert!(endpoints.iter().all(|endpoint| endpoint["input_tokens"] == 4));
+        assert_eq!(endpoints[0]["max_cost"], "0.000204");
+        assert_eq!(estimate["min_cost"], "0.000004");
+        assert_eq!(estimate["max_cost"], "0.000412");
+    }
+
+    #[tokio::test]
+    async fn enabled_header_overrides_replace_model_provider_and_reasoning_effort() {
+        let post = |state: AppState, headers: Vec<(&'static str, &'static str)>| async move {
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/api/v1/responses")
+                .header("content-type", "application/json");
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            let response = build_router(state)
+                .oneshot(
+                    request
+                        .body(Body::from(
+                            json!({"model": "deepseek/deepseek-chat", "input": "hello"})
+                                .to_string(),
+                        ))
+                        .expect("request must build"),
+                )
+                .await
+                .expect("request must complete");
+            let status = response.status();
+            let body = to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("response body read must succeed");
+            (status, serde_json::from_slice::<Value>(&body).expect("body must be json"))
+        };
+        let enabled = || {
+            test_app_state(false).with_header_overrides(
+                crate::config::HeaderOverrides::parse("all").expect("overrides must parse"),
+            )
+        };
+
+        let (status, ignored) =
+            post(test_app_state(false), vec![("x-xrouter-provider", "gigachat")]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ignored["meta"]["provider"], "deepseek");
+
+        let (status, rerouted) = post(enabled(), vec![("X-XRouter-Provider", "gigachat")]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(rerouted["meta"]["provider"], "gigachat", "{rerouted}");
+
+        let (status, replaced) = post(
+            enabled(),
+            vec![
+                ("x-xrouter-model", "deepseek/deepseek-reasoner"),
+                ("x-xrouter-reasoning-effort", "high"),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(
+            replaced["output"]
+                .as_array()
+                .is_some_and(|items| items.iter().any(|item| item["type"] == "reasoning")),
+            "{replaced}"
+        );
+
+        let (status, rejected) =
+            post(enabled(), vec![("x-xrouter-reasoning-effort", "extreme")]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(
+            rejected["error"]
+                .as_str()
+                .is_some_and(|error| error.contains("x-xrouter-reasoning-effort")),
+            "{rejected}"
+        );
+        let (status, _) = post(enabled(), vec![("x-xrouter-provider", "nowhere")]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn chat_reasoning_is_hidden_for_models_listed_in_hide_reasoning_models() {
+        let state = test_app_state(false)
+            .with_hide_reasoning_models(&["deepseek/deepseek-reasoner".to_string()]);
+        let response = build_router(state)
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/chat/completions")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({
+                            "model": "deepseek/deepseek-reasoner",
+                            "messages": [{"role": "user", "content": "Solve 2+2 briefly"}],
+                            "reasoning": {"effort": "high"}
+                        })
+                        .to_string(),
+                    ))
+                    .expect("request must build"),
+            )
+            .await
+            .expect("request must complete");
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("response body read must succeed");
+        let payload: Value = serde_json::from_slice(&body).expect("body must be json");
+        let message = &payload["choices"][0]["message"];
+        assert!(message.get("reasoning").is_none(), "{payload}");
+        assert!(message.get("reasoning_content").is_none(), "{payload}");
+        assert!(message["content"].as_str().is_some_and(|content| !content.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn responses_reasoning_is_hidden_for_keys_with_hide_reasoning() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let call = |uri: &'static str, bearer: String, body: Value| {
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {bearer}"))
+                .body(Body::from(body.to_string()))
+                .expect("request must build");
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                assert!(response.status().is_success(), "{}", response.status());
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                serde_json::from_slice::<Value>(&body).expect("body must be json")
+            }
+        };
+        let issue = |hide_reasoning: bool| {
+            let created = call(
+                "/admin/v1/keys",
+                "admin-secret".to_string(),
+                json!({"organization": "acme", "project": "web", "hide_reasoning": hide_reasoning}),
+            );
+            async move {
+                let created = created.await;
+                assert_eq!(created["api_key"]["hide_reasoning"], json!(hide_reasoning));
+                created["key"].as_str().expect("key must be returned").to_string()
+            }
+        };
+        let has_reasoning = |payload: &Value| {
+            payload["output"]
+                .as_array()
+                .is_some_and(|items| items.iter().any(|item| item["type"] == "reasoning"))
+        };
+        let body = json!({"model": "deepseek/deepseek-reasoner", "input": "Solve 2+2 briefly"});
+
+        let shown = call("/api/v1/responses", issue(false).await, body.clone()).await;
+        assert!(has_reasoning(&shown), "{shown}");
+        let hidden = call("/api/v1/responses", issue(true).await, body).await;
+        assert!(!has_reasoning(&hidden), "{hidden}");
+        assert_eq!(hidden["output"][0]["type"], "message", "{hidden}");
+    }
+
+    #[tokio::test]
+    async fn disabled_keys_are_rejected_before_replays_and_header_checks() {
+        let mut config = crate::config::AppConfig::for_tests();
+        config.admin_token = Some("admin-secret".to_string());
+        let app = build_router(AppBuilder::new(&config).build_state());
+        let send = |method: &'static str, uri: String, bearer: String, body: Value| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {bearer}"))
+                .header("idempotency-key", "order-1")
+                .header("x-xrouter-truncation", "not-a-strategy")
+                .body(Body::from(body.to_string()))
+                .expect("request must build");
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.expect("request must complete");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("response body read must succeed");
+                (status, serde_json::from_slice::<Value>(&body).expect("body must be json"))
+            }
+        };
+        let (status, created) = send(
+            "POST",
+            "/admin/v1/keys".to_string(),
+            "admin-secret".to_string(),
+            json!({"organization": "acme", "project": "web", "hide_reasoning": true}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{created}");
+        let key = created["key"].as_str().expect("key must be returned").to_string();
+        let id = created["api_key"]["id"].as_str().expect("id must be returned").to_string();
+        let (status, _) = send(
+            "PATCH",
+            format!("/admin/v1/keys/{id}"),
+            "admin-secret".to_string(),
+            json!({"disabled": true}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let body = json!({"model": "deepseek/deepseek-chat", "input": "hi"});
+        for uri in ["/api/v1/responses", "/api/v1/validate"] {
+            let (status, payload) = send("POST", uri.to_string(), key.clone(), body.clone()).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{uri}: {payload}");
+            assert_eq!(payload["error"], "API key is disabled", "{uri}");
+        }
+    }
+
     #[test]
     fn parse_bearer_token_accepts_case_insensitive_scheme() {
         let mut headers = HeaderMap::new();
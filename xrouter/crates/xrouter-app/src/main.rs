@@ -1,26 +1,230 @@
-use std::net::SocketAddr;
+use std::{
+    env,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use tracing::info;
-use xrouter_app::{AppBuilder, config::AppConfig};
-use xrouter_observability::init_observability;
+use clap::{Parser, Subcommand};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+use xrouter_app::{
+    AppBuilder, build_router, config::AppConfig, config_file::load_config_file, migrate_database,
+    prepare_database, probe_provider,
+};
+use xrouter_core::synthesize_model_id;
+use xrouter_observability::{init_observability, register_gauge};
 
-#[tokio::main]
-async fn main() {
+#[derive(Parser)]
+#[command(name = "xrouter", about = "LLM router with OpenAI-compatible APIs")]
+struct Cli {
+    /// YAML or TOML config file; non-empty env vars override its values.
+    #[arg(long, global = true, env = "XR_CONFIG_FILE")]
+    config: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the HTTP server (default).
+    Serve,
+    /// Inspect configuration.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Inspect the model catalog.
+    Models {
+        #[command(subcommand)]
+        command: ModelsCommand,
+    },
+    /// Send a one-token canary request to a provider and report latency.
+    Probe { provider: String },
+    /// Apply pending database migrations.
+    Migrate {
+        /// Only list pending migrations; fails when there are any.
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Validate the effective config and print it with secrets redacted.
+    Validate,
+}
+
+#[derive(Subcommand)]
+enum ModelsCommand {
+    /// Print the merged model catalog.
+    List,
+}
+
+/// Fills environment variables that are unset or empty from the config file, so real env vars
+/// (and `.env`) take precedence. Runs before any thread is spawned.
+fn apply_config_file(path: &Path) -> Result<(), String> {
+    let values = load_config_file(path).map_err(|error| error.to_string())?;
+    for (name, value) in values {
+        if env::var(&name).is_ok_and(|current| !current.trim().is_empty()) {
+            continue;
+        }
+        // SAFETY: called from `main` before the tokio runtime or any other thread starts.
+        unsafe { env::set_var(name, value) };
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
     let _ = dotenvy::dotenv();
+    let cli = Cli::parse();
+    let config_path = cli.config.filter(|path| !path.as_os_str().is_empty());
+    if let Some(path) = config_path.as_deref()
+        && let Err(error) = apply_config_file(path)
+    {
+        eprintln!("xrouter: {error}");
+        return ExitCode::FAILURE;
+    }
+    let config = match AppConfig::from_env() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("xrouter: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Config { command: ConfigCommand::Validate } => {
+            let summary = serde_json::to_string_pretty(&config.redacted_summary())
+                .expect("config summary must serialize");
+            println!("{summary}");
+            ExitCode::SUCCESS
+        }
+        Command::Models { command: ModelsCommand::List } => {
+            let state = AppBuilder::new(&config).build_state();
+            println!("id\tcontext_length\tmax_completion_tokens\tsupports_tools");
+            for model in state.models() {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    synthesize_model_id(&model.provider, &model.id),
+                    model.context_length,
+                    model.max_completion_tokens,
+                    model.supports_tools
+                );
+            }
+            ExitCode::SUCCESS
+        }
+        Command::Probe { provider } => runtime().block_on(async {
+            let state = AppBuilder::new(&config).build_state();
+            let Some(report) = probe_provider(&state, &provider).await else {
+                eprintln!("xrouter: provider `{provider}` is unknown or disabled");
+                return ExitCode::FAILURE;
+            };
+            let rendered =
+                serde_json::to_string_pretty(&report).expect("probe report must serialize");
+            println!("{rendered}");
+            if report.ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+        }),
+        Command::Migrate { check } => runtime().block_on(async {
+            match migrate_database(&config, check).await {
+                Ok(None) => {
+                    println!("no database-backed store is configured");
+                    ExitCode::SUCCESS
+                }
+                Ok(Some(versions)) if versions.is_empty() => {
+                    println!("database schema is up to date");
+                    ExitCode::SUCCESS
+                }
+                Ok(Some(versions)) if check => {
+                    println!("pending migrations: {versions:?}");
+                    ExitCode::FAILURE
+                }
+                Ok(Some(versions)) => {
+                    println!("applied migrations: {versions:?}");
+                    ExitCode::SUCCESS
+                }
+                Err(error) => {
+                    eprintln!("xrouter: {error}");
+                    ExitCode::FAILURE
+                }
+            }
+        }),
+        Command::Serve => runtime().block_on(serve(config, config_path)),
+    }
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime must build")
+}
+
+async fn serve(config: AppConfig, config_path: Option<PathBuf>) -> ExitCode {
     init_observability("xrouter-app");
 
-    let config = AppConfig::from_env().expect("configuration must be valid");
     info!(
         event = "app.starting",
         host = %config.host,
         port = config.port,
+        config_file = ?config_path,
         openai_compatible_api = config.openai_compatible_api,
         provider_max_inflight = config.provider_max_inflight
     );
-    let app = AppBuilder::new(&config).build_router();
+    if let Err(err) = prepare_database(&config).await {
+        error!(event = "app.database.schema_check_failed", error = %err);
+        eprintln!("xrouter: {err}");
+        return ExitCode::FAILURE;
+    }
+    let state = AppBuilder::new(&config).build_state();
+    let gauge_state = state.clone();
+    register_gauge(
+        "xrouter.stream.tasks_in_flight",
+        "Generations streaming on this replica",
+        move || gauge_state.tasks_in_flight() as u64,
+    );
+    let app = build_router(state.clone());
     let addr: SocketAddr =
         format!("{}:{}", config.host, config.port).parse().expect("socket address must be valid");
 
     let listener = tokio::net::TcpListener::bind(addr).await.expect("listener must bind");
-    axum::serve(listener, app).await.expect("server must run");
+    // Open response streams hold their connections, so they are cancelled as soon as the
+    // signal arrives rather than after the server has drained.
+    let stopping = CancellationToken::new();
+    let server =
+        axum::serve(listener, app).with_graceful_shutdown(stopping.clone().cancelled_owned());
+    let drain_streams = async {
+        shutdown_signal().await;
+        info!(event = "app.shutdown.started");
+        stopping.cancel();
+        state.shutdown_streams().await;
+    };
+    let (served, ()) = tokio::join!(server.into_future(), drain_streams);
+    served.expect("server must run");
+    info!(event = "app.shutdown.completed");
+    ExitCode::SUCCESS
+}
+
+/// Resolves on ctrl-c or, on unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
 }
@@ -0,0 +1,374 @@
+use std::path::Path;
+
+use rusqlite::{Connection, params};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::{
+    config::{AppConfig, KeyStoreConfig, ResponseStoreConfig, StorageConfig},
+    response_store::connect_postgres,
+    secrets::hex,
+    storage::connect_sqlite,
+};
+
+/// Serializes migrations across replicas starting at the same time.
+const MIGRATION_LOCK_KEY: i64 = 0x7872_6f75_7465_7200;
+const MIGRATIONS_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS xrouter_schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);";
+const SQLITE_MIGRATIONS_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS xrouter_schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);";
+
+struct Migration {
+    version: i32,
+    name: &'static str,
+    sql: &'static str,
+}
+
+/// Schema changes of the database-backed stores, in order. Applied migrations must never be
+/// edited; add a new one instead.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_responses",
+        sql: "
+CREATE TABLE IF NOT EXISTS xrouter_responses (
+    response_id TEXT PRIMARY KEY,
+    items JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS xrouter_responses_created_at ON xrouter_responses (created_at);",
+    },
+    Migration {
+        version: 2,
+        name: "create_api_keys",
+        sql: "
+CREATE TABLE IF NOT EXISTS xrouter_api_keys (
+    id TEXT PRIMARY KEY,
+    record JSONB NOT NULL
+);",
+    },
+];
+
+/// Schema changes of the embedded SQLite database of `XR_STORAGE`, numbered on their own.
+const SQLITE_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_responses",
+        sql: "
+CREATE TABLE IF NOT EXISTS xrouter_responses (
+    response_id TEXT PRIMARY KEY,
+    items TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS xrouter_responses_created_at ON xrouter_responses (created_at);",
+    },
+    Migration {
+        version: 2,
+        name: "create_api_keys",
+        sql: "
+CREATE TABLE IF NOT EXISTS xrouter_api_keys (
+    id TEXT PRIMARY KEY,
+    record TEXT NOT NULL
+);",
+    },
+    Migration {
+        version: 3,
+        name: "create_usage_ledger",
+        sql: "
+CREATE TABLE IF NOT EXISTS xrouter_usage (
+    date TEXT NOT NULL,
+    tenant_id TEXT NOT NULL,
+    model TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    requests INTEGER NOT NULL,
+    failed_requests INTEGER NOT NULL,
+    input_tokens INTEGER NOT NULL,
+    output_tokens INTEGER NOT NULL,
+    total_tokens INTEGER NOT NULL,
+    PRIMARY KEY (date, tenant_id, model, provider)
+);
+CREATE TABLE IF NOT EXISTS xrouter_generations (
+    request_id TEXT NOT NULL,
+    date TEXT NOT NULL,
+    record TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS xrouter_generations_date ON xrouter_generations (date);",
+    },
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("database is unavailable: {0}")]
+    Backend(String),
+    #[error("database schema is missing migrations {0:?}; run `xrouter migrate`")]
+    Pending(Vec<i32>),
+    #[error("database schema has migration {0}, which this build does not know")]
+    Unknown(i32),
+    #[error("database migration {0} differs from the one in this build")]
+    Modified(i32),
+}
+
+impl From<tokio_postgres::Error> for MigrationError {
+    fn from(error: tokio_postgres::Error) -> Self {
+        Self::Backend(error.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::Backend(error.to_string())
+    }
+}
+
+/// Applies pending migrations, or only lists them when `dry_run` is set. Returns the versions
+/// applied (or pending), or `None` when no database-backed store is configured.
+pub async fn migrate_database(
+    config: &AppConfig,
+    dry_run: bool,
+) -> Result<Option<Vec<i32>>, MigrationError> {
+    let urls = postgres_urls(config);
+    let sqlite_path = match &config.storage {
+        StorageConfig::PerStore => None,
+        StorageConfig::Sqlite { path } => Some(path),
+    };
+    if urls.is_empty() && sqlite_path.is_none() {
+        return Ok(None);
+    }
+    let mut versions = Vec::new();
+    for url in urls {
+        versions.extend(migrate_postgres(url, dry_run).await?);
+    }
+    if let Some(path) = sqlite_path {
+        versions.extend(migrate_sqlite_file(path, dry_run)?);
+    }
+    versions.sort_unstable();
+    versions.dedup();
+    Ok(Some(versions))
+}
+
+/// Every distinct Postgres database a configured store lives in.
+fn postgres_urls(config: &AppConfig) -> Vec<&str> {
+    let mut urls = Vec::new();
+    if let ResponseStoreConfig::Postgres { url } = &config.response_store {
+        urls.push(url.as_str());
+    }
+    if let KeyStoreConfig::Postgres { url } = &config.key_store
+        && !urls.contains(&url.as_str())
+    {
+        urls.push(url.as_str());
+    }
+    urls
+}
+
+async fn migrate_postgres(url: &str, dry_run: bool) -> Result<Vec<i32>, MigrationError> {
+    let mut client = connect_postgres(url).await?;
+    if dry_run {
+        let pending = pending(MIGRATIONS, &applied(&client).await?)?;
+        return Ok(pending.iter().map(|migration| migration.version).collect());
+    }
+    client.batch_execute(MIGRATIONS_TABLE).await?;
+    client.execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK_KEY]).await?;
+    let pending = pending(MIGRATIONS, &applied(&client).await?)?;
+    let versions = pending.iter().map(|migration| migration.version).collect::<Vec<_>>();
+    for migration in pending {
+        let transaction = client.transaction().await?;
+        transaction.batch_execute(migration.sql).await?;
+        transaction
+            .execute(
+                "INSERT INTO xrouter_schema_migrations (version, name, checksum) \
+                 VALUES ($1, $2, $3)",
+                &[&migration.version, &migration.name, &checksum(migration)],
+            )
+            .await?;
+        transaction.commit().await?;
+        info!(
+            event = "app.database.migration_applied",
+            version = migration.version,
+            name = migration.name
+        );
+    }
+    client.execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK_KEY]).await?;
+    Ok(versions)
+}
+
+/// Startup check: migrates Postgres when `XR_DATABASE_MIGRATE_ON_STARTUP` is on, otherwise
+/// requires its schema to be current already. The embedded SQLite database is always migrated.
+pub async fn prepare_database(config: &AppConfig) -> Result<(), MigrationError> {
+    let migrate = config.database_migrate_on_startup;
+    let mut pending = Vec::new();
+    for url in postgres_urls(config) {
+        pending.extend(migrate_postgres(url, !migrate).await?);
+    }
+    if !migrate && !pending.is_empty() {
+        return Err(MigrationError::Pending(pending));
+    }
+    if let StorageConfig::Sqlite { path } = &config.storage {
+        migrate_sqlite_file(path, false)?;
+    }
+    Ok(())
+}
+
+fn migrate_sqlite_file(path: &Path, dry_run: bool) -> Result<Vec<i32>, MigrationError> {
+    migrate_sqlite(&mut connect_sqlite(path)?, dry_run)
+}
+
+/// Applies the pending migrations of the embedded database, or only lists them on `dry_run`.
+/// A single node owns the file, so there is no lock to take.
+pub(crate) fn migrate_sqlite(
+    connection: &mut Connection,
+    dry_run: bool,
+) -> Result<Vec<i32>, MigrationError> {
+    let pending = pending(SQLITE_MIGRATIONS, &sqlite_applied(connection)?)?;
+    let versions = pending.iter().map(|migration| migration.version).collect::<Vec<_>>();
+    if dry_run {
+        return Ok(versions);
+    }
+    connection.execute_batch(SQLITE_MIGRATIONS_TABLE)?;
+    for migration in pending {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration.sql)?;
+        transaction.execute(
+            "INSERT INTO xrouter_schema_migrations (version, name, checksum) VALUES (?1, ?2, ?3)",
+            params![migration.version, migration.name, checksum(migration)],
+        )?;
+        transaction.commit()?;
+        info!(
+            event = "app.database.migration_applied",
+            backend = "sqlite",
+            version = migration.version,
+            name = migration.name
+        );
+    }
+    Ok(versions)
+}
+
+fn sqlite_applied(connection: &Connection) -> Result<Vec<(i32, String)>, MigrationError> {
+    let tracked = connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master \
+         WHERE type = 'table' AND name = 'xrouter_schema_migrations')",
+        [],
+        |row| row.get::<_, bool>(0),
+    )?;
+    if !tracked {
+        return Ok(Vec::new());
+    }
+    let mut statement = connection
+        .prepare("SELECT version, checksum FROM xrouter_schema_migrations ORDER BY version")?;
+    let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Versions and checksums recorded in the database; none before the first migration.
+async fn applied(client: &tokio_postgres::Client) -> Result<Vec<(i32, String)>, MigrationError> {
+    let tracked = client
+        .query_one("SELECT to_regclass('xrouter_schema_migrations') IS NOT NULL", &[])
+        .await?;
+    if !tracked.get::<_, bool>(0) {
+        return Ok(Vec::new());
+    }
+    let rows = client
+        .query("SELECT version, checksum FROM xrouter_schema_migrations ORDER BY version", &[])
+        .await?;
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// Migrations not yet in `applied`, after checking the applied ones match this build.
+fn pending(
+    migrations: &'static [Migration],
+    applied: &[(i32, String)],
+) -> Result<Vec<&'static Migration>, MigrationError> {
+    for (version, applied_checksum) in applied {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.version == *version)
+            .ok_or(MigrationError::Unknown(*version))?;
+        if checksum(migration) != *applied_checksum {
+            return Err(MigrationError::Modified(*version));
+        }
+    }
+    Ok(migrations
+        .iter()
+        .filter(|migration| !applied.iter().any(|(version, _)| *version == migration.version))
+        .collect())
+}
+
+fn checksum(migration: &Migration) -> String {
+    hex(&Sha256::digest(migration.sql.trim().as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        MIGRATIONS, MigrationError, SQLITE_MIGRATIONS, checksum, migrate_database, pending,
+        prepare_database,
+    };
+    use crate::config::{AppConfig, KeyStoreConfig, ResponseStoreConfig, StorageConfig};
+
+    #[test]
+    fn migrations_are_numbered_in_order_from_one() {
+        for migrations in [MIGRATIONS, SQLITE_MIGRATIONS] {
+            for (index, migration) in migrations.iter().enumerate() {
+                assert_eq!(migration.version, index as i32 + 1, "{}", migration.name);
+            }
+        }
+    }
+
+    #[test]
+    fn pending_skips_applied_migrations_and_rejects_unknown_or_modified_ones() {
+        let first = &MIGRATIONS[0];
+        assert_eq!(pending(MIGRATIONS, &[]).expect("fresh database").len(), MIGRATIONS.len());
+        assert!(
+            pending(MIGRATIONS, &[(first.version, checksum(first))])
+                .expect("current database")
+                .iter()
+                .all(|migration| migration.version != first.version)
+        );
+        assert!(matches!(
+            pending(MIGRATIONS, &[(first.version, "edited".to_string())]),
+            Err(MigrationError::Modified(1))
+        ));
+        assert!(matches!(
+            pending(MIGRATIONS, &[(999, String::new())]),
+            Err(MigrationError::Unknown(999))
+        ));
+    }
+
+    #[tokio::test]
+    async fn migrate_is_a_no_op_without_a_database_and_fails_when_it_is_unreachable() {
+        let mut config = AppConfig::for_tests();
+        assert_eq!(migrate_database(&config, false).await.expect("nothing to migrate"), None);
+
+        config.response_store =
+            ResponseStoreConfig::Postgres { url: "postgres://xrouter@127.0.0.1:1/xrouter".into() };
+        assert!(matches!(migrate_database(&config, true).await, Err(MigrationError::Backend(_))));
+
+        config.response_store = ResponseStoreConfig::Memory;
+        config.key_store =
+            KeyStoreConfig::Postgres { url: "postgres://xrouter@127.0.0.1:1/xrouter".into() };
+        assert!(matches!(migrate_database(&config, true).await, Err(MigrationError::Backend(_))));
+    }
+
+    #[tokio::test]
+    async fn sqlite_storage_is_migrated_at_startup_even_without_migrate_on_startup() {
+        let path = std::env::temp_dir().join(format!("xrouter-{}.db", uuid::Uuid::new_v4()));
+        let mut config = AppConfig::for_tests();
+        config.storage = StorageConfig::Sqlite { path: path.clone() };
+        config.database_migrate_on_startup = false;
+        let all = SQLITE_MIGRATIONS.iter().map(|migration| migration.version).collect::<Vec<_>>();
+
+        assert_eq!(migrate_database(&config, true).await.expect("dry run"), Some(all));
+        prepare_database(&config).await.expect("startup must migrate");
+        assert_eq!(migrate_database(&config, true).await.expect("dry run"), Some(Vec::new()));
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use xrouter_core::CoreError;
+
+const STATS_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+const MAX_SAMPLES_PER_MODEL: usize = 2048;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Sample {
+    pub(crate) at: Instant,
+    pub(crate) success: bool,
+    pub(crate) latency_ms: u64,
+}
+
+/// Rolling record of upstream request outcomes and latencies per key, such as a public model id
+/// or a provider: samples older than `window` are dropped and at most `max_samples` are kept.
+#[derive(Debug)]
+pub(crate) struct StatsRegistry<K> {
+    window: Duration,
+    max_samples: usize,
+    samples: Mutex<HashMap<K, VecDeque<Sample>>>,
+}
+
+/// Per-model stats over the last 24 hours, shown with the model endpoints.
+pub(crate) type ModelStatsRegistry = StatsRegistry<String>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct StatsSummary {
+    pub(crate) requests: usize,
+    pub(crate) failures: usize,
+    /// Latency percentiles over successful requests; `None` when every request failed.
+    pub(crate) p50_ms: Option<u64>,
+    pub(crate) p95_ms: Option<u64>,
+}
+
+impl StatsSummary {
+    pub(crate) fn error_rate(&self) -> f64 {
+        self.failures as f64 / self.requests as f64
+    }
+}
+
+impl ModelStatsRegistry {
+    pub(crate) fn for_models() -> Self {
+        Self::new(STATS_WINDOW, MAX_SAMPLES_PER_MODEL)
+    }
+}
+
+impl<K: Eq + Hash> StatsRegistry<K> {
+    pub(crate) fn new(window: Duration, max_samples: usize) -> Self {
+        Self { window, max_samples, samples: Mutex::default() }
+    }
+
+    /// Records a finished request. Only provider failures count as errors; other errors are not
+    /// the upstream's fault and are ignored.
+    pub(crate) fn record_result<Q, T>(
+        &self,
+        key: &Q,
+        result: &Result<T, CoreError>,
+        elapsed: Duration,
+    ) where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        let success = match result {
+            Ok(_) => true,
+            Err(CoreError::Provider(_)) => false,
+            Err(_) => return,
+        };
+        let latency_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        self.record(key, Sample { at: Instant::now(), success, latency_ms });
+    }
+
+    pub(crate) fn record<Q>(&self, key: &Q, sample: Sample)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        let mut samples = self.lock();
+        let entry = samples.entry(key.to_owned()).or_default();
+        prune(entry, sample.at, self.window);
+        if entry.len() == self.max_samples {
+            entry.pop_front();
+        }
+        entry.push_back(sample);
+    }
+
+    /// Requests of the whole window; `None` when `key` served no traffic inside it.
+    pub(crate) fn snapshot<Q>(&self, key: &Q) -> Option<StatsSummary>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.summary_at(key, Instant::now(), self.window)
+    }
+
+    /// Requests of the last `length` of the window as seen at `now`.
+    pub(crate) fn summary_at<Q>(
+        &self,
+        key: &Q,
+        now: Instant,
+        length: Duration,
+    ) -> Option<StatsSummary>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut samples = self.lock();
+        let entry = samples.get_mut(key)?;
+        prune(entry, now, self.window);
+        let (mut latencies, mut requests) = (Vec::new(), 0);
+        for sample in entry.iter().filter(|sample| now.duration_since(sample.at) <= length) {
+            requests += 1;
+            if sample.success {
+                latencies.push(sample.latency_ms);
+            }
+        }
+        if requests == 0 {
+            return None;
+        }
+        latencies.sort_unstable();
+        Some(StatsSummary {
+            requests,
+            failures: requests - latencies.len(),
+            p50_ms: percentile(&latencies, 50),
+            p95_ms: percentile(&latencies, 95),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<K, VecDeque<Sample>>> {
+        self.samples.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice.
+fn percentile(sorted: &[u64], percent: usize) -> Option<u64> {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+fn prune(entry: &mut VecDeque<Sample>, now: Instant, window: Duration) {
+    while entry.front().is_some_and(|sample| now.duration_since(sample.at) > window) {
+        entry.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use xrouter_core::CoreError;
+
+    use super::{ModelStatsRegistry, STATS_WINDOW, Sample, percentile};
+
+    #[test]
+    fn stats_report_error_rate_and_success_latency_percentiles() {
+        let registry = ModelStatsRegistry::for_models();
+        for latency in [100, 200, 300, 400] {
+            registry.record_result(
+                "zai/glm-4.5",
+                &Ok::<(), CoreError>(()),
+                Duration::from_millis(latency),
+            );
+        }
+        registry.record_result(
+            "zai/glm-4.5",
+            &Err::<(), _>(CoreError::Provider("x".to_string())),
+            Duration::from_millis(5_000),
+        );
+        registry.record_result(
+            "zai/glm-4.5",
+            &Err::<(), _>(CoreError::Validation("x".to_string())),
+            Duration::from_millis(1),
+        );
+
+        let stats = registry.snapshot("zai/glm-4.5").expect("stats must exist");
+        assert_eq!(stats.requests, 5);
+        assert_eq!(stats.error_rate(), 0.2);
+        assert_eq!(stats.p50_ms, Some(200));
+        assert_eq!(stats.p95_ms, Some(400));
+        assert!(registry.snapshot("deepseek/deepseek-chat").is_none());
+    }
+
+    #[test]
+    fn samples_older_than_a_day_are_dropped() {
+        let registry = ModelStatsRegistry::for_models();
+        let start = Instant::now();
+        registry.record("zai/glm-4.5", Sample { at: start, success: false, latency_ms: 10 });
+
+        let later = start + STATS_WINDOW + Duration::from_secs(1);
+        assert!(registry.summary_at("zai/glm-4.5", later, STATS_WINDOW).is_none());
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        assert_eq!(percentile(&[], 50), None);
+        assert_eq!(percentile(&[7], 95), Some(7));
+        assert_eq!(percentile(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 95), Some(10));
+        assert_eq!(percentile(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 50), Some(5));
+    }
+}
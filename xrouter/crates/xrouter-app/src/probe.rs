@@ -0,0 +1,82 @@
+use std::time::Instant;
+
+use serde::Serialize;
+use serde_json::json;
+use tracing::info;
+use utoipa::ToSchema;
+use xrouter_contracts::ResponsesRequest;
+use xrouter_core::synthesize_model_id;
+
+use crate::AppState;
+
+const PROBE_INPUT: &str = "ping";
+
+/// Outcome of a single canary request against one provider.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProbeReport {
+    pub provider: String,
+    pub model: Option<String>,
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Sends a one-token completion to the first catalog model of `provider`.
+/// Returns `None` when the provider is unknown or disabled.
+pub async fn probe_provider(state: &AppState, provider: &str) -> Option<ProbeReport> {
+    let engine = state.engines.get(provider)?.clone();
+    let Some(model) = state.models.iter().find(|model| model.provider == provider) else {
+        return Some(ProbeReport {
+            provider: provider.to_string(),
+            model: None,
+            ok: false,
+            latency_ms: 0,
+            error: Some("provider has no models in the catalog".to_string()),
+        });
+    };
+    let model_id = synthesize_model_id(&model.provider, &model.id);
+    let request: ResponsesRequest = serde_json::from_value(json!({
+        "model": model.id,
+        "input": PROBE_INPUT,
+        "max_output_tokens": 1,
+    }))
+    .expect("probe request must deserialize");
+
+    let started = Instant::now();
+    let result = engine.execute(request).await;
+    let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    let report = ProbeReport {
+        provider: provider.to_string(),
+        model: Some(model_id),
+        ok: result.is_ok(),
+        latency_ms,
+        error: result.err().map(|error| error.to_string()),
+    };
+    info!(
+        event = "app.provider.probed",
+        provider = provider,
+        model = report.model.as_deref().unwrap_or_default(),
+        ok = report.ok,
+        latency_ms = report.latency_ms
+    );
+    Some(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::probe_provider;
+    use crate::AppBuilder;
+
+    #[tokio::test]
+    async fn probe_reports_latency_for_enabled_provider_and_skips_unknown_ones() {
+        let state = AppBuilder::new(&crate::config::AppConfig::for_tests()).build_state();
+
+        let report = probe_provider(&state, "deepseek").await.expect("deepseek must be probed");
+        assert_eq!(report.provider, "deepseek");
+        assert!(report.model.as_deref().is_some_and(|model| model.starts_with("deepseek/")));
+        assert!(report.ok, "stub provider must answer: {:?}", report.error);
+        assert!(report.error.is_none());
+
+        assert!(probe_provider(&state, "acme").await.is_none());
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+use xrouter_core::CoreError;
+
+use crate::model_stats::StatsRegistry;
+
+const HEALTH_WINDOW: Duration = Duration::from_secs(30 * 60);
+const MAX_SAMPLES_PER_PROVIDER: usize = 1024;
+
+/// Rolling per-provider record of upstream request outcomes over the last 30 minutes.
+#[derive(Debug)]
+pub(crate) struct ProviderHealthRegistry {
+    samples: StatsRegistry<String>,
+}
+
+impl Default for ProviderHealthRegistry {
+    fn default() -> Self {
+        Self { samples: StatsRegistry::new(HEALTH_WINDOW, MAX_SAMPLES_PER_PROVIDER) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ProviderHealthSnapshot {
+    pub(crate) requests: usize,
+    pub(crate) failures: usize,
+}
+
+impl ProviderHealthSnapshot {
+    pub(crate) fn uptime_percent(&self) -> Option<f64> {
+        (self.requests > 0)
+            .then(|| (self.requests - self.failures) as f64 * 100.0 / self.requests as f64)
+    }
+}
+
+impl ProviderHealthRegistry {
+    /// Records a finished request. Only provider failures count against uptime; validation
+    /// errors and client disconnects say nothing about upstream health and are ignored.
+    pub(crate) fn record_result<T>(&self, provider: &str, result: &Result<T, CoreError>) {
+        self.samples.record_result(provider, result, Duration::ZERO);
+    }
+
+    pub(crate) fn snapshot(&self, provider: &str) -> ProviderHealthSnapshot {
+        self.snapshot_at(provider, Instant::now())
+    }
+
+    fn snapshot_at(&self, provider: &str, now: Instant) -> ProviderHealthSnapshot {
+        let summary = self.samples.summary_at(provider, now, HEALTH_WINDOW);
+        ProviderHealthSnapshot {
+            requests: summary.map_or(0, |summary| summary.requests),
+            failures: summary.map_or(0, |summary| summary.failures),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use xrouter_core::CoreError;
+
+    use super::{HEALTH_WINDOW, ProviderHealthRegistry};
+    use crate::model_stats::Sample;
+
+    #[test]
+    fn uptime_counts_only_provider_failures() {
+        let registry = ProviderHealthRegistry::default();
+        registry.record_result("deepseek", &Ok::<(), CoreError>(()));
+        registry.record_result("deepseek", &Err::<(), _>(CoreError::Provider("x".to_string())));
+        registry.record_result("deepseek", &Err::<(), _>(CoreError::Validation("x".to_string())));
+
+        let snapshot = registry.snapshot("deepseek");
+        assert_eq!(snapshot.requests, 2);
+        assert_eq!(snapshot.failures, 1);
+        assert_eq!(snapshot.uptime_percent(), Some(50.0));
+        assert_eq!(registry.snapshot("zai").uptime_percent(), None);
+    }
+
+    #[test]
+    fn samples_older_than_window_are_dropped() {
+        let registry = ProviderHealthRegistry::default();
+        let start = Instant::now();
+        registry.samples.record("zai", Sample { at: start, success: false, latency_ms: 0 });
+        let later = start + Duration::from_secs(60);
+        registry.samples.record("zai", Sample { at: later, success: true, latency_ms: 0 });
+
+        let snapshot = registry.snapshot_at("zai", start + HEALTH_WINDOW + Duration::from_secs(1));
+        assert_eq!(snapshot.requests, 1);
+        assert_eq!(snapshot.failures, 0);
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use xrouter_core::CoreError;
+
+use crate::model_stats::{Sample, StatsRegistry, StatsSummary};
+
+const MAX_SAMPLES_PER_PROVIDER: usize = 8192;
+/// Consecutive provider failures after which a provider is reported `down`.
+const DOWN_AFTER_CONSECUTIVE_FAILURES: u32 = 5;
+/// Success rate over the shortest window with traffic below which a provider is reported `degraded`.
+const DEGRADED_BELOW_SUCCESS_PERCENT: f64 = 95.0;
+const WINDOWS: [(&str, Duration); 3] = [
+    ("5m", Duration::from_secs(5 * 60)),
+    ("1h", Duration::from_secs(60 * 60)),
+    ("24h", Duration::from_secs(24 * 60 * 60)),
+];
+
+#[derive(Debug, Default)]
+struct ProviderStats {
+    in_flight: usize,
+    consecutive_failures: u32,
+    last_error: Option<LastError>,
+}
+
+/// Per-provider upstream outcomes, latencies and in-flight calls behind
+/// `/admin/v1/health/providers`. Unlike the 30 minute uptime of the model endpoints it keeps a
+/// day of samples, capped per provider.
+#[derive(Debug)]
+pub(crate) struct ProviderStatsRegistry {
+    samples: StatsRegistry<String>,
+    providers: Mutex<BTreeMap<String, ProviderStats>>,
+}
+
+impl Default for ProviderStatsRegistry {
+    fn default() -> Self {
+        let (_, longest) = WINDOWS[WINDOWS.len() - 1];
+        Self {
+            samples: StatsRegistry::new(longest, MAX_SAMPLES_PER_PROVIDER),
+            providers: Mutex::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LastError {
+    pub(crate) message: String,
+    /// Unix seconds.
+    pub(crate) at: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ProviderStatus {
+    Healthy,
+    Degraded,
+    Down,
+    Idle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ProviderStatsSnapshot {
+    pub(crate) provider: String,
+    pub(crate) status: ProviderStatus,
+    pub(crate) in_flight: usize,
+    pub(crate) consecutive_failures: u32,
+    pub(crate) last_error: Option<LastError>,
+    pub(crate) windows: Vec<WindowStats>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WindowStats {
+    pub(crate) window: &'static str,
+    pub(crate) requests: usize,
+    pub(crate) failures: usize,
+    pub(crate) success_percent: Option<f64>,
+    /// Latency percentiles over successful requests.
+    pub(crate) p50_ms: Option<u64>,
+    pub(crate) p95_ms: Option<u64>,
+}
+
+/// Counts an upstream call as in flight until dropped.
+pub(crate) struct InFlightCall {
+    registry: Arc<ProviderStatsRegistry>,
+    provider: String,
+}
+
+impl Drop for InFlightCall {
+    fn drop(&mut self) {
+        if let Some(stats) = self.registry.lock().get_mut(&self.provider) {
+            stats.in_flight = stats.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+impl ProviderStatsRegistry {
+    pub(crate) fn start(self: &Arc<Self>, provider: &str) -> InFlightCall {
+        self.lock().entry(provider.to_string()).or_default().in_flight += 1;
+        InFlightCall { registry: Arc::clone(self), provider: provider.to_string() }
+    }
+
+    /// Records a finished upstream call; as with provider health, errors other than provider
+    /// failures are ignored.
+    pub(crate) fn record_result<T>(
+        &self,
+        provider: &str,
+        result: &Result<T, CoreError>,
+        elapsed: Duration,
+    ) {
+        let error = match result {
+            Ok(_) => None,
+            Err(CoreError::Provider(message)) => Some(message.clone()),
+            Err(_) => return,
+        };
+        let latency_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        self.record(provider, Instant::now(), latency_ms, error);
+    }
+
+    fn record(&self, provider: &str, at: Instant, latency_ms: u64, error: Option<String>) {
+        self.samples.record(provider, Sample { at, success: error.is_none(), latency_ms });
+        let mut providers = self.lock();
+        let stats = providers.entry(provider.to_string()).or_default();
+        match error {
+            None => stats.consecutive_failures = 0,
+            Some(message) => {
+                stats.consecutive_failures += 1;
+                stats.last_error = Some(LastError { message, at: unix_now() });
+            }
+        }
+    }
+
+    /// Every provider in `configured` plus any that served traffic, by name.
+    pub(crate) fn snapshot<'a>(
+        &self,
+        configured: impl IntoIterator<Item = &'a String>,
+    ) -> Vec<ProviderStatsSnapshot> {
+        self.snapshot_at(configured, Instant::now())
+    }
+
+    fn snapshot_at<'a>(
+        &self,
+        configured: impl IntoIterator<Item = &'a String>,
+        now: Instant,
+    ) -> Vec<ProviderStatsSnapshot> {
+        let mut providers = self.lock();
+        for provider in configured {
+            providers.entry(provider.clone()).or_default();
+        }
+        providers
+            .iter()
+            .map(|(provider, stats)| {
+                let windows = WINDOWS
+                    .iter()
+                    .map(|(window, length)| WindowStats {
+                        window,
+                        ..WindowStats::from(self.samples.summary_at(provider, now, *length))
+                    })
+                    .collect::<Vec<WindowStats>>();
+                ProviderStatsSnapshot {
+                    provider: provider.clone(),
+                    status: status(stats.consecutive_failures, &windows),
+                    in_flight: stats.in_flight,
+                    consecutive_failures: stats.consecutive_failures,
+                    last_error: stats.last_error.clone(),
+                    windows,
+                }
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, ProviderStats>> {
+        self.providers.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl From<Option<StatsSummary>> for WindowStats {
+    fn from(summary: Option<StatsSummary>) -> Self {
+        let summary = summary.unwrap_or_default();
+        WindowStats {
+            window: "",
+            requests: summary.requests,
+            failures: summary.failures,
+            success_percent: (summary.requests > 0).then(|| {
+                (summary.requests - summary.failures) as f64 * 100.0 / summary.requests as f64
+            }),
+            p50_ms: summary.p50_ms,
+            p95_ms: summary.p95_ms,
+        }
+    }
+}
+
+fn status(consecutive_failures: u32, windows: &[WindowStats]) -> ProviderStatus {
+    if consecutive_failures >= DOWN_AFTER_CONSECUTIVE_FAILURES {
+        return ProviderStatus::Down;
+    }
+    let recent = windows.iter().find_map(|window| window.success_percent);
+    match recent {
+        None => ProviderStatus::Idle,
+        Some(percent) if percent < DEGRADED_BELOW_SUCCESS_PERCENT => ProviderStatus::Degraded,
+        Some(_) => ProviderStatus::Healthy,
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use xrouter_core::CoreError;
+
+    use super::{ProviderStatsRegistry, ProviderStatus};
+
+    #[test]
+    fn windows_split_outcomes_by_age_and_report_success_latency() {
+        let registry = ProviderStatsRegistry::default();
+        let start = Instant::now();
+        registry.record("zai", start, 900, None);
+        registry.record("zai", start + Duration::from_secs(3000), 0, Some("boom".to_string()));
+        registry.record("zai", start + Duration::from_secs(3500), 100, None);
+        registry.record("zai", start + Duration::from_secs(3550), 300, None);
+
+        let configured = ["deepseek".to_string()];
+        let snapshot = registry.snapshot_at(&configured, start + Duration::from_secs(3600));
+
+        assert_eq!(snapshot[0].provider, "deepseek");
+        assert_eq!(snapshot[0].status, ProviderStatus::Idle);
+        let zai = &snapshot[1];
+        let [five_minutes, hour, day] = zai.windows.as_slice() else {
+            panic!("three windows expected: {:?}", zai.windows);
+        };
+        assert_eq!((five_minutes.window, five_minutes.requests), ("5m", 2));
+        assert_eq!(five_minutes.success_percent, Some(100.0));
+        assert_eq!((five_minutes.p50_ms, five_minutes.p95_ms), (Some(100), Some(300)));
+        assert_eq!((hour.requests, hour.failures), (4, 1));
+        assert_eq!(hour.success_percent, Some(75.0));
+        assert_eq!(hour.p95_ms, Some(900));
+        assert_eq!(day.requests, 4);
+        assert_eq!(zai.status, ProviderStatus::Healthy);
+        assert_eq!(zai.last_error.as_ref().map(|error| error.message.as_str()), Some("boom"));
+    }
+
+    #[test]
+    fn consecutive_failures_mark_a_provider_down_until_it_succeeds() {
+        let registry = Arc::new(ProviderStatsRegistry::default());
+        let failure = Err::<(), _>(CoreError::Provider("503".to_string()));
+        for _ in 0..5 {
+            registry.record_result("zai", &failure, Duration::ZERO);
+        }
+        registry.record_result(
+            "zai",
+            &Err::<(), _>(CoreError::Validation("x".to_string())),
+            Duration::ZERO,
+        );
+        let in_flight = registry.start("zai");
+
+        let snapshot = registry.snapshot(&[]);
+        assert_eq!(snapshot[0].status, ProviderStatus::Down);
+        assert_eq!(snapshot[0].consecutive_failures, 5);
+        assert_eq!(snapshot[0].in_flight, 1);
+
+        drop(in_flight);
+        registry.record_result("zai", &Ok::<(), CoreError>(()), Duration::ZERO);
+        let snapshot = registry.snapshot(&[]);
+        assert_eq!(snapshot[0].status, ProviderStatus::Degraded);
+        assert_eq!(snapshot[0].in_flight, 0);
+    }
+}
@@ -0,0 +1,378 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use tokio::sync::watch;
+use tracing::{debug, info};
+use xrouter_contracts::ResponseEvent;
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome, ResponseEventSink,
+};
+
+/// Engine key and model namespace of raced models: `race/<alias>`.
+pub(crate) const RACE_ENGINE: &str = "race";
+
+pub(crate) struct RaceLeg {
+    provider: String,
+    model: String,
+    client: Arc<dyn ProviderClient>,
+}
+
+impl RaceLeg {
+    pub(crate) fn new(provider: &str, model: &str, client: Arc<dyn ProviderClient>) -> Self {
+        Self { provider: provider.to_string(), model: model.to_string(), client }
+    }
+}
+
+/// Sends each request to every leg of its alias at once. The first leg to produce output wins;
+/// the others are cancelled, so only the winner's outcome and usage are recorded.
+pub(crate) struct RacingProviderClient {
+    races: HashMap<String, Vec<RaceLeg>>,
+}
+
+impl RacingProviderClient {
+    pub(crate) fn new(races: HashMap<String, Vec<RaceLeg>>) -> Self {
+        Self { races }
+    }
+
+    fn legs(&self, request: &ProviderGenerateRequest<'_>) -> Result<&[RaceLeg], CoreError> {
+        if request.auth_bearer.is_some() {
+            return Err(CoreError::Validation(
+                "race models do not accept a caller-supplied provider key".to_string(),
+            ));
+        }
+        self.races.get(request.model).map(Vec::as_slice).ok_or_else(|| {
+            CoreError::Validation(format!("unknown race model: {RACE_ENGINE}/{}", request.model))
+        })
+    }
+}
+
+/// Index of the leg that produced output first, if any has.
+struct Finish {
+    winner: watch::Sender<Option<usize>>,
+}
+
+impl Default for Finish {
+    fn default() -> Self {
+        Self { winner: watch::Sender::new(None) }
+    }
+}
+
+impl Finish {
+    /// Whether `leg` is the winner, making it the winner if nobody is yet.
+    fn claim(&self, leg: usize) -> bool {
+        let mut claimed = false;
+        self.winner.send_if_modified(|winner| {
+            let first = winner.is_none();
+            claimed = *winner.get_or_insert(leg) == leg;
+            first
+        });
+        claimed
+    }
+
+    fn is_winner(&self, leg: usize) -> bool {
+        *self.winner.borrow() == Some(leg)
+    }
+
+    fn is_loser(&self, leg: usize) -> bool {
+        self.winner.borrow().is_some_and(|winner| winner != leg)
+    }
+
+    /// Resolves once another leg has won.
+    async fn lost(&self, leg: usize) {
+        let mut winner = self.winner.subscribe();
+        if winner.wait_for(|winner| winner.is_some_and(|winner| winner != leg)).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Forwards the events of one leg to the caller once that leg has won.
+struct LegSink<'a> {
+    leg: usize,
+    finish: &'a Finish,
+    sender: &'a dyn ResponseEventSink,
+}
+
+#[async_trait]
+impl ResponseEventSink for LegSink<'_> {
+    async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+        let forward = match &event {
+            Ok(_) => self.finish.claim(self.leg),
+            Err(_) => self.finish.is_winner(self.leg),
+        };
+        if forward {
+            self.sender.send(event).await;
+        }
+    }
+
+    fn output_closed(&self) -> bool {
+        self.finish.is_loser(self.leg) || self.sender.output_closed()
+    }
+
+    fn retained_output_limit(&self) -> Option<usize> {
+        self.sender.retained_output_limit()
+    }
+}
+
+fn won(alias: &str, leg: &RaceLeg, legs: usize) {
+    info!(
+        event = "provider.race.won",
+        race_model = %alias,
+        provider = %leg.provider,
+        provider_model = %leg.model,
+        legs = legs
+    );
+}
+
+fn leg_failed(leg: &RaceLeg, error: &CoreError) {
+    debug!(event = "provider.race.leg_failed", provider = %leg.provider, error = %error);
+}
+
+fn leg_cancelled(leg: &RaceLeg) {
+    debug!(event = "provider.race.leg_cancelled", provider = %leg.provider);
+}
+
+#[async_trait]
+impl ProviderClient for RacingProviderClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let legs = self.legs(&request)?;
+        let mut pending = legs
+            .iter()
+            .enumerate()
+            .map(|(index, leg)| async move {
+                let result = leg
+                    .client
+                    .generate(ProviderGenerateRequest { model: &leg.model, ..request })
+                    .await;
+                (index, result)
+            })
+            .collect::<FuturesUnordered<_>>();
+        let mut last_error = None;
+        while let Some((index, result)) = pending.next().await {
+            match result {
+                Ok(outcome) => {
+                    won(request.model, &legs[index], legs.len());
+                    return Ok(outcome);
+                }
+                Err(error) => {
+                    leg_failed(&legs[index], &error);
+                    last_error = Some(error);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| CoreError::Provider("race has no legs".to_string())))
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let Some(sender) = request.sender else {
+            return self.generate(request.request).await;
+        };
+        let legs = self.legs(&request.request)?;
+        let finish = Finish::default();
+        let sinks =
+            (0..legs.len()).map(|leg| LegSink { leg, finish: &finish, sender }).collect::<Vec<_>>();
+        // Losing legs are dropped as soon as another leg wins, which closes their upstream calls.
+        let finish = &finish;
+        let mut pending = legs
+            .iter()
+            .zip(&sinks)
+            .enumerate()
+            .map(|(index, (leg, sink))| async move {
+                let generate = leg.client.generate_stream(ProviderGenerateStreamRequest {
+                    request_id: request.request_id,
+                    request: ProviderGenerateRequest { model: &leg.model, ..request.request },
+                    sender: Some(sink),
+                });
+                tokio::select! {
+                    result = generate => (index, Some(result)),
+                    () = finish.lost(index) => (index, None),
+                }
+            })
+            .collect::<FuturesUnordered<_>>();
+        let mut last_error = None;
+        while let Some((index, result)) = pending.next().await {
+            let Some(result) = result else {
+                leg_cancelled(&legs[index]);
+                continue;
+            };
+            match result {
+                // A leg may finish without streaming anything; finishing first then wins too.
+                Ok(outcome) if finish.claim(index) => {
+                    won(request.request.model, &legs[index], legs.len());
+                    return Ok(outcome);
+                }
+                Ok(_) => {}
+                Err(error) if finish.is_winner(index) => return Err(error),
+                Err(error) => {
+                    leg_failed(&legs[index], &error);
+                    last_error = Some(error);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| CoreError::Provider("race has no legs".to_string())))
+    }
+
+    fn supports_required_tool_choice(&self) -> bool {
+        self.races.values().flatten().all(|leg| leg.client.supports_required_tool_choice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use tokio::sync::Notify;
+    use xrouter_contracts::{ResponseEvent, ResponsesInput};
+    use xrouter_core::{
+        CoreError, ExecutionControls, ProviderClient, ProviderGenerateRequest,
+        ProviderGenerateStreamRequest, ProviderOutcome, ResponseEventSink,
+    };
+
+    use super::{RaceLeg, RacingProviderClient};
+
+    fn outcome(text: &str) -> ProviderOutcome {
+        ProviderOutcome {
+            chunks: vec![text.to_string()],
+            output_tokens: 1,
+            reasoning: None,
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls: None,
+            annotations: None,
+            emitted_live: true,
+            finish_reason: None,
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
+        }
+    }
+
+    /// Streams one token, then finishes only once the other leg has been dropped.
+    struct FastLeg {
+        slow_dropped: Arc<Notify>,
+    }
+
+    #[async_trait]
+    impl ProviderClient for FastLeg {
+        async fn generate(
+            &self,
+            _request: ProviderGenerateRequest<'_>,
+        ) -> Result<ProviderOutcome, CoreError> {
+            Ok(outcome("fast"))
+        }
+
+        async fn generate_stream(
+            &self,
+            request: ProviderGenerateStreamRequest<'_>,
+        ) -> Result<ProviderOutcome, CoreError> {
+            let sender = request.sender.expect("race must pass a sink");
+            sender
+                .send(Ok(ResponseEvent::OutputTextDelta {
+                    id: "msg_1".to_string(),
+                    delta: "fast".to_string(),
+                }))
+                .await;
+            tokio::time::timeout(Duration::from_secs(1), self.slow_dropped.notified())
+                .await
+                .map_err(|_| CoreError::Provider("losing leg kept running".to_string()))?;
+            Ok(outcome("fast"))
+        }
+    }
+
+    /// Never answers; signals when the race drops it.
+    struct SlowLeg {
+        dropped: Arc<Notify>,
+    }
+
+    struct DropSignal(Arc<Notify>);
+
+    impl Drop for DropSignal {
+        fn drop(&mut self) {
+            self.0.notify_one();
+        }
+    }
+
+    #[async_trait]
+    impl ProviderClient for SlowLeg {
+        async fn generate(
+            &self,
+            _request: ProviderGenerateRequest<'_>,
+        ) -> Result<ProviderOutcome, CoreError> {
+            let _signal = DropSignal(Arc::clone(&self.dropped));
+            std::future::pending().await
+        }
+
+        async fn generate_stream(
+            &self,
+            _request: ProviderGenerateStreamRequest<'_>,
+        ) -> Result<ProviderOutcome, CoreError> {
+            let _signal = DropSignal(Arc::clone(&self.dropped));
+            std::future::pending().await
+        }
+    }
+
+    #[derive(Default)]
+    struct CollectingSink {
+        events: Mutex<Vec<ResponseEvent>>,
+    }
+
+    #[async_trait]
+    impl ResponseEventSink for CollectingSink {
+        async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+            if let Ok(event) = event {
+                self.events.lock().expect("lock must succeed").push(event);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn losing_legs_are_dropped_once_a_leg_streams_its_first_token() {
+        let slow_dropped = Arc::new(Notify::new());
+        let legs = vec![
+            RaceLeg::new("slow", "m", Arc::new(SlowLeg { dropped: Arc::clone(&slow_dropped) })),
+            RaceLeg::new("fast", "m", Arc::new(FastLeg { slow_dropped })),
+        ];
+        let client = RacingProviderClient::new(HashMap::from([("fastest".to_string(), legs)]));
+        let input = ResponsesInput::Text("hello".to_string());
+        let sink = CollectingSink::default();
+
+        let result = client
+            .generate_stream(ProviderGenerateStreamRequest {
+                request_id: "req_1",
+                request: ProviderGenerateRequest {
+                    model: "fastest",
+                    instructions: None,
+                    input: &input,
+                    reasoning: None,
+                    tools: None,
+                    tool_choice: None,
+                    include: None,
+                    text: None,
+                    modalities: None,
+                    auth_bearer: None,
+                    forward_headers: &[],
+                    extra: None,
+                    controls: ExecutionControls::default(),
+                },
+                sender: Some(&sink),
+            })
+            .await;
+
+        assert_eq!(result.expect("fast leg must win").chunks, vec!["fast".to_string()]);
+        assert_eq!(sink.events.lock().expect("lock must succeed").len(), 1);
+    }
+}
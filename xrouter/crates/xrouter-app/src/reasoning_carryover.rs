@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use serde_json::json;
+use tracing::warn;
+use xrouter_contracts::{
+    ResponseInputContent, ResponseInputItem, ResponseOutputItem, ResponsesInput, ResponsesRequest,
+};
+
+use crate::response_store::{InMemoryResponseStore, ResponseStore};
+
+/// Encrypted reasoning items of recent responses, keyed by response id. Upstreams are stateless,
+/// so a `previous_response_id` follow-up gets them re-sent to keep the reasoning context.
+/// Store failures are logged and treated as a miss.
+pub(crate) struct ReasoningCarryOver {
+    store: Arc<dyn ResponseStore>,
+}
+
+impl Default for ReasoningCarryOver {
+    fn default() -> Self {
+        Self::new(Arc::new(InMemoryResponseStore::default()))
+    }
+}
+
+impl ReasoningCarryOver {
+    pub(crate) fn new(store: Arc<dyn ResponseStore>) -> Self {
+        Self { store }
+    }
+
+    /// Keeps the encrypted reasoning of a finished response; responses without any are skipped.
+    pub(crate) async fn remember(&self, response_id: &str, output: &[ResponseOutputItem]) {
+        let items = output
+            .iter()
+            .filter_map(|item| match item {
+                ResponseOutputItem::Reasoning {
+                    summary,
+                    encrypted_content: Some(encrypted_content),
+                    ..
+                } => Some(ResponseInputItem {
+                    kind: Some("reasoning".to_string()),
+                    summary: Some(
+                        summary
+                            .iter()
+                            .map(|part| json!({"type": "summary_text", "text": part.text}))
+                            .collect(),
+                    ),
+                    encrypted_content: Some(encrypted_content.clone()),
+                    ..Default::default()
+                }),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if items.is_empty() {
+            return;
+        }
+        if let Err(error) = self.store.put(response_id, &items).await {
+            warn!(
+                event = "response_store.write_failed",
+                store = self.store.name(),
+                response_id = response_id,
+                error = %error
+            );
+        }
+    }
+
+    /// Prepends the reasoning remembered for `previous_response_id` to the request input.
+    /// Requests that already carry encrypted reasoning are left alone. Returns the number of
+    /// restored items.
+    pub(crate) async fn restore(&self, request: &mut ResponsesRequest) -> usize {
+        let Some(previous_response_id) = request.previous_response_id.as_deref() else {
+            return 0;
+        };
+        if let ResponsesInput::Items(items) = &request.input
+            && items.iter().any(|item| item.encrypted_content.is_some())
+        {
+            return 0;
+        }
+        let mut restored = match self.store.get(previous_response_id).await {
+            Ok(Some(restored)) => restored,
+            Ok(None) => return 0,
+            Err(error) => {
+                warn!(
+                    event = "response_store.read_failed",
+                    store = self.store.name(),
+                    response_id = previous_response_id,
+                    error = %error
+                );
+                return 0;
+            }
+        };
+        let count = restored.len();
+        match std::mem::replace(&mut request.input, ResponsesInput::Items(Vec::new())) {
+            ResponsesInput::Text(text) => restored.push(ResponseInputItem {
+                kind: Some("message".to_string()),
+                role: Some("user".to_string()),
+                content: Some(ResponseInputContent::Text(text)),
+                ..Default::default()
+            }),
+            ResponsesInput::Items(items) => restored.extend(items),
+        }
+        request.input = ResponsesInput::Items(restored);
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use xrouter_contracts::{
+        ResponseOutputItem, ResponseReasoningSummary, ResponsesInput, ResponsesRequest,
+    };
+
+    use super::ReasoningCarryOver;
+
+    fn follow_up(input: serde_json::Value) -> ResponsesRequest {
+        serde_json::from_value(json!({
+            "model": "yandexgpt",
+            "previous_response_id": "resp_1",
+            "input": input,
+        }))
+        .expect("request must deserialize")
+    }
+
+    #[tokio::test]
+    async fn follow_up_gets_remembered_reasoning_prepended_unless_it_carries_its_own() {
+        let carry_over = ReasoningCarryOver::default();
+        carry_over
+            .remember(
+                "resp_1",
+                &[ResponseOutputItem::Reasoning {
+                    id: "rs_0".to_string(),
+                    summary: vec![ResponseReasoningSummary { text: "thought".to_string() }],
+                    content: Vec::new(),
+                    encrypted_content: Some("gAAA-opaque".to_string()),
+                }],
+            )
+            .await;
+
+        let mut request = follow_up(json!("next question"));
+        assert_eq!(carry_over.restore(&mut request).await, 1);
+        let ResponsesInput::Items(items) = &request.input else {
+            panic!("expected item input");
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].kind.as_deref(), Some("reasoning"));
+        assert_eq!(items[0].encrypted_content.as_deref(), Some("gAAA-opaque"));
+        assert_eq!(items[1].role.as_deref(), Some("user"));
+
+        let mut resent =
+            follow_up(json!([{"type": "reasoning", "summary": [], "encrypted_content": "mine"}]));
+        assert_eq!(carry_over.restore(&mut resent).await, 0);
+
+        let mut unknown = follow_up(json!("next question"));
+        unknown.previous_response_id = Some("resp_unknown".to_string());
+        assert_eq!(carry_over.restore(&mut unknown).await, 0);
+        assert_eq!(unknown.input, ResponsesInput::Text("next question".to_string()));
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use reqwest::{Url, header::CONTENT_TYPE};
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::mpsc,
+};
+use tracing::{debug, warn};
+
+const MAX_BATCH: usize = 100;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+const KAFKA_REST_CONTENT_TYPE: &str = "application/vnd.kafka.json.v2+json";
+
+/// Where a [`RecordQueue`] delivers its JSON records.
+pub(crate) enum RecordSink {
+    /// Batches POSTed to the URL as a JSON array.
+    Webhook { url: String },
+    /// Batches produced to the topic through a Kafka REST proxy, keyed by `key_field`.
+    KafkaRest { url: String, topic: String, key_field: &'static str },
+    /// One NATS message per record on the subject.
+    Nats { url: String, subject: String },
+}
+
+impl RecordSink {
+    fn backend(&self) -> &'static str {
+        match self {
+            Self::Webhook { .. } => "webhook",
+            Self::KafkaRest { .. } => "kafka",
+            Self::Nats { .. } => "nats",
+        }
+    }
+}
+
+/// Bounded queue drained by one background task. A full queue drops new records and a failed
+/// delivery drops its batch, so publishers never wait on the sink.
+pub(crate) struct RecordQueue {
+    name: &'static str,
+    sender: mpsc::Sender<Value>,
+    /// Taken by the first publish, which starts the delivery task on the serving runtime.
+    worker: Mutex<Option<(mpsc::Receiver<Value>, Delivery)>>,
+    dropped: AtomicU64,
+}
+
+impl RecordQueue {
+    pub(crate) fn new(
+        name: &'static str,
+        sink: RecordSink,
+        buffer: usize,
+    ) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder().timeout(DELIVERY_TIMEOUT).build()?;
+        let (sender, receiver) = mpsc::channel(buffer);
+        let delivery = Delivery { name, client, sink, nats: None };
+        Ok(Self { name, sender, worker: Mutex::new(Some((receiver, delivery))), dropped: 0.into() })
+    }
+
+    pub(crate) fn publish(&self, record: Value) {
+        self.start_worker();
+        if self.sender.try_send(record).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped == 1 || dropped.is_multiple_of(1000) {
+                warn!(
+                    event = "record_queue.record_dropped",
+                    queue = self.name,
+                    dropped_total = dropped
+                );
+            }
+        }
+    }
+
+    fn start_worker(&self) {
+        let Some((receiver, delivery)) =
+            self.worker.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take()
+        else {
+            return;
+        };
+        tokio::spawn(delivery.run(receiver));
+    }
+}
+
+struct Delivery {
+    name: &'static str,
+    client: reqwest::Client,
+    sink: RecordSink,
+    nats: Option<NatsConnection>,
+}
+
+impl Delivery {
+    async fn run(mut self, mut receiver: mpsc::Receiver<Value>) {
+        let mut batch = Vec::with_capacity(MAX_BATCH);
+        while receiver.recv_many(&mut batch, MAX_BATCH).await > 0 {
+            match self.send(&batch).await {
+                Ok(()) => debug!(
+                    event = "record_queue.delivered",
+                    queue = self.name,
+                    records = batch.len()
+                ),
+                Err(error) => warn!(
+                    event = "record_queue.delivery_failed",
+                    queue = self.name,
+                    backend = self.sink.backend(),
+                    records = batch.len(),
+                    error = %error
+                ),
+            }
+            batch.clear();
+        }
+    }
+
+    async fn send(&mut self, records: &[Value]) -> Result<(), String> {
+        let request = match &self.sink {
+            RecordSink::Webhook { url } => self.client.post(url).json(records),
+            RecordSink::KafkaRest { url, topic, key_field } => self
+                .client
+                .post(format!("{url}/topics/{topic}"))
+                .header(CONTENT_TYPE, KAFKA_REST_CONTENT_TYPE)
+                .body(kafka_records(records, key_field).to_string()),
+            RecordSink::Nats { url, subject } => {
+                let (url, subject) = (url.clone(), subject.clone());
+                return self.send_nats(&url, &subject, records).await;
+            }
+        };
+        let response = request.send().await.map_err(|error| error.to_string())?;
+        response.error_for_status().map(|_| ()).map_err(|error| error.to_string())
+    }
+
+    /// Publishes over the kept connection; a connection the server dropped while idle is
+    /// reopened once before the batch counts as failed.
+    async fn send_nats(
+        &mut self,
+        url: &str,
+        subject: &str,
+        records: &[Value],
+    ) -> Result<(), String> {
+        let reused = self.nats.is_some();
+        let result = self.publish_nats(url, subject, records).await;
+        if result.is_err() {
+            self.nats = None;
+            if reused {
+                return self
+                    .publish_nats(url, subject, records)
+                    .await
+                    .inspect_err(|_| self.nats = None);
+            }
+        }
+        result
+    }
+
+    async fn publish_nats(
+        &mut self,
+        url: &str,
+        subject: &str,
+        records: &[Value],
+    ) -> Result<(), String> {
+        let connection = match &mut self.nats {
+            Some(connection) => connection,
+            None => self.nats.insert(NatsConnection::connect(url).await?),
+        };
+        tokio::time::timeout(DELIVERY_TIMEOUT, connection.publish(subject, records))
+            .await
+            .map_err(|_| "nats publish timed out".to_string())?
+    }
+}
+
+fn kafka_records(records: &[Value], key_field: &str) -> Value {
+    json!({
+        "records": records
+            .iter()
+            .map(|record| json!({"key": record[key_field], "value": record}))
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Plain-TCP NATS client that only publishes: `CONNECT` once, then `PUB` frames followed by a
+/// `PING` whose `PONG` confirms the server accepted them.
+struct NatsConnection {
+    stream: BufReader<TcpStream>,
+}
+
+impl NatsConnection {
+    async fn connect(url: &str) -> Result<Self, String> {
+        let parsed = Url::parse(url).map_err(|error| format!("invalid nats url: {error}"))?;
+        let host = parsed.host_str().ok_or("nats url has no host")?;
+        let port = parsed.port().unwrap_or(4222);
+        let connect = TcpStream::connect((host, port));
+        let stream = tokio::time::timeout(DELIVERY_TIMEOUT, connect)
+            .await
+            .map_err(|_| "nats connect timed out".to_string())?
+            .map_err(|error| format!("nats connect failed: {error}"))?;
+        let mut connection = Self { stream: BufReader::new(stream) };
+        let info = connection.read_line().await?;
+        if !info.starts_with("INFO ") {
+            return Err(format!("unexpected nats greeting: {info}"));
+        }
+        if info.contains("\"tls_required\":true") {
+            return Err("nats server requires TLS, which is not supported".to_string());
+        }
+        let mut options =
+            json!({"verbose": false, "pedantic": false, "name": "xrouter", "lang": "rust"});
+        match (parsed.username(), parsed.password()) {
+            ("", _) => {}
+            (token, None) => options["auth_token"] = json!(token),
+            (user, Some(pass)) => {
+                options["user"] = json!(user);
+                options["pass"] = json!(pass);
+            }
+        }
+        connection.write(format!("CONNECT {options}\r\nPING\r\n").as_bytes()).await?;
+        connection.await_pong().await?;
+        Ok(connection)
+    }
+
+    async fn publish(&mut self, subject: &str, records: &[Value]) -> Result<(), String> {
+        let mut frames = Vec::new();
+        for record in records {
+            let payload = record.to_string();
+            frames.extend_from_slice(format!("PUB {subject} {}\r\n", payload.len()).as_bytes());
+            frames.extend_from_slice(payload.as_bytes());
+            frames.extend_from_slice(b"\r\n");
+        }
+        frames.extend_from_slice(b"PING\r\n");
+        self.write(&frames).await?;
+        self.await_pong().await
+    }
+
+    async fn await_pong(&mut self) -> Result<(), String> {
+        loop {
+            let line = self.read_line().await?;
+            match line.as_str() {
+                "PONG" => return Ok(()),
+                "PING" => self.write(b"PONG\r\n").await?,
+                "+OK" => {}
+                _ if line.starts_with("INFO ") => {}
+                _ => return Err(format!("nats server replied: {line}")),
+            }
+        }
+    }
+
+    async fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        let read = tokio::time::timeout(DELIVERY_TIMEOUT, self.stream.read_line(&mut line))
+            .await
+            .map_err(|_| "nats read timed out".to_string())?
+            .map_err(|error| format!("nats read failed: {error}"))?;
+        if read == 0 {
+            return Err("nats server closed the connection".to_string());
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let stream = self.stream.get_mut();
+        stream.write_all(bytes).await.map_err(|error| format!("nats write failed: {error}"))?;
+        stream.flush().await.map_err(|error| format!("nats write failed: {error}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{Json, Router, extract::State, routing::post};
+    use serde_json::{Value, json};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    use super::{RecordQueue, RecordSink, kafka_records};
+
+    async fn next<T>(received: &mut mpsc::UnboundedReceiver<T>) -> T {
+        tokio::time::timeout(Duration::from_secs(5), received.recv())
+            .await
+            .expect("delivery must arrive")
+            .expect("server must stay up")
+    }
+
+    #[tokio::test]
+    async fn webhook_sink_receives_batches_as_json_arrays() {
+        let (sender, mut received) = mpsc::unbounded_channel();
+        let app =
+            Router::new().route(
+                "/hook",
+                post(
+                    |State(sender): State<mpsc::UnboundedSender<Value>>,
+                     Json(body): Json<Value>| async move {
+                        let _ = sender.send(body);
+                    },
+                )
+                .with_state(sender),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("listener must bind");
+        let url = format!("http://{}/hook", listener.local_addr().expect("address must resolve"));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let queue =
+            RecordQueue::new("test", RecordSink::Webhook { url }, 16).expect("queue must build");
+        queue.publish(json!({"id": 1}));
+        assert_eq!(next(&mut received).await, json!([{"id": 1}]));
+    }
+
+    #[tokio::test]
+    async fn nats_sink_connects_with_credentials_and_publishes_each_record() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("listener must bind");
+        let address = listener.local_addr().expect("address must resolve");
+        let (sender, mut received) = mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.expect("client must connect");
+            let mut socket = BufReader::new(socket);
+            socket.get_mut().write_all(b"INFO {\"server_id\":\"test\"}\r\n").await.expect("info");
+            loop {
+                let mut line = String::new();
+                if socket.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    return;
+                }
+                let line = line.trim_end().to_string();
+                if line == "PING" {
+                    socket.get_mut().write_all(b"PONG\r\n").await.expect("pong");
+                } else if let Some(header) = line.strip_prefix("PUB ") {
+                    let length =
+                        header.rsplit(' ').next().and_then(|it| it.parse().ok()).unwrap_or(0);
+                    let mut payload = vec![0; length + 2];
+                    socket.read_exact(&mut payload).await.expect("payload");
+                    let _ = sender
+                        .send(format!("{header}|{}", String::from_utf8_lossy(&payload[..length])));
+                } else {
+                    let _ = sender.send(line);
+                }
+            }
+        });
+
+        let sink = RecordSink::Nats {
+            url: format!("nats://alice:secret@{address}"),
+            subject: "xrouter.events".to_string(),
+        };
+        let queue = RecordQueue::new("test", sink, 16).expect("queue must build");
+        queue.publish(json!({"type": "request.received"}));
+        let connect = next(&mut received).await;
+        assert!(connect.starts_with("CONNECT "), "{connect}");
+        assert!(connect.contains("\"user\":\"alice\"") && connect.contains("\"pass\":\"secret\""));
+        let published = next(&mut received).await;
+        assert_eq!(published, r#"xrouter.events 27|{"type":"request.received"}"#);
+    }
+
+    #[tokio::test]
+    async fn full_queues_drop_records_instead_of_waiting() {
+        let sink = RecordSink::Webhook { url: "http://127.0.0.1:1/hook".to_string() };
+        let queue = RecordQueue::new("test", sink, 1).expect("queue must build");
+        for id in 0..10 {
+            queue.publish(json!({"id": id}));
+        }
+        assert!(queue.dropped.load(std::sync::atomic::Ordering::Relaxed) > 0);
+    }
+
+    #[test]
+    fn kafka_records_are_keyed_by_the_configured_field() {
+        let records =
+            kafka_records(&[json!({"response_id": "resp_1", "type": "x"})], "response_id");
+        assert_eq!(records["records"][0]["key"], "resp_1");
+        assert_eq!(records["records"][0]["value"]["type"], "x");
+    }
+}
@@ -0,0 +1,164 @@
+use axum::http::HeaderMap;
+use tracing::info;
+use xrouter_contracts::{RequestPriority, ResponsesRequest};
+use xrouter_core::{CoreError, ExecutionControls};
+
+use crate::{
+    AppState,
+    api_keys::ApiKeyRecord,
+    config::TruncationStrategy,
+    header_overrides::{
+        MODEL_HEADER, PROVIDER_HEADER, REASONING_EFFORT_HEADER, apply_header_overrides,
+    },
+    http::hops::downstream_router_headers,
+    scheduling::{PRIORITY_HEADER, resolve_priority},
+    tenancy::TenantPrincipal,
+    truncation::{TRUNCATION_HEADER, resolve_truncation},
+    upstream_limits::{RETRIES_HEADER, TIMEOUT_HEADER, resolve_upstream_limits},
+};
+
+/// What an inference request asks of xrouter through its `x-xrouter-*` headers, resolved once
+/// the caller is authenticated.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestContext {
+    pub(crate) controls: ExecutionControls,
+    pub(crate) truncation: TruncationStrategy,
+    /// Sent with every provider leg; each client keeps the ones meant for it.
+    pub(crate) forward_headers: Vec<(String, String)>,
+}
+
+impl RequestContext {
+    /// Applies the override headers to `request` and reads the scheduling, upstream and
+    /// truncation headers.
+    pub(crate) fn resolve(
+        state: &AppState,
+        principal: Option<&TenantPrincipal>,
+        route: &str,
+        headers: &HeaderMap,
+        request: &mut ResponsesRequest,
+    ) -> Result<Self, CoreError> {
+        override_from_headers(state, route, headers, request)?;
+        let key = principal.and_then(|principal| principal.key.as_ref());
+        let priority = request_priority(state, key, headers)?;
+        let upstream = resolve_upstream_limits(&state.upstream_limits, headers)?;
+        let truncation = resolve_truncation(state, key, headers)?;
+        Ok(Self {
+            controls: ExecutionControls { priority, upstream },
+            truncation,
+            forward_headers: forward_headers(headers),
+        })
+    }
+}
+
+/// App attribution for OpenRouter, and the next hop count and trace context for a downstream
+/// xrouter. They do not depend on the routed provider: race and bandit legs may go elsewhere.
+fn forward_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    const OPENROUTER_FORWARD_HEADERS: [&str; 4] =
+        ["HTTP-Referer", "X-OpenRouter-Title", "X-Title", "X-OpenRouter-Categories"];
+
+    OPENROUTER_FORWARD_HEADERS
+        .iter()
+        .filter_map(|name| {
+            headers
+                .get(*name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| ((*name).to_string(), value.to_string()))
+        })
+        .chain(downstream_router_headers(headers))
+        .collect()
+}
+
+pub(crate) fn override_from_headers(
+    state: &AppState,
+    route: &str,
+    headers: &HeaderMap,
+    request: &mut ResponsesRequest,
+) -> Result<(), CoreError> {
+    let applied = apply_header_overrides(state, headers, request).inspect_err(|err| {
+        info!(event = "http.request.invalid_header_override", route = route, error = %err);
+    })?;
+    if !applied.is_empty() {
+        info!(
+            event = "http.request.header_overrides",
+            route = route,
+            model = %request.model,
+            headers = ?applied
+        );
+    }
+    Ok(())
+}
+
+/// Scheduling class of the request; only looked up while priority scheduling is on.
+fn request_priority(
+    state: &AppState,
+    key: Option<&ApiKeyRecord>,
+    headers: &HeaderMap,
+) -> Result<RequestPriority, CoreError> {
+    if state.scheduler.is_none() {
+        return Ok(RequestPriority::default());
+    }
+    resolve_priority(key, headers)
+}
+
+/// The headers [`RequestContext::resolve`] would act on under the current config, with their
+/// raw values. Requests that differ in them get different responses, so they are neither
+/// coalesced nor replayed for each other.
+pub(crate) fn effective_headers<'a>(
+    state: &AppState,
+    headers: &'a HeaderMap,
+) -> Vec<(&'static str, &'a [u8])> {
+    let overrides = state.header_overrides;
+    [
+        (MODEL_HEADER, overrides.model),
+        (PROVIDER_HEADER, overrides.provider),
+        (REASONING_EFFORT_HEADER, overrides.reasoning_effort),
+        (TIMEOUT_HEADER, true),
+        (RETRIES_HEADER, true),
+        (PRIORITY_HEADER, state.scheduler.is_some()),
+        (TRUNCATION_HEADER, true),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .filter_map(|(name, _)| Some((name, headers.get(name)?.as_bytes())))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+    use crate::{
+        AppBuilder,
+        config::{AppConfig, HeaderOverrides},
+    };
+
+    #[test]
+    fn forward_headers_carry_attribution_and_the_next_hop_for_every_provider() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-title", HeaderValue::from_static("Example App"));
+        headers.insert("x-xrouter-hop", HeaderValue::from_static("1"));
+        headers.insert("x-unrelated", HeaderValue::from_static("1"));
+
+        let forwarded = forward_headers(&headers);
+        assert!(forwarded.contains(&("X-Title".to_string(), "Example App".to_string())));
+        assert!(forwarded.contains(&("x-xrouter-hop".to_string(), "2".to_string())));
+        assert!(!forwarded.iter().any(|(name, _)| name == "x-unrelated"));
+    }
+
+    #[test]
+    fn effective_headers_skip_overrides_the_config_does_not_enable() {
+        let mut state = AppBuilder::new(&AppConfig::for_tests()).build_state();
+        let mut headers = HeaderMap::new();
+        headers.insert(MODEL_HEADER, HeaderValue::from_static("deepseek/deepseek-chat"));
+        headers.insert(TIMEOUT_HEADER, HeaderValue::from_static("1500"));
+        headers.insert("x-unrelated", HeaderValue::from_static("1"));
+        assert_eq!(effective_headers(&state, &headers), vec![(TIMEOUT_HEADER, &b"1500"[..])]);
+
+        state.header_overrides = HeaderOverrides { model: true, ..HeaderOverrides::default() };
+        assert_eq!(
+            effective_headers(&state, &headers),
+            vec![(MODEL_HEADER, &b"deepseek/deepseek-chat"[..]), (TIMEOUT_HEADER, &b"1500"[..])]
+        );
+    }
+}
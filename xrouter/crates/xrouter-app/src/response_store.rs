@@ -0,0 +1,426 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use rusqlite::{OptionalExtension, params};
+use tokio::sync::{Mutex as AsyncMutex, OnceCell};
+use tracing::warn;
+use xrouter_contracts::ResponseInputItem;
+
+use crate::{
+    api_keys::unix_now,
+    coordination::{REDIS_TIMEOUT, connect_redis},
+    storage::SqliteDatabase,
+};
+
+const MAX_IN_MEMORY_RESPONSES: usize = 4096;
+const POSTGRES_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ResponseStoreError {
+    #[error("response store is unavailable: {0}")]
+    Backend(String),
+    #[error("response store data is invalid: {0}")]
+    Corrupt(String),
+}
+
+/// State a follow-up `previous_response_id` request needs, keyed by response id. Replicas that
+/// share a Redis or Postgres store can serve each other's follow-ups.
+#[async_trait]
+pub(crate) trait ResponseStore: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn put(
+        &self,
+        response_id: &str,
+        items: &[ResponseInputItem],
+    ) -> Result<(), ResponseStoreError>;
+
+    async fn get(
+        &self,
+        response_id: &str,
+    ) -> Result<Option<Vec<ResponseInputItem>>, ResponseStoreError>;
+}
+
+/// Keeps the newest responses of this replica only.
+#[derive(Debug, Default)]
+pub(crate) struct InMemoryResponseStore {
+    entries: Mutex<StoredResponses>,
+}
+
+#[derive(Debug, Default)]
+struct StoredResponses {
+    items: HashMap<String, Vec<ResponseInputItem>>,
+    order: VecDeque<String>,
+}
+
+#[async_trait]
+impl ResponseStore for InMemoryResponseStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn put(
+        &self,
+        response_id: &str,
+        items: &[ResponseInputItem],
+    ) -> Result<(), ResponseStoreError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if entries.items.insert(response_id.to_string(), items.to_vec()).is_none() {
+            entries.order.push_back(response_id.to_string());
+        }
+        while entries.order.len() > MAX_IN_MEMORY_RESPONSES {
+            if let Some(evicted) = entries.order.pop_front() {
+                entries.items.remove(&evicted);
+            }
+        }
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        response_id: &str,
+    ) -> Result<Option<Vec<ResponseInputItem>>, ResponseStoreError> {
+        let entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(entries.items.get(response_id).cloned())
+    }
+}
+
+/// JSON values under `xrouter:response:<id>` that Redis expires after the TTL.
+pub(crate) struct RedisResponseStore {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+    ttl: Duration,
+}
+
+impl RedisResponseStore {
+    pub(crate) fn open(url: &str, ttl: Duration) -> redis::RedisResult<Self> {
+        Ok(Self { client: redis::Client::open(url)?, connection: OnceCell::new(), ttl })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, ResponseStoreError> {
+        let connect = self.connection.get_or_try_init(|| connect_redis(&self.client));
+        match tokio::time::timeout(REDIS_TIMEOUT, connect).await {
+            Ok(Ok(connection)) => Ok(connection.clone()),
+            Ok(Err(error)) => Err(ResponseStoreError::Backend(error.to_string())),
+            Err(_) => Err(ResponseStoreError::Backend("redis connect timed out".to_string())),
+        }
+    }
+}
+
+fn redis_response_key(response_id: &str) -> String {
+    format!("xrouter:response:{response_id}")
+}
+
+#[async_trait]
+impl ResponseStore for RedisResponseStore {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn put(
+        &self,
+        response_id: &str,
+        items: &[ResponseInputItem],
+    ) -> Result<(), ResponseStoreError> {
+        let payload = serde_json::to_string(items)
+            .map_err(|error| ResponseStoreError::Corrupt(error.to_string()))?;
+        let mut connection = self.connection().await?;
+        redis::cmd("SET")
+            .arg(redis_response_key(response_id))
+            .arg(payload)
+            .arg("EX")
+            .arg(self.ttl.as_secs().max(1))
+            .exec_async(&mut connection)
+            .await
+            .map_err(|error| ResponseStoreError::Backend(error.to_string()))
+    }
+
+    async fn get(
+        &self,
+        response_id: &str,
+    ) -> Result<Option<Vec<ResponseInputItem>>, ResponseStoreError> {
+        let mut connection = self.connection().await?;
+        let payload: Option<String> = redis::cmd("GET")
+            .arg(redis_response_key(response_id))
+            .query_async(&mut connection)
+            .await
+            .map_err(|error| ResponseStoreError::Backend(error.to_string()))?;
+        payload
+            .map(|payload| serde_json::from_str(&payload))
+            .transpose()
+            .map_err(|error| ResponseStoreError::Corrupt(error.to_string()))
+    }
+}
+
+/// Opens a connection whose driver runs on its own task until the client is dropped.
+pub(crate) async fn connect_postgres(
+    url: &str,
+) -> Result<tokio_postgres::Client, tokio_postgres::Error> {
+    let mut config = url.parse::<tokio_postgres::Config>()?;
+    config.connect_timeout(POSTGRES_CONNECT_TIMEOUT);
+    let (client, connection) = config.connect(tokio_postgres::NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(error) = connection.await {
+            warn!(event = "app.database.connection_closed", error = %error);
+        }
+    });
+    Ok(client)
+}
+
+/// Client of a Postgres-backed store, connected on first use and again after it drops.
+pub(crate) struct PostgresConnection {
+    url: String,
+    client: AsyncMutex<Option<Arc<tokio_postgres::Client>>>,
+}
+
+impl PostgresConnection {
+    pub(crate) fn new(url: &str) -> Result<Self, tokio_postgres::Error> {
+        url.parse::<tokio_postgres::Config>()?;
+        Ok(Self { url: url.to_string(), client: AsyncMutex::new(None) })
+    }
+
+    pub(crate) async fn client(
+        &self,
+    ) -> Result<Arc<tokio_postgres::Client>, tokio_postgres::Error> {
+        let mut client = self.client.lock().await;
+        if let Some(client) = client.as_ref().filter(|client| !client.is_closed()) {
+            return Ok(Arc::clone(client));
+        }
+        let connected = Arc::new(connect_postgres(&self.url).await?);
+        *client = Some(Arc::clone(&connected));
+        Ok(connected)
+    }
+}
+
+/// Rows of the `xrouter_responses` table, created by the database migrations. Rows past the TTL
+/// are ignored on read and deleted on write.
+pub(crate) struct PostgresResponseStore {
+    connection: PostgresConnection,
+    ttl: Duration,
+}
+
+impl PostgresResponseStore {
+    pub(crate) fn open(url: &str, ttl: Duration) -> Result<Self, tokio_postgres::Error> {
+        Ok(Self { connection: PostgresConnection::new(url)?, ttl })
+    }
+
+    async fn client(&self) -> Result<Arc<tokio_postgres::Client>, ResponseStoreError> {
+        self.connection
+            .client()
+            .await
+            .map_err(|error| ResponseStoreError::Backend(error.to_string()))
+    }
+
+    fn ttl_seconds(&self) -> f64 {
+        self.ttl.as_secs() as f64
+    }
+}
+
+#[async_trait]
+impl ResponseStore for PostgresResponseStore {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn put(
+        &self,
+        response_id: &str,
+        items: &[ResponseInputItem],
+    ) -> Result<(), ResponseStoreError> {
+        let payload = serde_json::to_value(items)
+            .map_err(|error| ResponseStoreError::Corrupt(error.to_string()))?;
+        let backend = |error: tokio_postgres::Error| ResponseStoreError::Backend(error.to_string());
+        let client = self.client().await?;
+        client
+            .execute(
+                "INSERT INTO xrouter_responses (response_id, items) VALUES ($1, $2) \
+                 ON CONFLICT (response_id) DO UPDATE SET items = EXCLUDED.items, created_at = now()",
+                &[&response_id, &payload],
+            )
+            .await
+            .map_err(backend)?;
+        client
+            .execute(
+                "DELETE FROM xrouter_responses \
+                 WHERE created_at < now() - make_interval(secs => $1)",
+                &[&self.ttl_seconds()],
+            )
+            .await
+            .map_err(backend)?;
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        response_id: &str,
+    ) -> Result<Option<Vec<ResponseInputItem>>, ResponseStoreError> {
+        let client = self.client().await?;
+        let row = client
+            .query_opt(
+                "SELECT items FROM xrouter_responses \
+                 WHERE response_id = $1 AND created_at >= now() - make_interval(secs => $2)",
+                &[&response_id, &self.ttl_seconds()],
+            )
+            .await
+            .map_err(|error| ResponseStoreError::Backend(error.to_string()))?;
+        row.map(|row| serde_json::from_value(row.get::<_, serde_json::Value>(0)))
+            .transpose()
+            .map_err(|error| ResponseStoreError::Corrupt(error.to_string()))
+    }
+}
+
+/// Rows of the `xrouter_responses` table of the embedded database of `XR_STORAGE=sqlite`. Rows
+/// past the TTL are ignored on read and deleted on write.
+pub(crate) struct SqliteResponseStore {
+    database: Arc<SqliteDatabase>,
+    ttl: Duration,
+}
+
+impl SqliteResponseStore {
+    pub(crate) fn new(database: Arc<SqliteDatabase>, ttl: Duration) -> Self {
+        Self { database, ttl }
+    }
+
+    /// Unix seconds before which a stored response has expired.
+    fn expired_before(&self) -> i64 {
+        unix_now().saturating_sub(self.ttl.as_secs()) as i64
+    }
+}
+
+#[async_trait]
+impl ResponseStore for SqliteResponseStore {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn put(
+        &self,
+        response_id: &str,
+        items: &[ResponseInputItem],
+    ) -> Result<(), ResponseStoreError> {
+        let payload = serde_json::to_string(items)
+            .map_err(|error| ResponseStoreError::Corrupt(error.to_string()))?;
+        let response_id = response_id.to_string();
+        let expired_before = self.expired_before();
+        self.database
+            .call(move |connection| {
+                connection.execute(
+                    "INSERT INTO xrouter_responses (response_id, items, created_at) \
+                     VALUES (?1, ?2, ?3) ON CONFLICT (response_id) \
+                     DO UPDATE SET items = excluded.items, created_at = excluded.created_at",
+                    params![response_id, payload, unix_now() as i64],
+                )?;
+                connection.execute(
+                    "DELETE FROM xrouter_responses WHERE created_at < ?1",
+                    [expired_before],
+                )
+            })
+            .await
+            .map_err(ResponseStoreError::Backend)?;
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        response_id: &str,
+    ) -> Result<Option<Vec<ResponseInputItem>>, ResponseStoreError> {
+        let response_id = response_id.to_string();
+        let expired_before = self.expired_before();
+        let payload = self
+            .database
+            .call(move |connection| {
+                connection
+                    .query_row(
+                        "SELECT items FROM xrouter_responses \
+                         WHERE response_id = ?1 AND created_at >= ?2",
+                        params![response_id, expired_before],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .optional()
+            })
+            .await
+            .map_err(ResponseStoreError::Backend)?;
+        payload
+            .map(|payload| serde_json::from_str(&payload))
+            .transpose()
+            .map_err(|error| ResponseStoreError::Corrupt(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use xrouter_contracts::ResponseInputItem;
+
+    use super::{
+        InMemoryResponseStore, PostgresResponseStore, RedisResponseStore, ResponseStore,
+        ResponseStoreError, SqliteResponseStore,
+    };
+    use crate::storage::SqliteDatabase;
+
+    fn reasoning(encrypted_content: &str) -> ResponseInputItem {
+        ResponseInputItem {
+            kind: Some("reasoning".to_string()),
+            encrypted_content: Some(encrypted_content.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn in_memory_store_round_trips_and_overwrites_items() {
+        let store = InMemoryResponseStore::default();
+        store.put("resp_1", &[reasoning("a")]).await.expect("put must succeed");
+        store.put("resp_1", &[reasoning("b")]).await.expect("put must succeed");
+
+        assert_eq!(
+            store.get("resp_1").await.expect("get must succeed"),
+            Some(vec![reasoning("b")])
+        );
+        assert_eq!(store.get("resp_2").await.expect("get must succeed"), None);
+    }
+
+    #[tokio::test]
+    async fn sqlite_store_round_trips_items_and_skips_expired_ones() {
+        let path = std::env::temp_dir().join(format!("xrouter-{}.db", uuid::Uuid::new_v4()));
+        let database = Arc::new(SqliteDatabase::open(&path).expect("database must open"));
+        let store = SqliteResponseStore::new(Arc::clone(&database), Duration::from_secs(60));
+        store.put("resp_1", &[reasoning("a")]).await.expect("put must succeed");
+        store.put("resp_1", &[reasoning("b")]).await.expect("put must succeed");
+        assert_eq!(
+            store.get("resp_1").await.expect("get must succeed"),
+            Some(vec![reasoning("b")])
+        );
+
+        database
+            .call(|connection| {
+                connection.execute("UPDATE xrouter_responses SET created_at = created_at - 120", [])
+            })
+            .await
+            .expect("backdating must succeed");
+        assert_eq!(store.get("resp_1").await.expect("get must succeed"), None);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn remote_stores_report_unreachable_backends_as_errors() {
+        let redis = RedisResponseStore::open("redis://127.0.0.1:1/", Duration::from_secs(60))
+            .expect("url must parse");
+        assert!(matches!(redis.get("resp_1").await, Err(ResponseStoreError::Backend(_))));
+
+        let postgres = PostgresResponseStore::open(
+            "postgres://xrouter@127.0.0.1:1/xrouter",
+            Duration::from_secs(60),
+        )
+        .expect("url must parse");
+        assert!(matches!(
+            postgres.put("resp_1", &[reasoning("a")]).await,
+            Err(ResponseStoreError::Backend(_))
+        ));
+    }
+}
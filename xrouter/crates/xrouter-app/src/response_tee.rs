@@ -0,0 +1,178 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::json;
+use tracing::{info, warn};
+use xrouter_contracts::{ResponseEvent, ResponseOutputItem, Usage};
+
+use crate::{
+    config::ResponseTeeConfig,
+    record_queue::{RecordQueue, RecordSink},
+};
+
+/// Copies completed responses, and optionally stream deltas, to a webhook or Kafka topic
+/// without ever delaying the caller.
+pub(crate) struct ResponseTee {
+    queue: RecordQueue,
+    include_deltas: bool,
+}
+
+impl ResponseTee {
+    pub(crate) fn from_config(
+        config: &ResponseTeeConfig,
+        include_deltas: bool,
+        buffer: usize,
+    ) -> Option<Arc<Self>> {
+        let (backend, sink) = match config {
+            ResponseTeeConfig::Off => return None,
+            ResponseTeeConfig::Webhook { url } => {
+                ("webhook", RecordSink::Webhook { url: url.clone() })
+            }
+            // Keyed by response id, so the records of one response land in one partition in order.
+            ResponseTeeConfig::Kafka { url, topic } => (
+                "kafka",
+                RecordSink::KafkaRest {
+                    url: url.clone(),
+                    topic: topic.clone(),
+                    key_field: "response_id",
+                },
+            ),
+        };
+        let queue = match RecordQueue::new("response_tee", sink, buffer) {
+            Ok(queue) => queue,
+            Err(error) => {
+                warn!(event = "app.response_tee.client_failed", error = %error);
+                return None;
+            }
+        };
+        info!(
+            event = "app.response_tee.enabled",
+            backend = backend,
+            deltas = include_deltas,
+            buffer = buffer
+        );
+        Some(Arc::new(Self { queue, include_deltas }))
+    }
+
+    pub(crate) fn completed(
+        &self,
+        response_id: &str,
+        model: &str,
+        provider: &str,
+        output: &[ResponseOutputItem],
+        finish_reason: &str,
+        usage: &Usage,
+    ) {
+        self.queue.publish(json!({
+            "type": "response.completed",
+            "response_id": response_id,
+            "model": model,
+            "provider": provider,
+            "finish_reason": finish_reason,
+            "output": output,
+            "usage": usage,
+            "created_at": unix_seconds(),
+        }));
+    }
+
+    /// Tees one event of a streamed response.
+    pub(crate) fn observe(&self, model: &str, provider: &str, event: &ResponseEvent) {
+        let (kind, id, delta) = match event {
+            ResponseEvent::OutputTextDelta { id, delta } => {
+                ("response.output_text.delta", id, delta)
+            }
+            ResponseEvent::ReasoningDelta { id, delta } => ("response.reasoning.delta", id, delta),
+            ResponseEvent::ResponseCompleted { id, output, finish_reason, usage, .. } => {
+                return self.completed(id, model, provider, output, finish_reason, usage);
+            }
+            _ => return,
+        };
+        if self.include_deltas {
+            self.queue.publish(json!({
+                "type": kind,
+                "response_id": id,
+                "model": model,
+                "provider": provider,
+                "delta": delta,
+            }));
+        }
+    }
+}
+
+fn unix_seconds() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{Json, Router, extract::State, routing::post};
+    use serde_json::Value;
+    use tokio::sync::mpsc;
+    use xrouter_contracts::{ResponseEvent, Usage};
+
+    use super::ResponseTee;
+    use crate::config::ResponseTeeConfig;
+
+    async fn capture_server() -> (String, mpsc::UnboundedReceiver<Value>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let app =
+            Router::new().route(
+                "/{*path}",
+                post(
+                    |State(sender): State<mpsc::UnboundedSender<Value>>,
+                     Json(body): Json<Value>| async move {
+                        let _ = sender.send(body);
+                    },
+                )
+                .with_state(sender),
+            );
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("listener must bind");
+        let url = format!("http://{}", listener.local_addr().expect("address must resolve"));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, receiver)
+    }
+
+    fn delta(delta: &str) -> ResponseEvent {
+        ResponseEvent::OutputTextDelta { id: "resp_1".to_string(), delta: delta.to_string() }
+    }
+
+    #[tokio::test]
+    async fn webhook_receives_completed_responses_and_deltas_only_when_enabled() {
+        let (url, mut received) = capture_server().await;
+        let config = ResponseTeeConfig::Webhook { url: format!("{url}/hook") };
+        let tee = ResponseTee::from_config(&config, false, 16).expect("tee must be enabled");
+
+        tee.observe("zai/glm-4.5", "zai", &delta("hel"));
+        let usage = Usage {
+            input_tokens: 1,
+            output_tokens: 2,
+            total_tokens: 3,
+            input_tokens_details: None,
+            output_tokens_details: None,
+        };
+        tee.completed("resp_1", "zai/glm-4.5", "zai", &[], "stop", &usage);
+        let body = tokio::time::timeout(Duration::from_secs(5), received.recv())
+            .await
+            .expect("delivery must arrive")
+            .expect("server must stay up");
+        assert_eq!(body.as_array().map(Vec::len), Some(1));
+        assert_eq!(body[0]["type"], "response.completed");
+        assert_eq!(body[0]["model"], "zai/glm-4.5");
+        assert_eq!(body[0]["finish_reason"], "stop");
+        assert_eq!(body[0]["usage"]["total_tokens"], 3);
+
+        let tee = ResponseTee::from_config(&config, true, 16).expect("tee must be enabled");
+        tee.observe("zai/glm-4.5", "zai", &delta("hel"));
+        let body = tokio::time::timeout(Duration::from_secs(5), received.recv())
+            .await
+            .expect("delivery must arrive")
+            .expect("server must stay up");
+        assert_eq!(body[0]["type"], "response.output_text.delta");
+        assert_eq!(body[0]["delta"], "hel");
+    }
+}
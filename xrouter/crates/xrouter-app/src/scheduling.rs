@@ -0,0 +1,358 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use tokio::sync::oneshot;
+use xrouter_contracts::RequestPriority;
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome,
+};
+
+use crate::api_keys::ApiKeyRecord;
+
+pub(crate) const PRIORITY_HEADER: &str = "x-xrouter-priority";
+
+/// Queue slot of `priority` in a gate's per-class state.
+fn class_index(priority: RequestPriority) -> usize {
+    priority as usize
+}
+
+/// How long a request may wait for a provider slot before it fails as overloaded.
+fn queue_timeout(priority: RequestPriority) -> Duration {
+    match priority {
+        RequestPriority::Interactive => Duration::from_secs(5),
+        RequestPriority::Batch => Duration::from_secs(60),
+    }
+}
+
+/// Class of a request: the `priority` of its managed key when set, else the
+/// `x-xrouter-priority` header, else interactive.
+pub(crate) fn resolve_priority(
+    key: Option<&ApiKeyRecord>,
+    headers: &HeaderMap,
+) -> Result<RequestPriority, CoreError> {
+    if let Some(priority) = key.and_then(|key| key.priority) {
+        return Ok(priority);
+    }
+    let Some(raw) = headers.get(PRIORITY_HEADER) else {
+        return Ok(RequestPriority::default());
+    };
+    raw.to_str().ok().and_then(RequestPriority::parse).ok_or_else(|| {
+        CoreError::Validation(format!("{PRIORITY_HEADER} must be `interactive` or `batch`"))
+    })
+}
+
+/// Per-provider gates, one per enabled provider, shared with the admin API for their counters.
+#[derive(Debug)]
+pub(crate) struct PriorityScheduler {
+    capacity: usize,
+    gates: Mutex<BTreeMap<String, Arc<PriorityGate>>>,
+}
+
+impl PriorityScheduler {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self { capacity, gates: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub(crate) fn gate(&self, provider: &str) -> Arc<PriorityGate> {
+        let mut gates = self.gates.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Arc::clone(
+            gates.entry(provider.to_string()).or_insert_with(|| {
+                Arc::new(PriorityGate::new(provider.to_string(), self.capacity))
+            }),
+        )
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<GateSnapshot> {
+        let gates = self.gates.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        gates.values().map(|gate| gate.snapshot()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ClassCounters {
+    admitted: u64,
+    queued: u64,
+    rejected: u64,
+    wait_ms_total: u64,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    in_flight: usize,
+    /// Waiters by class, interactive first; each is handed a slot through its sender.
+    queues: [VecDeque<oneshot::Sender<()>>; 2],
+    counters: [ClassCounters; 2],
+}
+
+/// Provider slots handed out by class: a freed slot goes to the oldest interactive waiter, and
+/// only to a batch waiter when no interactive one is queued.
+#[derive(Debug)]
+pub(crate) struct PriorityGate {
+    provider: String,
+    capacity: usize,
+    state: Mutex<GateState>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ClassSnapshot {
+    pub(crate) priority: RequestPriority,
+    pub(crate) admitted: u64,
+    pub(crate) queued: u64,
+    pub(crate) rejected: u64,
+    pub(crate) waiting: usize,
+    pub(crate) avg_wait_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GateSnapshot {
+    pub(crate) provider: String,
+    pub(crate) capacity: usize,
+    pub(crate) in_flight: usize,
+    pub(crate) classes: Vec<ClassSnapshot>,
+}
+
+impl PriorityGate {
+    fn new(provider: String, capacity: usize) -> Self {
+        Self { provider, capacity, state: Mutex::new(GateState::default()) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, GateState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) async fn acquire(
+        self: &Arc<Self>,
+        priority: RequestPriority,
+    ) -> Result<GatePermit, CoreError> {
+        let class = class_index(priority);
+        let receiver = {
+            let mut state = self.lock();
+            for queue in &mut state.queues {
+                queue.retain(|waiter| !waiter.is_closed());
+            }
+            let queued_ahead = state.queues[..=class].iter().any(|queue| !queue.is_empty());
+            if state.in_flight < self.capacity && !queued_ahead {
+                state.in_flight += 1;
+                state.counters[class].admitted += 1;
+                return Ok(GatePermit { gate: Arc::clone(self) });
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.queues[class].push_back(sender);
+            state.counters[class].queued += 1;
+            receiver
+        };
+
+        let started_at = Instant::now();
+        let mut waiter = Waiter { gate: Arc::clone(self), receiver, admitted: false };
+        let handed_over = tokio::time::timeout(queue_timeout(priority), &mut waiter.receiver).await;
+        waiter.admitted = matches!(handed_over, Ok(Ok(())));
+        let admitted = waiter.admitted;
+        drop(waiter);
+        let mut state = self.lock();
+        if admitted {
+            let counters = &mut state.counters[class];
+            counters.admitted += 1;
+            counters.wait_ms_total += started_at.elapsed().as_millis() as u64;
+            return Ok(GatePermit { gate: Arc::clone(self) });
+        }
+        state.counters[class].rejected += 1;
+        Err(CoreError::Provider(format!(
+            "provider overloaded: no {} slot freed up for {} within {}s",
+            priority.as_str(),
+            self.provider,
+            queue_timeout(priority).as_secs()
+        )))
+    }
+
+    fn release(&self) {
+        let mut state = self.lock();
+        for class in 0..state.queues.len() {
+            while let Some(waiter) = state.queues[class].pop_front() {
+                if waiter.send(()).is_ok() {
+                    return;
+                }
+            }
+        }
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+
+    fn snapshot(&self) -> GateSnapshot {
+        let state = self.lock();
+        GateSnapshot {
+            provider: self.provider.clone(),
+            capacity: self.capacity,
+            in_flight: state.in_flight,
+            classes: RequestPriority::ALL
+                .into_iter()
+                .map(|priority| {
+                    let counters = state.counters[class_index(priority)];
+                    let waited = counters.admitted.min(counters.queued);
+                    ClassSnapshot {
+                        priority,
+                        admitted: counters.admitted,
+                        queued: counters.queued,
+                        rejected: counters.rejected,
+                        waiting: state.queues[class_index(priority)]
+                            .iter()
+                            .filter(|waiter| !waiter.is_closed())
+                            .count(),
+                        avg_wait_ms: counters.wait_ms_total.checked_div(waited).unwrap_or(0),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A queued request; a slot handed to it after it gave up is passed on to the next waiter.
+struct Waiter {
+    gate: Arc<PriorityGate>,
+    receiver: oneshot::Receiver<()>,
+    admitted: bool,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        if self.admitted {
+            return;
+        }
+        self.receiver.close();
+        if self.receiver.try_recv().is_ok() {
+            self.gate.release();
+        }
+    }
+}
+
+pub(crate) struct GatePermit {
+    gate: Arc<PriorityGate>,
+}
+
+impl Drop for GatePermit {
+    fn drop(&mut self) {
+        self.gate.release();
+    }
+}
+
+/// Waits for a slot of the provider's gate in the calling request's class before `inner` runs.
+pub(crate) struct PrioritizedProviderClient {
+    inner: Arc<dyn ProviderClient>,
+    gate: Arc<PriorityGate>,
+}
+
+impl PrioritizedProviderClient {
+    pub(crate) fn new(inner: Arc<dyn ProviderClient>, gate: Arc<PriorityGate>) -> Self {
+        Self { inner, gate }
+    }
+}
+
+#[async_trait]
+impl ProviderClient for PrioritizedProviderClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let _permit = self.gate.acquire(request.controls.priority).await?;
+        self.inner.generate(request).await
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let _permit = self.gate.acquire(request.request.controls.priority).await?;
+        self.inner.generate_stream(request).await
+    }
+
+    fn supports_required_tool_choice(&self) -> bool {
+        self.inner.supports_required_tool_choice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::{HeaderMap, HeaderValue};
+
+    use super::{PRIORITY_HEADER, PriorityScheduler, RequestPriority, resolve_priority};
+
+    #[tokio::test]
+    async fn freed_slots_go_to_interactive_waiters_before_older_batch_waiters() {
+        let scheduler = PriorityScheduler::new(1);
+        let gate = scheduler.gate("zai");
+        let running = gate.acquire(RequestPriority::Batch).await.expect("slot must be free");
+
+        let batch = tokio::spawn({
+            let gate = Arc::clone(&gate);
+            async move {
+                gate.acquire(RequestPriority::Batch).await.map(|_| tokio::time::Instant::now())
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let interactive = tokio::spawn({
+            let gate = Arc::clone(&gate);
+            async move {
+                let permit = gate.acquire(RequestPriority::Interactive).await;
+                let admitted_at = tokio::time::Instant::now();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                permit.map(|_| admitted_at)
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(running);
+
+        let interactive_at = interactive.await.expect("task must finish").expect("must admit");
+        let batch_at = batch.await.expect("task must finish").expect("must admit");
+        assert!(interactive_at < batch_at);
+
+        let snapshot = scheduler.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].in_flight, 0);
+        let counts = snapshot[0]
+            .classes
+            .iter()
+            .map(|class| (class.priority, class.admitted, class.queued, class.waiting))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![(RequestPriority::Interactive, 1, 1, 0), (RequestPriority::Batch, 2, 1, 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn abandoned_waiters_do_not_hold_on_to_freed_slots() {
+        let scheduler = PriorityScheduler::new(1);
+        let gate = scheduler.gate("zai");
+        let running = gate.acquire(RequestPriority::Interactive).await.expect("slot must be free");
+
+        let abandoned = tokio::time::timeout(
+            Duration::from_millis(20),
+            gate.acquire(RequestPriority::Interactive),
+        )
+        .await;
+        assert!(abandoned.is_err());
+        assert_eq!(scheduler.snapshot()[0].classes[0].waiting, 0);
+        drop(running);
+        assert_eq!(scheduler.snapshot()[0].in_flight, 0);
+
+        let next = gate.acquire(RequestPriority::Batch).await.expect("slot must be free again");
+        assert_eq!(scheduler.snapshot()[0].in_flight, 1);
+        drop(next);
+    }
+
+    #[test]
+    fn priority_header_is_parsed_and_invalid_values_are_rejected() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(resolve_priority(None, &headers).ok(), Some(RequestPriority::Interactive));
+        headers.insert(PRIORITY_HEADER, HeaderValue::from_static("Batch"));
+        assert_eq!(resolve_priority(None, &headers).ok(), Some(RequestPriority::Batch));
+        headers.insert(PRIORITY_HEADER, HeaderValue::from_static("urgent"));
+        assert!(resolve_priority(None, &headers).is_err());
+    }
+}
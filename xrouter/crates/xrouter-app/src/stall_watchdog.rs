@@ -0,0 +1,107 @@
+use std::{future::Future, sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+
+/// When a stream last heard from its provider. An event the client has not yet taken off the
+/// channel does not count as a stall: the provider is waiting on the client then.
+#[derive(Debug)]
+pub(crate) struct StreamProgress {
+    state: Mutex<(Instant, bool)>,
+}
+
+impl StreamProgress {
+    pub(crate) fn new() -> Self {
+        Self { state: Mutex::new((Instant::now(), false)) }
+    }
+
+    pub(crate) fn sending(&self) {
+        *self.lock() = (Instant::now(), true);
+    }
+
+    pub(crate) fn sent(&self) {
+        *self.lock() = (Instant::now(), false);
+    }
+
+    /// When the stream counts as stalled unless it makes progress first.
+    fn deadline(&self, timeout: Duration) -> Instant {
+        let (last_progress_at, sending) = *self.lock();
+        if sending { Instant::now() + timeout } else { last_progress_at + timeout }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, (Instant, bool)> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Runs `execution` until it finishes, or returns `None` once `progress` stands still for
+/// `timeout`; dropping `execution` then aborts the upstream call.
+pub(crate) async fn watch_for_stall<F: Future>(
+    execution: F,
+    progress: &StreamProgress,
+    timeout: Duration,
+) -> Option<F::Output> {
+    tokio::pin!(execution);
+    loop {
+        let deadline = progress.deadline(timeout);
+        tokio::select! {
+            output = &mut execution => return Some(output),
+            () = tokio::time::sleep_until(deadline) => {
+                if progress.deadline(timeout) <= Instant::now() {
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{StreamProgress, watch_for_stall};
+
+    #[tokio::test]
+    async fn executions_that_keep_sending_are_left_alone() {
+        let progress = StreamProgress::new();
+        let execution = async {
+            for _ in 0..4 {
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                progress.sent();
+            }
+            "done"
+        };
+
+        let output = watch_for_stall(execution, &progress, Duration::from_millis(80)).await;
+
+        assert_eq!(output, Some("done"));
+    }
+
+    #[tokio::test]
+    async fn executions_that_go_quiet_are_aborted() {
+        let progress = StreamProgress::new();
+        let execution = async {
+            progress.sent();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            "done"
+        };
+
+        let output = watch_for_stall(execution, &progress, Duration::from_millis(50)).await;
+
+        assert_eq!(output, None);
+    }
+
+    #[tokio::test]
+    async fn a_client_that_is_slow_to_read_is_not_a_stall() {
+        let progress = StreamProgress::new();
+        let execution = async {
+            progress.sending();
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            progress.sent();
+            "done"
+        };
+
+        let output = watch_for_stall(execution, &progress, Duration::from_millis(50)).await;
+
+        assert_eq!(output, Some("done"));
+    }
+}
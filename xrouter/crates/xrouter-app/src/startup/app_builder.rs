@@ -1,12 +1,31 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use axum::Router;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::{
-    AppState, config,
+    AppState,
+    alerts::ALERT_EVALUATION_INTERVAL,
+    bandit::BanditRegistry,
+    config,
+    coordination::RedisCoordinator,
+    event_bus::EventBus,
     http::docs::build_router,
-    startup::{model_catalog::load_models, provider_factory::build_engines},
+    response_tee::ResponseTee,
+    scheduling::PriorityScheduler,
+    startup::{
+        key_store::build_key_store,
+        model_catalog::load_models,
+        provider_factory::{build_engines, build_transcript_store},
+        response_store::build_response_store,
+        storage::open_storage,
+    },
+    usage_export::UsageExport,
+    usage_ledger::UsageLedger,
 };
 
 pub struct AppBuilder<'a> {
@@ -24,13 +43,51 @@ impl<'a> AppBuilder<'a> {
             event = "app.config.loaded",
             openai_compatible_api = self.config.openai_compatible_api,
             byok_enabled = self.config.byok_enabled,
+            catalog_only = self.config.catalog_only,
             provider_total = self.config.providers.len(),
             provider_enabled = enabled_providers.len()
         );
         debug!(event = "app.config.providers", enabled_providers = ?enabled_providers);
 
-        let engines = build_engines(self.config);
+        let coordinator = self.coordinator();
+        let scheduler = self.config.priority_scheduling.then(|| {
+            info!(
+                event = "app.priority_scheduling.enabled",
+                capacity = self.config.provider_max_inflight
+            );
+            Arc::new(PriorityScheduler::new(self.config.provider_max_inflight))
+        });
+        let transcripts = build_transcript_store(self.config);
+        let chaos = self.config.chaos.clone().map(|policy| {
+            warn!(event = "app.chaos.enabled", policy = ?policy);
+            Arc::new(RwLock::new(policy))
+        });
+        let bandits = (!self.config.catalog_only && !self.config.bandit_models.is_empty())
+            .then(|| {
+                BanditRegistry::new(
+                    &self.config.bandit_models,
+                    &enabled_providers,
+                    self.config.bandit_state_path.clone(),
+                )
+            })
+            .filter(|registry| !registry.is_empty())
+            .map(Arc::new);
+        // Catalog-only instances hold no provider clients, so inference capacity is never exposed.
+        let engines = if self.config.catalog_only {
+            HashMap::new()
+        } else {
+            build_engines(
+                self.config,
+                transcripts.clone(),
+                chaos.clone(),
+                coordinator.clone(),
+                scheduler.as_deref(),
+                bandits.as_ref(),
+            )
+        };
         let models = load_models(self.config, &enabled_providers);
+        let storage = open_storage(self.config);
+        let key_store = build_key_store(self.config, storage.as_ref());
 
         AppState::from_parts(
             self.config.openai_compatible_api,
@@ -38,12 +95,72 @@ impl<'a> AppBuilder<'a> {
             models,
             engines,
         )
+        .with_catalog_only(self.config.catalog_only)
+        .with_user_rate_limit(self.config.user_rate_limit_per_minute, coordinator.clone())
+        .with_stream_retained_output_limit(self.config.stream_retained_output_bytes)
+        .with_stream_stall_timeout(
+            self.config.stream_stall_timeout_seconds.map(Duration::from_secs),
+        )
+        .with_stream_salvage_partial(self.config.stream_salvage_partial)
+        .with_alerts(
+            &self.config.alert_rules,
+            self.config.alert_webhook_url.as_deref(),
+            ALERT_EVALUATION_INTERVAL,
+        )
+        .with_max_request_body_bytes(self.config.max_request_body_bytes)
+        .with_max_hops(self.config.max_hops)
+        .with_upstream_limits(self.config.upstream_limits)
+        .with_http_limits(&self.config.http_limits)
+        .with_request_coalescing(self.config.request_coalescing)
+        .with_scheduler(scheduler)
+        .with_bandits(bandits)
+        .with_cors(self.config.cors.clone())
+        .with_passthrough_fields(&self.config.passthrough_fields)
+        .with_unknown_model(self.config.unknown_model.clone())
+        .with_truncation(self.config.truncation, self.config.truncation_summary_model.clone())
+        .with_hide_reasoning_models(&self.config.hide_reasoning_models)
+        .with_header_overrides(self.config.header_overrides)
+        .with_provider_projects(
+            self.config
+                .providers
+                .iter()
+                .filter_map(|(name, provider)| Some((name.clone(), provider.project.clone()?)))
+                .collect(),
+        )
+        .with_response_store(build_response_store(self.config, storage.as_ref()))
+        .with_conversation_budget(
+            self.config.conversation_token_budget,
+            self.config.conversation_budget_enforced,
+        )
+        .with_idempotency_ttl(Duration::from_secs(self.config.idempotency_ttl_seconds))
+        .with_event_bus(EventBus::from_config(&self.config.event_bus, self.config.event_bus_buffer))
+        .with_usage_export(UsageExport::from_config(self.config.usage_export.as_ref()))
+        .with_usage_ledger(storage.map(UsageLedger::new))
+        .with_response_tee(ResponseTee::from_config(
+            &self.config.response_tee,
+            self.config.response_tee_deltas,
+            self.config.response_tee_buffer,
+        ))
+        .with_tenants(&self.config.tenants, key_store, coordinator)
+        .with_admin_token(self.config.admin_token.as_deref())
+        .with_transcripts(transcripts)
+        .with_chaos(chaos)
     }
 
     pub fn build_router(&self) -> Router {
         build_router(self.build_state())
     }
 
+    fn coordinator(&self) -> Option<Arc<RedisCoordinator>> {
+        let config::CoordinationConfig::Redis { url } = &self.config.coordination else {
+            return None;
+        };
+        let coordinator =
+            RedisCoordinator::open(url).expect("XR_REDIS_URL is validated when config loads");
+        info!(event = "app.coordination.enabled", backend = "redis");
+        Some(Arc::new(coordinator))
+    }
+
     fn enabled_providers(&self) -> HashSet<String> {
         self.config
             .providers
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use tracing::info;
+
+use crate::{
+    api_keys::{
+        ApiKeyStore, FileApiKeyStore, InMemoryApiKeyStore, PostgresApiKeyStore, SqliteApiKeyStore,
+    },
+    config::{AppConfig, KeyStoreConfig},
+    storage::SqliteDatabase,
+};
+
+pub(crate) fn build_key_store(
+    config: &AppConfig,
+    storage: Option<&Arc<SqliteDatabase>>,
+) -> Option<Arc<dyn ApiKeyStore>> {
+    let store: Arc<dyn ApiKeyStore> = match &config.key_store {
+        KeyStoreConfig::Memory if config.admin_token.is_none() => return None,
+        KeyStoreConfig::Memory => Arc::new(InMemoryApiKeyStore::default()),
+        KeyStoreConfig::File { path } => {
+            Arc::new(FileApiKeyStore::open(path.clone()).expect("API key store must be readable"))
+        }
+        KeyStoreConfig::Postgres { url } => {
+            Arc::new(PostgresApiKeyStore::open(url).expect("key store URL is validated on load"))
+        }
+        KeyStoreConfig::Sqlite => Arc::new(SqliteApiKeyStore::new(Arc::clone(
+            storage.expect("sqlite key store comes with XR_STORAGE=sqlite"),
+        ))),
+    };
+    info!(
+        event = "app.key_store.enabled",
+        store = store.name(),
+        admin_api_enabled = config.admin_token.is_some()
+    );
+    Some(store)
+}
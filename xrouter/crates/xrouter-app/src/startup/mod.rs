@@ -1,5 +1,9 @@
 pub(crate) mod app_builder;
+pub(crate) mod key_store;
 pub(crate) mod model_catalog;
+pub(crate) mod model_catalog_cache;
 pub(crate) mod model_catalog_remote;
 pub(crate) mod model_catalog_sources;
 pub(crate) mod provider_factory;
+pub(crate) mod response_store;
+pub(crate) mod storage;
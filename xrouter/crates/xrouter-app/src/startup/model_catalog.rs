@@ -1,14 +1,34 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::Duration,
+};
 
-use tracing::{debug, info};
-use xrouter_core::{ModelDescriptor, default_model_catalog};
+use futures::future::join_all;
+use tokio::time::{Instant, timeout_at};
+use tracing::{debug, info, warn};
+use xrouter_clients_openai::models::default_supported_parameters;
+use xrouter_core::{ModelDescriptor, ModelPricing, default_model_catalog, synthesize_model_id};
 
-use crate::config;
+use crate::config::{self, ModelOverrideConfig};
+use crate::startup::model_catalog_cache::ModelCatalogCache;
+use crate::startup::model_catalog_remote::catalog_http_client;
 use crate::startup::model_catalog_sources::{
-    BaseCatalogSource, GigachatCatalogSource, ModelCatalogContext, ModelCatalogSource,
-    OpenRouterCatalogSource, RegistryBackedCatalogSource, XrouterCatalogSource,
+    BaseCatalogSource, CloudflareCatalogSource, GigachatCatalogSource, ModelCatalogContext,
+    ModelCatalogSource, OpenRouterCatalogSource, RegistryBackedCatalogSource, TgiCatalogSource,
+    XrouterCatalogSource,
 };
 
+const REMOTE_SOURCES: [&dyn ModelCatalogSource; 8] = [
+    &OpenRouterCatalogSource,
+    &RegistryBackedCatalogSource::new("zai"),
+    &RegistryBackedCatalogSource::new("yandex"),
+    &RegistryBackedCatalogSource::new("selfhosted"),
+    &GigachatCatalogSource,
+    &XrouterCatalogSource,
+    &TgiCatalogSource,
+    &CloudflareCatalogSource,
+];
+
 pub(crate) struct ModelCatalogService<'a> {
     context: ModelCatalogContext<'a>,
     registry_seed: Vec<ModelDescriptor>,
@@ -19,26 +39,64 @@ impl<'a> ModelCatalogService<'a> {
         config: &'a config::AppConfig,
         enabled_providers: &'a HashSet<String>,
     ) -> Self {
+        let http_client = catalog_http_client(
+            config.provider_timeout_seconds,
+            config.model_catalog_fetch_timeout_seconds,
+            false,
+        );
+        let gigachat_http_client = if config.gigachat_insecure_tls {
+            catalog_http_client(
+                config.provider_timeout_seconds,
+                config.model_catalog_fetch_timeout_seconds,
+                true,
+            )
+        } else {
+            http_client.clone()
+        };
         Self {
-            context: ModelCatalogContext { config, enabled_providers, test_mode: cfg!(test) },
+            context: ModelCatalogContext {
+                config,
+                enabled_providers,
+                test_mode: cfg!(test),
+                http_client,
+                gigachat_http_client,
+                cache: config.model_catalog_cache_path.clone().map(ModelCatalogCache::open),
+            },
             registry_seed: default_model_catalog(),
         }
     }
 
     pub(crate) fn load(&self) -> Vec<ModelDescriptor> {
         let mut models = BaseCatalogSource.load_models(&self.context, &self.registry_seed);
+        let sources = REMOTE_SOURCES
+            .into_iter()
+            .filter(|source| self.context.enabled_providers.contains(source.provider()))
+            .collect::<Vec<_>>();
 
-        let sources: [&dyn ModelCatalogSource; 5] = [
-            &OpenRouterCatalogSource,
-            &RegistryBackedCatalogSource::new("zai"),
-            &RegistryBackedCatalogSource::new("yandex"),
-            &GigachatCatalogSource,
-            &XrouterCatalogSource,
-        ];
-
-        for source in sources {
-            models.extend(source.load_models(&self.context, &self.registry_seed));
+        // Startup may already run inside a Tokio runtime, which cannot block on another one.
+        let remote = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                        Ok(runtime) => runtime.block_on(self.load_remote(&sources)),
+                        Err(error) => {
+                            warn!(event = "models.catalog.runtime.failed", error = %error);
+                            sources.iter().map(|source| self.offline_models(*source)).collect()
+                        }
+                    }
+                })
+                .join()
+                .expect("model catalog thread panicked")
+        });
+        models.extend(remote.into_iter().flatten());
+        if let Some(cache) = &self.context.cache {
+            cache.persist();
+        }
+        for model in models.iter_mut().filter(|model| model.supported_parameters.is_empty()) {
+            model.supported_parameters =
+                default_supported_parameters(&model.provider, model.supports_tools);
         }
+        apply_model_overrides(&mut models, &self.context.config.model_overrides);
 
         info!(event = "models.registry.loaded", model_count = models.len());
         debug!(
@@ -48,6 +106,77 @@ impl<'a> ModelCatalogService<'a> {
 
         models
     }
+
+    /// Fetches every source concurrently; sources still pending when the startup budget runs out
+    /// are served their cached or fallback models.
+    async fn load_remote(&self, sources: &[&dyn ModelCatalogSource]) -> Vec<Vec<ModelDescriptor>> {
+        let budget = Duration::from_secs(self.context.config.model_catalog_startup_budget_seconds);
+        let deadline = Instant::now() + budget;
+        join_all(sources.iter().map(|source| async move {
+            match timeout_at(deadline, source.load_models(&self.context, &self.registry_seed)).await
+            {
+                Ok(models) => models,
+                Err(_) => {
+                    warn!(
+                        event = "models.catalog.budget_exceeded",
+                        provider = source.provider(),
+                        budget_seconds = budget.as_secs()
+                    );
+                    self.offline_models(*source)
+                }
+            }
+        }))
+        .await
+    }
+
+    fn offline_models(&self, source: &dyn ModelCatalogSource) -> Vec<ModelDescriptor> {
+        self.context
+            .cached_models(source.provider())
+            .unwrap_or_else(|| source.fallback_models(&self.context, &self.registry_seed))
+    }
+}
+
+/// Merges `XR_MODEL_OVERRIDES` over the catalog. Like `/api/v1/models/{id}/endpoints`, a key
+/// matches a model by its public `<provider>/<model>` id or by its upstream id.
+fn apply_model_overrides(
+    models: &mut [ModelDescriptor],
+    overrides: &BTreeMap<String, ModelOverrideConfig>,
+) {
+    for (model_id, entry) in overrides {
+        let mut matched = false;
+        for model in models.iter_mut().filter(|model| {
+            model.id == *model_id || synthesize_model_id(&model.provider, &model.id) == *model_id
+        }) {
+            matched = true;
+            if let Some(description) = &entry.description {
+                model.description.clone_from(description);
+            }
+            if let Some(context_length) = entry.context_length {
+                model.context_length = context_length;
+            }
+            if let Some(max_completion_tokens) = entry.max_completion_tokens {
+                model.max_completion_tokens = max_completion_tokens;
+            }
+            if let Some(supports_tools) = entry.supports_tools {
+                model.supports_tools = supports_tools;
+            }
+            if let Some(is_moderated) = entry.is_moderated {
+                model.is_moderated = is_moderated;
+            }
+            if let Some(pricing) = &entry.pricing {
+                model.pricing = Some(ModelPricing {
+                    prompt: pricing.prompt.clone(),
+                    completion: pricing.completion.clone(),
+                });
+            }
+            if let Some(supported_parameters) = &entry.supported_parameters {
+                model.supported_parameters.clone_from(supported_parameters);
+            }
+        }
+        if !matched {
+            warn!(event = "models.override.unmatched", model = %model_id);
+        }
+    }
 }
 
 pub(crate) fn load_models(
@@ -59,8 +188,35 @@ pub(crate) fn load_models(
 
 #[cfg(test)]
 mod tests {
-    use super::{ModelCatalogService, load_models};
-    use crate::config::AppConfig;
+    use std::{
+        collections::HashSet,
+        net::TcpListener,
+        time::{Duration, Instant},
+    };
+
+    use xrouter_clients_openai::models::fallback_openrouter_models;
+
+    use super::{ModelCatalogService, apply_model_overrides, load_models};
+    use crate::config::{AppConfig, ModelOverrideConfig, ModelPricingConfig};
+    use crate::startup::model_catalog_cache::ModelCatalogCache;
+
+    /// Connections complete through the listen backlog, but requests are never answered.
+    fn silent_upstream() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let base_url = format!("http://{}", listener.local_addr().expect("listener address"));
+        (listener, base_url)
+    }
+
+    fn remote_config(providers: &[&str], base_url: &str) -> (AppConfig, HashSet<String>) {
+        let mut config = AppConfig::for_tests();
+        for (name, provider) in &mut config.providers {
+            provider.enabled = providers.contains(&name.as_str());
+            provider.api_key = Some("test-key".to_string());
+            provider.base_url = Some(base_url.to_string());
+        }
+        let enabled_providers = providers.iter().map(ToString::to_string).collect();
+        (config, enabled_providers)
+    }
 
     #[test]
     fn model_catalog_service_loads_supported_provider_models_in_test_mode() {
@@ -95,4 +251,109 @@ mod tests {
 
         assert!(models.is_empty());
     }
+
+    #[test]
+    fn remote_catalogs_are_fetched_concurrently() {
+        let (_upstream, base_url) = silent_upstream();
+        let (mut config, enabled_providers) =
+            remote_config(&["openrouter", "zai", "xrouter"], &base_url);
+        config.model_catalog_fetch_timeout_seconds = 1;
+        let mut service = ModelCatalogService::new(&config, &enabled_providers);
+        service.context.test_mode = false;
+
+        let started = Instant::now();
+        let models = service.load();
+
+        let elapsed = started.elapsed();
+        assert!(elapsed < Duration::from_millis(2500), "took {elapsed:?}");
+        assert_eq!(
+            models.iter().filter(|model| model.provider == "openrouter").count(),
+            fallback_openrouter_models(&config.openrouter_supported_models).len()
+        );
+    }
+
+    #[test]
+    fn startup_budget_serves_fallback_models_for_pending_providers() {
+        let (_upstream, base_url) = silent_upstream();
+        let (mut config, enabled_providers) = remote_config(&["openrouter"], &base_url);
+        config.model_catalog_fetch_timeout_seconds = 60;
+        config.model_catalog_startup_budget_seconds = 1;
+        let mut service = ModelCatalogService::new(&config, &enabled_providers);
+        service.context.test_mode = false;
+
+        let started = Instant::now();
+        let models = service.load();
+
+        let elapsed = started.elapsed();
+        assert!(elapsed < Duration::from_secs(5), "took {elapsed:?}");
+        let fallback = fallback_openrouter_models(&config.openrouter_supported_models);
+        assert_eq!(
+            models.iter().map(|model| model.id.as_str()).collect::<Vec<_>>(),
+            fallback.iter().map(|model| model.id.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn unreachable_provider_is_served_its_cached_catalog() {
+        let (_upstream, base_url) = silent_upstream();
+        let (mut config, enabled_providers) = remote_config(&["zai"], &base_url);
+        config.model_catalog_fetch_timeout_seconds = 1;
+        let cache_path =
+            std::env::temp_dir().join(format!("xrouter-model-cache-{}.json", uuid::Uuid::new_v4()));
+        let mut cached = xrouter_core::default_model_catalog()
+            .into_iter()
+            .find(|model| model.provider == "zai")
+            .expect("zai seed model");
+        cached.id = "zai/glm-cached".to_string();
+        let cache = ModelCatalogCache::open(cache_path.clone());
+        cache.record("zai", std::slice::from_ref(&cached));
+        cache.persist();
+        config.model_catalog_cache_path = Some(cache_path.clone());
+        let mut service = ModelCatalogService::new(&config, &enabled_providers);
+        service.context.test_mode = false;
+
+        let models = service.load();
+
+        assert_eq!(models.iter().map(|model| model.id.as_str()).collect::<Vec<_>>(), [cached.id]);
+        std::fs::remove_file(cache_path).expect("cache file must be removed");
+    }
+
+    #[test]
+    fn model_overrides_replace_only_the_fields_they_set() {
+        let mut models = xrouter_core::default_model_catalog();
+        let original = models
+            .iter()
+            .find(|model| model.id == "anthropic/claude-3.5-sonnet")
+            .cloned()
+            .expect("seed model");
+        let overrides = [(
+            "openrouter/anthropic/claude-3.5-sonnet".to_string(),
+            ModelOverrideConfig {
+                context_length: Some(100_000),
+                supports_tools: Some(false),
+                pricing: Some(ModelPricingConfig {
+                    prompt: "0.000003".to_string(),
+                    completion: "0.000015".to_string(),
+                }),
+                ..ModelOverrideConfig::default()
+            },
+        )]
+        .into_iter()
+        .collect();
+
+        apply_model_overrides(&mut models, &overrides);
+
+        let patched = models
+            .iter()
+            .find(|model| model.id == "anthropic/claude-3.5-sonnet")
+            .expect("patched model");
+        assert_eq!(patched.context_length, 100_000);
+        assert!(!patched.supports_tools);
+        assert_eq!(
+            patched.pricing.as_ref().map(|pricing| pricing.prompt.as_str()),
+            Some("0.000003")
+        );
+        assert_eq!(patched.description, original.description);
+        assert_eq!(patched.max_completion_tokens, original.max_completion_tokens);
+    }
 }
@@ -0,0 +1,219 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use xrouter_core::{ModelDescriptor, ModelPricing};
+
+/// Last successfully fetched upstream catalog per provider, kept on disk so that a provider
+/// unreachable at boot still serves its real models instead of the fallback descriptors.
+#[derive(Debug)]
+pub(crate) struct ModelCatalogCache {
+    path: PathBuf,
+    cached: BTreeMap<String, Vec<CachedModel>>,
+    fetched: Mutex<BTreeMap<String, Vec<CachedModel>>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    providers: BTreeMap<String, Vec<CachedModel>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedModel {
+    id: String,
+    provider: String,
+    description: String,
+    context_length: u32,
+    tokenizer: String,
+    instruct_type: String,
+    modality: String,
+    top_provider_context_length: u32,
+    is_moderated: bool,
+    max_completion_tokens: u32,
+    supports_tools: bool,
+    #[serde(default)]
+    pricing: Option<CachedPricing>,
+    #[serde(default)]
+    supported_parameters: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedPricing {
+    prompt: String,
+    completion: String,
+}
+
+impl From<&ModelDescriptor> for CachedModel {
+    fn from(model: &ModelDescriptor) -> Self {
+        Self {
+            id: model.id.clone(),
+            provider: model.provider.clone(),
+            description: model.description.clone(),
+            context_length: model.context_length,
+            tokenizer: model.tokenizer.clone(),
+            instruct_type: model.instruct_type.clone(),
+            modality: model.modality.clone(),
+            top_provider_context_length: model.top_provider_context_length,
+            is_moderated: model.is_moderated,
+            max_completion_tokens: model.max_completion_tokens,
+            supports_tools: model.supports_tools,
+            pricing: model.pricing.as_ref().map(|pricing| CachedPricing {
+                prompt: pricing.prompt.clone(),
+                completion: pricing.completion.clone(),
+            }),
+            supported_parameters: model.supported_parameters.clone(),
+        }
+    }
+}
+
+impl From<CachedModel> for ModelDescriptor {
+    fn from(model: CachedModel) -> Self {
+        Self {
+            id: model.id,
+            provider: model.provider,
+            description: model.description,
+            context_length: model.context_length,
+            tokenizer: model.tokenizer,
+            instruct_type: model.instruct_type,
+            modality: model.modality,
+            top_provider_context_length: model.top_provider_context_length,
+            is_moderated: model.is_moderated,
+            max_completion_tokens: model.max_completion_tokens,
+            supports_tools: model.supports_tools,
+            pricing: model.pricing.map(|pricing| ModelPricing {
+                prompt: pricing.prompt,
+                completion: pricing.completion,
+            }),
+            supported_parameters: model.supported_parameters,
+        }
+    }
+}
+
+impl ModelCatalogCache {
+    /// A missing or unreadable cache file starts an empty cache; it is rewritten by
+    /// [`ModelCatalogCache::persist`].
+    pub(crate) fn open(path: PathBuf) -> Self {
+        let cached = match fs::read_to_string(&path) {
+            Ok(raw) => match serde_json::from_str::<CacheFile>(&raw) {
+                Ok(file) => file.providers,
+                Err(error) => {
+                    warn!(
+                        event = "models.catalog.cache.invalid",
+                        path = %path.display(),
+                        error = %error
+                    );
+                    BTreeMap::new()
+                }
+            },
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(error) => {
+                warn!(
+                    event = "models.catalog.cache.unreadable",
+                    path = %path.display(),
+                    error = %error
+                );
+                BTreeMap::new()
+            }
+        };
+        Self { path, cached, fetched: Mutex::default() }
+    }
+
+    pub(crate) fn models(&self, provider: &str) -> Option<Vec<ModelDescriptor>> {
+        let models = self.cached.get(provider)?;
+        Some(models.iter().cloned().map(ModelDescriptor::from).collect())
+    }
+
+    /// Remembers a successful fetch; empty catalogs never replace a cached one.
+    pub(crate) fn record(&self, provider: &str, models: &[ModelDescriptor]) {
+        if models.is_empty() {
+            return;
+        }
+        self.fetched().insert(provider.to_string(), models.iter().map(CachedModel::from).collect());
+    }
+
+    /// Writes the recorded fetches over the cached catalogs; providers not fetched this time
+    /// keep their cached entry.
+    pub(crate) fn persist(&self) {
+        let fetched = self.fetched();
+        if fetched.is_empty() {
+            return;
+        }
+        let mut providers = self.cached.clone();
+        providers.extend(fetched.iter().map(|(name, models)| (name.clone(), models.clone())));
+        let provider_count = providers.len();
+        let result = serde_json::to_string_pretty(&CacheFile { providers })
+            .map_err(|error| error.to_string())
+            .and_then(|payload| {
+                let temp_path = self.path.with_extension("tmp");
+                fs::write(&temp_path, payload)
+                    .and_then(|()| fs::rename(&temp_path, &self.path))
+                    .map_err(|error| error.to_string())
+            });
+        match result {
+            Ok(()) => info!(
+                event = "models.catalog.cache.saved",
+                path = %self.path.display(),
+                provider_count = provider_count
+            ),
+            Err(error) => warn!(
+                event = "models.catalog.cache.save_failed",
+                path = %self.path.display(),
+                error = %error
+            ),
+        }
+    }
+
+    fn fetched(&self) -> MutexGuard<'_, BTreeMap<String, Vec<CachedModel>>> {
+        self.fetched.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use xrouter_core::default_model_catalog;
+
+    use super::ModelCatalogCache;
+
+    fn cache_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("xrouter-model-cache-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn persisted_fetches_are_served_after_reopening() {
+        let path = cache_path();
+        let models = default_model_catalog()
+            .into_iter()
+            .filter(|model| model.provider == "zai")
+            .collect::<Vec<_>>();
+        assert!(!models.is_empty());
+
+        let cache = ModelCatalogCache::open(path.clone());
+        assert_eq!(cache.models("zai"), None);
+        cache.record("zai", &models);
+        cache.record("yandex", &[]);
+        cache.persist();
+
+        let reopened = ModelCatalogCache::open(path.clone());
+        assert_eq!(reopened.models("zai"), Some(models));
+        assert_eq!(reopened.models("yandex"), None);
+        fs::remove_file(path).expect("cache file must be removed");
+    }
+
+    #[test]
+    fn invalid_cache_file_starts_empty() {
+        let path = cache_path();
+        fs::write(&path, "not json").expect("cache file must be written");
+
+        let cache = ModelCatalogCache::open(path.clone());
+
+        assert_eq!(cache.models("zai"), None);
+        fs::remove_file(path).expect("cache file must be removed");
+    }
+}
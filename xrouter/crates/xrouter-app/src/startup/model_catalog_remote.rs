@@ -1,19 +1,17 @@
-use std::{sync::Arc, time::Duration};
+use std::time::Duration;
 
+use reqwest::Client;
 use serde::Deserialize;
 use tracing::warn;
-use ureq::rustls::{
-    self,
-    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
-    pki_types::{CertificateDer, ServerName, UnixTime},
-};
 use xrouter_clients_openai::model_discovery::{
-    HttpFormRequest, HttpJsonRequest, build_gigachat_models_request, build_gigachat_oauth_request,
-    build_openrouter_models_request, build_provider_models_request, build_xrouter_models_request,
+    HttpFormRequest, HttpJsonRequest, build_cloudflare_models_request,
+    build_gigachat_models_request, build_gigachat_oauth_request, build_openrouter_models_request,
+    build_provider_models_request, build_tgi_info_request, build_xrouter_models_request,
 };
 use xrouter_clients_openai::models::{
-    OpenRouterModelsResponse, ProviderModelsResponse, XrouterProviderModelsResponse,
-    extract_provider_model_ids, map_openrouter_models, map_xrouter_models,
+    CloudflareModelsResponse, OpenRouterModelsResponse, ProviderModelsResponse, TgiInfoResponse,
+    XrouterProviderModelsResponse, extract_provider_model_ids, map_cloudflare_models,
+    map_openrouter_models, map_tgi_info, map_xrouter_models,
 };
 use xrouter_core::ModelDescriptor;
 
@@ -27,101 +25,48 @@ struct GigachatOauthResponse {
 
 const GIGACHAT_SCOPE: &str = "GIGACHAT_API_PERS";
 
-#[derive(Debug)]
-struct AcceptAllCerts;
-
-impl ServerCertVerifier for AcceptAllCerts {
-    fn verify_server_cert(
-        &self,
-        _end_entity: &CertificateDer<'_>,
-        _intermediates: &[CertificateDer<'_>],
-        _server_name: &ServerName<'_>,
-        _ocsp_response: &[u8],
-        _now: UnixTime,
-    ) -> Result<ServerCertVerified, rustls::Error> {
-        Ok(ServerCertVerified::assertion())
-    }
-
-    fn verify_tls12_signature(
-        &self,
-        _message: &[u8],
-        _cert: &CertificateDer<'_>,
-        _dss: &rustls::DigitallySignedStruct,
-    ) -> Result<HandshakeSignatureValid, rustls::Error> {
-        Ok(HandshakeSignatureValid::assertion())
-    }
-
-    fn verify_tls13_signature(
-        &self,
-        _message: &[u8],
-        _cert: &CertificateDer<'_>,
-        _dss: &rustls::DigitallySignedStruct,
-    ) -> Result<HandshakeSignatureValid, rustls::Error> {
-        Ok(HandshakeSignatureValid::assertion())
-    }
-
-    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
-        vec![
-            rustls::SignatureScheme::ECDSA_NISTP256_SHA256,
-            rustls::SignatureScheme::ECDSA_NISTP384_SHA384,
-            rustls::SignatureScheme::ECDSA_NISTP521_SHA512,
-            rustls::SignatureScheme::ED25519,
-            rustls::SignatureScheme::ED448,
-            rustls::SignatureScheme::RSA_PSS_SHA256,
-            rustls::SignatureScheme::RSA_PSS_SHA384,
-            rustls::SignatureScheme::RSA_PSS_SHA512,
-            rustls::SignatureScheme::RSA_PKCS1_SHA256,
-            rustls::SignatureScheme::RSA_PKCS1_SHA384,
-            rustls::SignatureScheme::RSA_PKCS1_SHA512,
-        ]
-    }
-}
-
-fn gigachat_ureq_agent(connect_timeout_seconds: u64, insecure_tls: bool) -> ureq::Agent {
-    let builder =
-        ureq::AgentBuilder::new().timeout_connect(Duration::from_secs(connect_timeout_seconds));
-    if insecure_tls {
-        let tls_config = rustls::ClientConfig::builder()
-            .dangerous()
-            .with_custom_certificate_verifier(Arc::new(AcceptAllCerts))
-            .with_no_client_auth();
-        builder.tls_config(Arc::new(tls_config)).build()
-    } else {
-        builder.build()
-    }
+/// Client for catalog requests: `fetch_timeout_seconds` bounds each request, connect included.
+pub(crate) fn catalog_http_client(
+    connect_timeout_seconds: u64,
+    fetch_timeout_seconds: u64,
+    insecure_tls: bool,
+) -> Client {
+    Client::builder()
+        .connect_timeout(Duration::from_secs(connect_timeout_seconds))
+        .timeout(Duration::from_secs(fetch_timeout_seconds))
+        .danger_accept_invalid_certs(insecure_tls)
+        .build()
+        .unwrap_or_default()
 }
 
-pub(crate) fn fetch_openrouter_models(
+pub(crate) async fn fetch_openrouter_models(
+    client: &Client,
     provider_config: &config::ProviderConfig,
     supported_ids: &[String],
-    connect_timeout_seconds: u64,
 ) -> Option<Vec<ModelDescriptor>> {
     let request = build_openrouter_models_request(
         provider_config.base_url.as_deref(),
         provider_config.api_key.as_deref(),
     )?;
     let payload = fetch_json::<OpenRouterModelsResponse>(
+        client,
         request,
-        connect_timeout_seconds,
         "openrouter.models.fetch.failed",
         None,
-    )?;
+    )
+    .await?;
 
     Some(map_openrouter_models(payload, supported_ids))
 }
 
-pub(crate) fn fetch_provider_model_ids(
+/// For `gigachat`, `client` must be the one built with `GIGACHAT_INSECURE_TLS` applied.
+pub(crate) async fn fetch_provider_model_ids(
+    client: &Client,
     provider_name: &str,
     provider_config: &config::ProviderConfig,
-    connect_timeout_seconds: u64,
-    gigachat_insecure_tls: bool,
 ) -> Option<Vec<String>> {
     if provider_name == "gigachat" {
-        return fetch_gigachat_model_ids(
-            provider_config,
-            connect_timeout_seconds,
-            gigachat_insecure_tls,
-        );
+        return fetch_gigachat_model_ids(client, provider_config).await;
     }
 
     let request = build_provider_models_request(
@@ -131,130 +76,145 @@ pub(crate) fn fetch_provider_model_ids(
         provider_config.project.as_deref(),
     )?;
     let payload = fetch_json::<ProviderModelsResponse>(
+        client,
         request,
-        connect_timeout_seconds,
         "provider.models.fetch.failed",
         Some(provider_name),
-    )?;
+    )
+    .await?;
     Some(extract_provider_model_ids(payload))
 }
 
-pub(crate) fn fetch_xrouter_models(
+pub(crate) async fn fetch_xrouter_models(
+    client: &Client,
     provider_config: &config::ProviderConfig,
-    connect_timeout_seconds: u64,
 ) -> Option<Vec<ModelDescriptor>> {
     let request = build_xrouter_models_request(
         provider_config.base_url.as_deref(),
         provider_config.api_key.as_deref(),
     )?;
     let payload = fetch_json::<XrouterProviderModelsResponse>(
+        client,
         request,
-        connect_timeout_seconds,
         "xrouter.models.fetch.failed",
         None,
-    )?;
+    )
+    .await?;
     Some(map_xrouter_models(payload))
 }
 
-fn fetch_gigachat_access_token(
+/// The single model a TGI server or Hugging Face Inference Endpoint serves, from `/info`.
+pub(crate) async fn fetch_tgi_models(
+    client: &Client,
+    provider_config: &config::ProviderConfig,
+) -> Option<Vec<ModelDescriptor>> {
+    let request = build_tgi_info_request(
+        provider_config.base_url.as_deref(),
+        provider_config.api_key.as_deref(),
+    )?;
+    let info =
+        fetch_json::<TgiInfoResponse>(client, request, "provider.models.fetch.failed", Some("tgi"))
+            .await?;
+    Some(vec![map_tgi_info(info)])
+}
+
+/// Workers AI text generation models of `account_id`.
+pub(crate) async fn fetch_cloudflare_models(
+    client: &Client,
+    provider_config: &config::ProviderConfig,
+    account_id: Option<&str>,
+) -> Option<Vec<ModelDescriptor>> {
+    let request = build_cloudflare_models_request(
+        provider_config.base_url.as_deref(),
+        account_id,
+        provider_config.api_key.as_deref(),
+    )?;
+    let payload = fetch_json::<CloudflareModelsResponse>(
+        client,
+        request,
+        "provider.models.fetch.failed",
+        Some("cloudflare"),
+    )
+    .await?;
+    Some(map_cloudflare_models(payload))
+}
+
+async fn fetch_gigachat_access_token(
+    client: &Client,
     provider_config: &config::ProviderConfig,
-    connect_timeout_seconds: u64,
-    insecure_tls: bool,
 ) -> Option<String> {
     let api_key = provider_config.api_key.as_deref().filter(|v| !v.trim().is_empty())?;
-    let agent = gigachat_ureq_agent(connect_timeout_seconds, insecure_tls);
     let request_id = uuid::Uuid::new_v4().to_string();
     let request = build_gigachat_oauth_request(api_key, &request_id, GIGACHAT_SCOPE);
     fetch_form_json::<GigachatOauthResponse>(
-        &agent,
+        client,
         request,
         "provider.oauth.fetch.failed",
         Some("gigachat"),
     )
+    .await
     .map(|payload| payload.access_token)
 }
 
-fn fetch_gigachat_model_ids(
+async fn fetch_gigachat_model_ids(
+    client: &Client,
     provider_config: &config::ProviderConfig,
-    connect_timeout_seconds: u64,
-    insecure_tls: bool,
 ) -> Option<Vec<String>> {
-    let access_token =
-        fetch_gigachat_access_token(provider_config, connect_timeout_seconds, insecure_tls)?;
-    let agent = gigachat_ureq_agent(connect_timeout_seconds, insecure_tls);
+    let access_token = fetch_gigachat_access_token(client, provider_config).await?;
     let request =
         build_gigachat_models_request(provider_config.base_url.as_deref(), &access_token)?;
-    let payload = fetch_json_with_agent::<ProviderModelsResponse>(
-        &agent,
+    let payload = fetch_json::<ProviderModelsResponse>(
+        client,
         request,
         "provider.models.fetch.failed",
         Some("gigachat"),
-    )?;
+    )
+    .await?;
     Some(extract_provider_model_ids(payload))
 }
 
-fn fetch_json<T: serde::de::DeserializeOwned>(
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    client: &Client,
     request: HttpJsonRequest,
-    connect_timeout_seconds: u64,
     event: &'static str,
     provider: Option<&str>,
 ) -> Option<T> {
-    let agent = ureq::AgentBuilder::new()
-        .timeout_connect(Duration::from_secs(connect_timeout_seconds))
-        .build();
-    fetch_json_with_agent(&agent, request, event, provider)
+    let mut call = client.get(request.url.as_str());
+    for (name, value) in &request.headers {
+        call = call.header(name, value);
+    }
+    read_json(call, event, provider).await
 }
 
-fn fetch_json_with_agent<T: serde::de::DeserializeOwned>(
-    agent: &ureq::Agent,
-    request: HttpJsonRequest,
+async fn fetch_form_json<T: serde::de::DeserializeOwned>(
+    client: &Client,
+    request: HttpFormRequest,
     event: &'static str,
     provider: Option<&str>,
 ) -> Option<T> {
-    let mut call = agent.get(request.url.as_str());
+    let mut call = client.post(request.url.as_str());
     for (name, value) in &request.headers {
-        call = call.set(name, value);
-    }
-    match call.call() {
-        Ok(ok) => match ok.into_json::<T>() {
-            Ok(payload) => Some(payload),
-            Err(err) => {
-                log_fetch_failure(event, provider, "invalid_json", &err.to_string());
-                None
-            }
-        },
-        Err(err) => {
-            log_fetch_failure(event, provider, "request_failed", &err.to_string());
-            None
-        }
+        call = call.header(name, value);
     }
+    read_json(call.form(&request.form_fields), event, provider).await
 }
 
-fn fetch_form_json<T: serde::de::DeserializeOwned>(
-    agent: &ureq::Agent,
-    request: HttpFormRequest,
+async fn read_json<T: serde::de::DeserializeOwned>(
+    call: reqwest::RequestBuilder,
     event: &'static str,
     provider: Option<&str>,
 ) -> Option<T> {
-    let mut call = agent.post(request.url.as_str());
-    for (name, value) in &request.headers {
-        call = call.set(name, value);
-    }
-    let form_fields = request
-        .form_fields
-        .iter()
-        .map(|(name, value)| (name.as_str(), value.as_str()))
-        .collect::<Vec<_>>();
-    match call.send_form(&form_fields) {
-        Ok(ok) => match ok.into_json::<T>() {
-            Ok(payload) => Some(payload),
-            Err(err) => {
-                log_fetch_failure(event, provider, "invalid_json", &err.to_string());
-                None
-            }
-        },
+    let response = match call.send().await.and_then(reqwest::Response::error_for_status) {
+        Ok(response) => response,
         Err(err) => {
             log_fetch_failure(event, provider, "request_failed", &err.to_string());
+            return None;
+        }
+    };
+    match response.json::<T>().await {
+        Ok(payload) => Some(payload),
+        Err(err) => {
+            log_fetch_failure(event, provider, "invalid_json", &err.to_string());
             None
         }
     }
@@ -1,18 +1,35 @@
 use std::collections::HashSet;
 
+use async_trait::async_trait;
+use reqwest::Client;
 use tracing::{info, warn};
 use xrouter_clients_openai::models::{build_models_from_registry, fallback_openrouter_models};
 use xrouter_core::ModelDescriptor;
 
 use crate::{
     config,
-    startup::model_catalog_remote::{
-        fetch_openrouter_models, fetch_provider_model_ids, fetch_xrouter_models,
+    startup::{
+        model_catalog_cache::ModelCatalogCache,
+        model_catalog_remote::{
+            fetch_cloudflare_models, fetch_openrouter_models, fetch_provider_model_ids,
+            fetch_tgi_models, fetch_xrouter_models,
+        },
     },
 };
 
-pub(crate) trait ModelCatalogSource {
-    fn load_models(
+/// A provider whose models are fetched from its upstream catalog.
+#[async_trait]
+pub(crate) trait ModelCatalogSource: Sync {
+    fn provider(&self) -> &'static str;
+
+    async fn load_models(
+        &self,
+        context: &ModelCatalogContext<'_>,
+        registry_seed: &[ModelDescriptor],
+    ) -> Vec<ModelDescriptor>;
+
+    /// Models served when the upstream catalog cannot be fetched.
+    fn fallback_models(
         &self,
         context: &ModelCatalogContext<'_>,
         registry_seed: &[ModelDescriptor],
@@ -23,12 +40,36 @@ pub(crate) struct ModelCatalogContext<'a> {
     pub(crate) config: &'a config::AppConfig,
     pub(crate) enabled_providers: &'a HashSet<String>,
     pub(crate) test_mode: bool,
+    pub(crate) http_client: Client,
+    /// Same as `http_client`, with `GIGACHAT_INSECURE_TLS` applied.
+    pub(crate) gigachat_http_client: Client,
+    pub(crate) cache: Option<ModelCatalogCache>,
+}
+
+impl ModelCatalogContext<'_> {
+    pub(crate) fn record_fetched(&self, provider: &str, models: &[ModelDescriptor]) {
+        if let Some(cache) = &self.cache {
+            cache.record(provider, models);
+        }
+    }
+
+    /// Models of the last successful fetch, for a provider whose catalog is unavailable now.
+    pub(crate) fn cached_models(&self, provider: &str) -> Option<Vec<ModelDescriptor>> {
+        let models = self.cache.as_ref()?.models(provider)?;
+        warn!(
+            event = "provider.models.loaded",
+            provider = provider,
+            source = "cache",
+            model_count = models.len()
+        );
+        Some(models)
+    }
 }
 
 pub(crate) struct BaseCatalogSource;
 
-impl ModelCatalogSource for BaseCatalogSource {
-    fn load_models(
+impl BaseCatalogSource {
+    pub(crate) fn load_models(
         &self,
         context: &ModelCatalogContext<'_>,
         registry_seed: &[ModelDescriptor],
@@ -42,6 +83,9 @@ impl ModelCatalogSource for BaseCatalogSource {
                     && entry.provider != "yandex"
                     && entry.provider != "gigachat"
                     && entry.provider != "xrouter"
+                    && entry.provider != "tgi"
+                    && entry.provider != "selfhosted"
+                    && entry.provider != "cloudflare"
             })
             .cloned()
             .collect()
@@ -50,11 +94,16 @@ impl ModelCatalogSource for BaseCatalogSource {
 
 pub(crate) struct OpenRouterCatalogSource;
 
+#[async_trait]
 impl ModelCatalogSource for OpenRouterCatalogSource {
-    fn load_models(
+    fn provider(&self) -> &'static str {
+        "openrouter"
+    }
+
+    async fn load_models(
         &self,
         context: &ModelCatalogContext<'_>,
-        _registry_seed: &[ModelDescriptor],
+        registry_seed: &[ModelDescriptor],
     ) -> Vec<ModelDescriptor> {
         if !context.enabled_providers.contains("openrouter") {
             return Vec::new();
@@ -64,21 +113,27 @@ impl ModelCatalogSource for OpenRouterCatalogSource {
         };
 
         if context.test_mode {
-            return fallback_openrouter_models(&context.config.openrouter_supported_models);
+            return self.fallback_models(context, registry_seed);
         }
 
         if let Some(fetched) = fetch_openrouter_models(
+            &context.http_client,
             openrouter_config,
             &context.config.openrouter_supported_models,
-            context.config.provider_timeout_seconds,
-        ) {
+        )
+        .await
+        {
             info!(
                 event = "openrouter.models.loaded",
                 source = "remote",
                 model_count = fetched.len()
             );
+            context.record_fetched(self.provider(), &fetched);
             return fetched;
         }
+        if let Some(cached) = context.cached_models(self.provider()) {
+            return cached;
+        }
 
         warn!(
             event = "openrouter.models.loaded",
@@ -86,6 +141,14 @@ impl ModelCatalogSource for OpenRouterCatalogSource {
             reason = "fetch_failed",
             model_count = context.config.openrouter_supported_models.len()
         );
+        self.fallback_models(context, registry_seed)
+    }
+
+    fn fallback_models(
+        &self,
+        context: &ModelCatalogContext<'_>,
+        _registry_seed: &[ModelDescriptor],
+    ) -> Vec<ModelDescriptor> {
         fallback_openrouter_models(&context.config.openrouter_supported_models)
     }
 }
@@ -100,8 +163,13 @@ impl RegistryBackedCatalogSource {
     }
 }
 
+#[async_trait]
 impl ModelCatalogSource for RegistryBackedCatalogSource {
-    fn load_models(
+    fn provider(&self) -> &'static str {
+        self.provider
+    }
+
+    async fn load_models(
         &self,
         context: &ModelCatalogContext<'_>,
         registry_seed: &[ModelDescriptor],
@@ -113,20 +181,14 @@ impl ModelCatalogSource for RegistryBackedCatalogSource {
             return Vec::new();
         };
 
-        if context.test_mode {
-            return registry_seed
-                .iter()
-                .filter(|model| model.provider == self.provider)
-                .cloned()
-                .collect();
+        // Providers without a default base URL, like `selfhosted`, have nothing to fetch from.
+        if context.test_mode || provider_config.base_url.is_none() {
+            return self.fallback_models(context, registry_seed);
         }
 
-        if let Some(model_ids) = fetch_provider_model_ids(
-            self.provider,
-            provider_config,
-            context.config.provider_timeout_seconds,
-            context.config.gigachat_insecure_tls,
-        ) {
+        if let Some(model_ids) =
+            fetch_provider_model_ids(&context.http_client, self.provider, provider_config).await
+        {
             let models = build_models_from_registry(self.provider, &model_ids, registry_seed);
             info!(
                 event = "provider.models.loaded",
@@ -134,8 +196,12 @@ impl ModelCatalogSource for RegistryBackedCatalogSource {
                 source = "remote",
                 model_count = models.len()
             );
+            context.record_fetched(self.provider, &models);
             return models;
         }
+        if let Some(cached) = context.cached_models(self.provider) {
+            return cached;
+        }
 
         warn!(
             event = "provider.models.loaded",
@@ -143,14 +209,27 @@ impl ModelCatalogSource for RegistryBackedCatalogSource {
             source = "fallback",
             reason = "fetch_failed"
         );
+        self.fallback_models(context, registry_seed)
+    }
+
+    fn fallback_models(
+        &self,
+        _context: &ModelCatalogContext<'_>,
+        registry_seed: &[ModelDescriptor],
+    ) -> Vec<ModelDescriptor> {
         registry_seed.iter().filter(|model| model.provider == self.provider).cloned().collect()
     }
 }
 
 pub(crate) struct GigachatCatalogSource;
 
+#[async_trait]
 impl ModelCatalogSource for GigachatCatalogSource {
-    fn load_models(
+    fn provider(&self) -> &'static str {
+        "gigachat"
+    }
+
+    async fn load_models(
         &self,
         context: &ModelCatalogContext<'_>,
         registry_seed: &[ModelDescriptor],
@@ -170,12 +249,10 @@ impl ModelCatalogSource for GigachatCatalogSource {
                 .collect();
         }
 
-        if let Some(gigachat_model_ids) = fetch_provider_model_ids(
-            "gigachat",
-            gigachat_config,
-            context.config.provider_timeout_seconds,
-            context.config.gigachat_insecure_tls,
-        ) {
+        if let Some(gigachat_model_ids) =
+            fetch_provider_model_ids(&context.gigachat_http_client, "gigachat", gigachat_config)
+                .await
+        {
             let supported = context
                 .config
                 .gigachat_supported_models
@@ -193,22 +270,39 @@ impl ModelCatalogSource for GigachatCatalogSource {
                 model_count = models.len(),
                 configured_count = context.config.gigachat_supported_models.len()
             );
+            context.record_fetched(self.provider(), &models);
             return models;
         }
+        if let Some(cached) = context.cached_models(self.provider()) {
+            return cached;
+        }
 
         warn!(
             event = "gigachat.models.loaded",
             source = "none",
             reason = "fetch_failed_no_fallback"
         );
+        self.fallback_models(context, registry_seed)
+    }
+
+    fn fallback_models(
+        &self,
+        _context: &ModelCatalogContext<'_>,
+        _registry_seed: &[ModelDescriptor],
+    ) -> Vec<ModelDescriptor> {
         Vec::new()
     }
 }
 
 pub(crate) struct XrouterCatalogSource;
 
+#[async_trait]
 impl ModelCatalogSource for XrouterCatalogSource {
-    fn load_models(
+    fn provider(&self) -> &'static str {
+        "xrouter"
+    }
+
+    async fn load_models(
         &self,
         context: &ModelCatalogContext<'_>,
         registry_seed: &[ModelDescriptor],
@@ -221,25 +315,145 @@ impl ModelCatalogSource for XrouterCatalogSource {
         };
 
         if context.test_mode {
-            return registry_seed
-                .iter()
-                .filter(|model| model.provider == "xrouter")
-                .cloned()
-                .collect();
+            return self.fallback_models(context, registry_seed);
         }
 
         if let Some(xrouter_models) =
-            fetch_xrouter_models(xrouter_config, context.config.provider_timeout_seconds)
+            fetch_xrouter_models(&context.http_client, xrouter_config).await
         {
             info!(
                 event = "xrouter.models.loaded",
                 source = "remote",
                 model_count = xrouter_models.len()
             );
+            context.record_fetched(self.provider(), &xrouter_models);
             return xrouter_models;
         }
+        if let Some(cached) = context.cached_models(self.provider()) {
+            return cached;
+        }
 
         warn!(event = "xrouter.models.loaded", source = "fallback", reason = "fetch_failed");
+        self.fallback_models(context, registry_seed)
+    }
+
+    fn fallback_models(
+        &self,
+        _context: &ModelCatalogContext<'_>,
+        registry_seed: &[ModelDescriptor],
+    ) -> Vec<ModelDescriptor> {
         registry_seed.iter().filter(|model| model.provider == "xrouter").cloned().collect()
     }
 }
+
+pub(crate) struct TgiCatalogSource;
+
+#[async_trait]
+impl ModelCatalogSource for TgiCatalogSource {
+    fn provider(&self) -> &'static str {
+        "tgi"
+    }
+
+    async fn load_models(
+        &self,
+        context: &ModelCatalogContext<'_>,
+        registry_seed: &[ModelDescriptor],
+    ) -> Vec<ModelDescriptor> {
+        let Some(tgi_config) = context.config.providers.get("tgi") else {
+            return Vec::new();
+        };
+        if context.test_mode || tgi_config.base_url.is_none() {
+            return self.fallback_models(context, registry_seed);
+        }
+
+        if let Some(models) = fetch_tgi_models(&context.http_client, tgi_config).await {
+            info!(
+                event = "provider.models.loaded",
+                provider = "tgi",
+                source = "remote",
+                model_count = models.len()
+            );
+            context.record_fetched(self.provider(), &models);
+            return models;
+        }
+        if let Some(cached) = context.cached_models(self.provider()) {
+            return cached;
+        }
+
+        warn!(
+            event = "provider.models.loaded",
+            provider = "tgi",
+            source = "fallback",
+            reason = "fetch_failed"
+        );
+        self.fallback_models(context, registry_seed)
+    }
+
+    /// `/info` is the only source of the served model id, so there is nothing to fall back to.
+    fn fallback_models(
+        &self,
+        _context: &ModelCatalogContext<'_>,
+        _registry_seed: &[ModelDescriptor],
+    ) -> Vec<ModelDescriptor> {
+        Vec::new()
+    }
+}
+
+pub(crate) struct CloudflareCatalogSource;
+
+#[async_trait]
+impl ModelCatalogSource for CloudflareCatalogSource {
+    fn provider(&self) -> &'static str {
+        "cloudflare"
+    }
+
+    async fn load_models(
+        &self,
+        context: &ModelCatalogContext<'_>,
+        registry_seed: &[ModelDescriptor],
+    ) -> Vec<ModelDescriptor> {
+        let Some(cloudflare_config) = context.config.providers.get("cloudflare") else {
+            return Vec::new();
+        };
+        if context.test_mode || context.config.cloudflare_account_id.is_none() {
+            return self.fallback_models(context, registry_seed);
+        }
+
+        if let Some(models) = fetch_cloudflare_models(
+            &context.http_client,
+            cloudflare_config,
+            context.config.cloudflare_account_id.as_deref(),
+        )
+        .await
+        .filter(|models| !models.is_empty())
+        {
+            info!(
+                event = "provider.models.loaded",
+                provider = "cloudflare",
+                source = "remote",
+                model_count = models.len()
+            );
+            context.record_fetched(self.provider(), &models);
+            return models;
+        }
+        if let Some(cached) = context.cached_models(self.provider()) {
+            return cached;
+        }
+
+        warn!(
+            event = "provider.models.loaded",
+            provider = "cloudflare",
+            source = "fallback",
+            reason = "fetch_failed"
+        );
+        self.fallback_models(context, registry_seed)
+    }
+
+    fn fallback_models(
+        &self,
+        _context: &ModelCatalogContext<'_>,
+        registry_seed: &[ModelDescriptor],
+    ) -> Vec<ModelDescriptor> {
+        registry_seed.iter().filter(|model| model.provider == "cloudflare").cloned().collect()
+    }
+}
@@ -1,25 +1,48 @@
 use std::{collections::HashMap, sync::Arc};
 
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
 use xrouter_clients_openai::{
-    DeepSeekClient, GigachatClient, MockProviderClient, OpenAiClient, OpenRouterClient,
-    XrouterClient, YandexResponsesClient, ZaiClient, build_http_client,
-    build_http_client_insecure_tls,
+    ChaosProviderClient, CloudflareClient, CohereClient, DeepSeekClient, GigachatClient,
+    MockProviderClient, OpenAiClient, OpenRouterClient, PerplexityClient, ProviderProxy,
+    RecordingProviderClient, ReplayProviderClient, SelfHostedClient, SharedChaosPolicy, TgiClient,
+    TranscriptStore, XrouterClient, YandexChatClient, YandexResponsesClient, ZaiClient,
+    build_http_client_insecure_tls, build_provider_http_client,
+};
+use xrouter_core::{
+    BlocklistModerationPolicy, ExecutionEngine, OutputModeration, ProviderClient, ScopedTransform,
+    build_builtin_transform,
 };
-use xrouter_core::{ExecutionEngine, ProviderClient};
 
-use crate::config;
+use crate::{
+    bandit::{BANDIT_ENGINE, BanditArm, BanditProviderClient, BanditRegistry},
+    config,
+    coordination::{CoordinatedProviderClient, RedisCoordinator},
+    racing::{RACE_ENGINE, RaceLeg, RacingProviderClient},
+    scheduling::{PrioritizedProviderClient, PriorityScheduler},
+};
 
-pub(crate) fn build_engines(config: &config::AppConfig) -> HashMap<String, Arc<ExecutionEngine>> {
+pub(crate) fn build_engines(
+    config: &config::AppConfig,
+    transcripts: Option<Arc<TranscriptStore>>,
+    chaos: Option<SharedChaosPolicy>,
+    coordinator: Option<Arc<RedisCoordinator>>,
+    scheduler: Option<&PriorityScheduler>,
+    bandits: Option<&Arc<BanditRegistry>>,
+) -> HashMap<String, Arc<ExecutionEngine>> {
     let mut engines = HashMap::new();
-    let shared_http_client =
-        if cfg!(test) { None } else { build_http_client(config.provider_timeout_seconds) };
+    let mut clients = HashMap::new();
 
     for (provider, provider_config) in &config.providers {
         if !provider_config.enabled {
             continue;
         }
 
+        let http_client = if cfg!(test) {
+            None
+        } else {
+            build_isolated_http_client(config.provider_timeout_seconds, provider, provider_config)
+        };
+
         let client: Arc<dyn ProviderClient> = if cfg!(test) {
             Arc::new(MockProviderClient::new(provider.to_string()))
         } else {
@@ -27,27 +50,61 @@ pub(crate) fn build_engines(config: &config::AppConfig) -> HashMap<String, Arc<E
                 "openrouter" => Arc::new(OpenRouterClient::new(
                     provider_config.base_url.clone(),
                     provider_config.api_key.clone(),
-                    shared_http_client.clone(),
+                    http_client.clone(),
                     Some(config.provider_max_inflight),
+                    transcripts.clone(),
                 )),
                 "deepseek" => Arc::new(DeepSeekClient::new(
                     provider_config.base_url.clone(),
                     provider_config.api_key.clone(),
-                    shared_http_client.clone(),
+                    http_client.clone(),
                     Some(config.provider_max_inflight),
+                    transcripts.clone(),
+                )),
+                "cohere" => Arc::new(CohereClient::new(
+                    provider_config.base_url.clone(),
+                    provider_config.api_key.clone(),
+                    http_client.clone(),
+                    Some(config.provider_max_inflight),
+                    transcripts.clone(),
+                )),
+                "perplexity" => Arc::new(PerplexityClient::new(
+                    provider_config.base_url.clone(),
+                    provider_config.api_key.clone(),
+                    http_client.clone(),
+                    Some(config.provider_max_inflight),
+                    transcripts.clone(),
+                )),
+                "cloudflare" => Arc::new(CloudflareClient::new(
+                    provider_config.base_url.clone(),
+                    provider_config.api_key.clone(),
+                    config.cloudflare_account_id.clone(),
+                    http_client.clone(),
+                    Some(config.provider_max_inflight),
+                    transcripts.clone(),
                 )),
                 "zai" => Arc::new(ZaiClient::new(
                     provider_config.base_url.clone(),
                     provider_config.api_key.clone(),
-                    shared_http_client.clone(),
+                    http_client.clone(),
+                    Some(config.provider_max_inflight),
+                    transcripts.clone(),
+                )),
+                "yandex" if config.yandex_chat_completions => Arc::new(YandexChatClient::new(
+                    provider_config.base_url.clone(),
+                    provider_config.api_key.clone(),
+                    provider_config.project.clone(),
+                    http_client.clone(),
                     Some(config.provider_max_inflight),
+                    transcripts.clone(),
                 )),
                 "yandex" => Arc::new(YandexResponsesClient::new(
                     provider_config.base_url.clone(),
                     provider_config.api_key.clone(),
                     provider_config.project.clone(),
-                    shared_http_client.clone(),
+                    http_client.clone(),
                     Some(config.provider_max_inflight),
+                    transcripts.clone(),
                 )),
                 "gigachat" => Arc::new(GigachatClient::new(
                     provider_config.base_url.clone(),
@@ -56,27 +113,70 @@ pub(crate) fn build_engines(config: &config::AppConfig) -> HashMap<String, Arc<E
                     if config.gigachat_insecure_tls {
                         build_http_client_insecure_tls(config.provider_timeout_seconds)
                     } else {
-                        shared_http_client.clone()
+                        http_client.clone()
                     },
                     Some(config.provider_max_inflight),
+                    transcripts.clone(),
                 )),
                 "xrouter" => Arc::new(XrouterClient::new(
                     provider_config.base_url.clone(),
                     provider_config.api_key.clone(),
-                    shared_http_client.clone(),
+                    http_client.clone(),
+                    Some(config.provider_max_inflight),
+                    transcripts.clone(),
+                )),
+                "tgi" => Arc::new(TgiClient::new(
+                    provider_config.base_url.clone(),
+                    provider_config.api_key.clone(),
+                    config.tgi_native_api,
+                    http_client.clone(),
                     Some(config.provider_max_inflight),
+                    transcripts.clone(),
+                )),
+                "selfhosted" => Arc::new(SelfHostedClient::new(
+                    provider_config.base_url.clone(),
+                    provider_config.api_key.clone(),
+                    config.selfhosted_quirks,
+                    http_client.clone(),
+                    Some(config.provider_max_inflight),
+                    transcripts.clone(),
                 )),
                 _ => Arc::new(OpenAiClient::new(
                     provider.to_string(),
                     provider_config.base_url.clone(),
                     provider_config.api_key.clone(),
-                    shared_http_client.clone(),
+                    http_client.clone(),
                     Some(config.provider_max_inflight),
+                    transcripts.clone(),
                 )),
             }
         };
 
-        engines.insert(provider.to_string(), Arc::new(ExecutionEngine::new(client)));
+        let mut client = wrap_fixtures(&config.provider_fixtures, provider, client);
+        if let Some(policy) = &chaos {
+            client =
+                Arc::new(ChaosProviderClient::new(provider.to_string(), client, policy.clone()));
+        }
+        if let Some(coordinator) = &coordinator {
+            client = Arc::new(CoordinatedProviderClient::new(
+                provider.to_string(),
+                client,
+                Arc::clone(coordinator),
+                config.provider_max_inflight,
+            ));
+        }
+        // Outermost, so requests queue by class before taking any in-flight slot.
+        if let Some(scheduler) = scheduler {
+            client = Arc::new(PrioritizedProviderClient::new(client, scheduler.gate(provider)));
+        }
+        clients.insert(provider.as_str(), Arc::clone(&client));
+        engines.insert(provider.to_string(), build_engine(config, provider, client));
+    }
+    if let Some(racing) = build_racing_client(config, &clients) {
+        engines.insert(RACE_ENGINE.to_string(), build_engine(config, RACE_ENGINE, racing));
+    }
+    if let Some(bandit) = bandits.and_then(|registry| build_bandit_client(registry, &clients)) {
+        engines.insert(BANDIT_ENGINE.to_string(), build_engine(config, BANDIT_ENGINE, bandit));
     }
 
     info!(event = "app.engines.initialized", engine_count = engines.len());
@@ -86,3 +186,249 @@ pub(crate) fn build_engines(config: &config::AppConfig) -> HashMap<String, Arc<E
     );
     engines
 }
+
+pub(crate) fn build_transcript_store(config: &config::AppConfig) -> Option<Arc<TranscriptStore>> {
+    let dir = config.stream_transcript_dir.as_ref()?;
+    match TranscriptStore::open(dir, config.stream_transcript_max_entries) {
+        Ok(store) => {
+            warn!(
+                event = "app.stream_transcripts.enabled",
+                dir = %dir.display(),
+                max_entries = config.stream_transcript_max_entries
+            );
+            Some(Arc::new(store))
+        }
+        Err(error) => {
+            error!(
+                event = "app.stream_transcripts.open_failed",
+                dir = %dir.display(),
+                error = %error
+            );
+            None
+        }
+    }
+}
+
+fn wrap_fixtures(
+    fixtures: &config::ProviderFixturesConfig,
+    provider: &str,
+    client: Arc<dyn ProviderClient>,
+) -> Arc<dyn ProviderClient> {
+    match fixtures {
+        config::ProviderFixturesConfig::Off => client,
+        config::ProviderFixturesConfig::Record { dir } => {
+            warn!(event = "app.provider.fixtures_recording", provider = %provider, dir = %dir.display());
+            Arc::new(RecordingProviderClient::new(provider.to_string(), client, dir.clone()))
+        }
+        config::ProviderFixturesConfig::Replay { dir } => {
+            warn!(event = "app.provider.fixtures_replaying", provider = %provider, dir = %dir.display());
+            Arc::new(ReplayProviderClient::new(provider.to_string(), dir.clone()))
+        }
+    }
+}
+
+fn build_isolated_http_client(
+    timeout_seconds: u64,
+    provider: &str,
+    provider_config: &config::ProviderConfig,
+) -> Option<reqwest::Client> {
+    let http = &provider_config.http;
+    match build_provider_http_client(timeout_seconds, http) {
+        Ok(client) => {
+            info!(
+                event = "app.provider.http_client_configured",
+                provider = %provider,
+                proxy = match http.proxy {
+                    Some(ProviderProxy::Url(_)) => "url",
+                    Some(ProviderProxy::Direct) => "direct",
+                    None => "default",
+                },
+                custom_ca = http.tls.ca_bundle_path.is_some(),
+                client_cert = http.tls.client_cert_path.is_some(),
+                min_tls_version = ?http.tls.min_tls_version,
+                pool_max_idle_per_host = ?http.pool.max_idle_per_host,
+                pool_idle_timeout_seconds = ?http.pool.idle_timeout_seconds,
+                http2_keep_alive_interval_seconds = ?http.pool.http2_keep_alive_interval_seconds,
+                tcp_nodelay = ?http.pool.tcp_nodelay
+            );
+            Some(client)
+        }
+        Err(error) => {
+            error!(event = "app.provider.http_client_failed", provider = %provider, error = %error);
+            None
+        }
+    }
+}
+
+fn build_engine(
+    config: &config::AppConfig,
+    provider: &str,
+    client: Arc<dyn ProviderClient>,
+) -> Arc<ExecutionEngine> {
+    let mut engine = ExecutionEngine::new(client)
+        .with_transforms(build_transforms(config, provider))
+        .with_required_tool_choice_emulation(config.tool_choice_required_emulation);
+    if let Some(moderation) = build_output_moderation(config) {
+        engine = engine.with_output_moderation(moderation);
+    }
+    Arc::new(engine)
+}
+
+/// Races over the fully decorated provider clients; aliases left with fewer than two enabled
+/// providers are dropped.
+fn build_racing_client(
+    config: &config::AppConfig,
+    clients: &HashMap<&str, Arc<dyn ProviderClient>>,
+) -> Option<Arc<dyn ProviderClient>> {
+    let mut races = HashMap::new();
+    for (alias, models) in &config.race_models {
+        let legs = models
+            .iter()
+            .filter_map(|model| {
+                let (provider, provider_model) = model.split_once('/')?;
+                let Some(client) = clients.get(provider) else {
+                    warn!(event = "app.race.leg_disabled", race_model = %alias, model = %model);
+                    return None;
+                };
+                Some(RaceLeg::new(provider, provider_model, Arc::clone(client)))
+            })
+            .collect::<Vec<_>>();
+        if legs.len() < 2 {
+            warn!(event = "app.race.skipped", race_model = %alias, legs = legs.len());
+            continue;
+        }
+        races.insert(alias.clone(), legs);
+    }
+    if races.is_empty() {
+        return None;
+    }
+    info!(event = "app.race.initialized", race_models = races.len());
+    Some(Arc::new(RacingProviderClient::new(races)))
+}
+
+/// Bandit arms over the fully decorated provider clients, in the registry's model order.
+fn build_bandit_client(
+    registry: &Arc<BanditRegistry>,
+    clients: &HashMap<&str, Arc<dyn ProviderClient>>,
+) -> Option<Arc<dyn ProviderClient>> {
+    let arms = registry
+        .models()
+        .into_iter()
+        .map(|(alias, models)| {
+            let arms = models
+                .iter()
+                .filter_map(|model| {
+                    let (provider, provider_model) = model.split_once('/')?;
+                    let client = clients.get(provider)?;
+                    Some(BanditArm::new(provider, provider_model, Arc::clone(client)))
+                })
+                .collect::<Vec<_>>();
+            (alias, arms)
+        })
+        .collect::<HashMap<_, _>>();
+    if arms.is_empty() {
+        return None;
+    }
+    Some(Arc::new(BanditProviderClient::new(arms, Arc::clone(registry))))
+}
+
+fn build_output_moderation(config: &config::AppConfig) -> Option<OutputModeration> {
+    if config.output_moderation_blocklist.is_empty() {
+        return None;
+    }
+    Some(OutputModeration::new(
+        Arc::new(BlocklistModerationPolicy::new(config.output_moderation_blocklist.clone())),
+        config.output_moderation_message.clone(),
+        config.output_moderation_buffer_stream,
+    ))
+}
+
+fn build_transforms(config: &config::AppConfig, provider: &str) -> Vec<ScopedTransform> {
+    config
+        .transforms
+        .iter()
+        .filter(|entry| entry.provider.as_deref().is_none_or(|scoped| scoped == provider))
+        .filter_map(|entry| match build_builtin_transform(&entry.name, &entry.params) {
+            Ok(transform) => {
+                let model = entry.model.as_deref().map(|model| {
+                    model.strip_prefix(&format!("{provider}/")).unwrap_or(model).to_string()
+                });
+                info!(
+                    event = "app.transform.registered",
+                    provider = %provider,
+                    transform = %entry.name,
+                    model = model.as_deref().unwrap_or("*")
+                );
+                Some(ScopedTransform::new(transform, model))
+            }
+            Err(error) => {
+                warn!(
+                    event = "app.transform.skipped",
+                    provider = %provider,
+                    transform = %entry.name,
+                    error = %error
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use xrouter_contracts::{ResponseOutputItem, ResponsesRequest};
+
+    use super::build_engines;
+    use crate::config::{AppConfig, TransformConfig};
+
+    fn transform(name: &str, provider: Option<&str>, model: Option<&str>) -> TransformConfig {
+        let params = serde_json::json!({"patterns": ["secret"], "replacement": "***"});
+        TransformConfig {
+            name: name.to_string(),
+            provider: provider.map(ToString::to_string),
+            model: model.map(ToString::to_string),
+            params: params.as_object().cloned().expect("params must be an object"),
+        }
+    }
+
+    async fn output_text(config: &AppConfig, provider: &str, model: &str) -> String {
+        let engines = build_engines(config, None, None, None, None, None);
+        let request: ResponsesRequest =
+            serde_json::from_value(serde_json::json!({"model": model, "input": "my secret"}))
+                .expect("request must parse");
+        let response = engines[provider].execute(request).await.expect("request must succeed");
+        response
+            .output
+            .into_iter()
+            .find_map(|item| match item {
+                ResponseOutputItem::Message { content, .. } => {
+                    content.into_iter().next().map(|part| part.text)
+                }
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn build_engines_attaches_transforms_by_provider_and_prefixed_model() {
+        let mut config = AppConfig::for_tests();
+        config.transforms =
+            vec![transform("redact", Some("deepseek"), Some("deepseek/deepseek-chat"))];
+
+        assert_eq!(output_text(&config, "deepseek", "deepseek-chat").await, "[deepseek] my *** ");
+        assert_eq!(
+            output_text(&config, "deepseek", "deepseek-reasoner").await,
+            "[deepseek] my secret "
+        );
+        assert_eq!(output_text(&config, "zai", "deepseek-chat").await, "[zai] my secret ");
+    }
+
+    #[tokio::test]
+    async fn build_engines_attaches_output_moderation_when_blocklist_is_set() {
+        let mut config = AppConfig::for_tests();
+        config.output_moderation_blocklist = vec!["secret".to_string()];
+        config.output_moderation_message = "withheld".to_string();
+
+        assert_eq!(output_text(&config, "zai", "glm-4.5").await, "withheld");
+    }
+}
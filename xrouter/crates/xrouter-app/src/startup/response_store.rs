@@ -0,0 +1,34 @@
+use std::{sync::Arc, time::Duration};
+
+use tracing::info;
+
+use crate::{
+    config::{AppConfig, ResponseStoreConfig},
+    response_store::{
+        InMemoryResponseStore, PostgresResponseStore, RedisResponseStore, ResponseStore,
+        SqliteResponseStore,
+    },
+    storage::SqliteDatabase,
+};
+
+pub(crate) fn build_response_store(
+    config: &AppConfig,
+    storage: Option<&Arc<SqliteDatabase>>,
+) -> Arc<dyn ResponseStore> {
+    let ttl = Duration::from_secs(config.response_store_ttl_seconds);
+    let store: Arc<dyn ResponseStore> = match &config.response_store {
+        ResponseStoreConfig::Memory => return Arc::new(InMemoryResponseStore::default()),
+        ResponseStoreConfig::Redis { url } => Arc::new(
+            RedisResponseStore::open(url, ttl).expect("response store URL is validated on load"),
+        ),
+        ResponseStoreConfig::Postgres { url } => Arc::new(
+            PostgresResponseStore::open(url, ttl).expect("response store URL is validated on load"),
+        ),
+        ResponseStoreConfig::Sqlite => Arc::new(SqliteResponseStore::new(
+            Arc::clone(storage.expect("sqlite response store comes with XR_STORAGE=sqlite")),
+            ttl,
+        )),
+    };
+    info!(event = "app.response_store.enabled", store = store.name(), ttl_seconds = ttl.as_secs());
+    store
+}
@@ -0,0 +1,14 @@
+use std::sync::Arc;
+
+use crate::{
+    config::{AppConfig, StorageConfig},
+    storage::SqliteDatabase,
+};
+
+/// The embedded database of `XR_STORAGE=sqlite:<path>`, migrated on open; `None` otherwise.
+pub(crate) fn open_storage(config: &AppConfig) -> Option<Arc<SqliteDatabase>> {
+    let StorageConfig::Sqlite { path } = &config.storage else {
+        return None;
+    };
+    Some(Arc::new(SqliteDatabase::open(path).expect("SQLite storage must open and migrate")))
+}
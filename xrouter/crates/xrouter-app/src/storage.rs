@@ -0,0 +1,57 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rusqlite::Connection;
+use tracing::info;
+
+use crate::migrations::{MigrationError, migrate_sqlite};
+
+/// How long a statement waits for another connection's write lock, e.g. `xrouter migrate`.
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The embedded database of `XR_STORAGE=sqlite:<path>`, shared by the usage ledger, generation
+/// log, key store and response store of a single node.
+pub(crate) struct SqliteDatabase {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteDatabase {
+    /// Opens the database, creating it when missing, and applies pending migrations.
+    pub(crate) fn open(path: &Path) -> Result<Self, MigrationError> {
+        let mut connection = connect_sqlite(path)?;
+        let applied = migrate_sqlite(&mut connection, false)?;
+        info!(
+            event = "app.storage.opened",
+            backend = "sqlite",
+            path = %path.display(),
+            migrations_applied = applied.len()
+        );
+        Ok(Self { connection: Arc::new(Mutex::new(connection)) })
+    }
+
+    /// Runs `query` on the blocking pool; statements of all stores take turns on one connection.
+    pub(crate) async fn call<T, F>(&self, query: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            query(&mut connection)
+        })
+        .await
+        .map_err(|error| error.to_string())?
+        .map_err(|error| error.to_string())
+    }
+}
+
+pub(crate) fn connect_sqlite(path: &Path) -> rusqlite::Result<Connection> {
+    let connection = Connection::open(path)?;
+    connection.busy_timeout(SQLITE_BUSY_TIMEOUT)?;
+    connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    Ok(connection)
+}
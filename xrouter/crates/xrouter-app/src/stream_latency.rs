@@ -0,0 +1,83 @@
+use std::time::Instant;
+
+use tracing::Span;
+use xrouter_contracts::ResponseMeta;
+
+/// Delta timing of one stream as seen by the gateway: time to the first text or reasoning
+/// delta since the request arrived, and the mean gap between the deltas after it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StreamLatency {
+    started_at: Instant,
+    first_delta_at: Option<Instant>,
+    last_delta_at: Option<Instant>,
+    deltas: u32,
+}
+
+impl StreamLatency {
+    pub(crate) fn new(started_at: Instant) -> Self {
+        Self { started_at, first_delta_at: None, last_delta_at: None, deltas: 0 }
+    }
+
+    pub(crate) fn delta(&mut self) {
+        self.delta_at(Instant::now());
+    }
+
+    fn delta_at(&mut self, at: Instant) {
+        self.first_delta_at.get_or_insert(at);
+        self.last_delta_at = Some(at);
+        self.deltas += 1;
+    }
+
+    /// `None` until the first delta arrives.
+    pub(crate) fn time_to_first_token_ms(&self) -> Option<u64> {
+        self.first_delta_at.map(|at| millis(at.duration_since(self.started_at).as_millis()))
+    }
+
+    /// `None` with fewer than two deltas.
+    pub(crate) fn inter_token_latency_ms(&self) -> Option<u64> {
+        let (first, last) = self.first_delta_at.zip(self.last_delta_at)?;
+        let gaps = self.deltas.checked_sub(1).filter(|gaps| *gaps > 0)?;
+        Some(millis(last.duration_since(first).as_millis() / u128::from(gaps)))
+    }
+
+    pub(crate) fn record_on(&self, span: &Span) {
+        if let Some(ttft_ms) = self.time_to_first_token_ms() {
+            span.record("llm.time_to_first_token_ms", ttft_ms);
+        }
+        if let Some(itl_ms) = self.inter_token_latency_ms() {
+            span.record("llm.inter_token_latency_ms", itl_ms);
+        }
+    }
+
+    pub(crate) fn apply(&self, meta: &mut ResponseMeta) {
+        meta.time_to_first_token_ms = self.time_to_first_token_ms();
+        meta.inter_token_latency_ms = self.inter_token_latency_ms();
+    }
+}
+
+fn millis(value: u128) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::StreamLatency;
+
+    #[test]
+    fn latency_is_measured_from_the_request_and_averaged_over_later_gaps() {
+        let started_at = Instant::now();
+        let mut latency = StreamLatency::new(started_at);
+        assert_eq!(latency.time_to_first_token_ms(), None);
+
+        latency.delta_at(started_at + Duration::from_millis(300));
+        assert_eq!(latency.time_to_first_token_ms(), Some(300));
+        assert_eq!(latency.inter_token_latency_ms(), None);
+
+        latency.delta_at(started_at + Duration::from_millis(320));
+        latency.delta_at(started_at + Duration::from_millis(360));
+        assert_eq!(latency.time_to_first_token_ms(), Some(300));
+        assert_eq!(latency.inter_token_latency_ms(), Some(30));
+    }
+}
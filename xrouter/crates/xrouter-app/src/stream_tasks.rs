@@ -0,0 +1,101 @@
+use std::future::Future;
+
+use tokio::sync::mpsc;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+/// Engine executions behind response streams. Each one runs until it finishes, its client
+/// hangs up or the server shuts down, so no generation outlives the request that asked for it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StreamTasks {
+    tracker: TaskTracker,
+    shutdown: CancellationToken,
+}
+
+/// Why a stream task stopped before its execution finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StreamCancel {
+    ClientDisconnected,
+    Shutdown,
+}
+
+impl StreamCancel {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::ClientDisconnected => "client_disconnected",
+            Self::Shutdown => "shutdown",
+        }
+    }
+}
+
+impl StreamTasks {
+    /// Cancellation of one request: fires once `client` closes or the server shuts down.
+    pub(crate) fn cancellation<T>(&self, client: mpsc::Sender<T>) -> RequestCancellation<T> {
+        RequestCancellation { client, shutdown: self.shutdown.child_token() }
+    }
+
+    pub(crate) fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tracker.spawn(task);
+    }
+
+    pub(crate) fn in_flight(&self) -> usize {
+        self.tracker.len()
+    }
+
+    /// Cancels every running task and waits until all of them have stopped.
+    pub(crate) async fn shutdown(&self) {
+        self.tracker.close();
+        self.shutdown.cancel();
+        self.tracker.wait().await;
+    }
+}
+
+pub(crate) struct RequestCancellation<T> {
+    client: mpsc::Sender<T>,
+    shutdown: CancellationToken,
+}
+
+impl<T> RequestCancellation<T> {
+    /// Runs `work` unless the request is cancelled first; dropping `work` then aborts its
+    /// upstream calls.
+    pub(crate) async fn run<F: Future>(self, work: F) -> Result<F::Output, StreamCancel> {
+        tokio::select! {
+            biased;
+            output = work => Ok(output),
+            () = self.client.closed() => Err(StreamCancel::ClientDisconnected),
+            () = self.shutdown.cancelled() => Err(StreamCancel::Shutdown),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn tasks_stop_when_the_client_hangs_up_or_the_server_shuts_down() {
+        let tasks = StreamTasks::default();
+        let (outcomes, mut outcome_rx) = mpsc::unbounded_channel();
+        let (client, client_rx) = mpsc::channel::<()>(1);
+        let (other_client, _other_rx) = mpsc::channel::<()>(1);
+        for client in [client, other_client] {
+            let cancellation = tasks.cancellation(client);
+            let outcomes = outcomes.clone();
+            tasks.spawn(async move {
+                let outcome = cancellation.run(tokio::time::sleep(Duration::from_secs(60))).await;
+                let _ = outcomes.send(outcome);
+            });
+        }
+        assert_eq!(tasks.in_flight(), 2);
+
+        drop(client_rx);
+        assert_eq!(outcome_rx.recv().await, Some(Err(StreamCancel::ClientDisconnected)));
+        tasks.shutdown().await;
+        assert_eq!(outcome_rx.recv().await, Some(Err(StreamCancel::Shutdown)));
+        assert_eq!(tasks.in_flight(), 0);
+    }
+}
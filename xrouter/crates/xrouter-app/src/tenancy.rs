@@ -0,0 +1,609 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use axum::{
+    Json,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::{error, info, warn};
+use xrouter_contracts::Usage;
+
+use crate::{
+    api_keys::{ApiKeyRecord, ApiKeyStore, unix_now},
+    config::TenantConfig,
+    coordination::RedisCoordinator,
+    http::{
+        auth::parse_bearer_token,
+        docs::{AppUsageEntry, ErrorResponse, TenantUsageResponse},
+        rate_limit::{FixedWindowRateLimiter, QuotaReport},
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct Tenant {
+    pub(crate) id: String,
+    organization: String,
+    project: String,
+    model_access: ModelAccess,
+    rate_limiter: Option<FixedWindowRateLimiter>,
+    token_budget: Option<u64>,
+    usage: TenantUsage,
+}
+
+#[derive(Debug, Default)]
+struct TenantUsage {
+    requests: AtomicU64,
+    input_tokens: AtomicU64,
+    output_tokens: AtomicU64,
+    total_tokens: AtomicU64,
+    apps: Mutex<HashMap<AppAttribution, AppUsage>>,
+}
+
+/// Distinct apps tracked per tenant; usage of further apps only counts toward the tenant total.
+const MAX_TRACKED_APPS: usize = 256;
+/// Longest attribution value kept; longer header values are cut.
+const MAX_ATTRIBUTION_CHARS: usize = 256;
+
+/// Calling application as named by the OpenRouter-style `HTTP-Referer` and `X-Title`
+/// (or `X-OpenRouter-Title`) request headers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct AppAttribution {
+    pub(crate) referer: Option<String>,
+    pub(crate) title: Option<String>,
+}
+
+impl AppAttribution {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| value.chars().take(MAX_ATTRIBUTION_CHARS).collect::<String>())
+        };
+        let referer = header("http-referer");
+        let title = header("x-openrouter-title").or_else(|| header("x-title"));
+        (referer.is_some() || title.is_some()).then_some(Self { referer, title })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct AppUsage {
+    requests: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    total_tokens: u64,
+}
+
+impl Tenant {
+    fn from_config(config: &TenantConfig, coordinator: Option<Arc<RedisCoordinator>>) -> Self {
+        Self {
+            id: format!("{}/{}", config.organization, config.project),
+            organization: config.organization.clone(),
+            project: config.project.clone(),
+            model_access: ModelAccess::new(&config.allowed_models, &config.denied_models),
+            rate_limiter: config.rate_limit_per_minute.map(|limit| {
+                FixedWindowRateLimiter::per_minute(limit).shared(coordinator, "tenant")
+            }),
+            token_budget: config.token_budget,
+            usage: TenantUsage::default(),
+        }
+    }
+
+    fn budget_exhausted(&self) -> bool {
+        self.token_budget
+            .is_some_and(|budget| self.usage.total_tokens.load(Ordering::Relaxed) >= budget)
+    }
+
+    pub(crate) fn record_usage(&self, usage: &Usage, app: Option<&AppAttribution>) {
+        self.usage.input_tokens.fetch_add(u64::from(usage.input_tokens), Ordering::Relaxed);
+        self.usage.output_tokens.fetch_add(u64::from(usage.output_tokens), Ordering::Relaxed);
+        self.usage.total_tokens.fetch_add(u64::from(usage.total_tokens), Ordering::Relaxed);
+        let Some(app) = app else {
+            return;
+        };
+        let mut apps = self.usage.apps.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !apps.contains_key(app) && apps.len() >= MAX_TRACKED_APPS {
+            return;
+        }
+        let entry = apps.entry(app.clone()).or_default();
+        entry.requests += 1;
+        entry.input_tokens += u64::from(usage.input_tokens);
+        entry.output_tokens += u64::from(usage.output_tokens);
+        entry.total_tokens += u64::from(usage.total_tokens);
+    }
+
+    pub(crate) fn usage_snapshot(&self) -> TenantUsageResponse {
+        let total_tokens = self.usage.total_tokens.load(Ordering::Relaxed);
+        let mut apps = self
+            .usage
+            .apps
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|(app, usage)| AppUsageEntry {
+                referer: app.referer.clone(),
+                title: app.title.clone(),
+                requests: usage.requests,
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+                total_tokens: usage.total_tokens,
+            })
+            .collect::<Vec<_>>();
+        apps.sort_by(|left, right| {
+            right
+                .total_tokens
+                .cmp(&left.total_tokens)
+                .then_with(|| left.title.cmp(&right.title))
+                .then_with(|| left.referer.cmp(&right.referer))
+        });
+        TenantUsageResponse {
+            tenant_id: self.id.clone(),
+            organization: self.organization.clone(),
+            project: self.project.clone(),
+            requests: self.usage.requests.load(Ordering::Relaxed),
+            input_tokens: self.usage.input_tokens.load(Ordering::Relaxed),
+            output_tokens: self.usage.output_tokens.load(Ordering::Relaxed),
+            total_tokens,
+            token_budget: self.token_budget,
+            remaining_tokens: self.token_budget.map(|budget| budget.saturating_sub(total_tokens)),
+            apps,
+        }
+    }
+}
+
+/// Caller of an authenticated request. Handlers and middleware read per-key settings from
+/// `key` instead of verifying the bearer token again.
+#[derive(Debug, Clone)]
+pub(crate) struct TenantPrincipal {
+    pub(crate) tenant: Arc<Tenant>,
+    /// Managed key the caller presented; `None` for keys listed in `XR_TENANTS`.
+    pub(crate) key: Option<ApiKeyRecord>,
+    key_model_access: ModelAccess,
+}
+
+impl TenantPrincipal {
+    pub(crate) fn allows_model(&self, model: &str) -> bool {
+        self.tenant.model_access.allows(model) && self.key_model_access.allows(model)
+    }
+}
+
+/// Allow- and deny-lists of public model ids. Entries are glob patterns where `*` matches any
+/// run of characters, e.g. `openrouter/anthropic/*`. Deny wins over allow; an empty allow-list
+/// allows every model that is not denied.
+#[derive(Debug, Clone, Default)]
+struct ModelAccess {
+    allowed: Vec<String>,
+    denied: Vec<String>,
+}
+
+impl ModelAccess {
+    fn new(allowed: &[String], denied: &[String]) -> Self {
+        Self { allowed: allowed.to_vec(), denied: denied.to_vec() }
+    }
+
+    fn allows(&self, model: &str) -> bool {
+        !self.denied.iter().any(|pattern| glob_matches(pattern, model))
+            && (self.allowed.is_empty()
+                || self.allowed.iter().any(|pattern| glob_matches(pattern, model)))
+    }
+}
+
+fn glob_matches(pattern: &str, candidate: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == candidate;
+    };
+    let Some(mut remaining) = candidate.strip_prefix(prefix) else {
+        return false;
+    };
+    let mut parts = rest.split('*').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return remaining.len() >= part.len() && remaining.ends_with(part);
+        }
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+pub(crate) struct TenantRegistry {
+    tenants_by_key: HashMap<String, Arc<Tenant>>,
+    tenants_by_id: RwLock<HashMap<String, Arc<Tenant>>>,
+    key_store: Option<Arc<dyn ApiKeyStore>>,
+}
+
+impl TenantRegistry {
+    pub(crate) fn from_config(
+        tenants: &[TenantConfig],
+        key_store: Option<Arc<dyn ApiKeyStore>>,
+        coordinator: Option<Arc<RedisCoordinator>>,
+    ) -> Option<Self> {
+        if tenants.is_empty() && key_store.is_none() {
+            return None;
+        }
+        let mut tenants_by_key = HashMap::new();
+        let mut tenants_by_id = HashMap::new();
+        for config in tenants {
+            let tenant = Arc::new(Tenant::from_config(config, coordinator.clone()));
+            info!(
+                event = "app.tenant.registered",
+                tenant_id = %tenant.id,
+                api_key_count = config.api_keys.len(),
+                allowed_model_count = tenant.model_access.allowed.len(),
+                denied_model_count = tenant.model_access.denied.len(),
+                rate_limit_per_minute = ?config.rate_limit_per_minute,
+                token_budget = ?config.token_budget
+            );
+            for key in &config.api_keys {
+                tenants_by_key.insert(key.clone(), tenant.clone());
+            }
+            tenants_by_id.insert(tenant.id.clone(), tenant);
+        }
+        Some(Self { tenants_by_key, tenants_by_id: RwLock::new(tenants_by_id), key_store })
+    }
+
+    pub(crate) async fn authenticate(
+        &self,
+        api_key: &str,
+    ) -> Result<TenantPrincipal, TenantRejection> {
+        if let Some(tenant) = self.tenants_by_key.get(api_key) {
+            return Ok(TenantPrincipal {
+                tenant: tenant.clone(),
+                key: None,
+                key_model_access: ModelAccess::default(),
+            });
+        }
+        let Some(key_store) = self.key_store.as_ref() else {
+            return Err(TenantRejection::InvalidApiKey);
+        };
+        let record = match key_store.verify(api_key).await {
+            Ok(Some(record)) => record,
+            Ok(None) => return Err(TenantRejection::InvalidApiKey),
+            Err(err) => {
+                error!(event = "http.tenant.key_store_failed", store = key_store.name(), error = %err);
+                return Err(TenantRejection::InvalidApiKey);
+            }
+        };
+        if record.disabled {
+            return Err(TenantRejection::DisabledApiKey);
+        }
+        if record.is_expired_at(unix_now()) {
+            return Err(TenantRejection::ExpiredApiKey);
+        }
+        Ok(TenantPrincipal {
+            tenant: self.tenant(&record.organization, &record.project),
+            key_model_access: ModelAccess::new(&record.allowed_models, &record.denied_models),
+            key: Some(record),
+        })
+    }
+
+    fn tenant(&self, organization: &str, project: &str) -> Arc<Tenant> {
+        let id = format!("{organization}/{project}");
+        if let Some(tenant) =
+            self.tenants_by_id.read().unwrap_or_else(|poisoned| poisoned.into_inner()).get(&id)
+        {
+            return tenant.clone();
+        }
+        self.tenants_by_id
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(id)
+            .or_insert_with(|| {
+                Arc::new(Tenant::from_config(
+                    &TenantConfig {
+                        organization: organization.to_string(),
+                        project: project.to_string(),
+                        api_keys: Vec::new(),
+                        allowed_models: Vec::new(),
+                        denied_models: Vec::new(),
+                        rate_limit_per_minute: None,
+                        token_budget: None,
+                    },
+                    None,
+                ))
+            })
+            .clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TenantRejection {
+    MissingApiKey,
+    InvalidApiKey,
+    DisabledApiKey,
+    ExpiredApiKey,
+    ModelNotAllowed,
+    BudgetExhausted,
+    RateLimited,
+}
+
+impl TenantRejection {
+    fn reason(self) -> &'static str {
+        match self {
+            Self::MissingApiKey => "missing_api_key",
+            Self::InvalidApiKey => "invalid_api_key",
+            Self::DisabledApiKey => "disabled_api_key",
+            Self::ExpiredApiKey => "expired_api_key",
+            Self::ModelNotAllowed => "model_not_allowed",
+            Self::BudgetExhausted => "budget_exhausted",
+            Self::RateLimited => "rate_limited",
+        }
+    }
+}
+
+impl IntoResponse for TenantRejection {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Self::MissingApiKey => {
+                (StatusCode::UNAUTHORIZED, "authorization bearer API key is required")
+            }
+            Self::InvalidApiKey => (StatusCode::UNAUTHORIZED, "invalid API key"),
+            Self::DisabledApiKey => (StatusCode::UNAUTHORIZED, "API key is disabled"),
+            Self::ExpiredApiKey => (StatusCode::UNAUTHORIZED, "API key has expired"),
+            Self::ModelNotAllowed => {
+                (StatusCode::FORBIDDEN, "model is not allowed for this API key")
+            }
+            Self::BudgetExhausted => {
+                (StatusCode::PAYMENT_REQUIRED, "token budget exhausted for this API key")
+            }
+            Self::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "rate limit exceeded for tenant"),
+        };
+        (status, Json(ErrorResponse { error: message.to_string(), param: None })).into_response()
+    }
+}
+
+pub(crate) async fn authenticate_tenant(
+    registry: &TenantRegistry,
+    headers: &HeaderMap,
+    route: &str,
+) -> Result<TenantPrincipal, TenantRejection> {
+    let result = match parse_bearer_token(headers) {
+        None => Err(TenantRejection::MissingApiKey),
+        Some(api_key) => registry.authenticate(&api_key).await,
+    };
+    if let Err(rejection) = result.as_ref() {
+        warn!(event = "http.tenant.rejected", route = route, reason = rejection.reason());
+    }
+    result
+}
+
+/// Model listings stay public; a presented key narrows the listing to the models it may call.
+pub(crate) async fn listing_principal(
+    registry: Option<&TenantRegistry>,
+    headers: &HeaderMap,
+    route: &str,
+) -> Result<Option<TenantPrincipal>, TenantRejection> {
+    match registry {
+        Some(registry) if parse_bearer_token(headers).is_some() => {
+            authenticate_tenant(registry, headers, route).await.map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Authenticates an inference request; `None` while tenancy is off. Runs before anything else
+/// reads the request, so unauthenticated callers learn nothing from header validation.
+pub(crate) async fn authenticate_request(
+    registry: Option<&TenantRegistry>,
+    headers: &HeaderMap,
+    route: &str,
+) -> Result<Option<TenantPrincipal>, TenantRejection> {
+    match registry {
+        Some(registry) => authenticate_tenant(registry, headers, route).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// The checks of [`admit_tenant_request`] that use nothing up: model lists and the token
+/// budget. The request is neither counted nor given a rate limit slot.
+pub(crate) fn check_tenant_request(
+    principal: Option<&TenantPrincipal>,
+    model: &str,
+) -> Result<(), TenantRejection> {
+    let Some(principal) = principal else {
+        return Ok(());
+    };
+    if !principal.allows_model(model) {
+        return Err(TenantRejection::ModelNotAllowed);
+    }
+    if principal.tenant.budget_exhausted() {
+        return Err(TenantRejection::BudgetExhausted);
+    }
+    Ok(())
+}
+
+/// Checks the authenticated caller may use `model` and counts the request against its
+/// tenant's limits. The quotas it was counted against come back even when it is rejected, for
+/// the response's rate-limit headers.
+pub(crate) async fn admit_tenant_request(
+    principal: Option<&TenantPrincipal>,
+    model: &str,
+    route: &str,
+) -> (Result<Option<Arc<Tenant>>, TenantRejection>, QuotaReport) {
+    let mut quota = QuotaReport::default();
+    let Some(principal) = principal else {
+        return (Ok(None), quota);
+    };
+    let tenant = principal.tenant.clone();
+    if let Some(budget) = tenant.token_budget {
+        let used = tenant.usage.total_tokens.load(Ordering::Relaxed);
+        quota.record_tokens(budget, budget.saturating_sub(used));
+    }
+    let rejection = if !principal.allows_model(model) {
+        Some(TenantRejection::ModelNotAllowed)
+    } else if tenant.budget_exhausted() {
+        Some(TenantRejection::BudgetExhausted)
+    } else {
+        match &tenant.rate_limiter {
+            Some(limiter) => {
+                let result = limiter.acquire(&tenant.id).await;
+                let (Ok(window) | Err(window)) = result;
+                quota.record_requests(window);
+                result.err().map(|_| TenantRejection::RateLimited)
+            }
+            None => None,
+        }
+    };
+    if let Some(rejection) = rejection {
+        warn!(
+            event = "http.tenant.rejected",
+            route = route,
+            tenant_id = %tenant.id,
+            key_id = principal.key.as_ref().map_or("", |key| key.id.as_str()),
+            model = model,
+            reason = rejection.reason()
+        );
+        return (Err(rejection), quota);
+    }
+    tenant.usage.requests.fetch_add(1, Ordering::Relaxed);
+    (Ok(Some(tenant)), quota)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn registry(allowed_models: Vec<String>, token_budget: Option<u64>) -> TenantRegistry {
+        TenantRegistry::from_config(
+            &[TenantConfig {
+                organization: "acme".to_string(),
+                project: "web".to_string(),
+                api_keys: vec!["tenant-key".to_string()],
+                allowed_models,
+                denied_models: vec!["zai/*-air".to_string()],
+                rate_limit_per_minute: Some(2),
+                token_budget,
+            }],
+            None,
+            None,
+        )
+        .expect("registry must build")
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}")).expect("header must build"),
+        );
+        headers
+    }
+
+    async fn admit(
+        registry: &TenantRegistry,
+        headers: &HeaderMap,
+        model: &str,
+        route: &str,
+    ) -> (Result<Option<Arc<Tenant>>, TenantRejection>, QuotaReport) {
+        match authenticate_request(Some(registry), headers, route).await {
+            Ok(principal) => admit_tenant_request(principal.as_ref(), model, route).await,
+            Err(rejection) => (Err(rejection), QuotaReport::default()),
+        }
+    }
+
+    #[tokio::test]
+    async fn admit_tenant_request_rejects_missing_and_unknown_keys() {
+        let registry = registry(Vec::new(), None);
+        assert_eq!(
+            admit(&registry, &HeaderMap::new(), "m", "/r").await.0.unwrap_err(),
+            TenantRejection::MissingApiKey
+        );
+        assert_eq!(
+            admit(&registry, &bearer("other"), "m", "/r").await.0.unwrap_err(),
+            TenantRejection::InvalidApiKey
+        );
+        assert!(
+            admit_tenant_request(None, "m", "/r")
+                .await
+                .0
+                .expect("disabled tenancy must admit")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn admit_tenant_request_enforces_model_allow_list_and_rate_limit() {
+        let registry = registry(vec!["deepseek/deepseek-chat".to_string()], None);
+        let headers = bearer("tenant-key");
+        assert_eq!(
+            admit(&registry, &headers, "openrouter/x", "/r").await.0.unwrap_err(),
+            TenantRejection::ModelNotAllowed
+        );
+        for _ in 0..2 {
+            let tenant = admit(&registry, &headers, "deepseek/deepseek-chat", "/r")
+                .await
+                .0
+                .expect("tenant must be admitted")
+                .expect("tenant must be resolved");
+            assert_eq!(tenant.id, "acme/web");
+        }
+        assert_eq!(
+            admit(&registry, &headers, "deepseek/deepseek-chat", "/r").await.0.unwrap_err(),
+            TenantRejection::RateLimited
+        );
+    }
+
+    #[tokio::test]
+    async fn model_access_matches_globs_and_lets_deny_win() {
+        assert!(glob_matches("openrouter/anthropic/*", "openrouter/anthropic/claude-3.5-sonnet"));
+        assert!(!glob_matches("openrouter/anthropic/*", "openrouter/openai/gpt-5"));
+        assert!(glob_matches("*/glm-*", "zai/glm-4.5"));
+        assert!(glob_matches("zai/*-air", "zai/glm-4.5-air"));
+        assert!(!glob_matches("zai/*-air", "zai/glm-4.5"));
+        assert!(!glob_matches("a*a", "a"));
+        assert!(glob_matches("*", "anything"));
+
+        let registry = registry(vec!["zai/*".to_string()], None);
+        let headers = bearer("tenant-key");
+        assert!(admit(&registry, &headers, "zai/glm-4.5", "/r").await.0.is_ok());
+        assert_eq!(
+            admit(&registry, &headers, "zai/glm-4.5-air", "/r").await.0.unwrap_err(),
+            TenantRejection::ModelNotAllowed
+        );
+        assert_eq!(
+            admit(&registry, &headers, "deepseek/deepseek-chat", "/r").await.0.unwrap_err(),
+            TenantRejection::ModelNotAllowed
+        );
+    }
+
+    #[tokio::test]
+    async fn admit_tenant_request_rejects_once_token_budget_is_spent() {
+        let registry = registry(Vec::new(), Some(10));
+        let headers = bearer("tenant-key");
+        let tenant = admit(&registry, &headers, "m", "/r")
+            .await
+            .0
+            .expect("tenant must be admitted")
+            .expect("tenant must be resolved");
+        tenant.record_usage(
+            &Usage {
+                input_tokens: 4,
+                output_tokens: 6,
+                total_tokens: 10,
+                input_tokens_details: None,
+                output_tokens_details: None,
+            },
+            None,
+        );
+        assert_eq!(
+            admit(&registry, &headers, "m", "/r").await.0.unwrap_err(),
+            TenantRejection::BudgetExhausted
+        );
+        let snapshot = tenant.usage_snapshot();
+        assert_eq!(snapshot.requests, 1);
+        assert_eq!(snapshot.total_tokens, 10);
+        assert_eq!(snapshot.remaining_tokens, Some(0));
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::{BTreeSet, HashMap};
+
+use axum::http::HeaderMap;
+use serde_json::json;
+use tracing::{info, warn};
+use xrouter_contracts::{
+    RequestPriority, ResponseInputContent, ResponseInputItem, ResponsesInput, ResponsesRequest,
+};
+use xrouter_core::{CoreError, ExecutionControls};
+
+use crate::{
+    AppState,
+    api_keys::ApiKeyRecord,
+    config::TruncationStrategy,
+    http::routes::inference::{
+        INPUT_CHARS_PER_TOKEN, extract_message_text_from_output, run_responses_request,
+    },
+};
+
+pub(crate) const TRUNCATION_HEADER: &str = "x-xrouter-truncation";
+
+/// Upper bound of a summary, in tokens; at most a quarter of the context window is kept for it.
+const SUMMARY_MAX_OUTPUT_TOKENS: u32 = 512;
+
+const SUMMARY_INSTRUCTIONS: &str = "Summarize the conversation below for the assistant that \
+    continues it. Keep facts, decisions, open questions and tool results it will need; answer \
+    with the summary only.";
+
+/// Strategy of a request: the `x-xrouter-truncation` header, else the `truncation` of its
+/// managed key, else `XR_TRUNCATION`.
+pub(crate) fn resolve_truncation(
+    state: &AppState,
+    key: Option<&ApiKeyRecord>,
+    headers: &HeaderMap,
+) -> Result<TruncationStrategy, CoreError> {
+    if let Some(raw) = headers.get(TRUNCATION_HEADER) {
+        return raw.to_str().ok().and_then(TruncationStrategy::parse).ok_or_else(|| {
+            CoreError::Validation(format!(
+                "{TRUNCATION_HEADER} must be `off`, `drop_oldest`, `middle_out` or `summarize`"
+            ))
+        });
+    }
+    Ok(key.and_then(|key| key.truncation).unwrap_or(state.truncation))
+}
+
+/// Removes input items until the request fits the catalog context window of `provider_model`.
+/// System and developer messages, the last item and tool calls
+/// whose output is kept are never removed, so the request may still be too long afterwards.
+pub(crate) async fn fit_to_context(
+    state: &AppState,
+    route: &str,
+    provider: &str,
+    provider_model: &str,
+    strategy: TruncationStrategy,
+    request: &mut ResponsesRequest,
+) {
+    if strategy == TruncationStrategy::Off {
+        return;
+    }
+    let Some(context_length) = state.context_length(provider, provider_model) else {
+        return;
+    };
+    let ResponsesInput::Items(items) = &mut request.input else {
+        return;
+    };
+    let limit_chars = (context_length as usize * INPUT_CHARS_PER_TOKEN)
+        .saturating_sub(request.instructions.as_deref().map_or(0, |text| text.chars().count()));
+    if item_chars(items).iter().sum::<usize>() <= limit_chars {
+        return;
+    }
+    let summary_model = state.truncation_summary_model.as_deref();
+    let strategy = match (strategy, summary_model) {
+        (TruncationStrategy::Summarize, None) => TruncationStrategy::DropOldest,
+        (strategy, _) => strategy,
+    };
+    // A summary has to fit next to the kept turns.
+    let summary_chars = (SUMMARY_MAX_OUTPUT_TOKENS as usize * INPUT_CHARS_PER_TOKEN)
+        .min(limit_chars / 4)
+        * usize::from(strategy == TruncationStrategy::Summarize);
+    let kept = std::mem::take(items);
+    let (kept, removed, first_removed) =
+        remove_items(kept, limit_chars.saturating_sub(summary_chars), strategy);
+    *items = kept;
+    let removed_items = removed.len();
+    if removed.is_empty() {
+        return;
+    }
+    if strategy == TruncationStrategy::Summarize
+        && let Some(model) = summary_model
+    {
+        match summarize(state, model, &removed, summary_chars).await {
+            Ok(summary) => items.insert(
+                first_removed,
+                ResponseInputItem {
+                    kind: Some("message".to_string()),
+                    role: Some("user".to_string()),
+                    content: Some(ResponseInputContent::Text(format!(
+                        "Summary of the earlier conversation:\n{summary}"
+                    ))),
+                    ..Default::default()
+                },
+            ),
+            Err(error) => warn!(
+                event = "http.request.truncation_summary_failed",
+                route = route,
+                summary_model = model,
+                error = %error
+            ),
+        }
+    }
+    info!(
+        event = "http.request.truncated",
+        route = route,
+        provider = provider,
+        model = provider_model,
+        strategy = strategy.as_str(),
+        removed_items = removed_items,
+        context_length = context_length
+    );
+}
+
+/// Canonical text length of each item, plus the line break joining it to the next.
+fn item_chars(items: &[ResponseInputItem]) -> Vec<usize> {
+    items
+        .iter()
+        .map(|item| {
+            ResponsesInput::Items(vec![item.clone()]).to_canonical_text().chars().count() + 1
+        })
+        .collect()
+}
+
+/// Splits `items` into the kept and removed ones, removing whole groups (a tool call with its
+/// output) in `strategy` order until the kept ones fit `limit_chars`. Also returns where the
+/// first removed item sat among the kept ones.
+fn remove_items(
+    items: Vec<ResponseInputItem>,
+    limit_chars: usize,
+    strategy: TruncationStrategy,
+) -> (Vec<ResponseInputItem>, Vec<ResponseInputItem>, usize) {
+    let chars = item_chars(&items);
+    let mut total = chars.iter().sum::<usize>();
+    let last = items.len().saturating_sub(1);
+    let mut groups = Vec::<Vec<usize>>::new();
+    let mut group_of_call = HashMap::<&str, usize>::new();
+    for (index, item) in items.iter().enumerate() {
+        match item.call_id.as_deref().filter(|call_id| !call_id.is_empty()) {
+            Some(call_id) if group_of_call.contains_key(call_id) => {
+                groups[group_of_call[call_id]].push(index);
+            }
+            Some(call_id) => {
+                group_of_call.insert(call_id, groups.len());
+                groups.push(vec![index]);
+            }
+            None => groups.push(vec![index]),
+        }
+    }
+    let mut candidates = groups
+        .into_iter()
+        .filter(|group| {
+            !group.iter().any(|index| {
+                *index == last
+                    || matches!(items[*index].role.as_deref(), Some("system" | "developer"))
+            })
+        })
+        .collect::<Vec<_>>();
+    if strategy == TruncationStrategy::MiddleOut {
+        let middle = candidates.len() / 2;
+        let mut order = (0..candidates.len()).collect::<Vec<_>>();
+        order.sort_by_key(|position| position.abs_diff(middle));
+        candidates = order.into_iter().map(|position| candidates[position].clone()).collect();
+    }
+    let mut removed = BTreeSet::new();
+    for group in candidates {
+        if total <= limit_chars {
+            break;
+        }
+        total -= group.iter().map(|index| chars[*index]).sum::<usize>();
+        removed.extend(group);
+    }
+    let first_removed = removed
+        .first()
+        .map(|first| (0..*first).filter(|index| !removed.contains(index)).count())
+        .unwrap_or_default();
+    let (removed_items, kept_items) =
+        items.into_iter().enumerate().partition::<Vec<_>, _>(|(index, _)| removed.contains(index));
+    (
+        kept_items.into_iter().map(|(_, item)| item).collect(),
+        removed_items.into_iter().map(|(_, item)| item).collect(),
+        first_removed,
+    )
+}
+
+async fn summarize(
+    state: &AppState,
+    model: &str,
+    removed: &[ResponseInputItem],
+    summary_chars: usize,
+) -> Result<String, CoreError> {
+    let provider = state.resolve_provider_key(model);
+    let provider_model = state.resolve_provider_model_id(model);
+    let engine = state.resolve_engine(model)?;
+    let mut transcript = ResponsesInput::Items(removed.to_vec()).to_canonical_text();
+    // The summary model sees the newest part of the removed turns it has room for.
+    if let Some(context_length) = state.context_length(&provider, &provider_model) {
+        let room = (context_length as usize * INPUT_CHARS_PER_TOKEN)
+            .saturating_sub(SUMMARY_INSTRUCTIONS.len() + summary_chars);
+        let skip = transcript.chars().count().saturating_sub(room);
+        transcript = transcript.chars().skip(skip).collect();
+    }
+    let request = serde_json::from_value::<ResponsesRequest>(json!({
+        "model": provider_model,
+        "instructions": SUMMARY_INSTRUCTIONS,
+        "input": transcript,
+        "max_output_tokens": (summary_chars / INPUT_CHARS_PER_TOKEN).max(1),
+        "store": false,
+    }))
+    .map_err(|error| CoreError::Validation(error.to_string()))?;
+    let response = run_responses_request(
+        state,
+        &provider,
+        engine,
+        request,
+        None,
+        Vec::new(),
+        ExecutionControls { priority: RequestPriority::Batch, ..Default::default() },
+    )
+    .await?;
+    let summary = extract_message_text_from_output(&response.output);
+    if summary.trim().is_empty() {
+        return Err(CoreError::Provider("summary model returned no text".to_string()));
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use xrouter_contracts::{ResponseInputContent, ResponseInputItem};
+
+    use super::remove_items;
+    use crate::config::TruncationStrategy;
+
+    fn message(role: &str, text: &str) -> ResponseInputItem {
+        ResponseInputItem {
+            kind: Some("message".to_string()),
+            role: Some(role.to_string()),
+            content: Some(ResponseInputContent::Text(text.to_string())),
+            ..Default::default()
+        }
+    }
+
+    fn tool_item(kind: &str, call_id: &str) -> ResponseInputItem {
+        ResponseInputItem {
+            kind: Some(kind.to_string()),
+            call_id: Some(call_id.to_string()),
+            name: Some("lookup".to_string()),
+            arguments: Some("{}".to_string()),
+            output: serde_json::from_value(serde_json::json!("x".repeat(40))).ok(),
+            ..Default::default()
+        }
+    }
+
+    fn conversation() -> Vec<ResponseInputItem> {
+        vec![
+            message("system", "stay brief"),
+            message("user", &"a".repeat(40)),
+            message("assistant", &"b".repeat(40)),
+            message("user", &"c".repeat(40)),
+            message("assistant", &"d".repeat(40)),
+            message("user", "latest question"),
+        ]
+    }
+
+    fn texts(items: &[ResponseInputItem]) -> Vec<String> {
+        items
+            .iter()
+            .map(|item| match &item.content {
+                Some(ResponseInputContent::Text(text)) => text.chars().take(1).collect(),
+                _ => item.kind.clone().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drop_oldest_keeps_system_messages_and_the_latest_turn() {
+        let (kept, removed, first_removed) =
+            remove_items(conversation(), 150, TruncationStrategy::DropOldest);
+
+        assert_eq!(texts(&kept), vec!["s", "c", "d", "l"]);
+        assert_eq!(texts(&removed), vec!["a", "b"]);
+        assert_eq!(first_removed, 1);
+    }
+
+    #[test]
+    fn middle_out_keeps_the_start_and_end_of_the_conversation() {
+        let (kept, removed, first_removed) =
+            remove_items(conversation(), 150, TruncationStrategy::MiddleOut);
+
+        assert_eq!(texts(&kept), vec!["s", "a", "d", "l"]);
+        assert_eq!(texts(&removed), vec!["b", "c"]);
+        assert_eq!(first_removed, 2);
+    }
+
+    #[test]
+    fn tool_calls_are_removed_together_with_their_output() {
+        let mut items = vec![tool_item("function_call", "call_1")];
+        items.extend(conversation().into_iter().skip(1));
+        items.insert(1, tool_item("function_call_output", "call_1"));
+
+        let (kept, removed, _) = remove_items(items, 215, TruncationStrategy::DropOldest);
+
+        assert_eq!(removed.len(), 2);
+        assert!(removed.iter().all(|item| item.call_id.as_deref() == Some("call_1")));
+        assert!(kept.iter().all(|item| item.call_id.is_none()));
+    }
+}
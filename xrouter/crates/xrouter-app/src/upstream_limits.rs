@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use axum::http::HeaderMap;
+use xrouter_core::{CoreError, UpstreamLimits};
+
+use crate::config::UpstreamLimitsConfig;
+
+pub(crate) const TIMEOUT_HEADER: &str = "x-xrouter-provider-timeout-ms";
+pub(crate) const RETRIES_HEADER: &str = "x-xrouter-max-retries";
+
+/// Upstream timeout and retries the request asks for through its headers, checked against
+/// `bounds`; requests without the headers keep the provider defaults.
+pub(crate) fn resolve_upstream_limits(
+    bounds: &UpstreamLimitsConfig,
+    headers: &HeaderMap,
+) -> Result<UpstreamLimits, CoreError> {
+    let timeout_ms = header_number(headers, TIMEOUT_HEADER, u64::from(u32::MAX))?;
+    if let Some(timeout_ms) = timeout_ms
+        && !(1..=bounds.max_timeout_ms).contains(&timeout_ms)
+    {
+        return Err(CoreError::Validation(format!(
+            "{TIMEOUT_HEADER} must be between 1 and {}",
+            bounds.max_timeout_ms
+        )));
+    }
+    let max_retries = header_number(headers, RETRIES_HEADER, u64::from(u32::MAX))?;
+    if let Some(max_retries) = max_retries
+        && max_retries > u64::from(bounds.max_retries)
+    {
+        return Err(CoreError::Validation(format!(
+            "{RETRIES_HEADER} must be at most {}",
+            bounds.max_retries
+        )));
+    }
+    Ok(UpstreamLimits {
+        timeout: timeout_ms.map(Duration::from_millis),
+        max_retries: max_retries.map(|retries| retries as u32),
+    })
+}
+
+fn header_number(headers: &HeaderMap, name: &str, max: u64) -> Result<Option<u64>, CoreError> {
+    let Some(value) = headers.get(name) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|number| *number <= max)
+        .map(Some)
+        .ok_or_else(|| CoreError::Validation(format!("{name} must be a non-negative integer")))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn upstream_limits_come_from_headers_within_the_server_bounds() {
+        let bounds = UpstreamLimitsConfig { max_timeout_ms: 60_000, max_retries: 2 };
+        let mut headers = HeaderMap::new();
+        assert_eq!(resolve_upstream_limits(&bounds, &headers), Ok(UpstreamLimits::default()));
+
+        headers.insert(TIMEOUT_HEADER, HeaderValue::from_static("1500"));
+        headers.insert(RETRIES_HEADER, HeaderValue::from_static("0"));
+        assert_eq!(
+            resolve_upstream_limits(&bounds, &headers),
+            Ok(UpstreamLimits { timeout: Some(Duration::from_millis(1500)), max_retries: Some(0) })
+        );
+
+        for (name, value) in [
+            (TIMEOUT_HEADER, "60001"),
+            (TIMEOUT_HEADER, "0"),
+            (TIMEOUT_HEADER, "soon"),
+            (RETRIES_HEADER, "3"),
+        ] {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_static(value));
+            assert!(
+                matches!(resolve_upstream_limits(&bounds, &headers), Err(CoreError::Validation(_))),
+                "{name}: {value} must be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use reqwest::{
+    Url,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use xrouter_contracts::Usage;
+
+use crate::{
+    api_keys::unix_now,
+    config::UsageExportConfig,
+    secrets::{SigV4Request, amz_date, hex, sigv4_authorization},
+};
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+const UPLOAD_ATTEMPTS: u32 = 3;
+/// Generation records held in memory before they are uploaded as one part of the day.
+const MAX_BUFFERED_GENERATIONS: usize = 10_000;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Keeps this replica's usage ledger and generation log per UTC day and uploads them as JSONL
+/// objects to an S3-compatible bucket once the day is over.
+pub(crate) struct UsageExport {
+    bucket: Bucket,
+    prefix: String,
+    /// Tells apart the objects of replicas exporting to the same bucket.
+    instance: String,
+    ledger: Mutex<DayLedger>,
+    rollover_started: AtomicBool,
+}
+
+struct DayLedger {
+    day: u64,
+    usage: BTreeMap<(String, String, String), UsageTotals>,
+    generations: Vec<Value>,
+    /// Generation parts of the day already handed off for upload.
+    parts: u32,
+}
+
+#[derive(Default)]
+struct UsageTotals {
+    requests: u64,
+    failed_requests: u64,
+    input_tokens: u64,
+    output_tokens: u64,
+    total_tokens: u64,
+}
+
+struct Upload {
+    key: String,
+    lines: Vec<Value>,
+}
+
+impl UsageExport {
+    pub(crate) fn from_config(config: Option<&UsageExportConfig>) -> Option<Arc<Self>> {
+        let config = config?;
+        let bucket = match Bucket::new(config) {
+            Ok(bucket) => bucket,
+            Err(error) => {
+                warn!(event = "app.usage_export.client_failed", error = %error);
+                return None;
+            }
+        };
+        info!(event = "app.usage_export.enabled", region = %config.region, prefix = %config.prefix);
+        Some(Arc::new(Self {
+            bucket,
+            prefix: config.prefix.clone(),
+            instance: uuid::Uuid::new_v4().simple().to_string(),
+            ledger: Mutex::new(DayLedger::new(today())),
+            rollover_started: AtomicBool::new(false),
+        }))
+    }
+
+    /// Adds one finished request; `usage` is `None` when it failed.
+    pub(crate) fn record(
+        self: &Arc<Self>,
+        tenant_id: &str,
+        model: &str,
+        provider: &str,
+        usage: Option<&Usage>,
+        generation: Value,
+    ) {
+        self.start_rollover();
+        let mut uploads = self.close_day(today());
+        {
+            let mut ledger = self.ledger.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let totals = ledger
+                .usage
+                .entry((tenant_id.to_string(), model.to_string(), provider.to_string()))
+                .or_default();
+            totals.requests += 1;
+            match usage {
+                Some(usage) => {
+                    totals.input_tokens += u64::from(usage.input_tokens);
+                    totals.output_tokens += u64::from(usage.output_tokens);
+                    totals.total_tokens += u64::from(usage.total_tokens);
+                }
+                None => totals.failed_requests += 1,
+            }
+            ledger.generations.push(generation);
+            if ledger.generations.len() >= MAX_BUFFERED_GENERATIONS {
+                uploads.extend(self.generations_part(&mut ledger));
+            }
+        }
+        for upload in uploads {
+            let export = Arc::clone(self);
+            tokio::spawn(async move { export.upload(upload).await });
+        }
+    }
+
+    /// Uploads the ledger at each UTC midnight, so a quiet night does not hold back a day.
+    fn start_rollover(self: &Arc<Self>) {
+        if self.rollover_started.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let export = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                let until_midnight = SECONDS_PER_DAY - unix_now() % SECONDS_PER_DAY;
+                tokio::time::sleep(Duration::from_secs(until_midnight + 1)).await;
+                for upload in export.close_day(today()) {
+                    export.upload(upload).await;
+                }
+            }
+        });
+    }
+
+    /// Takes the objects of a day that ended before `today` and starts a fresh ledger.
+    fn close_day(&self, today: u64) -> Vec<Upload> {
+        let mut ledger = self.ledger.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if ledger.day >= today {
+            return Vec::new();
+        }
+        let mut uploads = self.generations_part(&mut ledger).into_iter().collect::<Vec<_>>();
+        let closed = std::mem::replace(&mut *ledger, DayLedger::new(today));
+        if !closed.usage.is_empty() {
+            let date = iso_date(closed.day);
+            let lines = closed
+                .usage
+                .into_iter()
+                .map(|((tenant_id, model, provider), totals)| {
+                    json!({
+                        "date": date,
+                        "tenant_id": tenant_id,
+                        "model": model,
+                        "provider": provider,
+                        "requests": totals.requests,
+                        "failed_requests": totals.failed_requests,
+                        "input_tokens": totals.input_tokens,
+                        "output_tokens": totals.output_tokens,
+                        "total_tokens": totals.total_tokens,
+                    })
+                })
+                .collect();
+            uploads.push(Upload {
+                key: format!("{}usage/dt={date}/{}.jsonl", self.prefix, self.instance),
+                lines,
+            });
+        }
+        uploads
+    }
+
+    fn generations_part(&self, ledger: &mut DayLedger) -> Option<Upload> {
+        if ledger.generations.is_empty() {
+            return None;
+        }
+        ledger.parts += 1;
+        Some(Upload {
+            key: format!(
+                "{}generations/dt={}/{}-{:04}.jsonl",
+                self.prefix,
+                iso_date(ledger.day),
+                self.instance,
+                ledger.parts
+            ),
+            lines: std::mem::take(&mut ledger.generations),
+        })
+    }
+
+    async fn upload(&self, upload: Upload) {
+        let body = upload.lines.iter().map(|line| format!("{line}\n")).collect::<String>();
+        let mut attempt = 1;
+        loop {
+            match self.bucket.put(&upload.key, body.clone()).await {
+                Ok(()) => {
+                    info!(
+                        event = "usage_export.uploaded",
+                        key = %upload.key,
+                        records = upload.lines.len()
+                    );
+                    return;
+                }
+                Err(error) if attempt < UPLOAD_ATTEMPTS => {
+                    warn!(
+                        event = "usage_export.upload_retry",
+                        key = %upload.key,
+                        attempt = attempt,
+                        error = %error
+                    );
+                    tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+                    attempt += 1;
+                }
+                Err(error) => {
+                    warn!(
+                        event = "usage_export.upload_failed",
+                        key = %upload.key,
+                        records = upload.lines.len(),
+                        error = %error
+                    );
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl DayLedger {
+    fn new(day: u64) -> Self {
+        Self { day, usage: BTreeMap::new(), generations: Vec::new(), parts: 0 }
+    }
+}
+
+/// Path-style S3 bucket written with SigV4-signed `PUT`s; GCS accepts the same with HMAC keys.
+struct Bucket {
+    url: Url,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    client: reqwest::Client,
+}
+
+impl Bucket {
+    fn new(config: &UsageExportConfig) -> Result<Self, String> {
+        let url = Url::parse(&config.bucket_url).map_err(|error| error.to_string())?;
+        let client = reqwest::Client::builder()
+            .timeout(UPLOAD_TIMEOUT)
+            .build()
+            .map_err(|error| error.to_string())?;
+        Ok(Self {
+            url,
+            region: config.region.clone(),
+            access_key_id: config.access_key_id.clone(),
+            secret_access_key: config.secret_access_key.clone(),
+            client,
+        })
+    }
+
+    async fn put(&self, key: &str, body: String) -> Result<(), String> {
+        let mut url = self.url.clone();
+        url.set_path(&format!("{}/{key}", self.url.path().trim_end_matches('/')));
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err("bucket URL has no host".to_string()),
+        };
+        let payload_hash = hex(&Sha256::digest(body.as_bytes()));
+        let amz_date = amz_date(unix_now());
+        let authorization = sigv4_authorization(
+            &SigV4Request {
+                method: "PUT",
+                path: url.path(),
+                service: "s3",
+                region: &self.region,
+                amz_date: &amz_date,
+                headers: vec![
+                    ("host", &host),
+                    ("x-amz-content-sha256", &payload_hash),
+                    ("x-amz-date", &amz_date),
+                ],
+                payload_hash: &payload_hash,
+            },
+            &self.access_key_id,
+            &self.secret_access_key,
+        );
+        let response = self
+            .client
+            .put(url.clone())
+            .header(CONTENT_TYPE, "application/x-ndjson")
+            .header("x-amz-content-sha256", &payload_hash)
+            .header("x-amz-date", &amz_date)
+            .header(AUTHORIZATION, authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|error| error.to_string())?;
+        response.error_for_status().map(|_| ()).map_err(|error| error.to_string())
+    }
+}
+
+pub(crate) fn today() -> u64 {
+    unix_now() / SECONDS_PER_DAY
+}
+
+/// `YYYY-MM-DD` of a day counted from the Unix epoch.
+pub(crate) fn iso_date(day: u64) -> String {
+    let stamp = amz_date(day * SECONDS_PER_DAY);
+    format!("{}-{}-{}", &stamp[..4], &stamp[4..6], &stamp[6..8])
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        extract::{Path, State},
+        http::HeaderMap,
+        routing::put,
+    };
+    use serde_json::{Value, json};
+    use tokio::sync::mpsc;
+    use xrouter_contracts::Usage;
+
+    use super::{UsageExport, iso_date, today};
+    use crate::config::UsageExportConfig;
+
+    type Received = (String, String, String);
+
+    async fn bucket_server() -> (String, mpsc::UnboundedReceiver<Received>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/{*path}",
+            put(
+                |State(sender): State<mpsc::UnboundedSender<Received>>,
+                 Path(path): Path<String>,
+                 headers: HeaderMap,
+                 body: String| async move {
+                    let authorization = headers
+                        .get("authorization")
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string();
+                    let _ = sender.send((path, authorization, body));
+                },
+            )
+            .with_state(sender),
+        );
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("listener must bind");
+        let url = format!("http://{}", listener.local_addr().expect("address must resolve"));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, receiver)
+    }
+
+    fn usage(input_tokens: u32, output_tokens: u32) -> Usage {
+        Usage {
+            input_tokens,
+            output_tokens,
+            total_tokens: input_tokens + output_tokens,
+            input_tokens_details: None,
+            output_tokens_details: None,
+        }
+    }
+
+    #[test]
+    fn iso_date_formats_epoch_days() {
+        assert_eq!(iso_date(0), "1970-01-01");
+        assert_eq!(iso_date(11_016), "2000-02-29");
+    }
+
+    #[tokio::test]
+    async fn closed_day_uploads_a_usage_ledger_and_generation_log_as_signed_jsonl() {
+        let (url, mut received) = bucket_server().await;
+        let export = UsageExport::from_config(Some(&UsageExportConfig {
+            bucket_url: format!("{url}/analytics"),
+            region: "us-east-1".to_string(),
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "test-secret".to_string(),
+            prefix: "xrouter/".to_string(),
+        }))
+        .expect("export must be enabled");
+
+        let generation = |id: &str| json!({"type": "request.completed", "request_id": id});
+        export.record("acme/web", "zai/glm-4.5", "zai", Some(&usage(3, 4)), generation("req_1"));
+        export.record("acme/web", "zai/glm-4.5", "zai", None, generation("req_2"));
+        export.record("acme/web", "zai/glm-4.6", "zai", Some(&usage(1, 1)), generation("req_3"));
+        assert!(export.close_day(today()).is_empty());
+        let uploads = export.close_day(today() + 1);
+        assert_eq!(uploads.len(), 2);
+        for upload in uploads {
+            export.upload(upload).await;
+        }
+
+        let date = iso_date(today());
+        let mut objects = Vec::new();
+        for _ in 0..2 {
+            objects.push(received.recv().await.expect("bucket must receive the upload"));
+        }
+        objects.sort();
+        let (generations_key, authorization, generations) = &objects[0];
+        assert_eq!(
+            generations_key,
+            &format!("analytics/xrouter/generations/dt={date}/{}-0001.jsonl", export.instance)
+        );
+        assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+        assert!(authorization.contains("/us-east-1/s3/aws4_request"));
+        let ids = generations
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("line must be JSON"))
+            .map(|line| line["request_id"].as_str().unwrap_or_default().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["req_1", "req_2", "req_3"]);
+
+        let (usage_key, _, ledger) = &objects[1];
+        assert_eq!(
+            usage_key,
+            &format!("analytics/xrouter/usage/dt={date}/{}.jsonl", export.instance)
+        );
+        let rows = ledger
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("line must be JSON"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["model"], "zai/glm-4.5");
+        assert_eq!(rows[0]["requests"], 2);
+        assert_eq!(rows[0]["failed_requests"], 1);
+        assert_eq!(rows[0]["total_tokens"], 7);
+        assert_eq!(rows[1]["model"], "zai/glm-4.6");
+        assert_eq!(rows[1]["date"], date);
+    }
+}
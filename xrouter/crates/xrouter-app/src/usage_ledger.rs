@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use rusqlite::params;
+use serde_json::Value;
+use tracing::warn;
+use xrouter_contracts::Usage;
+
+use crate::{
+    storage::SqliteDatabase,
+    usage_export::{iso_date, today},
+};
+
+/// The usage ledger and generation log of `XR_STORAGE=sqlite`: daily totals per tenant, model
+/// and provider in `xrouter_usage`, and one row per finished request in `xrouter_generations`.
+/// Unlike the usage export's day buffer, both survive a restart.
+pub(crate) struct UsageLedger {
+    database: Arc<SqliteDatabase>,
+}
+
+impl UsageLedger {
+    pub(crate) fn new(database: Arc<SqliteDatabase>) -> Arc<Self> {
+        Arc::new(Self { database })
+    }
+
+    /// Adds one finished request in the background; `usage` is `None` when it failed.
+    pub(crate) fn record(
+        self: &Arc<Self>,
+        tenant_id: &str,
+        model: &str,
+        provider: &str,
+        usage: Option<&Usage>,
+        generation: Value,
+    ) {
+        let ledger = Arc::clone(self);
+        let key = (tenant_id.to_string(), model.to_string(), provider.to_string());
+        let usage = usage.cloned();
+        tokio::spawn(async move {
+            if let Err(error) = ledger.write(key, usage, generation).await {
+                warn!(event = "usage_ledger.write_failed", error = %error);
+            }
+        });
+    }
+
+    async fn write(
+        &self,
+        (tenant_id, model, provider): (String, String, String),
+        usage: Option<Usage>,
+        generation: Value,
+    ) -> Result<(), String> {
+        let date = iso_date(today());
+        let request_id = generation["request_id"].as_str().unwrap_or_default().to_string();
+        let record = generation.to_string();
+        let (input_tokens, output_tokens, total_tokens) =
+            usage.as_ref().map_or((0, 0, 0), |usage| {
+                (usage.input_tokens, usage.output_tokens, usage.total_tokens)
+            });
+        let failed = u32::from(usage.is_none());
+        self.database
+            .call(move |connection| {
+                let transaction = connection.transaction()?;
+                transaction.execute(
+                    "INSERT INTO xrouter_usage (date, tenant_id, model, provider, requests, \
+                     failed_requests, input_tokens, output_tokens, total_tokens) \
+                     VALUES (?1, ?2, ?3, ?4, 1, ?5, ?6, ?7, ?8) \
+                     ON CONFLICT (date, tenant_id, model, provider) DO UPDATE SET \
+                     requests = requests + 1, \
+                     failed_requests = failed_requests + excluded.failed_requests, \
+                     input_tokens = input_tokens + excluded.input_tokens, \
+                     output_tokens = output_tokens + excluded.output_tokens, \
+                     total_tokens = total_tokens + excluded.total_tokens",
+                    params![
+                        date,
+                        tenant_id,
+                        model,
+                        provider,
+                        failed,
+                        input_tokens,
+                        output_tokens,
+                        total_tokens
+                    ],
+                )?;
+                transaction.execute(
+                    "INSERT INTO xrouter_generations (request_id, date, record) VALUES (?1, ?2, ?3)",
+                    params![request_id, date, record],
+                )?;
+                transaction.commit()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use xrouter_contracts::Usage;
+
+    use super::UsageLedger;
+    use crate::{
+        storage::SqliteDatabase,
+        usage_export::{iso_date, today},
+    };
+
+    fn key(model: &str) -> (String, String, String) {
+        ("acme/web".to_string(), model.to_string(), "zai".to_string())
+    }
+
+    #[tokio::test]
+    async fn ledger_adds_up_daily_totals_and_keeps_every_generation() {
+        let path = std::env::temp_dir().join(format!("xrouter-{}.db", uuid::Uuid::new_v4()));
+        let database = Arc::new(SqliteDatabase::open(&path).expect("database must open"));
+        let ledger = UsageLedger::new(Arc::clone(&database));
+        let usage = Usage {
+            input_tokens: 3,
+            output_tokens: 4,
+            total_tokens: 7,
+            input_tokens_details: None,
+            output_tokens_details: None,
+        };
+        let generation = |id: &str| json!({"type": "request.completed", "request_id": id});
+        for (model, usage, id) in [
+            ("zai/glm-4.5", Some(usage.clone()), "req_1"),
+            ("zai/glm-4.5", None, "req_2"),
+            ("zai/glm-4.6", Some(usage), "req_3"),
+        ] {
+            ledger.write(key(model), usage, generation(id)).await.expect("write must succeed");
+        }
+
+        let rows = database
+            .call(|connection| {
+                let mut statement = connection.prepare(
+                    "SELECT date, model, requests, failed_requests, total_tokens \
+                     FROM xrouter_usage ORDER BY model",
+                )?;
+                let rows = statement.query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)?,
+                        row.get::<_, i64>(4)?,
+                    ))
+                })?;
+                rows.collect::<Result<Vec<_>, _>>()
+            })
+            .await
+            .expect("ledger must be readable");
+        let date = iso_date(today());
+        assert_eq!(
+            rows,
+            [
+                (date.clone(), "zai/glm-4.5".to_string(), 2, 1, 7),
+                (date, "zai/glm-4.6".to_string(), 1, 0, 7),
+            ]
+        );
+        let generations = database
+            .call(|connection| {
+                let mut statement = connection
+                    .prepare("SELECT request_id FROM xrouter_generations ORDER BY rowid")?;
+                let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+                rows.collect::<Result<Vec<_>, _>>()
+            })
+            .await
+            .expect("generations must be readable");
+        assert_eq!(generations, ["req_1", "req_2", "req_3"]);
+        let _ = std::fs::remove_file(path);
+    }
+}
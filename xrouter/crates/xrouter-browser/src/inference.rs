@@ -4,9 +4,9 @@ use xrouter_clients_openai::runtime::SharedProviderRuntime;
 use xrouter_clients_openai::{DeepSeekClient, OpenAiClient, OpenRouterClient, ZaiClient};
 use xrouter_contracts::{ResponseEvent, ResponsesInput, ResponsesRequest, ResponsesResponse};
 use xrouter_core::{
-    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
-    ProviderOutcome, ResponseEventSink, response_completed_event_from_outcome,
-    responses_response_from_outcome,
+    CoreError, ExecutionControls, ProviderClient, ProviderGenerateRequest,
+    ProviderGenerateStreamRequest, ProviderOutcome, ResponseEventSink,
+    response_completed_event_from_outcome, responses_response_from_outcome,
 };
 
 use crate::error::BrowserError;
@@ -97,8 +97,13 @@ impl BrowserInferenceClient {
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
         let (outcome, _) =
             self.generate_responses_with_outcome(request_id, &request, None, sender).await?;
@@ -225,8 +230,13 @@ fn build_provider_request<'a>(
         reasoning: request.reasoning.as_ref(),
         tools: request.tools.as_deref(),
         tool_choice: request.tool_choice.as_ref(),
+        include: request.include.as_deref(),
+        text: request.text.as_ref(),
+        modalities: request.modalities.as_deref(),
         auth_bearer: None,
         forward_headers,
+        extra: None,
+        controls: ExecutionControls::default(),
     }
 }
 
@@ -405,12 +415,16 @@ mod tests {
             ]),
             parallel_tool_calls: None,
             stream: true,
-            reasoning: Some(ReasoningConfig { effort: Some("high".to_string()), summary: None }),
+            reasoning: Some(ReasoningConfig {
+                effort: Some("high".to_string()),
+                ..Default::default()
+            }),
             store: None,
             include: None,
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: Some(vec![json!({
                 "type": "function",
                 "function": {
@@ -419,6 +433,10 @@ mod tests {
                 }
             })]),
             tool_choice: Some(json!("auto")),
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
 
         let provider_request = build_provider_request(&request, &[]);
@@ -474,14 +492,20 @@ mod tests {
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
         let outcome = xrouter_core::ProviderOutcome {
             chunks: Vec::new(),
             output_tokens: 7,
             reasoning: None,
             reasoning_details: None,
+            reasoning_encrypted_content: None,
             tool_calls: Some(vec![xrouter_contracts::ToolCall {
                 id: "call_123".to_string(),
                 kind: "function".to_string(),
@@ -490,7 +514,13 @@ mod tests {
                     arguments: "{\"city\":\"Moscow\"}".to_string(),
                 },
             }]),
+            annotations: None,
             emitted_live: true,
+            finish_reason: None,
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
         };
 
         let response = responses_response_from_outcome(
@@ -514,8 +544,15 @@ mod tests {
             output_tokens: 2,
             reasoning: Some("thinking".to_string()),
             reasoning_details: None,
+            reasoning_encrypted_content: None,
             tool_calls: None,
+            annotations: None,
             emitted_live: false,
+            finish_reason: None,
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
         };
 
         futures::executor::block_on(emit_non_live_events("req-1", &outcome, Some(&sink)));
@@ -20,16 +20,17 @@ use web_sys::{
 };
 #[cfg(target_arch = "wasm32")]
 use xrouter_contracts::ResponseEvent;
-use xrouter_core::{CoreError, ProviderOutcome, ResponseEventSink};
+use xrouter_core::{CoreError, ProviderOutcome, ResponseEventSink, UpstreamLimits};
 
 use crate::error::BrowserError;
 #[cfg(target_arch = "wasm32")]
 use xrouter_clients_openai::parser::{
-    ChatCompletionsResponse, ResponsesApiResponse, drain_sse_frames, extract_chat_delta_chunks,
-    extract_chat_reasoning_delta, extract_responses_text_delta, map_chat_completion_response,
-    map_chat_completion_stream_text, map_responses_api_response, map_responses_stream_text,
+    ChatCompletionsResponse, ResponsesApiResponse, Utf8ChunkDecoder, drain_sse_frames,
+    extract_chat_delta_chunks, extract_chat_reasoning_delta, extract_responses_text_delta,
+    map_chat_completion_response, map_chat_completion_stream_text, map_responses_api_response,
+    map_responses_stream_text,
 };
-use xrouter_clients_openai::runtime::ProviderRuntime;
+use xrouter_clients_openai::runtime::{MultipartFile, ProviderRuntime};
 
 #[derive(Debug, Clone)]
 pub struct BrowserProviderRuntime {
@@ -119,8 +120,15 @@ impl BrowserProviderRuntime {
             output_tokens,
             reasoning: None,
             reasoning_details: None,
+            reasoning_encrypted_content: None,
             tool_calls: None,
+            annotations: None,
             emitted_live: false,
+            finish_reason: None,
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
         })
     }
 }
@@ -144,6 +152,7 @@ impl ProviderRuntime for BrowserProviderRuntime {
         payload: &Value,
         bearer_override: Option<&str>,
         extra_headers: &[(String, String)],
+        _limits: UpstreamLimits,
         sender: Option<&dyn ResponseEventSink>,
     ) -> Result<ProviderOutcome, CoreError> {
         post_chat_completions_stream_impl(
@@ -165,6 +174,7 @@ impl ProviderRuntime for BrowserProviderRuntime {
         payload: &Value,
         bearer_override: Option<&str>,
         extra_headers: &[(String, String)],
+        _limits: UpstreamLimits,
         sender: Option<&dyn ResponseEventSink>,
     ) -> Result<ProviderOutcome, CoreError> {
         post_responses_stream_impl(
@@ -187,6 +197,16 @@ impl ProviderRuntime for BrowserProviderRuntime {
     ) -> Result<Value, CoreError> {
         Err(Self::provider_error("browser form-post runtime is not implemented yet"))
     }
+
+    async fn post_multipart_json(
+        &self,
+        _url: &str,
+        _form_fields: &[(String, String)],
+        _file: MultipartFile,
+        _headers: &[(String, String)],
+    ) -> Result<Value, CoreError> {
+        Err(Self::provider_error("browser multipart runtime is not implemented yet"))
+    }
 }
 
 async fn post_chat_completions_stream_impl(
@@ -218,8 +238,9 @@ async fn post_chat_completions_stream_impl(
         let mut parse_buffer = String::new();
         let mut full_body = String::new();
         let mut reader = stream_response_reader(response.response)?;
+        let mut utf8 = Utf8ChunkDecoder::default();
 
-        while let Some(chunk) = read_reader_chunk(request_id, &mut reader).await? {
+        while let Some(chunk) = read_reader_chunk(request_id, &mut reader, &mut utf8).await? {
             let chunk = chunk.replace('\r', "");
             parse_buffer.push_str(&chunk);
             full_body.push_str(&chunk);
@@ -315,8 +336,9 @@ async fn post_responses_stream_impl(
         let mut parse_buffer = String::new();
         let mut full_body = String::new();
         let mut reader = stream_response_reader(response.response)?;
+        let mut utf8 = Utf8ChunkDecoder::default();
 
-        while let Some(chunk) = read_reader_chunk(request_id, &mut reader).await? {
+        while let Some(chunk) = read_reader_chunk(request_id, &mut reader, &mut utf8).await? {
             let chunk = chunk.replace('\r', "");
             parse_buffer.push_str(&chunk);
             full_body.push_str(&chunk);
@@ -485,9 +507,11 @@ fn stream_response_reader(response: Response) -> Result<ReadableStreamDefaultRea
 }
 
 #[cfg(target_arch = "wasm32")]
+/// Next decoded chunk; characters split across reads are completed by `utf8` on a later call.
 async fn read_reader_chunk(
     request_id: &str,
     reader: &mut ReadableStreamDefaultReader,
+    utf8: &mut Utf8ChunkDecoder,
 ) -> Result<Option<String>, CoreError> {
     let result = JsFuture::from(reader.read()).await.map_err(|err| {
         BrowserProviderRuntime::provider_error(
@@ -501,7 +525,8 @@ async fn read_reader_chunk(
         .as_bool()
         .unwrap_or(false);
     if done {
-        return Ok(None);
+        let tail = utf8.finish();
+        return Ok((!tail.is_empty()).then_some(tail));
     }
     let value = Reflect::get(&result, &JsValue::from_str("value")).map_err(|err| {
         BrowserProviderRuntime::provider_error(format!("stream value decode failed: {err:?}"))
@@ -509,7 +534,7 @@ async fn read_reader_chunk(
     let bytes = Uint8Array::new(&value);
     let mut out = vec![0u8; bytes.length() as usize];
     bytes.copy_to(&mut out);
-    Ok(Some(String::from_utf8_lossy(&out).into_owned()))
+    Ok(Some(utf8.push(&out)))
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -551,7 +576,7 @@ fn is_abort_error(err: &JsValue) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use xrouter_core::CoreError;
+    use xrouter_core::{CoreError, UpstreamLimits};
 
     use super::BrowserProviderRuntime;
     use xrouter_clients_openai::runtime::ProviderRuntime;
@@ -582,6 +607,7 @@ mod tests {
             &payload,
             None,
             &[],
+            UpstreamLimits::default(),
             None,
         ));
         assert!(matches!(result, Err(CoreError::Provider(message)) if message.contains("wasm32")));
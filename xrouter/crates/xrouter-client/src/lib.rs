@@ -0,0 +1,561 @@
+//! Typed async client for the xrouter HTTP API.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), xrouter_client::ClientError> {
+//! use futures::StreamExt;
+//! use xrouter_client::{XrouterClient, contracts::ResponseEvent};
+//!
+//! let client = XrouterClient::new("http://localhost:3000/api/v1").with_api_key("xr-...");
+//! let request = serde_json::from_value(serde_json::json!({
+//!     "model": "openrouter/anthropic/claude-3.5-sonnet",
+//!     "input": "hello",
+//! }))
+//! .expect("valid request");
+//! let mut events = client.responses_stream(&request).await?;
+//! while let Some(event) = events.next().await {
+//!     if let ResponseEvent::OutputTextDelta { delta, .. } = event? {
+//!         print!("{delta}");
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::VecDeque, pin::Pin};
+
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+pub use xrouter_contracts as contracts;
+use xrouter_contracts::{
+    ChatCompletionsRequest, ChatCompletionsResponse, ResponseEvent, ResponseMeta,
+    ResponseOutputItem, ResponseOutputText, ResponseReasoningSummary, ResponsesRequest,
+    ResponsesResponse, ToolCall, Usage,
+};
+
+/// Events of a streamed call, in the order the gateway sent them. Ends after
+/// `ResponseCompleted` or `ResponseError`.
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<ResponseEvent, ClientError>> + Send>>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("xrouter returned {status}: {message}")]
+    Api { status: u16, message: String, param: Option<String> },
+    #[error("unexpected response payload: {0}")]
+    Decode(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct XrouterClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl XrouterClient {
+    /// `base_url` includes the API prefix: `http://host/api/v1`, or `http://host/v1` when the
+    /// gateway runs with `ENABLE_OPENAI_COMPATIBLE_API`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Reuses a preconfigured client (timeouts, proxies, connection pool).
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub async fn responses(
+        &self,
+        request: &ResponsesRequest,
+    ) -> Result<ResponsesResponse, ClientError> {
+        let request = ResponsesRequest { stream: false, ..request.clone() };
+        decode_json(self.post("responses", &request).await?).await
+    }
+
+    pub async fn chat_completions(
+        &self,
+        request: &ChatCompletionsRequest,
+    ) -> Result<ChatCompletionsResponse, ClientError> {
+        let request = ChatCompletionsRequest { stream: false, ..request.clone() };
+        decode_json(self.post("chat/completions", &request).await?).await
+    }
+
+    pub async fn responses_stream(
+        &self,
+        request: &ResponsesRequest,
+    ) -> Result<EventStream, ClientError> {
+        let request = ResponsesRequest { stream: true, ..request.clone() };
+        let response = self.post("responses", &request).await?;
+        Ok(event_stream(response, Translator::Responses { id: String::new() }))
+    }
+
+    /// Chat completion chunks mapped onto [`ResponseEvent`]s. Chat streams carry no token
+    /// counts, so the final `ResponseCompleted` reports zero usage.
+    pub async fn chat_completions_stream(
+        &self,
+        request: &ChatCompletionsRequest,
+    ) -> Result<EventStream, ClientError> {
+        let request = ChatCompletionsRequest { stream: true, ..request.clone() };
+        let response = self.post("chat/completions", &request).await?;
+        Ok(event_stream(response, Translator::Chat(ChatState::default())))
+    }
+
+    async fn post(
+        &self,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<reqwest::Response, ClientError> {
+        let mut request = self.http.post(format!("{}/{path}", self.base_url)).json(body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+        Err(match serde_json::from_str::<ErrorBody>(&body) {
+            Ok(error) => ClientError::Api { status, message: error.error, param: error.param },
+            Err(_) => ClientError::Api { status, message: body, param: None },
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+    #[serde(default)]
+    param: Option<String>,
+}
+
+async fn decode_json<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, ClientError> {
+    let body = response.bytes().await?;
+    serde_json::from_slice(&body).map_err(|error| ClientError::Decode(error.to_string()))
+}
+
+struct StreamState {
+    body: Pin<Box<dyn Stream<Item = reqwest::Result<bytes::Bytes>> + Send>>,
+    decoder: SseDecoder,
+    translator: Translator,
+    pending: VecDeque<Result<ResponseEvent, ClientError>>,
+    finished: bool,
+}
+
+fn event_stream(response: reqwest::Response, translator: Translator) -> EventStream {
+    let state = StreamState {
+        body: Box::pin(response.bytes_stream()),
+        decoder: SseDecoder::default(),
+        translator,
+        pending: VecDeque::new(),
+        finished: false,
+    };
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.pending.pop_front() {
+                if is_terminal(&event) {
+                    state.finished = true;
+                    state.pending.clear();
+                }
+                return Some((event, state));
+            }
+            if state.finished {
+                return None;
+            }
+            let frames = match state.body.next().await {
+                Some(Ok(chunk)) => state.decoder.push(&chunk),
+                Some(Err(error)) => {
+                    state.pending.push_back(Err(error.into()));
+                    continue;
+                }
+                None => {
+                    state.finished = true;
+                    state.decoder.finish().into_iter().collect()
+                }
+            };
+            for data in frames {
+                if let Some(event) = state.translator.event(&data).transpose() {
+                    state.pending.push_back(event);
+                }
+            }
+            if state.finished && state.pending.iter().all(|event| !is_terminal(event)) {
+                state.pending.push_back(Err(ClientError::Decode(
+                    "stream ended before the response completed".to_string(),
+                )));
+            }
+        }
+    }))
+}
+
+fn is_terminal(event: &Result<ResponseEvent, ClientError>) -> bool {
+    matches!(
+        event,
+        Ok(ResponseEvent::ResponseCompleted { .. } | ResponseEvent::ResponseError { .. }) | Err(_)
+    )
+}
+
+/// Splits an SSE body into the `data` payloads of its events.
+#[derive(Debug, Default)]
+struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend(bytes.iter().filter(|byte| **byte != b'\r'));
+        let mut events = Vec::new();
+        while let Some(offset) = self.buffer.windows(2).position(|window| window == b"\n\n") {
+            let frame = self.buffer.drain(..offset + 2).collect::<Vec<_>>();
+            events.extend(frame_data(&frame));
+        }
+        events
+    }
+
+    fn finish(&mut self) -> Option<String> {
+        frame_data(&std::mem::take(&mut self.buffer))
+    }
+}
+
+fn frame_data(frame: &[u8]) -> Option<String> {
+    let frame = String::from_utf8_lossy(frame);
+    let lines = frame
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect::<Vec<_>>();
+    if lines.is_empty() { None } else { Some(lines.join("\n")) }
+}
+
+/// Maps wire payloads of either inference API onto [`ResponseEvent`]s.
+enum Translator {
+    Responses { id: String },
+    Chat(ChatState),
+}
+
+#[derive(Default)]
+struct ChatState {
+    text: String,
+    reasoning: String,
+}
+
+#[derive(Deserialize)]
+struct CompletedResponse {
+    id: String,
+    output: Vec<ResponseOutputItem>,
+    finish_reason: String,
+    usage: Usage,
+    #[serde(default)]
+    meta: Option<ResponseMeta>,
+}
+
+#[derive(Deserialize)]
+struct ChatChunk {
+    id: String,
+    #[serde(default)]
+    choices: Vec<ChatChunkChoice>,
+    #[serde(default)]
+    meta: Option<ResponseMeta>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatChunkChoice {
+    #[serde(default)]
+    delta: ChatChunkDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct ChatChunkDelta {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    tool_calls: Option<Vec<ToolCall>>,
+}
+
+impl Translator {
+    fn event(&mut self, data: &str) -> Result<Option<ResponseEvent>, ClientError> {
+        if data == "[DONE]" {
+            return Ok(None);
+        }
+        match self {
+            Self::Responses { id } => responses_event(id, data),
+            Self::Chat(state) => chat_event(state, data),
+        }
+    }
+}
+
+fn responses_event(id: &mut String, data: &str) -> Result<Option<ResponseEvent>, ClientError> {
+    let mut payload = parse::<Value>(data)?;
+    let text = |payload: &Value, field: &str| {
+        payload.get(field).and_then(Value::as_str).unwrap_or_default().to_string()
+    };
+    Ok(match payload.get("type").and_then(Value::as_str) {
+        Some("response.created") => {
+            *id = payload.pointer("/response/id").and_then(Value::as_str).unwrap_or("").into();
+            None
+        }
+        Some("response.output_text.delta") => {
+            Some(ResponseEvent::OutputTextDelta { id: id.clone(), delta: text(&payload, "delta") })
+        }
+        Some("response.reasoning.delta") => {
+            Some(ResponseEvent::ReasoningDelta { id: id.clone(), delta: text(&payload, "delta") })
+        }
+        Some("response.completed") => {
+            let response = serde_json::from_value::<CompletedResponse>(payload["response"].take())
+                .map_err(|error| ClientError::Decode(error.to_string()))?;
+            Some(ResponseEvent::ResponseCompleted {
+                id: response.id,
+                output: response.output,
+                finish_reason: response.finish_reason,
+                usage: response.usage,
+                meta: response.meta,
+            })
+        }
+        Some("response.error") => {
+            Some(ResponseEvent::ResponseError { id: id.clone(), message: text(&payload, "error") })
+        }
+        _ => None,
+    })
+}
+
+fn chat_event(state: &mut ChatState, data: &str) -> Result<Option<ResponseEvent>, ClientError> {
+    let chunk = parse::<ChatChunk>(data)?;
+    if let Some(message) = chunk.error {
+        return Ok(Some(ResponseEvent::ResponseError { id: chunk.id, message }));
+    }
+    let Some(choice) = chunk.choices.into_iter().next() else {
+        return Ok(None);
+    };
+    if let Some(finish_reason) = choice.finish_reason {
+        let mut output = Vec::new();
+        if !state.reasoning.is_empty() {
+            output.push(ResponseOutputItem::Reasoning {
+                id: "rs_0".to_string(),
+                summary: vec![ResponseReasoningSummary {
+                    text: std::mem::take(&mut state.reasoning),
+                }],
+                content: Vec::new(),
+                encrypted_content: None,
+            });
+        }
+        let text = std::mem::take(&mut state.text) + choice.delta.content.as_deref().unwrap_or("");
+        if !text.is_empty() {
+            output.push(ResponseOutputItem::Message {
+                id: "msg_0".to_string(),
+                role: "assistant".to_string(),
+                content: vec![ResponseOutputText {
+                    kind: "output_text".to_string(),
+                    text,
+                    annotations: Vec::new(),
+                }],
+            });
+        }
+        output.extend(choice.delta.tool_calls.into_iter().flatten().map(|call| {
+            ResponseOutputItem::FunctionCall {
+                id: call.id.clone(),
+                call_id: call.id,
+                name: call.function.name,
+                arguments: call.function.arguments,
+            }
+        }));
+        return Ok(Some(ResponseEvent::ResponseCompleted {
+            id: chunk.id,
+            output,
+            finish_reason,
+            usage: Usage {
+                input_tokens: 0,
+                output_tokens: 0,
+                total_tokens: 0,
+                input_tokens_details: None,
+                output_tokens_details: None,
+            },
+            meta: chunk.meta,
+        }));
+    }
+    if let Some(delta) = choice.delta.reasoning_content.filter(|delta| !delta.is_empty()) {
+        state.reasoning.push_str(&delta);
+        return Ok(Some(ResponseEvent::ReasoningDelta { id: chunk.id, delta }));
+    }
+    if let Some(delta) = choice.delta.content.filter(|delta| !delta.is_empty()) {
+        state.text.push_str(&delta);
+        return Ok(Some(ResponseEvent::OutputTextDelta { id: chunk.id, delta }));
+    }
+    Ok(None)
+}
+
+fn parse<T: DeserializeOwned>(data: &str) -> Result<T, ClientError> {
+    serde_json::from_str(data).map_err(|error| ClientError::Decode(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use serde_json::json;
+    use xrouter_app::{AppBuilder, config::AppConfig};
+    use xrouter_contracts::{ResponseEvent, ResponseOutputItem};
+
+    use super::{ClientError, SseDecoder, XrouterClient};
+
+    async fn serve(router: axum::Router) -> String {
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("listener must bind");
+        let address = listener.local_addr().expect("listener must have an address");
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        format!("http://{address}")
+    }
+
+    /// Gateway whose `openrouter` upstream streams a fixed "Hello, world" completion. Tests run
+    /// multi-threaded because building the gateway fetches that upstream's model catalog with a
+    /// blocking client.
+    async fn spawn_gateway() -> XrouterClient {
+        let chunks = [
+            json!({"choices": [{"index": 0, "delta": {"content": "Hello"}}]}),
+            json!({"choices": [{"index": 0, "delta": {"content": ", world"}}]}),
+            json!({
+                "choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 2, "completion_tokens": 3, "total_tokens": 5}
+            }),
+        ];
+        let body = chunks.iter().map(|chunk| format!("data: {chunk}\n\n")).collect::<String>()
+            + "data: [DONE]\n\n";
+        let upstream = axum::Router::new().route(
+            "/chat/completions",
+            axum::routing::post(move || async move {
+                ([(axum::http::header::CONTENT_TYPE, "text/event-stream")], body)
+            }),
+        );
+        let mut config = AppConfig::for_tests();
+        let openrouter = config.providers.get_mut("openrouter").expect("openrouter provider");
+        openrouter.base_url = Some(serve(upstream).await);
+        openrouter.api_key = Some("test-key".to_string());
+        let gateway = serve(AppBuilder::new(&config).build_router()).await;
+        XrouterClient::new(format!("{gateway}/api/v1/"))
+    }
+
+    fn message_text(output: &[ResponseOutputItem]) -> String {
+        output
+            .iter()
+            .filter_map(|item| match item {
+                ResponseOutputItem::Message { content, .. } => {
+                    Some(content.iter().map(|part| part.text.as_str()).collect::<String>())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    async fn collect_stream(mut events: super::EventStream) -> (String, ResponseEvent) {
+        let mut text = String::new();
+        while let Some(event) = events.next().await {
+            match event.expect("stream event") {
+                ResponseEvent::OutputTextDelta { delta, .. } => text.push_str(&delta),
+                ResponseEvent::ReasoningDelta { .. } => {}
+                last => {
+                    assert!(events.next().await.is_none(), "stream ends after {last:?}");
+                    return (text, last);
+                }
+            }
+        }
+        panic!("stream ended without a terminal event");
+    }
+
+    #[test]
+    fn sse_decoder_joins_frames_split_across_chunks() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"event: response.created\r\ndata: {\"a\":").is_empty());
+        assert_eq!(decoder.push(b"1}\r\n\r\n: ping\n\ndata: [DONE]"), vec!["{\"a\":1}"]);
+        assert_eq!(decoder.finish().as_deref(), Some("[DONE]"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn responses_returns_the_typed_response() {
+        let client = spawn_gateway().await;
+        let request = serde_json::from_value(json!({
+            "model": "openrouter/anthropic/claude-3.5-sonnet",
+            "input": "hello world",
+            "stream": true
+        }))
+        .expect("request");
+
+        let response = client.responses(&request).await.expect("response");
+
+        assert_eq!(message_text(&response.output), "Hello, world");
+        assert!(response.id.starts_with("resp_"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn responses_stream_deltas_add_up_to_the_completed_output() {
+        let client = spawn_gateway().await;
+        let request = serde_json::from_value(json!({
+            "model": "openrouter/anthropic/claude-3.5-sonnet",
+            "input": "hello world"
+        }))
+        .expect("request");
+
+        let events = client.responses_stream(&request).await.expect("stream");
+        let (text, last) = collect_stream(events).await;
+
+        let ResponseEvent::ResponseCompleted { id, output, usage, .. } = last else {
+            panic!("expected completion, got {last:?}");
+        };
+        assert_eq!(text, message_text(&output));
+        assert_eq!(text, "Hello, world");
+        assert!(id.starts_with("resp_"));
+        assert!(usage.total_tokens > 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn chat_completions_stream_maps_chunks_onto_response_events() {
+        let client = spawn_gateway().await;
+        let request = serde_json::from_value(json!({
+            "model": "openrouter/anthropic/claude-3.5-sonnet",
+            "messages": [{"role": "user", "content": "hello world"}]
+        }))
+        .expect("request");
+
+        let completion = client.chat_completions(&request).await.expect("completion");
+        let events = client.chat_completions_stream(&request).await.expect("stream");
+        let (text, last) = collect_stream(events).await;
+
+        assert_eq!(completion.choices[0].message.content.to_text(), text);
+        let ResponseEvent::ResponseCompleted { output, finish_reason, .. } = last else {
+            panic!("expected completion, got {last:?}");
+        };
+        assert_eq!(message_text(&output), text);
+        assert_eq!(finish_reason, completion.choices[0].finish_reason);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failures_surface_as_api_errors_or_terminal_stream_errors() {
+        let client = spawn_gateway().await;
+        let request = serde_json::from_value(json!({
+            "model": "openrouter/anthropic/claude-3.5-sonnet",
+            "input": ""
+        }))
+        .expect("request");
+
+        let error = client.responses(&request).await.expect_err("empty input is rejected");
+        assert!(matches!(error, ClientError::Api { status: 400, .. }), "{error:?}");
+        let events = client.responses_stream(&request).await.expect("stream starts");
+        let (text, last) = collect_stream(events).await;
+        assert!(text.is_empty());
+        assert!(matches!(last, ResponseEvent::ResponseError { .. }), "{last:?}");
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use xrouter_contracts::ResponseEvent;
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome, ResponseEventSink,
+};
+
+/// Faults injected by `ChaosProviderClient`. Probabilities are in `0.0..=1.0` and rolled
+/// independently per request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChaosPolicy {
+    /// Providers the policy applies to; empty means every provider.
+    pub providers: Vec<String>,
+    pub latency_ms: u64,
+    pub latency_probability: f64,
+    pub error_status: u16,
+    pub error_probability: f64,
+    /// Cuts a stream after its first delta, as if the upstream connection dropped.
+    pub disconnect_probability: f64,
+    /// Fails a stream after its first delta with an SSE parse error.
+    pub malformed_frame_probability: f64,
+}
+
+impl Default for ChaosPolicy {
+    fn default() -> Self {
+        Self {
+            providers: Vec::new(),
+            latency_ms: 0,
+            latency_probability: 0.0,
+            error_status: 503,
+            error_probability: 0.0,
+            disconnect_probability: 0.0,
+            malformed_frame_probability: 0.0,
+        }
+    }
+}
+
+impl ChaosPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        let probabilities = [
+            ("latency_probability", self.latency_probability),
+            ("error_probability", self.error_probability),
+            ("disconnect_probability", self.disconnect_probability),
+            ("malformed_frame_probability", self.malformed_frame_probability),
+        ];
+        if let Some((name, _)) =
+            probabilities.iter().find(|(_, value)| !(0.0..=1.0).contains(value))
+        {
+            return Err(format!("{name} must be between 0 and 1"));
+        }
+        if !(400..=599).contains(&self.error_status) {
+            return Err("error_status must be a 4xx or 5xx status".to_string());
+        }
+        Ok(())
+    }
+
+    fn applies_to(&self, provider: &str) -> bool {
+        self.providers.is_empty() || self.providers.iter().any(|name| name == provider)
+    }
+}
+
+pub type SharedChaosPolicy = Arc<RwLock<ChaosPolicy>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamFault {
+    Disconnect,
+    MalformedFrame,
+}
+
+/// Decorator that injects latency, error statuses and broken streams into `inner` according
+/// to a policy that can be changed while the server runs. Meant for staging only.
+pub struct ChaosProviderClient {
+    provider_id: String,
+    inner: Arc<dyn ProviderClient>,
+    policy: SharedChaosPolicy,
+}
+
+impl ChaosProviderClient {
+    pub fn new(
+        provider_id: String,
+        inner: Arc<dyn ProviderClient>,
+        policy: SharedChaosPolicy,
+    ) -> Self {
+        Self { provider_id, inner, policy }
+    }
+
+    fn policy(&self) -> Option<ChaosPolicy> {
+        let policy = self.policy.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        policy.applies_to(&self.provider_id).then_some(policy)
+    }
+
+    /// Applies latency and error-status faults; returns the stream fault to inject, if any.
+    async fn before_call(&self, policy: &ChaosPolicy) -> Result<Option<StreamFault>, CoreError> {
+        if policy.latency_ms > 0 && roll(policy.latency_probability) {
+            warn!(
+                event = "provider.chaos.latency",
+                provider = %self.provider_id,
+                latency_ms = policy.latency_ms
+            );
+            tokio::time::sleep(Duration::from_millis(policy.latency_ms)).await;
+        }
+        if roll(policy.error_probability) {
+            warn!(
+                event = "provider.chaos.error_status",
+                provider = %self.provider_id,
+                status = policy.error_status
+            );
+            let status = reqwest::StatusCode::from_u16(policy.error_status)
+                .unwrap_or(reqwest::StatusCode::SERVICE_UNAVAILABLE);
+            let reason = status.canonical_reason().unwrap_or("Unknown");
+            return Err(CoreError::Provider(format!(
+                "provider returned error status: {status} ({reason}) (chaos injection)"
+            )));
+        }
+        if roll(policy.disconnect_probability) {
+            return Ok(Some(StreamFault::Disconnect));
+        }
+        if roll(policy.malformed_frame_probability) {
+            return Ok(Some(StreamFault::MalformedFrame));
+        }
+        Ok(None)
+    }
+
+    fn stream_fault_error(&self, fault: StreamFault) -> CoreError {
+        warn!(event = "provider.chaos.stream_fault", provider = %self.provider_id, fault = ?fault);
+        CoreError::Provider(match fault {
+            StreamFault::Disconnect => {
+                "provider stream read failed: connection closed mid-stream (chaos injection)"
+                    .to_string()
+            }
+            StreamFault::MalformedFrame => {
+                "provider stream parse failed: malformed SSE frame (chaos injection)".to_string()
+            }
+        })
+    }
+}
+
+fn roll(probability: f64) -> bool {
+    if probability <= 0.0 {
+        return false;
+    }
+    let sample = (uuid::Uuid::new_v4().as_u128() as u64 >> 11) as f64 / (1u64 << 53) as f64;
+    sample < probability
+}
+
+/// Forwards the first delta, then reports the output as closed so the inner client stops
+/// reading the upstream stream.
+struct CuttingSink<'a> {
+    inner: &'a dyn ResponseEventSink,
+    forwarded: AtomicUsize,
+    cut: AtomicBool,
+}
+
+#[async_trait]
+impl ResponseEventSink for CuttingSink<'_> {
+    async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+        if self.cut.load(Ordering::Relaxed) {
+            return;
+        }
+        if self.forwarded.fetch_add(1, Ordering::Relaxed) >= 1 {
+            self.cut.store(true, Ordering::Relaxed);
+            return;
+        }
+        self.inner.send(event).await;
+    }
+
+    fn output_closed(&self) -> bool {
+        self.cut.load(Ordering::Relaxed) || self.inner.output_closed()
+    }
+
+    fn retained_output_limit(&self) -> Option<usize> {
+        self.inner.retained_output_limit()
+    }
+}
+
+#[async_trait]
+impl ProviderClient for ChaosProviderClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let Some(policy) = self.policy() else {
+            return self.inner.generate(request).await;
+        };
+        match self.before_call(&policy).await? {
+            Some(fault) => Err(self.stream_fault_error(fault)),
+            None => self.inner.generate(request).await,
+        }
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let Some(policy) = self.policy() else {
+            return self.inner.generate_stream(request).await;
+        };
+        let Some(fault) = self.before_call(&policy).await? else {
+            return self.inner.generate_stream(request).await;
+        };
+        let Some(sender) = request.sender else {
+            return Err(self.stream_fault_error(fault));
+        };
+        let cutting = CuttingSink {
+            inner: sender,
+            forwarded: AtomicUsize::new(0),
+            cut: AtomicBool::new(false),
+        };
+        self.inner
+            .generate_stream(ProviderGenerateStreamRequest {
+                request_id: request.request_id,
+                request: request.request,
+                sender: Some(&cutting),
+            })
+            .await?;
+        Err(self.stream_fault_error(fault))
+    }
+
+    fn supports_required_tool_choice(&self) -> bool {
+        self.inner.supports_required_tool_choice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex, RwLock};
+
+    use async_trait::async_trait;
+    use xrouter_contracts::{ResponseEvent, ResponsesInput};
+    use xrouter_core::{
+        CoreError, ExecutionControls, ProviderClient, ProviderGenerateRequest,
+        ProviderGenerateStreamRequest, ResponseEventSink,
+    };
+
+    use super::{ChaosPolicy, ChaosProviderClient};
+    use crate::MockProviderClient;
+
+    #[derive(Default)]
+    struct CollectingSink {
+        events: Mutex<Vec<ResponseEvent>>,
+    }
+
+    #[async_trait]
+    impl ResponseEventSink for CollectingSink {
+        async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+            if let Ok(event) = event {
+                self.events.lock().expect("sink lock").push(event);
+            }
+        }
+    }
+
+    fn chaos(policy: ChaosPolicy) -> ChaosProviderClient {
+        ChaosProviderClient::new(
+            "zai".to_string(),
+            Arc::new(MockProviderClient::new("zai".to_string())),
+            Arc::new(RwLock::new(policy)),
+        )
+    }
+
+    fn request(input: &ResponsesInput) -> ProviderGenerateRequest<'_> {
+        ProviderGenerateRequest {
+            model: "glm-4.5",
+            instructions: None,
+            input,
+            reasoning: None,
+            tools: None,
+            tool_choice: None,
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra: None,
+            controls: ExecutionControls::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn chaos_injects_error_status_only_for_targeted_providers() {
+        let input = ResponsesInput::Text("hello world".to_string());
+        let failing = chaos(ChaosPolicy {
+            error_probability: 1.0,
+            error_status: 502,
+            ..ChaosPolicy::default()
+        });
+        assert_eq!(
+            failing.generate(request(&input)).await,
+            Err(CoreError::Provider(
+                "provider returned error status: 502 Bad Gateway (Bad Gateway) (chaos injection)"
+                    .to_string()
+            ))
+        );
+
+        let other_provider = chaos(ChaosPolicy {
+            providers: vec!["deepseek".to_string()],
+            error_probability: 1.0,
+            ..ChaosPolicy::default()
+        });
+        assert!(other_provider.generate(request(&input)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn chaos_disconnect_cuts_the_stream_after_the_first_delta() {
+        let input = ResponsesInput::Text("one two three".to_string());
+        let client = chaos(ChaosPolicy { disconnect_probability: 1.0, ..ChaosPolicy::default() });
+        let sink = CollectingSink::default();
+
+        let result = client
+            .generate_stream(ProviderGenerateStreamRequest {
+                request_id: "req_1",
+                request: request(&input),
+                sender: Some(&sink),
+            })
+            .await;
+
+        assert!(
+            matches!(result, Err(CoreError::Provider(message)) if message.contains("connection closed mid-stream"))
+        );
+        assert_eq!(sink.events.lock().expect("sink lock").len(), 1);
+    }
+
+    #[test]
+    fn chaos_policy_rejects_out_of_range_values() {
+        assert!(ChaosPolicy::default().validate().is_ok());
+        assert!(
+            ChaosPolicy { error_probability: 1.5, ..ChaosPolicy::default() }.validate().is_err()
+        );
+        assert!(ChaosPolicy { error_status: 200, ..ChaosPolicy::default() }.validate().is_err());
+    }
+}
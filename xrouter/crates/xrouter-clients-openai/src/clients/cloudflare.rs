@@ -0,0 +1,316 @@
+use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::Client;
+use serde_json::{Map, Value};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use uuid::Uuid;
+use xrouter_contracts::{ResponsesRequest, ToolCall, ToolFunction};
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome, ProviderUsage,
+};
+
+use crate::parser::{
+    ContentBuffer, StreamAccumulator, StreamDelta, estimate_output_tokens, native_usage_from_value,
+};
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    compact_payload,
+};
+use crate::runtime::SharedProviderRuntime;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::HttpRuntime;
+
+/// Cloudflare Workers AI through its native REST API, where the model is part of an
+/// account-scoped URL: `accounts/{account_id}/ai/run/@cf/...`.
+pub struct CloudflareClient {
+    runtime: SharedProviderRuntime,
+    account_id: Option<String>,
+}
+
+impl CloudflareClient {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(
+        base_url: Option<String>,
+        api_key: Option<String>,
+        account_id: Option<String>,
+        http_client: Option<Client>,
+        max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
+    ) -> Self {
+        Self::with_runtime(
+            Arc::new(
+                HttpRuntime::new(
+                    "cloudflare".to_string(),
+                    base_url,
+                    api_key,
+                    http_client,
+                    max_inflight,
+                    transcripts,
+                )
+                .with_stream_accumulator(|| Box::<CloudflareStreamAccumulator>::default()),
+            ),
+            account_id,
+        )
+    }
+
+    pub fn with_runtime(runtime: SharedProviderRuntime, account_id: Option<String>) -> Self {
+        Self { runtime, account_id }
+    }
+
+    fn run_url(&self, model: &str) -> Result<String, CoreError> {
+        let account_id =
+            self.account_id.as_deref().filter(|value| !value.trim().is_empty()).ok_or_else(
+                || CoreError::Provider("provider account_id is not configured".to_string()),
+            )?;
+        self.runtime.build_url(&run_path(account_id, model))
+    }
+}
+
+/// The run path with placeholders, for payload previews that know neither account nor model.
+pub(crate) const RUN_ENDPOINT: &str = "accounts/{account_id}/ai/run/{model}";
+
+pub(crate) fn run_path(account_id: &str, model: &str) -> String {
+    format!("accounts/{account_id}/ai/run/{model}")
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ProviderClient for CloudflareClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.run_url(request.model)?;
+        let payload = request_payload(&request);
+        self.runtime
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &[],
+                request.controls.upstream,
+                None,
+            )
+            .await
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.run_url(request.request.model)?;
+        let payload = request_payload(&request.request);
+        self.runtime
+            .post_chat_completions_stream(
+                request.request_id,
+                &url,
+                &payload,
+                request.request.auth_bearer,
+                &[],
+                request.request.controls.upstream,
+                request.sender,
+            )
+            .await
+    }
+}
+
+/// Text generation input: the model is named by the URL, so the body has no `model`, and
+/// `tool_choice` and reasoning options are not part of the schema.
+pub(crate) fn request_payload(request: &ProviderGenerateRequest<'_>) -> Value {
+    let mut payload = base_chat_payload(
+        &ResponsesRequest {
+            model: request.model.to_string(),
+            instructions: request.instructions.map(str::to_string),
+            previous_response_id: None,
+            input: request.input.clone(),
+            parallel_tool_calls: None,
+            stream: true,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: Map::new(),
+        },
+        request.tools,
+        None,
+    );
+    payload.remove("model");
+    let mut payload = Value::Object(payload);
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::JsonObjectOnly,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    payload
+}
+
+/// Reads Workers AI stream events: `{"response": "<text>"}` deltas, tool calls either in the
+/// model-native `{"name", "arguments"}` shape or the OpenAI one, and `usage` on the last event.
+#[derive(Debug, Default)]
+pub(crate) struct CloudflareStreamAccumulator {
+    content: ContentBuffer,
+    tool_calls: Vec<ToolCall>,
+    output_tokens: Option<u32>,
+    usage: Option<ProviderUsage>,
+}
+
+impl StreamAccumulator for CloudflareStreamAccumulator {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError> {
+        let mut delta = StreamDelta::default();
+        if data == "[DONE]" {
+            return Ok(delta);
+        }
+        let event: Value = serde_json::from_str(data)
+            .map_err(|err| CoreError::Provider(format!("provider stream parse failed: {err}")))?;
+        if let Some(text) = event.get("response").and_then(Value::as_str)
+            && !text.is_empty()
+        {
+            self.content.push(text);
+            delta.content.push(text.to_string());
+        }
+        for call in event.get("tool_calls").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(call) = map_tool_call(call) {
+                self.tool_calls.push(call);
+            }
+        }
+        if let Some(usage) = event.get("usage") {
+            self.output_tokens = usage
+                .get("completion_tokens")
+                .and_then(Value::as_u64)
+                .and_then(|tokens| u32::try_from(tokens).ok());
+            self.usage = native_usage_from_value(Some(usage));
+        }
+        Ok(delta)
+    }
+
+    fn limit_retained_content(&mut self, max_bytes: usize) {
+        self.content.set_limit(max_bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError> {
+        let Self { content, tool_calls, output_tokens, usage } = *self;
+        if content.is_empty() && tool_calls.is_empty() {
+            return Err(CoreError::Provider("provider returned empty message content".to_string()));
+        }
+        let dropped_words = content.dropped_words();
+        let text = content.into_text();
+        Ok(ProviderOutcome {
+            output_tokens: output_tokens
+                .unwrap_or_else(|| estimate_output_tokens(&text, dropped_words)),
+            chunks: vec![text],
+            reasoning: None,
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            annotations: None,
+            emitted_live: false,
+            finish_reason: None,
+            usage,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
+        })
+    }
+}
+
+/// Arguments arrive as a JSON object or as an already serialized string.
+fn map_tool_call(call: &Value) -> Option<ToolCall> {
+    let function = call.get("function").unwrap_or(call);
+    let name = function.get("name").and_then(Value::as_str).filter(|name| !name.is_empty())?;
+    let arguments = match function.get("arguments") {
+        Some(Value::String(arguments)) => arguments.clone(),
+        Some(arguments) if !arguments.is_null() => arguments.to_string(),
+        _ => "{}".to_string(),
+    };
+    Some(ToolCall {
+        id: call
+            .get("id")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("call_{}", Uuid::new_v4().simple())),
+        kind: "function".to_string(),
+        function: ToolFunction { name: name.to_string(), arguments },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use xrouter_contracts::ResponsesInput;
+    use xrouter_core::{ExecutionControls, ProviderGenerateRequest};
+
+    use super::{CloudflareStreamAccumulator, request_payload, run_path};
+    use crate::parser::StreamAccumulator;
+
+    #[test]
+    fn payloads_name_the_model_in_the_account_scoped_path_only() {
+        let input = ResponsesInput::Text("Say hi".to_string());
+        let extra = json!({"max_tokens": 64});
+        let request = ProviderGenerateRequest {
+            model: "@cf/meta/llama-3.1-8b-instruct",
+            instructions: Some("Be brief."),
+            input: &input,
+            reasoning: None,
+            tools: None,
+            tool_choice: Some(&json!("required")),
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra: extra.as_object(),
+            controls: ExecutionControls::default(),
+        };
+
+        let payload = request_payload(&request);
+        assert!(payload.get("model").is_none());
+        assert!(payload.get("tool_choice").is_none());
+        assert_eq!(payload["messages"][1], json!({"role": "user", "content": "Say hi"}));
+        assert_eq!(payload["max_tokens"], 64);
+        assert_eq!(
+            run_path("acc123", request.model),
+            "accounts/acc123/ai/run/@cf/meta/llama-3.1-8b-instruct"
+        );
+    }
+
+    #[test]
+    fn stream_events_collect_text_tool_calls_and_final_usage() {
+        let mut accumulator = Box::<CloudflareStreamAccumulator>::default();
+        let events = [
+            r#"{"response":"Hi","p":"abcdef"}"#,
+            r#"{"response":" there","p":"abc"}"#,
+            r#"{"response":"","tool_calls":[{"name":"lookup","arguments":{"city":"Oslo"}}]}"#,
+            r#"{"response":"","usage":{"prompt_tokens":12,"completion_tokens":5,"total_tokens":17}}"#,
+            "[DONE]",
+        ];
+        let deltas = events
+            .iter()
+            .flat_map(|event| accumulator.push_event(event).expect("event must parse").content)
+            .collect::<Vec<_>>();
+        assert_eq!(deltas, ["Hi", " there"]);
+
+        let outcome = accumulator.finish().expect("stream must finish");
+        assert_eq!(outcome.chunks, ["Hi there"]);
+        assert_eq!(outcome.output_tokens, 5);
+        assert_eq!(outcome.usage.and_then(|usage| usage.prompt_tokens), Some(12));
+        let calls = outcome.tool_calls.expect("tool call must be kept");
+        assert_eq!(calls[0].function.name, "lookup");
+        assert_eq!(calls[0].function.arguments, r#"{"city":"Oslo"}"#);
+    }
+}
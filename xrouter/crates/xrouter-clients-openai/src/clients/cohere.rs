@@ -0,0 +1,469 @@
+use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use xrouter_contracts::{
+    CitationSource, OutputAnnotation, ResponsesRequest, ToolCall, ToolFunction,
+};
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome, ProviderUsage,
+};
+
+use crate::parser::{
+    ContentBuffer, StreamAccumulator, StreamDelta, estimate_output_tokens, normalize_finish_reason,
+};
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    compact_payload,
+};
+use crate::runtime::SharedProviderRuntime;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::HttpRuntime;
+
+pub(crate) const CHAT_ENDPOINT: &str = "chat";
+
+/// Cohere Chat API v2. RAG `documents` are forwarded as a passthrough field, and the citations
+/// the model makes over them come back as output text annotations.
+pub struct CohereClient {
+    runtime: SharedProviderRuntime,
+}
+
+impl CohereClient {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(
+        base_url: Option<String>,
+        api_key: Option<String>,
+        http_client: Option<Client>,
+        max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
+    ) -> Self {
+        Self::with_runtime(Arc::new(
+            HttpRuntime::new(
+                "cohere".to_string(),
+                base_url,
+                api_key,
+                http_client,
+                max_inflight,
+                transcripts,
+            )
+            .with_stream_accumulator(|| Box::<CohereStreamAccumulator>::default()),
+        ))
+    }
+
+    pub fn with_runtime(runtime: SharedProviderRuntime) -> Self {
+        Self { runtime }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ProviderClient for CohereClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.runtime.build_url(CHAT_ENDPOINT)?;
+        let payload = request_payload(&request);
+        self.runtime
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &[],
+                request.controls.upstream,
+                None,
+            )
+            .await
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.runtime.build_url(CHAT_ENDPOINT)?;
+        let payload = request_payload(&request.request);
+        self.runtime
+            .post_chat_completions_stream(
+                request.request_id,
+                &url,
+                &payload,
+                request.request.auth_bearer,
+                &[],
+                request.request.controls.upstream,
+                request.sender,
+            )
+            .await
+    }
+}
+
+/// Chat API v2 body. Messages and tools share the OpenAI shapes; `tool_choice` only knows
+/// `REQUIRED` and `NONE`, and reasoning options are not sent.
+pub(crate) fn request_payload(request: &ProviderGenerateRequest<'_>) -> Value {
+    let mut payload = base_chat_payload(
+        &ResponsesRequest {
+            model: request.model.to_string(),
+            instructions: request.instructions.map(str::to_string),
+            previous_response_id: None,
+            input: request.input.clone(),
+            parallel_tool_calls: None,
+            stream: true,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: Map::new(),
+        },
+        request.tools,
+        None,
+    );
+    if let Some(choice) = request.tool_choice.and_then(cohere_tool_choice) {
+        payload.insert("tool_choice".to_string(), Value::String(choice.to_string()));
+    }
+    if let Some(Value::Array(messages)) = payload.get_mut("messages") {
+        for message in messages.iter_mut().filter_map(Value::as_object_mut) {
+            if message.get("content").is_some_and(Value::is_null) {
+                message.remove("content");
+            }
+        }
+    }
+    let mut payload = Value::Object(payload);
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::JsonObjectOnly,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    payload
+}
+
+/// `auto` is Cohere's default and has no name of its own.
+fn cohere_tool_choice(choice: &Value) -> Option<&'static str> {
+    match choice.as_str()? {
+        "required" => Some("REQUIRED"),
+        "none" => Some("NONE"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereStreamEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    delta: Option<CohereEventDelta>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CohereEventDelta {
+    #[serde(default)]
+    message: Option<Value>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+    #[serde(default)]
+    usage: Option<CohereUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereUsage {
+    #[serde(default)]
+    tokens: Option<CohereTokens>,
+    #[serde(default)]
+    billed_units: Option<CohereTokens>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct CohereTokens {
+    #[serde(default)]
+    input_tokens: Option<f64>,
+    #[serde(default)]
+    output_tokens: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereCitation {
+    #[serde(default)]
+    start: u32,
+    #[serde(default)]
+    end: u32,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    sources: Vec<CohereCitationSource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereCitationSource {
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    document: Option<Value>,
+    #[serde(default)]
+    tool_output: Option<Value>,
+}
+
+impl From<CohereCitation> for OutputAnnotation {
+    fn from(citation: CohereCitation) -> Self {
+        Self {
+            kind: "citation".to_string(),
+            start_index: citation.start,
+            end_index: citation.end,
+            text: citation.text,
+            sources: citation
+                .sources
+                .into_iter()
+                .map(|source| CitationSource {
+                    kind: source.kind.unwrap_or_else(|| "document".to_string()),
+                    id: source.id,
+                    data: source.document.or(source.tool_output),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Reads Chat API v2 stream events. Tool plans are reported as reasoning.
+#[derive(Debug, Default)]
+pub(crate) struct CohereStreamAccumulator {
+    content: ContentBuffer,
+    tool_plan: String,
+    tool_calls: Vec<ToolCall>,
+    citations: Vec<OutputAnnotation>,
+    finish_reason: Option<String>,
+    output_tokens: Option<u32>,
+    usage: Option<ProviderUsage>,
+    provider_response_id: Option<String>,
+}
+
+impl StreamAccumulator for CohereStreamAccumulator {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError> {
+        let mut delta = StreamDelta::default();
+        let event: CohereStreamEvent = serde_json::from_str(data)
+            .map_err(|err| CoreError::Provider(format!("provider stream parse failed: {err}")))?;
+        let CohereEventDelta { message, finish_reason, usage } = event.delta.unwrap_or_default();
+        let message = message.unwrap_or(Value::Null);
+        match event.kind.as_str() {
+            "message-start" => {
+                self.provider_response_id = event.id.filter(|id| !id.trim().is_empty());
+            }
+            "content-delta" => {
+                if let Some(text) = message.pointer("/content/text").and_then(Value::as_str)
+                    && !text.is_empty()
+                {
+                    self.content.push(text);
+                    delta.content.push(text.to_string());
+                }
+            }
+            "tool-plan-delta" => {
+                if let Some(plan) = message.get("tool_plan").and_then(Value::as_str)
+                    && !plan.is_empty()
+                {
+                    self.tool_plan.push_str(plan);
+                    delta.reasoning = Some(plan.to_string());
+                }
+            }
+            "tool-call-start" => {
+                let call = &message["tool_calls"];
+                self.tool_calls.push(ToolCall {
+                    id: call["id"].as_str().unwrap_or_default().to_string(),
+                    kind: "function".to_string(),
+                    function: ToolFunction {
+                        name: call
+                            .pointer("/function/name")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        arguments: call
+                            .pointer("/function/arguments")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                    },
+                });
+            }
+            "tool-call-delta" => {
+                if let (Some(call), Some(arguments)) = (
+                    self.tool_calls.last_mut(),
+                    message.pointer("/tool_calls/function/arguments").and_then(Value::as_str),
+                ) {
+                    call.function.arguments.push_str(arguments);
+                }
+            }
+            "citation-start" => {
+                if let Ok(citation) =
+                    serde_json::from_value::<CohereCitation>(message["citations"].clone())
+                {
+                    self.citations.push(citation.into());
+                }
+            }
+            "message-end" => {
+                self.finish_reason = finish_reason.as_deref().and_then(normalize_finish_reason);
+                if let Some(tokens) = usage.and_then(|usage| usage.tokens.or(usage.billed_units)) {
+                    self.output_tokens = tokens.output_tokens.map(|tokens| tokens as u32);
+                    self.usage = Some(ProviderUsage {
+                        prompt_tokens: tokens.input_tokens.map(|tokens| tokens as u32),
+                        ..ProviderUsage::default()
+                    });
+                }
+            }
+            _ => {}
+        }
+        Ok(delta)
+    }
+
+    fn limit_retained_content(&mut self, max_bytes: usize) {
+        self.content.set_limit(max_bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError> {
+        let Self {
+            content,
+            tool_plan,
+            tool_calls,
+            citations,
+            finish_reason,
+            output_tokens,
+            usage,
+            provider_response_id,
+        } = *self;
+        if content.is_empty() && tool_calls.is_empty() {
+            return Err(CoreError::Provider("provider returned empty message content".to_string()));
+        }
+        let dropped_words = content.dropped_words();
+        let text = content.into_text();
+        Ok(ProviderOutcome {
+            output_tokens: output_tokens
+                .unwrap_or_else(|| estimate_output_tokens(&text, dropped_words)),
+            chunks: vec![text],
+            reasoning: (!tool_plan.trim().is_empty()).then_some(tool_plan),
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            annotations: (!citations.is_empty()).then_some(citations),
+            emitted_live: false,
+            finish_reason,
+            usage,
+            upstream_attempts: 1,
+            provider_response_id,
+            upstream_provider: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use xrouter_contracts::ResponsesInput;
+    use xrouter_core::{ExecutionControls, ProviderGenerateRequest};
+
+    use super::{CohereStreamAccumulator, request_payload};
+    use crate::parser::StreamAccumulator;
+
+    #[test]
+    fn payloads_forward_documents_and_map_tool_choice() {
+        let input = ResponsesInput::Text("Where do penguins live?".to_string());
+        let tools = vec![json!({"type": "function", "function": {"name": "search"}})];
+        let extra = json!({"documents": [{"id": "doc-1", "data": {"text": "Emperor penguins live in Antarctica."}}]});
+        let request = ProviderGenerateRequest {
+            model: "command-a-03-2025",
+            instructions: None,
+            input: &input,
+            reasoning: None,
+            tools: Some(&tools),
+            tool_choice: Some(&json!("required")),
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra: extra.as_object(),
+            controls: ExecutionControls::default(),
+        };
+
+        let payload = request_payload(&request);
+        assert_eq!(payload["tool_choice"], "REQUIRED");
+        assert_eq!(payload["documents"][0]["id"], "doc-1");
+        assert_eq!(
+            payload["messages"][0],
+            json!({"role": "user", "content": "Where do penguins live?"})
+        );
+        assert!(payload.get("reasoning").is_none());
+    }
+
+    #[test]
+    fn stream_events_map_citations_to_annotations() {
+        let mut accumulator = Box::<CohereStreamAccumulator>::default();
+        let events = [
+            r#"{"id":"c0ffee","type":"message-start","delta":{"message":{"role":"assistant"}}}"#,
+            r#"{"type":"content-delta","index":0,"delta":{"message":{"content":{"text":"Emperor penguins "}}}}"#,
+            r#"{"type":"content-delta","index":0,"delta":{"message":{"content":{"text":"live in Antarctica."}}}}"#,
+            r#"{"type":"citation-start","index":0,"delta":{"message":{"citations":{"start":25,"end":35,"text":"Antarctica","sources":[{"type":"document","id":"doc-1","document":{"id":"doc-1","text":"Emperor penguins live in Antarctica."}}]}}}}"#,
+            r#"{"type":"citation-end","index":0}"#,
+            r#"{"type":"message-end","delta":{"finish_reason":"COMPLETE","usage":{"billed_units":{"input_tokens":40,"output_tokens":6},"tokens":{"input_tokens":512,"output_tokens":6}}}}"#,
+        ];
+        let deltas = events
+            .iter()
+            .flat_map(|event| accumulator.push_event(event).expect("event must parse").content)
+            .collect::<Vec<_>>();
+        assert_eq!(deltas, ["Emperor penguins ", "live in Antarctica."]);
+
+        let outcome = accumulator.finish().expect("stream must finish");
+        assert_eq!(outcome.chunks, ["Emperor penguins live in Antarctica."]);
+        assert_eq!(outcome.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(outcome.output_tokens, 6);
+        assert_eq!(outcome.usage.and_then(|usage| usage.prompt_tokens), Some(512));
+        assert_eq!(outcome.provider_response_id.as_deref(), Some("c0ffee"));
+
+        let annotations = outcome.annotations.expect("citations must be kept");
+        assert_eq!((annotations[0].start_index, annotations[0].end_index), (25, 35));
+        assert_eq!(annotations[0].text, "Antarctica");
+        assert_eq!(annotations[0].sources[0].kind, "document");
+        assert_eq!(annotations[0].sources[0].id.as_deref(), Some("doc-1"));
+        assert_eq!(
+            annotations[0].sources[0].data.as_ref().and_then(|data| data.get("text")),
+            Some(&json!("Emperor penguins live in Antarctica."))
+        );
+    }
+
+    #[test]
+    fn stream_events_assemble_tool_calls_and_tool_plans() {
+        let mut accumulator = Box::<CohereStreamAccumulator>::default();
+        for event in [
+            r#"{"type":"tool-plan-delta","delta":{"message":{"tool_plan":"I will search."}}}"#,
+            r#"{"type":"tool-call-start","index":0,"delta":{"message":{"tool_calls":{"id":"search_1","type":"function","function":{"name":"search","arguments":""}}}}}"#,
+            r#"{"type":"tool-call-delta","index":0,"delta":{"message":{"tool_calls":{"function":{"arguments":"{\"query\":"}}}}}"#,
+            r#"{"type":"tool-call-delta","index":0,"delta":{"message":{"tool_calls":{"function":{"arguments":"\"penguins\"}"}}}}}"#,
+            r#"{"type":"tool-call-end","index":0}"#,
+            r#"{"type":"message-end","delta":{"finish_reason":"TOOL_CALL"}}"#,
+        ] {
+            accumulator.push_event(event).expect("event must parse");
+        }
+
+        let outcome = accumulator.finish().expect("stream must finish");
+        let calls = outcome.tool_calls.expect("tool call must be assembled");
+        assert_eq!(calls[0].id, "search_1");
+        assert_eq!(calls[0].function.arguments, r#"{"query":"penguins"}"#);
+        assert_eq!(outcome.reasoning.as_deref(), Some("I will search."));
+        assert_eq!(outcome.finish_reason.as_deref(), Some("tool_calls"));
+    }
+}
@@ -13,9 +13,14 @@ use xrouter_core::{
     ProviderOutcome,
 };
 
-use crate::protocol::base_chat_payload;
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    compact_payload,
+};
 use crate::runtime::SharedProviderRuntime;
 #[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::transport::HttpRuntime;
 
 pub struct DeepSeekClient {
@@ -29,6 +34,7 @@ impl DeepSeekClient {
         api_key: Option<String>,
         http_client: Option<Client>,
         max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
     ) -> Self {
         Self::with_runtime(Arc::new(HttpRuntime::new(
             "deepseek".to_string(),
@@ -36,6 +42,7 @@ impl DeepSeekClient {
             api_key,
             http_client,
             max_inflight,
+            transcripts,
         )))
     }
 
@@ -52,14 +59,7 @@ impl ProviderClient for DeepSeekClient {
         request: ProviderGenerateRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_deepseek_payload(
-            request.model,
-            request.instructions,
-            request.input,
-            request.reasoning,
-            request.tools,
-            request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request);
         info!(
             event = "provider.request.payload.normalized",
             provider = "deepseek",
@@ -79,7 +79,15 @@ impl ProviderClient for DeepSeekClient {
             );
         }
         self.runtime
-            .post_chat_completions_stream("request", &url, &payload, request.auth_bearer, &[], None)
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &[],
+                request.controls.upstream,
+                None,
+            )
             .await
     }
 
@@ -88,14 +96,7 @@ impl ProviderClient for DeepSeekClient {
         request: ProviderGenerateStreamRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_deepseek_payload(
-            request.request.model,
-            request.request.instructions,
-            request.request.input,
-            request.request.reasoning,
-            request.request.tools,
-            request.request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request.request);
         info!(
             event = "provider.request.payload.normalized",
             provider = "deepseek",
@@ -121,12 +122,36 @@ impl ProviderClient for DeepSeekClient {
                 &payload,
                 request.request.auth_bearer,
                 &[],
+                request.request.controls.upstream,
                 request.sender,
             )
             .await
     }
 }
 
+/// Upstream body for `request`, shared by both generate paths and payload previews.
+pub(crate) fn request_payload(
+    request: &ProviderGenerateRequest<'_>,
+) -> (Value, DeepseekNormalization) {
+    let (mut payload, normalization) = build_deepseek_payload(
+        request.model,
+        request.instructions,
+        request.input,
+        request.reasoning,
+        request.tools,
+        request.tool_choice,
+    );
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::JsonObjectOnly,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    (payload, normalization)
+}
+
 pub(crate) fn build_deepseek_payload(
     model: &str,
     instructions: Option<&str>,
@@ -152,15 +177,20 @@ pub(crate) fn build_deepseek_payload(
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         },
         Some(&normalized_tools.tools),
         normalized_tool_choice.as_ref(),
     );
     let has_effort = reasoning
-        .and_then(|cfg| cfg.effort.as_deref())
-        .is_some_and(|value| !value.trim().is_empty());
+        .and_then(ReasoningConfig::effective_effort)
+        .is_some_and(|effort| !effort.eq_ignore_ascii_case("none"));
     if model == "deepseek-chat" && has_effort {
         payload.insert("thinking".to_string(), json!({ "type": "enabled" }));
     }
@@ -372,17 +402,32 @@ mod tests {
     #[test]
     fn chat_enables_thinking_when_effort_present() {
         let input = ResponsesInput::Text("Reply with ok".to_string());
-        let reasoning = ReasoningConfig { effort: Some("medium".to_string()), summary: None };
+        let reasoning =
+            ReasoningConfig { effort: Some("medium".to_string()), ..Default::default() };
         let (payload, _) =
             build_deepseek_payload("deepseek-chat", None, &input, Some(&reasoning), None, None);
         assert_eq!(payload["thinking"]["type"], "enabled");
         assert!(payload.get("reasoning").is_none());
     }
 
+    #[test]
+    fn chat_thinking_follows_reasoning_budget() {
+        let input = ResponsesInput::Text("Reply with ok".to_string());
+        let budget = ReasoningConfig { max_tokens: Some(4000), ..Default::default() };
+        let (payload, _) =
+            build_deepseek_payload("deepseek-chat", None, &input, Some(&budget), None, None);
+        assert_eq!(payload["thinking"]["type"], "enabled");
+
+        let off = ReasoningConfig { max_tokens: Some(0), ..Default::default() };
+        let (payload, _) =
+            build_deepseek_payload("deepseek-chat", None, &input, Some(&off), None, None);
+        assert!(payload.get("thinking").is_none());
+    }
+
     #[test]
     fn reasoner_does_not_set_thinking() {
         let input = ResponsesInput::Text("Reply with ok".to_string());
-        let reasoning = ReasoningConfig { effort: Some("high".to_string()), summary: None };
+        let reasoning = ReasoningConfig { effort: Some("high".to_string()), ..Default::default() };
         let (payload, _) =
             build_deepseek_payload("deepseek-reasoner", None, &input, Some(&reasoning), None, None);
         assert!(payload.get("thinking").is_none());
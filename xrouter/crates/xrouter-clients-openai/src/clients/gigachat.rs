@@ -1,9 +1,11 @@
 use std::{
+    collections::HashMap,
     sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
 
 use async_trait::async_trait;
+use base64::Engine;
 use reqwest::Client;
 use serde::Deserialize;
 use serde_json::{Map, Value, json};
@@ -11,20 +13,27 @@ use tokio::sync::Mutex;
 use tracing::{debug, info};
 use uuid::Uuid;
 use xrouter_contracts::{
-    ResponseInputContent, ResponseInputItem, ResponseToolOutput, ResponsesInput, ToolCall,
-    ToolFunction,
+    ResponseInputContent, ResponseInputItem, ResponseInputPart, ResponseToolOutput, ResponsesInput,
+    ToolCall, ToolFunction,
 };
 use xrouter_core::{
     CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
-    ProviderOutcome,
+    ProviderOutcome, ProviderUsage,
 };
 
-use crate::runtime::SharedProviderRuntime;
+use crate::parser::{
+    ContentBuffer, StreamAccumulator, StreamDelta, estimate_output_tokens, native_usage_from_value,
+    normalize_finish_reason,
+};
+use crate::protocol::{apply_passthrough_fields, compact_payload};
+use crate::runtime::{MultipartFile, SharedProviderRuntime};
+use crate::transcript::TranscriptStore;
 use crate::transport::HttpRuntime;
 
 const GIGACHAT_OAUTH_URL: &str = "https://ngw.devices.sberbank.ru:9443/api/v2/oauth";
 const GIGACHAT_DEFAULT_SCOPE: &str = "GIGACHAT_API_PERS";
 const TOKEN_REFRESH_BUFFER_MS: i64 = 60_000;
+const FILES_ENDPOINT: &str = "files";
 
 pub struct GigachatClient {
     runtime: SharedProviderRuntime,
@@ -39,15 +48,21 @@ impl GigachatClient {
         scope: Option<String>,
         http_client: Option<Client>,
         max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
     ) -> Self {
         Self {
-            runtime: Arc::new(HttpRuntime::new(
-                "gigachat".to_string(),
-                base_url,
-                authorization_key,
-                http_client,
-                max_inflight,
-            )),
+            runtime: Arc::new(
+                HttpRuntime::new(
+                    "gigachat".to_string(),
+                    base_url,
+                    authorization_key,
+                    http_client,
+                    max_inflight,
+                    transcripts,
+                )
+                .with_stream_accumulator(|| Box::<GigachatStreamAccumulator>::default())
+                .with_json_response(map_gigachat_chat_completion_response_value),
+            ),
             scope: scope.unwrap_or_else(|| GIGACHAT_DEFAULT_SCOPE.to_string()),
             token_state: Arc::new(Mutex::new(None)),
         }
@@ -87,6 +102,79 @@ impl GigachatClient {
         *guard = Some(token);
         Ok(value)
     }
+
+    /// Uploads a file to GigaChat storage (`POST /files`, purpose `general`) and returns the id
+    /// that messages reference in their `attachments`.
+    pub async fn upload_file(
+        &self,
+        file_name: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+        auth_bearer: Option<&str>,
+    ) -> Result<String, CoreError> {
+        let access_token = match auth_bearer {
+            Some(token) => token.to_string(),
+            None => self.access_token().await?,
+        };
+        let url = self.runtime.build_url(FILES_ENDPOINT)?;
+        let size = bytes.len();
+        let file = MultipartFile {
+            field: "file".to_string(),
+            file_name: file_name.to_string(),
+            content_type: content_type.to_string(),
+            bytes,
+        };
+        let form_fields = vec![("purpose".to_string(), "general".to_string())];
+        let headers = vec![("Authorization".to_string(), format!("Bearer {access_token}"))];
+        let response: GigachatFileResponse = serde_json::from_value(
+            self.runtime.post_multipart_json(&url, &form_fields, file, &headers).await?,
+        )
+        .map_err(|err| CoreError::Provider(format!("provider response parse failed: {err}")))?;
+        info!(
+            event = "provider.file.uploaded",
+            provider = "gigachat",
+            file_id = %response.id,
+            content_type,
+            bytes = size
+        );
+        Ok(response.id)
+    }
+
+    /// Uploads every inline (`data:` URL) image or file of `input` once and maps each data URL
+    /// to the id of its uploaded copy.
+    async fn upload_inline_attachments(
+        &self,
+        input: &ResponsesInput,
+        access_token: &str,
+    ) -> Result<UploadedAttachments, CoreError> {
+        let mut uploaded = UploadedAttachments::new();
+        let ResponsesInput::Items(items) = input else {
+            return Ok(uploaded);
+        };
+        let parts = items
+            .iter()
+            .filter(|item| !is_system_like(item.role.as_deref()))
+            .flat_map(item_content_parts);
+        for part in parts {
+            let Some(AttachmentSource::Inline { data_url, file_name }) = attachment_source(part)
+            else {
+                continue;
+            };
+            if uploaded.contains_key(data_url) {
+                continue;
+            }
+            let (content_type, bytes) = decode_data_url(data_url).ok_or_else(|| {
+                CoreError::Validation("attachment data URL must be base64 encoded".to_string())
+            })?;
+            let file_name = file_name
+                .map(str::to_string)
+                .unwrap_or_else(|| default_attachment_file_name(&content_type));
+            let file_id =
+                self.upload_file(&file_name, &content_type, bytes, Some(access_token)).await?;
+            uploaded.insert(data_url.to_string(), file_id);
+        }
+        Ok(uploaded)
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -101,13 +189,9 @@ impl ProviderClient for GigachatClient {
         } else {
             self.access_token().await?
         };
+        let uploaded = self.upload_inline_attachments(request.input, &access_token).await?;
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_gigachat_payload(
-            request.model,
-            request.input,
-            request.tools,
-            request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request, &uploaded);
         info!(
             event = "provider.request.payload.normalized",
             provider = "gigachat",
@@ -133,6 +217,7 @@ impl ProviderClient for GigachatClient {
                 &payload,
                 Some(access_token.as_str()),
                 &[],
+                request.controls.upstream,
                 None,
             )
             .await
@@ -147,13 +232,9 @@ impl ProviderClient for GigachatClient {
         } else {
             self.access_token().await?
         };
+        let uploaded = self.upload_inline_attachments(request.request.input, &access_token).await?;
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_gigachat_payload(
-            request.request.model,
-            request.request.input,
-            request.request.tools,
-            request.request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request.request, &uploaded);
         info!(
             event = "provider.request.payload.normalized",
             provider = "gigachat",
@@ -179,10 +260,16 @@ impl ProviderClient for GigachatClient {
                 &payload,
                 Some(access_token.as_str()),
                 &[],
+                request.request.controls.upstream,
                 request.sender,
             )
             .await
     }
+
+    // GigaChat `function_call` has no "required" mode.
+    fn supports_required_tool_choice(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -202,16 +289,38 @@ struct NormalizedFunctions {
     dropped_tool_types: Vec<String>,
 }
 
+/// Data URLs of inline attachments mapped to the ids of their uploaded copies.
+pub(crate) type UploadedAttachments = HashMap<String, String>;
+
+/// Upstream body for `request`, shared by both generate paths and payload previews. Inline
+/// attachments missing from `uploaded` are left out of the message `attachments`.
+pub(crate) fn request_payload(
+    request: &ProviderGenerateRequest<'_>,
+    uploaded: &UploadedAttachments,
+) -> (Value, GigachatNormalization) {
+    let (mut payload, normalization) = build_gigachat_payload(
+        request.model,
+        request.input,
+        request.tools,
+        request.tool_choice,
+        uploaded,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    (payload, normalization)
+}
+
 pub(crate) fn build_gigachat_payload(
     model: &str,
     input: &ResponsesInput,
     tools: Option<&[Value]>,
     tool_choice: Option<&Value>,
+    uploaded: &UploadedAttachments,
 ) -> (Value, GigachatNormalization) {
     let normalized_tools = normalize_tools_for_gigachat(tools);
     let normalized_tool_choice =
         normalize_tool_choice_for_gigachat(tool_choice, !normalized_tools.functions.is_empty());
-    let messages = build_gigachat_messages(input);
+    let messages = build_gigachat_messages(input, uploaded);
     let mut payload = json!({
         "model": model,
         "messages": messages,
@@ -343,15 +452,18 @@ fn tool_choice_debug_label(value: &Value) -> String {
     "other".to_string()
 }
 
-fn build_gigachat_messages(input: &ResponsesInput) -> Vec<Value> {
+fn build_gigachat_messages(input: &ResponsesInput, uploaded: &UploadedAttachments) -> Vec<Value> {
     match input {
         ResponsesInput::Text(text) => vec![json!({ "role": "user", "content": text })],
-        ResponsesInput::Items(items) => map_input_items_to_gigachat_messages(items),
+        ResponsesInput::Items(items) => map_input_items_to_gigachat_messages(items, uploaded),
     }
 }
 
-fn map_input_items_to_gigachat_messages(items: &[ResponseInputItem]) -> Vec<Value> {
-    let mut call_id_to_name = std::collections::HashMap::<String, String>::new();
+fn map_input_items_to_gigachat_messages(
+    items: &[ResponseInputItem],
+    uploaded: &UploadedAttachments,
+) -> Vec<Value> {
+    let mut call_id_to_name = HashMap::<String, String>::new();
     let mut system_parts = Vec::<String>::new();
     let mut pending_tool_call_id: Option<String> = None;
     for item in items {
@@ -403,7 +515,7 @@ fn map_input_items_to_gigachat_messages(items: &[ResponseInputItem]) -> Vec<Valu
         {
             continue;
         }
-        if let Some(msg) = map_item_to_gigachat_message(item, &call_id_to_name) {
+        if let Some(msg) = map_item_to_gigachat_message(item, &call_id_to_name, uploaded) {
             messages.push(msg);
         }
     }
@@ -423,7 +535,8 @@ fn is_system_like(role: Option<&str>) -> bool {
 
 fn map_item_to_gigachat_message(
     item: &ResponseInputItem,
-    call_id_to_name: &std::collections::HashMap<String, String>,
+    call_id_to_name: &HashMap<String, String>,
+    uploaded: &UploadedAttachments,
 ) -> Option<Value> {
     let kind = item.kind.as_deref().unwrap_or_default();
     if kind == "function_call" {
@@ -469,11 +582,82 @@ fn map_item_to_gigachat_message(
 
     let role =
         item.role.as_deref().or_else(|| if kind == "message" { Some("user") } else { None })?;
-    let content = extract_input_item_text(item)?;
-    Some(json!({
+    let attachments = message_attachments(item, uploaded);
+    let content = extract_input_item_text(item);
+    if content.is_none() && attachments.is_empty() {
+        return None;
+    }
+    let mut message = json!({
         "role": role,
-        "content": content
-    }))
+        "content": content.unwrap_or_default()
+    });
+    if !attachments.is_empty() {
+        message["attachments"] = json!(attachments);
+    }
+    Some(message)
+}
+
+/// Where the file behind an image or file part lives: already in GigaChat storage under
+/// `file_id`, or inline as a `data:` URL that has to be uploaded first. Remote image URLs are
+/// not fetched by GigaChat and have no source.
+enum AttachmentSource<'a> {
+    Stored(&'a str),
+    Inline { data_url: &'a str, file_name: Option<&'a str> },
+}
+
+fn attachment_source(part: &ResponseInputPart) -> Option<AttachmentSource<'_>> {
+    if let Some(file_id) =
+        part.extra.get("file_id").and_then(Value::as_str).filter(|id| !id.trim().is_empty())
+    {
+        return Some(AttachmentSource::Stored(file_id));
+    }
+    let data_url = part
+        .image_url
+        .as_deref()
+        .or_else(|| part.extra.get("file_data").and_then(Value::as_str))
+        .filter(|url| url.starts_with("data:"))?;
+    let file_name = part.extra.get("filename").and_then(Value::as_str);
+    Some(AttachmentSource::Inline { data_url, file_name })
+}
+
+fn item_content_parts(item: &ResponseInputItem) -> &[ResponseInputPart] {
+    match &item.content {
+        Some(ResponseInputContent::Parts(parts)) => parts,
+        _ => &[],
+    }
+}
+
+fn message_attachments(item: &ResponseInputItem, uploaded: &UploadedAttachments) -> Vec<String> {
+    let mut attachments = Vec::<String>::new();
+    for part in item_content_parts(item) {
+        let file_id = match attachment_source(part) {
+            Some(AttachmentSource::Stored(file_id)) => file_id,
+            Some(AttachmentSource::Inline { data_url, .. }) => match uploaded.get(data_url) {
+                Some(file_id) => file_id.as_str(),
+                None => continue,
+            },
+            None => continue,
+        };
+        if !attachments.iter().any(|known| known == file_id) {
+            attachments.push(file_id.to_string());
+        }
+    }
+    attachments
+}
+
+/// Splits a `data:<media type>;base64,<data>` URL into its media type and decoded bytes.
+fn decode_data_url(data_url: &str) -> Option<(String, Vec<u8>)> {
+    let (header, data) = data_url.strip_prefix("data:")?.split_once(',')?;
+    let media_type = header.strip_suffix(";base64")?;
+    let bytes = base64::engine::general_purpose::STANDARD.decode(data.trim()).ok()?;
+    let media_type = if media_type.is_empty() { "application/octet-stream" } else { media_type };
+    Some((media_type.to_string(), bytes))
+}
+
+fn default_attachment_file_name(content_type: &str) -> String {
+    let extension = content_type.rsplit('/').next().unwrap_or("bin");
+    let extension = if extension == "jpeg" { "jpg" } else { extension };
+    format!("attachment.{extension}")
 }
 
 fn extract_input_item_text(item: &ResponseInputItem) -> Option<String> {
@@ -542,6 +726,11 @@ struct GigachatOauthResponse {
     expires_at: i64,
 }
 
+#[derive(Debug, Deserialize)]
+struct GigachatFileResponse {
+    id: String,
+}
+
 fn current_time_millis() -> i64 {
     let Ok(duration) = SystemTime::now().duration_since(UNIX_EPOCH) else {
         return 0;
@@ -584,24 +773,42 @@ pub(crate) fn map_gigachat_chat_completion_response_value(
         output_tokens,
         reasoning: None,
         reasoning_details: None,
+        reasoning_encrypted_content: None,
         tool_calls,
+        annotations: None,
         emitted_live: false,
+        finish_reason: first
+            .get("finish_reason")
+            .and_then(Value::as_str)
+            .and_then(normalize_finish_reason),
+        usage: native_usage_from_value(payload.get("usage")),
+        upstream_attempts: 1,
+        provider_response_id: payload.get("id").and_then(Value::as_str).map(str::to_string),
+        upstream_provider: None,
     })
 }
 
-pub(crate) fn map_gigachat_chat_completion_stream_text(
-    payload: &str,
-) -> Result<ProviderOutcome, CoreError> {
-    let mut chunks = Vec::<String>::new();
-    let mut all_content = String::new();
-    let mut output_tokens = None::<u32>;
-    let mut tool_calls = Vec::<ToolCall>::new();
+#[cfg(test)]
+fn map_gigachat_chat_completion_stream_text(payload: &str) -> Result<ProviderOutcome, CoreError> {
+    crate::parser::accumulate_stream_text(Box::<GigachatStreamAccumulator>::default(), payload)
+}
 
-    for event in extract_sse_data_events(payload) {
-        if event == "[DONE]" {
-            continue;
+#[derive(Debug, Default)]
+pub(crate) struct GigachatStreamAccumulator {
+    content: ContentBuffer,
+    output_tokens: Option<u32>,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<String>,
+    usage: Option<ProviderUsage>,
+}
+
+impl StreamAccumulator for GigachatStreamAccumulator {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError> {
+        let mut delta = StreamDelta::default();
+        if data == "[DONE]" {
+            return Ok(delta);
         }
-        let parsed = serde_json::from_str::<Value>(&event)
+        let parsed = serde_json::from_str::<Value>(data)
             .map_err(|err| CoreError::Provider(format!("provider stream parse failed: {err}")))?;
 
         if let Some(tokens) = parsed
@@ -610,57 +817,85 @@ pub(crate) fn map_gigachat_chat_completion_stream_text(
             .and_then(|usage| usage.get("completion_tokens"))
             .and_then(Value::as_u64)
         {
-            output_tokens = Some(tokens as u32);
+            self.output_tokens = Some(tokens as u32);
+        }
+        if let Some(usage) = native_usage_from_value(parsed.get("usage")) {
+            self.usage = Some(usage);
         }
 
         for choice in parsed.get("choices").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(reason) = choice
+                .get("finish_reason")
+                .and_then(Value::as_str)
+                .and_then(normalize_finish_reason)
+            {
+                self.finish_reason = Some(reason);
+            }
             if let Some(content_delta) =
                 extract_text_content(choice.get("delta").and_then(|delta| delta.get("content")))
                 && !content_delta.is_empty()
             {
-                all_content.push_str(&content_delta);
-                chunks.push(content_delta);
+                self.content.push(&content_delta);
+                delta.content.push(content_delta);
             }
             if let Some(content) = extract_text_content(
                 choice.get("message").and_then(|message| message.get("content")),
             ) && !content.is_empty()
             {
-                all_content.push_str(&content);
-                chunks.push(content);
+                self.content.push(&content);
             }
 
-            merge_tool_calls_unique(&mut tool_calls, extract_tool_calls_legacy_and_openai(choice));
+            merge_tool_calls_unique(
+                &mut self.tool_calls,
+                extract_tool_calls_legacy_and_openai(choice),
+            );
             if let Some(delta) = choice.get("delta") {
                 merge_tool_calls_unique(
-                    &mut tool_calls,
+                    &mut self.tool_calls,
                     extract_tool_calls_legacy_and_openai(delta),
                 );
             }
             if let Some(message) = choice.get("message") {
                 merge_tool_calls_unique(
-                    &mut tool_calls,
+                    &mut self.tool_calls,
                     extract_tool_calls_legacy_and_openai(message),
                 );
             }
         }
+        Ok(delta)
     }
 
-    let tool_calls = if tool_calls.is_empty() { None } else { Some(tool_calls) };
-    if all_content.is_empty() && tool_calls.is_none() {
-        return Err(CoreError::Provider("provider returned empty message content".to_string()));
+    fn limit_retained_content(&mut self, max_bytes: usize) {
+        self.content.set_limit(max_bytes);
     }
-    let output_tokens = output_tokens.unwrap_or_else(|| {
-        if all_content.is_empty() { 0 } else { all_content.split_whitespace().count() as u32 }
-    });
 
-    Ok(ProviderOutcome {
-        chunks: if all_content.is_empty() { Vec::new() } else { chunks },
-        output_tokens,
-        reasoning: None,
-        reasoning_details: None,
-        tool_calls,
-        emitted_live: false,
-    })
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError> {
+        let Self { content, output_tokens, tool_calls, finish_reason, usage } = *self;
+        let tool_calls = if tool_calls.is_empty() { None } else { Some(tool_calls) };
+        if content.is_empty() && tool_calls.is_none() {
+            return Err(CoreError::Provider("provider returned empty message content".to_string()));
+        }
+        let dropped_words = content.dropped_words();
+        let all_content = content.into_text();
+        let output_tokens =
+            output_tokens.unwrap_or_else(|| estimate_output_tokens(&all_content, dropped_words));
+
+        Ok(ProviderOutcome {
+            chunks: if all_content.is_empty() { Vec::new() } else { vec![all_content] },
+            output_tokens,
+            reasoning: None,
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls,
+            annotations: None,
+            emitted_live: false,
+            finish_reason,
+            usage,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
+        })
+    }
 }
 
 fn merge_tool_calls_unique(into: &mut Vec<ToolCall>, incoming: Vec<ToolCall>) {
@@ -769,29 +1004,18 @@ fn extract_text_content(value: Option<&Value>) -> Option<String> {
     }
 }
 
-fn extract_sse_data_events(payload: &str) -> Vec<String> {
-    payload
-        .replace('\r', "")
-        .split("\n\n")
-        .filter_map(|frame| {
-            let data_lines = frame
-                .lines()
-                .filter_map(|line| line.strip_prefix("data:").map(str::trim_start))
-                .collect::<Vec<_>>();
-            if data_lines.is_empty() { None } else { Some(data_lines.join("\n")) }
-        })
-        .collect()
-}
-
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use super::{
-        build_gigachat_payload, map_gigachat_chat_completion_response_value,
+        build_gigachat_payload, decode_data_url, map_gigachat_chat_completion_response_value,
         map_gigachat_chat_completion_stream_text,
     };
     use serde_json::{Value, json};
     use xrouter_contracts::{
-        ResponseInputContent, ResponseInputItem, ResponseToolOutput, ResponsesInput,
+        ResponseInputContent, ResponseInputItem, ResponseInputPart, ResponseToolOutput,
+        ResponsesInput,
     };
 
     #[test]
@@ -808,8 +1032,13 @@ mod tests {
             }),
             json!({"type":"web_search"}),
         ];
-        let (payload, norm) =
-            build_gigachat_payload("GigaChat-2", &input, Some(&tools), Some(&json!("auto")));
+        let (payload, norm) = build_gigachat_payload(
+            "GigaChat-2",
+            &input,
+            Some(&tools),
+            Some(&json!("auto")),
+            &HashMap::new(),
+        );
         assert_eq!(norm.tools_in, 2);
         assert_eq!(norm.tools_out, 1);
         assert_eq!(norm.tools_dropped, 1);
@@ -847,7 +1076,8 @@ mod tests {
                 ..Default::default()
             },
         ]);
-        let (payload, _) = build_gigachat_payload("GigaChat-2", &input, None, None);
+        let (payload, _) =
+            build_gigachat_payload("GigaChat-2", &input, None, None, &HashMap::new());
         let messages = payload["messages"].as_array().expect("messages must be array");
         assert_eq!(messages[0]["role"], "system");
         assert_eq!(messages[0]["content"], "s1\n\ns2");
@@ -913,7 +1143,8 @@ mod tests {
                 ..Default::default()
             },
         ]);
-        let (payload, _) = build_gigachat_payload("GigaChat-2", &input, None, None);
+        let (payload, _) =
+            build_gigachat_payload("GigaChat-2", &input, None, None, &HashMap::new());
         let messages = payload["messages"].as_array().expect("messages must be array");
         let function_msg =
             messages.iter().find(|m| m["role"] == "function").expect("function message must exist");
@@ -949,17 +1180,87 @@ mod tests {
                 ..Default::default()
             },
         ]);
-        let (payload, _) = build_gigachat_payload("GigaChat-2", &input, None, None);
+        let (payload, _) =
+            build_gigachat_payload("GigaChat-2", &input, None, None, &HashMap::new());
         let messages = payload["messages"].as_array().expect("messages must be array");
         assert_eq!(messages.len(), 2);
         assert_eq!(messages[0]["role"], "assistant");
         assert_eq!(messages[1]["role"], "function");
     }
 
+    #[test]
+    fn gigachat_stream_maps_blacklist_finish_reason_to_content_filter() {
+        let sse = concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Не могу\"},\"index\":0}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"index\":0,\"finish_reason\":\"blacklist\"}]}\n\n"
+        );
+
+        let outcome = map_gigachat_chat_completion_stream_text(sse).expect("stream must parse");
+        assert_eq!(outcome.finish_reason.as_deref(), Some("content_filter"));
+    }
+
     #[test]
     fn payload_forces_stream_true() {
         let input = ResponsesInput::Text("hello".to_string());
-        let (payload, _) = build_gigachat_payload("GigaChat-Pro", &input, None, None);
+        let (payload, _) =
+            build_gigachat_payload("GigaChat-Pro", &input, None, None, &HashMap::new());
         assert_eq!(payload["stream"], json!(true));
     }
+
+    #[test]
+    fn uploaded_and_stored_files_become_message_attachments() {
+        let data_url = "data:image/png;base64,iVBORw0KGgo=";
+        let input = ResponsesInput::Items(vec![ResponseInputItem {
+            kind: Some("message".to_string()),
+            role: Some("user".to_string()),
+            content: Some(ResponseInputContent::Parts(vec![
+                ResponseInputPart {
+                    kind: Some("input_text".to_string()),
+                    text: Some("What is on these?".to_string()),
+                    ..Default::default()
+                },
+                ResponseInputPart {
+                    kind: Some("input_image".to_string()),
+                    image_url: Some(data_url.to_string()),
+                    ..Default::default()
+                },
+                ResponseInputPart {
+                    kind: Some("input_file".to_string()),
+                    extra: [("file_id".to_string(), json!("stored-file"))].into(),
+                    ..Default::default()
+                },
+                ResponseInputPart {
+                    kind: Some("input_image".to_string()),
+                    image_url: Some("https://example.com/cat.png".to_string()),
+                    ..Default::default()
+                },
+            ])),
+            ..Default::default()
+        }]);
+        let uploaded = HashMap::from([(data_url.to_string(), "uploaded-file".to_string())]);
+
+        let (payload, _) = build_gigachat_payload("GigaChat-2-Max", &input, None, None, &uploaded);
+        assert_eq!(
+            payload["messages"][0],
+            json!({
+                "role": "user",
+                "content": "What is on these?",
+                "attachments": ["uploaded-file", "stored-file"]
+            })
+        );
+
+        let (payload, _) =
+            build_gigachat_payload("GigaChat-2-Max", &input, None, None, &HashMap::new());
+        assert_eq!(payload["messages"][0]["attachments"], json!(["stored-file"]));
+    }
+
+    #[test]
+    fn data_urls_decode_to_media_type_and_bytes() {
+        let (media_type, bytes) =
+            decode_data_url("data:image/png;base64,aGVsbG8=").expect("data URL must decode");
+        assert_eq!(media_type, "image/png");
+        assert_eq!(bytes, b"hello");
+        assert!(decode_data_url("data:text/plain,hello").is_none());
+        assert!(decode_data_url("https://example.com/cat.png").is_none());
+    }
 }
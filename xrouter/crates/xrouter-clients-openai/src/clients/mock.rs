@@ -1,5 +1,9 @@
 use async_trait::async_trait;
-use xrouter_core::{CoreError, ProviderClient, ProviderGenerateRequest, ProviderOutcome};
+use xrouter_contracts::ResponseEvent;
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome,
+};
 
 pub struct MockProviderClient {
     provider_id: String,
@@ -44,10 +48,50 @@ impl ProviderClient for MockProviderClient {
         Ok(ProviderOutcome {
             chunks,
             output_tokens,
+            reasoning_encrypted_content: reasoning
+                .as_ref()
+                .map(|_| format!("mock-encrypted:{}", self.provider_id)),
             reasoning,
             reasoning_details: None,
             tool_calls: None,
+            annotations: None,
             emitted_live: false,
+            finish_reason: None,
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
         })
     }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let mut outcome = self.generate(request.request).await?;
+        let Some(sender) = request.sender else {
+            return Ok(outcome);
+        };
+        if let Some(reasoning) = outcome.reasoning.clone() {
+            sender
+                .send(Ok(ResponseEvent::ReasoningDelta {
+                    id: request.request_id.to_string(),
+                    delta: reasoning,
+                }))
+                .await;
+        }
+        for chunk in &outcome.chunks {
+            if sender.output_closed() {
+                break;
+            }
+            sender
+                .send(Ok(ResponseEvent::OutputTextDelta {
+                    id: request.request_id.to_string(),
+                    delta: chunk.clone(),
+                }))
+                .await;
+        }
+        outcome.emitted_live = true;
+        Ok(outcome)
+    }
 }
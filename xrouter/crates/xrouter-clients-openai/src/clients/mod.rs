@@ -1,21 +1,50 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod chaos;
+pub(crate) mod cloudflare;
+pub(crate) mod cohere;
 pub(crate) mod deepseek;
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) mod gigachat;
 pub(crate) mod mock;
 pub(crate) mod openai;
 pub(crate) mod openrouter;
+pub(crate) mod perplexity;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod preview;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod replay;
+pub(crate) mod selfhosted;
+pub(crate) mod tgi;
 pub(crate) mod xrouter;
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) mod yandex;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod yandex_chat;
 pub(crate) mod zai;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use chaos::{ChaosPolicy, ChaosProviderClient, SharedChaosPolicy};
+pub use cloudflare::CloudflareClient;
+pub use cohere::CohereClient;
 pub use deepseek::DeepSeekClient;
 #[cfg(not(target_arch = "wasm32"))]
 pub use gigachat::GigachatClient;
 pub use mock::MockProviderClient;
 pub use openai::OpenAiClient;
 pub use openrouter::OpenRouterClient;
+pub use perplexity::PerplexityClient;
+#[cfg(not(target_arch = "wasm32"))]
+pub use preview::{PayloadPreview, ToolNormalization, preview_payload};
+#[cfg(not(target_arch = "wasm32"))]
+pub use replay::{
+    FixtureEvent, FixtureOutcome, ProviderFixture, RecordingProviderClient, ReplayProviderClient,
+    fixture_key,
+};
+pub use selfhosted::{SelfHostedClient, SelfHostedQuirks};
+pub use tgi::TgiClient;
 pub use xrouter::XrouterClient;
 #[cfg(not(target_arch = "wasm32"))]
 pub use yandex::YandexResponsesClient;
+#[cfg(not(target_arch = "wasm32"))]
+pub use yandex_chat::YandexChatClient;
 pub use zai::ZaiClient;
@@ -10,9 +10,14 @@ use xrouter_core::{
     ProviderOutcome,
 };
 
-use crate::protocol::base_chat_payload;
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    compact_payload,
+};
 use crate::runtime::SharedProviderRuntime;
 #[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::transport::HttpRuntime;
 
 pub struct OpenAiClient {
@@ -27,6 +32,7 @@ impl OpenAiClient {
         api_key: Option<String>,
         http_client: Option<Client>,
         max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
     ) -> Self {
         Self::with_runtime(Arc::new(HttpRuntime::new(
             provider_id,
@@ -34,6 +40,7 @@ impl OpenAiClient {
             api_key,
             http_client,
             max_inflight,
+            transcripts,
         )))
     }
 
@@ -50,16 +57,17 @@ impl ProviderClient for OpenAiClient {
         request: ProviderGenerateRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let payload = build_openai_payload(
-            request.model,
-            request.instructions,
-            request.input,
-            request.reasoning,
-            request.tools,
-            request.tool_choice,
-        );
+        let payload = request_payload(&request);
         self.runtime
-            .post_chat_completions_stream("request", &url, &payload, request.auth_bearer, &[], None)
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &[],
+                request.controls.upstream,
+                None,
+            )
             .await
     }
 
@@ -68,14 +76,7 @@ impl ProviderClient for OpenAiClient {
         request: ProviderGenerateStreamRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let payload = build_openai_payload(
-            request.request.model,
-            request.request.instructions,
-            request.request.input,
-            request.request.reasoning,
-            request.request.tools,
-            request.request.tool_choice,
-        );
+        let payload = request_payload(&request.request);
         self.runtime
             .post_chat_completions_stream(
                 request.request_id,
@@ -83,12 +84,34 @@ impl ProviderClient for OpenAiClient {
                 &payload,
                 request.request.auth_bearer,
                 &[],
+                request.request.controls.upstream,
                 request.sender,
             )
             .await
     }
 }
 
+/// Upstream body for `request`, shared by both generate paths and payload previews.
+pub(crate) fn request_payload(request: &ProviderGenerateRequest<'_>) -> Value {
+    let mut payload = build_openai_payload(
+        request.model,
+        request.instructions,
+        request.input,
+        request.reasoning,
+        request.tools,
+        request.tool_choice,
+    );
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::Full,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    payload
+}
+
 pub(crate) fn build_openai_payload(
     model: &str,
     instructions: Option<&str>,
@@ -111,8 +134,13 @@ pub(crate) fn build_openai_payload(
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         },
         tools,
         tool_choice,
@@ -124,10 +152,7 @@ pub(crate) fn build_openai_payload(
 }
 
 fn normalize_openai_reasoning(reasoning: Option<&ReasoningConfig>) -> Option<Value> {
-    let effort = reasoning?.effort.as_deref()?.trim();
-    if effort.is_empty() {
-        return None;
-    }
+    let effort = reasoning?.effective_effort()?;
     let mapped = if effort.eq_ignore_ascii_case("xhigh") { "high" } else { effort };
     Some(json!({ "effort": mapped }))
 }
@@ -141,12 +166,21 @@ mod tests {
     #[test]
     fn maps_xhigh_to_high() {
         let input = ResponsesInput::Text("Reply with ok".to_string());
-        let reasoning = ReasoningConfig { effort: Some("xhigh".to_string()), summary: None };
+        let reasoning = ReasoningConfig { effort: Some("xhigh".to_string()), ..Default::default() };
         let payload =
             build_openai_payload("gpt-4.1-mini", None, &input, Some(&reasoning), None, None);
         assert_eq!(payload["reasoning"]["effort"], "high");
     }
 
+    #[test]
+    fn approximates_effort_from_reasoning_budget() {
+        let input = ResponsesInput::Text("Reply with ok".to_string());
+        let reasoning = ReasoningConfig { max_tokens: Some(1024), ..Default::default() };
+        let payload =
+            build_openai_payload("gpt-4.1-mini", None, &input, Some(&reasoning), None, None);
+        assert_eq!(payload["reasoning"], json!({ "effort": "low" }));
+    }
+
     #[test]
     fn forces_stream_true() {
         let input = ResponsesInput::Text("hello".to_string());
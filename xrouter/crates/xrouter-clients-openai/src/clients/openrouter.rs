@@ -13,11 +13,20 @@ use xrouter_core::{
     ProviderOutcome,
 };
 
-use crate::protocol::base_chat_payload;
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    compact_payload,
+};
 use crate::runtime::SharedProviderRuntime;
 #[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::transport::HttpRuntime;
 
+/// App attribution headers OpenRouter reads from a request.
+const ATTRIBUTION_HEADERS: [&str; 4] =
+    ["HTTP-Referer", "X-OpenRouter-Title", "X-Title", "X-OpenRouter-Categories"];
+
 pub struct OpenRouterClient {
     runtime: SharedProviderRuntime,
 }
@@ -29,6 +38,7 @@ impl OpenRouterClient {
         api_key: Option<String>,
         http_client: Option<Client>,
         max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
     ) -> Self {
         Self::with_runtime(Arc::new(HttpRuntime::new(
             "openrouter".to_string(),
@@ -36,6 +46,7 @@ impl OpenRouterClient {
             api_key,
             http_client,
             max_inflight,
+            transcripts,
         )))
     }
 
@@ -52,14 +63,7 @@ impl ProviderClient for OpenRouterClient {
         request: ProviderGenerateRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_openrouter_payload(
-            request.model,
-            request.instructions,
-            request.input,
-            request.reasoning,
-            request.tools,
-            request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request);
         info!(
             event = "provider.request.payload.normalized",
             provider = "openrouter",
@@ -78,14 +82,16 @@ impl ProviderClient for OpenRouterClient {
                 dropped_tool_types = ?normalization.dropped_tool_types
             );
         }
-        log_forwarded_attribution_headers(request.model, request.forward_headers);
+        let attribution = attribution_headers(request.forward_headers);
+        log_forwarded_attribution_headers(request.model, &attribution);
         self.runtime
             .post_chat_completions_stream(
                 "request",
                 &url,
                 &payload,
                 request.auth_bearer,
-                request.forward_headers,
+                &attribution,
+                request.controls.upstream,
                 None,
             )
             .await
@@ -96,14 +102,7 @@ impl ProviderClient for OpenRouterClient {
         request: ProviderGenerateStreamRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_openrouter_payload(
-            request.request.model,
-            request.request.instructions,
-            request.request.input,
-            request.request.reasoning,
-            request.request.tools,
-            request.request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request.request);
         info!(
             event = "provider.request.payload.normalized",
             provider = "openrouter",
@@ -122,20 +121,45 @@ impl ProviderClient for OpenRouterClient {
                 dropped_tool_types = ?normalization.dropped_tool_types
             );
         }
-        log_forwarded_attribution_headers(request.request.model, request.request.forward_headers);
+        let attribution = attribution_headers(request.request.forward_headers);
+        log_forwarded_attribution_headers(request.request.model, &attribution);
         self.runtime
             .post_chat_completions_stream(
                 request.request_id,
                 &url,
                 &payload,
                 request.request.auth_bearer,
-                request.request.forward_headers,
+                &attribution,
+                request.request.controls.upstream,
                 request.sender,
             )
             .await
     }
 }
 
+/// Upstream body for `request`, shared by both generate paths and payload previews.
+pub(crate) fn request_payload(
+    request: &ProviderGenerateRequest<'_>,
+) -> (Value, OpenRouterNormalization) {
+    let (mut payload, normalization) = build_openrouter_payload(
+        request.model,
+        request.instructions,
+        request.input,
+        request.reasoning,
+        request.tools,
+        request.tool_choice,
+    );
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::Full,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    (payload, normalization)
+}
+
 pub(crate) fn build_openrouter_payload(
     model: &str,
     instructions: Option<&str>,
@@ -161,15 +185,27 @@ pub(crate) fn build_openrouter_payload(
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         },
         Some(&normalized_tools.tools),
         normalized_tool_choice.as_ref(),
     );
     if let Some(reasoning_cfg) = reasoning
-        && let Ok(value) = serde_json::to_value(reasoning_cfg)
+        && let Ok(mut value) = serde_json::to_value(reasoning_cfg)
     {
+        // OpenRouter takes either `effort` or `max_tokens` and turns the budget into Anthropic
+        // thinking `budget_tokens` itself, so an explicit budget wins.
+        if reasoning_cfg.max_tokens.is_some()
+            && let Some(object) = value.as_object_mut()
+        {
+            object.remove("effort");
+        }
         payload.insert("reasoning".to_string(), value);
     }
     (
@@ -314,6 +350,18 @@ fn normalize_function_tool(tool: &Value) -> Option<Value> {
     }))
 }
 
+/// The forwarded headers OpenRouter reads; hop and trace headers meant for a downstream
+/// xrouter stay behind.
+fn attribution_headers(headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            ATTRIBUTION_HEADERS.iter().any(|attribution| name.eq_ignore_ascii_case(attribution))
+        })
+        .cloned()
+        .collect()
+}
+
 fn log_forwarded_attribution_headers(model: &str, headers: &[(String, String)]) {
     let referer = find_forwarded_header(headers, "HTTP-Referer");
     let title = find_forwarded_header(headers, "X-OpenRouter-Title")
@@ -352,11 +400,11 @@ mod tests {
     use serde_json::{Value, json};
     use xrouter_contracts::{ReasoningConfig, ResponsesInput};
     use xrouter_core::{
-        CoreError, ProviderGenerateRequest, ProviderGenerateStreamRequest, ProviderOutcome,
-        ResponseEventSink,
+        CoreError, ExecutionControls, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+        ProviderOutcome, ResponseEventSink, UpstreamLimits,
     };
 
-    use crate::runtime::ProviderRuntime;
+    use crate::runtime::{MultipartFile, ProviderRuntime};
 
     #[test]
     fn keeps_only_function_tools_and_tracks_drops() {
@@ -399,13 +447,33 @@ mod tests {
     #[test]
     fn keeps_reasoning_effort_as_is() {
         let input = ResponsesInput::Text("Reply with ok".to_string());
-        let reasoning = ReasoningConfig { effort: Some("xhigh".to_string()), summary: None };
+        let reasoning = ReasoningConfig { effort: Some("xhigh".to_string()), ..Default::default() };
         let (payload, _) =
             build_openrouter_payload("openai/gpt-5.2", None, &input, Some(&reasoning), None, None);
         assert_eq!(payload["reasoning"]["effort"], "xhigh");
         assert!(payload.get("thinking").is_none());
     }
 
+    #[test]
+    fn reasoning_budget_replaces_effort_and_keeps_exclude() {
+        let input = ResponsesInput::Text("Reply with ok".to_string());
+        let reasoning = ReasoningConfig {
+            effort: Some("high".to_string()),
+            max_tokens: Some(4000),
+            exclude: Some(true),
+            ..Default::default()
+        };
+        let (payload, _) = build_openrouter_payload(
+            "anthropic/claude-sonnet-4",
+            None,
+            &input,
+            Some(&reasoning),
+            None,
+            None,
+        );
+        assert_eq!(payload["reasoning"], json!({ "max_tokens": 4000, "exclude": true }));
+    }
+
     #[test]
     fn forces_stream_true() {
         let input = ResponsesInput::Text("hello".to_string());
@@ -435,6 +503,7 @@ mod tests {
             _payload: &Value,
             _bearer_override: Option<&str>,
             extra_headers: &[(String, String)],
+            _limits: UpstreamLimits,
             _sender: Option<&dyn ResponseEventSink>,
         ) -> Result<ProviderOutcome, CoreError> {
             *self.seen_headers.lock().expect("lock must succeed") = extra_headers.to_vec();
@@ -443,8 +512,15 @@ mod tests {
                 output_tokens: 1,
                 reasoning: None,
                 reasoning_details: None,
+                reasoning_encrypted_content: None,
                 tool_calls: None,
+                annotations: None,
                 emitted_live: false,
+                finish_reason: None,
+                usage: None,
+                upstream_attempts: 1,
+                provider_response_id: None,
+                upstream_provider: None,
             })
         }
 
@@ -455,6 +531,7 @@ mod tests {
             _payload: &Value,
             _bearer_override: Option<&str>,
             _extra_headers: &[(String, String)],
+            _limits: UpstreamLimits,
             _sender: Option<&dyn ResponseEventSink>,
         ) -> Result<ProviderOutcome, CoreError> {
             panic!("OpenRouter client should use chat/completions transport");
@@ -468,6 +545,16 @@ mod tests {
         ) -> Result<Value, CoreError> {
             panic!("OpenRouter client should not use form transport");
         }
+
+        async fn post_multipart_json(
+            &self,
+            _url: &str,
+            _form_fields: &[(String, String)],
+            _file: MultipartFile,
+            _headers: &[(String, String)],
+        ) -> Result<Value, CoreError> {
+            panic!("OpenRouter client should not use multipart transport");
+        }
     }
 
     #[tokio::test]
@@ -476,10 +563,12 @@ mod tests {
         let runtime = Arc::new(HeaderCaptureRuntime { seen_headers: seen_headers.clone() });
         let client = OpenRouterClient::with_runtime(runtime);
         let input = ResponsesInput::Text("hello".to_string());
-        let forward_headers = vec![
+        let attribution = vec![
             ("HTTP-Referer".to_string(), "https://example.com".to_string()),
             ("X-OpenRouter-Title".to_string(), "Example App".to_string()),
         ];
+        let mut forward_headers = attribution.clone();
+        forward_headers.push(("x-xrouter-hop".to_string(), "1".to_string()));
 
         let result = xrouter_core::ProviderClient::generate(
             &client,
@@ -490,14 +579,19 @@ mod tests {
                 reasoning: None,
                 tools: None,
                 tool_choice: None,
+                include: None,
+                text: None,
+                modalities: None,
                 auth_bearer: None,
                 forward_headers: &forward_headers,
+                extra: None,
+                controls: ExecutionControls::default(),
             },
         )
         .await;
 
         assert!(result.is_ok());
-        assert_eq!(*seen_headers.lock().expect("lock must succeed"), forward_headers);
+        assert_eq!(*seen_headers.lock().expect("lock must succeed"), attribution);
     }
 
     #[tokio::test]
@@ -522,8 +616,13 @@ mod tests {
                     reasoning: None,
                     tools: None,
                     tool_choice: None,
+                    include: None,
+                    text: None,
+                    modalities: None,
                     auth_bearer: None,
                     forward_headers: &forward_headers,
+                    extra: None,
+                    controls: ExecutionControls::default(),
                 },
                 sender: None,
             },
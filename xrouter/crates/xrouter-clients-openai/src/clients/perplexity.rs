@@ -0,0 +1,331 @@
+use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::Client;
+use serde_json::{Map, Value, json};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use xrouter_contracts::{CitationSource, OutputAnnotation, ResponsesRequest};
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome,
+};
+
+use crate::parser::{ChatStreamAccumulator, StreamAccumulator, StreamDelta};
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    compact_payload,
+};
+use crate::runtime::SharedProviderRuntime;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::HttpRuntime;
+
+pub(crate) const CHAT_ENDPOINT: &str = "chat/completions";
+
+/// Perplexity Sonar, an OpenAI-compatible chat API that answers from web search. The sources
+/// it cites come back as URL citation annotations.
+pub struct PerplexityClient {
+    runtime: SharedProviderRuntime,
+}
+
+impl PerplexityClient {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(
+        base_url: Option<String>,
+        api_key: Option<String>,
+        http_client: Option<Client>,
+        max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
+    ) -> Self {
+        Self::with_runtime(Arc::new(
+            HttpRuntime::new(
+                "perplexity".to_string(),
+                base_url,
+                api_key,
+                http_client,
+                max_inflight,
+                transcripts,
+            )
+            .with_stream_accumulator(|| Box::<PerplexityStreamAccumulator>::default()),
+        ))
+    }
+
+    pub fn with_runtime(runtime: SharedProviderRuntime) -> Self {
+        Self { runtime }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ProviderClient for PerplexityClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.runtime.build_url(CHAT_ENDPOINT)?;
+        let payload = request_payload(&request);
+        self.runtime
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &[],
+                request.controls.upstream,
+                None,
+            )
+            .await
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.runtime.build_url(CHAT_ENDPOINT)?;
+        let payload = request_payload(&request.request);
+        self.runtime
+            .post_chat_completions_stream(
+                request.request_id,
+                &url,
+                &payload,
+                request.request.auth_bearer,
+                &[],
+                request.request.controls.upstream,
+                request.sender,
+            )
+            .await
+    }
+}
+
+/// Chat completions body without tools, which Sonar models do not call; reasoning effort is
+/// sent as `reasoning_effort`.
+pub(crate) fn request_payload(request: &ProviderGenerateRequest<'_>) -> Value {
+    let mut payload = base_chat_payload(
+        &ResponsesRequest {
+            model: request.model.to_string(),
+            instructions: request.instructions.map(str::to_string),
+            previous_response_id: None,
+            input: request.input.clone(),
+            parallel_tool_calls: None,
+            stream: true,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: Map::new(),
+        },
+        None,
+        None,
+    );
+    if let Some(effort) = request.reasoning.and_then(|reasoning| reasoning.effective_effort()) {
+        let effort = if effort.eq_ignore_ascii_case("xhigh") { "high" } else { effort };
+        payload.insert("reasoning_effort".to_string(), json!(effort));
+    }
+    let mut payload = Value::Object(payload);
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::Full,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    payload
+}
+
+/// Chat chunks carrying Perplexity's `citations` (source URLs) and `search_results` (their
+/// titles and dates). Both are repeated on every chunk, as is the running `usage`, so the last
+/// value seen wins.
+#[derive(Debug, Default)]
+pub(crate) struct PerplexityStreamAccumulator {
+    chat: ChatStreamAccumulator,
+    citations: Vec<String>,
+    search_results: Vec<Value>,
+}
+
+impl StreamAccumulator for PerplexityStreamAccumulator {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError> {
+        if let Ok(chunk) = serde_json::from_str::<Value>(data) {
+            if let Some(citations) = chunk.get("citations").and_then(Value::as_array) {
+                self.citations =
+                    citations.iter().filter_map(Value::as_str).map(str::to_string).collect();
+            }
+            if let Some(results) = chunk.get("search_results").and_then(Value::as_array) {
+                self.search_results = results.clone();
+            }
+        }
+        self.chat.push_event(data)
+    }
+
+    fn limit_retained_content(&mut self, max_bytes: usize) {
+        self.chat.limit_retained_content(max_bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError> {
+        let Self { chat, citations, search_results } = *self;
+        let mut outcome = Box::new(chat).finish()?;
+        let annotations =
+            citation_annotations(&outcome.chunks.concat(), citations, &search_results);
+        outcome.annotations = (!annotations.is_empty()).then_some(annotations);
+        Ok(outcome)
+    }
+}
+
+/// One annotation per `[n]` marker in `text` that refers to the n-th source. Sources the text
+/// never refers to are annotated with an empty span at its end, so none are lost.
+fn citation_annotations(
+    text: &str,
+    citations: Vec<String>,
+    search_results: &[Value],
+) -> Vec<OutputAnnotation> {
+    let urls = if citations.is_empty() {
+        search_results
+            .iter()
+            .filter_map(|result| result.get("url").and_then(Value::as_str))
+            .map(str::to_string)
+            .collect()
+    } else {
+        citations
+    };
+    let source = |url: &str| CitationSource {
+        kind: "url".to_string(),
+        id: Some(url.to_string()),
+        data: search_results
+            .iter()
+            .find(|result| result.get("url").and_then(Value::as_str) == Some(url))
+            .cloned(),
+    };
+
+    let mut annotations = Vec::new();
+    let mut cited = vec![false; urls.len()];
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut start = 0;
+    while start < chars.len() {
+        let digits = chars[start + 1..].iter().take_while(|c| c.is_ascii_digit()).count();
+        let end = start + digits + 2;
+        let number = (chars[start] == '[' && digits > 0 && chars.get(end - 1) == Some(&']'))
+            .then(|| chars[start + 1..end - 1].iter().collect::<String>().parse::<usize>().ok())
+            .flatten()
+            .filter(|number| (1..=urls.len()).contains(number));
+        let Some(number) = number else {
+            start += 1;
+            continue;
+        };
+        cited[number - 1] = true;
+        annotations.push(OutputAnnotation {
+            kind: "url_citation".to_string(),
+            start_index: start as u32,
+            end_index: end as u32,
+            text: chars[start..end].iter().collect(),
+            sources: vec![source(&urls[number - 1])],
+        });
+        start = end;
+    }
+    let end = chars.len() as u32;
+    for (url, _) in urls.iter().zip(cited).filter(|(_, cited)| !cited) {
+        annotations.push(OutputAnnotation {
+            kind: "url_citation".to_string(),
+            start_index: end,
+            end_index: end,
+            text: String::new(),
+            sources: vec![source(url)],
+        });
+    }
+    annotations
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use xrouter_contracts::{ReasoningConfig, ResponsesInput};
+    use xrouter_core::{ExecutionControls, ProviderGenerateRequest};
+
+    use super::{PerplexityStreamAccumulator, request_payload};
+    use crate::parser::StreamAccumulator;
+
+    #[test]
+    fn payloads_send_reasoning_effort_and_no_tools() {
+        let input = ResponsesInput::Text("Latest Rust release?".to_string());
+        let reasoning = ReasoningConfig { effort: Some("high".to_string()), ..Default::default() };
+        let tools = vec![json!({"type": "function", "function": {"name": "search"}})];
+        let request = ProviderGenerateRequest {
+            model: "sonar-reasoning-pro",
+            instructions: None,
+            input: &input,
+            reasoning: Some(&reasoning),
+            tools: Some(&tools),
+            tool_choice: None,
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra: None,
+            controls: ExecutionControls::default(),
+        };
+
+        let payload = request_payload(&request);
+        assert_eq!(payload["reasoning_effort"], "high");
+        assert!(payload.get("tools").is_none());
+        assert!(payload.get("reasoning").is_none());
+    }
+
+    #[test]
+    fn stream_citations_become_url_annotations_with_final_usage() {
+        let mut accumulator = Box::<PerplexityStreamAccumulator>::default();
+        let results = json!([
+            {"title": "Rust 1.90", "url": "https://blog.rust-lang.org/1.90", "date": "2025-09-18"},
+            {"title": "Releases", "url": "https://github.com/rust-lang/rust/releases"}
+        ]);
+        let chunk = |content: &str, finish: serde_json::Value, completion_tokens: u32| {
+            json!({
+                "id": "pplx-1",
+                "model": "sonar",
+                "citations": ["https://blog.rust-lang.org/1.90", "https://github.com/rust-lang/rust/releases"],
+                "search_results": results,
+                "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": finish}],
+                "usage": {"prompt_tokens": 6, "completion_tokens": completion_tokens, "total_tokens": 6 + completion_tokens, "citation_tokens": 310}
+            })
+            .to_string()
+        };
+        for event in [
+            chunk("Rust 1.90 is out", json!(null), 4),
+            chunk(" [1].", json!("stop"), 7),
+            "[DONE]".to_string(),
+        ] {
+            accumulator.push_event(&event).expect("event must parse");
+        }
+
+        let outcome = accumulator.finish().expect("stream must finish");
+        assert_eq!(outcome.chunks.concat(), "Rust 1.90 is out [1].");
+        assert_eq!(outcome.output_tokens, 7);
+        assert_eq!(outcome.usage.and_then(|usage| usage.prompt_tokens), Some(6));
+
+        let annotations = outcome.annotations.expect("citations must be annotated");
+        assert_eq!(annotations.len(), 2);
+        assert_eq!((annotations[0].start_index, annotations[0].end_index), (17, 20));
+        assert_eq!(annotations[0].text, "[1]");
+        assert_eq!(
+            annotations[0].sources[0].id.as_deref(),
+            Some("https://blog.rust-lang.org/1.90")
+        );
+        assert_eq!(
+            annotations[0].sources[0].data.as_ref().and_then(|data| data.get("title")),
+            Some(&json!("Rust 1.90"))
+        );
+        assert_eq!((annotations[1].start_index, annotations[1].end_index), (21, 21));
+        assert_eq!(
+            annotations[1].sources[0].id.as_deref(),
+            Some("https://github.com/rust-lang/rust/releases")
+        );
+    }
+}
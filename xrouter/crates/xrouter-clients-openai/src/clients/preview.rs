@@ -0,0 +1,158 @@
+use serde::Serialize;
+use serde_json::Value;
+use xrouter_core::{CoreError, ProviderGenerateRequest};
+
+use super::{
+    cloudflare, cohere, deepseek, gigachat, openai, openrouter, perplexity, selfhosted,
+    selfhosted::SelfHostedQuirks, tgi, xrouter, yandex, zai,
+};
+
+/// Upstream request body a provider client would send, built without sending it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PayloadPreview {
+    /// Path the body is posted to, relative to the provider base URL.
+    pub endpoint: &'static str,
+    pub payload: Value,
+    /// Tool normalization applied by the client; `None` for clients that pass tools as-is.
+    pub normalization: Option<ToolNormalization>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolNormalization {
+    pub tools_in: usize,
+    pub tools_out: usize,
+    pub tools_dropped: usize,
+    pub dropped_tool_types: Vec<String>,
+    pub tool_choice_in: String,
+    pub tool_choice_out: String,
+}
+
+macro_rules! tool_normalization {
+    ($normalization:expr) => {{
+        let normalization = $normalization;
+        Some(ToolNormalization {
+            tools_in: normalization.tools_in,
+            tools_out: normalization.tools_out,
+            tools_dropped: normalization.tools_dropped,
+            dropped_tool_types: normalization.dropped_tool_types,
+            tool_choice_in: normalization.tool_choice_in,
+            tool_choice_out: normalization.tool_choice_out,
+        })
+    }};
+}
+
+/// Runs the payload builder of `provider`'s client on `request`. Providers without a dedicated
+/// client use the generic OpenAI-compatible one, as in the provider factory.
+pub fn preview_payload(
+    provider: &str,
+    request: &ProviderGenerateRequest<'_>,
+    project: Option<&str>,
+) -> Result<PayloadPreview, CoreError> {
+    let chat = |(payload, normalization)| PayloadPreview {
+        endpoint: "chat/completions",
+        payload,
+        normalization,
+    };
+    Ok(match provider {
+        "openrouter" => {
+            let (payload, normalization) = openrouter::request_payload(request);
+            chat((payload, tool_normalization!(normalization)))
+        }
+        "deepseek" => {
+            let (payload, normalization) = deepseek::request_payload(request);
+            chat((payload, tool_normalization!(normalization)))
+        }
+        "zai" => {
+            let (payload, normalization) = zai::request_payload(request);
+            chat((payload, tool_normalization!(normalization)))
+        }
+        "gigachat" => {
+            let (payload, normalization) = gigachat::request_payload(request, &Default::default());
+            chat((payload, tool_normalization!(normalization)))
+        }
+        "xrouter" => {
+            let (payload, normalization) = xrouter::request_payload(request);
+            chat((payload, tool_normalization!(normalization)))
+        }
+        // Shown for the Responses API, whatever `YANDEX_CHAT_COMPLETIONS` is set to.
+        "yandex" => {
+            let (payload, normalization) = yandex::request_payload(request, project)?;
+            PayloadPreview {
+                endpoint: "responses",
+                payload,
+                normalization: tool_normalization!(normalization),
+            }
+        }
+        // Shown with the default quirks, whatever `SELFHOSTED_QUIRKS` is set to.
+        "selfhosted" => {
+            chat((selfhosted::request_payload(request, SelfHostedQuirks::default()), None))
+        }
+        "cloudflare" => PayloadPreview {
+            endpoint: cloudflare::RUN_ENDPOINT,
+            payload: cloudflare::request_payload(request),
+            normalization: None,
+        },
+        "cohere" => PayloadPreview {
+            endpoint: cohere::CHAT_ENDPOINT,
+            payload: cohere::request_payload(request),
+            normalization: None,
+        },
+        "perplexity" => PayloadPreview {
+            endpoint: perplexity::CHAT_ENDPOINT,
+            payload: perplexity::request_payload(request),
+            normalization: None,
+        },
+        "tgi" => PayloadPreview {
+            endpoint: tgi::MESSAGES_ENDPOINT,
+            payload: tgi::request_payload(request),
+            normalization: None,
+        },
+        _ => chat((openai::request_payload(request), None)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use xrouter_contracts::ResponsesInput;
+    use xrouter_core::{ExecutionControls, ProviderGenerateRequest};
+
+    use super::preview_payload;
+
+    #[test]
+    fn preview_shows_provider_specific_tool_normalization() {
+        let input = ResponsesInput::Text("list files".to_string());
+        let tools = vec![
+            json!({"type": "function", "name": "list_dir", "parameters": {"type": "object"}}),
+            json!({"type": "web_search"}),
+        ];
+        let request = |model| ProviderGenerateRequest {
+            model,
+            instructions: None,
+            input: &input,
+            reasoning: None,
+            tools: Some(&tools),
+            tool_choice: None,
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra: None,
+            controls: ExecutionControls::default(),
+        };
+
+        let yandex = preview_payload("yandex", &request("yandexgpt/rc"), Some("folder"))
+            .expect("yandex preview must build");
+        assert_eq!(yandex.endpoint, "responses");
+        assert_eq!(yandex.payload["model"], json!("gpt://folder/yandexgpt/rc"));
+        let normalization = yandex.normalization.expect("yandex normalizes tools");
+        assert_eq!((normalization.tools_in, normalization.tools_out), (2, 1));
+
+        let ollama =
+            preview_payload("ollama", &request("llama3"), None).expect("ollama preview must build");
+        assert_eq!(ollama.endpoint, "chat/completions");
+        assert_eq!(ollama.payload["model"], json!("llama3"));
+        assert!(ollama.normalization.is_none());
+    }
+}
@@ -0,0 +1,462 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+use xrouter_contracts::{OutputAnnotation, ResponseEvent, ResponsesInput, ToolCall};
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome, ProviderUsage, ResponseEventSink,
+};
+
+/// One recorded provider call: the live deltas it streamed and the outcome it returned.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderFixture {
+    pub provider: String,
+    pub model: String,
+    #[serde(default)]
+    pub events: Vec<FixtureEvent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<FixtureOutcome>,
+    /// Provider error message; replayed as `CoreError::Provider`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FixtureEvent {
+    OutputTextDelta { delta: String },
+    ReasoningDelta { delta: String },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FixtureOutcome {
+    pub chunks: Vec<String>,
+    pub output_tokens: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_details: Option<Vec<Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_encrypted_content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<OutputAnnotation>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_response_id: Option<String>,
+}
+
+impl From<&ProviderOutcome> for FixtureOutcome {
+    fn from(outcome: &ProviderOutcome) -> Self {
+        let usage = outcome.usage.clone().unwrap_or_default();
+        Self {
+            chunks: outcome.chunks.clone(),
+            output_tokens: outcome.output_tokens,
+            reasoning: outcome.reasoning.clone(),
+            reasoning_details: outcome.reasoning_details.clone(),
+            reasoning_encrypted_content: outcome.reasoning_encrypted_content.clone(),
+            tool_calls: outcome.tool_calls.clone(),
+            annotations: outcome.annotations.clone(),
+            finish_reason: outcome.finish_reason.clone(),
+            prompt_tokens: usage.prompt_tokens,
+            reasoning_tokens: usage.reasoning_tokens,
+            cached_tokens: usage.cached_tokens,
+            provider_response_id: outcome.provider_response_id.clone(),
+        }
+    }
+}
+
+impl FixtureOutcome {
+    fn into_outcome(self, emitted_live: bool) -> ProviderOutcome {
+        let usage = ProviderUsage {
+            prompt_tokens: self.prompt_tokens,
+            reasoning_tokens: self.reasoning_tokens,
+            cached_tokens: self.cached_tokens,
+        };
+        ProviderOutcome {
+            chunks: self.chunks,
+            output_tokens: self.output_tokens,
+            reasoning: self.reasoning,
+            reasoning_details: self.reasoning_details,
+            reasoning_encrypted_content: self.reasoning_encrypted_content,
+            tool_calls: self.tool_calls,
+            annotations: self.annotations,
+            emitted_live,
+            finish_reason: self.finish_reason,
+            usage: (usage != ProviderUsage::default()).then_some(usage),
+            upstream_attempts: 1,
+            provider_response_id: self.provider_response_id,
+            upstream_provider: None,
+        }
+    }
+}
+
+/// File name of the fixture for a request: provider, model and a stable hash of everything
+/// that shapes the upstream payload.
+pub fn fixture_key(provider: &str, request: &ProviderGenerateRequest<'_>) -> String {
+    #[derive(Serialize)]
+    struct KeyFields<'a> {
+        model: &'a str,
+        instructions: Option<&'a str>,
+        input: &'a ResponsesInput,
+        tools: Option<&'a [Value]>,
+        tool_choice: Option<&'a Value>,
+        include: Option<&'a [String]>,
+        modalities: Option<&'a [String]>,
+        // Skipped when absent so keys of fixtures recorded without passthrough fields hold.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        extra: Option<&'a serde_json::Map<String, Value>>,
+    }
+    let fields = KeyFields {
+        model: request.model,
+        instructions: request.instructions,
+        input: request.input,
+        tools: request.tools,
+        tool_choice: request.tool_choice,
+        include: request.include,
+        modalities: request.modalities,
+        extra: request.extra,
+    };
+    let bytes = serde_json::to_vec(&fields).unwrap_or_default();
+    // FNV-1a: stable across builds and platforms, unlike `DefaultHasher`.
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    let model = request
+        .model
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '.') { ch } else { '_' })
+        .collect::<String>();
+    format!("{provider}--{model}--{hash:016x}")
+}
+
+fn fixture_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{key}.json"))
+}
+
+/// Passes calls through to `inner` and writes each result to `<dir>/<fixture_key>.json`.
+pub struct RecordingProviderClient {
+    provider_id: String,
+    inner: Arc<dyn ProviderClient>,
+    dir: PathBuf,
+}
+
+impl RecordingProviderClient {
+    pub fn new(provider_id: String, inner: Arc<dyn ProviderClient>, dir: PathBuf) -> Self {
+        Self { provider_id, inner, dir }
+    }
+
+    fn write(&self, key: &str, fixture: &ProviderFixture) {
+        let written = fs::create_dir_all(&self.dir).and_then(|()| {
+            let payload = serde_json::to_vec_pretty(fixture).map_err(std::io::Error::other)?;
+            fs::write(fixture_path(&self.dir, key), payload)
+        });
+        match written {
+            Ok(()) => info!(
+                event = "provider.fixture.recorded",
+                provider = %self.provider_id,
+                fixture = %key
+            ),
+            Err(error) => warn!(
+                event = "provider.fixture.record_failed",
+                provider = %self.provider_id,
+                fixture = %key,
+                error = %error
+            ),
+        }
+    }
+
+    fn fixture(
+        &self,
+        model: &str,
+        events: Vec<FixtureEvent>,
+        result: &Result<ProviderOutcome, CoreError>,
+    ) -> ProviderFixture {
+        ProviderFixture {
+            provider: self.provider_id.clone(),
+            model: model.to_string(),
+            events,
+            outcome: result.as_ref().ok().map(FixtureOutcome::from),
+            error: result.as_ref().err().map(|error| match error {
+                CoreError::Provider(message) => message.clone(),
+                other => other.to_string(),
+            }),
+        }
+    }
+}
+
+struct RecordingSink<'a> {
+    inner: &'a dyn ResponseEventSink,
+    events: Mutex<Vec<FixtureEvent>>,
+}
+
+#[async_trait]
+impl ResponseEventSink for RecordingSink<'_> {
+    async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+        let recorded = match &event {
+            Ok(ResponseEvent::OutputTextDelta { delta, .. }) => {
+                Some(FixtureEvent::OutputTextDelta { delta: delta.clone() })
+            }
+            Ok(ResponseEvent::ReasoningDelta { delta, .. }) => {
+                Some(FixtureEvent::ReasoningDelta { delta: delta.clone() })
+            }
+            _ => None,
+        };
+        if let Some(recorded) = recorded {
+            self.events.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(recorded);
+        }
+        self.inner.send(event).await;
+    }
+
+    fn output_closed(&self) -> bool {
+        self.inner.output_closed()
+    }
+
+    fn retained_output_limit(&self) -> Option<usize> {
+        self.inner.retained_output_limit()
+    }
+}
+
+#[async_trait]
+impl ProviderClient for RecordingProviderClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let key = fixture_key(&self.provider_id, &request);
+        let result = self.inner.generate(request).await;
+        self.write(&key, &self.fixture(request.model, Vec::new(), &result));
+        result
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let key = fixture_key(&self.provider_id, &request.request);
+        let recorder =
+            request.sender.map(|inner| RecordingSink { inner, events: Mutex::new(Vec::new()) });
+        let result = self
+            .inner
+            .generate_stream(ProviderGenerateStreamRequest {
+                request_id: request.request_id,
+                request: request.request,
+                sender: recorder.as_ref().map(|sink| sink as &dyn ResponseEventSink),
+            })
+            .await;
+        let events = recorder
+            .map(|sink| sink.events.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner()))
+            .unwrap_or_default();
+        self.write(&key, &self.fixture(request.request.model, events, &result));
+        result
+    }
+
+    fn supports_required_tool_choice(&self) -> bool {
+        self.inner.supports_required_tool_choice()
+    }
+}
+
+/// Serves fixtures written by `RecordingProviderClient` without any network access.
+/// Requests without a fixture fail with a provider error naming the missing file.
+pub struct ReplayProviderClient {
+    provider_id: String,
+    dir: PathBuf,
+}
+
+impl ReplayProviderClient {
+    pub fn new(provider_id: String, dir: PathBuf) -> Self {
+        Self { provider_id, dir }
+    }
+
+    fn load(&self, request: &ProviderGenerateRequest<'_>) -> Result<ProviderFixture, CoreError> {
+        let key = fixture_key(&self.provider_id, request);
+        let path = fixture_path(&self.dir, &key);
+        let raw = fs::read(&path).map_err(|_| {
+            CoreError::Provider(format!("no replay fixture for this request: {key}.json"))
+        })?;
+        serde_json::from_slice(&raw).map_err(|error| {
+            CoreError::Provider(format!("replay fixture {key}.json is invalid: {error}"))
+        })
+    }
+}
+
+#[async_trait]
+impl ProviderClient for ReplayProviderClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let fixture = self.load(&request)?;
+        match (fixture.outcome, fixture.error) {
+            (Some(outcome), _) => Ok(outcome.into_outcome(false)),
+            (None, error) => Err(CoreError::Provider(
+                error.unwrap_or_else(|| "replay fixture has no outcome".to_string()),
+            )),
+        }
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let fixture = self.load(&request.request)?;
+        let Some(sender) = request.sender else {
+            return self.generate(request.request).await;
+        };
+        let events = if fixture.events.is_empty() {
+            fixture
+                .outcome
+                .iter()
+                .flat_map(|outcome| outcome.chunks.iter().cloned())
+                .map(|delta| FixtureEvent::OutputTextDelta { delta })
+                .collect()
+        } else {
+            fixture.events
+        };
+        let id = request.request_id.to_string();
+        for event in events {
+            if sender.output_closed() {
+                break;
+            }
+            let event = match event {
+                FixtureEvent::OutputTextDelta { delta } => {
+                    ResponseEvent::OutputTextDelta { id: id.clone(), delta }
+                }
+                FixtureEvent::ReasoningDelta { delta } => {
+                    ResponseEvent::ReasoningDelta { id: id.clone(), delta }
+                }
+            };
+            sender.send(Ok(event)).await;
+        }
+        match (fixture.outcome, fixture.error) {
+            (Some(outcome), _) => Ok(outcome.into_outcome(true)),
+            (None, error) => Err(CoreError::Provider(
+                error.unwrap_or_else(|| "replay fixture has no outcome".to_string()),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use xrouter_contracts::{ResponseEvent, ResponsesInput};
+    use xrouter_core::{
+        CoreError, ExecutionControls, ProviderClient, ProviderGenerateRequest,
+        ProviderGenerateStreamRequest, ResponseEventSink,
+    };
+
+    use super::{RecordingProviderClient, ReplayProviderClient};
+    use crate::MockProviderClient;
+
+    #[derive(Default)]
+    struct CollectingSink {
+        events: Mutex<Vec<ResponseEvent>>,
+    }
+
+    #[async_trait]
+    impl ResponseEventSink for CollectingSink {
+        async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+            if let Ok(event) = event {
+                self.events.lock().expect("sink lock").push(event);
+            }
+        }
+    }
+
+    fn request<'a>(model: &'a str, input: &'a ResponsesInput) -> ProviderGenerateRequest<'a> {
+        ProviderGenerateRequest {
+            model,
+            instructions: None,
+            input,
+            reasoning: None,
+            tools: None,
+            tool_choice: None,
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra: None,
+            controls: ExecutionControls::default(),
+        }
+    }
+
+    async fn stream(
+        client: &dyn ProviderClient,
+        request_id: &str,
+        request: ProviderGenerateRequest<'_>,
+    ) -> (Result<xrouter_core::ProviderOutcome, CoreError>, Vec<ResponseEvent>) {
+        let sink = CollectingSink::default();
+        let result = client
+            .generate_stream(ProviderGenerateStreamRequest {
+                request_id,
+                request,
+                sender: Some(&sink),
+            })
+            .await;
+        (result, sink.events.into_inner().expect("sink lock"))
+    }
+
+    #[tokio::test]
+    async fn recorded_streams_replay_the_same_deltas_and_outcome() {
+        let dir = std::env::temp_dir().join(format!("xrouter-fixtures-{}", uuid::Uuid::new_v4()));
+        let recorder = RecordingProviderClient::new(
+            "deepseek".to_string(),
+            Arc::new(MockProviderClient::new("deepseek".to_string())),
+            dir.clone(),
+        );
+        let replay = ReplayProviderClient::new("deepseek".to_string(), dir.clone());
+        let input = ResponsesInput::Text("hello fixtures".to_string());
+        let failing = ResponsesInput::Text("__FAIL_PROVIDER__".to_string());
+
+        let (recorded, recorded_events) =
+            stream(&recorder, "req_a", request("deepseek-reasoner", &input)).await;
+        let (replayed, replayed_events) =
+            stream(&replay, "req_a", request("deepseek-reasoner", &input)).await;
+        assert_eq!(replayed, recorded);
+        assert_eq!(replayed_events, recorded_events);
+        assert!(
+            replayed_events
+                .iter()
+                .any(|event| matches!(event, ResponseEvent::ReasoningDelta { .. }))
+        );
+
+        let unbuffered = replay
+            .generate(request("deepseek-reasoner", &input))
+            .await
+            .expect("replay without a sink must succeed");
+        assert_eq!(unbuffered.chunks, recorded.expect("recorded call must succeed").chunks);
+        assert!(!unbuffered.emitted_live);
+
+        let _ = recorder.generate(request("deepseek-chat", &failing)).await;
+        assert_eq!(
+            replay.generate(request("deepseek-chat", &failing)).await,
+            Err(CoreError::Provider("provider failed".to_string()))
+        );
+
+        let other = ResponsesInput::Text("never recorded".to_string());
+        let missing = replay.generate(request("deepseek-chat", &other)).await;
+        assert!(
+            matches!(missing, Err(CoreError::Provider(message)) if message.starts_with("no replay fixture"))
+        );
+
+        std::fs::remove_dir_all(dir).expect("temp dir must be removed");
+    }
+}
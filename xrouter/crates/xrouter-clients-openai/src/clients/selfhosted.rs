@@ -0,0 +1,283 @@
+use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::Client;
+use serde_json::Value;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome,
+};
+
+use super::openai;
+use crate::parser::estimate_output_tokens;
+use crate::runtime::SharedProviderRuntime;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::HttpRuntime;
+
+/// Deviations of self-hosted OpenAI-compatible servers (vLLM, llama.cpp server) from the
+/// OpenAI API that the `selfhosted` provider works around. All are on by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfHostedQuirks {
+    /// The server rejects or misreads `reasoning`/`reasoning_effort`; they are not sent.
+    pub no_reasoning: bool,
+    /// The server only accepts `stop` as an array of non-empty strings.
+    pub strict_stop: bool,
+    /// The server cannot run parallel tool calls; one is requested and only the first is kept.
+    pub single_tool_call: bool,
+    /// The server omits or zeroes usage; `stream_options` is not sent and tokens are estimated.
+    pub no_usage: bool,
+}
+
+impl Default for SelfHostedQuirks {
+    fn default() -> Self {
+        Self { no_reasoning: true, strict_stop: true, single_tool_call: true, no_usage: true }
+    }
+}
+
+impl SelfHostedQuirks {
+    pub const NAMES: [&'static str; 4] =
+        ["no_reasoning", "strict_stop", "single_tool_call", "no_usage"];
+
+    pub fn none() -> Self {
+        Self { no_reasoning: false, strict_stop: false, single_tool_call: false, no_usage: false }
+    }
+
+    /// Comma-separated quirk names; `none` turns every quirk off. `None` for unknown names.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let mut quirks = Self::none();
+        for name in raw.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name {
+                "none" => {}
+                "no_reasoning" => quirks.no_reasoning = true,
+                "strict_stop" => quirks.strict_stop = true,
+                "single_tool_call" => quirks.single_tool_call = true,
+                "no_usage" => quirks.no_usage = true,
+                _ => return None,
+            }
+        }
+        Some(quirks)
+    }
+
+    fn apply_to_payload(self, payload: &mut Value) {
+        let Some(fields) = payload.as_object_mut() else {
+            return;
+        };
+        if self.no_reasoning {
+            fields.remove("reasoning");
+            fields.remove("reasoning_effort");
+        }
+        if self.strict_stop {
+            let stops = match fields.remove("stop") {
+                Some(Value::String(stop)) => vec![Value::String(stop)],
+                Some(Value::Array(stops)) => stops,
+                _ => Vec::new(),
+            };
+            let stops = stops
+                .into_iter()
+                .filter(|stop| stop.as_str().is_some_and(|stop| !stop.is_empty()))
+                .collect::<Vec<_>>();
+            if !stops.is_empty() {
+                fields.insert("stop".to_string(), Value::Array(stops));
+            }
+        }
+        if self.single_tool_call && fields.contains_key("tools") {
+            fields.insert("parallel_tool_calls".to_string(), Value::Bool(false));
+        }
+        if self.no_usage {
+            fields.remove("stream_options");
+        }
+    }
+
+    fn apply_to_outcome(self, outcome: &mut ProviderOutcome) {
+        if self.single_tool_call
+            && let Some(tool_calls) = outcome.tool_calls.as_mut()
+        {
+            tool_calls.truncate(1);
+        }
+        if self.no_usage {
+            outcome.usage = None;
+            outcome.output_tokens = estimate_output_tokens(&outcome.chunks.concat(), 0);
+        }
+    }
+}
+
+/// OpenAI-compatible self-hosted backend with [`SelfHostedQuirks`] applied.
+pub struct SelfHostedClient {
+    runtime: SharedProviderRuntime,
+    quirks: SelfHostedQuirks,
+}
+
+impl SelfHostedClient {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(
+        base_url: Option<String>,
+        api_key: Option<String>,
+        quirks: SelfHostedQuirks,
+        http_client: Option<Client>,
+        max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
+    ) -> Self {
+        Self::with_runtime(
+            Arc::new(HttpRuntime::new(
+                "selfhosted".to_string(),
+                base_url,
+                api_key,
+                http_client,
+                max_inflight,
+                transcripts,
+            )),
+            quirks,
+        )
+    }
+
+    pub fn with_runtime(runtime: SharedProviderRuntime, quirks: SelfHostedQuirks) -> Self {
+        Self { runtime, quirks }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ProviderClient for SelfHostedClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.runtime.build_url("chat/completions")?;
+        let payload = request_payload(&request, self.quirks);
+        let mut outcome = self
+            .runtime
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &[],
+                request.controls.upstream,
+                None,
+            )
+            .await?;
+        self.quirks.apply_to_outcome(&mut outcome);
+        Ok(outcome)
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.runtime.build_url("chat/completions")?;
+        let payload = request_payload(&request.request, self.quirks);
+        let mut outcome = self
+            .runtime
+            .post_chat_completions_stream(
+                request.request_id,
+                &url,
+                &payload,
+                request.request.auth_bearer,
+                &[],
+                request.request.controls.upstream,
+                request.sender,
+            )
+            .await?;
+        self.quirks.apply_to_outcome(&mut outcome);
+        Ok(outcome)
+    }
+}
+
+/// The generic OpenAI-compatible body with `quirks` applied, passthrough fields included.
+pub(crate) fn request_payload(
+    request: &ProviderGenerateRequest<'_>,
+    quirks: SelfHostedQuirks,
+) -> Value {
+    let mut payload = openai::request_payload(request);
+    quirks.apply_to_payload(&mut payload);
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use xrouter_contracts::{ReasoningConfig, ResponsesInput, ToolCall, ToolFunction};
+    use xrouter_core::{
+        ExecutionControls, ProviderGenerateRequest, ProviderOutcome, ProviderUsage,
+    };
+
+    use super::{SelfHostedQuirks, request_payload};
+
+    #[test]
+    fn quirks_parse_from_names_and_reject_unknown_ones() {
+        assert_eq!(
+            SelfHostedQuirks::parse("no_usage, single_tool_call"),
+            Some(SelfHostedQuirks {
+                single_tool_call: true,
+                no_usage: true,
+                ..SelfHostedQuirks::none()
+            })
+        );
+        assert_eq!(SelfHostedQuirks::parse("none"), Some(SelfHostedQuirks::none()));
+        assert_eq!(SelfHostedQuirks::parse("no_logprobs"), None);
+    }
+
+    #[test]
+    fn payloads_drop_reasoning_and_normalize_stop_and_tool_calls() {
+        let input = ResponsesInput::Text("list files".to_string());
+        let reasoning = ReasoningConfig { effort: Some("high".to_string()), ..Default::default() };
+        let tools = vec![json!({"type": "function", "function": {"name": "ls"}})];
+        let extra = json!({"stop": "END", "stream_options": {"include_usage": true}});
+        let request = ProviderGenerateRequest {
+            model: "qwen2.5-7b-instruct",
+            instructions: None,
+            input: &input,
+            reasoning: Some(&reasoning),
+            tools: Some(&tools),
+            tool_choice: None,
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra: extra.as_object(),
+            controls: ExecutionControls::default(),
+        };
+
+        let payload = request_payload(&request, SelfHostedQuirks::default());
+        assert!(payload.get("reasoning").is_none());
+        assert!(payload.get("stream_options").is_none());
+        assert_eq!(payload["stop"], json!(["END"]));
+        assert_eq!(payload["parallel_tool_calls"], false);
+
+        let untouched = request_payload(&request, SelfHostedQuirks::none());
+        assert_eq!(untouched["reasoning"], json!({"effort": "high"}));
+        assert_eq!(untouched["stop"], "END");
+    }
+
+    #[test]
+    fn outcomes_keep_one_tool_call_and_estimate_missing_usage() {
+        let call = |id: &str| ToolCall {
+            id: id.to_string(),
+            kind: "function".to_string(),
+            function: ToolFunction { name: "ls".to_string(), arguments: "{}".to_string() },
+        };
+        let mut outcome = ProviderOutcome {
+            chunks: vec!["three words here".to_string()],
+            output_tokens: 0,
+            reasoning: None,
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls: Some(vec![call("a"), call("b")]),
+            annotations: None,
+            emitted_live: false,
+            finish_reason: None,
+            usage: Some(ProviderUsage::default()),
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
+        };
+
+        SelfHostedQuirks::default().apply_to_outcome(&mut outcome);
+        assert_eq!(outcome.tool_calls.as_ref().map(Vec::len), Some(1));
+        assert_eq!((outcome.output_tokens, outcome.usage), (3, None));
+    }
+}
@@ -0,0 +1,337 @@
+use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::Client;
+use serde_json::{Map, Value, json};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use xrouter_contracts::{ResponsesInput, ResponsesRequest};
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome,
+};
+
+use crate::parser::{
+    ChatStreamAccumulator, ContentBuffer, StreamAccumulator, StreamDelta, estimate_output_tokens,
+    normalize_finish_reason,
+};
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    build_chat_messages_from_responses_input, compact_payload,
+};
+use crate::runtime::SharedProviderRuntime;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::HttpRuntime;
+
+pub(crate) const MESSAGES_ENDPOINT: &str = "v1/chat/completions";
+const NATIVE_ENDPOINT: &str = "generate_stream";
+
+/// Text Generation Inference, self-hosted or behind a Hugging Face Inference Endpoint. The
+/// OpenAI-compatible Messages API is used unless `native` selects `generate_stream`, which
+/// takes a plain-text prompt without the model's chat template.
+pub struct TgiClient {
+    runtime: SharedProviderRuntime,
+    native: bool,
+}
+
+impl TgiClient {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(
+        base_url: Option<String>,
+        api_key: Option<String>,
+        native: bool,
+        http_client: Option<Client>,
+        max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
+    ) -> Self {
+        Self::with_runtime(
+            Arc::new(
+                HttpRuntime::new(
+                    "tgi".to_string(),
+                    base_url,
+                    api_key,
+                    http_client,
+                    max_inflight,
+                    transcripts,
+                )
+                .with_stream_accumulator(|| Box::<TgiStreamAccumulator>::default()),
+            ),
+            native,
+        )
+    }
+
+    pub fn with_runtime(runtime: SharedProviderRuntime, native: bool) -> Self {
+        Self { runtime, native }
+    }
+
+    fn endpoint(
+        &self,
+        request: &ProviderGenerateRequest<'_>,
+    ) -> Result<(String, Value), CoreError> {
+        if self.native {
+            Ok((self.runtime.build_url(NATIVE_ENDPOINT)?, native_payload(request)))
+        } else {
+            Ok((self.runtime.build_url(MESSAGES_ENDPOINT)?, request_payload(request)))
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ProviderClient for TgiClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let (url, payload) = self.endpoint(&request)?;
+        self.runtime
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &[],
+                request.controls.upstream,
+                None,
+            )
+            .await
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let (url, payload) = self.endpoint(&request.request)?;
+        self.runtime
+            .post_chat_completions_stream(
+                request.request_id,
+                &url,
+                &payload,
+                request.request.auth_bearer,
+                &[],
+                request.request.controls.upstream,
+                request.sender,
+            )
+            .await
+    }
+}
+
+/// Messages API body, shared by both generate paths and payload previews. TGI serves a single
+/// model and does not know OpenAI reasoning options, so none are sent.
+pub(crate) fn request_payload(request: &ProviderGenerateRequest<'_>) -> Value {
+    let mut payload = Value::Object(base_chat_payload(
+        &ResponsesRequest {
+            model: request.model.to_string(),
+            instructions: request.instructions.map(str::to_string),
+            previous_response_id: None,
+            input: request.input.clone(),
+            parallel_tool_calls: None,
+            stream: true,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: Map::new(),
+        },
+        request.tools,
+        request.tool_choice,
+    ));
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::JsonObjectOnly,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    payload
+}
+
+/// `generate_stream` body; passthrough fields become generation `parameters`, with
+/// `max_tokens` renamed to TGI's `max_new_tokens`.
+pub(crate) fn native_payload(request: &ProviderGenerateRequest<'_>) -> Value {
+    let mut parameters = Map::new();
+    for (name, value) in request.extra.into_iter().flatten() {
+        let name = if name == "max_tokens" { "max_new_tokens" } else { name.as_str() };
+        parameters.insert(name.to_string(), value.clone());
+    }
+    parameters.insert("details".to_string(), Value::Bool(true));
+    parameters.insert("return_full_text".to_string(), Value::Bool(false));
+    json!({
+        "inputs": native_prompt(request.instructions, request.input),
+        "parameters": parameters,
+        "stream": true
+    })
+}
+
+/// Instructions and message texts separated by blank lines.
+fn native_prompt(instructions: Option<&str>, input: &ResponsesInput) -> String {
+    build_chat_messages_from_responses_input(instructions, input)
+        .iter()
+        .filter_map(|message| message.get("content").and_then(Value::as_str))
+        .filter(|content| !content.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Reads both `generate_stream` token events and Messages API chunks, which TGI serves from the
+/// same base URL.
+#[derive(Debug, Default)]
+pub(crate) struct TgiStreamAccumulator {
+    chat: ChatStreamAccumulator,
+    native: bool,
+    content: ContentBuffer,
+    generated_tokens: Option<u32>,
+    finish_reason: Option<String>,
+}
+
+impl StreamAccumulator for TgiStreamAccumulator {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError> {
+        let Ok(event) = serde_json::from_str::<Value>(data) else {
+            return self.chat.push_event(data);
+        };
+        if let Some(error) = event.get("error").and_then(Value::as_str) {
+            return Err(CoreError::Provider(format!("provider stream error: {error}")));
+        }
+        let Some(token) = event.get("token") else {
+            return self.chat.push_event(data);
+        };
+        self.native = true;
+        let mut delta = StreamDelta::default();
+        if let Some(text) = token.get("text").and_then(Value::as_str)
+            && !token.get("special").and_then(Value::as_bool).unwrap_or(false)
+            && !text.is_empty()
+        {
+            self.content.push(text);
+            delta.content.push(text.to_string());
+        }
+        if let Some(details) = event.get("details").filter(|details| !details.is_null()) {
+            self.finish_reason = details
+                .get("finish_reason")
+                .and_then(Value::as_str)
+                .and_then(normalize_finish_reason);
+            self.generated_tokens = details
+                .get("generated_tokens")
+                .and_then(Value::as_u64)
+                .and_then(|tokens| u32::try_from(tokens).ok());
+        }
+        Ok(delta)
+    }
+
+    fn limit_retained_content(&mut self, max_bytes: usize) {
+        self.chat.limit_retained_content(max_bytes);
+        self.content.set_limit(max_bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError> {
+        let Self { chat, native, content, generated_tokens, finish_reason } = *self;
+        if !native {
+            return Box::new(chat).finish();
+        }
+        if content.is_empty() {
+            return Err(CoreError::Provider("provider returned empty message content".to_string()));
+        }
+        let dropped_words = content.dropped_words();
+        let text = content.into_text();
+        Ok(ProviderOutcome {
+            output_tokens: generated_tokens
+                .unwrap_or_else(|| estimate_output_tokens(&text, dropped_words)),
+            chunks: vec![text],
+            reasoning: None,
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls: None,
+            annotations: None,
+            emitted_live: false,
+            finish_reason,
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Map, json};
+    use xrouter_contracts::ResponsesInput;
+    use xrouter_core::{ExecutionControls, ProviderGenerateRequest};
+
+    use super::{TgiStreamAccumulator, native_payload, request_payload};
+    use crate::parser::StreamAccumulator;
+
+    fn request<'a>(
+        input: &'a ResponsesInput,
+        extra: Option<&'a Map<String, serde_json::Value>>,
+    ) -> ProviderGenerateRequest<'a> {
+        ProviderGenerateRequest {
+            model: "meta-llama/Llama-3.1-8B-Instruct",
+            instructions: Some("Be brief."),
+            input,
+            reasoning: None,
+            tools: None,
+            tool_choice: None,
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra,
+            controls: ExecutionControls::default(),
+        }
+    }
+
+    #[test]
+    fn native_requests_send_a_prompt_and_generation_parameters() {
+        let input = ResponsesInput::Text("Say hi".to_string());
+        let extra = json!({"max_tokens": 64, "temperature": 0.2});
+        let payload = native_payload(&request(&input, extra.as_object()));
+
+        assert_eq!(payload["inputs"], "Be brief.\n\nSay hi");
+        assert_eq!(payload["parameters"]["max_new_tokens"], 64);
+        assert_eq!(payload["parameters"]["temperature"], 0.2);
+        assert_eq!(payload["parameters"]["details"], true);
+        assert!(payload["parameters"].get("max_tokens").is_none());
+
+        let messages = request_payload(&request(&input, None));
+        assert_eq!(messages["messages"][1], json!({"role": "user", "content": "Say hi"}));
+        assert!(messages.get("reasoning").is_none());
+    }
+
+    #[test]
+    fn native_stream_events_skip_special_tokens_and_report_generated_tokens() {
+        let mut accumulator = Box::<TgiStreamAccumulator>::default();
+        let events = [
+            r#"{"index":1,"token":{"id":1,"text":"Hi","special":false},"generated_text":null,"details":null}"#,
+            r#"{"index":2,"token":{"id":2,"text":" there","special":false},"generated_text":null,"details":null}"#,
+            r#"{"index":3,"token":{"id":3,"text":"</s>","special":true},"generated_text":"Hi there","details":{"finish_reason":"eos_token","generated_tokens":3}}"#,
+        ];
+        let deltas = events
+            .iter()
+            .flat_map(|event| accumulator.push_event(event).expect("event must parse").content)
+            .collect::<Vec<_>>();
+        assert_eq!(deltas, ["Hi", " there"]);
+
+        let outcome = accumulator.finish().expect("stream must finish");
+        assert_eq!(outcome.chunks, ["Hi there"]);
+        assert_eq!(outcome.output_tokens, 3);
+        assert_eq!(outcome.finish_reason.as_deref(), Some("stop"));
+
+        let mut failing = TgiStreamAccumulator::default();
+        assert!(
+            failing
+                .push_event(r#"{"error":"Input validation error","error_type":"validation"}"#)
+                .is_err()
+        );
+    }
+}
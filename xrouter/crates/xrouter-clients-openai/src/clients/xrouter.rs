@@ -13,9 +13,14 @@ use xrouter_core::{
     ProviderOutcome,
 };
 
-use crate::protocol::base_chat_payload;
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    compact_payload,
+};
 use crate::runtime::SharedProviderRuntime;
 #[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::transport::HttpRuntime;
 
 pub struct XrouterClient {
@@ -29,6 +34,7 @@ impl XrouterClient {
         api_key: Option<String>,
         http_client: Option<Client>,
         max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
     ) -> Self {
         Self::with_runtime(Arc::new(HttpRuntime::new(
             "xrouter".to_string(),
@@ -36,6 +42,7 @@ impl XrouterClient {
             api_key,
             http_client,
             max_inflight,
+            transcripts,
         )))
     }
 
@@ -52,14 +59,7 @@ impl ProviderClient for XrouterClient {
         request: ProviderGenerateRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_xrouter_payload(
-            request.model,
-            request.instructions,
-            request.input,
-            request.reasoning,
-            request.tools,
-            request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request);
         info!(
             event = "provider.request.payload.normalized",
             provider = "xrouter",
@@ -79,7 +79,15 @@ impl ProviderClient for XrouterClient {
             );
         }
         self.runtime
-            .post_chat_completions_stream("request", &url, &payload, request.auth_bearer, &[], None)
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                request.forward_headers,
+                request.controls.upstream,
+                None,
+            )
             .await
     }
 
@@ -88,14 +96,7 @@ impl ProviderClient for XrouterClient {
         request: ProviderGenerateStreamRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_xrouter_payload(
-            request.request.model,
-            request.request.instructions,
-            request.request.input,
-            request.request.reasoning,
-            request.request.tools,
-            request.request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request.request);
         info!(
             event = "provider.request.payload.normalized",
             provider = "xrouter",
@@ -120,13 +121,37 @@ impl ProviderClient for XrouterClient {
                 &url,
                 &payload,
                 request.request.auth_bearer,
-                &[],
+                request.request.forward_headers,
+                request.request.controls.upstream,
                 request.sender,
             )
             .await
     }
 }
 
+/// Upstream body for `request`, shared by both generate paths and payload previews.
+pub(crate) fn request_payload(
+    request: &ProviderGenerateRequest<'_>,
+) -> (Value, XrouterNormalization) {
+    let (mut payload, normalization) = build_xrouter_payload(
+        request.model,
+        request.instructions,
+        request.input,
+        request.reasoning,
+        request.tools,
+        request.tool_choice,
+    );
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::Full,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    (payload, normalization)
+}
+
 pub(crate) fn build_xrouter_payload(
     model: &str,
     instructions: Option<&str>,
@@ -152,8 +177,13 @@ pub(crate) fn build_xrouter_payload(
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         },
         Some(&normalized_tools.tools),
         normalized_tool_choice.as_ref(),
@@ -7,20 +7,29 @@ use std::sync::Arc;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 use xrouter_contracts::{
-    ResponseInputContent, ResponseInputItem, ResponseToolOutput, ResponsesInput, ToolCall,
-    ToolFunction,
+    CitationSource, OutputAnnotation, ResponseInputContent, ResponseInputItem, ResponseToolOutput,
+    ResponsesInput, TextControls, ToolCall, ToolFunction,
 };
 use xrouter_core::{
     CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
     ProviderOutcome,
 };
 
+use crate::parser::{
+    ContentBuffer, StreamAccumulator, StreamDelta, native_usage_from_value, responses_finish_reason,
+};
+use crate::protocol::{apply_passthrough_fields, compact_payload};
 use crate::runtime::SharedProviderRuntime;
 #[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::transport::HttpRuntime;
 
 const LEGACY_TOOL_CALL_START_MARKER: &str = "[TOOL_CALL_START]";
 const LEGACY_TOOL_CALL_END_MARKER: &str = "[TOOL_CALL_END]";
+/// Request field with Yandex-specific options; it must be allowed in `XR_PASSTHROUGH_FIELDS`.
+const OPTIONS_FIELD: &str = "options";
+const SEARCH_CONTEXT_SIZES: [&str; 3] = ["low", "medium", "high"];
 
 pub struct YandexResponsesClient {
     runtime: SharedProviderRuntime,
@@ -35,15 +44,20 @@ impl YandexResponsesClient {
         project: Option<String>,
         http_client: Option<Client>,
         max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
     ) -> Self {
         Self::with_runtime(
-            Arc::new(HttpRuntime::new(
-                "yandex".to_string(),
-                base_url,
-                api_key,
-                http_client,
-                max_inflight,
-            )),
+            Arc::new(
+                HttpRuntime::new(
+                    "yandex".to_string(),
+                    base_url,
+                    api_key,
+                    http_client,
+                    max_inflight,
+                    transcripts,
+                )
+                .with_stream_accumulator(|| Box::<YandexStreamAccumulator>::default()),
+            ),
             project,
         )
     }
@@ -61,13 +75,7 @@ impl ProviderClient for YandexResponsesClient {
         request: ProviderGenerateRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("responses")?;
-        let upstream_model = build_yandex_upstream_model(request.model, self.project.as_deref())?;
-        let (payload, normalization) = build_yandex_responses_payload(
-            &upstream_model,
-            request.input,
-            request.tools,
-            request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request, self.project.as_deref())?;
         info!(
             event = "provider.request.payload.normalized",
             provider = "yandex",
@@ -91,7 +99,15 @@ impl ProviderClient for YandexResponsesClient {
             headers.push(("OpenAI-Project".to_string(), project.to_string()));
         }
         self.runtime
-            .post_responses_stream("request", &url, &payload, request.auth_bearer, &headers, None)
+            .post_responses_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &headers,
+                request.controls.upstream,
+                None,
+            )
             .await
     }
 
@@ -100,14 +116,7 @@ impl ProviderClient for YandexResponsesClient {
         request: ProviderGenerateStreamRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("responses")?;
-        let upstream_model =
-            build_yandex_upstream_model(request.request.model, self.project.as_deref())?;
-        let (payload, normalization) = build_yandex_responses_payload(
-            &upstream_model,
-            request.request.input,
-            request.request.tools,
-            request.request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request.request, self.project.as_deref())?;
         info!(
             event = "provider.request.payload.normalized",
             provider = "yandex",
@@ -130,6 +139,17 @@ impl ProviderClient for YandexResponsesClient {
         if let Some(project) = self.project.as_deref().filter(|value| !value.trim().is_empty()) {
             headers.push(("OpenAI-Project".to_string(), project.to_string()));
         }
+        // Yandex may answer tool requests with legacy tool-call text, which must not reach the
+        // client as output deltas, so those responses are buffered until completion.
+        let live_sender = request.sender.filter(|_| normalization.tools_out == 0);
+        if request.sender.is_some() && live_sender.is_none() {
+            debug!(
+                event = "provider.stream.buffered",
+                provider = "yandex",
+                model = request.request.model,
+                reason = "tools"
+            );
+        }
         self.runtime
             .post_responses_stream(
                 request.request_id,
@@ -137,17 +157,186 @@ impl ProviderClient for YandexResponsesClient {
                 &payload,
                 request.request.auth_bearer,
                 &headers,
-                request.sender,
+                request.request.controls.upstream,
+                live_sender,
             )
             .await
     }
 }
 
+/// Upstream body for `request`, shared by both generate paths and payload previews. A passed
+/// through `options` block is validated and applied instead of being forwarded as is.
+pub(crate) fn request_payload(
+    request: &ProviderGenerateRequest<'_>,
+    project: Option<&str>,
+) -> Result<(Value, YandexNormalization), CoreError> {
+    let (extra, options) = take_yandex_options(request.extra)?;
+    let upstream_model = build_yandex_upstream_model(request.model, project)?;
+    let (mut payload, normalization) = build_yandex_responses_payload(
+        &upstream_model,
+        request.input,
+        request.tools,
+        request.tool_choice,
+        request.include,
+        request.text,
+    );
+    if let Some(options) = &options {
+        options.apply(&mut payload);
+    }
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, Some(&extra));
+    Ok((payload, normalization))
+}
+
+/// YandexGPT options a request may set in its `options` block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct YandexOptions {
+    /// Sampling temperature on the YandexGPT scale, `0` to `1`.
+    temperature: Option<f64>,
+    /// Grounds the answer in web search results through the `web_search` tool.
+    search: Option<YandexSearch>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct YandexSearch {
+    allowed_domains: Vec<String>,
+    context_size: Option<String>,
+}
+
+impl YandexOptions {
+    /// Chat completions have no `web_search` tool, so search grounding is refused there.
+    pub(crate) fn apply_chat(&self, payload: &mut Value) -> Result<(), CoreError> {
+        if self.search.is_some() {
+            return Err(invalid_option(
+                "search",
+                "search grounding needs the Responses API; unset YANDEX_CHAT_COMPLETIONS",
+            ));
+        }
+        if let (Some(temperature), Some(payload)) = (self.temperature, payload.as_object_mut()) {
+            payload.insert("temperature".to_string(), json!(temperature));
+        }
+        Ok(())
+    }
+
+    fn apply(&self, payload: &mut Value) {
+        let Some(payload) = payload.as_object_mut() else {
+            return;
+        };
+        if let Some(temperature) = self.temperature {
+            payload.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(search) = &self.search {
+            let mut tool = json!({"type": "web_search"});
+            if let Some(size) = &search.context_size {
+                tool["search_context_size"] = json!(size);
+            }
+            if !search.allowed_domains.is_empty() {
+                tool["filters"] = json!({"allowed_domains": search.allowed_domains});
+            }
+            match payload.get_mut("tools").and_then(Value::as_array_mut) {
+                Some(tools) => tools.push(tool),
+                None => {
+                    payload.insert("tools".to_string(), json!([tool]));
+                }
+            }
+        }
+    }
+}
+
+/// Splits the passthrough fields into the ones forwarded as is and the parsed `options` block.
+pub(crate) fn take_yandex_options(
+    extra: Option<&Map<String, Value>>,
+) -> Result<(Map<String, Value>, Option<YandexOptions>), CoreError> {
+    let mut extra = extra.cloned().unwrap_or_default();
+    let options =
+        extra.remove(OPTIONS_FIELD).map(|options| parse_yandex_options(&options)).transpose()?;
+    Ok((extra, options))
+}
+
+fn invalid_option(param: &str, message: &str) -> CoreError {
+    CoreError::InvalidParam {
+        param: format!("{OPTIONS_FIELD}.{param}"),
+        message: message.to_string(),
+    }
+}
+
+pub(crate) fn parse_yandex_options(value: &Value) -> Result<YandexOptions, CoreError> {
+    let fields = value.as_object().ok_or_else(|| CoreError::InvalidParam {
+        param: OPTIONS_FIELD.to_string(),
+        message: "must be an object".to_string(),
+    })?;
+    let mut options = YandexOptions::default();
+    for (name, value) in fields {
+        match name.as_str() {
+            "temperature" => {
+                let temperature = value
+                    .as_f64()
+                    .filter(|temperature| (0.0..=1.0).contains(temperature))
+                    .ok_or_else(|| invalid_option(name, "must be a number from 0 to 1"))?;
+                options.temperature = Some(temperature);
+            }
+            "search" => options.search = parse_search_option(value)?,
+            "async" => {
+                return Err(invalid_option(
+                    name,
+                    "asynchronous completions are not supported; responses are always streamed",
+                ));
+            }
+            _ => return Err(invalid_option(name, "is not a Yandex option")),
+        }
+    }
+    Ok(options)
+}
+
+/// `true` enables search with defaults; an object narrows it to `allowed_domains` and sets
+/// `context_size` (`low`, `medium` or `high`).
+fn parse_search_option(value: &Value) -> Result<Option<YandexSearch>, CoreError> {
+    let fields = match value {
+        Value::Bool(enabled) => return Ok(enabled.then(YandexSearch::default)),
+        Value::Object(fields) => fields,
+        _ => return Err(invalid_option("search", "must be a boolean or an object")),
+    };
+    let mut search = YandexSearch::default();
+    for (name, value) in fields {
+        match name.as_str() {
+            "allowed_domains" => {
+                search.allowed_domains = value
+                    .as_array()
+                    .and_then(|domains| {
+                        domains
+                            .iter()
+                            .map(|domain| {
+                                domain.as_str().map(str::trim).filter(|domain| !domain.is_empty())
+                            })
+                            .map(|domain| domain.map(str::to_string))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .ok_or_else(|| {
+                        invalid_option("search.allowed_domains", "must be an array of domains")
+                    })?;
+            }
+            "context_size" => {
+                let size =
+                    value.as_str().filter(|size| SEARCH_CONTEXT_SIZES.contains(size)).ok_or_else(
+                        || invalid_option("search.context_size", "must be low, medium or high"),
+                    )?;
+                search.context_size = Some(size.to_string());
+            }
+            _ => {
+                return Err(invalid_option(&format!("search.{name}"), "is not a search option"));
+            }
+        }
+    }
+    Ok(Some(search))
+}
+
 pub(crate) fn build_yandex_responses_payload(
     model: &str,
     input: &ResponsesInput,
     tools: Option<&[Value]>,
     tool_choice: Option<&Value>,
+    include: Option<&[String]>,
+    text: Option<&TextControls>,
 ) -> (Value, YandexNormalization) {
     let normalized_tools = normalize_tools_for_responses(tools);
     let normalized_tool_choice =
@@ -167,6 +356,12 @@ pub(crate) fn build_yandex_responses_payload(
         if let Some(choice) = normalized_tool_choice.clone() {
             obj.insert("tool_choice".to_string(), choice);
         }
+        if let Some(include) = include.filter(|include| !include.is_empty()) {
+            obj.insert("include".to_string(), json!(include));
+        }
+        if let Some(text) = text.and_then(|text| serde_json::to_value(text).ok()) {
+            obj.insert("text".to_string(), text);
+        }
     }
 
     (
@@ -187,7 +382,7 @@ pub(crate) fn build_yandex_responses_payload(
     )
 }
 
-fn sanitize_yandex_input(input: &ResponsesInput) -> ResponsesInput {
+pub(crate) fn sanitize_yandex_input(input: &ResponsesInput) -> ResponsesInput {
     let ResponsesInput::Items(items) = input else {
         return input.clone();
     };
@@ -457,31 +652,52 @@ fn tool_choice_debug_label(value: &Value) -> String {
     "other".to_string()
 }
 
-pub(crate) fn map_yandex_responses_stream_text(
-    payload: &str,
-) -> Result<ProviderOutcome, CoreError> {
-    let mut chunks = Vec::<String>::new();
-    let mut all_content = String::new();
-    let mut tool_calls = Vec::<ToolCall>::new();
+#[cfg(test)]
+fn map_yandex_responses_stream_text(payload: &str) -> Result<ProviderOutcome, CoreError> {
+    crate::parser::accumulate_stream_text(Box::<YandexStreamAccumulator>::default(), payload)
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct YandexStreamAccumulator {
+    content: ContentBuffer,
+    tool_calls: Vec<ToolCall>,
+    completed: Option<ProviderOutcome>,
+}
 
-    for event in extract_sse_data_events(payload) {
-        if event == "[DONE]" {
-            continue;
+impl YandexStreamAccumulator {
+    fn complete(&mut self, response: &Value) {
+        let mut mapped = map_yandex_response_object(response);
+        apply_legacy_tool_fallback_from_accumulated_stream(&mut mapped, self.content.as_str());
+        if !self.content.is_empty() && mapped.chunks.is_empty() && mapped.tool_calls.is_none() {
+            mapped.chunks = vec![std::mem::take(&mut self.content).into_text()];
         }
-        let parsed: Value = serde_json::from_str(&event)
+        if mapped.tool_calls.is_none() && !self.tool_calls.is_empty() {
+            mapped.tool_calls = Some(std::mem::take(&mut self.tool_calls));
+        }
+        self.completed = Some(mapped);
+    }
+}
+
+impl StreamAccumulator for YandexStreamAccumulator {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError> {
+        let mut delta = StreamDelta::default();
+        if data == "[DONE]" || self.completed.is_some() {
+            return Ok(delta);
+        }
+        let parsed: Value = serde_json::from_str(data)
             .map_err(|err| CoreError::Provider(format!("provider stream parse failed: {err}")))?;
         let kind = parsed.get("type").and_then(Value::as_str).unwrap_or_default();
 
         if kind == "response.output_text.delta"
-            && let Some(delta) = parsed
+            && let Some(text) = parsed
                 .get("delta")
                 .and_then(Value::as_str)
                 .or_else(|| parsed.get("text").and_then(Value::as_str))
-            && !delta.is_empty()
+            && !text.is_empty()
         {
-            all_content.push_str(delta);
-            chunks.push(delta.to_string());
-            continue;
+            self.content.push(text);
+            delta.content.push(text.to_string());
+            return Ok(delta);
         }
 
         if kind == "response.output_item.added"
@@ -492,7 +708,7 @@ pub(crate) fn map_yandex_responses_stream_text(
             && !call_id.is_empty()
             && !name.is_empty()
         {
-            tool_calls.push(ToolCall {
+            self.tool_calls.push(ToolCall {
                 id: call_id.to_string(),
                 kind: "function".to_string(),
                 function: ToolFunction {
@@ -504,21 +720,14 @@ pub(crate) fn map_yandex_responses_stream_text(
                         .unwrap_or_else(|| "{}".to_string()),
                 },
             });
-            continue;
+            return Ok(delta);
         }
 
-        if kind == "response.completed"
+        if matches!(kind, "response.completed" | "response.incomplete")
             && let Some(response) = parsed.get("response")
         {
-            let mut mapped = map_yandex_response_object(response);
-            apply_legacy_tool_fallback_from_accumulated_stream(&mut mapped, &all_content);
-            if !all_content.is_empty() && mapped.chunks.is_empty() && mapped.tool_calls.is_none() {
-                mapped.chunks = chunks.clone();
-            }
-            if mapped.tool_calls.is_none() && !tool_calls.is_empty() {
-                mapped.tool_calls = Some(tool_calls.clone());
-            }
-            return Ok(mapped);
+            self.complete(response);
+            return Ok(delta);
         }
 
         // Yandex can stream cumulative response snapshots without `type`.
@@ -527,60 +736,71 @@ pub(crate) fn map_yandex_responses_stream_text(
         {
             let snapshot_text = extract_text_from_response_output(response);
             if !snapshot_text.is_empty() {
-                if snapshot_text.starts_with(&all_content) {
-                    let delta = &snapshot_text[all_content.len()..];
-                    if !delta.is_empty() {
-                        all_content.push_str(delta);
-                        chunks.push(delta.to_string());
+                if snapshot_text.starts_with(self.content.as_str()) {
+                    let snapshot_delta = &snapshot_text[self.content.as_str().len()..];
+                    if !snapshot_delta.is_empty() {
+                        self.content.push(snapshot_delta);
+                        delta.content.push(snapshot_delta.to_string());
                     }
-                } else if all_content.is_empty() {
-                    all_content = snapshot_text.clone();
-                    chunks.push(snapshot_text);
                 } else {
-                    all_content = snapshot_text.clone();
-                    chunks = vec![snapshot_text];
+                    warn!(
+                        event = "provider.stream.snapshot_diverged",
+                        provider = "yandex",
+                        previous_chars = self.content.as_str().chars().count(),
+                        snapshot_chars = snapshot_text.chars().count()
+                    );
+                    self.content.replace(snapshot_text);
                 }
             }
 
-            merge_tool_calls(&mut tool_calls, extract_tool_calls_from_response_output(response));
+            merge_tool_calls(
+                &mut self.tool_calls,
+                extract_tool_calls_from_response_output(response),
+            );
 
-            if response.get("status").and_then(Value::as_str) == Some("completed") {
-                let mut mapped = map_yandex_response_object(response);
-                apply_legacy_tool_fallback_from_accumulated_stream(&mut mapped, &all_content);
-                if !all_content.is_empty()
-                    && mapped.chunks.is_empty()
-                    && mapped.tool_calls.is_none()
-                {
-                    mapped.chunks = chunks.clone();
-                }
-                if mapped.tool_calls.is_none() && !tool_calls.is_empty() {
-                    mapped.tool_calls = Some(tool_calls.clone());
-                }
-                return Ok(mapped);
+            if matches!(
+                response.get("status").and_then(Value::as_str),
+                Some("completed" | "incomplete")
+            ) {
+                self.complete(response);
             }
-            continue;
         }
+        Ok(delta)
     }
 
-    let tool_calls = if tool_calls.is_empty() { None } else { Some(tool_calls) };
-    let output_tokens =
-        if all_content.is_empty() { 0 } else { all_content.split_whitespace().count() as u32 };
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError> {
+        let Self { content, tool_calls, completed } = *self;
+        if let Some(completed) = completed {
+            return Ok(completed);
+        }
+        let all_content = content.into_text();
+        let tool_calls = if tool_calls.is_empty() { None } else { Some(tool_calls) };
+        let output_tokens =
+            if all_content.is_empty() { 0 } else { all_content.split_whitespace().count() as u32 };
+
+        if all_content.is_empty() && tool_calls.is_none() {
+            warn!(
+                event = "provider.responses.stream.empty_message_content.tail",
+                "provider returned empty message content in responses stream tail; treating as empty completed response"
+            );
+        }
 
-    if all_content.is_empty() && tool_calls.is_none() {
-        warn!(
-            event = "provider.responses.stream.empty_message_content.tail",
-            "provider returned empty message content in responses stream tail; treating as empty completed response"
-        );
+        Ok(ProviderOutcome {
+            chunks: if all_content.is_empty() { Vec::new() } else { vec![all_content] },
+            output_tokens,
+            reasoning: None,
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls,
+            annotations: None,
+            emitted_live: false,
+            finish_reason: None,
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
+        })
     }
-
-    Ok(ProviderOutcome {
-        chunks: if all_content.is_empty() { Vec::new() } else { chunks },
-        output_tokens,
-        reasoning: None,
-        reasoning_details: None,
-        tool_calls,
-        emitted_live: false,
-    })
 }
 
 fn apply_legacy_tool_fallback_from_accumulated_stream(
@@ -620,11 +840,81 @@ fn map_yandex_response_object(response: &Value) -> ProviderOutcome {
         output_tokens,
         reasoning: None,
         reasoning_details: None,
+        reasoning_encrypted_content: extract_reasoning_encrypted_content(response),
         tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+        annotations: Some(extract_url_citations(response)).filter(|found| !found.is_empty()),
         emitted_live: false,
+        finish_reason: responses_finish_reason(
+            response.get("status").and_then(Value::as_str),
+            response.pointer("/incomplete_details/reason").and_then(Value::as_str),
+        ),
+        usage: native_usage_from_value(response.get("usage")),
+        upstream_attempts: 1,
+        provider_response_id: response.get("id").and_then(Value::as_str).map(str::to_string),
+        upstream_provider: None,
     }
 }
 
+fn extract_reasoning_encrypted_content(response: &Value) -> Option<String> {
+    response.get("output")?.as_array()?.iter().find_map(|item| {
+        (item.get("type").and_then(Value::as_str) == Some("reasoning"))
+            .then(|| item.get("encrypted_content").and_then(Value::as_str))
+            .flatten()
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    })
+}
+
+/// `url_citation` annotations of the `output_text` parts, with offsets moved from their part to
+/// the joined output text.
+fn extract_url_citations(response: &Value) -> Vec<OutputAnnotation> {
+    let parts = response
+        .get("output")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|item| item.get("type").and_then(Value::as_str) == Some("message"))
+        .filter_map(|item| item.get("content").and_then(Value::as_array))
+        .flatten()
+        .filter(|part| part.get("type").and_then(Value::as_str) == Some("output_text"));
+    let mut annotations = Vec::new();
+    let mut offset = 0;
+    for part in parts {
+        let text = part.get("text").and_then(Value::as_str).unwrap_or_default();
+        let chars = text.chars().collect::<Vec<_>>();
+        let leading = chars.iter().take_while(|c| c.is_whitespace()).count();
+        for annotation in part.get("annotations").and_then(Value::as_array).into_iter().flatten() {
+            let (Some("url_citation"), Some(url)) = (
+                annotation.get("type").and_then(Value::as_str),
+                annotation.get("url").and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            let index = |key: &str| {
+                annotation
+                    .get(key)
+                    .and_then(Value::as_u64)
+                    .map_or(0, |index| (index as usize).min(chars.len()))
+            };
+            let (start, end) = (index("start_index"), index("end_index"));
+            let (start, end) = (start.min(end), end);
+            annotations.push(OutputAnnotation {
+                kind: "url_citation".to_string(),
+                start_index: (offset + start.saturating_sub(leading)) as u32,
+                end_index: (offset + end.saturating_sub(leading)) as u32,
+                text: chars[start..end].iter().collect(),
+                sources: vec![CitationSource {
+                    kind: "url".to_string(),
+                    id: Some(url.to_string()),
+                    data: annotation.get("title").map(|title| json!({"url": url, "title": title})),
+                }],
+            });
+        }
+        offset += text.trim().chars().count();
+    }
+    annotations
+}
+
 fn extract_text_from_response_output(response: &Value) -> String {
     let mut parts = Vec::new();
     if let Some(output) = response.get("output") {
@@ -869,31 +1159,19 @@ fn merge_tool_calls(into: &mut Vec<ToolCall>, incoming: Vec<ToolCall>) {
     }
 }
 
-fn extract_sse_data_events(payload: &str) -> Vec<String> {
-    payload
-        .replace('\r', "")
-        .split("\n\n")
-        .filter_map(|frame| {
-            let data_lines = frame
-                .lines()
-                .filter_map(|line| line.strip_prefix("data:").map(str::trim_start))
-                .collect::<Vec<_>>();
-            if data_lines.is_empty() { None } else { Some(data_lines.join("\n")) }
-        })
-        .collect()
-}
-
 #[cfg(test)]
 mod tests {
     use super::{
-        build_yandex_responses_payload, build_yandex_upstream_model,
+        YandexStreamAccumulator, build_yandex_responses_payload, build_yandex_upstream_model,
         map_yandex_responses_stream_text, normalize_tool_choice_for_responses,
-        sanitize_yandex_input,
+        parse_yandex_options, request_payload, sanitize_yandex_input,
     };
+    use crate::parser::StreamAccumulator;
     use serde_json::json;
     use xrouter_contracts::{
         ResponseInputContent, ResponseInputItem, ResponseToolOutput, ResponsesInput,
     };
+    use xrouter_core::{CoreError, ExecutionControls, ProviderGenerateRequest};
 
     #[test]
     fn includes_normalized_tools_in_responses_payload() {
@@ -914,6 +1192,8 @@ mod tests {
             &input,
             Some(&tools),
             Some(&json!("auto")),
+            None,
+            None,
         );
 
         assert_eq!(normalization.tools_in, 2);
@@ -952,6 +1232,24 @@ mod tests {
         assert_eq!(outcome.chunks.join(""), "hello");
     }
 
+    #[test]
+    fn yandex_snapshot_growth_is_emitted_as_live_deltas() {
+        let snapshot = |text: &str, status: &str| {
+            json!({"response":{"output":[{"type":"message","content":[{"type":"output_text","text":text}]}],"status":status}})
+                .to_string()
+        };
+        let mut accumulator = Box::<YandexStreamAccumulator>::default();
+        let deltas = [snapshot("hel", "in_progress"), snapshot("hello", "in_progress")]
+            .iter()
+            .map(|event| accumulator.push_event(event).expect("snapshot must parse").content)
+            .collect::<Vec<_>>();
+        assert_eq!(deltas, vec![vec!["hel".to_string()], vec!["lo".to_string()]]);
+
+        accumulator.push_event(&snapshot("hello", "completed")).expect("snapshot must parse");
+        let outcome = accumulator.finish().expect("outcome must build");
+        assert_eq!(outcome.chunks.join(""), "hello");
+    }
+
     #[test]
     fn yandex_extracts_text_when_output_uses_value_field() {
         let sse = "data: {\"response\":{\"id\":\"resp_1\",\"output\":[{\"type\":\"message\",\"content\":[{\"type\":\"output_text\",\"value\":\"ok\"}]}],\"status\":\"completed\"}}\n\n";
@@ -1102,7 +1400,127 @@ mod tests {
     #[test]
     fn responses_payload_forces_stream_true() {
         let input = ResponsesInput::Text("hello".to_string());
-        let (payload, _) = build_yandex_responses_payload("gpt://p/m", &input, None, None);
+        let (payload, _) =
+            build_yandex_responses_payload("gpt://p/m", &input, None, None, None, None);
         assert_eq!(payload["stream"], json!(true));
+        assert!(payload.get("include").is_none());
+        assert!(payload.get("text").is_none());
+    }
+
+    #[test]
+    fn responses_payload_passes_text_format_through() {
+        let input = ResponsesInput::Text("hello".to_string());
+        let text = serde_json::from_value(json!({"format": {"type": "json_object"}}))
+            .expect("text controls must deserialize");
+        let (payload, _) =
+            build_yandex_responses_payload("gpt://p/m", &input, None, None, None, Some(&text));
+        assert_eq!(payload["text"], json!({"format": {"type": "json_object"}}));
+    }
+
+    #[test]
+    fn encrypted_reasoning_round_trips_through_responses_payload_and_stream() {
+        let input: ResponsesInput = serde_json::from_value(json!([
+            {"type": "reasoning", "summary": [], "encrypted_content": "gAAA-previous"},
+            {"type": "message", "role": "user", "content": "continue"}
+        ]))
+        .expect("input must deserialize");
+        let include = vec!["reasoning.encrypted_content".to_string()];
+        let (payload, _) =
+            build_yandex_responses_payload("gpt://p/m", &input, None, None, Some(&include), None);
+        assert_eq!(payload["include"], json!(["reasoning.encrypted_content"]));
+        assert_eq!(payload["input"][0]["encrypted_content"], "gAAA-previous");
+
+        let sse = "data: {\"type\":\"response.completed\",\"response\":{\"status\":\"completed\",\"output\":[{\"type\":\"reasoning\",\"encrypted_content\":\"gAAA-next\"},{\"type\":\"message\",\"content\":[{\"type\":\"output_text\",\"text\":\"ok\"}]}]}}\n\n";
+        let outcome = map_yandex_responses_stream_text(sse).expect("stream must parse");
+        assert_eq!(outcome.reasoning_encrypted_content.as_deref(), Some("gAAA-next"));
+    }
+
+    #[test]
+    fn options_set_temperature_and_search_tool_instead_of_passing_through() {
+        let input = ResponsesInput::Text("Weather in Moscow?".to_string());
+        let extra = json!({
+            "options": {
+                "temperature": 0.3,
+                "search": {"allowed_domains": ["yandex.ru"], "context_size": "low"}
+            },
+            "max_output_tokens": 200
+        });
+        let request = ProviderGenerateRequest {
+            model: "yandexgpt/rc",
+            instructions: None,
+            input: &input,
+            reasoning: None,
+            tools: None,
+            tool_choice: None,
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra: extra.as_object(),
+            controls: ExecutionControls::default(),
+        };
+
+        let (payload, _) = request_payload(&request, Some("folder")).expect("payload must build");
+        assert_eq!(payload["temperature"], json!(0.3));
+        assert_eq!(
+            payload["tools"],
+            json!([{
+                "type": "web_search",
+                "search_context_size": "low",
+                "filters": {"allowed_domains": ["yandex.ru"]}
+            }])
+        );
+        assert_eq!(payload["max_output_tokens"], 200);
+        assert!(payload.get("options").is_none());
+    }
+
+    #[test]
+    fn invalid_options_are_rejected_with_the_offending_param() {
+        let param = |options: serde_json::Value| match parse_yandex_options(&options) {
+            Err(CoreError::InvalidParam { param, .. }) => param,
+            other => panic!("expected InvalidParam, got {other:?}"),
+        };
+        assert_eq!(param(json!({"temperature": 1.5})), "options.temperature");
+        assert_eq!(
+            param(json!({"search": {"context_size": "huge"}})),
+            "options.search.context_size"
+        );
+        assert_eq!(param(json!({"async": true})), "options.async");
+        assert_eq!(param(json!({"top_k": 5})), "options.top_k");
+        assert_eq!(param(json!("fast")), "options");
+        assert_eq!(
+            parse_yandex_options(&json!({"search": false})).expect("options must parse"),
+            Default::default()
+        );
+    }
+
+    #[test]
+    fn search_citations_in_completed_response_become_annotations() {
+        let response = json!({
+            "status": "completed",
+            "output": [
+                {"type": "web_search_call", "status": "completed"},
+                {"type": "message", "content": [{
+                    "type": "output_text",
+                    "text": "It is sunny in Moscow.",
+                    "annotations": [{
+                        "type": "url_citation",
+                        "start_index": 15,
+                        "end_index": 21,
+                        "url": "https://yandex.ru/pogoda",
+                        "title": "Погода"
+                    }]
+                }]}
+            ]
+        });
+        let sse =
+            format!("data: {}\n\n", json!({"type": "response.completed", "response": response}));
+        let outcome = map_yandex_responses_stream_text(&sse).expect("stream must parse");
+        assert_eq!(outcome.chunks, ["It is sunny in Moscow."]);
+        let annotations = outcome.annotations.expect("citations must be annotated");
+        assert_eq!((annotations[0].start_index, annotations[0].end_index), (15, 21));
+        assert_eq!(annotations[0].text, "Moscow");
+        assert_eq!(annotations[0].sources[0].id.as_deref(), Some("https://yandex.ru/pogoda"));
     }
 }
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{Map, Value};
+use xrouter_contracts::ResponsesRequest;
+use xrouter_core::{
+    CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
+    ProviderOutcome,
+};
+
+use super::yandex::{build_yandex_upstream_model, sanitize_yandex_input, take_yandex_options};
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    compact_payload,
+};
+use crate::runtime::SharedProviderRuntime;
+use crate::transcript::TranscriptStore;
+use crate::transport::HttpRuntime;
+
+pub(crate) const CHAT_ENDPOINT: &str = "chat/completions";
+
+/// Yandex through its OpenAI-compatible chat completions endpoint, an alternative to
+/// [`super::yandex::YandexResponsesClient`] selected by `YANDEX_CHAT_COMPLETIONS`. Streams are
+/// read by the generic chat mapper.
+pub struct YandexChatClient {
+    runtime: SharedProviderRuntime,
+    project: Option<String>,
+}
+
+impl YandexChatClient {
+    pub fn new(
+        base_url: Option<String>,
+        api_key: Option<String>,
+        project: Option<String>,
+        http_client: Option<Client>,
+        max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
+    ) -> Self {
+        Self::with_runtime(
+            Arc::new(HttpRuntime::new(
+                "yandex".to_string(),
+                base_url,
+                api_key,
+                http_client,
+                max_inflight,
+                transcripts,
+            )),
+            project,
+        )
+    }
+
+    pub fn with_runtime(runtime: SharedProviderRuntime, project: Option<String>) -> Self {
+        Self { runtime, project }
+    }
+
+    fn project_headers(&self) -> Vec<(String, String)> {
+        self.project
+            .as_deref()
+            .filter(|value| !value.trim().is_empty())
+            .map(|project| ("OpenAI-Project".to_string(), project.to_string()))
+            .into_iter()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ProviderClient for YandexChatClient {
+    async fn generate(
+        &self,
+        request: ProviderGenerateRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.runtime.build_url(CHAT_ENDPOINT)?;
+        let payload = request_payload(&request, self.project.as_deref())?;
+        self.runtime
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &self.project_headers(),
+                request.controls.upstream,
+                None,
+            )
+            .await
+    }
+
+    async fn generate_stream(
+        &self,
+        request: ProviderGenerateStreamRequest<'_>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let url = self.runtime.build_url(CHAT_ENDPOINT)?;
+        let payload = request_payload(&request.request, self.project.as_deref())?;
+        self.runtime
+            .post_chat_completions_stream(
+                request.request_id,
+                &url,
+                &payload,
+                request.request.auth_bearer,
+                &self.project_headers(),
+                request.request.controls.upstream,
+                request.sender,
+            )
+            .await
+    }
+}
+
+/// Chat completions body for the same `gpt://<folder>/<model>` URI and sanitized input the
+/// Responses path sends.
+pub(crate) fn request_payload(
+    request: &ProviderGenerateRequest<'_>,
+    project: Option<&str>,
+) -> Result<Value, CoreError> {
+    let (extra, options) = take_yandex_options(request.extra)?;
+    let payload = base_chat_payload(
+        &ResponsesRequest {
+            model: build_yandex_upstream_model(request.model, project)?,
+            instructions: request.instructions.map(str::to_string),
+            previous_response_id: None,
+            input: sanitize_yandex_input(request.input),
+            parallel_tool_calls: None,
+            stream: true,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: Map::new(),
+        },
+        request.tools,
+        request.tool_choice,
+    );
+    let mut payload = Value::Object(payload);
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::Full,
+    );
+    if let Some(options) = &options {
+        options.apply_chat(&mut payload)?;
+    }
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, Some(&extra));
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use xrouter_contracts::ResponsesInput;
+    use xrouter_core::{CoreError, ExecutionControls, ProviderGenerateRequest};
+
+    use super::request_payload;
+
+    fn request<'a>(
+        input: &'a ResponsesInput,
+        extra: Option<&'a serde_json::Map<String, serde_json::Value>>,
+    ) -> ProviderGenerateRequest<'a> {
+        ProviderGenerateRequest {
+            model: "yandexgpt/rc",
+            instructions: Some("Answer in Russian."),
+            input,
+            reasoning: None,
+            tools: None,
+            tool_choice: None,
+            include: None,
+            text: None,
+            modalities: None,
+            auth_bearer: None,
+            forward_headers: &[],
+            extra,
+            controls: ExecutionControls::default(),
+        }
+    }
+
+    #[test]
+    fn payloads_use_the_folder_model_uri_and_chat_messages() {
+        let input = ResponsesInput::Text("Привет".to_string());
+        let extra = json!({"options": {"temperature": 0.2}, "max_tokens": 100});
+
+        let payload = request_payload(&request(&input, extra.as_object()), Some("folder"))
+            .expect("payload must build");
+        assert_eq!(payload["model"], "gpt://folder/yandexgpt/rc");
+        assert_eq!(
+            payload["messages"],
+            json!([
+                {"role": "system", "content": "Answer in Russian."},
+                {"role": "user", "content": "Привет"}
+            ])
+        );
+        assert_eq!(payload["temperature"], json!(0.2));
+        assert_eq!(payload["max_tokens"], 100);
+        assert!(payload.get("options").is_none());
+    }
+
+    #[test]
+    fn search_option_is_refused_on_chat_completions() {
+        let input = ResponsesInput::Text("Погода?".to_string());
+        let extra = json!({"options": {"search": true}});
+
+        let error = request_payload(&request(&input, extra.as_object()), Some("folder"))
+            .expect_err("search must be refused");
+        assert!(
+            matches!(error, CoreError::InvalidParam { ref param, .. } if param == "options.search")
+        );
+    }
+}
@@ -4,18 +4,27 @@ use reqwest::Client;
 use serde_json::Map;
 use serde_json::Value;
 use serde_json::json;
+use std::collections::HashMap;
 #[cfg(not(target_arch = "wasm32"))]
 use std::sync::Arc;
 use tracing::{debug, info};
-use xrouter_contracts::{ReasoningConfig, ResponsesInput, ResponsesRequest};
+use uuid::Uuid;
+use xrouter_contracts::{ReasoningConfig, ResponsesInput, ResponsesRequest, ToolCall};
 use xrouter_core::{
     CoreError, ProviderClient, ProviderGenerateRequest, ProviderGenerateStreamRequest,
     ProviderOutcome,
 };
 
-use crate::protocol::base_chat_payload;
+use crate::parser::{ChatStreamAccumulator, StreamAccumulator, StreamDelta};
+
+use crate::protocol::{
+    ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields, base_chat_payload,
+    compact_payload,
+};
 use crate::runtime::SharedProviderRuntime;
 #[cfg(not(target_arch = "wasm32"))]
+use crate::transcript::TranscriptStore;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::transport::HttpRuntime;
 
 pub struct ZaiClient {
@@ -29,14 +38,19 @@ impl ZaiClient {
         api_key: Option<String>,
         http_client: Option<Client>,
         max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
     ) -> Self {
-        Self::with_runtime(Arc::new(HttpRuntime::new(
-            "zai".to_string(),
-            base_url,
-            api_key,
-            http_client,
-            max_inflight,
-        )))
+        Self::with_runtime(Arc::new(
+            HttpRuntime::new(
+                "zai".to_string(),
+                base_url,
+                api_key,
+                http_client,
+                max_inflight,
+                transcripts,
+            )
+            .with_stream_accumulator(|| Box::<ZaiStreamAccumulator>::default()),
+        ))
     }
 
     pub fn with_runtime(runtime: SharedProviderRuntime) -> Self {
@@ -52,14 +66,7 @@ impl ProviderClient for ZaiClient {
         request: ProviderGenerateRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_zai_payload(
-            request.model,
-            request.instructions,
-            request.input,
-            request.reasoning,
-            request.tools,
-            request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request);
         info!(
             event = "provider.request.payload.normalized",
             provider = "zai",
@@ -79,7 +86,15 @@ impl ProviderClient for ZaiClient {
             );
         }
         self.runtime
-            .post_chat_completions_stream("request", &url, &payload, request.auth_bearer, &[], None)
+            .post_chat_completions_stream(
+                "request",
+                &url,
+                &payload,
+                request.auth_bearer,
+                &[],
+                request.controls.upstream,
+                None,
+            )
             .await
     }
 
@@ -88,14 +103,7 @@ impl ProviderClient for ZaiClient {
         request: ProviderGenerateStreamRequest<'_>,
     ) -> Result<ProviderOutcome, CoreError> {
         let url = self.runtime.build_url("chat/completions")?;
-        let (payload, normalization) = build_zai_payload(
-            request.request.model,
-            request.request.instructions,
-            request.request.input,
-            request.request.reasoning,
-            request.request.tools,
-            request.request.tool_choice,
-        );
+        let (payload, normalization) = request_payload(&request.request);
         info!(
             event = "provider.request.payload.normalized",
             provider = "zai",
@@ -121,10 +129,37 @@ impl ProviderClient for ZaiClient {
                 &payload,
                 request.request.auth_bearer,
                 &[],
+                request.request.controls.upstream,
                 request.sender,
             )
             .await
     }
+
+    // ZAI chat/completions only accepts `tool_choice: "auto"`.
+    fn supports_required_tool_choice(&self) -> bool {
+        false
+    }
+}
+
+/// Upstream body for `request`, shared by both generate paths and payload previews.
+pub(crate) fn request_payload(request: &ProviderGenerateRequest<'_>) -> (Value, ZaiNormalization) {
+    let (mut payload, normalization) = build_zai_payload(
+        request.model,
+        request.instructions,
+        request.input,
+        request.reasoning,
+        request.tools,
+        request.tool_choice,
+    );
+    apply_chat_output_controls(
+        &mut payload,
+        request.text,
+        request.modalities,
+        ChatOutputSupport::JsonObjectOnly,
+    );
+    compact_payload(&mut payload);
+    apply_passthrough_fields(&mut payload, request.extra);
+    (payload, normalization)
 }
 
 pub(crate) fn build_zai_payload(
@@ -152,8 +187,13 @@ pub(crate) fn build_zai_payload(
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         },
         Some(&normalized_tools.tools),
         normalized_tool_choice.as_ref(),
@@ -163,9 +203,7 @@ pub(crate) fn build_zai_payload(
         payload.insert("tool_stream".to_string(), Value::Bool(true));
     }
 
-    if let Some(effort) = reasoning.and_then(|cfg| cfg.effort.as_deref()).map(str::trim)
-        && !effort.is_empty()
-    {
+    if let Some(effort) = reasoning.and_then(ReasoningConfig::effective_effort) {
         let thinking_type =
             if effort.eq_ignore_ascii_case("none") { "disabled" } else { "enabled" };
         payload.insert("thinking".to_string(), json!({ "type": thinking_type }));
@@ -298,9 +336,160 @@ fn normalize_function_tool(tool: &Value) -> Option<Value> {
     }))
 }
 
+/// Chat chunks from Z.AI, whose tool-call deltas may lose their `index`, reuse an index for a
+/// new call, resend a finished call, or be separated by blank content chunks. Deltas are
+/// renumbered per call id before the generic accumulator merges them, and calls that still
+/// share an id at the end are deduplicated.
+#[derive(Debug, Default)]
+pub(crate) struct ZaiStreamAccumulator {
+    chat: ChatStreamAccumulator,
+    calls: Vec<ZaiStreamToolCall>,
+    upstream_indexes: HashMap<u64, usize>,
+    current: Option<usize>,
+    repairs: usize,
+}
+
+#[derive(Debug, Default)]
+struct ZaiStreamToolCall {
+    id: Option<String>,
+    arguments: String,
+}
+
+impl ZaiStreamAccumulator {
+    /// Index of the call `delta` continues, allocating one for a new call.
+    fn call_index(&mut self, delta: &Value) -> usize {
+        let id = delta.get("id").and_then(Value::as_str).filter(|id| !id.trim().is_empty());
+        let upstream = delta.get("index").and_then(Value::as_u64);
+        let by_upstream = upstream.and_then(|index| self.upstream_indexes.get(&index).copied());
+        let index = match (id, by_upstream) {
+            (Some(id), _) if let Some(known) = self.position_of(id) => known,
+            (Some(id), Some(known)) if self.calls[known].id.is_none() => {
+                self.calls[known].id = Some(id.to_string());
+                known
+            }
+            (Some(id), _) => self.push_call(Some(id)),
+            (None, Some(known)) => known,
+            (None, None) if upstream.is_none() && self.current.is_some() => {
+                self.current.unwrap_or_default()
+            }
+            (None, None) => self.push_call(None),
+        };
+        if upstream != Some(index as u64) {
+            self.repairs += 1;
+        }
+        if let Some(upstream) = upstream {
+            self.upstream_indexes.insert(upstream, index);
+        }
+        self.current = Some(index);
+        index
+    }
+
+    fn position_of(&self, id: &str) -> Option<usize> {
+        self.calls.iter().position(|call| call.id.as_deref() == Some(id))
+    }
+
+    fn push_call(&mut self, id: Option<&str>) -> usize {
+        self.calls.push(ZaiStreamToolCall { id: id.map(str::to_string), arguments: String::new() });
+        self.calls.len() - 1
+    }
+
+    /// Renumbers the tool-call deltas of `chunk` and drops resent arguments and blank content
+    /// between tool-call deltas; returns whether anything changed.
+    fn stabilize(&mut self, chunk: &mut Value) -> bool {
+        let mut changed = false;
+        let Some(choices) = chunk.get_mut("choices").and_then(Value::as_array_mut) else {
+            return false;
+        };
+        for choice in choices {
+            let blank_content = choice
+                .pointer("/delta/content")
+                .and_then(Value::as_str)
+                .is_some_and(|content| !content.is_empty() && content.trim().is_empty());
+            if blank_content && self.current.is_some() {
+                choice["delta"]["content"] = Value::Null;
+                changed = true;
+            }
+            for path in ["/delta/tool_calls", "/tool_calls"] {
+                let Some(deltas) = choice.pointer_mut(path).and_then(Value::as_array_mut) else {
+                    continue;
+                };
+                for delta in deltas {
+                    let index = self.call_index(delta);
+                    delta["index"] = json!(index);
+                    changed = true;
+                    let Some(arguments) =
+                        delta.pointer("/function/arguments").and_then(Value::as_str)
+                    else {
+                        continue;
+                    };
+                    let call = &mut self.calls[index];
+                    if is_resent_arguments(&call.arguments, arguments) {
+                        delta["function"]["arguments"] = Value::Null;
+                        self.repairs += 1;
+                    } else {
+                        call.arguments.push_str(arguments);
+                    }
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// A complete JSON object sent for a call whose arguments already form one.
+fn is_resent_arguments(accumulated: &str, incoming: &str) -> bool {
+    let is_object =
+        |raw: &str| raw.trim_start().starts_with('{') && serde_json::from_str::<Value>(raw).is_ok();
+    is_object(accumulated) && is_object(incoming)
+}
+
+impl StreamAccumulator for ZaiStreamAccumulator {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError> {
+        let Ok(mut chunk) = serde_json::from_str::<Value>(data) else {
+            return self.chat.push_event(data);
+        };
+        if self.stabilize(&mut chunk) {
+            return self.chat.push_event(&chunk.to_string());
+        }
+        self.chat.push_event(data)
+    }
+
+    fn limit_retained_content(&mut self, max_bytes: usize) {
+        self.chat.limit_retained_content(max_bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError> {
+        let Self { chat, mut repairs, .. } = *self;
+        let mut outcome = Box::new(chat).finish()?;
+        if let Some(calls) = outcome.tool_calls.take() {
+            let mut unique = Vec::<ToolCall>::with_capacity(calls.len());
+            for mut call in calls {
+                if unique.iter().any(|known| known.id == call.id && known.function == call.function)
+                {
+                    repairs += 1;
+                    continue;
+                }
+                if unique.iter().any(|known| known.id == call.id) {
+                    call.id = format!("call_{}", Uuid::new_v4().simple());
+                    repairs += 1;
+                }
+                unique.push(call);
+            }
+            outcome.tool_calls = Some(unique);
+        }
+        if repairs > 0 {
+            debug!(event = "provider.stream.tool_calls_repaired", provider = "zai", repairs);
+        }
+        Ok(outcome)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{build_zai_payload, normalize_tool_choice_for_chat_completions};
+    use super::{
+        ZaiStreamAccumulator, build_zai_payload, normalize_tool_choice_for_chat_completions,
+    };
+    use crate::parser::StreamAccumulator;
     use serde_json::{Value, json};
     use xrouter_contracts::{ReasoningConfig, ResponsesInput};
 
@@ -383,7 +572,7 @@ mod tests {
     #[test]
     fn enables_thinking_when_effort_present() {
         let input = ResponsesInput::Text("Reply with ok".to_string());
-        let reasoning = ReasoningConfig { effort: Some("high".to_string()), summary: None };
+        let reasoning = ReasoningConfig { effort: Some("high".to_string()), ..Default::default() };
         let (payload, _) = build_zai_payload("glm-5", None, &input, Some(&reasoning), None, None);
         assert_eq!(payload["thinking"]["type"], "enabled");
         assert!(payload.get("reasoning").is_none());
@@ -392,16 +581,90 @@ mod tests {
     #[test]
     fn disables_thinking_when_effort_none() {
         let input = ResponsesInput::Text("Reply with ok".to_string());
-        let reasoning = ReasoningConfig { effort: Some("none".to_string()), summary: None };
+        let reasoning = ReasoningConfig { effort: Some("none".to_string()), ..Default::default() };
         let (payload, _) = build_zai_payload("glm-5", None, &input, Some(&reasoning), None, None);
         assert_eq!(payload["thinking"]["type"], "disabled");
         assert!(payload.get("reasoning").is_none());
     }
 
+    #[test]
+    fn reasoning_budget_toggles_thinking() {
+        let input = ResponsesInput::Text("Reply with ok".to_string());
+        for (max_tokens, thinking_type) in [(0, "disabled"), (8000, "enabled")] {
+            let reasoning = ReasoningConfig { max_tokens: Some(max_tokens), ..Default::default() };
+            let (payload, _) =
+                build_zai_payload("glm-5", None, &input, Some(&reasoning), None, None);
+            assert_eq!(payload["thinking"]["type"], thinking_type);
+        }
+    }
+
     #[test]
     fn forces_stream_true() {
         let input = ResponsesInput::Text("hello".to_string());
         let (payload, _) = build_zai_payload("glm-5", None, &input, None, None, None);
         assert_eq!(payload["stream"], json!(true));
     }
+
+    fn accumulate(events: &[Value]) -> xrouter_core::ProviderOutcome {
+        let mut accumulator = Box::<ZaiStreamAccumulator>::default();
+        for event in events {
+            accumulator.push_event(&event.to_string()).expect("event must parse");
+        }
+        accumulator.finish().expect("stream must finish")
+    }
+
+    fn tool_delta(call: Value) -> Value {
+        json!({"id": "chatcmpl-1", "choices": [{"index": 0, "delta": {"tool_calls": [call]}}]})
+    }
+
+    #[test]
+    fn stream_merges_unindexed_argument_fragments_across_blank_content() {
+        let outcome = accumulate(&[
+            tool_delta(json!({
+                "index": 0, "id": "call_a", "type": "function",
+                "function": {"name": "read_file", "arguments": "{\"path\":"}
+            })),
+            json!({"choices": [{"index": 0, "delta": {"content": "\n"}}]}),
+            tool_delta(json!({"function": {"arguments": "\"a.txt\"}"}})),
+            tool_delta(json!({
+                "index": 0, "id": "call_b", "type": "function",
+                "function": {"name": "list_dir", "arguments": "{}"}
+            })),
+            json!({"choices": [{"index": 0, "delta": {}, "finish_reason": "tool_calls"}]}),
+        ]);
+
+        assert_eq!(outcome.chunks.concat(), "");
+        let calls = outcome.tool_calls.expect("tool calls must be kept");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(
+            (calls[0].id.as_str(), calls[0].function.name.as_str()),
+            ("call_a", "read_file")
+        );
+        assert_eq!(calls[0].function.arguments, r#"{"path":"a.txt"}"#);
+        assert_eq!((calls[1].id.as_str(), calls[1].function.name.as_str()), ("call_b", "list_dir"));
+        assert_eq!(outcome.finish_reason.as_deref(), Some("tool_calls"));
+    }
+
+    #[test]
+    fn stream_drops_resent_calls_and_keeps_call_ids_unique() {
+        let call = json!({
+            "index": 0, "id": "call_a", "type": "function",
+            "function": {"name": "ping", "arguments": "{\"host\":\"a\"}"}
+        });
+        let outcome = accumulate(&[
+            tool_delta(call.clone()),
+            tool_delta(call),
+            json!({"choices": [{"index": 0, "delta": {}, "message": {"role": "assistant", "content": null, "tool_calls": [
+                {"id": "call_a", "type": "function", "function": {"name": "ping", "arguments": "{\"host\":\"a\"}"}},
+                {"id": "call_a", "type": "function", "function": {"name": "ping", "arguments": "{\"host\":\"b\"}"}}
+            ]}}]}),
+        ]);
+
+        let calls = outcome.tool_calls.expect("tool calls must be kept");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_a");
+        assert_eq!(calls[0].function.arguments, r#"{"host":"a"}"#);
+        assert_ne!(calls[1].id, "call_a");
+        assert_eq!(calls[1].function.arguments, r#"{"host":"b"}"#);
+    }
 }
@@ -0,0 +1,203 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use reqwest::{Certificate, Client, Identity, Proxy, tls};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinTlsVersion {
+    Tls12,
+    Tls13,
+}
+
+impl MinTlsVersion {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().trim_start_matches("tls").trim_start_matches('v') {
+            "1.2" | "12" => Some(Self::Tls12),
+            "1.3" | "13" => Some(Self::Tls13),
+            _ => None,
+        }
+    }
+
+    fn as_reqwest(self) -> tls::Version {
+        match self {
+            Self::Tls12 => tls::Version::TLS_1_2,
+            Self::Tls13 => tls::Version::TLS_1_3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderTlsConfig {
+    pub ca_bundle_path: Option<PathBuf>,
+    pub client_cert_path: Option<PathBuf>,
+    pub client_key_path: Option<PathBuf>,
+    pub min_tls_version: Option<MinTlsVersion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderProxy {
+    Direct,
+    Url(String),
+}
+
+impl ProviderProxy {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("direct") || value.eq_ignore_ascii_case("none") {
+            return Some(Self::Direct);
+        }
+        let scheme = value.split_once("://")?.0.to_ascii_lowercase();
+        matches!(scheme.as_str(), "http" | "https" | "socks5" | "socks5h")
+            .then(|| Self::Url(value.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderPoolConfig {
+    pub max_idle_per_host: Option<usize>,
+    pub idle_timeout_seconds: Option<u64>,
+    pub http2_keep_alive_interval_seconds: Option<u64>,
+    pub tcp_nodelay: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderHttpConfig {
+    pub proxy: Option<ProviderProxy>,
+    pub tls: ProviderTlsConfig,
+    pub pool: ProviderPoolConfig,
+}
+
+pub fn build_provider_http_client(
+    timeout_seconds: u64,
+    http_config: &ProviderHttpConfig,
+) -> Result<Client, String> {
+    let tls_config = &http_config.tls;
+    let pool = &http_config.pool;
+    let mut builder = Client::builder().connect_timeout(Duration::from_secs(timeout_seconds));
+    if let Some(max_idle) = pool.max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if let Some(seconds) = pool.idle_timeout_seconds {
+        builder = builder.pool_idle_timeout(Duration::from_secs(seconds));
+    }
+    if let Some(seconds) = pool.http2_keep_alive_interval_seconds {
+        builder = builder
+            .http2_keep_alive_interval(Duration::from_secs(seconds))
+            .http2_keep_alive_while_idle(true);
+    }
+    if let Some(nodelay) = pool.tcp_nodelay {
+        builder = builder.tcp_nodelay(nodelay);
+    }
+    match http_config.proxy.as_ref() {
+        Some(ProviderProxy::Direct) => builder = builder.no_proxy(),
+        Some(ProviderProxy::Url(url)) => {
+            let proxy = Proxy::all(url).map_err(|_| "invalid proxy url".to_string())?;
+            builder = builder.proxy(proxy);
+        }
+        None => {}
+    }
+    if let Some(path) = tls_config.ca_bundle_path.as_ref() {
+        let pem = fs::read(path)
+            .map_err(|error| format!("failed to read CA bundle {}: {error}", path.display()))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|error| format!("invalid CA bundle {}: {error}", path.display()))?;
+        if certificates.is_empty() {
+            return Err(format!("CA bundle {} contains no certificates", path.display()));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    match (tls_config.client_cert_path.as_ref(), tls_config.client_key_path.as_ref()) {
+        (Some(cert_path), Some(key_path)) => {
+            let mut pem = fs::read(cert_path).map_err(|error| {
+                format!("failed to read client certificate {}: {error}", cert_path.display())
+            })?;
+            pem.push(b'\n');
+            pem.extend(fs::read(key_path).map_err(|error| {
+                format!("failed to read client key {}: {error}", key_path.display())
+            })?);
+            let identity = Identity::from_pem(&pem)
+                .map_err(|error| format!("invalid client certificate or key: {error}"))?;
+            builder = builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => return Err("client certificate and key must be configured together".to_string()),
+    }
+    if let Some(version) = tls_config.min_tls_version {
+        builder = builder.min_tls_version(version.as_reqwest());
+    }
+    builder.build().map_err(|error| format!("failed to build http client: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_tls_version_parses_common_spellings() {
+        assert_eq!(MinTlsVersion::parse("1.2"), Some(MinTlsVersion::Tls12));
+        assert_eq!(MinTlsVersion::parse("TLSv1.3"), Some(MinTlsVersion::Tls13));
+        assert_eq!(MinTlsVersion::parse("tls1.2"), Some(MinTlsVersion::Tls12));
+        assert_eq!(MinTlsVersion::parse("1.1"), None);
+    }
+
+    #[test]
+    fn provider_proxy_accepts_http_and_socks_schemes_and_direct() {
+        assert_eq!(ProviderProxy::parse("direct"), Some(ProviderProxy::Direct));
+        assert_eq!(
+            ProviderProxy::parse("socks5h://127.0.0.1:1080"),
+            Some(ProviderProxy::Url("socks5h://127.0.0.1:1080".to_string()))
+        );
+        assert!(ProviderProxy::parse("http://proxy.internal:3128").is_some());
+        assert_eq!(ProviderProxy::parse("ftp://proxy"), None);
+        assert_eq!(ProviderProxy::parse("proxy:3128"), None);
+    }
+
+    #[test]
+    fn build_provider_http_client_applies_proxy_settings() {
+        for proxy in [ProviderProxy::Direct, ProviderProxy::Url("socks5://127.0.0.1:1080".into())] {
+            let http_config = ProviderHttpConfig { proxy: Some(proxy), ..Default::default() };
+            assert!(build_provider_http_client(5, &http_config).is_ok());
+        }
+    }
+
+    #[test]
+    fn build_provider_http_client_applies_pool_tuning() {
+        let http_config = ProviderHttpConfig {
+            pool: ProviderPoolConfig {
+                max_idle_per_host: Some(0),
+                idle_timeout_seconds: Some(30),
+                http2_keep_alive_interval_seconds: Some(15),
+                tcp_nodelay: Some(false),
+            },
+            ..Default::default()
+        };
+        assert!(build_provider_http_client(5, &http_config).is_ok());
+    }
+
+    #[test]
+    fn build_provider_http_client_reports_unreadable_files_and_half_configured_identity() {
+        let missing = ProviderHttpConfig {
+            tls: ProviderTlsConfig {
+                ca_bundle_path: Some(PathBuf::from("/nonexistent/xrouter-ca.pem")),
+                ..ProviderTlsConfig::default()
+            },
+            ..Default::default()
+        };
+        let error = build_provider_http_client(5, &missing).expect_err("missing CA must fail");
+        assert!(error.contains("failed to read CA bundle"), "unexpected error: {error}");
+
+        let half_identity = ProviderHttpConfig {
+            tls: ProviderTlsConfig {
+                client_cert_path: Some(PathBuf::from("/nonexistent/client.pem")),
+                ..ProviderTlsConfig::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            build_provider_http_client(5, &half_identity).expect_err("half identity must fail"),
+            "client certificate and key must be configured together"
+        );
+        assert!(build_provider_http_client(5, &ProviderHttpConfig::default()).is_ok());
+    }
+}
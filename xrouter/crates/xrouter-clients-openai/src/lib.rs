@@ -1,18 +1,40 @@
 mod clients;
+#[cfg(not(target_arch = "wasm32"))]
+mod http_client;
 pub mod model_discovery;
 pub mod models;
 pub mod parser;
 pub mod protocol;
 pub mod runtime;
 #[cfg(not(target_arch = "wasm32"))]
+mod transcript;
+#[cfg(not(target_arch = "wasm32"))]
 mod transport;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use clients::GigachatClient;
 #[cfg(not(target_arch = "wasm32"))]
-pub use clients::YandexResponsesClient;
+pub use clients::{ChaosPolicy, ChaosProviderClient, SharedChaosPolicy};
+pub use clients::{
+    CloudflareClient, CohereClient, DeepSeekClient, MockProviderClient, OpenAiClient,
+    OpenRouterClient, PerplexityClient, SelfHostedClient, SelfHostedQuirks, TgiClient,
+    XrouterClient, ZaiClient,
+};
+#[cfg(not(target_arch = "wasm32"))]
 pub use clients::{
-    DeepSeekClient, MockProviderClient, OpenAiClient, OpenRouterClient, XrouterClient, ZaiClient,
+    FixtureEvent, FixtureOutcome, ProviderFixture, RecordingProviderClient, ReplayProviderClient,
+    fixture_key,
 };
 #[cfg(not(target_arch = "wasm32"))]
+pub use clients::{PayloadPreview, ToolNormalization, preview_payload};
+#[cfg(not(target_arch = "wasm32"))]
+pub use clients::{YandexChatClient, YandexResponsesClient};
+#[cfg(not(target_arch = "wasm32"))]
+pub use http_client::{
+    MinTlsVersion, ProviderHttpConfig, ProviderPoolConfig, ProviderProxy, ProviderTlsConfig,
+    build_provider_http_client,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use transcript::{StreamTranscript, TRANSCRIPT_MAX_BYTES, TranscriptCapture, TranscriptStore};
+#[cfg(not(target_arch = "wasm32"))]
 pub use transport::{build_http_client, build_http_client_insecure_tls};
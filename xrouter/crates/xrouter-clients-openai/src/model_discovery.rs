@@ -78,6 +78,51 @@ pub fn build_xrouter_models_request(
     Some(HttpJsonRequest { url: format!("{base_url}/models"), headers })
 }
 
+/// TGI `/info`, which describes the single model an endpoint serves.
+pub fn build_tgi_info_request(
+    base_url: Option<&str>,
+    api_key: Option<&str>,
+) -> Option<HttpJsonRequest> {
+    let base_url = base_url?.trim();
+    if base_url.is_empty() {
+        return None;
+    }
+    let base_url = base_url.trim_end_matches('/').to_string();
+
+    let mut headers = vec![("Accept".to_string(), "application/json".to_string())];
+    if let Some(api_key) = api_key.filter(|value| !value.trim().is_empty()) {
+        headers.push(("Authorization".to_string(), format!("Bearer {api_key}")));
+    }
+
+    Some(HttpJsonRequest { url: format!("{base_url}/info"), headers })
+}
+
+/// Workers AI text generation models of an account.
+pub fn build_cloudflare_models_request(
+    base_url: Option<&str>,
+    account_id: Option<&str>,
+    api_key: Option<&str>,
+) -> Option<HttpJsonRequest> {
+    let base_url = base_url?.trim();
+    let account_id = account_id?.trim();
+    if base_url.is_empty() || account_id.is_empty() {
+        return None;
+    }
+    let base_url = base_url.trim_end_matches('/').to_string();
+
+    let mut headers = vec![("Accept".to_string(), "application/json".to_string())];
+    if let Some(api_key) = api_key.filter(|value| !value.trim().is_empty()) {
+        headers.push(("Authorization".to_string(), format!("Bearer {api_key}")));
+    }
+
+    Some(HttpJsonRequest {
+        url: format!(
+            "{base_url}/accounts/{account_id}/ai/models/search?task=Text%20Generation&per_page=100"
+        ),
+        headers,
+    })
+}
+
 pub fn build_gigachat_oauth_request(
     api_key: &str,
     request_id: &str,
@@ -117,9 +162,9 @@ pub fn build_gigachat_models_request(
 #[cfg(test)]
 mod tests {
     use super::{
-        build_gigachat_models_request, build_gigachat_oauth_request,
-        build_openrouter_models_request, build_provider_models_request,
-        build_xrouter_models_request,
+        build_cloudflare_models_request, build_gigachat_models_request,
+        build_gigachat_oauth_request, build_openrouter_models_request,
+        build_provider_models_request, build_tgi_info_request, build_xrouter_models_request,
     };
 
     #[test]
@@ -156,6 +201,37 @@ mod tests {
         assert!(build_xrouter_models_request(None, Some("secret")).is_none());
     }
 
+    #[test]
+    fn tgi_info_request_targets_the_endpoint_root() {
+        let request =
+            build_tgi_info_request(Some("https://abc.endpoints.huggingface.cloud/"), Some("hf_x"))
+                .expect("request");
+        assert_eq!(request.url, "https://abc.endpoints.huggingface.cloud/info");
+        assert!(build_tgi_info_request(None, Some("hf_x")).is_none());
+    }
+
+    #[test]
+    fn cloudflare_models_request_is_account_scoped() {
+        let request = build_cloudflare_models_request(
+            Some("https://api.cloudflare.com/client/v4/"),
+            Some("acc123"),
+            Some("cf-token"),
+        )
+        .expect("request");
+        assert_eq!(
+            request.url,
+            "https://api.cloudflare.com/client/v4/accounts/acc123/ai/models/search?task=Text%20Generation&per_page=100"
+        );
+        assert!(
+            build_cloudflare_models_request(
+                Some("https://api.cloudflare.com/client/v4"),
+                None,
+                None
+            )
+            .is_none()
+        );
+    }
+
     #[test]
     fn gigachat_oauth_request_contains_expected_form_fields() {
         let request = build_gigachat_oauth_request("auth-key", "req-1", "scope-1");
@@ -1,7 +1,8 @@
 use std::collections::{HashMap, HashSet};
 
 use serde::Deserialize;
-use xrouter_core::ModelDescriptor;
+use serde_json::Value;
+use xrouter_core::{ModelDescriptor, ModelPricing};
 
 #[derive(Debug, Deserialize)]
 pub struct OpenRouterModelsResponse {
@@ -20,6 +21,16 @@ pub struct OpenRouterModelData {
     pub architecture: OpenRouterArchitecture,
     #[serde(default)]
     pub top_provider: OpenRouterTopProvider,
+    #[serde(default)]
+    pub supported_parameters: Vec<String>,
+    #[serde(default)]
+    pub pricing: Option<OpenRouterPricing>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenRouterPricing {
+    pub prompt: String,
+    pub completion: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -85,6 +96,98 @@ pub struct XrouterProviderEndpoint {
     pub path: String,
 }
 
+/// TGI `/info`; older servers report `max_input_length` instead of `max_input_tokens`.
+#[derive(Debug, Default, Deserialize)]
+pub struct TgiInfoResponse {
+    pub model_id: String,
+    #[serde(default)]
+    pub max_total_tokens: u32,
+    #[serde(default, alias = "max_input_length")]
+    pub max_input_tokens: u32,
+}
+
+/// Workers AI `ai/models/search` result; model properties carry string values.
+#[derive(Debug, Default, Deserialize)]
+pub struct CloudflareModelsResponse {
+    #[serde(default)]
+    pub result: Vec<CloudflareModelEntry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CloudflareModelEntry {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub properties: Vec<CloudflareModelProperty>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CloudflareModelProperty {
+    pub property_id: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+pub fn map_cloudflare_models(payload: CloudflareModelsResponse) -> Vec<ModelDescriptor> {
+    payload
+        .result
+        .into_iter()
+        .map(|model| {
+            let property = |id: &str| {
+                model.properties.iter().find(|property| property.property_id == id).map(
+                    |property| match &property.value {
+                        Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    },
+                )
+            };
+            let context_length = property("context_window")
+                .and_then(|value| value.parse::<u32>().ok())
+                .unwrap_or(8_192);
+            let supports_tools = property("function_calling").is_some_and(|value| value == "true");
+            ModelDescriptor {
+                description: if model.description.is_empty() {
+                    format!("{} via Cloudflare Workers AI", model.name)
+                } else {
+                    model.description
+                },
+                id: model.name,
+                provider: "cloudflare".to_string(),
+                context_length,
+                tokenizer: "unknown".to_string(),
+                instruct_type: "none".to_string(),
+                modality: "text->text".to_string(),
+                top_provider_context_length: context_length,
+                is_moderated: false,
+                max_completion_tokens: context_length.min(4_096),
+                supports_tools,
+                pricing: None,
+                supported_parameters: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+pub fn map_tgi_info(info: TgiInfoResponse) -> ModelDescriptor {
+    let context_length = if info.max_total_tokens > 0 { info.max_total_tokens } else { 4_096 };
+    ModelDescriptor {
+        description: format!("{} via Text Generation Inference", info.model_id),
+        id: info.model_id,
+        provider: "tgi".to_string(),
+        context_length,
+        tokenizer: "unknown".to_string(),
+        instruct_type: "none".to_string(),
+        modality: "text->text".to_string(),
+        top_provider_context_length: context_length,
+        is_moderated: false,
+        max_completion_tokens: context_length.saturating_sub(info.max_input_tokens).max(1),
+        supports_tools: true,
+        pricing: None,
+        supported_parameters: Vec::new(),
+    }
+}
+
 pub fn map_openrouter_models(
     payload: OpenRouterModelsResponse,
     supported_ids: &[String],
@@ -98,6 +201,9 @@ pub fn map_openrouter_models(
             let context_length = if model.context_length > 0 { model.context_length } else { 4096 };
             let top_context_length = model.top_provider.context_length.unwrap_or(context_length);
             let max_completion_tokens = model.top_provider.max_completion_tokens.unwrap_or(4096);
+            // OpenRouter omits `supported_parameters` for some entries; assume tools then.
+            let supports_tools = model.supported_parameters.is_empty()
+                || model.supported_parameters.iter().any(|param| param == "tools");
             ModelDescriptor {
                 id: model.id.clone(),
                 provider: "openrouter".to_string(),
@@ -124,11 +230,33 @@ pub fn map_openrouter_models(
                 top_provider_context_length: top_context_length,
                 is_moderated: model.top_provider.is_moderated.unwrap_or(true),
                 max_completion_tokens,
+                supports_tools,
+                pricing: model.pricing.map(|pricing| ModelPricing {
+                    prompt: pricing.prompt,
+                    completion: pricing.completion,
+                }),
+                supported_parameters: model.supported_parameters,
             }
         })
         .collect::<Vec<_>>()
 }
 
+/// Parameters the router forwards to `provider`, for catalog entries without upstream data.
+/// `max_tokens` and `stop` are enforced by the router itself, so every provider takes them.
+pub fn default_supported_parameters(provider: &str, supports_tools: bool) -> Vec<String> {
+    let mut parameters = vec!["max_tokens", "stop"];
+    if supports_tools {
+        parameters.extend(["tools", "tool_choice"]);
+    }
+    match provider {
+        "gigachat" => {}
+        "selfhosted" | "cohere" | "perplexity" | "cloudflare" => parameters.push("response_format"),
+        "deepseek" | "zai" => parameters.extend(["reasoning", "response_format"]),
+        _ => parameters.extend(["reasoning", "response_format", "structured_outputs"]),
+    }
+    parameters.into_iter().map(str::to_string).collect()
+}
+
 pub fn fallback_openrouter_models(model_ids: &[String]) -> Vec<ModelDescriptor> {
     model_ids
         .iter()
@@ -149,6 +277,9 @@ pub fn fallback_openrouter_models(model_ids: &[String]) -> Vec<ModelDescriptor>
             top_provider_context_length: 128_000,
             is_moderated: true,
             max_completion_tokens: 16_384,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         })
         .collect()
 }
@@ -185,6 +316,9 @@ pub fn map_xrouter_models(payload: XrouterProviderModelsResponse) -> Vec<ModelDe
                 top_provider_context_length: context_length,
                 is_moderated: true,
                 max_completion_tokens: 8_192,
+                supports_tools: true,
+                pricing: None,
+                supported_parameters: Vec::new(),
             }
         })
         .collect()
@@ -224,6 +358,9 @@ pub fn build_models_from_registry(
                     top_provider_context_length: 128_000,
                     is_moderated: true,
                     max_completion_tokens: 8_192,
+                    supports_tools: true,
+                    pricing: None,
+                    supported_parameters: Vec::new(),
                 }
             }
         })
@@ -295,6 +432,9 @@ fn zai_fallback_model_descriptor(id: &str) -> ModelDescriptor {
         top_provider_context_length: context_length,
         is_moderated: true,
         max_completion_tokens,
+        supports_tools: true,
+        pricing: None,
+        supported_parameters: Vec::new(),
     }
 }
 
@@ -310,17 +450,61 @@ fn yandex_fallback_model_descriptor(id: &str) -> ModelDescriptor {
         top_provider_context_length: 32_768,
         is_moderated: true,
         max_completion_tokens: 8_192,
+        supports_tools: true,
+        pricing: None,
+        supported_parameters: Vec::new(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        OpenRouterModelsResponse, XrouterProviderModelsResponse, build_models_from_registry,
-        map_openrouter_models, map_xrouter_models,
+        CloudflareModelsResponse, OpenRouterModelsResponse, TgiInfoResponse,
+        XrouterProviderModelsResponse, build_models_from_registry, default_supported_parameters,
+        map_cloudflare_models, map_openrouter_models, map_tgi_info, map_xrouter_models,
     };
     use serde_json::json;
 
+    #[test]
+    fn tgi_info_describes_the_served_model_and_its_token_limits() {
+        let info: TgiInfoResponse = serde_json::from_value(json!({
+            "model_id": "meta-llama/Llama-3.1-8B-Instruct",
+            "max_input_length": 7168,
+            "max_total_tokens": 8192,
+            "router": "text-generation-router"
+        }))
+        .expect("info must parse");
+        let model = map_tgi_info(info);
+        assert_eq!(
+            (model.id.as_str(), model.provider.as_str()),
+            ("meta-llama/Llama-3.1-8B-Instruct", "tgi")
+        );
+        assert_eq!((model.context_length, model.max_completion_tokens), (8192, 1024));
+    }
+
+    #[test]
+    fn cloudflare_models_read_context_window_and_function_calling_properties() {
+        let payload: CloudflareModelsResponse = serde_json::from_value(json!({
+            "success": true,
+            "result": [{
+                "id": "0f002249-7d86-4698-aabf-8529ed86cefb",
+                "name": "@cf/meta/llama-3.3-70b-instruct-fp8-fast",
+                "description": "Llama 3.3 70B quantized to fp8.",
+                "task": {"name": "Text Generation"},
+                "properties": [
+                    {"property_id": "context_window", "value": "24000"},
+                    {"property_id": "function_calling", "value": "true"}
+                ]
+            }]
+        }))
+        .expect("models must parse");
+        let models = map_cloudflare_models(payload);
+        assert_eq!(models[0].id, "@cf/meta/llama-3.3-70b-instruct-fp8-fast");
+        assert_eq!(models[0].provider, "cloudflare");
+        assert_eq!(models[0].context_length, 24000);
+        assert!(models[0].supports_tools);
+    }
+
     #[test]
     fn map_openrouter_models_uses_provider_payload_fields() {
         let payload: OpenRouterModelsResponse = serde_json::from_value(json!({
@@ -333,7 +517,8 @@ mod tests {
                     "context_length": 210000,
                     "max_completion_tokens": 12345,
                     "is_moderated": false
-                }
+                },
+                "pricing": {"prompt": "0.00000175", "completion": "0.000014", "request": "0"}
             }, {
                 "id": "ignore/me",
                 "description": "ignored",
@@ -354,6 +539,42 @@ mod tests {
         assert_eq!(model.modality, "text->text");
         assert_eq!(model.tokenizer, "unknown");
         assert_eq!(model.instruct_type, "none");
+        assert!(model.supports_tools);
+        assert!(model.supported_parameters.is_empty());
+        let pricing = model.pricing.as_ref().expect("pricing");
+        assert_eq!(
+            (pricing.prompt.as_str(), pricing.completion.as_str()),
+            ("0.00000175", "0.000014")
+        );
+    }
+
+    #[test]
+    fn map_openrouter_models_reads_tools_support_from_supported_parameters() {
+        let payload: OpenRouterModelsResponse = serde_json::from_value(json!({
+            "data": [
+                {"id": "a/tools", "supported_parameters": ["temperature", "tools"]},
+                {"id": "b/plain", "supported_parameters": ["temperature"]}
+            ]
+        }))
+        .expect("payload must deserialize");
+
+        let models =
+            map_openrouter_models(payload, &["a/tools".to_string(), "b/plain".to_string()]);
+        assert!(models[0].supports_tools);
+        assert!(!models[1].supports_tools);
+    }
+
+    #[test]
+    fn default_supported_parameters_follow_what_the_client_forwards() {
+        let deepseek = default_supported_parameters("deepseek", true);
+        assert!(deepseek.iter().any(|parameter| parameter == "response_format"));
+        assert!(!deepseek.iter().any(|parameter| parameter == "structured_outputs"));
+        assert!(
+            default_supported_parameters("ollama", true)
+                .iter()
+                .any(|parameter| parameter == "structured_outputs")
+        );
+        assert_eq!(default_supported_parameters("gigachat", false), vec!["max_tokens", "stop"]);
     }
 
     #[test]
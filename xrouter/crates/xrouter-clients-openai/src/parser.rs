@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 
+use bytes::BytesMut;
 use serde::Deserialize;
 use serde_json::Value;
 use tracing::warn;
 use uuid::Uuid;
 use xrouter_contracts::{ToolCall, ToolFunction};
-use xrouter_core::{CoreError, ProviderOutcome};
+use xrouter_core::{CoreError, ProviderOutcome, ProviderUsage};
 
 pub fn map_chat_completion_response(
     payload: ChatCompletionsResponse,
@@ -15,7 +16,8 @@ pub fn map_chat_completion_response(
         .first()
         .ok_or_else(|| CoreError::Provider("provider returned empty choices".to_string()))?;
 
-    let content = extract_message_content(&first.message.content).unwrap_or_default();
+    let (content, inline_reasoning) =
+        split_inline_think(&extract_message_content(&first.message.content).unwrap_or_default());
     let tool_calls = first
         .message
         .tool_calls
@@ -28,35 +30,50 @@ pub fn map_chat_completion_response(
     }
 
     let output_tokens =
-        payload.usage.and_then(|usage| usage.completion_tokens).unwrap_or_else(|| {
+        payload.usage.as_ref().and_then(Usage::output_tokens).unwrap_or_else(|| {
             if content.is_empty() { 0 } else { content.split_whitespace().count() as u32 }
         });
 
     let reasoning_details = first.message.reasoning_details.clone();
-    let reasoning = first
-        .message
-        .reasoning_content
-        .clone()
-        .or_else(|| first.message.reasoning.clone())
-        .or_else(|| {
-            reasoning_details.as_ref().and_then(|details| extract_reasoning_from_details(details))
-        });
+    let reasoning = merge_reasoning(
+        first
+            .message
+            .reasoning_content
+            .clone()
+            .or_else(|| first.message.reasoning.clone())
+            .or_else(|| {
+                reasoning_details
+                    .as_ref()
+                    .and_then(|details| extract_reasoning_from_details(details))
+            }),
+        inline_reasoning,
+    );
 
+    let finish_reason = first.finish_reason.as_deref().and_then(normalize_finish_reason);
     let chunks = if content.is_empty() { Vec::new() } else { vec![content] };
     Ok(ProviderOutcome {
         chunks,
         output_tokens,
         reasoning,
         reasoning_details,
+        reasoning_encrypted_content: None,
         tool_calls,
+        annotations: None,
         emitted_live: false,
+        finish_reason,
+        usage: payload.usage.as_ref().and_then(Usage::native),
+        upstream_attempts: 1,
+        provider_response_id: payload.id.filter(|id| !id.trim().is_empty()),
+        upstream_provider: None,
     })
 }
 
 pub fn map_responses_api_response(
     payload: ResponsesApiResponse,
 ) -> Result<ProviderOutcome, CoreError> {
-    let content = extract_message_text_from_responses_output(&payload.output).unwrap_or_default();
+    let (content, inline_reasoning) = split_inline_think(
+        &extract_message_text_from_responses_output(&payload.output).unwrap_or_default(),
+    );
     let tool_calls = extract_tool_calls_from_responses_output(&payload.output)
         .or_else(|| extract_deepseek_dsml_tool_calls(&content));
     if content.is_empty() && tool_calls.is_none() {
@@ -67,72 +84,313 @@ pub fn map_responses_api_response(
     }
 
     let reasoning_details = extract_reasoning_content_items_from_responses_output(&payload.output);
-    let reasoning = extract_reasoning_text_from_responses_output(&payload.output).or_else(|| {
-        reasoning_details.as_ref().and_then(|details| extract_reasoning_from_details(details))
-    });
+    let reasoning_encrypted_content =
+        extract_reasoning_encrypted_content_from_responses_output(&payload.output);
+    let reasoning = merge_reasoning(
+        extract_reasoning_text_from_responses_output(&payload.output).or_else(|| {
+            reasoning_details.as_ref().and_then(|details| extract_reasoning_from_details(details))
+        }),
+        inline_reasoning,
+    );
 
-    let output_tokens = payload.usage.as_ref().map(|u| u.output_tokens).unwrap_or_else(|| {
-        if content.is_empty() { 0 } else { content.split_whitespace().count() as u32 }
-    });
+    let output_tokens =
+        payload.usage.as_ref().and_then(Usage::output_tokens).unwrap_or_else(|| {
+            if content.is_empty() { 0 } else { content.split_whitespace().count() as u32 }
+        });
 
+    let finish_reason = responses_finish_reason(
+        payload.status.as_deref(),
+        payload.incomplete_details.as_ref().and_then(|details| details.reason.as_deref()),
+    );
     let chunks = if content.is_empty() { Vec::new() } else { vec![content] };
     Ok(ProviderOutcome {
         chunks,
         output_tokens,
         reasoning,
         reasoning_details,
+        reasoning_encrypted_content,
         tool_calls,
+        annotations: None,
         emitted_live: false,
+        finish_reason,
+        usage: payload.usage.as_ref().and_then(Usage::native),
+        upstream_attempts: 1,
+        provider_response_id: payload.id.filter(|id| !id.trim().is_empty()),
+        upstream_provider: None,
     })
 }
 
-pub fn map_chat_completion_stream_text(payload: &str) -> Result<ProviderOutcome, CoreError> {
-    let mut chunks = Vec::<String>::new();
-    let mut all_content = String::new();
-    let mut reasoning = String::new();
-    let mut reasoning_details = Vec::<Value>::new();
-    let mut output_tokens = None::<u32>;
-    let mut tool_calls_by_index = HashMap::<usize, StreamToolCall>::new();
-    let mut direct_tool_calls = Vec::<ToolCall>::new();
-
-    for event in extract_sse_data_events(payload) {
-        if event == "[DONE]" {
+/// Maps a provider-native stop reason onto the router's `finish_reason` vocabulary
+/// (`stop`, `length`, `content_filter`, `tool_calls`); unknown reasons map to `None`.
+pub fn normalize_finish_reason(raw: &str) -> Option<String> {
+    let normalized = match raw.trim().to_ascii_lowercase().as_str() {
+        "stop" | "end_turn" | "stop_sequence" | "eos" | "eos_token" | "complete" => "stop",
+        "length" | "max_tokens" | "max_output_tokens" | "model_length" => "length",
+        "content_filter" | "blacklist" | "safety" => "content_filter",
+        "tool_calls" | "tool_use" | "function_call" | "tool_call" => "tool_calls",
+        _ => return None,
+    };
+    Some(normalized.to_string())
+}
+
+pub(crate) fn responses_finish_reason(
+    status: Option<&str>,
+    incomplete_reason: Option<&str>,
+) -> Option<String> {
+    if status != Some("incomplete") {
+        return None;
+    }
+    incomplete_reason.and_then(normalize_finish_reason)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StreamDelta {
+    pub content: Vec<String>,
+    pub reasoning: Option<String>,
+}
+
+pub trait StreamAccumulator: Send {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError>;
+
+    fn limit_retained_content(&mut self, _max_bytes: usize) {}
+
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError>;
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ContentBuffer {
+    text: String,
+    limit: Option<usize>,
+    dropped_bytes: usize,
+    dropped_words: usize,
+}
+
+impl ContentBuffer {
+    pub(crate) fn set_limit(&mut self, max_bytes: usize) {
+        self.limit = Some(max_bytes);
+    }
+
+    pub(crate) fn push(&mut self, chunk: &str) {
+        if self.dropped_bytes > 0
+            || self.limit.is_some_and(|limit| self.text.len() + chunk.len() > limit)
+        {
+            self.dropped_bytes += chunk.len();
+            self.dropped_words += chunk.split_whitespace().count();
+            return;
+        }
+        self.text.push_str(chunk);
+    }
+
+    pub(crate) fn replace(&mut self, text: String) {
+        self.text = text;
+        self.dropped_bytes = 0;
+        self.dropped_words = 0;
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.dropped_bytes == 0
+    }
+
+    pub(crate) fn retained_bytes(&self) -> usize {
+        self.text.len()
+    }
+
+    pub(crate) fn dropped_bytes(&self) -> usize {
+        self.dropped_bytes
+    }
+
+    pub(crate) fn dropped_words(&self) -> usize {
+        self.dropped_words
+    }
+
+    pub(crate) fn into_text(self) -> String {
+        self.text
+    }
+}
+
+pub(crate) fn estimate_output_tokens(text: &str, dropped_words: usize) -> u32 {
+    (text.split_whitespace().count() + dropped_words) as u32
+}
+
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: BytesMut,
+    scanned: usize,
+}
+
+impl SseDecoder {
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.reserve(bytes.len());
+        for chunk in bytes.split(|byte| *byte == b'\r') {
+            self.buffer.extend_from_slice(chunk);
+        }
+        let mut events = Vec::new();
+        while let Some(offset) =
+            self.buffer[self.scanned..].windows(2).position(|window| window == b"\n\n")
+        {
+            let frame = self.buffer.split_to(self.scanned + offset + 2);
+            self.scanned = 0;
+            if let Some(data) = sse_frame_bytes_to_data(&frame[..frame.len() - 2]) {
+                events.push(data);
+            }
+        }
+        self.scanned = self.buffer.len().saturating_sub(1);
+        events
+    }
+
+    pub fn finish(&mut self) -> Option<String> {
+        let frame = self.buffer.split();
+        self.scanned = 0;
+        sse_frame_bytes_to_data(&frame)
+    }
+
+    pub fn pending_bytes(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// Decodes a byte stream read in arbitrary chunks, holding back a multi-byte character split
+/// across chunks until its remaining bytes arrive instead of replacing its halves with U+FFFD.
+#[derive(Debug, Default)]
+pub struct Utf8ChunkDecoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8ChunkDecoder {
+    pub fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let complete = self.pending.len() - incomplete_utf8_tail_len(&self.pending);
+        let tail = self.pending.split_off(complete);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = tail;
+        text
+    }
+
+    /// Whatever is still held back, lossily; non-empty only when the stream ended mid-character.
+    pub fn finish(&mut self) -> String {
+        String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned()
+    }
+}
+
+/// Length of a trailing UTF-8 sequence whose lead byte promises more bytes than follow it.
+fn incomplete_utf8_tail_len(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 == 0x80 {
             continue;
         }
-        let parsed: ChatCompletionsStreamChunk = serde_json::from_str(&event)
+        let sequence_len = match byte {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return if sequence_len > back { back } else { 0 };
+    }
+    0
+}
+
+fn sse_frame_bytes_to_data(frame: &[u8]) -> Option<String> {
+    let frame = String::from_utf8_lossy(frame);
+    let frame = frame.trim();
+    if frame.is_empty() { None } else { sse_frame_to_data(frame) }
+}
+
+pub(crate) fn sse_data_events(payload: &str) -> Vec<String> {
+    let mut decoder = SseDecoder::default();
+    let mut events = decoder.push(payload.as_bytes());
+    events.extend(decoder.finish());
+    events
+}
+
+pub(crate) fn accumulate_stream_text(
+    mut accumulator: Box<dyn StreamAccumulator>,
+    payload: &str,
+) -> Result<ProviderOutcome, CoreError> {
+    for event in sse_data_events(payload) {
+        accumulator.push_event(&event)?;
+    }
+    accumulator.finish()
+}
+
+pub fn map_chat_completion_stream_text(payload: &str) -> Result<ProviderOutcome, CoreError> {
+    accumulate_stream_text(Box::<ChatStreamAccumulator>::default(), payload)
+}
+
+pub fn map_responses_stream_text(payload: &str) -> Result<ProviderOutcome, CoreError> {
+    accumulate_stream_text(Box::<ResponsesStreamAccumulator>::default(), payload)
+}
+
+#[derive(Debug, Default)]
+pub struct ChatStreamAccumulator {
+    content: ContentBuffer,
+    reasoning: String,
+    reasoning_details: Vec<Value>,
+    output_tokens: Option<u32>,
+    tool_calls_by_index: HashMap<usize, StreamToolCall>,
+    direct_tool_calls: Vec<ToolCall>,
+    finish_reason: Option<String>,
+    usage: Option<ProviderUsage>,
+    provider_response_id: Option<String>,
+}
+
+impl StreamAccumulator for ChatStreamAccumulator {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError> {
+        let mut delta = StreamDelta::default();
+        if data == "[DONE]" {
+            return Ok(delta);
+        }
+        let parsed: ChatCompletionsStreamChunk = serde_json::from_str(data)
             .map_err(|err| CoreError::Provider(format!("provider stream parse failed: {err}")))?;
+        if self.provider_response_id.is_none() {
+            self.provider_response_id = parsed.id.filter(|id| !id.trim().is_empty());
+        }
 
-        if let Some(usage) = parsed.usage.and_then(|usage| usage.completion_tokens) {
-            output_tokens = Some(usage);
+        if let Some(usage) = parsed.usage {
+            if let Some(tokens) = usage.output_tokens() {
+                self.output_tokens = Some(tokens);
+            }
+            if let Some(native) = usage.native() {
+                self.usage = Some(native);
+            }
         }
 
+        let mut reasoning_delta = String::new();
         for choice in parsed.choices {
+            if let Some(reason) = choice.finish_reason.as_deref().and_then(normalize_finish_reason)
+            {
+                self.finish_reason = Some(reason);
+            }
             if let Some(content_delta) = extract_message_content(&choice.delta.content)
                 && !content_delta.is_empty()
             {
-                all_content.push_str(&content_delta);
-                chunks.push(content_delta);
+                self.content.push(&content_delta);
+                delta.content.push(content_delta);
             }
             if let Some(message) = choice.message.as_ref() {
                 if let Some(content) = extract_message_content(&message.content)
                     && !content.is_empty()
                 {
-                    all_content.push_str(&content);
-                    chunks.push(content);
+                    self.content.push(&content);
                 }
                 if let Some(tool_calls) = message.tool_calls.as_ref() {
-                    direct_tool_calls.extend(map_provider_tool_calls(tool_calls));
+                    self.direct_tool_calls.extend(map_provider_tool_calls(tool_calls));
                 }
             }
 
-            if let Some(text) = choice.delta.reasoning_content.or(choice.delta.reasoning)
-                && !text.trim().is_empty()
-            {
-                reasoning.push_str(&text);
+            if let Some(text) = choice.delta.reasoning_content.or(choice.delta.reasoning) {
+                reasoning_delta.push_str(&text);
+                if !text.trim().is_empty() {
+                    self.reasoning.push_str(&text);
+                }
             }
 
             if let Some(details) = choice.delta.reasoning_details {
-                reasoning_details.extend(details);
+                self.reasoning_details.extend(details);
             }
 
             for tool_delta in choice
@@ -142,8 +400,8 @@ pub fn map_chat_completion_stream_text(payload: &str) -> Result<ProviderOutcome,
                 .into_iter()
                 .chain(choice.tool_calls.unwrap_or_default())
             {
-                let index = tool_delta.index.unwrap_or(tool_calls_by_index.len());
-                let entry = tool_calls_by_index.entry(index).or_default();
+                let index = tool_delta.index.unwrap_or(self.tool_calls_by_index.len());
+                let entry = self.tool_calls_by_index.entry(index).or_default();
                 if let Some(id) = tool_delta.id.filter(|v| !v.trim().is_empty()) {
                     entry.id = Some(id);
                 }
@@ -160,63 +418,102 @@ pub fn map_chat_completion_stream_text(payload: &str) -> Result<ProviderOutcome,
                 }
             }
         }
-    }
-
-    let mut tool_calls = finalize_stream_tool_calls(tool_calls_by_index);
-    if !direct_tool_calls.is_empty() {
-        if let Some(existing) = tool_calls.as_mut() {
-            existing.extend(direct_tool_calls);
-        } else {
-            tool_calls = Some(direct_tool_calls);
+        if !reasoning_delta.trim().is_empty() {
+            delta.reasoning = Some(reasoning_delta);
         }
+        Ok(delta)
     }
-    if tool_calls.is_none() {
-        tool_calls = extract_deepseek_dsml_tool_calls(&all_content);
+
+    fn limit_retained_content(&mut self, max_bytes: usize) {
+        self.content.set_limit(max_bytes);
     }
-    let reasoning = if reasoning.trim().is_empty() { None } else { Some(reasoning) };
-    let reasoning_details =
-        if reasoning_details.is_empty() { None } else { Some(reasoning_details) };
-    let output_tokens = output_tokens.unwrap_or_else(|| {
-        if all_content.is_empty() { 0 } else { all_content.split_whitespace().count() as u32 }
-    });
 
-    if all_content.is_empty() && tool_calls.is_none() {
-        warn!(
-            event = "provider.responses.stream.empty_message_content",
-            "provider returned empty message content in responses stream; treating as empty completed response"
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError> {
+        let Self {
+            content,
+            reasoning,
+            reasoning_details,
+            output_tokens,
+            tool_calls_by_index,
+            direct_tool_calls,
+            finish_reason,
+            usage,
+            provider_response_id,
+        } = *self;
+        let dropped_words = content.dropped_words();
+        let mut all_content = content.into_text();
+        let (content, inline_reasoning) = split_inline_think(&all_content);
+        if inline_reasoning.is_some() {
+            all_content = content;
+        }
+        let mut tool_calls = finalize_stream_tool_calls(tool_calls_by_index);
+        if !direct_tool_calls.is_empty() {
+            if let Some(existing) = tool_calls.as_mut() {
+                existing.extend(direct_tool_calls);
+            } else {
+                tool_calls = Some(direct_tool_calls);
+            }
+        }
+        if tool_calls.is_none() {
+            tool_calls = extract_deepseek_dsml_tool_calls(&all_content);
+        }
+        let reasoning = merge_reasoning(
+            if reasoning.trim().is_empty() { None } else { Some(reasoning) },
+            inline_reasoning,
         );
-    }
+        let reasoning_details =
+            if reasoning_details.is_empty() { None } else { Some(reasoning_details) };
+        let output_tokens =
+            output_tokens.unwrap_or_else(|| estimate_output_tokens(&all_content, dropped_words));
+
+        if all_content.is_empty() && tool_calls.is_none() {
+            warn!(
+                event = "provider.responses.stream.empty_message_content",
+                "provider returned empty message content in responses stream; treating as empty completed response"
+            );
+        }
 
-    let final_chunks = if all_content.is_empty() { Vec::new() } else { chunks };
-    Ok(ProviderOutcome {
-        chunks: final_chunks,
-        output_tokens,
-        reasoning,
-        reasoning_details,
-        tool_calls,
-        emitted_live: false,
-    })
+        Ok(ProviderOutcome {
+            chunks: if all_content.is_empty() { Vec::new() } else { vec![all_content] },
+            output_tokens,
+            reasoning,
+            reasoning_details,
+            reasoning_encrypted_content: None,
+            tool_calls,
+            annotations: None,
+            emitted_live: false,
+            finish_reason,
+            usage,
+            upstream_attempts: 1,
+            provider_response_id,
+            upstream_provider: None,
+        })
+    }
 }
 
-pub fn map_responses_stream_text(payload: &str) -> Result<ProviderOutcome, CoreError> {
-    let mut chunks = Vec::<String>::new();
-    let mut all_content = String::new();
-    let mut tool_calls = Vec::<ToolCall>::new();
+#[derive(Debug, Default)]
+pub struct ResponsesStreamAccumulator {
+    content: ContentBuffer,
+    tool_calls: Vec<ToolCall>,
+    completed: Option<ProviderOutcome>,
+}
 
-    for event in extract_sse_data_events(payload) {
-        if event == "[DONE]" {
-            continue;
+impl StreamAccumulator for ResponsesStreamAccumulator {
+    fn push_event(&mut self, data: &str) -> Result<StreamDelta, CoreError> {
+        let mut delta = StreamDelta::default();
+        if data == "[DONE]" || self.completed.is_some() {
+            return Ok(delta);
         }
-        let parsed: ResponsesStreamEvent = serde_json::from_str(&event)
+        let parsed: ResponsesStreamEvent = serde_json::from_str(data)
             .map_err(|err| CoreError::Provider(format!("provider stream parse failed: {err}")))?;
 
         if parsed.kind == "response.output_text.delta"
-            && let Some(delta) = parsed.delta.or(parsed.text)
-            && !delta.is_empty()
+            && let Some(text) = parsed.delta.or(parsed.text)
+            && !text.is_empty()
         {
-            all_content.push_str(&delta);
-            chunks.push(delta);
-            continue;
+            self.content.push(&text);
+            delta.content.push(text);
+            return Ok(delta);
         }
 
         if parsed.kind == "response.output_item.added"
@@ -227,7 +524,7 @@ pub fn map_responses_stream_text(payload: &str) -> Result<ProviderOutcome, CoreE
             && !call_id.trim().is_empty()
             && !name.trim().is_empty()
         {
-            tool_calls.push(ToolCall {
+            self.tool_calls.push(ToolCall {
                 id: call_id.to_string(),
                 kind: "function".to_string(),
                 function: ToolFunction {
@@ -235,50 +532,71 @@ pub fn map_responses_stream_text(payload: &str) -> Result<ProviderOutcome, CoreE
                     arguments: item.arguments.unwrap_or_else(|| "{}".to_string()),
                 },
             });
-            continue;
+            return Ok(delta);
         }
 
-        if ((parsed.kind == "response.completed") || parsed.kind.is_empty())
+        if matches!(parsed.kind.as_str(), "response.completed" | "response.incomplete" | "")
             && let Some(response) = parsed.response
         {
             let mut mapped = map_responses_api_response(response)?;
-            if !all_content.is_empty() && mapped.chunks.is_empty() {
-                mapped.chunks = chunks.clone();
+            if !self.content.as_str().is_empty() && mapped.chunks.is_empty() {
+                let (content, inline_reasoning) = split_inline_think(self.content.as_str());
+                if inline_reasoning.is_some() {
+                    mapped.chunks = if content.is_empty() { Vec::new() } else { vec![content] };
+                    mapped.reasoning = merge_reasoning(mapped.reasoning, inline_reasoning);
+                } else {
+                    mapped.chunks = vec![std::mem::take(&mut self.content).into_text()];
+                }
             }
-            if mapped.tool_calls.is_none() && !tool_calls.is_empty() {
-                mapped.tool_calls = Some(tool_calls.clone());
+            if mapped.tool_calls.is_none() && !self.tool_calls.is_empty() {
+                mapped.tool_calls = Some(std::mem::take(&mut self.tool_calls));
             }
-            return Ok(mapped);
+            self.completed = Some(mapped);
         }
+        Ok(delta)
     }
 
-    let tool_calls = if tool_calls.is_empty() { None } else { Some(tool_calls) };
-    let output_tokens =
-        if all_content.is_empty() { 0 } else { all_content.split_whitespace().count() as u32 };
-
-    if all_content.is_empty() && tool_calls.is_none() {
-        warn!(
-            event = "provider.responses.stream.empty_message_content.tail",
-            "provider returned empty message content in responses stream tail; treating as empty completed response"
-        );
+    fn limit_retained_content(&mut self, max_bytes: usize) {
+        self.content.set_limit(max_bytes);
     }
 
-    Ok(ProviderOutcome {
-        chunks: if all_content.is_empty() { Vec::new() } else { chunks },
-        output_tokens,
-        reasoning: None,
-        reasoning_details: None,
-        tool_calls,
-        emitted_live: false,
-    })
-}
+    fn finish(self: Box<Self>) -> Result<ProviderOutcome, CoreError> {
+        let Self { content, tool_calls, completed } = *self;
+        if let Some(completed) = completed {
+            return Ok(completed);
+        }
+        let dropped_words = content.dropped_words();
+        let mut all_content = content.into_text();
+        let (content, reasoning) = split_inline_think(&all_content);
+        if reasoning.is_some() {
+            all_content = content;
+        }
+        let tool_calls = if tool_calls.is_empty() { None } else { Some(tool_calls) };
+        let output_tokens = estimate_output_tokens(&all_content, dropped_words);
+
+        if all_content.is_empty() && tool_calls.is_none() {
+            warn!(
+                event = "provider.responses.stream.empty_message_content.tail",
+                "provider returned empty message content in responses stream tail; treating as empty completed response"
+            );
+        }
 
-fn extract_sse_data_events(payload: &str) -> Vec<String> {
-    let mut owned = payload.replace('\r', "");
-    drain_sse_frames(&mut owned, true)
-        .into_iter()
-        .filter_map(|frame| sse_frame_to_data(&frame))
-        .collect()
+        Ok(ProviderOutcome {
+            chunks: if all_content.is_empty() { Vec::new() } else { vec![all_content] },
+            output_tokens,
+            reasoning,
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls,
+            annotations: None,
+            emitted_live: false,
+            finish_reason: None,
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
+        })
+    }
 }
 
 pub fn drain_sse_frames(buffer: &mut String, flush_tail: bool) -> Vec<String> {
@@ -366,8 +684,115 @@ pub fn extract_responses_text_delta(frame: &str) -> Result<Option<String>, CoreE
     Ok(None)
 }
 
+const THINK_OPEN_TAG: &str = "<think>";
+const THINK_CLOSE_TAG: &str = "</think>";
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ThinkSplit {
+    pub content: String,
+    pub reasoning: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ThinkState {
+    #[default]
+    Start,
+    Thinking,
+    AfterThink,
+    Passthrough,
+}
+
+#[derive(Debug, Default)]
+pub struct ThinkTagSplitter {
+    state: ThinkState,
+    pending: String,
+}
+
+impl ThinkTagSplitter {
+    pub fn push(&mut self, delta: &str) -> ThinkSplit {
+        self.pending.push_str(delta);
+        let mut split = ThinkSplit::default();
+        loop {
+            match self.state {
+                ThinkState::Start => {
+                    let trimmed = self.pending.trim_start();
+                    if let Some(rest) = trimmed.strip_prefix(THINK_OPEN_TAG) {
+                        self.pending = rest.to_string();
+                        self.state = ThinkState::Thinking;
+                    } else if THINK_OPEN_TAG.starts_with(trimmed) {
+                        return split;
+                    } else {
+                        self.state = ThinkState::Passthrough;
+                    }
+                }
+                ThinkState::Thinking => {
+                    if let Some(end) = self.pending.find(THINK_CLOSE_TAG) {
+                        split.reasoning.push_str(&self.pending[..end]);
+                        self.pending.replace_range(..end + THINK_CLOSE_TAG.len(), "");
+                        self.state = ThinkState::AfterThink;
+                        continue;
+                    }
+                    let emit_to =
+                        self.pending.len() - partial_tag_suffix_len(&self.pending, THINK_CLOSE_TAG);
+                    split.reasoning.push_str(&self.pending[..emit_to]);
+                    self.pending.replace_range(..emit_to, "");
+                    return split;
+                }
+                ThinkState::AfterThink => {
+                    let trimmed = self.pending.trim_start();
+                    if trimmed.is_empty() {
+                        self.pending.clear();
+                        return split;
+                    }
+                    self.pending = trimmed.to_string();
+                    self.state = ThinkState::Passthrough;
+                }
+                ThinkState::Passthrough => {
+                    split.content.push_str(&self.pending);
+                    self.pending.clear();
+                    return split;
+                }
+            }
+        }
+    }
+
+    pub fn finish(&mut self) -> ThinkSplit {
+        let pending = std::mem::take(&mut self.pending);
+        match self.state {
+            ThinkState::Thinking => ThinkSplit { content: String::new(), reasoning: pending },
+            _ => ThinkSplit { content: pending, reasoning: String::new() },
+        }
+    }
+}
+
+fn partial_tag_suffix_len(text: &str, tag: &str) -> usize {
+    (1..tag.len()).rev().find(|len| text.ends_with(&tag[..*len])).unwrap_or(0)
+}
+
+pub fn split_inline_think(content: &str) -> (String, Option<String>) {
+    let mut splitter = ThinkTagSplitter::default();
+    let mut split = splitter.push(content);
+    let tail = splitter.finish();
+    split.content.push_str(&tail.content);
+    split.reasoning.push_str(&tail.reasoning);
+    let reasoning = if split.reasoning.trim().is_empty() { None } else { Some(split.reasoning) };
+    (split.content, reasoning)
+}
+
+fn merge_reasoning(primary: Option<String>, inline: Option<String>) -> Option<String> {
+    match (primary, inline) {
+        (Some(primary), Some(inline)) if !primary.trim().is_empty() => {
+            Some(format!("{primary}\n{inline}"))
+        }
+        (Some(primary), None) => Some(primary),
+        (_, inline) => inline,
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ChatCompletionsResponse {
+    #[serde(default)]
+    pub(crate) id: Option<String>,
     pub(crate) choices: Vec<Choice>,
     #[serde(default)]
     pub(crate) usage: Option<Usage>,
@@ -376,6 +801,8 @@ pub struct ChatCompletionsResponse {
 #[derive(Debug, Deserialize)]
 pub(crate) struct Choice {
     pub(crate) message: Message,
+    #[serde(default)]
+    pub(crate) finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -392,24 +819,87 @@ pub(crate) struct Message {
     pub(crate) tool_calls: Option<Vec<ProviderToolCall>>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Usage block of either Chat Completions (`prompt_tokens`/`completion_tokens`) or Responses
+/// (`input_tokens`/`output_tokens`) payloads, plus vendor-specific cache counters.
+#[derive(Debug, Default, Deserialize)]
 pub(crate) struct Usage {
     #[serde(default)]
     pub(crate) completion_tokens: Option<u32>,
+    #[serde(default)]
+    pub(crate) prompt_tokens: Option<u32>,
+    #[serde(default)]
+    pub(crate) output_tokens: Option<u32>,
+    #[serde(default)]
+    pub(crate) input_tokens: Option<u32>,
+    #[serde(default)]
+    pub(crate) prompt_tokens_details: Option<UsageTokenDetails>,
+    #[serde(default)]
+    pub(crate) completion_tokens_details: Option<UsageTokenDetails>,
+    #[serde(default)]
+    pub(crate) input_tokens_details: Option<UsageTokenDetails>,
+    #[serde(default)]
+    pub(crate) output_tokens_details: Option<UsageTokenDetails>,
+    // DeepSeek context caching.
+    #[serde(default)]
+    pub(crate) prompt_cache_hit_tokens: Option<u32>,
+    // GigaChat prompt caching.
+    #[serde(default)]
+    pub(crate) precached_prompt_tokens: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct UsageTokenDetails {
+    #[serde(default)]
+    pub(crate) cached_tokens: Option<u32>,
+    #[serde(default)]
+    pub(crate) reasoning_tokens: Option<u32>,
+}
+
+impl Usage {
+    pub(crate) fn output_tokens(&self) -> Option<u32> {
+        self.completion_tokens.or(self.output_tokens)
+    }
+
+    pub(crate) fn native(&self) -> Option<ProviderUsage> {
+        let details = |details: &Option<UsageTokenDetails>,
+                       pick: fn(&UsageTokenDetails) -> Option<u32>| {
+            details.as_ref().and_then(pick)
+        };
+        let usage = ProviderUsage {
+            prompt_tokens: self.prompt_tokens.or(self.input_tokens),
+            reasoning_tokens: details(&self.completion_tokens_details, |d| d.reasoning_tokens)
+                .or_else(|| details(&self.output_tokens_details, |d| d.reasoning_tokens)),
+            cached_tokens: details(&self.prompt_tokens_details, |d| d.cached_tokens)
+                .or_else(|| details(&self.input_tokens_details, |d| d.cached_tokens))
+                .or(self.prompt_cache_hit_tokens)
+                .or(self.precached_prompt_tokens),
+        };
+        (usage != ProviderUsage::default()).then_some(usage)
+    }
+}
+
+pub(crate) fn native_usage_from_value(usage: Option<&Value>) -> Option<ProviderUsage> {
+    serde_json::from_value::<Usage>(usage?.clone()).ok()?.native()
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ResponsesApiResponse {
+    #[serde(default)]
+    pub(crate) id: Option<String>,
     #[serde(default)]
     pub(crate) output: Vec<ResponsesApiOutputItem>,
     #[serde(default)]
-    pub(crate) usage: Option<ResponsesApiUsage>,
+    pub(crate) usage: Option<Usage>,
+    #[serde(default)]
+    pub(crate) status: Option<String>,
+    #[serde(default)]
+    pub(crate) incomplete_details: Option<ResponsesApiIncompleteDetails>,
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct ResponsesApiUsage {
+pub(crate) struct ResponsesApiIncompleteDetails {
     #[serde(default)]
-    pub(crate) output_tokens: u32,
+    pub(crate) reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -426,10 +916,14 @@ pub(crate) struct ResponsesApiOutputItem {
     pub(crate) name: Option<String>,
     #[serde(default)]
     pub(crate) arguments: Option<String>,
+    #[serde(default)]
+    pub(crate) encrypted_content: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ChatCompletionsStreamChunk {
+    #[serde(default)]
+    id: Option<String>,
     #[serde(default)]
     choices: Vec<StreamChoice>,
     #[serde(default)]
@@ -444,6 +938,8 @@ struct StreamChoice {
     tool_calls: Option<Vec<ProviderToolCallDelta>>,
     #[serde(default)]
     message: Option<Message>,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -491,17 +987,21 @@ struct StreamToolCall {
 #[cfg(test)]
 mod tests {
     use super::{
-        ChatCompletionsResponse, Choice, Message, ProviderToolCall, ProviderToolFunction,
-        ResponsesApiOutputItem, ResponsesApiResponse, ResponsesApiUsage, Usage,
+        ChatCompletionsResponse, ChatStreamAccumulator, Choice, ContentBuffer, Message,
+        ProviderToolCall, ProviderToolFunction, ResponsesApiOutputItem, ResponsesApiResponse,
+        SseDecoder, StreamAccumulator, ThinkTagSplitter, Usage, Utf8ChunkDecoder,
         extract_reasoning_from_details, map_chat_completion_response,
         map_chat_completion_stream_text, map_responses_api_response, map_responses_stream_text,
+        native_usage_from_value, normalize_finish_reason, split_inline_think,
     };
     use serde_json::{Value, json};
     use xrouter_contracts::{ToolCall, ToolFunction};
+    use xrouter_core::ProviderUsage;
 
     #[test]
     fn map_chat_completion_response_accepts_tool_only_message() {
         let payload = ChatCompletionsResponse {
+            id: None,
             choices: vec![Choice {
                 message: Message {
                     content: Value::String(String::new()),
@@ -517,8 +1017,9 @@ mod tests {
                         }),
                     }]),
                 },
+                finish_reason: None,
             }],
-            usage: Some(Usage { completion_tokens: Some(7) }),
+            usage: Some(Usage { completion_tokens: Some(7), ..Default::default() }),
         };
 
         let outcome = map_chat_completion_response(payload).expect("tool-only completion is valid");
@@ -540,6 +1041,7 @@ mod tests {
     #[test]
     fn map_chat_completion_response_extracts_deepseek_dsml_tool_call() {
         let payload = ChatCompletionsResponse {
+            id: None,
             choices: vec![Choice {
                 message: Message {
                     content: Value::String(
@@ -551,8 +1053,9 @@ mod tests {
                     reasoning_details: None,
                     tool_calls: None,
                 },
+                finish_reason: None,
             }],
-            usage: Some(Usage { completion_tokens: Some(7) }),
+            usage: Some(Usage { completion_tokens: Some(7), ..Default::default() }),
         };
 
         let outcome = map_chat_completion_response(payload).expect("dsml tool call must parse");
@@ -604,6 +1107,155 @@ mod tests {
         assert!(outcome.tool_calls.is_none());
     }
 
+    #[test]
+    fn sse_decoder_reassembles_frames_split_across_chunks_and_multibyte_chars() {
+        let payload = "data: {\"choices\":[{\"delta\":{\"content\":\"привет\"}}]}\r\n\r\n: ping\n\ndata: [DONE]";
+        let bytes = payload.as_bytes();
+        let mut decoder = SseDecoder::default();
+        let mut events = Vec::new();
+        for chunk in bytes.chunks(7) {
+            events.extend(decoder.push(chunk));
+        }
+        events.extend(decoder.finish());
+        assert_eq!(
+            events,
+            vec![
+                "{\"choices\":[{\"delta\":{\"content\":\"привет\"}}]}".to_string(),
+                "[DONE]".to_string()
+            ]
+        );
+        assert_eq!(decoder.pending_bytes(), 0);
+    }
+
+    #[test]
+    fn utf8_chunk_decoder_keeps_characters_split_across_chunks_intact() {
+        let text = "привет, 世界 👋!";
+        for chunk_len in 1..=5 {
+            let mut decoder = Utf8ChunkDecoder::default();
+            let mut decoded = String::new();
+            for chunk in text.as_bytes().chunks(chunk_len) {
+                decoded.push_str(&decoder.push(chunk));
+            }
+            decoded.push_str(&decoder.finish());
+            assert_eq!(decoded, text, "chunks of {chunk_len} bytes");
+        }
+
+        let mut decoder = Utf8ChunkDecoder::default();
+        assert_eq!(decoder.push(&[b'a', 0xFF, 0xD0]), "a\u{FFFD}");
+        assert_eq!(decoder.finish(), "\u{FFFD}");
+    }
+
+    #[test]
+    fn content_buffer_keeps_prefix_and_counts_output_past_limit() {
+        let mut buffer = ContentBuffer::default();
+        buffer.set_limit(8);
+        for chunk in ["one ", "two ", "three four"] {
+            buffer.push(chunk);
+        }
+        assert_eq!(buffer.as_str(), "one two ");
+        assert_eq!(buffer.dropped_bytes(), "three four".len());
+        assert_eq!(buffer.dropped_words(), 2);
+        assert!(!buffer.is_empty());
+    }
+
+    #[test]
+    fn chat_stream_accumulator_estimates_tokens_including_dropped_output() {
+        let mut accumulator = Box::<ChatStreamAccumulator>::default();
+        accumulator.limit_retained_content(4);
+        for content in ["abc ", "def ghi"] {
+            accumulator
+                .push_event(&format!(r#"{{"choices":[{{"delta":{{"content":"{content}"}}}}]}}"#))
+                .expect("chunk must parse");
+        }
+        let outcome = accumulator.finish().expect("outcome must build");
+        assert_eq!(outcome.chunks, vec!["abc ".to_string()]);
+        assert_eq!(outcome.output_tokens, 3);
+    }
+
+    #[test]
+    fn chat_stream_accumulator_returns_live_deltas_and_final_outcome() {
+        let mut accumulator = Box::<ChatStreamAccumulator>::default();
+        let first = accumulator
+            .push_event(r#"{"choices":[{"delta":{"content":"Hel","reasoning_content":"think"}}]}"#)
+            .expect("chunk must parse");
+        assert_eq!(first.content, vec!["Hel".to_string()]);
+        assert_eq!(first.reasoning.as_deref(), Some("think"));
+        accumulator
+            .push_event(
+                r#"{"choices":[{"delta":{"content":"lo"}}],"usage":{"completion_tokens":3}}"#,
+            )
+            .expect("chunk must parse");
+        assert!(accumulator.push_event("[DONE]").expect("done must parse").content.is_empty());
+
+        let outcome = accumulator.finish().expect("outcome must build");
+        assert_eq!(outcome.chunks.join(""), "Hello");
+        assert_eq!(outcome.output_tokens, 3);
+        assert_eq!(outcome.reasoning.as_deref(), Some("think"));
+    }
+
+    #[test]
+    fn think_splitter_routes_leading_block_to_reasoning_across_chunk_boundaries() {
+        let mut splitter = ThinkTagSplitter::default();
+        let mut content = String::new();
+        let mut reasoning = String::new();
+        for delta in ["<thi", "nk>plan ", "step</th", "ink>\n\n", "Answer", " <think>x"] {
+            let split = splitter.push(delta);
+            content.push_str(&split.content);
+            reasoning.push_str(&split.reasoning);
+        }
+        let tail = splitter.finish();
+        content.push_str(&tail.content);
+        reasoning.push_str(&tail.reasoning);
+        assert_eq!(reasoning, "plan step");
+        assert_eq!(content, "Answer <think>x");
+    }
+
+    #[test]
+    fn split_inline_think_leaves_plain_and_mid_text_tags_untouched() {
+        assert_eq!(split_inline_think("plain"), ("plain".to_string(), None));
+        assert_eq!(
+            split_inline_think("a <think>b</think>"),
+            ("a <think>b</think>".to_string(), None)
+        );
+        assert_eq!(
+            split_inline_think("<think>unterminated"),
+            (String::new(), Some("unterminated".to_string()))
+        );
+    }
+
+    #[test]
+    fn chat_sse_with_inline_think_block_surfaces_reasoning() {
+        let sse = concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"<think>weigh\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\" options</think>\\n\\nok\"}}]}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let outcome = map_chat_completion_stream_text(sse).expect("think SSE must parse");
+        assert_eq!(outcome.chunks.join(""), "ok");
+        assert_eq!(outcome.reasoning.as_deref(), Some("weigh options"));
+    }
+
+    #[test]
+    fn map_chat_completion_response_moves_inline_think_to_reasoning() {
+        let payload = ChatCompletionsResponse {
+            id: None,
+            choices: vec![Choice {
+                message: Message {
+                    content: Value::String("<think>hmm</think>answer".to_string()),
+                    reasoning: None,
+                    reasoning_content: None,
+                    reasoning_details: None,
+                    tool_calls: None,
+                },
+                finish_reason: None,
+            }],
+            usage: None,
+        };
+        let outcome = map_chat_completion_response(payload).expect("response must map");
+        assert_eq!(outcome.chunks, vec!["answer".to_string()]);
+        assert_eq!(outcome.reasoning.as_deref(), Some("hmm"));
+    }
+
     #[test]
     fn responses_sse_with_delta_only_is_not_empty() {
         let sse = concat!(
@@ -658,6 +1310,7 @@ mod tests {
     #[test]
     fn responses_message_text_skips_empty_parts_and_joins_non_empty() {
         let payload = ResponsesApiResponse {
+            id: None,
             output: vec![ResponsesApiOutputItem {
                 kind: "message".to_string(),
                 content: Some(vec![
@@ -669,8 +1322,11 @@ mod tests {
                 call_id: None,
                 name: None,
                 arguments: None,
+                encrypted_content: None,
             }],
-            usage: Some(ResponsesApiUsage { output_tokens: 2 }),
+            usage: Some(Usage { output_tokens: Some(2), ..Default::default() }),
+            status: None,
+            incomplete_details: None,
         };
         let outcome = map_responses_api_response(payload).expect("message text must be extracted");
         assert_eq!(outcome.chunks.join(""), "helloworld");
@@ -679,6 +1335,7 @@ mod tests {
     #[test]
     fn map_responses_api_response_extracts_deepseek_dsml_tool_call() {
         let payload = ResponsesApiResponse {
+            id: None,
             output: vec![ResponsesApiOutputItem {
                 kind: "message".to_string(),
                 content: Some(vec![json!({
@@ -689,8 +1346,11 @@ mod tests {
                 call_id: None,
                 name: None,
                 arguments: None,
+                encrypted_content: None,
             }],
-            usage: Some(ResponsesApiUsage { output_tokens: 2 }),
+            usage: Some(Usage { output_tokens: Some(2), ..Default::default() }),
+            status: None,
+            incomplete_details: None,
         };
 
         let outcome = map_responses_api_response(payload).expect("responses dsml must parse");
@@ -703,6 +1363,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn upstream_generation_id_is_kept_from_chat_and_responses_streams() {
+        let chat = concat!(
+            "data: {\"id\":\"gen-123\",\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\n",
+            "data: {\"id\":\"gen-123\",\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let outcome = map_chat_completion_stream_text(chat).expect("chat stream must parse");
+        assert_eq!(outcome.provider_response_id.as_deref(), Some("gen-123"));
+
+        let responses = "data: {\"type\":\"response.completed\",\"response\":{\"id\":\"resp_up_1\",\"status\":\"completed\",\"output\":[{\"type\":\"message\",\"content\":[{\"type\":\"output_text\",\"text\":\"ok\"}]}]}}\n\n";
+        let outcome = map_responses_stream_text(responses).expect("responses stream must parse");
+        assert_eq!(outcome.provider_response_id.as_deref(), Some("resp_up_1"));
+    }
+
     #[test]
     fn chat_sse_with_choice_level_tool_calls_is_not_empty() {
         let sse = concat!(
@@ -746,6 +1421,86 @@ mod tests {
             "{\"command\":\"find /workspace -type f | head -5\"}"
         );
     }
+
+    #[test]
+    fn chat_stream_captures_native_finish_reason() {
+        let sse = concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"partial\"},\"index\":0,\"finish_reason\":null}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"index\":0,\"finish_reason\":\"length\"}]}\n\n",
+            "data: [DONE]\n\n"
+        );
+
+        let outcome = map_chat_completion_stream_text(sse).expect("stream must parse");
+        assert_eq!(outcome.finish_reason.as_deref(), Some("length"));
+    }
+
+    #[test]
+    fn responses_incomplete_status_maps_to_length_or_content_filter() {
+        let sse = "data: {\"type\":\"response.incomplete\",\"response\":{\"output\":[{\"type\":\"message\",\"content\":[{\"type\":\"output_text\",\"text\":\"cut\"}]}],\"status\":\"incomplete\",\"incomplete_details\":{\"reason\":\"max_output_tokens\"}}}\n\n";
+        let outcome = map_responses_stream_text(sse).expect("stream must parse");
+        assert_eq!(outcome.finish_reason.as_deref(), Some("length"));
+
+        let sse = "data: {\"type\":\"response.incomplete\",\"response\":{\"output\":[],\"status\":\"incomplete\",\"incomplete_details\":{\"reason\":\"content_filter\"}}}\n\n";
+        let outcome = map_responses_stream_text(sse).expect("stream must parse");
+        assert_eq!(outcome.finish_reason.as_deref(), Some("content_filter"));
+    }
+
+    #[test]
+    fn chat_stream_captures_native_prompt_reasoning_and_cached_tokens() {
+        let sse = concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"ok\"},\"index\":0}]}\n\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":42,\"completion_tokens\":9,\"prompt_tokens_details\":{\"cached_tokens\":32},\"completion_tokens_details\":{\"reasoning_tokens\":5}}}\n\n",
+            "data: [DONE]\n\n"
+        );
+
+        let outcome = map_chat_completion_stream_text(sse).expect("stream must parse");
+        assert_eq!(outcome.output_tokens, 9);
+        assert_eq!(
+            outcome.usage,
+            Some(ProviderUsage {
+                prompt_tokens: Some(42),
+                reasoning_tokens: Some(5),
+                cached_tokens: Some(32),
+            })
+        );
+    }
+
+    #[test]
+    fn responses_stream_keeps_encrypted_reasoning_from_completed_output() {
+        let sse = "data: {\"type\":\"response.completed\",\"response\":{\"output\":[{\"type\":\"reasoning\",\"summary\":[{\"text\":\"thought\"}],\"encrypted_content\":\"gAAA-opaque\"},{\"type\":\"message\",\"content\":[{\"type\":\"output_text\",\"text\":\"ok\"}]}]}}\n\n";
+        let outcome = map_responses_stream_text(sse).expect("stream must parse");
+        assert_eq!(outcome.reasoning.as_deref(), Some("thought"));
+        assert_eq!(outcome.reasoning_encrypted_content.as_deref(), Some("gAAA-opaque"));
+    }
+
+    #[test]
+    fn responses_usage_and_deepseek_cache_hits_map_to_native_usage() {
+        let sse = "data: {\"type\":\"response.completed\",\"response\":{\"output\":[{\"type\":\"message\",\"content\":[{\"type\":\"output_text\",\"text\":\"ok\"}]}],\"usage\":{\"input_tokens\":11,\"output_tokens\":1,\"output_tokens_details\":{\"reasoning_tokens\":0}}}}\n\n";
+        let outcome = map_responses_stream_text(sse).expect("stream must parse");
+        assert_eq!(
+            outcome.usage,
+            Some(ProviderUsage {
+                prompt_tokens: Some(11),
+                reasoning_tokens: Some(0),
+                cached_tokens: None,
+            })
+        );
+
+        let usage = native_usage_from_value(Some(
+            &json!({"prompt_tokens":20,"completion_tokens":3,"prompt_cache_hit_tokens":16}),
+        ));
+        assert_eq!(usage.and_then(|usage| usage.cached_tokens), Some(16));
+        assert_eq!(native_usage_from_value(Some(&json!({"completion_tokens":3}))), None);
+    }
+
+    #[test]
+    fn normalize_finish_reason_unifies_provider_vocabularies() {
+        assert_eq!(normalize_finish_reason("max_tokens").as_deref(), Some("length"));
+        assert_eq!(normalize_finish_reason("blacklist").as_deref(), Some("content_filter"));
+        assert_eq!(normalize_finish_reason("function_call").as_deref(), Some("tool_calls"));
+        assert_eq!(normalize_finish_reason("STOP").as_deref(), Some("stop"));
+        assert_eq!(normalize_finish_reason("error"), None);
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -1005,3 +1760,14 @@ fn extract_reasoning_content_items_from_responses_output(
         item.content.clone().filter(|items| !items.is_empty())
     })
 }
+
+fn extract_reasoning_encrypted_content_from_responses_output(
+    output: &[ResponsesApiOutputItem],
+) -> Option<String> {
+    output.iter().find_map(|item| {
+        if item.kind != "reasoning" {
+            return None;
+        }
+        item.encrypted_content.clone().filter(|value| !value.is_empty())
+    })
+}
@@ -1,6 +1,7 @@
 use serde_json::{Map, Value, json};
 use xrouter_contracts::{
     ResponseInputContent, ResponseInputItem, ResponseToolOutput, ResponsesInput, ResponsesRequest,
+    TextControls, TextFormatConfig, TextFormatType,
 };
 
 pub fn base_chat_payload(
@@ -30,6 +31,117 @@ pub fn base_chat_payload(
     payload
 }
 
+/// Fields `compact_payload` never drops: some upstreams require message `content` even when it
+/// is null, and an empty `properties` or `required` means something inside a JSON Schema.
+const COMPACT_PRESERVED_KEYS: &[&str] = &["content", "parameters", "return_parameters", "schema"];
+
+/// Drops null fields and empty objects/arrays from a built upstream body and keeps one copy of
+/// each repeated tool definition, so long tool loops send less. Runs before passthrough fields
+/// are applied, which are forwarded as the caller sent them.
+pub fn compact_payload(payload: &mut Value) {
+    if let Some(payload) = payload.as_object_mut() {
+        for key in ["tools", "functions"] {
+            if let Some(Value::Array(tools)) = payload.get_mut(key) {
+                let mut seen = Vec::with_capacity(tools.len());
+                tools.retain(|tool| {
+                    let first = !seen.contains(tool);
+                    if first {
+                        seen.push(tool.clone());
+                    }
+                    first
+                });
+            }
+        }
+    }
+    compact_value(payload);
+}
+
+fn compact_value(value: &mut Value) {
+    match value {
+        Value::Object(fields) => fields.retain(|key, field| {
+            if COMPACT_PRESERVED_KEYS.contains(&key.as_str()) {
+                return true;
+            }
+            compact_value(field);
+            !(field.is_null()
+                || field.as_object().is_some_and(Map::is_empty)
+                || field.as_array().is_some_and(Vec::is_empty))
+        }),
+        Value::Array(items) => items.iter_mut().for_each(compact_value),
+        _ => {}
+    }
+}
+
+/// Adds caller-supplied provider-specific fields to `payload`; fields set by the router win.
+pub fn apply_passthrough_fields(payload: &mut Value, extra: Option<&Map<String, Value>>) {
+    let (Some(payload), Some(extra)) = (payload.as_object_mut(), extra) else {
+        return;
+    };
+    for (name, value) in extra {
+        payload.entry(name.clone()).or_insert_with(|| value.clone());
+    }
+}
+
+/// How much of `text.format` and `modalities` a chat completions upstream understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatOutputSupport {
+    /// `response_format` with JSON schemas, plus `modalities`.
+    Full,
+    /// `response_format: json_object` only; schemas degrade to it and modalities are not sent.
+    JsonObjectOnly,
+}
+
+/// Maps Responses-style `text.format` to `response_format` and forwards `modalities`.
+pub fn apply_chat_output_controls(
+    payload: &mut Value,
+    text: Option<&TextControls>,
+    modalities: Option<&[String]>,
+    support: ChatOutputSupport,
+) {
+    let Some(payload) = payload.as_object_mut() else {
+        return;
+    };
+    if let Some(response_format) = text
+        .and_then(|text| text.format.as_ref())
+        .and_then(|format| chat_response_format(format, support))
+    {
+        payload.insert("response_format".to_string(), response_format);
+    }
+    if support == ChatOutputSupport::Full
+        && let Some(modalities) = modalities.filter(|modalities| !modalities.is_empty())
+    {
+        payload.insert("modalities".to_string(), json!(modalities));
+    }
+}
+
+fn chat_response_format(format: &TextFormatConfig, support: ChatOutputSupport) -> Option<Value> {
+    match format.kind {
+        TextFormatType::Text => None,
+        TextFormatType::JsonObject => Some(json!({ "type": "json_object" })),
+        TextFormatType::JsonSchema if support == ChatOutputSupport::JsonObjectOnly => {
+            Some(json!({ "type": "json_object" }))
+        }
+        TextFormatType::JsonSchema => {
+            let mut json_schema = Map::new();
+            json_schema.insert(
+                "name".to_string(),
+                Value::String(format.name.clone().unwrap_or_else(|| "response".to_string())),
+            );
+            if let Some(description) = format.description.as_deref() {
+                json_schema
+                    .insert("description".to_string(), Value::String(description.to_string()));
+            }
+            if let Some(schema) = format.schema.clone() {
+                json_schema.insert("schema".to_string(), schema);
+            }
+            if let Some(strict) = format.strict {
+                json_schema.insert("strict".to_string(), Value::Bool(strict));
+            }
+            Some(json!({ "type": "json_schema", "json_schema": json_schema }))
+        }
+    }
+}
+
 pub fn build_chat_messages_from_responses_input(
     instructions: Option<&str>,
     input: &ResponsesInput,
@@ -161,10 +273,42 @@ fn map_response_input_item_to_chat_message(
     let role =
         item.role.as_deref().or_else(|| if kind == "message" { Some("user") } else { None })?;
     let normalized_role = if role == "developer" { "system" } else { role };
+    if let Some(parts) = image_content_parts(item) {
+        return Some(json!({ "role": normalized_role, "content": parts }));
+    }
     let content = extract_input_item_text(item)?;
     Some(json!({ "role": normalized_role, "content": content }))
 }
 
+/// Chat content parts for a message carrying images, so they reach vision models.
+fn image_content_parts(item: &ResponseInputItem) -> Option<Vec<Value>> {
+    let Some(ResponseInputContent::Parts(parts)) = item.content.as_ref() else {
+        return None;
+    };
+    if !parts.iter().any(|part| part.image_url.is_some()) {
+        return None;
+    }
+    let parts = parts
+        .iter()
+        .filter_map(|part| {
+            if let Some(url) = part.image_url.as_deref() {
+                let mut image_url = json!({ "url": url });
+                if let Some(detail) = part.detail.as_deref() {
+                    image_url["detail"] = Value::String(detail.to_string());
+                }
+                return Some(json!({ "type": "image_url", "image_url": image_url }));
+            }
+            part.input_text
+                .as_deref()
+                .or(part.text.as_deref())
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(|text| json!({ "type": "text", "text": text }))
+        })
+        .collect();
+    Some(parts)
+}
+
 fn extract_input_item_text(item: &ResponseInputItem) -> Option<String> {
     if let Some(text) = item.text.as_deref().map(str::trim).filter(|value| !value.is_empty()) {
         return Some(text.to_string());
@@ -188,11 +332,57 @@ fn extract_summary_text(summary: &[Value]) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::build_chat_messages_from_responses_input;
+    use super::{
+        ChatOutputSupport, apply_chat_output_controls, apply_passthrough_fields,
+        build_chat_messages_from_responses_input, compact_payload,
+    };
     use xrouter_contracts::{
         ResponseInputContent, ResponseInputItem, ResponseToolOutput, ResponsesInput,
     };
 
+    #[test]
+    fn compact_payload_drops_empty_fields_and_repeated_tools_but_keeps_schemas_and_content() {
+        let tool = serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": "now",
+                "description": null,
+                "parameters": {"type": "object", "properties": {}, "required": []}
+            }
+        });
+        let mut payload = serde_json::json!({
+            "model": "m",
+            "messages": [
+                {"role": "assistant", "content": null, "tool_calls": [{"id": "c1", "extra": {}}]},
+                {"role": "tool", "content": "", "name": null, "tool_call_id": "c1"}
+            ],
+            "tools": [tool.clone(), tool.clone()],
+            "tool_choice": null,
+            "stop": [],
+            "metadata": {"tags": [], "trace": {}}
+        });
+
+        compact_payload(&mut payload);
+
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "model": "m",
+                "messages": [
+                    {"role": "assistant", "content": null, "tool_calls": [{"id": "c1"}]},
+                    {"role": "tool", "content": "", "tool_call_id": "c1"}
+                ],
+                "tools": [{
+                    "type": "function",
+                    "function": {
+                        "name": "now",
+                        "parameters": {"type": "object", "properties": {}, "required": []}
+                    }
+                }]
+            })
+        );
+    }
+
     #[test]
     fn responses_input_items_map_to_chat_messages_with_tool_roundtrip() {
         let input = ResponsesInput::Items(vec![
@@ -254,6 +444,88 @@ mod tests {
         assert_eq!(parsed, serde_json::json!([{ "type": "input_text", "text": "line 1" }]));
     }
 
+    #[test]
+    fn text_format_maps_to_response_format_by_upstream_support() {
+        let text: xrouter_contracts::TextControls = serde_json::from_value(serde_json::json!({
+            "format": {"type": "json_schema", "name": "answer", "strict": true, "schema": {"type": "object"}}
+        }))
+        .expect("text controls must deserialize");
+        let modalities = ["text".to_string()];
+
+        let mut full = serde_json::json!({});
+        apply_chat_output_controls(
+            &mut full,
+            Some(&text),
+            Some(&modalities),
+            ChatOutputSupport::Full,
+        );
+        assert_eq!(
+            full["response_format"],
+            serde_json::json!({
+                "type": "json_schema",
+                "json_schema": {"name": "answer", "strict": true, "schema": {"type": "object"}}
+            })
+        );
+        assert_eq!(full["modalities"], serde_json::json!(["text"]));
+
+        let mut json_only = serde_json::json!({});
+        apply_chat_output_controls(
+            &mut json_only,
+            Some(&text),
+            Some(&modalities),
+            ChatOutputSupport::JsonObjectOnly,
+        );
+        assert_eq!(json_only, serde_json::json!({"response_format": {"type": "json_object"}}));
+
+        let plain: xrouter_contracts::TextControls =
+            serde_json::from_value(serde_json::json!({"format": {"type": "text"}}))
+                .expect("text controls must deserialize");
+        let mut untouched = serde_json::json!({});
+        apply_chat_output_controls(&mut untouched, Some(&plain), None, ChatOutputSupport::Full);
+        assert_eq!(untouched, serde_json::json!({}));
+    }
+
+    #[test]
+    fn passthrough_fields_are_added_without_overriding_router_fields() {
+        let mut payload = serde_json::json!({"model": "glm-4.5", "stream": true});
+        let extra = serde_json::json!({"top_k": 40, "stream": false});
+
+        apply_passthrough_fields(&mut payload, extra.as_object());
+
+        assert_eq!(payload, serde_json::json!({"model": "glm-4.5", "stream": true, "top_k": 40}));
+    }
+
+    #[test]
+    fn image_parts_are_forwarded_as_chat_content_parts() {
+        let input = ResponsesInput::Items(vec![ResponseInputItem {
+            kind: Some("message".to_string()),
+            role: Some("user".to_string()),
+            content: Some(ResponseInputContent::Parts(vec![
+                xrouter_contracts::ResponseInputPart {
+                    kind: Some("input_text".to_string()),
+                    text: Some("what is this?".to_string()),
+                    ..Default::default()
+                },
+                xrouter_contracts::ResponseInputPart {
+                    kind: Some("input_image".to_string()),
+                    image_url: Some("https://example.com/cat.png".to_string()),
+                    detail: Some("low".to_string()),
+                    ..Default::default()
+                },
+            ])),
+            ..Default::default()
+        }]);
+
+        let messages = build_chat_messages_from_responses_input(None, &input);
+        assert_eq!(
+            messages[0]["content"],
+            serde_json::json!([
+                {"type": "text", "text": "what is this?"},
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png", "detail": "low"}}
+            ])
+        );
+    }
+
     #[test]
     fn instructions_are_prepended_as_system_message() {
         let input = ResponsesInput::Items(vec![ResponseInputItem {
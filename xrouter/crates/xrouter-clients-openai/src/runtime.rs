@@ -2,10 +2,19 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use serde_json::Value;
-use xrouter_core::{CoreError, ProviderOutcome, ResponseEventSink};
+use xrouter_core::{CoreError, ProviderOutcome, ResponseEventSink, UpstreamLimits};
 
 pub type SharedProviderRuntime = Arc<dyn ProviderRuntime>;
 
+/// A file part of a `multipart/form-data` upload.
+#[derive(Debug, Clone)]
+pub struct MultipartFile {
+    pub field: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 pub trait ProviderRuntime: Send + Sync {
@@ -13,6 +22,7 @@ pub trait ProviderRuntime: Send + Sync {
 
     fn build_url(&self, path: &str) -> Result<String, CoreError>;
 
+    #[allow(clippy::too_many_arguments)]
     async fn post_chat_completions_stream(
         &self,
         request_id: &str,
@@ -20,9 +30,11 @@ pub trait ProviderRuntime: Send + Sync {
         payload: &Value,
         bearer_override: Option<&str>,
         extra_headers: &[(String, String)],
+        limits: UpstreamLimits,
         sender: Option<&dyn ResponseEventSink>,
     ) -> Result<ProviderOutcome, CoreError>;
 
+    #[allow(clippy::too_many_arguments)]
     async fn post_responses_stream(
         &self,
         request_id: &str,
@@ -30,6 +42,7 @@ pub trait ProviderRuntime: Send + Sync {
         payload: &Value,
         bearer_override: Option<&str>,
         extra_headers: &[(String, String)],
+        limits: UpstreamLimits,
         sender: Option<&dyn ResponseEventSink>,
     ) -> Result<ProviderOutcome, CoreError>;
 
@@ -39,4 +52,12 @@ pub trait ProviderRuntime: Send + Sync {
         form_fields: &[(String, String)],
         headers: &[(String, String)],
     ) -> Result<Value, CoreError>;
+
+    async fn post_multipart_json(
+        &self,
+        url: &str,
+        form_fields: &[(String, String)],
+        file: MultipartFile,
+        headers: &[(String, String)],
+    ) -> Result<Value, CoreError>;
 }
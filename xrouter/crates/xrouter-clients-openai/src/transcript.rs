@@ -0,0 +1,192 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Raw bytes kept per transcript; the rest of the stream is dropped and `truncated` is set.
+pub const TRANSCRIPT_MAX_BYTES: usize = 1024 * 1024;
+
+/// Raw upstream SSE body of one provider stream, as received on the wire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamTranscript {
+    pub id: String,
+    pub request_id: String,
+    pub provider: String,
+    pub stream_kind: String,
+    pub recorded_at_ms: u64,
+    /// False when the stream failed or was aborted before the upstream finished it.
+    pub complete: bool,
+    pub truncated: bool,
+    pub body: String,
+}
+
+/// Directory of JSON transcripts that keeps only the newest `max_entries` files.
+#[derive(Debug)]
+pub struct TranscriptStore {
+    dir: PathBuf,
+    max_entries: usize,
+    write_lock: Mutex<()>,
+}
+
+impl TranscriptStore {
+    pub fn open(dir: impl Into<PathBuf>, max_entries: usize) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, max_entries: max_entries.max(1), write_lock: Mutex::new(()) })
+    }
+
+    pub fn record(
+        &self,
+        request_id: &str,
+        provider: &str,
+        stream_kind: &str,
+        capture: TranscriptCapture,
+        complete: bool,
+    ) -> io::Result<String> {
+        let recorded_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+        let sanitized = request_id
+            .chars()
+            .filter(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+            .take(64)
+            .collect::<String>();
+        let id = format!("{recorded_at_ms:013}-{sanitized}");
+        let transcript = StreamTranscript {
+            id: id.clone(),
+            request_id: request_id.to_string(),
+            provider: provider.to_string(),
+            stream_kind: stream_kind.to_string(),
+            recorded_at_ms,
+            complete,
+            truncated: capture.truncated,
+            body: String::from_utf8_lossy(&capture.bytes).into_owned(),
+        };
+        let payload = serde_json::to_vec(&transcript).map_err(io::Error::other)?;
+
+        let _guard = self.write_lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        fs::write(self.dir.join(format!("{id}.json")), payload)?;
+        let ids = self.ids()?;
+        for stale in ids.iter().take(ids.len().saturating_sub(self.max_entries)) {
+            fs::remove_file(self.dir.join(format!("{stale}.json")))?;
+        }
+        Ok(id)
+    }
+
+    /// Stored transcripts, newest first, with `body` left empty.
+    pub fn list(&self) -> io::Result<Vec<StreamTranscript>> {
+        let mut entries = Vec::new();
+        for id in self.ids()?.into_iter().rev() {
+            if let Some(mut transcript) = self.get(&id)? {
+                transcript.body.clear();
+                entries.push(transcript);
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn get(&self, id: &str) -> io::Result<Option<StreamTranscript>> {
+        if id.is_empty()
+            || !id.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+        {
+            return Ok(None);
+        }
+        match fs::read(self.dir.join(format!("{id}.json"))) {
+            Ok(raw) => serde_json::from_slice(&raw).map(Some).map_err(io::Error::other),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn ids(&self) -> io::Result<Vec<String>> {
+        let mut ids = fs::read_dir(&self.dir)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                entry.file_name().to_str()?.strip_suffix(".json").map(ToString::to_string)
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+/// Bytes of one stream collected for a transcript, capped at `TRANSCRIPT_MAX_BYTES`.
+#[derive(Debug, Default)]
+pub struct TranscriptCapture {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+impl TranscriptCapture {
+    pub fn push(&mut self, chunk: &[u8]) {
+        let room = TRANSCRIPT_MAX_BYTES.saturating_sub(self.bytes.len());
+        if chunk.len() > room {
+            self.truncated = true;
+        }
+        self.bytes.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TRANSCRIPT_MAX_BYTES, TranscriptCapture, TranscriptStore};
+
+    fn capture(body: &str) -> TranscriptCapture {
+        let mut capture = TranscriptCapture::default();
+        capture.push(body.as_bytes());
+        capture
+    }
+
+    #[test]
+    fn store_keeps_only_the_newest_transcripts() {
+        let dir =
+            std::env::temp_dir().join(format!("xrouter-transcripts-{}", uuid::Uuid::new_v4()));
+        let store = TranscriptStore::open(&dir, 2).expect("store must open");
+
+        let mut ids = Vec::new();
+        for index in 0..3 {
+            let body = format!("data: {{\"n\":{index}}}\n\n");
+            ids.push(
+                store
+                    .record(
+                        &format!("req_{index}"),
+                        "zai",
+                        "chat_completions",
+                        capture(&body),
+                        true,
+                    )
+                    .expect("record must succeed"),
+            );
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let listed = store.list().expect("list must succeed");
+        assert_eq!(
+            listed.iter().map(|entry| entry.request_id.as_str()).collect::<Vec<_>>(),
+            vec!["req_2", "req_1"]
+        );
+        assert!(listed.iter().all(|entry| entry.body.is_empty()));
+        assert!(store.get(&ids[0]).expect("get must succeed").is_none());
+        let newest = store.get(&ids[2]).expect("get must succeed").expect("transcript must exist");
+        assert_eq!(newest.body, "data: {\"n\":2}\n\n");
+        assert!(newest.complete);
+        assert!(store.get("../secrets").expect("get must succeed").is_none());
+
+        std::fs::remove_dir_all(dir).expect("temp dir must be removed");
+    }
+
+    #[test]
+    fn capture_truncates_past_the_byte_cap() {
+        let mut capture = TranscriptCapture::default();
+        capture.push(&vec![b'a'; TRANSCRIPT_MAX_BYTES - 1]);
+        capture.push(b"bc");
+
+        assert!(capture.truncated);
+        assert_eq!(capture.bytes.len(), TRANSCRIPT_MAX_BYTES);
+    }
+}
@@ -2,24 +2,29 @@ use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use futures::StreamExt;
+#[cfg(feature = "otel")]
 use opentelemetry::{global, propagation::Injector, trace::Status};
 use reqwest::Client;
+#[cfg(feature = "otel")]
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use tokio::sync::Semaphore;
 use tokio::time::sleep;
-use tracing::{Instrument, debug, field, info_span, warn};
+use tracing::{Instrument, debug, field, info, info_span, warn};
+#[cfg(feature = "otel")]
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use xrouter_contracts::ResponseEvent;
-use xrouter_core::{CoreError, ProviderOutcome, ResponseEventSink};
+use xrouter_core::{CoreError, ProviderOutcome, ResponseEventSink, UpstreamLimits};
 
 use crate::parser::{
-    ChatCompletionsResponse, ResponsesApiResponse, drain_sse_frames, extract_chat_delta_chunks,
-    extract_chat_reasoning_delta, extract_responses_text_delta, map_chat_completion_response,
-    map_chat_completion_stream_text, map_responses_api_response, map_responses_stream_text,
+    ChatCompletionsResponse, ChatStreamAccumulator, ContentBuffer, ResponsesApiResponse,
+    ResponsesStreamAccumulator, SseDecoder, StreamAccumulator, StreamDelta, ThinkSplit,
+    ThinkTagSplitter, estimate_output_tokens, map_chat_completion_response,
+    map_responses_api_response,
 };
-use crate::runtime::ProviderRuntime;
+use crate::runtime::{MultipartFile, ProviderRuntime};
+use crate::transcript::{TranscriptCapture, TranscriptStore};
 
 const STREAM_DEBUG_SAMPLE_EVERY: usize = 25;
 const STREAM_DEBUG_PREVIEW_LIMIT: usize = 120;
@@ -37,6 +42,12 @@ pub fn build_http_client_insecure_tls(timeout_seconds: u64) -> Option<Client> {
         .ok()
 }
 
+/// Builds the accumulator a provider reads one upstream event stream with.
+pub(crate) type StreamAccumulatorFactory = fn() -> Box<dyn StreamAccumulator>;
+
+/// Maps a provider's non-streamed JSON answer on a chat completions stream request.
+pub(crate) type JsonResponseMapper = fn(&Value) -> Result<ProviderOutcome, CoreError>;
+
 #[derive(Clone)]
 pub(crate) struct HttpRuntime {
     provider_id: String,
@@ -44,6 +55,9 @@ pub(crate) struct HttpRuntime {
     api_key: Option<String>,
     http_client: Option<Client>,
     max_inflight: Option<Arc<Semaphore>>,
+    transcripts: Option<Arc<TranscriptStore>>,
+    stream_accumulator: Option<StreamAccumulatorFactory>,
+    json_response: Option<JsonResponseMapper>,
 }
 
 impl HttpRuntime {
@@ -53,9 +67,39 @@ impl HttpRuntime {
         api_key: Option<String>,
         http_client: Option<Client>,
         max_inflight: Option<usize>,
+        transcripts: Option<Arc<TranscriptStore>>,
     ) -> Self {
         let max_inflight = max_inflight.map(Semaphore::new).map(Arc::new);
-        Self { provider_id, base_url, api_key, http_client, max_inflight }
+        Self {
+            provider_id,
+            base_url,
+            api_key,
+            http_client,
+            max_inflight,
+            transcripts,
+            stream_accumulator: None,
+            json_response: None,
+        }
+    }
+
+    /// Reads event streams with the provider's own accumulator instead of the generic chat
+    /// completions or Responses one.
+    pub(crate) fn with_stream_accumulator(mut self, factory: StreamAccumulatorFactory) -> Self {
+        self.stream_accumulator = Some(factory);
+        self
+    }
+
+    /// Maps JSON answers to chat completions stream requests with the provider's own mapper.
+    pub(crate) fn with_json_response(mut self, mapper: JsonResponseMapper) -> Self {
+        self.json_response = Some(mapper);
+        self
+    }
+
+    fn stream_accumulator<A>(&self) -> Box<dyn StreamAccumulator>
+    where
+        A: StreamAccumulator + Default + 'static,
+    {
+        self.stream_accumulator.map_or_else(|| Box::<A>::default() as _, |factory| factory())
     }
 
     pub(crate) fn api_key_ref(&self) -> Option<&str> {
@@ -103,9 +147,10 @@ impl HttpRuntime {
         payload: &Value,
         bearer_override: Option<&str>,
         extra_headers: &[(String, String)],
-    ) -> Result<reqwest::Response, CoreError> {
+        limits: UpstreamLimits,
+    ) -> Result<(reqwest::Response, u32), CoreError> {
         let _permit = self.acquire_inflight_permit()?;
-        for attempt in 1..=2 {
+        for attempt in 1..=limits.max_retries.unwrap_or(1).saturating_add(1) {
             let client = self.client()?;
             let http_span = info_span!(
                 "provider_http_request",
@@ -131,24 +176,46 @@ impl HttpRuntime {
                 for (name, value) in extra_headers {
                     request = request.header(name, value);
                 }
-                request
-                    .send()
-                    .await
-                    .map_err(|err| CoreError::Provider(format!("provider request failed: {err}")))
+                if let Some(timeout) = limits.timeout {
+                    request = request.timeout(timeout);
+                }
+                request.send().await
             }
             .instrument(http_span.clone())
             .await;
             let response = match response {
                 Ok(response) => response,
-                Err(error) => {
-                    http_span.set_status(Status::error(error.to_string()));
+                Err(err)
+                    if (err.is_connect() || err.is_timeout())
+                        && limits.max_retries.is_some_and(|retries| attempt <= retries) =>
+                {
+                    warn!(
+                        event = "provider.request.retrying",
+                        provider = %self.provider_id,
+                        url = url,
+                        error = %err,
+                        attempt = attempt,
+                        next_attempt = attempt + 1,
+                    );
+                    sleep(Duration::from_millis(300)).await;
+                    continue;
+                }
+                Err(err) => {
+                    let error = match limits.timeout.filter(|_| err.is_timeout()) {
+                        Some(timeout) => CoreError::Provider(format!(
+                            "provider request timed out after {}ms",
+                            timeout.as_millis()
+                        )),
+                        None => CoreError::Provider(format!("provider request failed: {err}")),
+                    };
+                    mark_span_error(&http_span, error.to_string());
                     return Err(error);
                 }
             };
             let status = response.status();
             http_span.record("http.response.status_code", status.as_u16());
             if status.is_success() {
-                return Ok(response);
+                return Ok((response, attempt));
             }
 
             let body = response.text().await.unwrap_or_default();
@@ -156,7 +223,13 @@ impl HttpRuntime {
                 body.replace('\n', "\\n").replace('\r', "\\r").as_str(),
                 UPSTREAM_ERROR_BODY_PREVIEW_LIMIT,
             );
-            let retryable = should_retry_failed_status(&self.provider_id, status, &body, attempt);
+            let retryable = should_retry_failed_status(
+                &self.provider_id,
+                status,
+                &body,
+                attempt,
+                limits.max_retries,
+            );
             warn!(
                 event = "provider.request.failed_status",
                 provider = %self.provider_id,
@@ -189,9 +262,10 @@ impl HttpRuntime {
             }
 
             let reason = status.canonical_reason().unwrap_or("Unknown");
-            http_span.set_status(Status::error(format!(
-                "provider returned error status: {status} ({reason})"
-            )));
+            mark_span_error(
+                &http_span,
+                format!("provider returned error status: {status} ({reason})"),
+            );
             return Err(CoreError::Provider(format!(
                 "provider returned error status: {status} ({reason}) for url ({url})"
             )));
@@ -202,6 +276,7 @@ impl HttpRuntime {
         )))
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn post_chat_completions_stream(
         &self,
         request_id: &str,
@@ -209,6 +284,7 @@ impl HttpRuntime {
         payload: &Value,
         bearer_override: Option<&str>,
         extra_headers: &[(String, String)],
+        limits: UpstreamLimits,
         sender: Option<&dyn ResponseEventSink>,
     ) -> Result<ProviderOutcome, CoreError> {
         let request_span = info_span!(
@@ -221,8 +297,8 @@ impl HttpRuntime {
             request_id = request_id,
             stream_kind = "chat_completions"
         );
-        let response = self
-            .send_post(request_id, url, payload, bearer_override, extra_headers)
+        let (response, attempts) = self
+            .send_post(request_id, url, payload, bearer_override, extra_headers, limits)
             .instrument(request_span)
             .await?;
         let is_json = response
@@ -230,145 +306,29 @@ impl HttpRuntime {
             .get(reqwest::header::CONTENT_TYPE)
             .and_then(|value| value.to_str().ok())
             .is_some_and(|value| value.contains("application/json"));
+        let upstream_provider = upstream_provider(&self.provider_id, response.headers());
 
-        if is_json {
-            if self.provider_id == "gigachat" {
-                let payload = response.json::<Value>().await.map_err(|err| {
-                    CoreError::Provider(format!("provider response parse failed: {err}"))
-                })?;
-                return crate::clients::gigachat::map_gigachat_chat_completion_response_value(
-                    &payload,
-                );
-            }
-            let payload = response.json::<ChatCompletionsResponse>().await.map_err(|err| {
+        let mut outcome = if let (true, Some(mapper)) = (is_json, self.json_response) {
+            let payload = response.json::<Value>().await.map_err(|err| {
                 CoreError::Provider(format!("provider response parse failed: {err}"))
             })?;
-            return map_chat_completion_response(payload);
-        }
-
-        let mut all_chunks = Vec::<String>::new();
-        let mut parse_buffer = String::new();
-        let mut full_body = String::new();
-        let mut stream = response.bytes_stream();
-        let mut transport_chunk_index = 0usize;
-        let mut delta_count = 0usize;
-        while let Some(next) = stream.next().await {
-            let bytes = next.map_err(|err| {
-                CoreError::Provider(format!("provider stream read failed: {err}"))
+            mapper(&payload)?
+        } else if is_json {
+            let payload = response.json::<ChatCompletionsResponse>().await.map_err(|err| {
+                CoreError::Provider(format!("provider response parse failed: {err}"))
             })?;
-            transport_chunk_index += 1;
-            let chunk = String::from_utf8_lossy(&bytes).replace('\r', "");
-            if should_log_stream_chunk_debug(transport_chunk_index) {
-                debug!(
-                    event = "provider.stream.chunk.received",
-                    provider = %self.provider_id,
-                    request_id = request_id,
-                    stream_kind = "chat_completions",
-                    chunk_index = transport_chunk_index,
-                    chunk_bytes = bytes.len(),
-                    chunk_preview = %truncate_for_debug(&chunk, STREAM_DEBUG_PREVIEW_LIMIT)
-                );
-            }
-            parse_buffer.push_str(&chunk);
-            full_body.push_str(&chunk);
-            for frame in drain_sse_frames(&mut parse_buffer, false) {
-                for delta in extract_chat_delta_chunks(&frame, request_id)? {
-                    delta_count += 1;
-                    if should_log_stream_chunk_debug(delta_count) {
-                        debug!(
-                            event = "provider.stream.delta.received",
-                            provider = %self.provider_id,
-                            request_id = request_id,
-                            stream_kind = "chat_completions",
-                            delta_index = delta_count,
-                            delta_chars = delta.chars().count(),
-                            delta_preview = %truncate_for_debug(&delta, STREAM_DEBUG_PREVIEW_LIMIT)
-                        );
-                    }
-                    if let Some(tx) = sender {
-                        tx.send(Ok(ResponseEvent::OutputTextDelta {
-                            id: request_id.to_string(),
-                            delta: delta.clone(),
-                        }))
-                        .await;
-                    }
-                    all_chunks.push(delta);
-                }
-                if let Some(reasoning_delta) = extract_chat_reasoning_delta(&frame, request_id)?
-                    && let Some(tx) = sender
-                {
-                    tx.send(Ok(ResponseEvent::ReasoningDelta {
-                        id: request_id.to_string(),
-                        delta: reasoning_delta,
-                    }))
-                    .await;
-                }
-            }
-        }
-        for frame in drain_sse_frames(&mut parse_buffer, true) {
-            for delta in extract_chat_delta_chunks(&frame, request_id)? {
-                delta_count += 1;
-                if should_log_stream_chunk_debug(delta_count) {
-                    debug!(
-                        event = "provider.stream.delta.received",
-                        provider = %self.provider_id,
-                        request_id = request_id,
-                        stream_kind = "chat_completions",
-                        delta_index = delta_count,
-                        delta_chars = delta.chars().count(),
-                        delta_preview = %truncate_for_debug(&delta, STREAM_DEBUG_PREVIEW_LIMIT)
-                    );
-                }
-                if let Some(tx) = sender {
-                    tx.send(Ok(ResponseEvent::OutputTextDelta {
-                        id: request_id.to_string(),
-                        delta: delta.clone(),
-                    }))
-                    .await;
-                }
-                all_chunks.push(delta);
-            }
-            if let Some(reasoning_delta) = extract_chat_reasoning_delta(&frame, request_id)?
-                && let Some(tx) = sender
-            {
-                tx.send(Ok(ResponseEvent::ReasoningDelta {
-                    id: request_id.to_string(),
-                    delta: reasoning_delta,
-                }))
-                .await;
-            }
-        }
-        let mut outcome = match if self.provider_id == "gigachat" {
-            crate::clients::gigachat::map_gigachat_chat_completion_stream_text(&full_body)
+            map_chat_completion_response(payload)?
         } else {
-            map_chat_completion_stream_text(&full_body)
-        } {
-            Ok(parsed) => parsed,
-            Err(error) => {
-                if all_chunks.is_empty() {
-                    return Err(error);
-                }
-                let output_tokens = all_chunks
-                    .iter()
-                    .map(|chunk| chunk.split_whitespace().count() as u32)
-                    .sum::<u32>();
-                ProviderOutcome {
-                    chunks: all_chunks.clone(),
-                    output_tokens,
-                    reasoning: None,
-                    reasoning_details: None,
-                    tool_calls: None,
-                    emitted_live: false,
-                }
-            }
+            let accumulator = self.stream_accumulator::<ChatStreamAccumulator>();
+            self.consume_event_stream(request_id, "chat_completions", response, accumulator, sender)
+                .await?
         };
-        if !all_chunks.is_empty() {
-            outcome.chunks = all_chunks;
-        }
-        outcome.emitted_live = sender.is_some();
+        outcome.upstream_attempts = attempts;
+        outcome.upstream_provider = upstream_provider;
         Ok(outcome)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn post_responses_stream(
         &self,
         request_id: &str,
@@ -376,6 +336,7 @@ impl HttpRuntime {
         payload: &Value,
         bearer_override: Option<&str>,
         extra_headers: &[(String, String)],
+        limits: UpstreamLimits,
         sender: Option<&dyn ResponseEventSink>,
     ) -> Result<ProviderOutcome, CoreError> {
         let request_span = info_span!(
@@ -388,8 +349,8 @@ impl HttpRuntime {
             request_id = request_id,
             stream_kind = "responses"
         );
-        let response = self
-            .send_post(request_id, url, payload, bearer_override, extra_headers)
+        let (response, attempts) = self
+            .send_post(request_id, url, payload, bearer_override, extra_headers, limits)
             .instrument(request_span)
             .await?;
         let is_json = response
@@ -398,123 +359,231 @@ impl HttpRuntime {
             .and_then(|value| value.to_str().ok())
             .is_some_and(|value| value.contains("application/json"));
 
-        if is_json {
+        let mut outcome = if is_json {
             let payload = response.json::<ResponsesApiResponse>().await.map_err(|err| {
                 CoreError::Provider(format!("provider response parse failed: {err}"))
             })?;
-            return map_responses_api_response(payload);
-        }
+            map_responses_api_response(payload)?
+        } else {
+            let accumulator = self.stream_accumulator::<ResponsesStreamAccumulator>();
+            self.consume_event_stream(request_id, "responses", response, accumulator, sender)
+                .await?
+        };
+        outcome.upstream_attempts = attempts;
+        Ok(outcome)
+    }
 
-        let mut all_chunks = Vec::<String>::new();
-        let mut parse_buffer = String::new();
-        let mut full_body = String::new();
-        let is_yandex_provider = self.provider_id == "yandex";
+    async fn consume_event_stream(
+        &self,
+        request_id: &str,
+        stream_kind: &'static str,
+        response: reqwest::Response,
+        accumulator: Box<dyn StreamAccumulator>,
+        sender: Option<&dyn ResponseEventSink>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let Some(store) = self.transcripts.clone() else {
+            return self
+                .read_event_stream(request_id, stream_kind, response, accumulator, sender, None)
+                .await;
+        };
+        let mut capture = TranscriptCapture::default();
+        let result = self
+            .read_event_stream(
+                request_id,
+                stream_kind,
+                response,
+                accumulator,
+                sender,
+                Some(&mut capture),
+            )
+            .await;
+        let complete = result.is_ok() && !sender.is_some_and(|tx| tx.output_closed());
+        let (request_id, provider) = (request_id.to_string(), self.provider_id.clone());
+        tokio::task::spawn_blocking(move || {
+            match store.record(&request_id, &provider, stream_kind, capture, complete) {
+                Ok(id) => info!(
+                    event = "provider.stream.transcript_recorded",
+                    provider = %provider,
+                    request_id = %request_id,
+                    stream_kind = stream_kind,
+                    transcript_id = %id,
+                    complete = complete
+                ),
+                Err(error) => warn!(
+                    event = "provider.stream.transcript_failed",
+                    provider = %provider,
+                    request_id = %request_id,
+                    error = %error
+                ),
+            }
+        });
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn read_event_stream(
+        &self,
+        request_id: &str,
+        stream_kind: &'static str,
+        response: reqwest::Response,
+        mut accumulator: Box<dyn StreamAccumulator>,
+        sender: Option<&dyn ResponseEventSink>,
+        mut capture: Option<&mut TranscriptCapture>,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let mut live_content = ContentBuffer::default();
+        if let Some(max_bytes) = sender.and_then(|tx| tx.retained_output_limit()) {
+            accumulator.limit_retained_content(max_bytes);
+            live_content.set_limit(max_bytes);
+        }
+        let mut think_splitter = ThinkTagSplitter::default();
+        let mut decoder = SseDecoder::default();
         let mut stream = response.bytes_stream();
         let mut transport_chunk_index = 0usize;
         let mut delta_count = 0usize;
+        let mut stream_aborted = false;
         while let Some(next) = stream.next().await {
             let bytes = next.map_err(|err| {
                 CoreError::Provider(format!("provider stream read failed: {err}"))
             })?;
             transport_chunk_index += 1;
-            let chunk = String::from_utf8_lossy(&bytes).replace('\r', "");
+            if let Some(capture) = capture.as_deref_mut() {
+                capture.push(&bytes);
+            }
             if should_log_stream_chunk_debug(transport_chunk_index) {
                 debug!(
                     event = "provider.stream.chunk.received",
                     provider = %self.provider_id,
                     request_id = request_id,
-                    stream_kind = "responses",
+                    stream_kind = stream_kind,
                     chunk_index = transport_chunk_index,
                     chunk_bytes = bytes.len(),
-                    chunk_preview = %truncate_for_debug(&chunk, STREAM_DEBUG_PREVIEW_LIMIT)
+                    chunk_preview = %truncate_for_debug(
+                        &String::from_utf8_lossy(&bytes),
+                        STREAM_DEBUG_PREVIEW_LIMIT
+                    )
                 );
             }
-            parse_buffer.push_str(&chunk);
-            full_body.push_str(&chunk);
-            for frame in drain_sse_frames(&mut parse_buffer, false) {
-                if let Some(delta) = extract_responses_text_delta(&frame)? {
-                    delta_count += 1;
-                    if should_log_stream_chunk_debug(delta_count) {
-                        debug!(
-                            event = "provider.stream.delta.received",
-                            provider = %self.provider_id,
-                            request_id = request_id,
-                            stream_kind = "responses",
-                            delta_index = delta_count,
-                            delta_chars = delta.chars().count(),
-                            delta_preview = %truncate_for_debug(&delta, STREAM_DEBUG_PREVIEW_LIMIT)
-                        );
-                    }
-                    if let Some(tx) = sender
-                        && !is_yandex_provider
-                    {
-                        tx.send(Ok(ResponseEvent::OutputTextDelta {
-                            id: request_id.to_string(),
-                            delta: delta.clone(),
-                        }))
-                        .await;
-                    }
-                    all_chunks.push(delta);
-                }
+            for data in decoder.push(&bytes) {
+                let delta = accumulator.push_event(&data)?;
+                self.forward_stream_delta(
+                    request_id,
+                    stream_kind,
+                    delta,
+                    &mut delta_count,
+                    sender,
+                    &mut think_splitter,
+                    &mut live_content,
+                )
+                .await;
             }
-        }
-        for frame in drain_sse_frames(&mut parse_buffer, true) {
-            if let Some(delta) = extract_responses_text_delta(&frame)? {
-                delta_count += 1;
-                if should_log_stream_chunk_debug(delta_count) {
-                    debug!(
-                        event = "provider.stream.delta.received",
-                        provider = %self.provider_id,
-                        request_id = request_id,
-                        stream_kind = "responses",
-                        delta_index = delta_count,
-                        delta_chars = delta.chars().count(),
-                        delta_preview = %truncate_for_debug(&delta, STREAM_DEBUG_PREVIEW_LIMIT)
-                    );
-                }
-                if let Some(tx) = sender
-                    && !is_yandex_provider
-                {
-                    tx.send(Ok(ResponseEvent::OutputTextDelta {
-                        id: request_id.to_string(),
-                        delta: delta.clone(),
-                    }))
-                    .await;
-                }
-                all_chunks.push(delta);
+            if sender.is_some_and(|tx| tx.output_closed()) {
+                stream_aborted = true;
+                info!(
+                    event = "provider.stream.aborted",
+                    provider = %self.provider_id,
+                    request_id = request_id,
+                    stream_kind = stream_kind,
+                    reason = "output_closed",
+                    chunk_index = transport_chunk_index,
+                    discarded_bytes = decoder.pending_bytes()
+                );
+                break;
             }
         }
-        let mut outcome = match if self.provider_id == "yandex" {
-            crate::clients::yandex::map_yandex_responses_stream_text(&full_body)
-        } else {
-            map_responses_stream_text(&full_body)
-        } {
+        if !stream_aborted && let Some(data) = decoder.finish() {
+            let delta = accumulator.push_event(&data)?;
+            self.forward_stream_delta(
+                request_id,
+                stream_kind,
+                delta,
+                &mut delta_count,
+                sender,
+                &mut think_splitter,
+                &mut live_content,
+            )
+            .await;
+        }
+        forward_split_delta(sender, request_id, think_splitter.finish(), &mut live_content).await;
+        if live_content.dropped_bytes() > 0 {
+            warn!(
+                event = "provider.stream.output_retention_capped",
+                provider = %self.provider_id,
+                request_id = request_id,
+                stream_kind = stream_kind,
+                retained_bytes = live_content.retained_bytes(),
+                dropped_bytes = live_content.dropped_bytes()
+            );
+        }
+        let mut outcome = match accumulator.finish() {
             Ok(parsed) => parsed,
             Err(error) => {
-                if all_chunks.is_empty() {
+                if live_content.is_empty() {
                     return Err(error);
                 }
-                let output_tokens = all_chunks
-                    .iter()
-                    .map(|chunk| chunk.split_whitespace().count() as u32)
-                    .sum::<u32>();
+                let output_tokens =
+                    estimate_output_tokens(live_content.as_str(), live_content.dropped_words());
                 ProviderOutcome {
-                    chunks: all_chunks.clone(),
+                    chunks: vec![std::mem::take(&mut live_content).into_text()],
                     output_tokens,
                     reasoning: None,
                     reasoning_details: None,
+                    reasoning_encrypted_content: None,
                     tool_calls: None,
+                    annotations: None,
                     emitted_live: false,
+                    finish_reason: None,
+                    usage: None,
+                    upstream_attempts: 1,
+                    provider_response_id: None,
+                    upstream_provider: None,
                 }
             }
         };
-        if !all_chunks.is_empty() && !is_yandex_provider {
-            outcome.chunks = all_chunks;
+        if !live_content.is_empty() {
+            outcome.chunks = vec![live_content.into_text()];
         }
         outcome.emitted_live = sender.is_some();
         Ok(outcome)
     }
 
+    #[allow(clippy::too_many_arguments)]
+    async fn forward_stream_delta(
+        &self,
+        request_id: &str,
+        stream_kind: &'static str,
+        delta: StreamDelta,
+        delta_count: &mut usize,
+        sender: Option<&dyn ResponseEventSink>,
+        think_splitter: &mut ThinkTagSplitter,
+        live_content: &mut ContentBuffer,
+    ) {
+        for content in delta.content {
+            *delta_count += 1;
+            if should_log_stream_chunk_debug(*delta_count) {
+                debug!(
+                    event = "provider.stream.delta.received",
+                    provider = %self.provider_id,
+                    request_id = request_id,
+                    stream_kind = stream_kind,
+                    delta_index = *delta_count,
+                    delta_chars = content.chars().count(),
+                    delta_preview = %truncate_for_debug(&content, STREAM_DEBUG_PREVIEW_LIMIT)
+                );
+            }
+            forward_split_delta(sender, request_id, think_splitter.push(&content), live_content)
+                .await;
+        }
+        if let Some(reasoning) = delta.reasoning
+            && let Some(tx) = sender
+        {
+            tx.send(Ok(ResponseEvent::ReasoningDelta {
+                id: request_id.to_string(),
+                delta: reasoning,
+            }))
+            .await;
+        }
+    }
+
     async fn post_form<T: DeserializeOwned>(
         &self,
         url: &str,
@@ -537,6 +606,38 @@ impl HttpRuntime {
             .await
             .map_err(|err| CoreError::Provider(format!("provider response parse failed: {err}")))
     }
+
+    async fn post_multipart<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        form_fields: &[(String, String)],
+        file: MultipartFile,
+        headers: &[(String, String)],
+    ) -> Result<T, CoreError> {
+        let client = self.client()?;
+        let part = reqwest::multipart::Part::bytes(file.bytes)
+            .file_name(file.file_name)
+            .mime_str(&file.content_type)
+            .map_err(|err| CoreError::Provider(format!("provider upload is invalid: {err}")))?;
+        let mut form = reqwest::multipart::Form::new().part(file.field, part);
+        for (name, value) in form_fields {
+            form = form.text(name.clone(), value.clone());
+        }
+        let mut request = client.post(url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        request
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|err| CoreError::Provider(format!("provider request failed: {err}")))?
+            .error_for_status()
+            .map_err(|err| CoreError::Provider(format!("provider returned error status: {err}")))?
+            .json::<T>()
+            .await
+            .map_err(|err| CoreError::Provider(format!("provider response parse failed: {err}")))
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -557,6 +658,7 @@ impl ProviderRuntime for HttpRuntime {
         payload: &Value,
         bearer_override: Option<&str>,
         extra_headers: &[(String, String)],
+        limits: UpstreamLimits,
         sender: Option<&dyn ResponseEventSink>,
     ) -> Result<ProviderOutcome, CoreError> {
         HttpRuntime::post_chat_completions_stream(
@@ -566,6 +668,7 @@ impl ProviderRuntime for HttpRuntime {
             payload,
             bearer_override,
             extra_headers,
+            limits,
             sender,
         )
         .await
@@ -578,6 +681,7 @@ impl ProviderRuntime for HttpRuntime {
         payload: &Value,
         bearer_override: Option<&str>,
         extra_headers: &[(String, String)],
+        limits: UpstreamLimits,
         sender: Option<&dyn ResponseEventSink>,
     ) -> Result<ProviderOutcome, CoreError> {
         HttpRuntime::post_responses_stream(
@@ -587,6 +691,7 @@ impl ProviderRuntime for HttpRuntime {
             payload,
             bearer_override,
             extra_headers,
+            limits,
             sender,
         )
         .await
@@ -600,10 +705,22 @@ impl ProviderRuntime for HttpRuntime {
     ) -> Result<Value, CoreError> {
         self.post_form::<Value>(url, form_fields, headers).await
     }
+
+    async fn post_multipart_json(
+        &self,
+        url: &str,
+        form_fields: &[(String, String)],
+        file: MultipartFile,
+        headers: &[(String, String)],
+    ) -> Result<Value, CoreError> {
+        self.post_multipart::<Value>(url, form_fields, file, headers).await
+    }
 }
 
+#[cfg(feature = "otel")]
 struct HeaderMapInjector<'a>(&'a mut HeaderMap);
 
+#[cfg(feature = "otel")]
 impl<'a> Injector for HeaderMapInjector<'a> {
     fn set(&mut self, key: &str, value: String) {
         if let (Ok(name), Ok(value)) =
@@ -614,6 +731,35 @@ impl<'a> Injector for HeaderMapInjector<'a> {
     }
 }
 
+async fn forward_split_delta(
+    sender: Option<&dyn ResponseEventSink>,
+    request_id: &str,
+    split: ThinkSplit,
+    live_content: &mut ContentBuffer,
+) {
+    if let Some(tx) = sender
+        && !split.reasoning.is_empty()
+    {
+        tx.send(Ok(ResponseEvent::ReasoningDelta {
+            id: request_id.to_string(),
+            delta: split.reasoning,
+        }))
+        .await;
+    }
+    if split.content.is_empty() {
+        return;
+    }
+    if let Some(tx) = sender {
+        tx.send(Ok(ResponseEvent::OutputTextDelta {
+            id: request_id.to_string(),
+            delta: split.content.clone(),
+        }))
+        .await;
+    }
+    live_content.push(&split.content);
+}
+
+#[cfg(feature = "otel")]
 pub(crate) fn inject_trace_headers(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
     let mut headers = HeaderMap::new();
     global::get_text_map_propagator(|propagator| {
@@ -625,6 +771,19 @@ pub(crate) fn inject_trace_headers(request: reqwest::RequestBuilder) -> reqwest:
     request.headers(headers)
 }
 
+#[cfg(not(feature = "otel"))]
+pub(crate) fn inject_trace_headers(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    request
+}
+
+#[cfg(feature = "otel")]
+fn mark_span_error(span: &tracing::Span, message: String) {
+    span.set_status(Status::error(message));
+}
+
+#[cfg(not(feature = "otel"))]
+fn mark_span_error(_span: &tracing::Span, _message: String) {}
+
 fn should_log_stream_chunk_debug(index: usize) -> bool {
     index <= 3 || index.is_multiple_of(STREAM_DEBUG_SAMPLE_EVERY)
 }
@@ -685,63 +844,71 @@ pub(crate) fn should_retry_failed_status(
     provider_id: &str,
     status: reqwest::StatusCode,
     body: &str,
-    attempt: usize,
+    attempt: u32,
+    max_retries: Option<u32>,
 ) -> bool {
-    if attempt >= 2 {
-        return false;
-    }
-    provider_id == "zai"
+    let zai_transient = provider_id == "zai"
         && status.is_server_error()
-        && body.to_ascii_lowercase().contains("operation failed")
+        && body.to_ascii_lowercase().contains("operation failed");
+    match max_retries {
+        None => attempt < 2 && zai_transient,
+        Some(max_retries) => {
+            attempt <= max_retries
+                && (zai_transient || matches!(status.as_u16(), 429 | 502 | 503 | 504))
+        }
+    }
+}
+
+/// Provider a downstream xrouter reports to have served the request; other upstreams are not
+/// trusted to name one.
+fn upstream_provider(provider_id: &str, headers: &reqwest::header::HeaderMap) -> Option<String> {
+    if provider_id != "xrouter" {
+        return None;
+    }
+    headers.get("x-xrouter-provider").and_then(|value| value.to_str().ok()).map(str::to_string)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{inject_trace_headers, should_retry_failed_status};
-    use opentelemetry::{
-        global,
-        propagation::{Extractor, TextMapPropagator},
-        trace::{TraceContextExt, TracerProvider},
-    };
-    use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider};
-    use tracing::trace_span;
-    use tracing_opentelemetry::OpenTelemetrySpanExt;
-    use tracing_subscriber::layer::SubscriberExt;
-    use tracing_subscriber::util::SubscriberInitExt;
+    use xrouter_core::CoreError;
+
+    use super::{HttpRuntime, should_retry_failed_status, upstream_provider};
+    use crate::parser::{ChatStreamAccumulator, StreamAccumulator, StreamDelta};
+
+    #[derive(Default)]
+    struct ProviderAccumulator;
+
+    impl StreamAccumulator for ProviderAccumulator {
+        fn push_event(&mut self, _data: &str) -> Result<StreamDelta, CoreError> {
+            Ok(StreamDelta { content: vec!["provider".to_string()], reasoning: None })
+        }
+
+        fn finish(self: Box<Self>) -> Result<xrouter_core::ProviderOutcome, CoreError> {
+            Err(CoreError::Provider("unused".to_string()))
+        }
+    }
 
     #[test]
-    fn inject_trace_headers_uses_current_span_context() {
-        global::set_text_map_propagator(TraceContextPropagator::new());
-        let provider = SdkTracerProvider::builder().build();
-        let tracer = provider.tracer("test-tracer");
-        let subscriber =
-            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
-        let _guard = subscriber.set_default();
-
-        let span = trace_span!("provider_http_request_test");
-        let _entered = span.enter();
-        let span_context = span.context().span().span_context().clone();
-        let request = reqwest::Client::new().post("http://localhost/health");
-        let request = inject_trace_headers(request);
-        let request = request.build().expect("request must build");
-
-        let extracted =
-            TraceContextPropagator::new().extract(&HeaderMapExtractor(request.headers()));
-        let extracted_span = extracted.span();
-        let extracted_context = extracted_span.span_context();
-        assert!(extracted_context.is_valid());
-        assert_eq!(extracted_context.trace_id(), span_context.trace_id());
+    fn event_streams_use_the_accumulator_the_provider_supplies() {
+        let runtime = || HttpRuntime::new("tgi".to_string(), None, None, None, None, None);
+        let chunk = r#"{"choices":[{"delta":{"content":"generic"}}]}"#;
+
+        let mut generic = runtime().stream_accumulator::<ChatStreamAccumulator>();
+        assert_eq!(generic.push_event(chunk).expect("chunk must parse").content, ["generic"]);
+
+        let supplied = runtime().with_stream_accumulator(|| Box::<ProviderAccumulator>::default());
+        let mut accumulator = supplied.stream_accumulator::<ChatStreamAccumulator>();
+        assert_eq!(accumulator.push_event(chunk).expect("chunk must parse").content, ["provider"]);
     }
 
     #[test]
-    fn inject_trace_headers_without_active_span_does_not_fail() {
-        global::set_text_map_propagator(TraceContextPropagator::new());
-        let request = reqwest::Client::new().post("http://localhost/health");
-        let request = inject_trace_headers(request);
-        let request = request.build().expect("request must build");
-        let maybe_traceparent =
-            request.headers().get("traceparent").and_then(|value| value.to_str().ok());
-        assert!(maybe_traceparent.is_none() || maybe_traceparent == Some(""));
+    fn only_downstream_xrouters_name_the_upstream_provider() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-xrouter-provider", "deepseek".parse().expect("header must parse"));
+
+        assert_eq!(upstream_provider("xrouter", &headers).as_deref(), Some("deepseek"));
+        assert_eq!(upstream_provider("openrouter", &headers), None);
+        assert_eq!(upstream_provider("xrouter", &reqwest::header::HeaderMap::new()), None);
     }
 
     #[test]
@@ -751,12 +918,14 @@ mod tests {
             reqwest::StatusCode::INTERNAL_SERVER_ERROR,
             "{\"error\":{\"code\":\"500\",\"message\":\"Operation failed\"}}",
             1,
+            None,
         ));
         assert!(!should_retry_failed_status(
             "zai",
             reqwest::StatusCode::INTERNAL_SERVER_ERROR,
             "{\"error\":{\"code\":\"500\",\"message\":\"Operation failed\"}}",
             2,
+            None,
         ));
     }
 
@@ -767,30 +936,109 @@ mod tests {
             reqwest::StatusCode::INTERNAL_SERVER_ERROR,
             "{\"error\":{\"message\":\"Operation failed\"}}",
             1,
+            None,
         ));
         assert!(!should_retry_failed_status(
             "zai",
             reqwest::StatusCode::BAD_REQUEST,
             "{\"error\":{\"message\":\"Operation failed\"}}",
             1,
+            None,
         ));
         assert!(!should_retry_failed_status(
             "zai",
             reqwest::StatusCode::INTERNAL_SERVER_ERROR,
             "{\"error\":{\"message\":\"Different\"}}",
             1,
+            None,
+        ));
+    }
+
+    #[test]
+    fn requested_retries_cover_transient_statuses_of_any_provider() {
+        let retryable = |status: u16, attempt: u32, max_retries: Option<u32>| {
+            should_retry_failed_status(
+                "deepseek",
+                reqwest::StatusCode::from_u16(status).expect("status must be valid"),
+                "",
+                attempt,
+                max_retries,
+            )
+        };
+        assert!(retryable(503, 1, Some(3)));
+        assert!(retryable(429, 3, Some(3)));
+        assert!(!retryable(429, 4, Some(3)));
+        assert!(!retryable(500, 1, Some(3)));
+        assert!(!retryable(503, 1, None));
+        assert!(!should_retry_failed_status(
+            "zai",
+            reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+            "Operation failed",
+            1,
+            Some(0),
         ));
     }
 
-    struct HeaderMapExtractor<'a>(&'a reqwest::header::HeaderMap);
+    #[cfg(feature = "otel")]
+    mod trace_context {
+        use opentelemetry::{
+            global,
+            propagation::{Extractor, TextMapPropagator},
+            trace::{TraceContextExt, TracerProvider},
+        };
+        use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider};
+        use tracing::trace_span;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        use tracing_subscriber::layer::SubscriberExt;
+        use tracing_subscriber::util::SubscriberInitExt;
+
+        use super::super::inject_trace_headers;
+
+        #[test]
+        fn inject_trace_headers_uses_current_span_context() {
+            global::set_text_map_propagator(TraceContextPropagator::new());
+            let provider = SdkTracerProvider::builder().build();
+            let tracer = provider.tracer("test-tracer");
+            let subscriber = tracing_subscriber::registry()
+                .with(tracing_opentelemetry::layer().with_tracer(tracer));
+            let _guard = subscriber.set_default();
+
+            let span = trace_span!("provider_http_request_test");
+            let _entered = span.enter();
+            let span_context = span.context().span().span_context().clone();
+            let request = reqwest::Client::new().post("http://localhost/health");
+            let request = inject_trace_headers(request);
+            let request = request.build().expect("request must build");
 
-    impl<'a> Extractor for HeaderMapExtractor<'a> {
-        fn get(&self, key: &str) -> Option<&str> {
-            self.0.get(key).and_then(|value| value.to_str().ok())
+            let extracted =
+                TraceContextPropagator::new().extract(&HeaderMapExtractor(request.headers()));
+            let extracted_span = extracted.span();
+            let extracted_context = extracted_span.span_context();
+            assert!(extracted_context.is_valid());
+            assert_eq!(extracted_context.trace_id(), span_context.trace_id());
         }
 
-        fn keys(&self) -> Vec<&str> {
-            self.0.keys().map(reqwest::header::HeaderName::as_str).collect()
+        #[test]
+        fn inject_trace_headers_without_active_span_does_not_fail() {
+            global::set_text_map_propagator(TraceContextPropagator::new());
+            let request = reqwest::Client::new().post("http://localhost/health");
+            let request = inject_trace_headers(request);
+            let request = request.build().expect("request must build");
+            let maybe_traceparent =
+                request.headers().get("traceparent").and_then(|value| value.to_str().ok());
+            assert!(maybe_traceparent.is_none() || maybe_traceparent == Some(""));
+        }
+
+        struct HeaderMapExtractor<'a>(&'a reqwest::header::HeaderMap);
+
+        impl<'a> Extractor for HeaderMapExtractor<'a> {
+            fn get(&self, key: &str) -> Option<&str> {
+                self.0.get(key).and_then(|value| value.to_str().ok())
+            }
+
+            fn keys(&self) -> Vec<&str> {
+                self.0.keys().map(reqwest::header::HeaderName::as_str).collect()
+            }
         }
     }
 }
@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
@@ -12,12 +12,69 @@ pub enum StageName {
     Generate,
 }
 
+/// Scheduling class of a request; interactive requests take free provider slots before any
+/// queued batch request does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    #[default]
+    Interactive,
+    Batch,
+}
+
+impl RequestPriority {
+    pub const ALL: [Self; 2] = [Self::Interactive, Self::Batch];
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "interactive" => Some(Self::Interactive),
+            "batch" => Some(Self::Batch),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Interactive => "interactive",
+            Self::Batch => "batch",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
 pub struct ReasoningConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effort: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
+    /// Reasoning token budget.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Let the model reason but leave the reasoning out of the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<bool>,
+}
+
+impl ReasoningConfig {
+    /// Effort for providers that only understand effort levels: the explicit `effort`, otherwise
+    /// an approximation of `max_tokens` (a zero budget turns reasoning off).
+    pub fn effective_effort(&self) -> Option<&str> {
+        if let Some(effort) =
+            self.effort.as_deref().map(str::trim).filter(|value| !value.is_empty())
+        {
+            return Some(effort);
+        }
+        self.max_tokens.map(|budget| match budget {
+            0 => "none",
+            1..=2048 => "low",
+            2049..=8192 => "medium",
+            _ => "high",
+        })
+    }
+
+    pub fn excludes_output(&self) -> bool {
+        self.exclude == Some(true)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
@@ -31,6 +88,8 @@ pub enum TextVerbosity {
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum TextFormatType {
+    Text,
+    JsonObject,
     JsonSchema,
 }
 
@@ -38,9 +97,14 @@ pub enum TextFormatType {
 pub struct TextFormatConfig {
     #[serde(rename = "type")]
     pub kind: TextFormatType,
-    pub strict: bool,
-    pub schema: Value,
-    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
@@ -209,10 +273,86 @@ pub struct ResponsesRequest {
     pub prompt_cache_key: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<TextControls>,
+    /// Output modalities, e.g. `["text"]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<Value>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_stop_sequences",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Fields this API does not model, e.g. `top_k`; forwarded upstream when the provider's
+    /// passthrough allowlist names them.
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    #[schema(ignore)]
+    pub extra: Map<String, Value>,
+}
+
+/// `include` value asking for opaque reasoning state to be returned on reasoning output items.
+pub const INCLUDE_REASONING_ENCRYPTED_CONTENT: &str = "reasoning.encrypted_content";
+
+impl ResponsesRequest {
+    pub fn includes(&self, field: &str) -> bool {
+        self.include.as_ref().is_some_and(|include| include.iter().any(|value| value == field))
+    }
+}
+
+/// Fields some clients send to the Responses API in place of `input`, in the order they are
+/// tried.
+pub const LEGACY_RESPONSES_INPUT_FIELDS: [&str; 2] = ["prompt", "messages"];
+
+/// Moves a legacy `prompt` (text or input items) or Chat Completions `messages` onto `input` of
+/// a Responses request body that has none; returns the field that was moved.
+pub fn upgrade_legacy_responses_input(body: &mut Map<String, Value>) -> Option<&'static str> {
+    if body.contains_key("input") {
+        return None;
+    }
+    let field =
+        LEGACY_RESPONSES_INPUT_FIELDS.into_iter().find(|field| body.contains_key(*field))?;
+    let value = body.remove(field)?;
+    // Messages that do not parse as chat messages are left for `input` to accept or reject.
+    let chat_items = (field == "messages")
+        .then(|| serde_json::from_value::<Vec<ChatMessage>>(value.clone()).ok())
+        .flatten()
+        .map(|messages| {
+            messages
+                .into_iter()
+                .flat_map(ChatMessage::into_response_input_items)
+                .collect::<Vec<_>>()
+        })
+        .and_then(|items| serde_json::to_value(items).ok());
+    let input = chat_items.unwrap_or(value);
+    body.insert("input".to_string(), input);
+    Some(field)
+}
+
+fn deserialize_stop_sequences<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StopSequences {
+        Single(String),
+        Multiple(Vec<String>),
+    }
+
+    let stops = match Option::<StopSequences>::deserialize(deserializer)? {
+        Some(StopSequences::Single(value)) => vec![value],
+        Some(StopSequences::Multiple(values)) => values,
+        None => return Ok(None),
+    };
+    let stops = stops.into_iter().filter(|value| !value.is_empty()).collect::<Vec<_>>();
+    Ok(if stops.is_empty() { None } else { Some(stops) })
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
@@ -220,6 +360,20 @@ pub struct Usage {
     pub input_tokens: u32,
     pub output_tokens: u32,
     pub total_tokens: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens_details: Option<InputTokensDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tokens_details: Option<OutputTokensDetails>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct InputTokensDetails {
+    pub cached_tokens: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct OutputTokensDetails {
+    pub reasoning_tokens: u32,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
@@ -230,8 +384,51 @@ pub struct ResponsesResponse {
     pub output: Vec<ResponseOutputItem>,
     pub finish_reason: String,
     pub usage: Usage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ResponseMeta>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<ConversationBudget>,
 }
 
+/// Tokens spent by a conversation of turns linked through `previous_response_id`, this turn
+/// included, against the configured cap.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct ConversationBudget {
+    pub used_tokens: u64,
+    pub limit_tokens: u64,
+    pub remaining_tokens: u64,
+}
+
+/// Which upstream actually served a request and how it got there.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct ResponseMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_reason: Option<String>,
+    /// The upstream's own id for the generation, for cross-referencing its dashboard.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_response_id: Option<String>,
+    /// The provider a downstream xrouter reported serving the request, when `provider` is
+    /// another router.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_provider: Option<String>,
+    /// Streams only: milliseconds from the request reaching the gateway to the first delta.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_to_first_token_ms: Option<u64>,
+    /// Streams only: mean milliseconds between the deltas after the first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inter_token_latency_ms: Option<u64>,
+    /// USD cost of the usage at the catalog pricing of the model, as a decimal string; unset
+    /// for models without pricing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<String>,
+}
+
+// Only one `ResponseCompleted` is sent per response; boxing its `meta` is not worth changing
+// the public shape every client matches on.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ResponseEvent {
@@ -248,6 +445,8 @@ pub enum ResponseEvent {
         output: Vec<ResponseOutputItem>,
         finish_reason: String,
         usage: Usage,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        meta: Option<ResponseMeta>,
     },
     ResponseError {
         id: String,
@@ -260,6 +459,33 @@ pub struct ResponseOutputText {
     #[serde(rename = "type")]
     pub kind: String,
     pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<OutputAnnotation>,
+}
+
+/// A span of output text backed by sources, such as a citation of a RAG document. Indices are
+/// character offsets into the text.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct OutputAnnotation {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub start_index: u32,
+    pub end_index: u32,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<CitationSource>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct CitationSource {
+    /// `document` for a request document, `tool` for a tool result.
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The cited document or tool output as the upstream returned it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
@@ -280,6 +506,8 @@ pub enum ResponseOutputItem {
         summary: Vec<ResponseReasoningSummary>,
         #[serde(default, skip_serializing_if = "Vec::is_empty")]
         content: Vec<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        encrypted_content: Option<String>,
     },
     FunctionCall {
         id: String,
@@ -306,7 +534,8 @@ pub struct ToolCall {
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
 pub struct ChatMessage {
     pub role: String,
-    pub content: String,
+    #[serde(default, deserialize_with = "deserialize_nullable_content")]
+    pub content: ChatMessageContent,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -315,6 +544,129 @@ pub struct ChatMessage {
     pub reasoning_details: Option<Vec<Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<OutputAnnotation>>,
+}
+
+/// Chat message content: a plain string or an array of content parts.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(untagged)]
+pub enum ChatMessageContent {
+    Text(String),
+    Parts(Vec<ChatContentPart>),
+}
+
+impl Default for ChatMessageContent {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl ChatMessageContent {
+    /// Text parts joined by newlines; image parts are left out.
+    pub fn to_text(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| part.text.as_deref())
+                .filter(|text| !text.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    pub fn has_images(&self) -> bool {
+        matches!(self, Self::Parts(parts) if parts.iter().any(|part| part.image_url.is_some()))
+    }
+
+    fn into_response_input_content(self) -> Option<ResponseInputContent> {
+        match self {
+            Self::Text(text) if text.trim().is_empty() => None,
+            Self::Text(text) => Some(ResponseInputContent::Text(text)),
+            Self::Parts(parts) => {
+                let parts = parts
+                    .into_iter()
+                    .filter_map(|part| match (part.text, part.image_url) {
+                        (_, Some(image)) => Some(ResponseInputPart {
+                            kind: Some("input_image".to_string()),
+                            image_url: Some(image.url),
+                            detail: image.detail,
+                            ..Default::default()
+                        }),
+                        (Some(text), None) if !text.trim().is_empty() => Some(ResponseInputPart {
+                            kind: Some("input_text".to_string()),
+                            text: Some(text),
+                            ..Default::default()
+                        }),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                (!parts.is_empty()).then_some(ResponseInputContent::Parts(parts))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct ChatContentPart {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<ChatImageUrl>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct ChatImageUrl {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ChatMessage {
+    /// Response input items for this message: assistant tool calls become `function_call`
+    /// items and `tool` messages become `function_call_output` items.
+    fn into_response_input_items(self) -> Vec<ResponseInputItem> {
+        if self.role == "tool" {
+            return vec![ResponseInputItem {
+                kind: Some("function_call_output".to_string()),
+                call_id: self.tool_call_id,
+                name: self.name,
+                output: Some(ResponseToolOutput::Text(self.content.to_text())),
+                ..Default::default()
+            }];
+        }
+        let mut items = Vec::new();
+        if let Some(content) = self.content.into_response_input_content() {
+            items.push(ResponseInputItem {
+                kind: Some("message".to_string()),
+                role: Some(self.role),
+                content: Some(content),
+                ..Default::default()
+            });
+        }
+        items.extend(self.tool_calls.into_iter().flatten().map(|call| ResponseInputItem {
+            kind: Some("function_call".to_string()),
+            call_id: Some(call.id),
+            name: Some(call.function.name),
+            arguments: Some(call.function.arguments),
+            ..Default::default()
+        }));
+        items
+    }
+}
+
+fn deserialize_nullable_content<'de, D>(deserializer: D) -> Result<ChatMessageContent, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<ChatMessageContent>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
@@ -325,6 +677,28 @@ pub struct ChatCompletionsRequest {
     pub stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning: Option<ReasoningConfig>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_stop_sequences",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Fields this API does not model; see `ResponsesRequest::extra`.
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    #[schema(ignore)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
@@ -340,23 +714,28 @@ pub struct ChatCompletionsResponse {
     pub object: String,
     pub choices: Vec<ChatChoice>,
     pub usage: Usage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ResponseMeta>,
 }
 
 impl ChatCompletionsRequest {
+    pub fn has_image_parts(&self) -> bool {
+        self.messages.iter().any(|message| message.content.has_images())
+    }
+
     pub fn into_responses_request(self) -> ResponsesRequest {
         let input = self
             .messages
             .into_iter()
-            .map(|m| format!("{}:{}", m.role, m.content))
-            .collect::<Vec<_>>()
-            .join("\n");
+            .flat_map(ChatMessage::into_response_input_items)
+            .collect::<Vec<_>>();
 
         ResponsesRequest {
             model: self.model,
             instructions: None,
             previous_response_id: None,
-            input: ResponsesInput::Text(input),
-            parallel_tool_calls: None,
+            input: ResponsesInput::Items(input),
+            parallel_tool_calls: self.parallel_tool_calls,
             stream: self.stream,
             reasoning: self.reasoning,
             store: None,
@@ -364,8 +743,13 @@ impl ChatCompletionsRequest {
             service_tier: None,
             prompt_cache_key: None,
             text: None,
-            tools: None,
-            tool_choice: None,
+            modalities: None,
+            tools: self.tools,
+            tool_choice: self.tool_choice,
+            stop: self.stop,
+            max_output_tokens: self.max_completion_tokens.or(self.max_tokens),
+            user: self.user,
+            extra: self.extra,
         }
     }
 }
@@ -373,6 +757,7 @@ impl ChatCompletionsRequest {
 impl ChatCompletionsResponse {
     pub fn from_responses(response: ResponsesResponse) -> Self {
         let mut content = String::new();
+        let mut annotations = None;
         let mut reasoning = None;
         let mut reasoning_details = None;
         let mut tool_calls = Vec::new();
@@ -382,6 +767,9 @@ impl ChatCompletionsResponse {
                 ResponseOutputItem::Message { content: parts, .. } => {
                     if let Some(first) = parts.first() {
                         content = first.text.clone();
+                        if !first.annotations.is_empty() {
+                            annotations = Some(first.annotations.clone());
+                        }
                     }
                 }
                 ResponseOutputItem::Reasoning { summary, content: details, .. } => {
@@ -409,15 +797,139 @@ impl ChatCompletionsResponse {
                 index: 0,
                 message: ChatMessage {
                     role: "assistant".to_string(),
-                    content,
+                    content: ChatMessageContent::Text(content),
                     reasoning: reasoning.clone(),
                     reasoning_content: reasoning,
                     reasoning_details,
                     tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+                    tool_call_id: None,
+                    name: None,
+                    annotations,
                 },
                 finish_reason: response.finish_reason,
             }],
             usage: response.usage,
+            meta: response.meta,
+        }
+    }
+}
+
+/// Legacy text completion request, served as a one-message chat completion.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct CompletionsRequest {
+    pub model: String,
+    pub prompt: CompletionPrompt,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_stop_sequences",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Prepends the prompt to the completion text.
+    #[serde(default)]
+    pub echo: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Fields this API does not model; see `ResponsesRequest::extra`.
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    #[schema(ignore)]
+    pub extra: Map<String, Value>,
+}
+
+/// A prompt string, or a list of them; only single prompts can be served.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(untagged)]
+pub enum CompletionPrompt {
+    Text(String),
+    Batch(Vec<String>),
+}
+
+impl CompletionPrompt {
+    /// The prompt text, or `None` for a batch of other than one prompt.
+    pub fn single(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            Self::Batch(prompts) => match prompts.as_slice() {
+                [prompt] => Some(prompt),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl CompletionsRequest {
+    /// The chat request asking for `prompt` as a user message; `None` for a batch prompt.
+    pub fn to_chat_completions_request(&self) -> Option<ChatCompletionsRequest> {
+        Some(ChatCompletionsRequest {
+            model: self.model.clone(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: ChatMessageContent::Text(self.prompt.single()?.to_string()),
+                reasoning: None,
+                reasoning_content: None,
+                reasoning_details: None,
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+                annotations: None,
+            }],
+            stream: self.stream,
+            reasoning: None,
+            stop: self.stop.clone(),
+            max_tokens: self.max_tokens,
+            max_completion_tokens: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            user: self.user.clone(),
+            extra: self.extra.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct CompletionChoice {
+    pub text: String,
+    pub index: u32,
+    /// Always `null`; token log probabilities are not available.
+    #[schema(value_type = Option<Object>)]
+    pub logprobs: Option<Value>,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub struct CompletionsResponse {
+    pub id: String,
+    /// Always `text_completion`.
+    pub object: String,
+    pub model: String,
+    pub choices: Vec<CompletionChoice>,
+    pub usage: Usage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ResponseMeta>,
+}
+
+impl CompletionsResponse {
+    /// The text of the assistant message of `chat`, after `echo` when set.
+    pub fn from_chat(chat: ChatCompletionsResponse, model: String, echo: Option<&str>) -> Self {
+        let choice = chat.choices.into_iter().next();
+        let text = choice.as_ref().map(|choice| choice.message.content.to_text());
+        Self {
+            id: chat.id.replacen("chatcmpl_", "cmpl_", 1),
+            object: "text_completion".to_string(),
+            model,
+            choices: vec![CompletionChoice {
+                text: format!("{}{}", echo.unwrap_or_default(), text.unwrap_or_default()),
+                index: 0,
+                logprobs: None,
+                finish_reason: choice.map(|choice| choice.finish_reason),
+            }],
+            usage: chat.usage,
+            meta: chat.meta,
         }
     }
 }
@@ -558,6 +1070,58 @@ fn serialize_json_value(value: &Value) -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn completions_requests_become_a_single_user_message() {
+        let request: CompletionsRequest = serde_json::from_str(
+            r#"{"model":"zai/glm-4.5","prompt":["Once upon"],"stop":"\n","temperature":0.2}"#,
+        )
+        .expect("request must deserialize");
+        let chat = request.to_chat_completions_request().expect("single prompt must convert");
+        assert_eq!(chat.messages.len(), 1);
+        assert_eq!(chat.messages[0].role, "user");
+        assert_eq!(chat.messages[0].content.to_text(), "Once upon");
+        assert_eq!(chat.stop, Some(vec!["\n".to_string()]));
+        assert_eq!(chat.extra.get("temperature"), Some(&serde_json::json!(0.2)));
+
+        let batch: CompletionsRequest =
+            serde_json::from_str(r#"{"model":"zai/glm-4.5","prompt":["a","b"]}"#)
+                .expect("batch must deserialize");
+        assert!(batch.to_chat_completions_request().is_none());
+    }
+
+    #[test]
+    fn chat_responses_carry_output_text_annotations_on_the_message() {
+        let response: ResponsesResponse = serde_json::from_value(serde_json::json!({
+            "id": "resp_1",
+            "object": "response",
+            "status": "completed",
+            "output": [{
+                "type": "message",
+                "id": "msg_0",
+                "role": "assistant",
+                "content": [{
+                    "type": "output_text",
+                    "text": "Penguins live in Antarctica.",
+                    "annotations": [{
+                        "type": "citation",
+                        "start_index": 17,
+                        "end_index": 27,
+                        "text": "Antarctica",
+                        "sources": [{"type": "document", "id": "doc-1"}]
+                    }]
+                }]
+            }],
+            "finish_reason": "stop",
+            "usage": {"input_tokens": 1, "output_tokens": 4, "total_tokens": 5}
+        }))
+        .expect("response must deserialize");
+
+        let chat = ChatCompletionsResponse::from_responses(response);
+        let annotations = chat.choices[0].message.annotations.as_ref().expect("annotations");
+        assert_eq!(annotations[0].text, "Antarctica");
+        assert_eq!(annotations[0].sources[0].id.as_deref(), Some("doc-1"));
+    }
+
     #[test]
     fn responses_input_deserializes_text_variant() {
         let request: ResponsesRequest = serde_json::from_str(
@@ -642,6 +1206,24 @@ mod tests {
         assert_eq!(request.input.to_canonical_text(), "tool:call_123:{\"count\":2,\"ok\":true}");
     }
 
+    #[test]
+    fn reasoning_budget_approximates_effort_and_explicit_effort_wins() {
+        let budget =
+            |max_tokens| ReasoningConfig { max_tokens: Some(max_tokens), ..Default::default() };
+        assert_eq!(budget(0).effective_effort(), Some("none"));
+        assert_eq!(budget(1024).effective_effort(), Some("low"));
+        assert_eq!(budget(4096).effective_effort(), Some("medium"));
+        assert_eq!(budget(32_000).effective_effort(), Some("high"));
+        let explicit = ReasoningConfig { effort: Some("minimal".to_string()), ..budget(32_000) };
+        assert_eq!(explicit.effective_effort(), Some("minimal"));
+        assert_eq!(ReasoningConfig::default().effective_effort(), None);
+
+        let parsed: ReasoningConfig =
+            serde_json::from_str(r#"{"max_tokens":2000,"exclude":true}"#).expect("must parse");
+        assert!(parsed.excludes_output());
+        assert!(!ReasoningConfig::default().excludes_output());
+    }
+
     #[test]
     fn responses_request_deserializes_full_codex_style_contract() {
         let request: ResponsesRequest = serde_json::from_str(
@@ -708,12 +1290,18 @@ mod tests {
             request.include.as_ref().expect("include"),
             &vec!["reasoning.encrypted_content".to_string()]
         );
+        assert!(request.includes(INCLUDE_REASONING_ENCRYPTED_CONTENT));
+        assert!(!request.includes("message.output_text.logprobs"));
         assert_eq!(request.service_tier.as_deref(), Some("priority"));
         assert_eq!(request.prompt_cache_key.as_deref(), Some("conv_123"));
         assert_eq!(
             request.text.as_ref().and_then(|text| text.verbosity.as_ref()),
             Some(&TextVerbosity::High)
         );
+        let format = request.text.as_ref().and_then(|text| text.format.as_ref()).expect("format");
+        assert_eq!(format.kind, TextFormatType::JsonSchema);
+        assert_eq!(format.name.as_deref(), Some("codex_output_schema"));
+        assert_eq!(format.strict, Some(true));
         let ResponsesInput::Items(items) = &request.input else {
             panic!("expected item input");
         };
@@ -735,4 +1323,145 @@ mod tests {
             "user:hello\nassistant:working on it\nassistant_reasoning:checked workspace\nassistant_function_call:list_dir:{\"dir_path\":\"/workspace\"}\ntool:call_1:Absolute path: /workspace\ntool:call_2:patch applied"
         );
     }
+
+    #[test]
+    fn stop_sequences_accept_string_or_array_and_drop_empty_values() {
+        let single: ResponsesRequest =
+            serde_json::from_str(r#"{"model":"m","input":"hi","stop":"END"}"#)
+                .expect("request must deserialize");
+        assert_eq!(single.stop, Some(vec!["END".to_string()]));
+
+        let chat: ChatCompletionsRequest = serde_json::from_str(
+            r#"{"model":"m","messages":[{"role":"user","content":"hi"}],"stop":["a",""]}"#,
+        )
+        .expect("request must deserialize");
+        assert_eq!(chat.into_responses_request().stop, Some(vec!["a".to_string()]));
+
+        let empty: ResponsesRequest =
+            serde_json::from_str(r#"{"model":"m","input":"hi","stop":[]}"#)
+                .expect("request must deserialize");
+        assert_eq!(empty.stop, None);
+    }
+
+    #[test]
+    fn legacy_prompt_and_messages_become_input() {
+        let mut prompt = serde_json::json!({"model": "m", "prompt": "hello"});
+        let prompt = prompt.as_object_mut().expect("object");
+        assert_eq!(upgrade_legacy_responses_input(prompt), Some("prompt"));
+        let request: ResponsesRequest =
+            serde_json::from_value(Value::Object(prompt.clone())).expect("request");
+        assert_eq!(request.input, ResponsesInput::Text("hello".to_string()));
+
+        let mut messages = serde_json::json!({
+            "model": "m",
+            "messages": [
+                {"role": "user", "content": "weather?"},
+                {"role": "assistant", "content": null, "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{}"}
+                }]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "sunny"}
+            ]
+        });
+        let messages = messages.as_object_mut().expect("object");
+        assert_eq!(upgrade_legacy_responses_input(messages), Some("messages"));
+        let request: ResponsesRequest =
+            serde_json::from_value(Value::Object(messages.clone())).expect("request");
+        assert!(request.extra.is_empty());
+        assert_eq!(
+            request.input.to_canonical_text(),
+            "user:weather?\nassistant_function_call:get_weather:{}\ntool:call_1:sunny"
+        );
+
+        let mut current = serde_json::json!({"model": "m", "input": "hi", "prompt": "ignored"});
+        assert_eq!(upgrade_legacy_responses_input(current.as_object_mut().expect("object")), None);
+    }
+
+    #[test]
+    fn chat_tools_and_tool_choice_reach_the_responses_request() {
+        let chat: ChatCompletionsRequest = serde_json::from_value(serde_json::json!({
+            "model": "m",
+            "messages": [{"role": "user", "content": "weather?"}],
+            "tools": [{
+                "type": "function",
+                "function": {"name": "get_weather", "parameters": {"type": "object"}}
+            }],
+            "tool_choice": {"type": "function", "function": {"name": "get_weather"}},
+            "parallel_tool_calls": false
+        }))
+        .expect("request must deserialize");
+
+        let request = chat.into_responses_request();
+        let tools = request.tools.expect("tools must be kept");
+        assert_eq!(tools[0]["function"]["name"], "get_weather");
+        assert_eq!(
+            request.tool_choice,
+            Some(serde_json::json!({"type": "function", "function": {"name": "get_weather"}}))
+        );
+        assert_eq!(request.parallel_tool_calls, Some(false));
+    }
+
+    #[test]
+    fn chat_content_accepts_string_or_parts_and_keeps_images() {
+        let chat: ChatCompletionsRequest = serde_json::from_value(serde_json::json!({
+            "model": "m",
+            "messages": [
+                {"role": "system", "content": "be brief"},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "what is"},
+                    {"type": "text", "text": "this?"},
+                    {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+                ]}
+            ]
+        }))
+        .expect("request must deserialize");
+
+        assert!(!chat.messages[0].content.has_images());
+        assert!(chat.messages[1].content.has_images());
+        assert_eq!(chat.messages[1].content.to_text(), "what is\nthis?");
+        assert!(chat.has_image_parts());
+
+        let ResponsesInput::Items(items) = chat.into_responses_request().input else {
+            panic!("expected item input");
+        };
+        let Some(ResponseInputContent::Parts(parts)) = &items[1].content else {
+            panic!("expected content parts");
+        };
+        assert_eq!(parts[2].kind.as_deref(), Some("input_image"));
+        assert_eq!(parts[2].image_url.as_deref(), Some("https://example.com/cat.png"));
+    }
+
+    #[test]
+    fn chat_tool_roundtrip_becomes_function_call_items() {
+        let chat: ChatCompletionsRequest = serde_json::from_value(serde_json::json!({
+            "model": "m",
+            "messages": [
+                {"role": "system", "content": "be brief"},
+                {"role": "user", "content": "weather?"},
+                {"role": "assistant", "content": null, "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Oslo\"}"}
+                }]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "sunny"}
+            ]
+        }))
+        .expect("request must deserialize");
+
+        let ResponsesInput::Items(items) = chat.into_responses_request().input else {
+            panic!("expected item input");
+        };
+        let kinds =
+            items.iter().map(|item| item.kind.as_deref().unwrap_or_default()).collect::<Vec<_>>();
+        assert_eq!(kinds, ["message", "message", "function_call", "function_call_output"]);
+        assert_eq!(items[0].role.as_deref(), Some("system"));
+        assert_eq!(items[2].call_id.as_deref(), Some("call_1"));
+        assert_eq!(items[2].name.as_deref(), Some("get_weather"));
+        assert_eq!(items[3].call_id.as_deref(), Some("call_1"));
+        assert_eq!(
+            ResponsesInput::Items(items).to_canonical_text(),
+            "system:be brief\nuser:weather?\nassistant_function_call:get_weather:{\"city\":\"Oslo\"}\ntool:call_1:sunny"
+        );
+    }
 }
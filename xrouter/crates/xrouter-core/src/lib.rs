@@ -1,22 +1,46 @@
-use std::{sync::Arc, time::Instant};
+mod moderation;
+mod output_guard;
+mod reasoning_filter;
+mod tool_choice;
+mod tool_validation;
+mod transforms;
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
-use tracing::{Instrument, error, field, info, info_span, warn};
+use tracing::{Instrument, debug, error, field, info, info_span, warn};
 use uuid::Uuid;
 use xrouter_contracts::{
-    ReasoningConfig, ResponseEvent, ResponseOutputItem, ResponseOutputText,
-    ResponseReasoningSummary, ResponsesInput, ResponsesRequest, ResponsesResponse, StageName,
-    ToolCall, ToolFunction, Usage,
+    INCLUDE_REASONING_ENCRYPTED_CONTENT, InputTokensDetails, OutputAnnotation, OutputTokensDetails,
+    ReasoningConfig, RequestPriority, ResponseEvent, ResponseMeta, ResponseOutputItem,
+    ResponseOutputText, ResponseReasoningSummary, ResponsesInput, ResponsesRequest,
+    ResponsesResponse, StageName, TextControls, ToolCall, ToolFunction, Usage,
+};
+
+pub use moderation::{
+    BlocklistModerationPolicy, CONTENT_FILTER_FINISH_REASON, OutputModeration,
+    OutputModerationPolicy,
+};
+pub use output_guard::{
+    OutputTokenBudget, StopSequenceScanner, find_stop_sequence, truncate_to_output_tokens,
+};
+pub use transforms::{
+    ExecutionTransform, OutputStreamRewriter, ScopedTransform, build_builtin_transform,
 };
 
 #[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
 pub enum CoreError {
     #[error("validation failed: {0}")]
     Validation(String),
+    #[error("validation failed: {param}: {message}")]
+    InvalidParam { param: String, message: String },
     #[error("provider error: {0}")]
     Provider(String),
-    #[error("client disconnected during {0:?}")]
-    ClientDisconnected(StageName),
+    #[error("client disconnected during {stage:?}")]
+    ClientDisconnected { stage: StageName, usage: Usage },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -42,21 +66,47 @@ pub struct ExecutionContext {
     pub request_reasoning: Option<ReasoningConfig>,
     pub request_tools: Option<Vec<serde_json::Value>>,
     pub request_tool_choice: Option<serde_json::Value>,
+    pub request_stop: Option<Vec<String>>,
+    pub request_max_output_tokens: Option<u32>,
+    pub request_include: Option<Vec<String>>,
+    pub request_text: Option<TextControls>,
+    pub request_modalities: Option<Vec<String>>,
+    pub request_extra: serde_json::Map<String, serde_json::Value>,
+    pub user: Option<String>,
     pub auth_bearer: Option<String>,
     pub forward_headers: Vec<(String, String)>,
+    pub controls: ExecutionControls,
     pub output_text: String,
     pub tool_calls: Option<Vec<ToolCall>>,
+    pub annotations: Option<Vec<OutputAnnotation>>,
     pub reasoning: Option<String>,
     pub reasoning_details: Option<Vec<serde_json::Value>>,
+    pub reasoning_encrypted_content: Option<String>,
     pub input_tokens: u32,
     pub output_tokens: u32,
+    pub finish_reason: Option<String>,
+    pub provider_usage: Option<ProviderUsage>,
+    pub upstream_attempts: u32,
+    pub provider_response_id: Option<String>,
+    pub upstream_provider: Option<String>,
 }
 
 impl ExecutionContext {
+    fn excludes_reasoning(&self) -> bool {
+        self.request_reasoning.as_ref().is_some_and(ReasoningConfig::excludes_output)
+    }
+
+    fn includes_encrypted_reasoning(&self) -> bool {
+        self.request_include.as_ref().is_some_and(|include| {
+            include.iter().any(|value| value == INCLUDE_REASONING_ENCRYPTED_CONTENT)
+        })
+    }
+
     fn new(
         request: ResponsesRequest,
         auth_bearer: Option<String>,
         forward_headers: Vec<(String, String)>,
+        controls: ExecutionControls,
     ) -> Self {
         let request_input = request.input.clone();
         let input = request_input.to_canonical_text();
@@ -72,14 +122,29 @@ impl ExecutionContext {
             request_reasoning: request.reasoning,
             request_tools: request.tools,
             request_tool_choice: request.tool_choice,
+            request_stop: request.stop,
+            request_max_output_tokens: request.max_output_tokens,
+            request_include: request.include,
+            request_text: request.text,
+            request_modalities: request.modalities,
+            request_extra: request.extra,
+            user: request.user,
             auth_bearer,
             forward_headers,
+            controls,
             output_text: String::new(),
             tool_calls: None,
+            annotations: None,
             reasoning: None,
             reasoning_details: None,
+            reasoning_encrypted_content: None,
             input_tokens: 0,
             output_tokens: 0,
+            finish_reason: None,
+            provider_usage: None,
+            upstream_attempts: 0,
+            provider_response_id: None,
+            upstream_provider: None,
         }
     }
 }
@@ -90,8 +155,28 @@ pub struct ProviderOutcome {
     pub output_tokens: u32,
     pub reasoning: Option<String>,
     pub reasoning_details: Option<Vec<serde_json::Value>>,
+    /// Opaque reasoning state a Responses-API upstream returns for later turns.
+    pub reasoning_encrypted_content: Option<String>,
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Citations over the output text, e.g. of documents sent to a RAG-capable upstream.
+    pub annotations: Option<Vec<OutputAnnotation>>,
     pub emitted_live: bool,
+    pub finish_reason: Option<String>,
+    pub usage: Option<ProviderUsage>,
+    /// Upstream requests made to produce this outcome, including transport retries.
+    pub upstream_attempts: u32,
+    /// The upstream's own id for the generation, e.g. OpenRouter `gen-...`.
+    pub provider_response_id: Option<String>,
+    /// The provider a downstream router reported serving the request, in tiered routing.
+    pub upstream_provider: Option<String>,
+}
+
+/// Token counts reported by the upstream; preferred over local estimates when present.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderUsage {
+    pub prompt_tokens: Option<u32>,
+    pub reasoning_tokens: Option<u32>,
+    pub cached_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -106,6 +191,17 @@ pub struct ModelDescriptor {
     pub top_provider_context_length: u32,
     pub is_moderated: bool,
     pub max_completion_tokens: u32,
+    pub supports_tools: bool,
+    pub pricing: Option<ModelPricing>,
+    /// Request parameters the model accepts, in OpenRouter's `supported_parameters` vocabulary.
+    pub supported_parameters: Vec<String>,
+}
+
+/// USD per token, as decimal strings the way OpenRouter reports them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelPricing {
+    pub prompt: String,
+    pub completion: String,
 }
 
 pub fn synthesize_model_id(provider: &str, provider_model: &str) -> String {
@@ -203,6 +299,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 128000,
             is_moderated: true,
             max_completion_tokens: 16384,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "anthropic/claude-3.5-sonnet".to_string(),
@@ -215,6 +314,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 200000,
             is_moderated: true,
             max_completion_tokens: 8192,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "deepseek-chat".to_string(),
@@ -227,6 +329,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 128000,
             is_moderated: true,
             max_completion_tokens: 8192,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "deepseek-reasoner".to_string(),
@@ -239,6 +344,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 128000,
             is_moderated: true,
             max_completion_tokens: 64000,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "GigaChat-2".to_string(),
@@ -251,6 +359,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 128000,
             is_moderated: true,
             max_completion_tokens: 8192,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "GigaChat-2-Pro".to_string(),
@@ -259,10 +370,13 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             context_length: 128000,
             tokenizer: "unknown".to_string(),
             instruct_type: "none".to_string(),
-            modality: "text->text".to_string(),
+            modality: "text+image->text".to_string(),
             top_provider_context_length: 128000,
             is_moderated: true,
             max_completion_tokens: 8192,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "GigaChat-2-Max".to_string(),
@@ -271,10 +385,13 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             context_length: 128000,
             tokenizer: "unknown".to_string(),
             instruct_type: "none".to_string(),
-            modality: "text->text".to_string(),
+            modality: "text+image->text".to_string(),
             top_provider_context_length: 128000,
             is_moderated: true,
             max_completion_tokens: 8192,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "yandexgpt/latest".to_string(),
@@ -287,6 +404,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 32768,
             is_moderated: true,
             max_completion_tokens: 8192,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "yandexgpt/rc".to_string(),
@@ -299,6 +419,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 32768,
             is_moderated: true,
             max_completion_tokens: 8192,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "yandexgpt-lite/latest".to_string(),
@@ -311,6 +434,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 32768,
             is_moderated: true,
             max_completion_tokens: 8192,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "aliceai-llm/latest".to_string(),
@@ -323,6 +449,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 32768,
             is_moderated: true,
             max_completion_tokens: 8192,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "llama3.1:8b".to_string(),
@@ -335,6 +464,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 8192,
             is_moderated: true,
             max_completion_tokens: 4096,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "glm-4.5".to_string(),
@@ -347,6 +479,9 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 128000,
             is_moderated: true,
             max_completion_tokens: 98304,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
         ModelDescriptor {
             id: "gpt-4.1-mini".to_string(),
@@ -359,6 +494,129 @@ pub fn default_model_catalog() -> Vec<ModelDescriptor> {
             top_provider_context_length: 128000,
             is_moderated: true,
             max_completion_tokens: 16384,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
+        },
+        ModelDescriptor {
+            id: "command-a-03-2025".to_string(),
+            provider: "cohere".to_string(),
+            description: "Cohere Command A is tuned for tool use, agents, and retrieval-augmented generation with grounded citations.".to_string(),
+            context_length: 256000,
+            tokenizer: "unknown".to_string(),
+            instruct_type: "none".to_string(),
+            modality: "text->text".to_string(),
+            top_provider_context_length: 256000,
+            is_moderated: true,
+            max_completion_tokens: 8000,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
+        },
+        ModelDescriptor {
+            id: "command-r-plus-08-2024".to_string(),
+            provider: "cohere".to_string(),
+            description: "Cohere Command R+ handles complex RAG and multi-step tool use over long contexts.".to_string(),
+            context_length: 128000,
+            tokenizer: "unknown".to_string(),
+            instruct_type: "none".to_string(),
+            modality: "text->text".to_string(),
+            top_provider_context_length: 128000,
+            is_moderated: true,
+            max_completion_tokens: 4000,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
+        },
+        ModelDescriptor {
+            id: "command-r-08-2024".to_string(),
+            provider: "cohere".to_string(),
+            description: "Cohere Command R is a fast model for RAG and tool use at lower cost.".to_string(),
+            context_length: 128000,
+            tokenizer: "unknown".to_string(),
+            instruct_type: "none".to_string(),
+            modality: "text->text".to_string(),
+            top_provider_context_length: 128000,
+            is_moderated: true,
+            max_completion_tokens: 4000,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
+        },
+        ModelDescriptor {
+            id: "sonar".to_string(),
+            provider: "perplexity".to_string(),
+            description: "Perplexity Sonar answers from live web search with cited sources.".to_string(),
+            context_length: 128000,
+            tokenizer: "unknown".to_string(),
+            instruct_type: "none".to_string(),
+            modality: "text->text".to_string(),
+            top_provider_context_length: 128000,
+            is_moderated: true,
+            max_completion_tokens: 8000,
+            supports_tools: false,
+            pricing: None,
+            supported_parameters: Vec::new(),
+        },
+        ModelDescriptor {
+            id: "sonar-pro".to_string(),
+            provider: "perplexity".to_string(),
+            description: "Perplexity Sonar Pro runs deeper web searches for complex, multi-part questions, with more cited sources.".to_string(),
+            context_length: 200000,
+            tokenizer: "unknown".to_string(),
+            instruct_type: "none".to_string(),
+            modality: "text->text".to_string(),
+            top_provider_context_length: 200000,
+            is_moderated: true,
+            max_completion_tokens: 8000,
+            supports_tools: false,
+            pricing: None,
+            supported_parameters: Vec::new(),
+        },
+        ModelDescriptor {
+            id: "sonar-reasoning-pro".to_string(),
+            provider: "perplexity".to_string(),
+            description: "Perplexity Sonar Reasoning Pro combines step-by-step reasoning with web search and cited sources.".to_string(),
+            context_length: 128000,
+            tokenizer: "unknown".to_string(),
+            instruct_type: "none".to_string(),
+            modality: "text->text".to_string(),
+            top_provider_context_length: 128000,
+            is_moderated: true,
+            max_completion_tokens: 8000,
+            supports_tools: false,
+            pricing: None,
+            supported_parameters: Vec::new(),
+        },
+        ModelDescriptor {
+            id: "@cf/meta/llama-3.1-8b-instruct".to_string(),
+            provider: "cloudflare".to_string(),
+            description: "Llama 3.1 8B Instruct on Cloudflare Workers AI.".to_string(),
+            context_length: 7968,
+            tokenizer: "unknown".to_string(),
+            instruct_type: "none".to_string(),
+            modality: "text->text".to_string(),
+            top_provider_context_length: 7968,
+            is_moderated: false,
+            max_completion_tokens: 4096,
+            supports_tools: false,
+            pricing: None,
+            supported_parameters: Vec::new(),
+        },
+        ModelDescriptor {
+            id: "@cf/meta/llama-3.3-70b-instruct-fp8-fast".to_string(),
+            provider: "cloudflare".to_string(),
+            description: "Llama 3.3 70B Instruct, fp8-quantized for speed, on Cloudflare Workers AI.".to_string(),
+            context_length: 24000,
+            tokenizer: "unknown".to_string(),
+            instruct_type: "none".to_string(),
+            modality: "text->text".to_string(),
+            top_provider_context_length: 24000,
+            is_moderated: false,
+            max_completion_tokens: 4096,
+            supports_tools: true,
+            pricing: None,
+            supported_parameters: Vec::new(),
         },
     ]
 }
@@ -379,12 +637,43 @@ pub trait ProviderClient: Send + Sync {
         let _ = request.sender;
         self.generate(request.request).await
     }
+
+    /// Whether the upstream honours `tool_choice: "required"` natively.
+    fn supports_required_tool_choice(&self) -> bool {
+        true
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 pub trait ResponseEventSink: Send + Sync {
     async fn send(&self, event: Result<ResponseEvent, CoreError>);
+
+    fn output_closed(&self) -> bool {
+        false
+    }
+
+    fn retained_output_limit(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Per-request bounds on upstream calls; unset fields keep the provider defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpstreamLimits {
+    /// Bounds each upstream attempt, from connecting to the end of the response body.
+    pub timeout: Option<Duration>,
+    /// Retries after transient failures (`429`, `502`-`504`, connect errors, timeouts); unset
+    /// keeps the single retry of Z.AI's transient `Operation failed`.
+    pub max_retries: Option<u32>,
+}
+
+/// Caller-chosen knobs carried with a request down to the provider clients.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionControls {
+    /// Scheduling class for providers behind a priority gate.
+    pub priority: RequestPriority,
+    pub upstream: UpstreamLimits,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -395,8 +684,14 @@ pub struct ProviderGenerateRequest<'a> {
     pub reasoning: Option<&'a ReasoningConfig>,
     pub tools: Option<&'a [serde_json::Value]>,
     pub tool_choice: Option<&'a serde_json::Value>,
+    pub include: Option<&'a [String]>,
+    pub text: Option<&'a TextControls>,
+    pub modalities: Option<&'a [String]>,
     pub auth_bearer: Option<&'a str>,
     pub forward_headers: &'a [(String, String)],
+    /// Provider-specific body fields passed through as-is; already filtered by the caller.
+    pub extra: Option<&'a serde_json::Map<String, serde_json::Value>>,
+    pub controls: ExecutionControls,
 }
 
 #[derive(Clone, Copy)]
@@ -426,6 +721,10 @@ impl StageHandler for IngestHandler {
         if context.input.trim().is_empty() {
             return Err(CoreError::Validation("input must not be empty".to_string()));
         }
+        tool_validation::validate_tools_and_choice(
+            context.request_tools.as_deref(),
+            context.request_tool_choice.as_ref(),
+        )?;
         context.state = KernelState::Tokenize;
         Ok(())
     }
@@ -450,6 +749,65 @@ impl StageHandler for TokenizeHandler {
 struct GenerateHandler {
     provider: Arc<dyn ProviderClient>,
     sender: Option<Arc<dyn ResponseEventSink>>,
+    emulate_required_tool_choice: bool,
+}
+
+impl GenerateHandler {
+    async fn generate_once(
+        &self,
+        context: &ExecutionContext,
+        instructions: Option<&str>,
+        live: bool,
+    ) -> Result<ProviderOutcome, CoreError> {
+        self.provider
+            .generate_stream(ProviderGenerateStreamRequest {
+                request_id: &context.request_id,
+                request: ProviderGenerateRequest {
+                    model: &context.model,
+                    instructions,
+                    input: &context.request_input,
+                    reasoning: context.request_reasoning.as_ref(),
+                    tools: context.request_tools.as_deref(),
+                    tool_choice: context.request_tool_choice.as_ref(),
+                    include: context.request_include.as_deref(),
+                    text: context.request_text.as_ref(),
+                    modalities: context.request_modalities.as_deref(),
+                    auth_bearer: context.auth_bearer.as_deref(),
+                    forward_headers: &context.forward_headers,
+                    extra: Some(&context.request_extra).filter(|extra| !extra.is_empty()),
+                    controls: context.controls,
+                },
+                sender: self.sender.as_deref().filter(|_| live && context.client_connected),
+            })
+            .await
+    }
+
+    // Output is buffered while emulating so a reply without a tool call never reaches the client.
+    async fn generate_with_required_tool_call(
+        &self,
+        context: &ExecutionContext,
+    ) -> Result<ProviderOutcome, CoreError> {
+        let base = context.request_instructions.as_deref();
+        let instructions = tool_choice::emulated_instructions(base, false);
+        let outcome = self.generate_once(context, Some(&instructions), false).await?;
+        if outcome.tool_calls.as_ref().is_some_and(|calls| !calls.is_empty()) {
+            return Ok(outcome);
+        }
+        warn!(
+            event = "provider.tool_choice.emulation_retry",
+            provider_model = %context.model
+        );
+        let first_attempts = outcome.upstream_attempts;
+        let instructions = tool_choice::emulated_instructions(base, true);
+        let mut outcome = self.generate_once(context, Some(&instructions), false).await?;
+        outcome.upstream_attempts += first_attempts;
+        if outcome.tool_calls.as_ref().is_some_and(|calls| !calls.is_empty()) {
+            return Ok(outcome);
+        }
+        Err(CoreError::Provider(
+            "tool_choice required: provider returned no tool call after retry".to_string(),
+        ))
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -479,6 +837,7 @@ impl StageHandler for GenerateHandler {
             llm.provider = %canonical_llm.provider,
             llm.model_name = %canonical_llm.model_name,
             xrouter.model_id = %context.model,
+            enduser.id = context.user.as_deref().unwrap_or_default(),
             input.value = %truncate_text(&context.input, 512),
             output_tokens = field::Empty,
             chunk_count = field::Empty,
@@ -491,25 +850,21 @@ impl StageHandler for GenerateHandler {
             llm.token_count.total = field::Empty
         );
         provider_span.record("otel.name", "provider_generate");
-        let result = match self
-            .provider
-            .generate_stream(ProviderGenerateStreamRequest {
-                request_id: &context.request_id,
-                request: ProviderGenerateRequest {
-                    model: &context.model,
-                    instructions: context.request_instructions.as_deref(),
-                    input: &context.request_input,
-                    reasoning: context.request_reasoning.as_ref(),
-                    tools: context.request_tools.as_deref(),
-                    tool_choice: context.request_tool_choice.as_ref(),
-                    auth_bearer: context.auth_bearer.as_deref(),
-                    forward_headers: &context.forward_headers,
-                },
-                sender: self.sender.as_deref(),
-            })
-            .instrument(provider_span.clone())
-            .await
-        {
+        let emulate = self.emulate_required_tool_choice
+            && tool_choice::requires_tool_call(
+                context.request_tools.as_deref(),
+                context.request_tool_choice.as_ref(),
+            )
+            && !self.provider.supports_required_tool_choice();
+        let generation = if emulate {
+            debug!(event = "provider.tool_choice.emulated", provider_model = %context.model);
+            self.generate_with_required_tool_call(context).instrument(provider_span.clone()).await
+        } else {
+            self.generate_once(context, context.request_instructions.as_deref(), true)
+                .instrument(provider_span.clone())
+                .await
+        };
+        let result = match generation {
             Ok(result) => result,
             Err(error) => {
                 warn!(
@@ -540,8 +895,22 @@ impl StageHandler for GenerateHandler {
 
         context.output_tokens = result.output_tokens;
         context.tool_calls = result.tool_calls;
-        context.reasoning = result.reasoning;
-        context.reasoning_details = result.reasoning_details;
+        context.annotations = result.annotations;
+        if context.excludes_reasoning() {
+            context.reasoning = None;
+            context.reasoning_details = None;
+        } else {
+            context.reasoning = result.reasoning;
+            context.reasoning_details = result.reasoning_details;
+        }
+        // Encrypted reasoning is only surfaced when the client opted in through `include`.
+        context.reasoning_encrypted_content =
+            result.reasoning_encrypted_content.filter(|_| context.includes_encrypted_reasoning());
+        context.finish_reason = result.finish_reason;
+        context.provider_usage = result.usage;
+        context.upstream_attempts = result.upstream_attempts;
+        context.provider_response_id = result.provider_response_id;
+        context.upstream_provider = result.upstream_provider;
         if !result.emitted_live
             && context.client_connected
             && let (Some(reasoning), Some(sender)) = (&context.reasoning, &self.sender)
@@ -576,6 +945,9 @@ impl StageHandler for GenerateHandler {
 
 pub struct ExecutionEngine {
     provider: Arc<dyn ProviderClient>,
+    transforms: Vec<ScopedTransform>,
+    output_moderation: Option<OutputModeration>,
+    emulate_required_tool_choice: bool,
 }
 
 fn tool_call_id_from_response_id(response_id: &str) -> String {
@@ -608,8 +980,10 @@ fn parse_tool_call(output_text: &str, response_id: &str) -> Option<ToolCall> {
 fn build_output_items(
     _response_id: &str,
     output_text: &str,
+    annotations: Option<Vec<OutputAnnotation>>,
     reasoning: Option<String>,
     reasoning_details: Option<Vec<serde_json::Value>>,
+    reasoning_encrypted_content: Option<String>,
     tool_calls: Option<Vec<ToolCall>>,
 ) -> Vec<ResponseOutputItem> {
     let mut output = Vec::new();
@@ -620,13 +994,14 @@ fn build_output_items(
         content: vec![ResponseOutputText {
             kind: "output_text".to_string(),
             text: output_text.to_string(),
+            annotations: annotations.unwrap_or_default(),
         }],
     });
 
     let has_reasoning_text = reasoning.as_ref().is_some_and(|value| !value.trim().is_empty());
     let reasoning_content = reasoning_details.unwrap_or_default();
     let has_reasoning_details = !reasoning_content.is_empty();
-    if has_reasoning_text || has_reasoning_details {
+    if has_reasoning_text || has_reasoning_details || reasoning_encrypted_content.is_some() {
         let summary = reasoning
             .filter(|value| !value.trim().is_empty())
             .map(|text| vec![ResponseReasoningSummary { text }])
@@ -635,6 +1010,7 @@ fn build_output_items(
             id: "rs_0".to_string(),
             summary,
             content: reasoning_content,
+            encrypted_content: reasoning_encrypted_content,
         });
     }
 
@@ -657,8 +1033,12 @@ pub fn responses_response_from_outcome(
     input_tokens: u32,
     outcome: &ProviderOutcome,
 ) -> ResponsesResponse {
-    let finish_reason =
-        if outcome.tool_calls.is_some() { "tool_calls".to_string() } else { "stop".to_string() };
+    // Some upstreams report `stop` alongside tool calls; clients key off `tool_calls`.
+    let finish_reason = match outcome.finish_reason.as_deref() {
+        Some("stop") | None if outcome.tool_calls.is_some() => "tool_calls".to_string(),
+        Some(reason) => reason.to_string(),
+        None => "stop".to_string(),
+    };
     ResponsesResponse {
         id: response_id.to_string(),
         object: "response".to_string(),
@@ -666,16 +1046,49 @@ pub fn responses_response_from_outcome(
         output: build_output_items(
             response_id,
             &outcome.chunks.join(""),
+            outcome.annotations.clone(),
             outcome.reasoning.clone(),
             outcome.reasoning_details.clone(),
+            outcome.reasoning_encrypted_content.clone(),
             outcome.tool_calls.clone(),
         ),
         finish_reason,
-        usage: Usage {
-            input_tokens,
-            output_tokens: outcome.output_tokens,
-            total_tokens: input_tokens + outcome.output_tokens,
-        },
+        usage: usage_from_outcome(input_tokens, outcome),
+        meta: Some(ResponseMeta {
+            attempts: outcome.upstream_attempts,
+            provider_response_id: outcome.provider_response_id.clone(),
+            upstream_provider: outcome.upstream_provider.clone(),
+            ..ResponseMeta::default()
+        }),
+        budget: None,
+    }
+}
+
+fn cancelled_usage(context: &ExecutionContext) -> Usage {
+    let input_tokens = context.input.split_whitespace().count() as u32;
+    let output_tokens = context.output_text.split_whitespace().count() as u32;
+    Usage {
+        input_tokens,
+        output_tokens,
+        total_tokens: input_tokens + output_tokens,
+        input_tokens_details: None,
+        output_tokens_details: None,
+    }
+}
+
+fn usage_from_outcome(estimated_input_tokens: u32, outcome: &ProviderOutcome) -> Usage {
+    let native = outcome.usage.clone().unwrap_or_default();
+    let input_tokens = native.prompt_tokens.unwrap_or(estimated_input_tokens);
+    Usage {
+        input_tokens,
+        output_tokens: outcome.output_tokens,
+        total_tokens: input_tokens + outcome.output_tokens,
+        input_tokens_details: native
+            .cached_tokens
+            .map(|cached_tokens| InputTokensDetails { cached_tokens }),
+        output_tokens_details: native
+            .reasoning_tokens
+            .map(|reasoning_tokens| OutputTokensDetails { reasoning_tokens }),
     }
 }
 
@@ -690,16 +1103,52 @@ pub fn response_completed_event_from_outcome(
         output: response.output,
         finish_reason: response.finish_reason,
         usage: response.usage,
+        meta: response.meta,
     }
 }
 
 impl ExecutionEngine {
     pub fn new(provider: Arc<dyn ProviderClient>) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            transforms: Vec::new(),
+            output_moderation: None,
+            emulate_required_tool_choice: false,
+        }
+    }
+
+    pub fn with_transforms(mut self, transforms: Vec<ScopedTransform>) -> Self {
+        self.transforms = transforms;
+        self
+    }
+
+    pub fn with_output_moderation(mut self, moderation: OutputModeration) -> Self {
+        self.output_moderation = Some(moderation);
+        self
+    }
+
+    /// Emulates `tool_choice: "required"` for providers that cannot force tool use.
+    pub fn with_required_tool_choice_emulation(mut self, enabled: bool) -> Self {
+        self.emulate_required_tool_choice = enabled;
+        self
+    }
+
+    /// Runs the ingest and tokenize stages of `request` without calling the provider, and
+    /// returns the input token count tokenize produced.
+    pub async fn validate(&self, request: ResponsesRequest) -> Result<u32, CoreError> {
+        let mut context = ExecutionContext::new(
+            self.rewrite_request(request),
+            None,
+            Vec::new(),
+            ExecutionControls::default(),
+        );
+        IngestHandler.handle(&mut context).await?;
+        TokenizeHandler.handle(&mut context).await?;
+        Ok(context.input_tokens)
     }
 
     pub async fn execute(&self, request: ResponsesRequest) -> Result<ResponsesResponse, CoreError> {
-        self.execute_with_auth(request, None, Vec::new()).await
+        self.execute_with_auth(request, None, Vec::new(), ExecutionControls::default()).await
     }
 
     pub async fn execute_with_auth(
@@ -707,8 +1156,9 @@ impl ExecutionEngine {
         request: ResponsesRequest,
         auth_bearer: Option<String>,
         forward_headers: Vec<(String, String)>,
+        controls: ExecutionControls,
     ) -> Result<ResponsesResponse, CoreError> {
-        self.execute_internal(request, None, None, auth_bearer, forward_headers).await
+        self.execute_internal(request, None, None, auth_bearer, forward_headers, controls).await
     }
 
     pub async fn execute_with_disconnect(
@@ -716,7 +1166,15 @@ impl ExecutionEngine {
         request: ResponsesRequest,
         disconnect_at: Option<StageName>,
     ) -> Result<ResponsesResponse, CoreError> {
-        self.execute_internal(request, disconnect_at, None, None, Vec::new()).await
+        self.execute_internal(
+            request,
+            disconnect_at,
+            None,
+            None,
+            Vec::new(),
+            ExecutionControls::default(),
+        )
+        .await
     }
 
     pub async fn execute_stream_to_sink(
@@ -725,6 +1183,7 @@ impl ExecutionEngine {
         disconnect_at: Option<StageName>,
         auth_bearer: Option<String>,
         forward_headers: Vec<(String, String)>,
+        controls: ExecutionControls,
         sender: Arc<dyn ResponseEventSink>,
     ) -> Result<(), CoreError> {
         let execute_stream_span = info_span!(
@@ -741,6 +1200,7 @@ impl ExecutionEngine {
                 Some(sender.clone()),
                 auth_bearer,
                 forward_headers,
+                controls,
             )
             .instrument(execute_stream_span)
             .await;
@@ -762,14 +1222,54 @@ impl ExecutionEngine {
         sender: Option<Arc<dyn ResponseEventSink>>,
         auth_bearer: Option<String>,
         forward_headers: Vec<(String, String)>,
+        controls: ExecutionControls,
     ) -> Result<ResponsesResponse, CoreError> {
         let request_started_at = Instant::now();
-        let mut context = ExecutionContext::new(request, auth_bearer, forward_headers);
+        let request = self.rewrite_request(request);
+        let mut context = ExecutionContext::new(request, auth_bearer, forward_headers, controls);
+        let stream = sender.is_some();
+        let rewriters = self.stream_rewriters(&context.model);
+        let sender = match sender {
+            Some(inner) if !rewriters.is_empty() => Some(Arc::new(
+                transforms::TransformStreamSink::new(inner, context.request_id.clone(), rewriters),
+            )
+                as Arc<dyn ResponseEventSink>),
+            sender => sender,
+        };
+        let sender = match (sender, &self.output_moderation) {
+            (Some(inner), Some(moderation)) if moderation.buffer_stream => {
+                Some(Arc::new(moderation::ModerationBufferSink::new(inner))
+                    as Arc<dyn ResponseEventSink>)
+            }
+            (sender, _) => sender,
+        };
+        let sender = match sender {
+            Some(inner)
+                if context.request_stop.is_some()
+                    || context.request_max_output_tokens.is_some() =>
+            {
+                Some(Arc::new(output_guard::OutputGuardSink::new(
+                    inner,
+                    context.request_id.clone(),
+                    context.request_stop.clone(),
+                    context.request_max_output_tokens,
+                )) as Arc<dyn ResponseEventSink>)
+            }
+            sender => sender,
+        };
+        let sender = match sender {
+            Some(inner) if context.excludes_reasoning() => {
+                Some(Arc::new(reasoning_filter::ReasoningExcludeSink::new(inner))
+                    as Arc<dyn ResponseEventSink>)
+            }
+            sender => sender,
+        };
         info!(
             event = "core.request.started",
             request_id = %context.request_id,
             model = %context.model,
-            stream = sender.is_some(),
+            user = context.user.as_deref().unwrap_or_default(),
+            stream,
             input_chars = context.input.len()
         );
 
@@ -799,8 +1299,11 @@ impl ExecutionEngine {
             return Err(error);
         }
 
-        let generate =
-            GenerateHandler { provider: Arc::clone(&self.provider), sender: sender.clone() };
+        let generate = GenerateHandler {
+            provider: Arc::clone(&self.provider),
+            sender: sender.clone(),
+            emulate_required_tool_choice: self.emulate_required_tool_choice,
+        };
         if let Err(error) = self.run_stage(&generate, &mut context, disconnect_at.as_ref()).await {
             warn!(
                 event = "core.request.failed",
@@ -827,6 +1330,32 @@ impl ExecutionEngine {
                 "terminal state reached without completion".to_string(),
             ));
         }
+        if let Some(stops) = &context.request_stop
+            && let Some(cut) = find_stop_sequence(&context.output_text, stops)
+        {
+            context.output_text.truncate(cut);
+            info!(
+                event = "core.stop_sequence.matched",
+                request_id = %context.request_id,
+                model = %context.model,
+                output_chars = cut
+            );
+        }
+        if let Some(limit) = context.request_max_output_tokens
+            && let Some(cut) = truncate_to_output_tokens(&context.output_text, limit)
+        {
+            context.output_text.truncate(cut);
+            context.finish_reason = Some("length".to_string());
+            info!(
+                event = "core.max_output_tokens.reached",
+                request_id = %context.request_id,
+                model = %context.model,
+                max_output_tokens = limit,
+                output_chars = cut
+            );
+        }
+        self.process_output(&mut context);
+        self.moderate_output(&mut context);
 
         let tool_calls = context.tool_calls.clone().or_else(|| {
             parse_tool_call(&context.output_text, &context.request_id).map(|call| vec![call])
@@ -836,8 +1365,15 @@ impl ExecutionEngine {
             output_tokens: context.output_tokens,
             reasoning: context.reasoning.clone(),
             reasoning_details: context.reasoning_details.clone(),
+            reasoning_encrypted_content: context.reasoning_encrypted_content.clone(),
             tool_calls: tool_calls.clone(),
+            annotations: context.annotations.clone(),
             emitted_live: true,
+            finish_reason: context.finish_reason.clone(),
+            usage: context.provider_usage.clone(),
+            upstream_attempts: context.upstream_attempts,
+            provider_response_id: context.provider_response_id.clone(),
+            upstream_provider: context.upstream_provider.clone(),
         };
 
         if let Some(tx) = sender {
@@ -858,6 +1394,7 @@ impl ExecutionEngine {
         info!(
             event = "core.request.completed",
             request_id = %response.id,
+            user = context.user.as_deref().unwrap_or_default(),
             status = %response.status,
             finish_reason = %finish_reason,
             input_tokens = response.usage.input_tokens,
@@ -869,6 +1406,60 @@ impl ExecutionEngine {
         Ok(response)
     }
 
+    /// Applies the request transforms scoped to `request.model`, as execution does before the
+    /// provider is called.
+    pub fn rewrite_request(&self, mut request: ResponsesRequest) -> ResponsesRequest {
+        let model = request.model.clone();
+        for scoped in self.transforms.iter().filter(|scoped| scoped.applies_to(&model)) {
+            scoped.transform().rewrite_request(&mut request);
+            debug!(event = "core.transform.request.applied", transform = scoped.name(), model = %model);
+        }
+        request
+    }
+
+    fn stream_rewriters(&self, model: &str) -> Vec<Box<dyn OutputStreamRewriter>> {
+        self.transforms
+            .iter()
+            .filter(|scoped| scoped.applies_to(model))
+            .filter_map(|scoped| scoped.transform().stream_rewriter())
+            .collect()
+    }
+
+    fn process_output(&self, context: &mut ExecutionContext) {
+        for scoped in self.transforms.iter().filter(|scoped| scoped.applies_to(&context.model)) {
+            let before_chars = context.output_text.len();
+            scoped.transform().process_output(&mut context.output_text);
+            debug!(
+                event = "core.transform.output.applied",
+                request_id = %context.request_id,
+                transform = scoped.name(),
+                model = %context.model,
+                before_chars,
+                after_chars = context.output_text.len()
+            );
+        }
+    }
+
+    fn moderate_output(&self, context: &mut ExecutionContext) {
+        let Some(moderation) = &self.output_moderation else {
+            return;
+        };
+        let Some(violation) = moderation.policy.evaluate(&context.output_text) else {
+            return;
+        };
+        warn!(
+            event = "core.output_moderation.flagged",
+            request_id = %context.request_id,
+            model = %context.model,
+            policy = moderation.policy.name(),
+            violation = %violation,
+            buffered_stream = moderation.buffer_stream
+        );
+        context.output_text = moderation.message.clone();
+        context.tool_calls = None;
+        context.finish_reason = Some(CONTENT_FILTER_FINISH_REASON.to_string());
+    }
+
     async fn run_stage<H: StageHandler>(
         &self,
         handler: &H,
@@ -890,18 +1481,18 @@ impl ExecutionEngine {
             let stage_started_at = Instant::now();
             info!(event = "pipeline.stage.started");
             if disconnect_at == Some(&stage) {
+                // A disconnect cancels the generation in every stage; the usage consumed so
+                // far is settled on the error so callers can bill it.
                 context.client_connected = false;
-                match stage {
-                    StageName::Ingest | StageName::Tokenize => {
-                        context.state = KernelState::Failed;
-                        warn!(
-                            event = "pipeline.stage.disconnected",
-                            duration_ms = stage_started_at.elapsed().as_millis() as u64
-                        );
-                        return Err(CoreError::ClientDisconnected(stage));
-                    }
-                    StageName::Generate => {}
-                }
+                context.state = KernelState::Failed;
+                let usage = cancelled_usage(context);
+                warn!(
+                    event = "pipeline.stage.disconnected",
+                    input_tokens = usage.input_tokens,
+                    output_tokens = usage.output_tokens,
+                    duration_ms = stage_started_at.elapsed().as_millis() as u64
+                );
+                return Err(CoreError::ClientDisconnected { stage, usage });
             }
 
             let result = handler.handle(context).await;
@@ -1017,8 +1608,15 @@ mod tests {
                         chunks,
                         reasoning: None,
                         reasoning_details: None,
+                        reasoning_encrypted_content: None,
                         tool_calls: None,
+                        annotations: None,
                         emitted_live: false,
+                        finish_reason: None,
+                        usage: None,
+                        upstream_attempts: 1,
+                        provider_response_id: None,
+                        upstream_provider: None,
                     })
                 }
                 ProviderBehavior::Fail => Err(CoreError::Provider("provider failed".to_string())),
@@ -1054,15 +1652,23 @@ mod tests {
                     response.status, output_text, response.usage.total_tokens
                 )
             }
-            Err(error) => format!("kind=err\nerror_kind={}\nerror={}", error_kind(&error), error),
+            Err(error) => {
+                let mut rendered =
+                    format!("kind=err\nerror_kind={}\nerror={}", error_kind(&error), error);
+                if let CoreError::ClientDisconnected { usage, .. } = &error {
+                    rendered.push_str(&format!("\nusage_total={}", usage.total_tokens));
+                }
+                rendered
+            }
         }
     }
 
     fn error_kind(error: &CoreError) -> &'static str {
         match error {
             CoreError::Validation(_) => "Validation",
+            CoreError::InvalidParam { .. } => "InvalidParam",
             CoreError::Provider(_) => "Provider",
-            CoreError::ClientDisconnected(_) => "ClientDisconnected",
+            CoreError::ClientDisconnected { .. } => "ClientDisconnected",
         }
     }
 
@@ -1092,8 +1698,13 @@ mod tests {
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
         let result = engine.execute_with_disconnect(request, disconnect).await;
         let actual_snapshot = render_result(result);
@@ -1144,21 +1755,22 @@ disconnect=ingest
 kind=err
 error_kind=ClientDisconnected
 error=client disconnected during Ingest
+usage_total=1
 "#,
             ),
             (
                 r#"
-name=disconnect_generate_does_not_cancel
+name=disconnect_generate_cancels_and_records_usage
 model=fake
 input=world
 provider=success
 disconnect=generate
 "#,
                 r#"
-kind=ok
-status=completed
-output=hello world
-usage_total=3
+kind=err
+error_kind=ClientDisconnected
+error=client disconnected during Generate
+usage_total=1
 "#,
             ),
         ];
@@ -1195,6 +1807,7 @@ usage_total=3
     struct AuthCaptureProvider {
         seen: Arc<Mutex<Option<String>>>,
         seen_headers: Arc<Mutex<Vec<(String, String)>>>,
+        seen_controls: Arc<Mutex<Option<ExecutionControls>>>,
     }
 
     struct CaptureSink {
@@ -1220,13 +1833,21 @@ usage_total=3
                 request.auth_bearer.map(ToString::to_string);
             *self.seen_headers.lock().expect("lock must succeed") =
                 request.forward_headers.to_vec();
+            *self.seen_controls.lock().expect("lock must succeed") = Some(request.controls);
             Ok(ProviderOutcome {
                 chunks: vec!["ok".to_string()],
                 output_tokens: 1,
                 reasoning: None,
                 reasoning_details: None,
+                reasoning_encrypted_content: None,
                 tool_calls: None,
+                annotations: None,
                 emitted_live: false,
+                finish_reason: None,
+                usage: None,
+                upstream_attempts: 1,
+                provider_response_id: None,
+                upstream_provider: None,
             })
         }
     }
@@ -1238,6 +1859,7 @@ usage_total=3
         let provider = Arc::new(AuthCaptureProvider {
             seen: seen.clone(),
             seen_headers: seen_headers.clone(),
+            seen_controls: Arc::default(),
         });
         let engine = ExecutionEngine::new(provider);
         let request = ResponsesRequest {
@@ -1253,12 +1875,22 @@ usage_total=3
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
 
         let _ = engine
-            .execute_with_auth(request, Some("byok-test-token".to_string()), Vec::new())
+            .execute_with_auth(
+                request,
+                Some("byok-test-token".to_string()),
+                Vec::new(),
+                ExecutionControls::default(),
+            )
             .await
             .expect("request must succeed");
 
@@ -1274,6 +1906,7 @@ usage_total=3
         let provider = Arc::new(AuthCaptureProvider {
             seen: seen.clone(),
             seen_headers: seen_headers.clone(),
+            seen_controls: Arc::default(),
         });
         let engine = ExecutionEngine::new(provider);
         let request = ResponsesRequest {
@@ -1289,8 +1922,13 @@ usage_total=3
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
 
         let _ = engine.execute(request).await.expect("request must succeed");
@@ -1299,12 +1937,14 @@ usage_total=3
     }
 
     #[tokio::test]
-    async fn execute_with_auth_passes_forward_headers_to_provider() {
+    async fn execute_with_auth_passes_forward_headers_and_controls_to_provider() {
         let seen = Arc::new(Mutex::new(None));
         let seen_headers = Arc::new(Mutex::new(Vec::new()));
+        let seen_controls = Arc::new(Mutex::new(None));
         let provider = Arc::new(AuthCaptureProvider {
             seen: seen.clone(),
             seen_headers: seen_headers.clone(),
+            seen_controls: seen_controls.clone(),
         });
         let engine = ExecutionEngine::new(provider);
         let request = ResponsesRequest {
@@ -1320,8 +1960,13 @@ usage_total=3
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
 
         let forward_headers = vec![
@@ -1329,13 +1974,22 @@ usage_total=3
             ("X-OpenRouter-Title".to_string(), "Example App".to_string()),
         ];
 
+        let controls = ExecutionControls {
+            priority: RequestPriority::Batch,
+            upstream: UpstreamLimits {
+                timeout: Some(Duration::from_millis(1500)),
+                max_retries: Some(0),
+            },
+        };
+
         let _ = engine
-            .execute_with_auth(request, None, forward_headers.clone())
+            .execute_with_auth(request, None, forward_headers.clone(), controls)
             .await
             .expect("request must succeed");
 
         assert_eq!(seen.lock().expect("lock must succeed").as_deref(), None);
         assert_eq!(*seen_headers.lock().expect("lock must succeed"), forward_headers);
+        assert_eq!(*seen_controls.lock().expect("lock must succeed"), Some(controls));
     }
 
     #[tokio::test]
@@ -1356,12 +2010,24 @@ usage_total=3
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
 
         engine
-            .execute_stream_to_sink(request, None, None, Vec::new(), sink)
+            .execute_stream_to_sink(
+                request,
+                None,
+                None,
+                Vec::new(),
+                ExecutionControls::default(),
+                sink,
+            )
             .await
             .expect("stream request must succeed");
 
@@ -1389,11 +2055,25 @@ usage_total=3
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
 
-        let result = engine.execute_stream_to_sink(request, None, None, Vec::new(), sink).await;
+        let result = engine
+            .execute_stream_to_sink(
+                request,
+                None,
+                None,
+                Vec::new(),
+                ExecutionControls::default(),
+                sink,
+            )
+            .await;
 
         assert!(matches!(result, Err(CoreError::Provider(_))));
         let events = events.lock().expect("lock must succeed");
@@ -1420,15 +2100,39 @@ usage_total=3
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
 
         let result = engine
-            .execute_stream_to_sink(request, Some(StageName::Ingest), None, Vec::new(), sink)
+            .execute_stream_to_sink(
+                request,
+                Some(StageName::Ingest),
+                None,
+                Vec::new(),
+                ExecutionControls::default(),
+                sink,
+            )
             .await;
 
-        assert_eq!(result, Err(CoreError::ClientDisconnected(StageName::Ingest)));
+        assert_eq!(
+            result,
+            Err(CoreError::ClientDisconnected {
+                stage: StageName::Ingest,
+                usage: Usage {
+                    input_tokens: 1,
+                    output_tokens: 0,
+                    total_tokens: 1,
+                    input_tokens_details: None,
+                    output_tokens_details: None,
+                },
+            })
+        );
         let events = events.lock().expect("lock must succeed");
         assert!(
             events.iter().any(|event| matches!(event, Ok(ResponseEvent::ResponseError { .. }))),
@@ -1443,7 +2147,7 @@ usage_total=3
     }
 
     #[tokio::test]
-    async fn execute_stream_to_sink_with_generate_disconnect_keeps_completed_event() {
+    async fn execute_stream_to_sink_with_generate_disconnect_cancels_and_records_usage() {
         let engine = ExecutionEngine::new(build_provider(ProviderBehavior::Success));
         let events = Arc::new(Mutex::new(Vec::new()));
         let sink = Arc::new(CaptureSink { events: events.clone() });
@@ -1460,26 +2164,426 @@ usage_total=3
             service_tier: None,
             prompt_cache_key: None,
             text: None,
+            modalities: None,
             tools: None,
             tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
         };
 
-        engine
-            .execute_stream_to_sink(request, Some(StageName::Generate), None, Vec::new(), sink)
-            .await
-            .expect("generate disconnect must not cancel in-flight generation");
+        let result = engine
+            .execute_stream_to_sink(
+                request,
+                Some(StageName::Generate),
+                None,
+                Vec::new(),
+                ExecutionControls::default(),
+                sink,
+            )
+            .await;
 
+        let Err(CoreError::ClientDisconnected { stage, usage }) = result else {
+            panic!("generate disconnect must cancel the generation, got {result:?}");
+        };
+        assert_eq!(stage, StageName::Generate);
+        assert_eq!((usage.input_tokens, usage.output_tokens, usage.total_tokens), (1, 0, 1));
         let events = events.lock().expect("lock must succeed");
         assert!(
-            events.iter().any(|event| matches!(event, Ok(ResponseEvent::ResponseCompleted { .. }))),
-            "generate disconnect must still emit completion"
+            events.iter().any(|event| matches!(event, Ok(ResponseEvent::ResponseError { .. }))),
+            "generate disconnect must surface as stream error event"
         );
         assert!(
-            !events.iter().any(|event| matches!(event, Err(CoreError::ClientDisconnected(_)))),
-            "generate disconnect must not surface as stream error"
+            !events
+                .iter()
+                .any(|event| matches!(event, Ok(ResponseEvent::ResponseCompleted { .. }))),
+            "generate disconnect must not emit completion"
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_applies_output_transforms_scoped_to_request_model() {
+        let stop = build_builtin_transform(
+            "stop_sequences",
+            serde_json::json!({"stop": [" STOP"]}).as_object().expect("params must be an object"),
+        )
+        .expect("transform must build");
+        let redact = build_builtin_transform(
+            "redact",
+            serde_json::json!({"patterns": ["hello"]})
+                .as_object()
+                .expect("params must be an object"),
+        )
+        .expect("transform must build");
+        let engine = ExecutionEngine::new(build_provider(ProviderBehavior::Success))
+            .with_transforms(vec![
+                ScopedTransform::new(stop, Some("fake".to_string())),
+                ScopedTransform::new(redact, Some("other".to_string())),
+            ]);
+        let request = ResponsesRequest {
+            model: "fake".to_string(),
+            instructions: None,
+            previous_response_id: None,
+            input: xrouter_contracts::ResponsesInput::Text("world STOP tail".to_string()),
+            parallel_tool_calls: None,
+            stream: false,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
+        };
+
+        let response = engine.execute(request).await.expect("request must succeed");
+        assert!(response.output.iter().any(|item| matches!(
+            item,
+            ResponseOutputItem::Message { content, .. }
+            if content.first().is_some_and(|part| part.text == "hello world")
+        )));
+    }
+
+    #[tokio::test]
+    async fn execute_stream_to_sink_truncates_output_at_stop_sequence() {
+        let engine = ExecutionEngine::new(build_provider(ProviderBehavior::Success));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::new(CaptureSink { events: events.clone() });
+        let request = ResponsesRequest {
+            model: "fake".to_string(),
+            instructions: None,
+            previous_response_id: None,
+            input: xrouter_contracts::ResponsesInput::Text("world STOP tail".to_string()),
+            parallel_tool_calls: None,
+            stream: true,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: Some(vec![" STOP".to_string()]),
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
+        };
+
+        engine
+            .execute_stream_to_sink(
+                request,
+                None,
+                None,
+                Vec::new(),
+                ExecutionControls::default(),
+                sink,
+            )
+            .await
+            .expect("stream request must succeed");
+
+        let events = events.lock().expect("lock must succeed");
+        let streamed = events
+            .iter()
+            .filter_map(|event| match event {
+                Ok(ResponseEvent::OutputTextDelta { delta, .. }) => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+        assert_eq!(streamed, "hello world");
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Ok(ResponseEvent::ResponseCompleted { output, finish_reason, .. })
+            if finish_reason == "stop"
+                && matches!(
+                    output.first(),
+                    Some(ResponseOutputItem::Message { content, .. })
+                    if content.first().is_some_and(|part| part.text == "hello world")
+                )
+        )));
+    }
+
+    #[tokio::test]
+    async fn execute_stream_to_sink_redacts_patterns_split_across_deltas() {
+        let redact = build_builtin_transform(
+            "redact",
+            serde_json::json!({"patterns": ["lo wor"]})
+                .as_object()
+                .expect("params must be an object"),
+        )
+        .expect("transform must build");
+        let engine = ExecutionEngine::new(build_provider(ProviderBehavior::Success))
+            .with_transforms(vec![ScopedTransform::new(redact, Some("fake".to_string()))]);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::new(CaptureSink { events: events.clone() });
+        let request = ResponsesRequest {
+            model: "fake".to_string(),
+            instructions: None,
+            previous_response_id: None,
+            input: xrouter_contracts::ResponsesInput::Text("world".to_string()),
+            parallel_tool_calls: None,
+            stream: true,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
+        };
+
+        engine
+            .execute_stream_to_sink(
+                request,
+                None,
+                None,
+                Vec::new(),
+                ExecutionControls::default(),
+                sink,
+            )
+            .await
+            .expect("stream request must succeed");
+
+        let events = events.lock().expect("lock must succeed");
+        let streamed = events
+            .iter()
+            .filter_map(|event| match event {
+                Ok(ResponseEvent::OutputTextDelta { delta, .. }) => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+        assert_eq!(streamed, "hel[REDACTED]ld");
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Ok(ResponseEvent::ResponseCompleted { output, .. })
+            if matches!(
+                output.first(),
+                Some(ResponseOutputItem::Message { content, .. })
+                if content.first().is_some_and(|part| part.text == "hel[REDACTED]ld")
+            )
+        )));
+    }
+
+    #[tokio::test]
+    async fn execute_stream_to_sink_cuts_output_at_max_output_tokens_with_length_reason() {
+        let engine = ExecutionEngine::new(build_provider(ProviderBehavior::Success));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::new(CaptureSink { events: events.clone() });
+        let request = ResponsesRequest {
+            model: "fake".to_string(),
+            instructions: None,
+            previous_response_id: None,
+            input: xrouter_contracts::ResponsesInput::Text("world foo bar".to_string()),
+            parallel_tool_calls: None,
+            stream: true,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: None,
+            max_output_tokens: Some(2),
+            user: None,
+            extra: serde_json::Map::new(),
+        };
+
+        engine
+            .execute_stream_to_sink(
+                request,
+                None,
+                None,
+                Vec::new(),
+                ExecutionControls::default(),
+                sink,
+            )
+            .await
+            .expect("stream request must succeed");
+
+        let events = events.lock().expect("lock must succeed");
+        let streamed = events
+            .iter()
+            .filter_map(|event| match event {
+                Ok(ResponseEvent::OutputTextDelta { delta, .. }) => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+        assert_eq!(streamed, "hello world ");
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Ok(ResponseEvent::ResponseCompleted { finish_reason, .. }) if finish_reason == "length"
+        )));
+    }
+
+    fn moderated_engine(buffer_stream: bool) -> ExecutionEngine {
+        ExecutionEngine::new(build_provider(ProviderBehavior::Success)).with_output_moderation(
+            OutputModeration::new(
+                Arc::new(BlocklistModerationPolicy::new(vec!["forbidden".to_string()])),
+                "withheld".to_string(),
+                buffer_stream,
+            ),
+        )
+    }
+
+    fn text_request(input: &str, stream: bool) -> ResponsesRequest {
+        ResponsesRequest {
+            model: "fake".to_string(),
+            instructions: None,
+            previous_response_id: None,
+            input: xrouter_contracts::ResponsesInput::Text(input.to_string()),
+            parallel_tool_calls: None,
+            stream,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: None,
+            tool_choice: None,
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_replaces_flagged_output_with_policy_message() {
+        let response = moderated_engine(false)
+            .execute(text_request("forbidden words", false))
+            .await
+            .expect("request must succeed");
+        assert_eq!(response.finish_reason, "content_filter");
+        assert_eq!(render_result(Ok(response)).lines().nth(2), Some("output=withheld"));
+
+        let clean = moderated_engine(false)
+            .execute(text_request("fine words", false))
+            .await
+            .expect("request must succeed");
+        assert_eq!(clean.finish_reason, "stop");
+    }
+
+    #[tokio::test]
+    async fn buffered_stream_moderation_releases_only_policy_message_on_violation() {
+        for (input, expected_stream, expected_reason) in [
+            ("forbidden words", "withheld", "content_filter"),
+            ("fine words", "hello fine words", "stop"),
+        ] {
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let sink = Arc::new(CaptureSink { events: events.clone() });
+            moderated_engine(true)
+                .execute_stream_to_sink(
+                    text_request(input, true),
+                    None,
+                    None,
+                    Vec::new(),
+                    ExecutionControls::default(),
+                    sink,
+                )
+                .await
+                .expect("stream request must succeed");
+
+            let events = events.lock().expect("lock must succeed");
+            let streamed = events
+                .iter()
+                .filter_map(|event| match event {
+                    Ok(ResponseEvent::OutputTextDelta { delta, .. }) => Some(delta.as_str()),
+                    _ => None,
+                })
+                .collect::<String>();
+            assert_eq!(streamed, expected_stream);
+            assert!(events.iter().any(|event| matches!(
+                event,
+                Ok(ResponseEvent::ResponseCompleted { finish_reason, .. })
+                if finish_reason == expected_reason
+            )));
+        }
+    }
+
+    #[test]
+    fn responses_response_from_outcome_keeps_native_finish_reason() {
+        let mut outcome = ProviderOutcome {
+            chunks: vec!["partial".to_string()],
+            output_tokens: 3,
+            reasoning: None,
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls: None,
+            annotations: None,
+            emitted_live: false,
+            finish_reason: Some("length".to_string()),
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
+        };
+        assert_eq!(responses_response_from_outcome("resp_1", 1, &outcome).finish_reason, "length");
+
+        outcome.finish_reason = Some("stop".to_string());
+        outcome.tool_calls = Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            kind: "function".to_string(),
+            function: ToolFunction { name: "f".to_string(), arguments: "{}".to_string() },
+        }]);
+        assert_eq!(
+            responses_response_from_outcome("resp_1", 1, &outcome).finish_reason,
+            "tool_calls"
         );
     }
 
+    #[test]
+    fn responses_usage_prefers_native_provider_counts() {
+        let outcome = ProviderOutcome {
+            chunks: vec!["ok".to_string()],
+            output_tokens: 7,
+            reasoning: None,
+            reasoning_details: None,
+            reasoning_encrypted_content: None,
+            tool_calls: None,
+            annotations: None,
+            emitted_live: false,
+            finish_reason: None,
+            usage: Some(ProviderUsage {
+                prompt_tokens: Some(120),
+                reasoning_tokens: Some(4),
+                cached_tokens: Some(100),
+            }),
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
+        };
+
+        let usage = responses_response_from_outcome("resp_1", 3, &outcome).usage;
+        assert_eq!(usage.input_tokens, 120);
+        assert_eq!(usage.total_tokens, 127);
+        assert_eq!(usage.input_tokens_details, Some(InputTokensDetails { cached_tokens: 100 }));
+        assert_eq!(usage.output_tokens_details, Some(OutputTokensDetails { reasoning_tokens: 4 }));
+
+        let estimated = ProviderOutcome { usage: None, ..outcome };
+        let usage = responses_response_from_outcome("resp_1", 3, &estimated).usage;
+        assert_eq!(usage.input_tokens, 3);
+        assert_eq!(usage.input_tokens_details, None);
+    }
+
     #[test]
     fn responses_response_from_outcome_preserves_function_calls() {
         let outcome = ProviderOutcome {
@@ -1487,6 +2591,7 @@ usage_total=3
             output_tokens: 3,
             reasoning: None,
             reasoning_details: None,
+            reasoning_encrypted_content: None,
             tool_calls: Some(vec![ToolCall {
                 id: "call_123".to_string(),
                 kind: "function".to_string(),
@@ -1495,7 +2600,13 @@ usage_total=3
                     arguments: "{\"city\":\"Moscow\"}".to_string(),
                 },
             }]),
+            annotations: None,
             emitted_live: true,
+            finish_reason: None,
+            usage: None,
+            upstream_attempts: 1,
+            provider_response_id: None,
+            upstream_provider: None,
         };
 
         let response = responses_response_from_outcome("resp_1", 5, &outcome);
@@ -1507,4 +2618,250 @@ usage_total=3
         )));
         assert_eq!(response.usage.total_tokens, 8);
     }
+
+    struct NoRequiredToolChoiceProvider {
+        tool_call_on_attempt: usize,
+        seen_instructions: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+    impl ProviderClient for NoRequiredToolChoiceProvider {
+        async fn generate(
+            &self,
+            request: ProviderGenerateRequest<'_>,
+        ) -> Result<ProviderOutcome, CoreError> {
+            let mut seen = self.seen_instructions.lock().expect("lock must succeed");
+            seen.push(request.instructions.map(ToString::to_string));
+            let tool_calls = (seen.len() == self.tool_call_on_attempt).then(|| {
+                vec![ToolCall {
+                    id: "call_1".to_string(),
+                    kind: "function".to_string(),
+                    function: ToolFunction {
+                        name: "list_dir".to_string(),
+                        arguments: "{}".to_string(),
+                    },
+                }]
+            });
+            Ok(ProviderOutcome {
+                chunks: vec!["plain answer".to_string()],
+                output_tokens: 2,
+                reasoning: None,
+                reasoning_details: None,
+                reasoning_encrypted_content: None,
+                tool_calls,
+                annotations: None,
+                emitted_live: false,
+                finish_reason: None,
+                usage: None,
+                upstream_attempts: 1,
+                provider_response_id: None,
+                upstream_provider: None,
+            })
+        }
+
+        fn supports_required_tool_choice(&self) -> bool {
+            false
+        }
+    }
+
+    fn required_tool_choice_request() -> ResponsesRequest {
+        ResponsesRequest {
+            model: "fake".to_string(),
+            instructions: Some("be brief".to_string()),
+            previous_response_id: None,
+            input: xrouter_contracts::ResponsesInput::Text("list files".to_string()),
+            parallel_tool_calls: None,
+            stream: false,
+            reasoning: None,
+            store: None,
+            include: None,
+            service_tier: None,
+            prompt_cache_key: None,
+            text: None,
+            modalities: None,
+            tools: Some(vec![serde_json::json!({"type":"function","name":"list_dir"})]),
+            tool_choice: Some(serde_json::json!("required")),
+            stop: None,
+            max_output_tokens: None,
+            user: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn required_tool_choice_emulation_retries_once_with_stronger_directive() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let provider = Arc::new(NoRequiredToolChoiceProvider {
+            tool_call_on_attempt: 2,
+            seen_instructions: seen.clone(),
+        });
+        let engine = ExecutionEngine::new(provider).with_required_tool_choice_emulation(true);
+
+        let response =
+            engine.execute(required_tool_choice_request()).await.expect("request must succeed");
+
+        assert_eq!(response.finish_reason, "tool_calls");
+        assert_eq!(response.meta.map(|meta| meta.attempts), Some(2));
+        let seen = seen.lock().expect("lock must succeed");
+        assert_eq!(seen.len(), 2);
+        let first = seen[0].as_deref().expect("instructions must be set");
+        let retry = seen[1].as_deref().expect("instructions must be set");
+        assert!(first.starts_with("be brief\n\nYou must respond by calling"));
+        assert!(retry.contains("Your previous reply did not call a tool."));
+    }
+
+    #[tokio::test]
+    async fn required_tool_choice_emulation_fails_when_retry_has_no_tool_call() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let provider = Arc::new(NoRequiredToolChoiceProvider {
+            tool_call_on_attempt: 0,
+            seen_instructions: seen.clone(),
+        });
+        let engine = ExecutionEngine::new(provider).with_required_tool_choice_emulation(true);
+
+        let result = engine.execute(required_tool_choice_request()).await;
+
+        assert!(matches!(
+            result,
+            Err(CoreError::Provider(message)) if message.starts_with("tool_choice required:")
+        ));
+        assert_eq!(seen.lock().expect("lock must succeed").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn required_tool_choice_passes_through_when_emulation_disabled() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let provider = Arc::new(NoRequiredToolChoiceProvider {
+            tool_call_on_attempt: 0,
+            seen_instructions: seen.clone(),
+        });
+        let engine = ExecutionEngine::new(provider);
+
+        let response =
+            engine.execute(required_tool_choice_request()).await.expect("request must succeed");
+
+        assert_eq!(response.finish_reason, "stop");
+        assert_eq!(*seen.lock().expect("lock must succeed"), vec![Some("be brief".to_string())]);
+    }
+
+    struct EncryptedReasoningProvider {
+        seen_include: Arc<Mutex<Option<Vec<String>>>>,
+    }
+
+    #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+    impl ProviderClient for EncryptedReasoningProvider {
+        async fn generate(
+            &self,
+            request: ProviderGenerateRequest<'_>,
+        ) -> Result<ProviderOutcome, CoreError> {
+            *self.seen_include.lock().expect("lock must succeed") =
+                request.include.map(<[String]>::to_vec);
+            Ok(ProviderOutcome {
+                chunks: vec!["ok".to_string()],
+                output_tokens: 1,
+                reasoning: Some("thought".to_string()),
+                reasoning_details: None,
+                reasoning_encrypted_content: Some("gAAA-opaque".to_string()),
+                tool_calls: None,
+                annotations: None,
+                emitted_live: false,
+                finish_reason: None,
+                usage: None,
+                upstream_attempts: 1,
+                provider_response_id: Some("gen-42".to_string()),
+                upstream_provider: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn encrypted_reasoning_is_returned_only_when_included() {
+        let seen_include = Arc::new(Mutex::new(None));
+        let engine = ExecutionEngine::new(Arc::new(EncryptedReasoningProvider {
+            seen_include: seen_include.clone(),
+        }));
+        let encrypted_content = |response: &ResponsesResponse| {
+            response.output.iter().find_map(|item| match item {
+                ResponseOutputItem::Reasoning { encrypted_content, .. } => {
+                    encrypted_content.clone()
+                }
+                _ => None,
+            })
+        };
+
+        let mut request = text_request("hello", false);
+        let plain = engine.execute(request.clone()).await.expect("request must succeed");
+        assert_eq!(encrypted_content(&plain), None);
+        assert_eq!(
+            plain.meta.and_then(|meta| meta.provider_response_id).as_deref(),
+            Some("gen-42")
+        );
+        assert_eq!(*seen_include.lock().expect("lock must succeed"), None);
+
+        request.include = Some(vec![INCLUDE_REASONING_ENCRYPTED_CONTENT.to_string()]);
+        let included = engine.execute(request).await.expect("request must succeed");
+        assert_eq!(encrypted_content(&included).as_deref(), Some("gAAA-opaque"));
+        assert_eq!(
+            *seen_include.lock().expect("lock must succeed"),
+            Some(vec!["reasoning.encrypted_content".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn excluded_reasoning_is_dropped_from_stream_and_output() {
+        let engine = ExecutionEngine::new(Arc::new(EncryptedReasoningProvider {
+            seen_include: Arc::new(Mutex::new(None)),
+        }));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::new(CaptureSink { events: events.clone() });
+        let mut request = text_request("hello", true);
+        request.reasoning = Some(ReasoningConfig { exclude: Some(true), ..Default::default() });
+
+        engine
+            .execute_stream_to_sink(
+                request,
+                None,
+                None,
+                Vec::new(),
+                ExecutionControls::default(),
+                sink,
+            )
+            .await
+            .expect("stream request must succeed");
+
+        let events = events.lock().expect("lock must succeed");
+        assert!(
+            !events.iter().any(|event| matches!(event, Ok(ResponseEvent::ReasoningDelta { .. })))
+        );
+        let Some(Ok(ResponseEvent::ResponseCompleted { output, .. })) = events.last() else {
+            panic!("expected completed event, got {events:?}");
+        };
+        assert!(!output.iter().any(|item| matches!(item, ResponseOutputItem::Reasoning { .. })));
+    }
+
+    #[tokio::test]
+    async fn validate_runs_ingest_and_tokenize_without_calling_the_provider() {
+        let seen_instructions = Arc::new(Mutex::new(Vec::new()));
+        let engine = ExecutionEngine::new(Arc::new(NoRequiredToolChoiceProvider {
+            tool_call_on_attempt: 0,
+            seen_instructions: seen_instructions.clone(),
+        }));
+
+        let tokens = engine
+            .validate(text_request("count these four words", false))
+            .await
+            .expect("request must validate");
+        assert_eq!(tokens, 4);
+        assert!(seen_instructions.lock().expect("lock must succeed").is_empty());
+
+        let mut request = text_request("hello", false);
+        request.tool_choice =
+            Some(serde_json::json!({"type": "function", "function": {"name": "missing"}}));
+        let error = engine.validate(request).await.expect_err("undeclared tool must fail");
+        assert!(matches!(error, CoreError::InvalidParam { .. }), "{error:?}");
+        let error = engine.validate(text_request("  ", false)).await.expect_err("must fail");
+        assert!(matches!(error, CoreError::Validation(_)), "{error:?}");
+    }
 }
@@ -0,0 +1,142 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use xrouter_contracts::{ResponseEvent, ResponseOutputItem};
+
+use crate::{CoreError, ResponseEventSink};
+
+pub const CONTENT_FILTER_FINISH_REASON: &str = "content_filter";
+
+pub trait OutputModerationPolicy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn evaluate(&self, output_text: &str) -> Option<String>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlocklistModerationPolicy {
+    terms: Vec<String>,
+}
+
+impl BlocklistModerationPolicy {
+    pub fn new(terms: Vec<String>) -> Self {
+        Self {
+            terms: terms
+                .into_iter()
+                .map(|term| term.trim().to_lowercase())
+                .filter(|term| !term.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl OutputModerationPolicy for BlocklistModerationPolicy {
+    fn name(&self) -> &'static str {
+        "blocklist"
+    }
+
+    fn evaluate(&self, output_text: &str) -> Option<String> {
+        let lowered = output_text.to_lowercase();
+        self.terms
+            .iter()
+            .find(|term| lowered.contains(term.as_str()))
+            .map(|term| format!("blocked term: {term}"))
+    }
+}
+
+#[derive(Clone)]
+pub struct OutputModeration {
+    pub(crate) policy: Arc<dyn OutputModerationPolicy>,
+    pub(crate) message: String,
+    pub(crate) buffer_stream: bool,
+}
+
+impl OutputModeration {
+    pub fn new(
+        policy: Arc<dyn OutputModerationPolicy>,
+        message: String,
+        buffer_stream: bool,
+    ) -> Self {
+        Self { policy, message, buffer_stream }
+    }
+}
+
+pub(crate) struct ModerationBufferSink {
+    inner: Arc<dyn ResponseEventSink>,
+    buffered: Mutex<Vec<ResponseEvent>>,
+}
+
+impl ModerationBufferSink {
+    pub(crate) fn new(inner: Arc<dyn ResponseEventSink>) -> Self {
+        Self { inner, buffered: Mutex::new(Vec::new()) }
+    }
+
+    fn take_buffered(&self) -> Vec<ResponseEvent> {
+        std::mem::take(&mut *self.buffered.lock().unwrap_or_else(|poisoned| poisoned.into_inner()))
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ResponseEventSink for ModerationBufferSink {
+    async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+        match event {
+            Ok(event @ ResponseEvent::OutputTextDelta { .. }) => {
+                self.buffered.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(event);
+            }
+            Ok(ResponseEvent::ResponseCompleted { id, output, finish_reason, usage, meta }) => {
+                let buffered = self.take_buffered();
+                if finish_reason == CONTENT_FILTER_FINISH_REASON {
+                    let replacement = output.iter().find_map(|item| match item {
+                        ResponseOutputItem::Message { content, .. } => {
+                            content.first().map(|part| part.text.clone())
+                        }
+                        _ => None,
+                    });
+                    if let Some(delta) = replacement.filter(|text| !text.is_empty()) {
+                        self.inner
+                            .send(Ok(ResponseEvent::OutputTextDelta { id: id.clone(), delta }))
+                            .await;
+                    }
+                } else {
+                    for event in buffered {
+                        self.inner.send(Ok(event)).await;
+                    }
+                }
+                self.inner
+                    .send(Ok(ResponseEvent::ResponseCompleted {
+                        id,
+                        output,
+                        finish_reason,
+                        usage,
+                        meta,
+                    }))
+                    .await;
+            }
+            Ok(ResponseEvent::ResponseError { .. }) | Err(_) => {
+                self.take_buffered();
+                self.inner.send(event).await;
+            }
+            Ok(ResponseEvent::ReasoningDelta { .. }) => self.inner.send(event).await,
+        }
+    }
+
+    fn output_closed(&self) -> bool {
+        self.inner.output_closed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocklist_policy_matches_case_insensitively() {
+        let policy = BlocklistModerationPolicy::new(vec![" Forbidden ".to_string(), String::new()]);
+        assert_eq!(
+            policy.evaluate("this is FORBIDDEN text"),
+            Some("blocked term: forbidden".to_string())
+        );
+        assert_eq!(policy.evaluate("clean text"), None);
+    }
+}
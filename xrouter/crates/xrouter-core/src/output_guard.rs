@@ -0,0 +1,266 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use xrouter_contracts::ResponseEvent;
+
+use crate::{CoreError, ResponseEventSink};
+
+pub fn find_stop_sequence(text: &str, stops: &[String]) -> Option<usize> {
+    stops.iter().filter(|stop| !stop.is_empty()).filter_map(|stop| text.find(stop.as_str())).min()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StopSequenceScanner {
+    stops: Vec<String>,
+    held: String,
+    stopped: bool,
+}
+
+impl StopSequenceScanner {
+    pub fn new(stops: Vec<String>) -> Self {
+        Self {
+            stops: stops.into_iter().filter(|stop| !stop.is_empty()).collect(),
+            held: String::new(),
+            stopped: false,
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn push(&mut self, delta: &str) -> String {
+        if self.stopped {
+            return String::new();
+        }
+        self.held.push_str(delta);
+        if let Some(cut) = find_stop_sequence(&self.held, &self.stops) {
+            self.held.truncate(cut);
+            self.stopped = true;
+            return std::mem::take(&mut self.held);
+        }
+        let keep_from = self.partial_match_start();
+        let tail = self.held.split_off(keep_from);
+        std::mem::replace(&mut self.held, tail)
+    }
+
+    pub fn finish(&mut self) -> String {
+        std::mem::take(&mut self.held)
+    }
+
+    fn partial_match_start(&self) -> usize {
+        let max_hold = self.stops.iter().map(String::len).max().unwrap_or(0).saturating_sub(1);
+        let min_start = self.held.len().saturating_sub(max_hold);
+        self.held
+            .char_indices()
+            .map(|(idx, _)| idx)
+            .filter(|idx| *idx >= min_start)
+            .find(|idx| {
+                let suffix = &self.held[*idx..];
+                self.stops.iter().any(|stop| stop.starts_with(suffix))
+            })
+            .unwrap_or(self.held.len())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTokenBudget {
+    limit: u32,
+    used: u32,
+    in_word: bool,
+    exhausted: bool,
+}
+
+impl OutputTokenBudget {
+    pub fn new(limit: u32) -> Self {
+        Self { limit, used: 0, in_word: false, exhausted: false }
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    pub fn used(&self) -> u32 {
+        self.used
+    }
+
+    pub fn admit<'a>(&mut self, text: &'a str) -> &'a str {
+        if self.exhausted {
+            return "";
+        }
+        for (idx, ch) in text.char_indices() {
+            if ch.is_whitespace() {
+                self.in_word = false;
+                continue;
+            }
+            if !self.in_word {
+                if self.used >= self.limit {
+                    self.exhausted = true;
+                    return &text[..idx];
+                }
+                self.used += 1;
+                self.in_word = true;
+            }
+        }
+        text
+    }
+}
+
+pub fn truncate_to_output_tokens(text: &str, limit: u32) -> Option<usize> {
+    let mut budget = OutputTokenBudget::new(limit);
+    let admitted = budget.admit(text).len();
+    budget.is_exhausted().then_some(admitted)
+}
+
+#[derive(Debug, Default)]
+struct OutputGuardState {
+    stops: Option<StopSequenceScanner>,
+    budget: Option<OutputTokenBudget>,
+}
+
+impl OutputGuardState {
+    fn push(&mut self, delta: &str) -> String {
+        let text = match self.stops.as_mut() {
+            Some(scanner) => scanner.push(delta),
+            None => delta.to_string(),
+        };
+        self.admit(text)
+    }
+
+    fn finish(&mut self) -> String {
+        let text = self.stops.as_mut().map(StopSequenceScanner::finish).unwrap_or_default();
+        self.admit(text)
+    }
+
+    fn admit(&mut self, text: String) -> String {
+        match self.budget.as_mut() {
+            Some(budget) => budget.admit(&text).to_string(),
+            None => text,
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.stops.as_ref().is_some_and(StopSequenceScanner::is_stopped)
+            || self.budget.as_ref().is_some_and(OutputTokenBudget::is_exhausted)
+    }
+}
+
+pub(crate) struct OutputGuardSink {
+    inner: Arc<dyn ResponseEventSink>,
+    request_id: String,
+    state: Mutex<OutputGuardState>,
+}
+
+impl OutputGuardSink {
+    pub(crate) fn new(
+        inner: Arc<dyn ResponseEventSink>,
+        request_id: String,
+        stops: Option<Vec<String>>,
+        max_output_tokens: Option<u32>,
+    ) -> Self {
+        let state = OutputGuardState {
+            stops: stops.map(StopSequenceScanner::new),
+            budget: max_output_tokens.map(OutputTokenBudget::new),
+        };
+        Self { inner, request_id, state: Mutex::new(state) }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, OutputGuardState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ResponseEventSink for OutputGuardSink {
+    async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+        match event {
+            Ok(ResponseEvent::OutputTextDelta { id, delta }) => {
+                let delta = self.state().push(&delta);
+                if !delta.is_empty() {
+                    self.inner.send(Ok(ResponseEvent::OutputTextDelta { id, delta })).await;
+                }
+            }
+            Ok(ResponseEvent::ReasoningDelta { .. }) => self.inner.send(event).await,
+            other => {
+                let held = self.state().finish();
+                if !held.is_empty() {
+                    self.inner
+                        .send(Ok(ResponseEvent::OutputTextDelta {
+                            id: self.request_id.clone(),
+                            delta: held,
+                        }))
+                        .await;
+                }
+                self.inner.send(other).await;
+            }
+        }
+    }
+
+    fn output_closed(&self) -> bool {
+        self.state().is_closed() || self.inner.output_closed()
+    }
+
+    fn retained_output_limit(&self) -> Option<usize> {
+        self.inner.retained_output_limit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(stops: &[&str], deltas: &[&str]) -> (String, bool) {
+        let mut scanner =
+            StopSequenceScanner::new(stops.iter().map(|stop| (*stop).to_string()).collect());
+        let mut out = String::new();
+        for delta in deltas {
+            out.push_str(&scanner.push(delta));
+        }
+        let stopped = scanner.is_stopped();
+        out.push_str(&scanner.finish());
+        (out, stopped)
+    }
+
+    #[test]
+    fn scanner_truncates_at_stop_split_across_deltas() {
+        assert_eq!(scan(&["END"], &["hello E", "N", "D tail"]), ("hello ".to_string(), true));
+        assert_eq!(scan(&["\n\n", "###"], &["a\n", "\nb"]), ("a".to_string(), true));
+    }
+
+    #[test]
+    fn scanner_releases_held_prefix_when_match_breaks() {
+        let mut scanner = StopSequenceScanner::new(vec!["END".to_string()]);
+        assert_eq!(scanner.push("abc E"), "abc ");
+        assert_eq!(scanner.push("Nx"), "ENx");
+        assert!(!scanner.is_stopped());
+        assert_eq!(scan(&["END"], &["tail E"]), ("tail E".to_string(), false));
+    }
+
+    #[test]
+    fn scanner_holds_back_on_char_boundaries() {
+        assert_eq!(scan(&["éé"], &["привет é", "é!"]), ("привет ".to_string(), true));
+    }
+
+    #[test]
+    fn find_stop_sequence_returns_earliest_match() {
+        let stops = vec!["b".to_string(), "a".to_string()];
+        assert_eq!(find_stop_sequence("xab", &stops), Some(1));
+        assert_eq!(find_stop_sequence("xyz", &stops), None);
+    }
+
+    #[test]
+    fn token_budget_cuts_before_first_word_over_limit_across_deltas() {
+        let mut budget = OutputTokenBudget::new(3);
+        assert_eq!(budget.admit("one tw"), "one tw");
+        assert_eq!(budget.admit("o three four"), "o three ");
+        assert!(budget.is_exhausted());
+        assert_eq!(budget.admit("five"), "");
+    }
+
+    #[test]
+    fn truncate_to_output_tokens_reports_cut_only_when_limit_exceeded() {
+        assert_eq!(truncate_to_output_tokens("a b c", 2), Some(4));
+        assert_eq!(truncate_to_output_tokens("a b", 2), None);
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use xrouter_contracts::ResponseEvent;
+
+use crate::{CoreError, ResponseEventSink};
+
+/// Drops live reasoning deltas for requests sent with `reasoning.exclude: true`.
+pub(crate) struct ReasoningExcludeSink {
+    inner: Arc<dyn ResponseEventSink>,
+}
+
+impl ReasoningExcludeSink {
+    pub(crate) fn new(inner: Arc<dyn ResponseEventSink>) -> Self {
+        Self { inner }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ResponseEventSink for ReasoningExcludeSink {
+    async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+        if !matches!(event, Ok(ResponseEvent::ReasoningDelta { .. })) {
+            self.inner.send(event).await;
+        }
+    }
+
+    fn output_closed(&self) -> bool {
+        self.inner.output_closed()
+    }
+
+    fn retained_output_limit(&self) -> Option<usize> {
+        self.inner.retained_output_limit()
+    }
+}
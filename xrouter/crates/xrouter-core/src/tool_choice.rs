@@ -0,0 +1,50 @@
+use serde_json::Value;
+
+const REQUIRED_TOOL_DIRECTIVE: &str =
+    "You must respond by calling one of the provided tools. Do not answer with plain text.";
+const REQUIRED_TOOL_RETRY_DIRECTIVE: &str = "Your previous reply did not call a tool. \
+     You MUST call exactly one of the provided tools now. Plain-text answers are rejected.";
+
+/// Whether the request forces the model to call some tool (`"required"` or its `"any"` alias).
+pub(crate) fn requires_tool_call(tools: Option<&[Value]>, tool_choice: Option<&Value>) -> bool {
+    tools.is_some_and(|tools| !tools.is_empty())
+        && tool_choice
+            .and_then(Value::as_str)
+            .is_some_and(|choice| matches!(choice, "required" | "any"))
+}
+
+pub(crate) fn emulated_instructions(base: Option<&str>, retry: bool) -> String {
+    let directive = if retry { REQUIRED_TOOL_RETRY_DIRECTIVE } else { REQUIRED_TOOL_DIRECTIVE };
+    match base.map(str::trim).filter(|base| !base.is_empty()) {
+        Some(base) => format!("{base}\n\n{directive}"),
+        None => directive.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{emulated_instructions, requires_tool_call};
+
+    #[test]
+    fn requires_tool_call_only_for_required_choice_with_tools() {
+        let tools = vec![json!({"type":"function","name":"f"})];
+
+        assert!(requires_tool_call(Some(&tools), Some(&json!("required"))));
+        assert!(requires_tool_call(Some(&tools), Some(&json!("any"))));
+        assert!(!requires_tool_call(Some(&tools), Some(&json!("auto"))));
+        assert!(!requires_tool_call(Some(&tools), None));
+        assert!(!requires_tool_call(Some(&[]), Some(&json!("required"))));
+    }
+
+    #[test]
+    fn emulated_instructions_append_directive_to_existing_instructions() {
+        let first = emulated_instructions(Some("be brief"), false);
+        let retry = emulated_instructions(None, true);
+
+        assert!(first.starts_with("be brief\n\n"));
+        assert!(first.ends_with("Do not answer with plain text."));
+        assert!(retry.starts_with("Your previous reply did not call a tool."));
+    }
+}
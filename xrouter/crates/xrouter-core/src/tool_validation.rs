@@ -0,0 +1,300 @@
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+use crate::CoreError;
+
+const MAX_FUNCTION_NAME_LEN: usize = 64;
+const JSON_SCHEMA_TYPES: [&str; 7] =
+    ["object", "array", "string", "number", "integer", "boolean", "null"];
+const TOOL_CHOICE_MODES: [&str; 4] = ["none", "auto", "required", "any"];
+
+pub(crate) fn validate_tools_and_choice(
+    tools: Option<&[Value]>,
+    tool_choice: Option<&Value>,
+) -> Result<(), CoreError> {
+    let declared = match tools {
+        Some(tools) => validate_tools(tools)?,
+        None => HashSet::new(),
+    };
+    if let Some(choice) = tool_choice {
+        validate_tool_choice(choice, tools.is_some_and(|tools| !tools.is_empty()), &declared)?;
+    }
+    Ok(())
+}
+
+fn validate_tools(tools: &[Value]) -> Result<HashSet<String>, CoreError> {
+    let mut declared = HashSet::new();
+    for (index, tool) in tools.iter().enumerate() {
+        let path = format!("tools[{index}]");
+        let Some(object) = tool.as_object() else {
+            return Err(invalid(path, "must be an object"));
+        };
+        let Some(kind) = object.get("type").and_then(Value::as_str) else {
+            return Err(invalid(format!("{path}.type"), "must be a string"));
+        };
+        if kind != "function" {
+            continue;
+        }
+        // Chat Completions nests the definition under `function`; Responses keeps it flat.
+        let (definition, definition_path) = match object.get("function") {
+            Some(Value::Object(function)) => (function, format!("{path}.function")),
+            Some(_) => return Err(invalid(format!("{path}.function"), "must be an object")),
+            None => (object, path),
+        };
+        let name = validate_function_name(definition.get("name"), &definition_path)?;
+        if !declared.insert(name.to_string()) {
+            return Err(invalid(
+                format!("{definition_path}.name"),
+                &format!("duplicate function name '{name}'"),
+            ));
+        }
+        if let Some(parameters) = definition.get("parameters") {
+            validate_parameters_schema(parameters, &format!("{definition_path}.parameters"))?;
+        }
+    }
+    Ok(declared)
+}
+
+fn validate_function_name<'a>(name: Option<&'a Value>, path: &str) -> Result<&'a str, CoreError> {
+    let Some(name) = name.and_then(Value::as_str) else {
+        return Err(invalid(format!("{path}.name"), "must be a string"));
+    };
+    let valid = !name.is_empty()
+        && name.len() <= MAX_FUNCTION_NAME_LEN
+        && name.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-');
+    if !valid {
+        return Err(invalid(format!("{path}.name"), "must match ^[a-zA-Z0-9_-]{1,64}$"));
+    }
+    Ok(name)
+}
+
+fn validate_parameters_schema(schema: &Value, path: &str) -> Result<(), CoreError> {
+    let Some(object) = schema.as_object() else {
+        return Err(invalid(path.to_string(), "must be a JSON schema object"));
+    };
+    if object.get("type").is_some_and(|kind| kind != "object") {
+        return Err(invalid(format!("{path}.type"), "must be \"object\""));
+    }
+    validate_schema_node(object, path)
+}
+
+fn validate_schema_node(schema: &Map<String, Value>, path: &str) -> Result<(), CoreError> {
+    if let Some(kind) = schema.get("type") {
+        let known = |value: &Value| value.as_str().is_some_and(|t| JSON_SCHEMA_TYPES.contains(&t));
+        let valid = match kind {
+            Value::String(_) => known(kind),
+            Value::Array(kinds) => !kinds.is_empty() && kinds.iter().all(known),
+            _ => false,
+        };
+        if !valid {
+            return Err(invalid(format!("{path}.type"), "must be a JSON schema type"));
+        }
+    }
+    if let Some(properties) = schema.get("properties") {
+        let Some(properties) = properties.as_object() else {
+            return Err(invalid(format!("{path}.properties"), "must be an object"));
+        };
+        for (name, property) in properties {
+            let property_path = format!("{path}.properties.{name}");
+            let Some(property) = property.as_object() else {
+                return Err(invalid(property_path, "must be a JSON schema object"));
+            };
+            validate_schema_node(property, &property_path)?;
+        }
+    }
+    if let Some(items) = schema.get("items") {
+        match items {
+            Value::Object(items) => validate_schema_node(items, &format!("{path}.items"))?,
+            Value::Bool(_) => {}
+            _ => return Err(invalid(format!("{path}.items"), "must be a JSON schema object")),
+        }
+    }
+    if let Some(required) = schema.get("required") {
+        let valid = required.as_array().is_some_and(|fields| fields.iter().all(Value::is_string));
+        if !valid {
+            return Err(invalid(format!("{path}.required"), "must be an array of strings"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_tool_choice(
+    choice: &Value,
+    has_tools: bool,
+    declared: &HashSet<String>,
+) -> Result<(), CoreError> {
+    match choice {
+        Value::String(mode) => {
+            if !TOOL_CHOICE_MODES.contains(&mode.as_str()) {
+                return Err(invalid(
+                    "tool_choice".to_string(),
+                    "must be one of \"none\", \"auto\", \"required\" or a function selector",
+                ));
+            }
+            if matches!(mode.as_str(), "required" | "any") && !has_tools {
+                return Err(invalid("tool_choice".to_string(), "requires at least one tool"));
+            }
+            Ok(())
+        }
+        Value::Object(object) => {
+            let Some(kind) = object.get("type").and_then(Value::as_str) else {
+                return Err(invalid("tool_choice.type".to_string(), "must be a string"));
+            };
+            if kind != "function" {
+                return Ok(());
+            }
+            let (name, path) = match object.get("function") {
+                Some(Value::Object(function)) => (function.get("name"), "tool_choice.function"),
+                Some(_) => {
+                    return Err(invalid("tool_choice.function".to_string(), "must be an object"));
+                }
+                None => (object.get("name"), "tool_choice"),
+            };
+            let name = validate_function_name(name, path)?;
+            if !declared.contains(name) {
+                return Err(invalid(
+                    format!("{path}.name"),
+                    &format!("function '{name}' is not declared in tools"),
+                ));
+            }
+            Ok(())
+        }
+        _ => Err(invalid("tool_choice".to_string(), "must be a string or an object")),
+    }
+}
+
+fn invalid(param: String, message: &str) -> CoreError {
+    CoreError::InvalidParam { param, message: message.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::validate_tools_and_choice;
+    use crate::CoreError;
+
+    fn param_of(result: Result<(), CoreError>) -> String {
+        match result {
+            Err(CoreError::InvalidParam { param, .. }) => param,
+            other => panic!("expected InvalidParam, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn accepts_chat_and_responses_function_shapes() {
+        let tools = vec![
+            json!({"type":"function","function":{"name":"list_dir","parameters":{
+                "type":"object",
+                "properties":{"path":{"type":"string"},"depth":{"type":["integer","null"]}},
+                "required":["path"]
+            }}}),
+            json!({"type":"function","name":"read-file","parameters":{"type":"object"}}),
+            json!({"type":"web_search"}),
+        ];
+        let choice = json!({"type":"function","function":{"name":"list_dir"}});
+
+        assert!(validate_tools_and_choice(Some(&tools), Some(&choice)).is_ok());
+        assert!(validate_tools_and_choice(Some(&tools), Some(&json!("required"))).is_ok());
+        assert!(
+            validate_tools_and_choice(
+                Some(&tools),
+                Some(&json!({"type":"function","name":"read-file"}))
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_function_names_with_param_path() {
+        let tools = vec![
+            json!({"type":"function","function":{"name":"ok"}}),
+            json!({"type":"function","function":{"name":"bad.name"}}),
+        ];
+
+        assert_eq!(
+            param_of(validate_tools_and_choice(Some(&tools), None)),
+            "tools[1].function.name"
+        );
+
+        let long = vec![json!({"type":"function","name":"a".repeat(65)})];
+        assert_eq!(param_of(validate_tools_and_choice(Some(&long), None)), "tools[0].name");
+    }
+
+    #[test]
+    fn rejects_duplicate_function_names() {
+        let tools = vec![
+            json!({"type":"function","function":{"name":"same"}}),
+            json!({"type":"function","name":"same"}),
+        ];
+
+        assert_eq!(param_of(validate_tools_and_choice(Some(&tools), None)), "tools[1].name");
+    }
+
+    #[test]
+    fn rejects_malformed_parameter_schemas() {
+        let not_object = vec![json!({"type":"function","function":{"name":"f","parameters":"{}"}})];
+        assert_eq!(
+            param_of(validate_tools_and_choice(Some(&not_object), None)),
+            "tools[0].function.parameters"
+        );
+
+        let wrong_root = vec![json!({"type":"function","name":"f","parameters":{"type":"string"}})];
+        assert_eq!(
+            param_of(validate_tools_and_choice(Some(&wrong_root), None)),
+            "tools[0].parameters.type"
+        );
+
+        let nested = vec![json!({"type":"function","name":"f","parameters":{
+            "type":"object","properties":{"path":{"type":"text"}}
+        }})];
+        assert_eq!(
+            param_of(validate_tools_and_choice(Some(&nested), None)),
+            "tools[0].parameters.properties.path.type"
+        );
+
+        let required = vec![json!({"type":"function","name":"f","parameters":{
+            "type":"object","required":"path"
+        }})];
+        assert_eq!(
+            param_of(validate_tools_and_choice(Some(&required), None)),
+            "tools[0].parameters.required"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_tool_choice_shapes() {
+        let tools = vec![json!({"type":"function","function":{"name":"list_dir"}})];
+
+        assert_eq!(
+            param_of(validate_tools_and_choice(Some(&tools), Some(&json!("sometimes")))),
+            "tool_choice"
+        );
+        assert_eq!(
+            param_of(validate_tools_and_choice(None, Some(&json!("required")))),
+            "tool_choice"
+        );
+        assert_eq!(
+            param_of(validate_tools_and_choice(Some(&tools), Some(&json!(42)))),
+            "tool_choice"
+        );
+        assert_eq!(
+            param_of(validate_tools_and_choice(
+                Some(&tools),
+                Some(&json!({"type":"function","function":{"name":"missing"}}))
+            )),
+            "tool_choice.function.name"
+        );
+        assert_eq!(
+            param_of(validate_tools_and_choice(Some(&tools), Some(&json!({"type":"function"})))),
+            "tool_choice.name"
+        );
+    }
+
+    #[test]
+    fn auto_and_none_are_accepted_without_tools() {
+        assert!(validate_tools_and_choice(None, Some(&json!("auto"))).is_ok());
+        assert!(validate_tools_and_choice(None, Some(&json!("none"))).is_ok());
+    }
+}
@@ -0,0 +1,443 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use xrouter_contracts::{ResponseEvent, ResponsesRequest};
+
+use crate::{CoreError, ResponseEventSink, find_stop_sequence};
+
+pub trait ExecutionTransform: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn rewrite_request(&self, _request: &mut ResponsesRequest) {}
+
+    fn process_output(&self, _output_text: &mut String) {}
+
+    /// Rewrites output deltas as they are streamed; without one only the final aggregated
+    /// output is rewritten.
+    fn stream_rewriter(&self) -> Option<Box<dyn OutputStreamRewriter>> {
+        None
+    }
+}
+
+/// Rewrites a stream of output deltas, holding back text that a later delta may still change.
+pub trait OutputStreamRewriter: Send {
+    fn push(&mut self, delta: &str) -> String;
+
+    fn finish(&mut self) -> String;
+}
+
+#[derive(Clone)]
+pub struct ScopedTransform {
+    model: Option<String>,
+    transform: Arc<dyn ExecutionTransform>,
+}
+
+impl ScopedTransform {
+    pub fn new(transform: Arc<dyn ExecutionTransform>, model: Option<String>) -> Self {
+        Self { model, transform }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.transform.name()
+    }
+
+    pub fn applies_to(&self, model: &str) -> bool {
+        self.model.as_deref().is_none_or(|scoped| scoped == model)
+    }
+
+    pub(crate) fn transform(&self) -> &dyn ExecutionTransform {
+        self.transform.as_ref()
+    }
+}
+
+/// Applies the stream rewriters of the transforms scoped to the request model to live deltas.
+pub(crate) struct TransformStreamSink {
+    inner: Arc<dyn ResponseEventSink>,
+    request_id: String,
+    rewriters: Mutex<Vec<Box<dyn OutputStreamRewriter>>>,
+}
+
+impl TransformStreamSink {
+    pub(crate) fn new(
+        inner: Arc<dyn ResponseEventSink>,
+        request_id: String,
+        rewriters: Vec<Box<dyn OutputStreamRewriter>>,
+    ) -> Self {
+        Self { inner, request_id, rewriters: Mutex::new(rewriters) }
+    }
+
+    fn push(&self, delta: &str) -> String {
+        let mut rewriters = self.rewriters.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        rewriters.iter_mut().fold(delta.to_string(), |text, rewriter| rewriter.push(&text))
+    }
+
+    fn finish(&self) -> String {
+        let mut rewriters = self.rewriters.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        rewriters.iter_mut().fold(String::new(), |text, rewriter| {
+            let mut out = rewriter.push(&text);
+            out.push_str(&rewriter.finish());
+            out
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl ResponseEventSink for TransformStreamSink {
+    async fn send(&self, event: Result<ResponseEvent, CoreError>) {
+        match event {
+            Ok(ResponseEvent::OutputTextDelta { id, delta }) => {
+                let delta = self.push(&delta);
+                if !delta.is_empty() {
+                    self.inner.send(Ok(ResponseEvent::OutputTextDelta { id, delta })).await;
+                }
+            }
+            Ok(ResponseEvent::ReasoningDelta { .. }) => self.inner.send(event).await,
+            other => {
+                let held = self.finish();
+                if !held.is_empty() {
+                    self.inner
+                        .send(Ok(ResponseEvent::OutputTextDelta {
+                            id: self.request_id.clone(),
+                            delta: held,
+                        }))
+                        .await;
+                }
+                self.inner.send(other).await;
+            }
+        }
+    }
+
+    fn output_closed(&self) -> bool {
+        self.inner.output_closed()
+    }
+
+    fn retained_output_limit(&self) -> Option<usize> {
+        self.inner.retained_output_limit()
+    }
+}
+
+pub fn build_builtin_transform(
+    name: &str,
+    params: &Map<String, Value>,
+) -> Result<Arc<dyn ExecutionTransform>, CoreError> {
+    match name {
+        "strip_think_tags" => Ok(Arc::new(StripThinkTags)),
+        "stop_sequences" => {
+            let stop = required_string_list(name, params, "stop")?;
+            Ok(Arc::new(StopSequences { stop }))
+        }
+        "redact" => {
+            let patterns = required_string_list(name, params, "patterns")?;
+            let replacement = match params.get("replacement") {
+                None => "[REDACTED]".to_string(),
+                Some(Value::String(value)) => value.clone(),
+                Some(_) => {
+                    return Err(CoreError::Validation(
+                        "transform redact: replacement must be a string".to_string(),
+                    ));
+                }
+            };
+            Ok(Arc::new(RedactPatterns { patterns, replacement }))
+        }
+        "prepend_instructions" => {
+            let text = params
+                .get("text")
+                .and_then(Value::as_str)
+                .filter(|value| !value.trim().is_empty())
+                .ok_or_else(|| {
+                    CoreError::Validation(
+                        "transform prepend_instructions: text must be a non-empty string"
+                            .to_string(),
+                    )
+                })?;
+            Ok(Arc::new(PrependInstructions { text: text.to_string() }))
+        }
+        other => Err(CoreError::Validation(format!("unknown transform: {other}"))),
+    }
+}
+
+fn required_string_list(
+    transform: &str,
+    params: &Map<String, Value>,
+    key: &str,
+) -> Result<Vec<String>, CoreError> {
+    let values = params
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|item| !item.is_empty())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if values.is_empty() {
+        return Err(CoreError::Validation(format!(
+            "transform {transform}: {key} must be a non-empty string array"
+        )));
+    }
+    Ok(values)
+}
+
+struct StripThinkTags;
+
+impl ExecutionTransform for StripThinkTags {
+    fn name(&self) -> &'static str {
+        "strip_think_tags"
+    }
+
+    fn process_output(&self, output_text: &mut String) {
+        if let Some(stripped) = strip_think_blocks(output_text) {
+            *output_text = stripped;
+        }
+    }
+}
+
+fn strip_think_blocks(text: &str) -> Option<String> {
+    const OPEN: &str = "<think>";
+    const CLOSE: &str = "</think>";
+
+    if !text.contains(OPEN) {
+        return None;
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+        out.push_str(&rest[..start]);
+        let after_open = &rest[start + OPEN.len()..];
+        match after_open.find(CLOSE) {
+            Some(end) => rest = &after_open[end + CLOSE.len()..],
+            None => {
+                rest = "";
+                break;
+            }
+        }
+    }
+    out.push_str(rest);
+    Some(out.trim_start().to_string())
+}
+
+struct StopSequences {
+    stop: Vec<String>,
+}
+
+impl ExecutionTransform for StopSequences {
+    fn name(&self) -> &'static str {
+        "stop_sequences"
+    }
+
+    fn process_output(&self, output_text: &mut String) {
+        if let Some(cut) = find_stop_sequence(output_text, &self.stop) {
+            output_text.truncate(cut);
+        }
+    }
+}
+
+struct RedactPatterns {
+    patterns: Vec<String>,
+    replacement: String,
+}
+
+impl ExecutionTransform for RedactPatterns {
+    fn name(&self) -> &'static str {
+        "redact"
+    }
+
+    fn process_output(&self, output_text: &mut String) {
+        if self.patterns.iter().any(|pattern| output_text.contains(pattern.as_str())) {
+            let end = output_text.len();
+            *output_text = redact_before(output_text, &self.patterns, &self.replacement, end).0;
+        }
+    }
+
+    fn stream_rewriter(&self) -> Option<Box<dyn OutputStreamRewriter>> {
+        Some(Box::new(RedactStream {
+            patterns: self.patterns.clone(),
+            replacement: self.replacement.clone(),
+            held: String::new(),
+        }))
+    }
+}
+
+struct RedactStream {
+    patterns: Vec<String>,
+    replacement: String,
+    held: String,
+}
+
+impl OutputStreamRewriter for RedactStream {
+    fn push(&mut self, delta: &str) -> String {
+        self.held.push_str(delta);
+        // A match starting later may still be completed by the next delta.
+        let longest = self.patterns.iter().map(String::len).max().unwrap_or(0);
+        let decided = (self.held.len() + 1).saturating_sub(longest);
+        let (out, consumed) = redact_before(&self.held, &self.patterns, &self.replacement, decided);
+        self.held.drain(..consumed);
+        out
+    }
+
+    fn finish(&mut self) -> String {
+        let held = std::mem::take(&mut self.held);
+        redact_before(&held, &self.patterns, &self.replacement, held.len()).0
+    }
+}
+
+/// Replaces pattern matches starting before `decided`, leftmost first, and the first listed
+/// pattern at a shared position. Returns the rewritten text and the length of `text` it covers.
+fn redact_before(
+    text: &str,
+    patterns: &[String],
+    replacement: &str,
+    decided: usize,
+) -> (String, usize) {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = 0;
+    loop {
+        let next = patterns
+            .iter()
+            .filter_map(|pattern| {
+                text[rest..].find(pattern.as_str()).map(|at| (rest + at, pattern.len()))
+            })
+            .filter(|(at, _)| *at < decided)
+            .min_by_key(|(at, _)| *at);
+        match next {
+            Some((at, len)) => {
+                out.push_str(&text[rest..at]);
+                out.push_str(replacement);
+                rest = at + len;
+            }
+            None => {
+                let end = text.floor_char_boundary(decided.max(rest));
+                out.push_str(&text[rest..end]);
+                return (out, end);
+            }
+        }
+    }
+}
+
+struct PrependInstructions {
+    text: String,
+}
+
+impl ExecutionTransform for PrependInstructions {
+    fn name(&self) -> &'static str {
+        "prepend_instructions"
+    }
+
+    fn rewrite_request(&self, request: &mut ResponsesRequest) {
+        request.instructions = Some(match request.instructions.take() {
+            Some(existing) if !existing.trim().is_empty() => format!("{}\n\n{existing}", self.text),
+            _ => self.text.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn params(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().expect("params must be an object")
+    }
+
+    fn apply_output(name: &str, raw_params: Value, text: &str) -> String {
+        let transform =
+            build_builtin_transform(name, &params(raw_params)).expect("transform must build");
+        let mut output = text.to_string();
+        transform.process_output(&mut output);
+        output
+    }
+
+    #[test]
+    fn strip_think_tags_removes_closed_and_trailing_unclosed_blocks() {
+        assert_eq!(
+            apply_output("strip_think_tags", json!({}), "<think>plan</think>\n\nanswer"),
+            "answer"
+        );
+        assert_eq!(
+            apply_output("strip_think_tags", json!({}), "a<think>x</think>b<think>tail"),
+            "ab"
+        );
+        assert_eq!(apply_output("strip_think_tags", json!({}), " plain "), " plain ");
+    }
+
+    #[test]
+    fn stop_sequences_truncates_at_earliest_match() {
+        assert_eq!(
+            apply_output("stop_sequences", json!({"stop": ["END", "\n\n"]}), "one\n\ntwo END"),
+            "one"
+        );
+        assert_eq!(apply_output("stop_sequences", json!({"stop": ["END"]}), "no stop"), "no stop");
+    }
+
+    #[test]
+    fn redact_replaces_every_pattern_occurrence() {
+        assert_eq!(
+            apply_output(
+                "redact",
+                json!({"patterns": ["secret"], "replacement": "***"}),
+                "secret and secret"
+            ),
+            "*** and ***"
+        );
+        assert_eq!(apply_output("redact", json!({"patterns": ["x"]}), "x"), "[REDACTED]");
+    }
+
+    #[test]
+    fn redact_stream_replaces_patterns_split_across_deltas() {
+        let transform = build_builtin_transform(
+            "redact",
+            &params(json!({"patterns": ["secret", "sec"], "replacement": "***"})),
+        )
+        .expect("transform must build");
+        let mut rewriter = transform.stream_rewriter().expect("redact must rewrite streams");
+        let mut streamed = String::new();
+        for delta in ["a se", "cr", "et, é sec", "ond s", "ec"] {
+            streamed.push_str(&rewriter.push(delta));
+        }
+        streamed.push_str(&rewriter.finish());
+
+        let mut output = "a secret, é second sec".to_string();
+        transform.process_output(&mut output);
+        assert_eq!(streamed, "a ***, é ***ond ***");
+        assert_eq!(streamed, output);
+    }
+
+    #[test]
+    fn prepend_instructions_keeps_existing_instructions_after_prefix() {
+        let transform =
+            build_builtin_transform("prepend_instructions", &params(json!({"text": "Be brief."})))
+                .expect("transform must build");
+        let mut request: ResponsesRequest =
+            serde_json::from_value(json!({"model": "m", "input": "hi", "instructions": "Reply."}))
+                .expect("request must parse");
+        transform.rewrite_request(&mut request);
+        assert_eq!(request.instructions.as_deref(), Some("Be brief.\n\nReply."));
+    }
+
+    #[test]
+    fn build_builtin_transform_rejects_unknown_names_and_missing_params() {
+        assert!(matches!(
+            build_builtin_transform("nope", &Map::new()),
+            Err(CoreError::Validation(message)) if message == "unknown transform: nope"
+        ));
+        assert!(build_builtin_transform("stop_sequences", &Map::new()).is_err());
+        assert!(build_builtin_transform("prepend_instructions", &Map::new()).is_err());
+    }
+
+    #[test]
+    fn scoped_transform_matches_model_when_scoped() {
+        let transform =
+            build_builtin_transform("strip_think_tags", &Map::new()).expect("transform must build");
+        let scoped = ScopedTransform::new(transform.clone(), Some("deepseek-reasoner".to_string()));
+        assert!(scoped.applies_to("deepseek-reasoner"));
+        assert!(!scoped.applies_to("deepseek-chat"));
+        assert!(ScopedTransform::new(transform, None).applies_to("anything"));
+    }
+}
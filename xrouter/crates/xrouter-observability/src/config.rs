@@ -1,12 +1,18 @@
-use std::{env, time::Duration};
+use std::env;
+#[cfg(feature = "otel")]
+use std::time::Duration;
 
+#[cfg(feature = "otel")]
 use opentelemetry_otlp::Protocol;
 
-use crate::exporters::{
-    otlp::{TraceSinkConfig, parse_http_protocol, parse_trace_sinks_from_env},
-    stdout::{LogExporterKind, parse_log_exporter_kind},
+#[cfg(feature = "otel")]
+use crate::exporters::otlp::{
+    MetricsSinkConfig, TraceSinkConfig, parse_http_protocol, parse_metrics_sink_from_env,
+    parse_trace_sinks_from_env,
 };
+use crate::exporters::stdout::{LogExporterKind, parse_log_exporter_kind};
 
+#[cfg(feature = "otel")]
 const DEFAULT_TRACE_TIMEOUT_MS: u64 = 3_000;
 
 #[derive(Debug, Clone)]
@@ -15,9 +21,15 @@ pub struct ObservabilityConfig {
     pub log_span_events: bool,
     pub log_exporter: LogExporterKind,
     pub trace_enabled: bool,
+    #[cfg(feature = "otel")]
     pub trace_http_protocol: Protocol,
+    #[cfg(feature = "otel")]
     pub trace_timeout: Duration,
+    #[cfg(feature = "otel")]
     pub trace_sinks: Vec<TraceSinkConfig>,
+    pub metrics_enabled: bool,
+    #[cfg(feature = "otel")]
+    pub metrics_sink: MetricsSinkConfig,
 }
 
 impl ObservabilityConfig {
@@ -28,25 +40,37 @@ impl ObservabilityConfig {
             &env::var("XR_LOG_EXPORTER").unwrap_or_else(|_| "stdout".to_string()),
         );
         let trace_enabled = env_truthy("XR_TRACE_ENABLED", false);
+        #[cfg(feature = "otel")]
         let trace_http_protocol = parse_http_protocol(
             &env::var("XR_OTEL_TRACE_HTTP_PROTOCOL").unwrap_or_else(|_| "binary".to_string()),
         );
+        #[cfg(feature = "otel")]
         let trace_timeout = Duration::from_millis(
             env::var("XR_OTEL_TRACE_TIMEOUT_MS")
                 .ok()
                 .and_then(|value| value.parse::<u64>().ok())
                 .unwrap_or(DEFAULT_TRACE_TIMEOUT_MS),
         );
+        #[cfg(feature = "otel")]
         let trace_sinks = parse_trace_sinks_from_env(trace_enabled);
+        let metrics_enabled = env_truthy("XR_METRICS_ENABLED", false);
+        #[cfg(feature = "otel")]
+        let metrics_sink = parse_metrics_sink_from_env();
 
         Self {
             log_level,
             log_span_events,
             log_exporter,
             trace_enabled,
+            #[cfg(feature = "otel")]
             trace_http_protocol,
+            #[cfg(feature = "otel")]
             trace_timeout,
+            #[cfg(feature = "otel")]
             trace_sinks,
+            metrics_enabled,
+            #[cfg(feature = "otel")]
+            metrics_sink,
         }
     }
 }
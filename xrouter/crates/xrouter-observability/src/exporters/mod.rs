@@ -1,2 +1,3 @@
+#[cfg(feature = "otel")]
 pub mod otlp;
 pub mod stdout;
@@ -1,9 +1,10 @@
 use std::{env, time::Duration};
 
-use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_otlp::{MetricExporter, Protocol, SpanExporter, WithExportConfig};
 
 const DEFAULT_OTEL_TRACE_GRPC_ENDPOINT: &str = "http://127.0.0.1:4317";
 const DEFAULT_OTEL_TRACE_HTTP_ENDPOINT: &str = "http://127.0.0.1:4318/v1/traces";
+const DEFAULT_OTEL_METRICS_HTTP_ENDPOINT: &str = "http://127.0.0.1:4318/v1/metrics";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TraceExporterKind {
@@ -40,6 +41,13 @@ pub struct TraceSinkConfig {
     pub endpoint: String,
 }
 
+/// OTLP endpoint metrics are pushed to; uses the same exporter kinds as traces.
+#[derive(Debug, Clone)]
+pub struct MetricsSinkConfig {
+    pub kind: TraceExporterKind,
+    pub endpoint: String,
+}
+
 pub fn parse_trace_exporter_kind(raw: &str) -> Option<TraceExporterKind> {
     match raw.trim().to_ascii_lowercase().as_str() {
         "otlp_grpc" | "otlp-grpc" => Some(TraceExporterKind::OtlpGrpc),
@@ -120,6 +128,51 @@ pub fn parse_trace_sinks_from_env(trace_enabled: bool) -> Vec<TraceSinkConfig> {
     vec![TraceSinkConfig { kind, endpoint }]
 }
 
+pub fn parse_metrics_sink_from_env() -> MetricsSinkConfig {
+    let kind = env::var("XR_OTEL_METRICS_EXPORTER")
+        .ok()
+        .and_then(|v| parse_trace_exporter_kind(&v))
+        .unwrap_or(TraceExporterKind::OtlpGrpc);
+    let endpoint = env::var("XR_OTEL_METRICS_ENDPOINT")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| match kind {
+            TraceExporterKind::OtlpGrpc => DEFAULT_OTEL_TRACE_GRPC_ENDPOINT.to_string(),
+            TraceExporterKind::OtlpHttp => DEFAULT_OTEL_METRICS_HTTP_ENDPOINT.to_string(),
+        });
+    MetricsSinkConfig { kind, endpoint }
+}
+
+pub fn build_metrics_exporter(
+    sink: &MetricsSinkConfig,
+    timeout: Duration,
+    http_protocol: Protocol,
+) -> Option<MetricExporter> {
+    let built = match sink.kind {
+        TraceExporterKind::OtlpGrpc => MetricExporter::builder()
+            .with_tonic()
+            .with_endpoint(sink.endpoint.clone())
+            .with_timeout(timeout)
+            .build(),
+        TraceExporterKind::OtlpHttp => MetricExporter::builder()
+            .with_http()
+            .with_endpoint(sink.endpoint.clone())
+            .with_timeout(timeout)
+            .with_protocol(http_protocol)
+            .build(),
+    };
+    built
+        .inspect_err(|error| {
+            eprintln!(
+                "xrouter: failed to initialize OTLP metrics exporter (kind={}, endpoint={}): {error}. continuing without metrics.",
+                sink.kind.as_str(),
+                sink.endpoint
+            );
+        })
+        .ok()
+}
+
 pub fn build_trace_exporters(
     trace_sinks: &[TraceSinkConfig],
     trace_timeout: Duration,
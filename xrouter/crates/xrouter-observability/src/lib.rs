@@ -1,22 +1,34 @@
+#[cfg(feature = "otel")]
 use std::{env, sync::OnceLock};
 
+#[cfg(feature = "otel")]
 use opentelemetry::{KeyValue, global, trace::TracerProvider};
-use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
-use tracing::{info, warn};
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::{
+    Resource, metrics::SdkMeterProvider, propagation::TraceContextPropagator,
+    trace::SdkTracerProvider,
+};
+#[cfg(feature = "otel")]
+use tracing::info;
+use tracing::warn;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
 mod exporters;
+#[cfg(feature = "otel")]
 mod preflight;
 
 use config::ObservabilityConfig;
-use exporters::{
-    otlp::build_trace_exporters,
-    stdout::{LogExporterKind, span_events_mask},
-};
-use preflight::preflight_trace_endpoints;
+#[cfg(feature = "otel")]
+use exporters::otlp::{build_metrics_exporter, build_trace_exporters};
+use exporters::stdout::{LogExporterKind, span_events_mask};
+#[cfg(feature = "otel")]
+use preflight::{TraceEndpointPreflight, preflight_trace_endpoints};
 
+#[cfg(feature = "otel")]
 static TRACER_PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+#[cfg(feature = "otel")]
+static METER_PROVIDER: OnceLock<SdkMeterProvider> = OnceLock::new();
 
 pub fn init_observability(service_name: &str) {
     let config = ObservabilityConfig::from_env();
@@ -41,18 +53,22 @@ pub fn init_observability(service_name: &str) {
         None
     };
 
+    #[cfg(feature = "otel")]
     let preflights = if config.trace_enabled {
         preflight_trace_endpoints(&config.trace_sinks)
     } else {
         Vec::new()
     };
 
+    #[cfg(feature = "otel")]
     let telemetry_layer = if config.trace_enabled {
         let tracer = build_tracer(service_name, &config);
         Some(tracing_opentelemetry::layer().with_tracer(tracer))
     } else {
         None
     };
+    #[cfg(not(feature = "otel"))]
+    let telemetry_layer = None::<tracing_subscriber::layer::Identity>;
 
     tracing_subscriber::registry()
         .with(env_filter)
@@ -61,6 +77,61 @@ pub fn init_observability(service_name: &str) {
         .try_init()
         .ok();
 
+    #[cfg(feature = "otel")]
+    report_trace_setup(&config, preflights);
+    #[cfg(not(feature = "otel"))]
+    if config.trace_enabled {
+        warn!(
+            event = "observability.trace.unavailable",
+            service_name = service_name,
+            "XR_TRACE_ENABLED is set, but this build has no `otel` feature; traces are not exported"
+        );
+    }
+
+    #[cfg(feature = "otel")]
+    if config.metrics_enabled {
+        METER_PROVIDER.get_or_init(|| init_meter_provider(service_name, &config));
+        info!(
+            event = "observability.metrics.configured",
+            exporter = config.metrics_sink.kind.as_str(),
+            metrics_endpoint = config.metrics_sink.endpoint
+        );
+    }
+    #[cfg(not(feature = "otel"))]
+    if config.metrics_enabled {
+        warn!(
+            event = "observability.metrics.unavailable",
+            service_name = service_name,
+            "XR_METRICS_ENABLED is set, but this build has no `otel` feature; metrics are not exported"
+        );
+    }
+}
+
+/// Reports `observe()` as the gauge `name` on every metrics export; a no-op unless
+/// `XR_METRICS_ENABLED` is set in a build with the `otel` feature.
+pub fn register_gauge(
+    name: &'static str,
+    description: &'static str,
+    observe: impl Fn() -> u64 + Send + Sync + 'static,
+) {
+    #[cfg(feature = "otel")]
+    if METER_PROVIDER.get().is_some() {
+        global::meter("xrouter")
+            .u64_observable_gauge(name)
+            .with_description(description)
+            .with_callback(move |gauge| gauge.observe(observe(), &[]))
+            .build();
+    }
+    #[cfg(not(feature = "otel"))]
+    let _ = (name, description, observe);
+}
+
+pub fn init_tracing(service_name: &str) {
+    init_observability(service_name);
+}
+
+#[cfg(feature = "otel")]
+fn report_trace_setup(config: &ObservabilityConfig, preflights: Vec<TraceEndpointPreflight>) {
     if config.trace_enabled {
         info!(
             event = "observability.trace.configured",
@@ -93,10 +164,7 @@ pub fn init_observability(service_name: &str) {
     }
 }
 
-pub fn init_tracing(service_name: &str) {
-    init_observability(service_name);
-}
-
+#[cfg(feature = "otel")]
 fn build_tracer(
     service_name: &str,
     config: &ObservabilityConfig,
@@ -105,6 +173,7 @@ fn build_tracer(
     provider.tracer(service_name.to_string())
 }
 
+#[cfg(feature = "otel")]
 fn init_tracer_provider(service_name: &str, config: &ObservabilityConfig) -> SdkTracerProvider {
     let mut provider_builder =
         SdkTracerProvider::builder().with_resource(default_resource(service_name));
@@ -121,6 +190,23 @@ fn init_tracer_provider(service_name: &str, config: &ObservabilityConfig) -> Sdk
     provider
 }
 
+#[cfg(feature = "otel")]
+fn init_meter_provider(service_name: &str, config: &ObservabilityConfig) -> SdkMeterProvider {
+    let mut provider_builder =
+        SdkMeterProvider::builder().with_resource(default_resource(service_name));
+    if let Some(exporter) = build_metrics_exporter(
+        &config.metrics_sink,
+        config.trace_timeout,
+        config.trace_http_protocol,
+    ) {
+        provider_builder = provider_builder.with_periodic_exporter(exporter);
+    }
+    let provider = provider_builder.build();
+    global::set_meter_provider(provider.clone());
+    provider
+}
+
+#[cfg(feature = "otel")]
 fn default_resource(service_name: &str) -> Resource {
     Resource::builder()
         .with_attributes(vec![